@@ -7,7 +7,8 @@ use crate::services::database::{
     query_clipboard_items, update_clipboard_item as db_update_clipboard_item,
     increment_paste_counts as db_increment_paste_counts,
     toggle_pin_clipboard_item as db_toggle_pin,
-    ClipboardItem, PaginatedResult, QueryParams,
+    set_clipboard_item_expiry as db_set_clipboard_item_expiry,
+    ClipboardItem, PaginatedResult, QueryParams, SearchMode,
 };
 use crate::services::paste::FilesData;
 use std::path::Path;
@@ -54,6 +55,8 @@ pub async fn get_clipboard_history(
     limit: Option<i64>,
     search: Option<String>,
     content_type: Option<String>,
+    order_by_relevance: Option<bool>,
+    search_mode: Option<SearchMode>,
 ) -> Result<PaginatedResult<ClipboardItem>, String> {
     tokio::task::spawn_blocking(move || {
         let params = QueryParams {
@@ -61,6 +64,8 @@ pub async fn get_clipboard_history(
             limit: limit.unwrap_or(50),
             search,
             content_type,
+            order_by_relevance: order_by_relevance.unwrap_or(false),
+            search_mode: search_mode.unwrap_or_default(),
         };
         let result = query_clipboard_items(params)?;
         let mut items = result.items;
@@ -104,7 +109,9 @@ pub async fn save_image_from_path(
         .ok_or("用户取消保存")?;
 
     let dest = save_path.as_path().ok_or("无效的文件路径")?;
-    std::fs::copy(&file_path, dest).map_err(|e| format!("保存失败: {}", e))?;
+    // 启用本地加密时图片是密文，解密后再保存
+    let bytes = crate::services::local_encryption::read_file(path).map_err(|e| format!("保存失败: {}", e))?;
+    std::fs::write(dest, bytes).map_err(|e| format!("保存失败: {}", e))?;
 
     Ok(dest.to_string_lossy().to_string())
 }
@@ -161,6 +168,7 @@ pub async fn paste_content(params: PasteParams, app: tauri::AppHandle) -> Result
         paste_favorite_item_with_format, paste_favorite_item_with_update,
     };
     use crate::services::paste::PasteAction;
+    crate::services::local_encryption::record_activity();
 
     let paste_action = match params.action.as_deref() {
         Some(action) if !action.trim().is_empty() => {
@@ -210,6 +218,8 @@ pub async fn paste_content(params: PasteParams, app: tauri::AppHandle) -> Result
                 char_count: favorite.char_count,
                 created_at: favorite.created_at,
                 updated_at: favorite.updated_at,
+                expires_at: None,
+                search_snippet: None,
             };
 
             if paste_action.is_some() {
@@ -296,6 +306,12 @@ pub fn toggle_pin_clipboard_item(id: i64) -> Result<bool, String> {
     result
 }
 
+// 设置剪贴板项过期时间（None 表示取消过期）
+#[tauri::command]
+pub fn set_clipboard_item_expiry(id: i64, expires_at: Option<i64>) -> Result<(), String> {
+    db_set_clipboard_item_expiry(id, expires_at)
+}
+
 // 复制图片文件到剪贴板
 #[tauri::command]
 pub fn copy_image_to_clipboard(file_path: String) -> Result<(), String> {
@@ -309,7 +325,7 @@ pub fn copy_image_to_clipboard(file_path: String) -> Result<(), String> {
     }
     
     // 统一转为 PNG 缓存，避免剪贴板直接依赖会被销毁的源文件
-    let image_data = std::fs::read(path)
+    let image_data = crate::services::local_encryption::read_file(path)
         .map_err(|e| format!("读取图片失败: {}", e))?;
 
     let image = image::load_from_memory(&image_data)
@@ -329,7 +345,7 @@ pub fn copy_image_to_clipboard(file_path: String) -> Result<(), String> {
 
     let saved_path = clipboard_images_dir.join(&filename);
     if !saved_path.exists() {
-        std::fs::write(&saved_path, &png_data)
+        crate::services::local_encryption::write_file(&saved_path, &png_data)
             .map_err(|e| format!("保存图片失败: {}", e))?;
     }
     
@@ -440,6 +456,50 @@ pub async fn merge_paste_clipboard_items(ids: Vec<i64>, app: tauri::AppHandle) -
     Ok(())
 }
 
+// 粘贴队列
+#[tauri::command]
+pub fn get_paste_queue_status() -> crate::services::paste::queue::PasteQueueStatus {
+    crate::services::paste::queue::get_paste_queue_status()
+}
+
+#[tauri::command]
+pub fn set_paste_queue_armed(armed: bool) {
+    crate::services::paste::queue::set_paste_queue_armed(armed);
+}
+
+#[tauri::command]
+pub fn set_paste_queue_order(order: String) -> Result<(), String> {
+    use crate::services::paste::queue::PasteQueueOrder;
+    crate::services::paste::queue::set_paste_queue_order(PasteQueueOrder::parse(&order))
+}
+
+#[tauri::command]
+pub fn enqueue_paste_queue_items(ids: Vec<i64>) -> Result<(), String> {
+    if ids.is_empty() {
+        return Err("至少需要选择一项内容".to_string());
+    }
+    crate::services::paste::queue::enqueue_paste_queue_items(&ids);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn clear_paste_queue() {
+    crate::services::paste::queue::clear_paste_queue();
+}
+
+#[tauri::command]
+pub async fn paste_next_queued_item(app: tauri::AppHandle) -> Result<bool, String> {
+    if !crate::get_window_state().is_pinned {
+        if let Some(window) = crate::get_main_window(&app) {
+            crate::hide_main_window(&window);
+        }
+    }
+
+    tokio::task::spawn_blocking(crate::services::paste::queue::paste_next_queued_item)
+        .await
+        .map_err(|e| format!("粘贴队列任务执行失败: {}", e))?
+}
+
 // 直接粘贴文本
 #[tauri::command]
 pub async fn paste_text_direct(text: String, app: tauri::AppHandle) -> Result<(), String> {
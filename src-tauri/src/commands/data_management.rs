@@ -90,6 +90,208 @@ pub fn dm_reset_all_data(app: tauri::AppHandle) -> Result<String, String> {
     Ok(path)
 }
 
+#[derive(Deserialize)]
+pub struct StorageGcPayload {
+    #[serde(default, alias = "dry_run", alias = "dryRun")]
+    dry_run: bool,
+}
+
+#[tauri::command]
+pub fn dm_collect_storage_garbage(payload: StorageGcPayload) -> Result<crate::services::data_management::storage_gc::StorageGcReport, String> {
+    crate::services::data_management::storage_gc::run_storage_gc(payload.dry_run)
+}
+
+#[derive(Deserialize)]
+pub struct SnapshotIdPayload {
+    id: String,
+}
+
+#[derive(Deserialize)]
+pub struct SnapshotItemsPayload {
+    id: String,
+    #[serde(default)]
+    kind: String,
+    #[serde(default)]
+    search: Option<String>,
+    #[serde(default = "default_snapshot_items_limit")]
+    limit: u32,
+}
+
+fn default_snapshot_items_limit() -> u32 {
+    200
+}
+
+#[derive(Deserialize)]
+pub struct RestoreSnapshotItemsPayload {
+    id: String,
+    #[serde(default, alias = "clipboard_ids", alias = "clipboardIds")]
+    clipboard_ids: Vec<i64>,
+    #[serde(default, alias = "favorite_ids", alias = "favoriteIds")]
+    favorite_ids: Vec<String>,
+}
+
+#[tauri::command]
+pub fn dm_create_snapshot() -> Result<crate::services::data_management::snapshots::SnapshotManifest, String> {
+    crate::services::data_management::snapshots::create_snapshot(crate::services::data_management::snapshots::TRIGGER_MANUAL)
+}
+
+#[tauri::command]
+pub fn dm_list_snapshots() -> Result<Vec<crate::services::data_management::snapshots::SnapshotSummary>, String> {
+    crate::services::data_management::snapshots::list_snapshots()
+}
+
+#[tauri::command]
+pub fn dm_verify_snapshots() -> Result<Vec<crate::services::data_management::snapshots::SnapshotVerifyResult>, String> {
+    crate::services::data_management::snapshots::verify_snapshots()
+}
+
+#[tauri::command]
+pub fn dm_restore_snapshot(app: tauri::AppHandle, payload: SnapshotIdPayload) -> Result<crate::services::data_management::snapshots::SnapshotManifest, String> {
+    let manifest = crate::services::data_management::snapshots::restore_snapshot(&payload.id)?;
+    let _ = crate::commands::window::emit_clipboard_updated_event(&app, None);
+    let _ = crate::commands::window::emit_quick_texts_updated_event(&app, None);
+    Ok(manifest)
+}
+
+#[tauri::command]
+pub fn dm_list_snapshot_items(payload: SnapshotItemsPayload) -> Result<Vec<crate::services::data_management::snapshots::SnapshotItem>, String> {
+    crate::services::data_management::snapshots::list_snapshot_items(&payload.id, &payload.kind, payload.search.as_deref(), payload.limit)
+}
+
+#[tauri::command]
+pub fn dm_restore_snapshot_items(app: tauri::AppHandle, payload: RestoreSnapshotItemsPayload) -> Result<crate::services::data_management::snapshots::SnapshotRestoreReport, String> {
+    let report = crate::services::data_management::snapshots::restore_snapshot_items(&payload.id, &payload.clipboard_ids, &payload.favorite_ids)?;
+    if report.clipboard > 0 {
+        let _ = crate::commands::window::emit_clipboard_updated_event(&app, None);
+        crate::services::sync_transfer::lan_notify_local_change(app.clone(), "clipboard");
+    }
+    if report.favorites > 0 {
+        let _ = crate::commands::window::emit_quick_texts_updated_event(&app, None);
+        crate::services::sync_transfer::lan_notify_local_change(app.clone(), "favorites");
+    }
+    Ok(report)
+}
+
+#[derive(Deserialize)]
+pub struct PortableExportPayload {
+    #[serde(alias = "target_path", alias = "targetPath")]
+    target_path: String,
+    format: crate::services::data_management::portable::PortableFormat,
+    #[serde(default)]
+    filter: crate::services::data_management::portable::PortableFilter,
+}
+
+#[derive(Deserialize)]
+pub struct PortableImportPayload {
+    #[serde(alias = "source_path", alias = "sourcePath")]
+    source_path: String,
+}
+
+#[tauri::command]
+pub fn dm_export_portable(payload: PortableExportPayload) -> Result<crate::services::data_management::portable::PortableExportReport, String> {
+    let path = std::path::PathBuf::from(payload.target_path);
+    crate::services::data_management::portable::export_portable(&path, payload.format, &payload.filter)
+}
+
+#[tauri::command]
+pub fn dm_import_portable(app: tauri::AppHandle, payload: PortableImportPayload) -> Result<crate::services::data_management::portable::PortableImportReport, String> {
+    let path = std::path::PathBuf::from(payload.source_path);
+    let report = crate::services::data_management::portable::import_portable(&path)?;
+    if report.clipboard > 0 {
+        let _ = crate::commands::window::emit_clipboard_updated_event(&app, None);
+        crate::services::sync_transfer::lan_notify_local_change(app.clone(), "clipboard");
+    }
+    if report.favorites > 0 || report.groups > 0 {
+        let _ = crate::commands::window::emit_quick_texts_updated_event(&app, None);
+        crate::services::sync_transfer::lan_notify_local_change(app.clone(), "favorites");
+    }
+    Ok(report)
+}
+
+#[derive(Deserialize)]
+pub struct ExternalImportPayload {
+    source: crate::services::data_management::importers::ExternalSource,
+    path: String,
+}
+
+#[tauri::command]
+pub fn dm_import_external(app: tauri::AppHandle, payload: ExternalImportPayload) -> Result<crate::services::data_management::portable::PortableImportReport, String> {
+    let path = std::path::PathBuf::from(payload.path);
+    let report = crate::services::data_management::importers::import_external(payload.source, &path)?;
+    if report.clipboard > 0 {
+        let _ = crate::commands::window::emit_clipboard_updated_event(&app, None);
+        crate::services::sync_transfer::lan_notify_local_change(app.clone(), "clipboard");
+    }
+    if report.favorites > 0 || report.groups > 0 {
+        let _ = crate::commands::window::emit_quick_texts_updated_event(&app, None);
+        crate::services::sync_transfer::lan_notify_local_change(app.clone(), "favorites");
+    }
+    Ok(report)
+}
+
+#[derive(Deserialize)]
+pub struct LocalEncryptionPasswordPayload {
+    password: String,
+}
+
+#[derive(Deserialize)]
+pub struct LocalEncryptionRekeyPayload {
+    #[serde(alias = "old_password", alias = "oldPassword")]
+    old_password: String,
+    #[serde(alias = "new_password", alias = "newPassword")]
+    new_password: String,
+}
+
+#[tauri::command]
+pub fn dm_local_encryption_status() -> crate::services::local_encryption::LocalEncryptionStatus {
+    crate::services::local_encryption::status()
+}
+
+#[tauri::command]
+pub async fn dm_enable_local_encryption(app: tauri::AppHandle, payload: LocalEncryptionPasswordPayload) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || crate::services::local_encryption::enable(&payload.password))
+        .await
+        .map_err(|e| format!("启用本地加密失败: {}", e))??;
+    crate::services::local_encryption::notify_changed(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn dm_unlock_local_encryption(app: tauri::AppHandle, payload: LocalEncryptionPasswordPayload) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || crate::services::local_encryption::unlock(&payload.password))
+        .await
+        .map_err(|e| format!("解锁本地数据失败: {}", e))??;
+    crate::services::local_encryption::notify_changed(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn dm_lock_local_encryption(app: tauri::AppHandle) -> Result<(), String> {
+    tokio::task::spawn_blocking(crate::services::local_encryption::lock)
+        .await
+        .map_err(|e| format!("锁定本地数据失败: {}", e))??;
+    crate::services::local_encryption::notify_changed(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn dm_rekey_local_encryption(payload: LocalEncryptionRekeyPayload) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        crate::services::local_encryption::rekey(&payload.old_password, &payload.new_password)
+    })
+    .await
+    .map_err(|e| format!("更换主密码失败: {}", e))?
+}
+
+#[tauri::command]
+pub async fn dm_disable_local_encryption(app: tauri::AppHandle, payload: LocalEncryptionPasswordPayload) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || crate::services::local_encryption::disable(&payload.password))
+        .await
+        .map_err(|e| format!("关闭本地加密失败: {}", e))??;
+    crate::services::local_encryption::notify_changed(&app);
+    Ok(())
+}
+
 #[tauri::command]
 pub fn dm_list_backups() -> Result<Vec<crate::services::data_management::BackupInfo>, String> {
     crate::services::data_management::list_backups()
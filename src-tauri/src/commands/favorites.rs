@@ -56,6 +56,7 @@ pub async fn get_favorites_history(
     group_name: Option<String>,
     search: Option<String>,
     content_type: Option<String>,
+    order_by_relevance: Option<bool>,
 ) -> Result<PaginatedResult<FavoriteItem>, String> {
     tokio::task::spawn_blocking(move || {
         let params = FavoritesQueryParams {
@@ -64,6 +65,7 @@ pub async fn get_favorites_history(
             group_name,
             search,
             content_type,
+            order_by_relevance: order_by_relevance.unwrap_or(false),
         };
 
         let mut result = query_favorites(params)?;
@@ -255,6 +257,8 @@ fn favorite_to_clipboard_item(id: &str) -> Result<crate::services::database::Cli
         char_count: favorite.char_count,
         created_at: favorite.created_at,
         updated_at: favorite.updated_at,
+        expires_at: None,
+        search_snippet: None,
     })
 }
 
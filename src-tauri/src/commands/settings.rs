@@ -97,10 +97,15 @@ pub fn save_settings(mut settings: AppSettings, app: tauri::AppHandle) -> Result
         !old_settings.remember_window_size && settings.remember_window_size;
     let remember_window_size_disabled =
         old_settings.remember_window_size && !settings.remember_window_size;
-    let webdav_crypto_scope_changed = old_settings.webdav_url != settings.webdav_url
+    let webdav_crypto_scope_changed = old_settings.webdav_backend != settings.webdav_backend
+        || old_settings.webdav_url != settings.webdav_url
         || old_settings.webdav_username != settings.webdav_username
-        || old_settings.webdav_root_path != settings.webdav_root_path;
+        || old_settings.webdav_root_path != settings.webdav_root_path
+        || old_settings.webdav_s3_bucket != settings.webdav_s3_bucket
+        || old_settings.webdav_folder_path != settings.webdav_folder_path;
     let show_tray_icon_changed = old_settings.show_tray_icon != settings.show_tray_icon;
+    let local_api_changed = old_settings.local_api_enabled != settings.local_api_enabled
+        || old_settings.local_api_port != settings.local_api_port;
 
     if edge_hide_changed && !settings.edge_hide_enabled {
         settings.edge_snap_position = None;
@@ -155,6 +160,15 @@ pub fn save_settings(mut settings: AppSettings, app: tauri::AppHandle) -> Result
         }
     }
 
+    if local_api_changed {
+        let app_clone = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = crate::services::local_api::apply_settings(app_clone).await {
+                eprintln!("应用本机控制接口设置失败: {}", e);
+            }
+        });
+    }
+
     if show_tray_icon_changed {
         if let Some(tray) = app.tray_by_id("main-tray") {
             if let Err(e) = tray.set_visible(settings.show_tray_icon) {
@@ -448,3 +462,14 @@ pub fn set_one_time_paste_enabled(enabled: bool) -> Result<bool, String> {
     Ok(enabled)
 }
 
+
+// 本机控制接口状态（含访问令牌）
+#[tauri::command]
+pub fn get_local_api_status() -> Result<crate::services::local_api::LocalApiStatus, String> {
+    crate::services::local_api::status()
+}
+
+#[tauri::command]
+pub fn regenerate_local_api_token() -> Result<String, String> {
+    crate::services::local_api::regenerate_token()
+}
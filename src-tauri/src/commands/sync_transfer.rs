@@ -42,6 +42,15 @@ pub fn sync_transfer_lan_remove_paired_peer(device_id: String) -> Result<bool, S
     services::sync_transfer::lan_remove_paired_peer(&device_id)
 }
 
+#[tauri::command]
+pub fn sync_transfer_lan_update_peer_sync_policy(
+    device_id: String,
+    policy: services::sync_transfer::lan::LanPeerSyncPolicy,
+    app: tauri::AppHandle,
+) -> Result<services::sync_transfer::lan::PairedPeerInfo, String> {
+    services::sync_transfer::lan_update_peer_sync_policy(app, &device_id, policy)
+}
+
 #[tauri::command]
 pub async fn sync_transfer_lan_pair_with_peer(
     base_url: String,
@@ -78,7 +87,7 @@ pub async fn sync_transfer_lan_update_auto_sync_settings(
     app: tauri::AppHandle,
 ) -> Result<services::sync_transfer::lan::LanAutoSyncSettings, String> {
     let settings = services::sync_transfer::lan_update_auto_sync_settings(settings)?;
-    if settings.receive_enabled {
+    if services::sync_transfer::lan_needs_http_server() {
         services::sync_transfer::lan_start_http_server(app.clone()).await?;
     } else {
         services::sync_transfer::lan_stop_http_server().await;
@@ -86,6 +95,25 @@ pub async fn sync_transfer_lan_update_auto_sync_settings(
     Ok(settings)
 }
 
+#[tauri::command]
+pub fn sync_transfer_lan_get_live_share_status() -> Result<services::sync_transfer::lan::LanLiveShareStatus, String> {
+    Ok(services::sync_transfer::lan_live_share_status())
+}
+
+#[tauri::command]
+pub async fn sync_transfer_lan_update_live_share_settings(
+    settings: services::sync_transfer::lan::LanLiveShareSettings,
+    app: tauri::AppHandle,
+) -> Result<services::sync_transfer::lan::LanLiveShareSettings, String> {
+    let settings = services::sync_transfer::lan_update_live_share_settings(settings)?;
+    if services::sync_transfer::lan_needs_http_server() {
+        services::sync_transfer::lan_start_http_server(app).await?;
+    } else {
+        services::sync_transfer::lan_stop_http_server().await;
+    }
+    Ok(settings)
+}
+
 #[tauri::command]
 pub async fn sync_transfer_lan_pull_from_peer(device_id: String, app: tauri::AppHandle) -> Result<services::webdav_sync::SyncReport, String> {
     let report = services::sync_transfer::lan_pull_from_peer(&device_id).await?;
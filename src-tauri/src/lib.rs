@@ -112,7 +112,11 @@ pub fn run() {
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_drag::init())
-        .plugin(tauri_plugin_store::Builder::new().build());
+        .plugin(tauri_plugin_store::Builder::new().build())
+        // 启用本地加密时图片缓存是密文，前端通过该协议读取解密后的图片
+        .register_uri_scheme_protocol("qcimage", |_ctx, request| {
+            services::local_encryption::image_protocol_response(request.uri().path())
+        });
     
     #[cfg(feature = "gpu-image-viewer")]
     let builder = builder.plugin(gpu_image_viewer::init());
@@ -184,6 +188,7 @@ pub fn run() {
                 commands::get_clipboard_item_paste_options_cmd,
                 commands::update_clipboard_item_cmd,
                 commands::toggle_pin_clipboard_item,
+                commands::set_clipboard_item_expiry,
                 commands::paste_text_direct,
                 commands::paste_image_file,
                 commands::move_clipboard_item,
@@ -199,6 +204,12 @@ pub fn run() {
                 commands::copy_clipboard_item,
                 commands::merge_copy_clipboard_items,
                 commands::merge_paste_clipboard_items,
+                commands::get_paste_queue_status,
+                commands::set_paste_queue_armed,
+                commands::set_paste_queue_order,
+                commands::enqueue_paste_queue_items,
+                commands::clear_paste_queue,
+                commands::paste_next_queued_item,
                 commands::resolve_image_path,
                 commands::get_favorites_history,
                 commands::get_favorites_total_count,
@@ -246,6 +257,8 @@ pub fn run() {
                 commands::save_quickpaste_window_size,
                 commands::get_one_time_paste_enabled,
                 commands::set_one_time_paste_enabled,
+                commands::get_local_api_status,
+                commands::regenerate_local_api_token,
                 commands::dm_get_current_storage_path,
                 commands::dm_get_default_storage_path,
                 commands::dm_check_target_has_data,
@@ -255,6 +268,22 @@ pub fn run() {
                 commands::dm_import_data_zip,
                 commands::dm_reset_all_data,
                 commands::dm_list_backups,
+                commands::dm_collect_storage_garbage,
+                commands::dm_create_snapshot,
+                commands::dm_list_snapshots,
+                commands::dm_verify_snapshots,
+                commands::dm_restore_snapshot,
+                commands::dm_list_snapshot_items,
+                commands::dm_restore_snapshot_items,
+                commands::dm_export_portable,
+                commands::dm_import_portable,
+                commands::dm_import_external,
+                commands::dm_local_encryption_status,
+                commands::dm_enable_local_encryption,
+                commands::dm_unlock_local_encryption,
+                commands::dm_lock_local_encryption,
+                commands::dm_rekey_local_encryption,
+                commands::dm_disable_local_encryption,
                 commands::set_mouse_position,
                 commands::get_mouse_position,
                 commands::start_screenshot,
@@ -302,12 +331,15 @@ pub fn run() {
                 commands::sync_transfer_lan_refresh_pairing_code,
                 commands::sync_transfer_lan_list_paired_peers,
                 commands::sync_transfer_lan_remove_paired_peer,
+                commands::sync_transfer_lan_update_peer_sync_policy,
                 commands::sync_transfer_lan_pair_with_peer,
                 commands::sync_transfer_lan_fetch_peer_snapshot,
                 commands::sync_transfer_lan_get_local_snapshot,
                 commands::sync_transfer_lan_discover_peers,
                 commands::sync_transfer_lan_get_auto_sync_status,
                 commands::sync_transfer_lan_update_auto_sync_settings,
+                commands::sync_transfer_lan_get_live_share_status,
+                commands::sync_transfer_lan_update_live_share_settings,
                 commands::sync_transfer_lan_pull_from_peer,
                 commands::sync_transfer_lan_push_to_peer,
                 commands::sync_transfer_lan_send_file_to_peer,
@@ -392,7 +424,12 @@ pub fn run() {
                 startup_diagnostics::set_startup_stage("执行 setup：初始化数据库");
                 let db_path_buf = get_data_directory()?.join("quickclipboard.db");
                 let db_path_str = db_path_buf.to_str().ok_or("数据库路径无效")?;
+                services::local_encryption::init_before_database();
                 if let Err(e1) = services::database::init_database(db_path_str) {
+                    // 数据库来自更新的版本时不能清理 wal，否则会丢失尚未合并的数据
+                    if e1.starts_with(services::database::migrations::NEWER_SCHEMA_ERROR) {
+                        return Err(e1.into());
+                    }
                     if let Some(dir) = db_path_buf.parent() {
                         for name in ["quickclipboard.db-wal", "quickclipboard.db-shm"] {
                             let p = dir.join(name);
@@ -422,6 +459,9 @@ pub fn run() {
                     }
                 }
                 let _ = services::database::limit_clipboard_history(settings.history_limit);
+                services::clipboard::init_expiry_sweeper(app.handle().clone());
+                services::data_management::snapshots::init_snapshot_scheduler();
+                services::local_encryption::init_idle_lock(app.handle().clone());
                 
                 startup_diagnostics::set_startup_stage("执行 setup：初始化屏幕与输入监听");
                 utils::init_screen_utils(app.handle().clone());
@@ -467,6 +507,15 @@ pub fn run() {
                     });
                 }
 
+                if settings.local_api_enabled {
+                    let app_handle = app.handle().clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = services::local_api::apply_settings(app_handle).await {
+                            eprintln!("启动本机控制接口失败: {}", e);
+                        }
+                    });
+                }
+
                 if settings.clipboard_monitor {
                     let _ = start_clipboard_monitor();
                 }
@@ -498,11 +547,13 @@ pub fn run() {
                     {
                         api.prevent_exit();
                     } else {
+                        services::local_encryption::lock_on_exit();
                         services::webdav_sync::crypto::clear_cached_keys();
                     }
                 }
                 tauri::RunEvent::WindowEvent { label, event: tauri::WindowEvent::Destroyed, .. } => {
                     if label == "main" && !services::low_memory::is_low_memory_mode() {
+                        services::local_encryption::lock_on_exit();
                         services::webdav_sync::crypto::clear_cached_keys();
                         app.exit(0);
                     }
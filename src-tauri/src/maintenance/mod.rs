@@ -138,6 +138,7 @@ pub fn run() {
 
     let app = App {
         db,
+        data_dir,
         items: Vec::new(),
         total_count: 0,
         current_page: 0,
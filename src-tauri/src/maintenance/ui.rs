@@ -1,7 +1,9 @@
 use std::io;
+use std::path::PathBuf;
 
 use chrono::Local;
 use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crate::services::data_management::storage_gc::{collect_storage_garbage, StorageGcOptions, StorageGcReport};
 use ratatui::{
     layout::{Alignment, Constraint, Layout, Rect},
     style::{Color, Modifier, Style},
@@ -49,10 +51,12 @@ pub enum Screen {
     List,
     Detail(usize),
     ConfirmDelete(usize),
+    StorageGc(StorageGcReport),
 }
 
 pub struct App {
     pub db: rusqlite::Connection,
+    pub data_dir: PathBuf,
     pub items: Vec<ClipboardRow>,
     pub total_count: i64,
     pub current_page: usize,
@@ -121,6 +125,16 @@ fn human_timestamp_full(ts: i64) -> String {
     }
 }
 
+fn human_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
 fn content_preview(s: &str, max_len: usize) -> String {
     let s = s.replace('\n', " ").replace('\r', "");
     if s.chars().count() > max_len {
@@ -483,6 +497,39 @@ impl App {
             self.table_state.select(None);
         }
     }
+    // 维护模式下无法读取已配对设备的同步状态，不清理删除记录
+    pub fn scan_storage_garbage(&mut self) {
+        let options = StorageGcOptions { dry_run: true, tombstone_cutoff: None };
+        match collect_storage_garbage(&self.db, &self.data_dir, options) {
+            Ok(report) => self.screen = Screen::StorageGc(report),
+            Err(e) => self.status_message = format!("扫描失败: {}", e),
+        }
+    }
+
+    pub fn run_storage_gc(&mut self) {
+        let options = StorageGcOptions { dry_run: false, tombstone_cutoff: None };
+        match collect_storage_garbage(&self.db, &self.data_dir, options) {
+            Ok(report) => {
+                let freed = report.database_bytes_before.saturating_sub(report.database_bytes_after)
+                    + report.orphan_image_bytes;
+                self.status_message = format!("清理完成，已释放 {}", human_size(freed));
+                match self.current_tab {
+                    Tab::Clipboard => self.load_clipboard(),
+                    Tab::Favorites => self.load_favorites(),
+                    Tab::Groups => self.load_groups(),
+                }
+            }
+            Err(e) => self.status_message = format!("清理失败: {}", e),
+        }
+    }
+
+    pub fn rebuild_search_index(&mut self) {
+        match crate::services::database::search::rebuild_search_index(&self.db) {
+            Ok(()) => self.status_message = "检索索引已重建".to_string(),
+            Err(e) => self.status_message = format!("重建检索索引失败: {}", e),
+        }
+    }
+
     pub fn next_page(&mut self) {
         let total_pages = self.tab_total_pages().max(1);
         let page = self.tab_page();
@@ -563,7 +610,7 @@ pub fn draw(f: &mut Frame, app: &mut App) {
     .split(inner);
 
     match app.screen {
-        Screen::List | Screen::ConfirmDelete(_) | Screen::Detail(_) => {
+        Screen::List | Screen::ConfirmDelete(_) | Screen::Detail(_) | Screen::StorageGc(_) => {
             draw_tab_bar(f, app, chunks[0]);
 
             match app.current_tab {
@@ -588,23 +635,24 @@ pub fn draw(f: &mut Frame, app: &mut App) {
                             if app.is_searching {
                                 ("输入关键字后按 Enter 搜索, Tab/Esc 取消搜索 | ←→ 切换面板 | ↑↓ 导航 | PgUp/PgDn 翻页 | D 删除 | Q 退出", Color::Gray)
                             } else {
-                                ("←→ 切换面板 | ↑↓ 导航 | Enter 查看详情 | PgUp/PgDn 翻页 | Tab 搜索 | D 删除 | R 刷新 | Q 退出", Color::Gray)
+                                ("←→ 切换面板 | ↑↓ 导航 | Enter 查看详情 | PgUp/PgDn 翻页 | Tab 搜索 | D 删除 | R 刷新 | G 清理存储 | I 重建索引 | Q 退出", Color::Gray)
                             }
                         }
                         Tab::Favorites => {
                             if app.fav_is_searching {
                                 ("输入关键字后按 Enter 搜索, Tab/Esc 取消搜索 | ←→ 切换面板 | ↑↓ 导航 | PgUp/PgDn 翻页 | D 删除 | Q 退出", Color::Gray)
                             } else {
-                                ("←→ 切换面板 | ↑↓ 导航 | Enter 查看详情 | PgUp/PgDn 翻页 | Tab 搜索 | D 删除 | R 刷新 | Q 退出", Color::Gray)
+                                ("←→ 切换面板 | ↑↓ 导航 | Enter 查看详情 | PgUp/PgDn 翻页 | Tab 搜索 | D 删除 | R 刷新 | G 清理存储 | I 重建索引 | Q 退出", Color::Gray)
                             }
                         }
                         Tab::Groups => {
-                            ("←→ 切换面板 | ↑↓ 导航 | Q 退出", Color::Gray)
+                            ("←→ 切换面板 | ↑↓ 导航 | G 清理存储 | I 重建索引 | Q 退出", Color::Gray)
                         }
                     }
                 }
                 Screen::Detail(_) => ("Esc 返回 | D 删除此项", Color::Gray),
                 Screen::ConfirmDelete(_) => ("Enter / Y 确认删除 | Esc / N 取消", Color::Red),
+                Screen::StorageGc(_) => ("Enter / Y 开始清理 | Esc / N 取消", Color::Yellow),
             };
             let help = Paragraph::new(help_text)
                 .style(Style::new().fg(help_color))
@@ -633,6 +681,7 @@ pub fn draw(f: &mut Frame, app: &mut App) {
                 draw_favorite_confirm_popup(f, item, area);
             }
         }
+        (Screen::StorageGc(report), _) => draw_storage_gc_popup(f, report, area),
         _ => {}
     }
     if !app.status_message.is_empty() {
@@ -1055,6 +1104,63 @@ fn draw_clipboard_confirm_popup(f: &mut Frame, item: &ClipboardRow, area: Rect)
     let p = Paragraph::new(text);
     f.render_widget(p, inner);
 }
+fn draw_storage_gc_popup(f: &mut Frame, report: &StorageGcReport, area: Rect) {
+    let popup_width = 56u16;
+    let popup_height = 12u16;
+    let popup_area = Rect {
+        x: area.x + (area.width.saturating_sub(popup_width)) / 2,
+        y: area.y + (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::bordered()
+        .title(" 清理存储空间 ")
+        .title_alignment(Alignment::Center)
+        .border_style(Style::new().fg(Color::Yellow))
+        .style(Style::new().bg(Color::Rgb(30, 30, 40)));
+
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let text = vec![
+        Line::from(""),
+        Line::from(format!(
+            "  孤立图片: {} 个 ({})",
+            report.orphan_image_count,
+            human_size(report.orphan_image_bytes)
+        )),
+        Line::from(format!(
+            "  孤立原始格式数据: {} 条 ({})",
+            report.orphan_raw_format_count,
+            human_size(report.orphan_raw_format_bytes)
+        )),
+        Line::from(format!(
+            "  数据库空闲空间: {}",
+            human_size(report.database_free_bytes)
+        )),
+        Line::from(vec![Span::styled(
+            "  删除记录需在应用内清理（需要设备同步状态）",
+            Style::new().fg(Color::Gray),
+        )]),
+        Line::from(""),
+        Line::from(vec![Span::styled(
+            format!("  预计可回收: {}", human_size(report.reclaimable_bytes())),
+            Style::new().fg(Color::White).add_modifier(Modifier::BOLD),
+        )]),
+        Line::from(""),
+        Line::from(vec![Span::styled(
+            "  [ Enter / Y 清理 ]    [ Esc / N 取消 ]",
+            Style::new().fg(Color::Yellow),
+        )]),
+    ];
+
+    let p = Paragraph::new(text);
+    f.render_widget(p, inner);
+}
+
 fn draw_favorite_detail_popup(f: &mut Frame, item: &FavoriteRow, area: Rect) {
     let popup_width = area.width.saturating_sub(12).min(80);
     let popup_height = area.height.saturating_sub(6).min(20);
@@ -1247,6 +1353,7 @@ fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> io::Result<()> {
                 Screen::List => handle_list_input(app, key),
                 Screen::Detail(_) => handle_detail_input(app, key),
                 Screen::ConfirmDelete(idx) => handle_confirm_input(app, key, *idx),
+                Screen::StorageGc(_) => handle_storage_gc_input(app, key),
             }
         }
     }
@@ -1341,6 +1448,9 @@ fn handle_list_input(app: &mut App, key: event::KeyEvent) {
             app.status_message = "已刷新".into();
         }
 
+        KeyCode::Char('g') | KeyCode::Char('G') => app.scan_storage_garbage(),
+        KeyCode::Char('i') | KeyCode::Char('I') => app.rebuild_search_index(),
+
         KeyCode::Down | KeyCode::Char('j') => app.select_next(),
         KeyCode::Up | KeyCode::Char('k') => app.select_prev(),
         KeyCode::PageDown => app.next_page(),
@@ -1441,3 +1551,16 @@ fn handle_confirm_input(app: &mut App, key: event::KeyEvent, idx: usize) {
         _ => {}
     }
 }
+
+fn handle_storage_gc_input(app: &mut App, key: event::KeyEvent) {
+    match key.code {
+        KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => {
+            app.screen = Screen::List;
+            app.run_storage_gc();
+        }
+        KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => {
+            app.screen = Screen::List;
+        }
+        _ => {}
+    }
+}
@@ -53,7 +53,7 @@ fn save_clipboard_image(rust_image: RustImageData) -> Result<String, String> {
         return Ok(format!("clipboard_images/{}", filename));
     }
 
-    std::fs::write(&final_path, &png_data).map_err(|e| e.to_string())?;
+    crate::services::local_encryption::write_file(&final_path, &png_data)?;
 
     Ok(format!("clipboard_images/{}", filename))
 }
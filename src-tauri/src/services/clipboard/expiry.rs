@@ -0,0 +1,64 @@
+// 过期与保留策略清理
+// - 设置了 expires_at 的条目到期后删除（敏感内容、手动设置过期）
+// - 按设置中的保留规则删除超龄条目
+// 删除走 delete_clipboard_items，会记录同步删除状态，WebDAV / 局域网对端同样会删除
+
+use crate::commands::window::emit_clipboard_updated_event;
+use crate::services::database::{delete_clipboard_items_older_than, delete_expired_clipboard_items};
+use crate::services::settings::RetentionRule;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::AppHandle;
+
+const SWEEP_INTERVAL_SECS: u64 = 30;
+
+static SWEEPER_STARTED: AtomicBool = AtomicBool::new(false);
+
+pub fn init_expiry_sweeper(app: AppHandle) {
+    if SWEEPER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(SWEEP_INTERVAL_SECS));
+
+        loop {
+            interval.tick().await;
+
+            let deleted = match tauri::async_runtime::spawn_blocking(sweep_once).await {
+                Ok(Ok(count)) => count,
+                Ok(Err(e)) => {
+                    eprintln!("清理过期剪贴板项失败: {}", e);
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("清理过期剪贴板项任务失败: {}", e);
+                    continue;
+                }
+            };
+
+            if deleted > 0 {
+                let _ = emit_clipboard_updated_event(&app, None);
+                crate::services::sync_transfer::lan_notify_local_change(app.clone(), "clipboard");
+            }
+        }
+    });
+}
+
+fn sweep_once() -> Result<usize, String> {
+    let mut deleted = delete_expired_clipboard_items()?;
+
+    let settings = crate::services::get_settings();
+    let now = chrono::Local::now().timestamp();
+    for rule in settings.retention_rules.iter().filter(|rule| rule.enabled && rule.max_age_days > 0) {
+        deleted += apply_retention_rule(rule, now)?;
+    }
+
+    Ok(deleted)
+}
+
+fn apply_retention_rule(rule: &RetentionRule, now: i64) -> Result<usize, String> {
+    let cutoff = now - rule.max_age_days as i64 * 24 * 60 * 60;
+    let content_type = Some(rule.content_type.trim())
+        .filter(|content_type| !content_type.is_empty() && *content_type != "all");
+    delete_clipboard_items_older_than(cutoff, content_type, rule.include_pinned)
+}
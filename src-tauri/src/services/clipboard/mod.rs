@@ -1,9 +1,13 @@
 mod capture;
 mod content_type;
+mod expiry;
 mod monitor;
 mod processor;
+mod rules;
+mod sensitive;
 mod storage;
 
+pub use expiry::init_expiry_sweeper;
 pub use monitor::{
     clear_last_content_cache, get_app_handle, is_monitor_running, pause_clipboard_monitor_for,
     set_app_handle, set_last_hash_contents, set_last_hash_file, set_last_hash_files,
@@ -1,5 +1,6 @@
 use super::capture::ClipboardContent;
 use super::processor::process_content;
+use super::rules::apply_capture_rules;
 use super::storage::store_clipboard_item;
 use crate::commands::window::{emit_clipboard_updated_event, ClipboardUpdatedEventPayload};
 use clipboard_rs::{
@@ -289,12 +290,15 @@ fn process_clipboard_change_once() {
     let mut any_stored = false;
     for content in new_contents {
         match process_content(content) {
-            Ok(processed) => match store_clipboard_item(processed) {
+            Ok(processed) => match apply_capture_rules(processed).and_then(store_clipboard_item) {
                 Ok(id) => {
                     any_stored = true;
+                    crate::services::paste::queue::record_captured_item(id);
                     match crate::services::database::get_clipboard_item_by_id(id) {
                         Ok(Some(mut item)) => {
                             crate::commands::clipboard::hydrate_clipboard_item_for_ui(&mut item);
+                            crate::services::local_api::publish_captured_item(&item);
+                            crate::services::sync_transfer::lan_publish_capture(&item);
                             let insert_index = crate::services::database::get_clipboard_item_position(id)
                                 .ok()
                                 .flatten();
@@ -317,9 +321,10 @@ fn process_clipboard_change_once() {
                     }
 
                 }
-                Err(e) if e.contains("重复内容") || e.contains("已禁止保存图片") => {}
+                Err(e) if e.contains("重复内容") || e.contains("已禁止保存图片") || e.contains("已被捕获规则丢弃") => {}
                 Err(e) => eprintln!("存储剪贴板内容失败: {}", e),
             },
+            Err(e) if e.contains("敏感内容已跳过") => {}
             Err(e) => eprintln!("处理剪贴板内容失败: {}", e),
         }
     }
@@ -415,6 +420,8 @@ mod tests {
             char_count: Some(1),
             created_at: 1,
             updated_at: 1,
+            expires_at: None,
+            search_snippet: None,
         }
     }
 }
@@ -42,10 +42,20 @@ pub struct ProcessedContent {
     pub source_app: Option<String>,      
     pub source_icon_hash: Option<String>,
     pub raw_formats: Vec<ClipboardDataSeed>,
+    pub expires_at: Option<i64>,
+    // 捕获规则设置：自动置顶、自动收藏到指定分组
+    pub pinned: bool,
+    pub favorite_group: Option<String>,
 }
 
 // 处理剪贴板内容，将原始数据转换为可存储的格式
 pub fn process_content(content: ClipboardContent) -> Result<ProcessedContent, String> {
+    let mut processed = convert_content(content)?;
+    super::sensitive::apply_sensitive_policy(&mut processed, &crate::services::get_settings())?;
+    Ok(processed)
+}
+
+fn convert_content(content: ClipboardContent) -> Result<ProcessedContent, String> {
     let (source_app, source_icon_hash) = get_source_info();
     
     match content.content_type {
@@ -77,6 +87,9 @@ pub fn process_content(content: ClipboardContent) -> Result<ProcessedContent, St
                 source_app,
                 source_icon_hash,
                 raw_formats: content.raw_formats,
+                expires_at: None,
+                pinned: false,
+                favorite_group: None,
             })
         }
         
@@ -118,6 +131,9 @@ pub fn process_content(content: ClipboardContent) -> Result<ProcessedContent, St
                 source_app,
                 source_icon_hash,
                 raw_formats: content.raw_formats,
+                expires_at: None,
+                pinned: false,
+                favorite_group: None,
             })
         }
         
@@ -154,6 +170,9 @@ pub fn process_content(content: ClipboardContent) -> Result<ProcessedContent, St
                 source_app,
                 source_icon_hash,
                 raw_formats: content.raw_formats,
+                expires_at: None,
+                pinned: false,
+                favorite_group: None,
             })
         }
     }
@@ -399,9 +418,11 @@ fn fetch_image_data(src: &str) -> Result<Vec<u8>, String> {
     } else if src.starts_with("file://") {
         let path = src.trim_start_matches("file://");
         let path = path.trim_start_matches('/');
-        std::fs::read(path).map_err(|e| format!("读取本地图片失败 [{}]: {}", path, e))
+        crate::services::local_encryption::read_file(std::path::Path::new(path))
+            .map_err(|e| format!("读取本地图片失败 [{}]: {}", path, e))
     } else if std::path::Path::new(&src).exists() {
-        std::fs::read(&src).map_err(|e| format!("读取图片失败 [{}]: {}", src, e))
+        crate::services::local_encryption::read_file(std::path::Path::new(&src))
+            .map_err(|e| format!("读取图片失败 [{}]: {}", src, e))
     } else {
         Err(format!("不支持的图片源或文件不存在: {}", src))
     }
@@ -480,7 +501,7 @@ fn save_image_as_file(image_data: &[u8]) -> Result<String, String> {
     let image_path = images_dir.join(format!("{}.png", image_id));
     
     if !image_path.exists() {
-        fs::write(&image_path, png_data)
+        crate::services::local_encryption::write_file(&image_path, &png_data)
             .map_err(|e| format!("保存图片文件失败: {}", e))?;
     }
     
@@ -0,0 +1,279 @@
+// 捕获规则
+//
+// 在内容处理（含敏感检测）之后、入库之前按顺序执行用户配置的规则。
+// 每条规则按来源应用、内容类型、正则、长度匹配，命中后执行丢弃、整理文本、
+// 清理链接追踪参数、转纯文本、自动收藏到分组或自动置顶。
+
+use super::processor::ProcessedContent;
+use crate::services::settings::CaptureRule;
+use crate::services::system::app_filter::wildcard_match;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+pub const CAPTURE_RULE_DROPPED_ERROR: &str = "已被捕获规则丢弃";
+
+static URL_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r#"https?://[^\s<>"'`]+"#).unwrap());
+
+// 常见的广告 / 统计追踪参数
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "twclid", "ttclid",
+    "igshid", "mc_cid", "mc_eid", "_ga", "_gl", "_hsenc", "_hsmi", "mkt_tok", "ref_src",
+    "spm", "si", "vero_id",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CaptureAction {
+    Drop,
+    Trim,
+    StripTracking,
+    PlainText,
+    AssignGroup,
+    Pin,
+}
+
+impl CaptureAction {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "drop" => Some(CaptureAction::Drop),
+            "trim" => Some(CaptureAction::Trim),
+            "strip_tracking" => Some(CaptureAction::StripTracking),
+            "plain_text" => Some(CaptureAction::PlainText),
+            "assign_group" => Some(CaptureAction::AssignGroup),
+            "pin" => Some(CaptureAction::Pin),
+            _ => None,
+        }
+    }
+}
+
+// 读取设置并执行捕获规则
+pub fn apply_capture_rules(mut content: ProcessedContent) -> Result<ProcessedContent, String> {
+    let settings = crate::services::get_settings();
+    run_capture_rules(&mut content, &settings.capture_rules)?;
+    Ok(content)
+}
+
+// 按顺序执行规则；后面的规则基于前面规则修改后的内容匹配
+pub fn run_capture_rules(content: &mut ProcessedContent, rules: &[CaptureRule]) -> Result<(), String> {
+    for rule in rules.iter().filter(|rule| rule.enabled) {
+        let Some(action) = CaptureAction::parse(&rule.action) else {
+            continue;
+        };
+        if !rule_matches(rule, content) {
+            continue;
+        }
+
+        match action {
+            CaptureAction::Drop => return Err(CAPTURE_RULE_DROPPED_ERROR.to_string()),
+            CaptureAction::Trim => trim_content(content)?,
+            CaptureAction::StripTracking => strip_tracking_content(content),
+            CaptureAction::PlainText => convert_to_plain_text(content),
+            CaptureAction::AssignGroup => {
+                let group_name = rule.group_name.trim();
+                if !group_name.is_empty() {
+                    content.favorite_group = Some(group_name.to_string());
+                }
+            }
+            CaptureAction::Pin => content.pinned = true,
+        }
+    }
+    Ok(())
+}
+
+fn rule_matches(rule: &CaptureRule, content: &ProcessedContent) -> bool {
+    let source_app = rule.source_app.trim();
+    if !source_app.is_empty() {
+        let app = content.source_app.as_deref().unwrap_or_default();
+        let matched = if source_app.contains('*') || source_app.contains('?') {
+            wildcard_match(source_app, app)
+        } else {
+            app.to_lowercase().contains(&source_app.to_lowercase())
+        };
+        if !matched {
+            return false;
+        }
+    }
+
+    let content_type = rule.content_type.trim();
+    if !content_type.is_empty()
+        && content_type != "all"
+        && !content.content_type.split(',').any(|t| t == content_type)
+    {
+        return false;
+    }
+
+    // 正则与长度条件只对文本内容生效
+    let has_text_conditions = !rule.pattern.is_empty() || rule.min_length.is_some() || rule.max_length.is_some();
+    if !has_text_conditions {
+        return true;
+    }
+    if !is_text_content(content) {
+        return false;
+    }
+
+    let length = content.content.chars().count() as u32;
+    if rule.min_length.is_some_and(|min| length < min) || rule.max_length.is_some_and(|max| length > max) {
+        return false;
+    }
+
+    if !rule.pattern.is_empty() {
+        return match Regex::new(&rule.pattern) {
+            Ok(regex) => regex.is_match(&content.content),
+            Err(_) => false,
+        };
+    }
+
+    true
+}
+
+fn is_text_content(content: &ProcessedContent) -> bool {
+    let content_type = &content.content_type;
+    content_type.contains("text") || content_type.contains("link")
+}
+
+// 修改了文本后原始格式与内容不再一致，一并丢弃
+fn trim_content(content: &mut ProcessedContent) -> Result<(), String> {
+    if !is_text_content(content) {
+        return Ok(());
+    }
+    let trimmed = content.content.trim();
+    if trimmed.is_empty() {
+        return Err(CAPTURE_RULE_DROPPED_ERROR.to_string());
+    }
+    if trimmed.len() != content.content.len() {
+        content.content = trimmed.to_string();
+        content.raw_formats.clear();
+    }
+    Ok(())
+}
+
+fn strip_tracking_content(content: &mut ProcessedContent) {
+    if !is_text_content(content) {
+        return;
+    }
+    let stripped = strip_tracking_params(&content.content);
+    if stripped == content.content {
+        return;
+    }
+    content.content = stripped;
+    content.html_content = content.html_content.as_deref().map(strip_tracking_params);
+    content.raw_formats.clear();
+}
+
+fn convert_to_plain_text(content: &mut ProcessedContent) {
+    if !content.content_type.contains("rich_text") {
+        return;
+    }
+    content.html_content = None;
+    content.content_type = content.content_type.replacen("rich_text", "text", 1);
+    content.raw_formats.clear();
+}
+
+// 清理文本中所有链接的追踪参数，保留其余参数与锚点
+pub fn strip_tracking_params(text: &str) -> String {
+    URL_REGEX
+        .replace_all(text, |caps: &regex::Captures| strip_url_tracking(&caps[0]))
+        .into_owned()
+}
+
+fn strip_url_tracking(url: &str) -> String {
+    let (without_fragment, fragment) = match url.find('#') {
+        Some(pos) => url.split_at(pos),
+        None => (url, ""),
+    };
+    let Some((base, query)) = without_fragment.split_once('?') else {
+        return url.to_string();
+    };
+
+    let kept: Vec<&str> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .filter(|pair| {
+            let key = pair.split('=').next().unwrap_or_default().to_lowercase();
+            !(key.starts_with("utm_") || TRACKING_PARAMS.contains(&key.as_str()))
+        })
+        .collect();
+
+    if kept.is_empty() {
+        format!("{}{}", base, fragment)
+    } else {
+        format!("{}?{}{}", base, kept.join("&"), fragment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_content(text: &str) -> ProcessedContent {
+        ProcessedContent {
+            content: text.to_string(),
+            html_content: None,
+            content_type: "text".to_string(),
+            image_id: None,
+            source_app: Some("Google Chrome".to_string()),
+            source_icon_hash: None,
+            raw_formats: Vec::new(),
+            expires_at: None,
+            pinned: false,
+            favorite_group: None,
+        }
+    }
+
+    fn rule(action: &str) -> CaptureRule {
+        CaptureRule {
+            action: action.to_string(),
+            ..CaptureRule::default()
+        }
+    }
+
+    #[test]
+    fn strips_tracking_params_but_keeps_others() {
+        assert_eq!(
+            strip_tracking_params("see https://example.com/a?utm_source=x&id=7&fbclid=abc#top now"),
+            "see https://example.com/a?id=7#top now"
+        );
+        assert_eq!(strip_tracking_params("https://example.com/?utm_medium=mail"), "https://example.com/");
+        assert_eq!(strip_tracking_params("no links here"), "no links here");
+    }
+
+    #[test]
+    fn rules_match_on_source_app_type_pattern_and_length() {
+        let drop_chrome_otp = [CaptureRule {
+            source_app: "*chrome*".to_string(),
+            content_type: "text".to_string(),
+            pattern: r"^\d{6}$".to_string(),
+            ..rule("drop")
+        }];
+        assert_eq!(
+            run_capture_rules(&mut text_content("123456"), &drop_chrome_otp),
+            Err(CAPTURE_RULE_DROPPED_ERROR.to_string())
+        );
+        assert!(run_capture_rules(&mut text_content("1234567"), &drop_chrome_otp).is_ok());
+
+        let mut from_other_app = text_content("123456");
+        from_other_app.source_app = Some("Terminal".to_string());
+        assert!(run_capture_rules(&mut from_other_app, &drop_chrome_otp).is_ok());
+
+        let drop_short = [CaptureRule { max_length: Some(2), ..rule("drop") }];
+        assert!(run_capture_rules(&mut text_content("ab"), &drop_short).is_err());
+        assert!(run_capture_rules(&mut text_content("abc"), &drop_short).is_ok());
+    }
+
+    #[test]
+    fn rules_run_in_order_and_accumulate_actions() {
+        let rules = vec![
+            rule("trim"),
+            CaptureRule { pattern: "^https://".to_string(), ..rule("strip_tracking") },
+            CaptureRule { group_name: "链接".to_string(), content_type: "link".to_string(), ..rule("assign_group") },
+            CaptureRule { enabled: false, ..rule("drop") },
+            rule("pin"),
+        ];
+        let mut content = text_content("  https://example.com/?gclid=1&q=rust \n");
+        content.content_type = "text,link".to_string();
+        run_capture_rules(&mut content, &rules).expect("规则执行应成功");
+
+        assert_eq!(content.content, "https://example.com/?q=rust");
+        assert_eq!(content.favorite_group.as_deref(), Some("链接"));
+        assert!(content.pinned);
+    }
+}
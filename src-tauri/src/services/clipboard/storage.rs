@@ -2,6 +2,7 @@ use super::processor::ProcessedContent;
 use crate::services::database::connection::with_connection;
 use crate::services::database::clipboard::limit_clipboard_history;
 use crate::services::database::ClipboardDataSeed;
+use crate::services::database::{add_clipboard_to_favorites, favorite_content_exists};
 use crate::services::settings::get_settings;
 use rusqlite::params;
 use chrono;
@@ -45,13 +46,14 @@ pub fn store_clipboard_item(content: ProcessedContent) -> Result<i64, String> {
             return Ok(clipboard_id);
         }
 
-        let new_order = next_item_order(&tx, 0, None)?;
+        let is_pinned = i64::from(content.pinned);
+        let new_order = next_item_order(&tx, is_pinned, None)?;
         let char_count = calculate_char_count(&content.content, &content.content_type);
         let uuid = Uuid::new_v4().to_string();
         
         tx.execute(
-            "INSERT INTO clipboard (content, html_content, content_type, image_id, item_order, source_app, source_icon_hash, char_count, uuid, source_device_id, is_remote, created_at, updated_at) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            "INSERT INTO clipboard (content, html_content, content_type, image_id, item_order, source_app, source_icon_hash, char_count, uuid, source_device_id, is_remote, created_at, updated_at, expires_at, is_pinned) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
             params![
                 &content.content,
                 content.html_content.as_deref(),
@@ -65,7 +67,9 @@ pub fn store_clipboard_item(content: ProcessedContent) -> Result<i64, String> {
                 Option::<&str>::None,
                 0,
                 now,
-                now
+                now,
+                content.expires_at,
+                is_pinned
             ],
         )?;
 
@@ -82,6 +86,9 @@ pub fn store_clipboard_item(content: ProcessedContent) -> Result<i64, String> {
     
     match result {
         Ok(id) => {
+            if let Some(group_name) = content.favorite_group.as_deref() {
+                assign_favorite_group(id, &content.content, group_name);
+            }
             let _ = limit_clipboard_history(settings.history_limit);
             Ok(id)
         },
@@ -140,7 +147,8 @@ fn refresh_duplicate_item(
     duplicate: DuplicateClipboardItem,
     now: i64,
 ) -> Result<i64, rusqlite::Error> {
-    let new_order = next_item_order(conn, duplicate.is_pinned, Some(duplicate.id))?;
+    let is_pinned = if content.pinned { 1 } else { duplicate.is_pinned };
+    let new_order = next_item_order(conn, is_pinned, Some(duplicate.id))?;
     let char_count = calculate_char_count(&content.content, &content.content_type);
 
     let rows = conn.execute(
@@ -153,8 +161,10 @@ fn refresh_duplicate_item(
              source_app = ?6,
              source_icon_hash = ?7,
              char_count = ?8,
-             updated_at = ?9
-         WHERE id = ?10",
+             updated_at = ?9,
+             expires_at = ?10,
+             is_pinned = ?11
+         WHERE id = ?12",
         params![
             &content.content,
             content.html_content.as_deref(),
@@ -165,6 +175,8 @@ fn refresh_duplicate_item(
             content.source_icon_hash.as_deref(),
             char_count,
             now,
+            content.expires_at,
+            is_pinned,
             duplicate.id,
         ],
     )?;
@@ -186,6 +198,19 @@ fn refresh_duplicate_item(
     Ok(duplicate.id)
 }
 
+// 捕获规则自动收藏：同一分组已有相同内容时不再重复添加
+fn assign_favorite_group(clipboard_id: i64, content: &str, group_name: &str) {
+    match favorite_content_exists(content, group_name) {
+        Ok(true) => {}
+        Ok(false) => {
+            if let Err(e) = add_clipboard_to_favorites(clipboard_id, Some(group_name.to_string())) {
+                eprintln!("捕获规则自动收藏失败: {}", e);
+            }
+        }
+        Err(e) => eprintln!("捕获规则自动收藏失败: {}", e),
+    }
+}
+
 fn next_item_order(
     conn: &rusqlite::Connection,
     is_pinned: i64,
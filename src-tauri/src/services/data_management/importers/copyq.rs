@@ -0,0 +1,298 @@
+// CopyQ 标签页数据导入
+//
+// CopyQ 把每个标签页保存为 copyq_tab_<base64 标签名>.dat，内容是 Qt QDataStream（大端）：
+//   [QString "CopyQ v.."]      新版本才有的文件头
+//   qint32 条目数
+//   每个条目以 qint32 开头：
+//     -2：之后是 qint32 格式数，每个格式为 MIME、bool 是否压缩、QByteArray 数据
+//     ≥0：旧格式，即格式数，每个格式为 QString MIME、qCompress 压缩的 QByteArray
+// 新格式的 MIME 可能去掉了常见前缀，这里只按结尾识别文本、HTML、图片和备注；
+// 以 "FILE:" 开头的格式表示数据另存为文件，内容是文件路径。
+// "&clipboard" 标签导入为历史记录，其他标签导入为同名分组的收藏。
+// CopyQ 不记录复制时间，按条目顺序从文件修改时间往前倒推，保持原有顺序。
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose, Engine as _};
+
+use super::{first_line, image_record, stable_id, text_record};
+use crate::services::data_management::portable::{PortableData, PortableGroup, PortableRecord, KIND_FAVORITE};
+
+const SOURCE: &str = "copyq";
+const TAB_FILE_PREFIX: &str = "copyq_tab_";
+const CLIPBOARD_TAB: &str = "&clipboard";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum MimeRole {
+    Text,
+    Html,
+    Image,
+    Notes,
+    Other,
+}
+
+struct QtReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> QtReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.data.len()).ok_or("CopyQ 数据不完整")?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn read_i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn read_bool(&mut self) -> Result<bool, String> {
+        Ok(self.take(1)?[0] != 0)
+    }
+
+    // QByteArray / QString 都以 quint32 字节数开头，0xFFFFFFFF 表示空值
+    fn read_bytes(&mut self) -> Result<&'a [u8], String> {
+        let len = self.read_i32()? as u32;
+        if len == u32::MAX {
+            return Ok(&[]);
+        }
+        self.take(len as usize)
+    }
+
+    fn read_string(&mut self) -> Result<String, String> {
+        Ok(decode_utf16be(self.read_bytes()?))
+    }
+}
+
+fn decode_utf16be(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+    String::from_utf16_lossy(&units)
+}
+
+// 新格式的 MIME 可能以 QString 或 UTF-8 QByteArray 写入，按内容判断
+fn decode_mime(bytes: &[u8]) -> String {
+    let looks_utf16 = bytes.len() >= 2 && bytes.len().is_multiple_of(2) && bytes.iter().step_by(2).all(|b| *b == 0);
+    if looks_utf16 {
+        decode_utf16be(bytes)
+    } else {
+        String::from_utf8_lossy(bytes).into_owned()
+    }
+}
+
+fn mime_role(mime: &str) -> MimeRole {
+    let mime = mime.to_ascii_lowercase();
+    let tail = match mime.rsplit_once('/') {
+        Some((_, tail)) => tail.to_string(),
+        // 缩写形式：首字符是前缀编号
+        None => mime.chars().skip(1).collect(),
+    };
+    if tail.ends_with("item-notes") {
+        MimeRole::Notes
+    } else if tail.starts_with("plain") {
+        MimeRole::Text
+    } else if tail == "html" {
+        MimeRole::Html
+    } else if ["png", "jpeg", "jpg", "gif", "bmp", "webp"].contains(&tail.as_str()) {
+        MimeRole::Image
+    } else {
+        MimeRole::Other
+    }
+}
+
+// qCompress：4 字节大端原始长度 + zlib 数据
+fn q_uncompress(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    if bytes.len() < 4 {
+        return Ok(Vec::new());
+    }
+    let mut out = Vec::new();
+    flate2::read::ZlibDecoder::new(&bytes[4..])
+        .read_to_end(&mut out)
+        .map_err(|e| format!("解压 CopyQ 数据失败: {}", e))?;
+    Ok(out)
+}
+
+fn read_item(reader: &mut QtReader, base_dir: &Path) -> Result<HashMap<MimeRole, Vec<u8>>, String> {
+    let mut formats = HashMap::new();
+    let marker = reader.read_i32()?;
+
+    if marker == -2 {
+        let count = reader.read_i32()?;
+        for _ in 0..count.max(0) {
+            let mime = decode_mime(reader.read_bytes()?);
+            let compressed = reader.read_bool()?;
+            let mut value = reader.read_bytes()?.to_vec();
+            if compressed {
+                value = q_uncompress(&value)?;
+            }
+            let role = match mime.strip_prefix("FILE:") {
+                Some(real_mime) => {
+                    let path = base_dir.join(String::from_utf8_lossy(&value).trim());
+                    value = std::fs::read(path).unwrap_or_default();
+                    mime_role(real_mime)
+                }
+                None => mime_role(&mime),
+            };
+            formats.entry(role).or_insert(value);
+        }
+    } else if marker >= 0 {
+        for _ in 0..marker {
+            let mime = reader.read_string()?;
+            let value = q_uncompress(reader.read_bytes()?)?;
+            formats.entry(mime_role(&mime)).or_insert(value);
+        }
+    } else {
+        return Err(format!("不支持的 CopyQ 条目格式: {}", marker));
+    }
+    Ok(formats)
+}
+
+fn parse_tab(data: &[u8], base_dir: &Path) -> Result<Vec<HashMap<MimeRole, Vec<u8>>>, String> {
+    let mut reader = QtReader::new(data);
+    let header_start = reader.pos;
+    let has_header = reader.read_string().map(|h| h.starts_with("CopyQ v")).unwrap_or(false);
+    if !has_header {
+        reader.pos = header_start;
+    }
+
+    let count = reader.read_i32()?;
+    let mut items = Vec::new();
+    for _ in 0..count.max(0) {
+        items.push(read_item(&mut reader, base_dir)?);
+    }
+    Ok(items)
+}
+
+fn tab_name(path: &Path) -> String {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let encoded = stem.strip_prefix(TAB_FILE_PREFIX).unwrap_or(stem);
+    general_purpose::STANDARD
+        .decode(encoded)
+        .or_else(|_| general_purpose::URL_SAFE.decode(encoded))
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .unwrap_or_else(|| encoded.to_string())
+}
+
+fn tab_files(path: &Path) -> Result<Vec<PathBuf>, String> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let entries = std::fs::read_dir(path).map_err(|e| format!("读取 CopyQ 配置目录失败: {}", e))?;
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|p| {
+            p.extension().map(|e| e == "dat").unwrap_or(false)
+                && p.file_name().and_then(|n| n.to_str()).map(|n| n.starts_with(TAB_FILE_PREFIX)).unwrap_or(false)
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+pub(super) fn read(path: &Path) -> Result<PortableData, String> {
+    let mut data = PortableData::default();
+    for file in tab_files(path)? {
+        let bytes = std::fs::read(&file).map_err(|e| format!("读取 CopyQ 数据失败: {}", e))?;
+        let modified = std::fs::metadata(&file)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_else(|| chrono::Local::now().timestamp());
+        let base_dir = file.parent().unwrap_or(Path::new("."));
+        let items = parse_tab(&bytes, base_dir).map_err(|e| format!("{}: {}", file.display(), e))?;
+        append_tab(&mut data, &tab_name(&file), items, modified);
+    }
+    Ok(data)
+}
+
+fn append_tab(data: &mut PortableData, tab: &str, items: Vec<HashMap<MimeRole, Vec<u8>>>, newest_at: i64) {
+    let is_history = tab == CLIPBOARD_TAB;
+    let mut has_items = false;
+
+    for (index, formats) in items.into_iter().enumerate() {
+        let created_at = newest_at - index as i64;
+        let Some(mut record) = item_record(&formats, created_at) else { continue };
+        // 相同内容在同一标签页中唯一，用标签名和内容哈希作为标识
+        record.id = stable_id(SOURCE, &format!("{}\n{}\n{}", tab, record.content, record.image_id.clone().unwrap_or_default()));
+        if !is_history {
+            let notes = formats.get(&MimeRole::Notes).map(|n| String::from_utf8_lossy(n).into_owned());
+            record.kind = KIND_FAVORITE.to_string();
+            record.title = notes.or_else(|| Some(record.content.clone())).map(|t| first_line(&t)).filter(|t| !t.is_empty());
+            record.group_name = Some(tab.to_string());
+        }
+        data.records.push(record);
+        has_items = true;
+    }
+
+    if has_items && !is_history {
+        data.groups.push(PortableGroup {
+            name: tab.to_string(),
+            icon: "ti ti-folder".to_string(),
+            color: "#dc2626".to_string(),
+        });
+    }
+}
+
+fn item_record(formats: &HashMap<MimeRole, Vec<u8>>, created_at: i64) -> Option<PortableRecord> {
+    let text = formats
+        .get(&MimeRole::Text)
+        .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+        .filter(|text| !text.is_empty());
+    if let Some(text) = text {
+        let html = formats
+            .get(&MimeRole::Html)
+            .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+            .filter(|html| !html.trim().is_empty());
+        return Some(text_record(text, html, created_at));
+    }
+    formats.get(&MimeRole::Image).and_then(|bytes| image_record(bytes, created_at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::data_management::portable::KIND_CLIPBOARD;
+
+    #[test]
+    fn reads_copyq_tab_fixtures() {
+        let dir = super::super::fixture_path("copyq");
+        let history = parse_tab(&std::fs::read(dir.join("copyq_tab_JmNsaXBib2FyZA==.dat")).unwrap(), &dir).unwrap();
+        assert_eq!(history.len(), 3);
+
+        let mut data = PortableData::default();
+        append_tab(&mut data, CLIPBOARD_TAB, history, 1000);
+        assert_eq!(data.records.len(), 3);
+        assert!(data.records.iter().all(|r| r.kind == KIND_CLIPBOARD));
+        assert_eq!(data.records[0].content, "newest text");
+        assert_eq!(data.records[0].created_at, 1000);
+        assert_eq!(data.records[1].html_content.as_deref(), Some("<i>compressed</i>"));
+        assert_eq!(data.records[2].content_type, "image");
+
+        let data = read(&dir).unwrap();
+        assert_eq!(tab_name(&dir.join("copyq_tab_U25pcHBldHM=.dat")), "Snippets");
+        let favorite = data.records.iter().find(|r| r.kind == KIND_FAVORITE).unwrap();
+        assert_eq!(favorite.group_name.as_deref(), Some("Snippets"));
+        assert_eq!(favorite.title.as_deref(), Some("Deploy command"));
+        assert_eq!(favorite.content, "kubectl apply -f .");
+        assert_eq!(data.groups.len(), 1);
+    }
+
+    #[test]
+    fn recognizes_abbreviated_mime() {
+        assert_eq!(mime_role("text/plain"), MimeRole::Text);
+        assert_eq!(mime_role("1plain"), MimeRole::Text);
+        assert_eq!(mime_role("image/png"), MimeRole::Image);
+        assert_eq!(mime_role("application/x-copyq-item-notes"), MimeRole::Notes);
+        assert_eq!(mime_role("0owner"), MimeRole::Other);
+    }
+}
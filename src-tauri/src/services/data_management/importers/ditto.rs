@@ -0,0 +1,226 @@
+// Ditto 数据库导入
+//
+// Ditto 使用 SQLite：Main 表每行一个条目（lDate 为秒级时间戳，mText 为文本描述），
+// bIsGroup = 1 的行是分组，条目通过 lParentID 归属分组；
+// Data 表按剪贴板格式保存原始数据，lParentID 指向 Main.lID。
+// 分组内的条目导入为收藏，其余导入为历史记录。文件列表（CF_HDROP）等其他格式不导入。
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use rusqlite::{params, Connection, OpenFlags};
+
+use super::{first_line, image_record, stable_id, text_record};
+use crate::services::data_management::portable::{PortableData, PortableGroup, PortableRecord, KIND_FAVORITE};
+
+const SOURCE: &str = "ditto";
+
+struct DittoClip {
+    id: i64,
+    date: i64,
+    text: String,
+    parent_id: i64,
+}
+
+pub(super) fn read(path: &Path) -> Result<PortableData, String> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("打开 Ditto 数据库失败: {}", e))?;
+    read_connection(&conn).map_err(|e| format!("读取 Ditto 数据库失败: {}", e))
+}
+
+fn table_columns(conn: &Connection, table: &str) -> Result<HashSet<String>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    rows.collect()
+}
+
+fn read_connection(conn: &Connection) -> Result<PortableData, rusqlite::Error> {
+    let columns = table_columns(conn, "Main")?;
+    if !columns.contains("lID") || !columns.contains("mText") {
+        return Err(rusqlite::Error::InvalidQuery);
+    }
+    let is_group = if columns.contains("bIsGroup") { "bIsGroup" } else { "0" };
+    let parent = if columns.contains("lParentID") { "lParentID" } else { "-1" };
+    let date = if columns.contains("lDate") { "lDate" } else { "0" };
+
+    let mut groups: HashMap<i64, String> = HashMap::new();
+    let mut clips = Vec::new();
+    let sql = format!(
+        "SELECT lID, {}, COALESCE(mText, ''), {}, {} FROM Main ORDER BY lID",
+        date, is_group, parent
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let clip = DittoClip {
+            id: row.get(0)?,
+            date: row.get::<_, Option<i64>>(1)?.unwrap_or(0),
+            text: row.get(2)?,
+            parent_id: row.get::<_, Option<i64>>(4)?.unwrap_or(-1),
+        };
+        if row.get::<_, Option<i64>>(3)?.unwrap_or(0) != 0 {
+            groups.insert(clip.id, first_line(&clip.text));
+        } else {
+            clips.push(clip);
+        }
+    }
+
+    let mut data = PortableData::default();
+    let mut used_groups = HashSet::new();
+    let mut format_stmt = conn.prepare("SELECT strClipBoardFormat, ooData FROM Data WHERE lParentID = ?1")?;
+
+    for clip in clips {
+        let mut formats: HashMap<String, Vec<u8>> = HashMap::new();
+        let mut rows = format_stmt.query(params![clip.id])?;
+        while let Some(row) = rows.next()? {
+            let name: String = row.get(0)?;
+            let data: Option<Vec<u8>> = row.get(1)?;
+            formats.insert(name, data.unwrap_or_default());
+        }
+
+        let Some(mut record) = clip_record(&clip, &formats) else { continue };
+        record.id = stable_id(SOURCE, &clip.id.to_string());
+        if let Some(group) = groups.get(&clip.parent_id).filter(|g| !g.is_empty()) {
+            record.kind = KIND_FAVORITE.to_string();
+            record.title = Some(first_line(&clip.text)).filter(|t| !t.is_empty());
+            record.group_name = Some(group.clone());
+            used_groups.insert(group.clone());
+        }
+        data.records.push(record);
+    }
+
+    let mut group_names: Vec<String> = used_groups.into_iter().collect();
+    group_names.sort();
+    data.groups = group_names
+        .into_iter()
+        .map(|name| PortableGroup {
+            name,
+            icon: "ti ti-folder".to_string(),
+            color: "#dc2626".to_string(),
+        })
+        .collect();
+    Ok(data)
+}
+
+fn clip_record(clip: &DittoClip, formats: &HashMap<String, Vec<u8>>) -> Option<PortableRecord> {
+    let text = formats
+        .get("CF_UNICODETEXT")
+        .map(|bytes| decode_utf16le(bytes))
+        .or_else(|| formats.get("CF_TEXT").map(|bytes| decode_ansi(bytes)))
+        .filter(|text| !text.is_empty());
+
+    if let Some(text) = text {
+        let html = formats
+            .get("HTML Format")
+            .map(|bytes| decode_ansi(bytes))
+            .map(|html| crate::utils::cf_html::normalize_clipboard_html(&html))
+            .filter(|html| !html.trim().is_empty());
+        return Some(text_record(text, html, clip.date));
+    }
+
+    let image = formats
+        .get("PNG")
+        .and_then(|bytes| image_record(bytes, clip.date))
+        .or_else(|| formats.get("CF_DIB").and_then(|dib| dib_to_bmp(dib)).and_then(|bmp| image_record(&bmp, clip.date)));
+    if image.is_some() {
+        return image;
+    }
+
+    // 没有可识别的格式时退回 Ditto 保存的文本描述
+    Some(clip.text.trim_end_matches('\0').to_string())
+        .filter(|text| !text.is_empty())
+        .map(|text| text_record(text, None, clip.date))
+}
+
+fn decode_utf16le(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|unit| *unit != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn decode_ansi(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+// CF_DIB 缺少 BMP 文件头，补上后交给图片解码
+fn dib_to_bmp(dib: &[u8]) -> Option<Vec<u8>> {
+    let u32_at = |offset: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(dib.get(offset..offset + 4)?.try_into().ok()?))
+    };
+    let header_size = u32_at(0)?;
+    let bit_count = u16::from_le_bytes(dib.get(14..16)?.try_into().ok()?);
+    let compression = u32_at(16)?;
+    let colors_used = u32_at(32)?;
+
+    let palette_entries = if colors_used > 0 {
+        colors_used
+    } else if bit_count <= 8 {
+        1 << bit_count
+    } else {
+        0
+    };
+    // BI_BITFIELDS 的颜色掩码紧跟在 40 字节的信息头后面
+    let masks = if header_size == 40 && compression == 3 { 12 } else { 0 };
+    let pixel_offset = 14 + header_size + masks + palette_entries * 4;
+
+    let mut bmp = Vec::with_capacity(14 + dib.len());
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&(14 + dib.len() as u32).to_le_bytes());
+    bmp.extend_from_slice(&0u32.to_le_bytes());
+    bmp.extend_from_slice(&pixel_offset.to_le_bytes());
+    bmp.extend_from_slice(dib);
+    Some(bmp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::data_management::portable::KIND_CLIPBOARD;
+
+    #[test]
+    fn reads_ditto_fixture() {
+        let conn = Connection::open_in_memory().unwrap();
+        let sql = std::fs::read_to_string(super::super::fixture_path("ditto.sql")).unwrap();
+        conn.execute_batch(&sql).unwrap();
+
+        let data = read_connection(&conn).unwrap();
+        assert_eq!(data.records.len(), 4);
+        assert_eq!(data.groups.len(), 1);
+
+        let text = &data.records[0];
+        assert_eq!((text.kind.as_str(), text.content.as_str(), text.created_at), (KIND_CLIPBOARD, "你好 Ditto", 1700000000));
+
+        let html = &data.records[1];
+        assert_eq!(html.content_type, "rich_text,link");
+        assert_eq!(html.html_content.as_deref(), Some("<b>https://example.com</b>"));
+
+        let image = &data.records[2];
+        assert_eq!(image.content_type, "image");
+        assert_eq!(image.images.len(), 1);
+        assert_eq!(image.created_at, 1700000200);
+
+        let favorite = &data.records[3];
+        assert_eq!(favorite.kind, KIND_FAVORITE);
+        assert_eq!(favorite.group_name.as_deref(), Some("Snippets"));
+        assert_eq!(favorite.id, stable_id(SOURCE, "5"));
+    }
+
+    #[test]
+    fn converts_dib_to_bmp() {
+        // 1x1 24 位 DIB
+        let mut dib = vec![0u8; 40];
+        dib[0] = 40;
+        dib[4] = 1;
+        dib[8] = 1;
+        dib[12] = 1;
+        dib[14] = 24;
+        dib.extend_from_slice(&[0, 0, 255, 0]);
+        let bmp = dib_to_bmp(&dib).unwrap();
+        let img = image::load_from_memory(&bmp).unwrap().to_rgb8();
+        assert_eq!(img.get_pixel(0, 0).0, [255, 0, 0]);
+    }
+}
@@ -0,0 +1,180 @@
+// 剪贴板历史 JSON 导入
+//
+// 各种剪贴板历史工具导出的 JSON 字段名不统一，这里按常见字段名宽松识别：
+// - 顶层是数组，或对象中的 items / history / clips / entries / data 数组
+// - 文本：text / content / value / data，HTML：html / htmlContent
+// - 时间：timestamp / time / date / createdAt / created_at / firstCopiedAt 等，
+//   支持秒、毫秒和 RFC 3339 / "YYYY-MM-DD HH:MM:SS" 字符串
+// - 图片：image / imageData，base64 或 data URL
+// - 收藏：favorite / pinned / starred 为真，或带有 group / folder / category 时导入为收藏
+
+use std::path::Path;
+
+use base64::{engine::general_purpose, Engine as _};
+use serde_json::{Map, Value};
+
+use super::{first_line, image_record, stable_id, text_record};
+use crate::services::data_management::portable::{PortableData, PortableGroup, PortableRecord, KIND_FAVORITE};
+
+const SOURCE: &str = "clipboard-history-json";
+const DEFAULT_GROUP: &str = "全部";
+
+const LIST_KEYS: &[&str] = &["items", "history", "clips", "entries", "data"];
+const TEXT_KEYS: &[&str] = &["text", "content", "value", "data", "plainText"];
+const HTML_KEYS: &[&str] = &["html", "htmlContent", "html_content"];
+const TIME_KEYS: &[&str] = &[
+    "timestamp", "time", "date", "createdAt", "created_at", "created", "firstCopiedAt", "lastCopiedAt", "copiedAt",
+];
+const IMAGE_KEYS: &[&str] = &["image", "imageData", "image_data"];
+const FAVORITE_KEYS: &[&str] = &["favorite", "favourite", "pinned", "starred", "isFavorite"];
+const GROUP_KEYS: &[&str] = &["group", "folder", "category", "collection"];
+const TITLE_KEYS: &[&str] = &["title", "name", "label"];
+const ID_KEYS: &[&str] = &["id", "uuid", "_id"];
+
+pub(super) fn read(path: &Path) -> Result<PortableData, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("读取 JSON 文件失败: {}", e))?;
+    parse(text.trim_start_matches('\u{feff}'))
+}
+
+fn parse(text: &str) -> Result<PortableData, String> {
+    let root: Value = serde_json::from_str(text).map_err(|e| format!("JSON 格式错误: {}", e))?;
+    let items = match &root {
+        Value::Array(items) => items,
+        Value::Object(map) => LIST_KEYS
+            .iter()
+            .find_map(|key| map.get(*key).and_then(|v| v.as_array()))
+            .ok_or("JSON 中没有找到条目列表")?,
+        _ => return Err("JSON 中没有找到条目列表".to_string()),
+    };
+
+    let now = chrono::Local::now().timestamp();
+    let mut data = PortableData::default();
+    for (index, item) in items.iter().enumerate() {
+        let record = match item {
+            Value::String(text) if !text.is_empty() => Some(text_record(text.clone(), None, now - index as i64)),
+            Value::Object(map) => item_record(map, now - index as i64),
+            _ => None,
+        };
+        let Some(mut record) = record else { continue };
+
+        let key = get_str(item.as_object(), ID_KEYS)
+            .map(|id| format!("id:{}", id))
+            .unwrap_or_else(|| format!("{}\n{}", record.content, record.image_id.clone().unwrap_or_default()));
+        record.id = stable_id(SOURCE, &key);
+
+        if let Some(map) = item.as_object() {
+            let group = get_str(Some(map), GROUP_KEYS).filter(|g| !g.trim().is_empty());
+            let favorite = FAVORITE_KEYS.iter().any(|key| map.get(*key).and_then(|v| v.as_bool()).unwrap_or(false));
+            if favorite || group.is_some() {
+                let group_name = group.unwrap_or_else(|| DEFAULT_GROUP.to_string());
+                if group_name != DEFAULT_GROUP && !data.groups.iter().any(|g| g.name == group_name) {
+                    data.groups.push(PortableGroup {
+                        name: group_name.clone(),
+                        icon: "ti ti-folder".to_string(),
+                        color: "#dc2626".to_string(),
+                    });
+                }
+                record.kind = KIND_FAVORITE.to_string();
+                record.title = get_str(Some(map), TITLE_KEYS)
+                    .or_else(|| Some(first_line(&record.content)))
+                    .filter(|t| !t.is_empty());
+                record.group_name = Some(group_name);
+            }
+        }
+        data.records.push(record);
+    }
+    Ok(data)
+}
+
+fn get_str(map: Option<&Map<String, Value>>, keys: &[&str]) -> Option<String> {
+    let map = map?;
+    keys.iter().find_map(|key| match map.get(*key)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    })
+}
+
+fn item_record(map: &Map<String, Value>, fallback_time: i64) -> Option<PortableRecord> {
+    let created_at = TIME_KEYS
+        .iter()
+        .find_map(|key| map.get(*key).and_then(parse_time))
+        .unwrap_or(fallback_time);
+
+    if let Some(text) = get_str(Some(map), TEXT_KEYS).filter(|t| !t.is_empty()) {
+        let html = get_str(Some(map), HTML_KEYS).filter(|h| !h.trim().is_empty());
+        return Some(text_record(text, html, created_at));
+    }
+
+    let image = get_str(Some(map), IMAGE_KEYS)?;
+    let encoded = match image.split_once(',') {
+        Some((prefix, data)) if prefix.starts_with("data:") => data,
+        _ => image.as_str(),
+    };
+    let bytes = general_purpose::STANDARD.decode(encoded.trim()).ok()?;
+    image_record(&bytes, created_at)
+}
+
+// 数值大于 1e11 视为毫秒
+fn parse_time(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(n) => {
+            let n = n.as_f64()?;
+            Some(if n.abs() > 1e11 { (n / 1000.0) as i64 } else { n as i64 })
+        }
+        Value::String(s) => {
+            let s = s.trim();
+            if let Ok(n) = s.parse::<f64>() {
+                return parse_time(&Value::from(n));
+            }
+            if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) {
+                return Some(dt.timestamp());
+            }
+            ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y/%m/%d %H:%M:%S"]
+                .iter()
+                .find_map(|fmt| chrono::NaiveDateTime::parse_from_str(s, fmt).ok())
+                .and_then(|naive| naive.and_local_timezone(chrono::Local).earliest())
+                .map(|dt| dt.timestamp())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::data_management::portable::KIND_CLIPBOARD;
+
+    #[test]
+    fn reads_history_json_fixture() {
+        let data = read(&super::super::fixture_path("clipboard_history.json")).unwrap();
+        assert_eq!(data.records.len(), 4);
+
+        let first = &data.records[0];
+        assert_eq!((first.kind.as_str(), first.content.as_str(), first.created_at), (KIND_CLIPBOARD, "plain entry", 1700000000));
+        assert_eq!(data.records[1].created_at, 1700000100);
+        assert_eq!(data.records[1].content_type, "text,link");
+
+        let favorite = &data.records[2];
+        assert_eq!(favorite.kind, KIND_FAVORITE);
+        assert_eq!(favorite.group_name.as_deref(), Some("Work"));
+        assert_eq!(favorite.title.as_deref(), Some("Signature"));
+        assert_eq!(favorite.created_at, 1700000200);
+
+        let image = &data.records[3];
+        assert_eq!(image.content_type, "image");
+        assert_eq!(image.images.len(), 1);
+        assert_eq!(data.groups.len(), 1);
+
+        // 重复读取得到相同的标识，导入时才能去重
+        let again = read(&super::super::fixture_path("clipboard_history.json")).unwrap();
+        assert_eq!(again.records[0].id, first.id);
+    }
+
+    #[test]
+    fn parses_time_formats() {
+        assert_eq!(parse_time(&Value::from(1700000000)), Some(1700000000));
+        assert_eq!(parse_time(&Value::from(1700000000123i64)), Some(1700000000));
+        assert_eq!(parse_time(&Value::from("2023-11-14T22:13:20Z")), Some(1700000000));
+    }
+}
@@ -0,0 +1,119 @@
+// 其他剪贴板管理器的数据导入
+//
+// 每个导入器只负责把对方的存储格式读成 PortableData，
+// 去重、分组创建和图片落盘统一交给 portable::import_data 处理。
+// 条目 ID 由来源和对方的条目标识生成，重复导入同一份数据不会产生重复条目。
+
+mod copyq;
+mod ditto;
+mod history_json;
+
+use std::io::Cursor;
+use std::path::Path;
+
+use base64::{engine::general_purpose, Engine as _};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use super::portable::{self, PortableData, PortableImage, PortableImportReport, PortableRecord, KIND_CLIPBOARD};
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalSource {
+    Ditto,
+    Copyq,
+    ClipboardHistoryJson,
+}
+
+pub fn read_external(source: ExternalSource, path: &Path) -> Result<PortableData, String> {
+    match source {
+        ExternalSource::Ditto => ditto::read(path),
+        ExternalSource::Copyq => copyq::read(path),
+        ExternalSource::ClipboardHistoryJson => history_json::read(path),
+    }
+}
+
+pub fn import_external(source: ExternalSource, path: &Path) -> Result<PortableImportReport, String> {
+    let data = read_external(source, path)?;
+    if data.records.is_empty() {
+        return Err("没有找到可导入的条目".to_string());
+    }
+    portable::import_data(&data)
+}
+
+// 由来源和原条目标识生成固定的 UUID
+fn stable_id(source: &str, key: &str) -> String {
+    let digest = Sha256::digest(format!("{}:{}", source, key).as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Uuid::from_bytes(bytes).to_string()
+}
+
+fn text_record(text: String, html: Option<String>, created_at: i64) -> PortableRecord {
+    let mut content_type = if html.is_some() { "rich_text" } else { "text" }.to_string();
+    let trimmed = text.trim();
+    if trimmed.starts_with("http://") || trimmed.starts_with("https://") || trimmed.starts_with("www.") {
+        content_type.push_str(",link");
+    }
+    PortableRecord {
+        kind: KIND_CLIPBOARD.to_string(),
+        content_type,
+        content: text,
+        html_content: html,
+        created_at,
+        updated_at: created_at,
+        ..Default::default()
+    }
+}
+
+// 图片统一转成 PNG 并按内容哈希命名，条目结构与截取剪贴板图片时一致
+fn image_record(bytes: &[u8], created_at: i64) -> Option<PortableRecord> {
+    let img = image::load_from_memory(bytes).ok()?;
+    let mut png = Vec::new();
+    img.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png).ok()?;
+
+    let image_id = format!("{:x}", Sha256::digest(&png))[..16].to_string();
+    let files = serde_json::json!({
+        "files": [{
+            "path": format!("clipboard_images/{}.png", image_id),
+            "name": format!("{}.png", image_id),
+            "size": png.len(),
+            "is_directory": false,
+            "file_type": "PNG",
+            "exists": true,
+            "width": img.width(),
+            "height": img.height(),
+        }],
+        "operation": "copy",
+    });
+
+    Some(PortableRecord {
+        kind: KIND_CLIPBOARD.to_string(),
+        content_type: "image".to_string(),
+        content: format!("files:{}", files),
+        image_id: Some(image_id.clone()),
+        images: vec![PortableImage {
+            id: image_id,
+            data: general_purpose::STANDARD.encode(png),
+        }],
+        created_at,
+        updated_at: created_at,
+        ..Default::default()
+    })
+}
+
+// 收藏标题取首个非空行
+fn first_line(text: &str) -> String {
+    text.lines()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+        .chars()
+        .take(80)
+        .collect()
+}
+
+#[cfg(test)]
+fn fixture_path(name: &str) -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/importers").join(name)
+}
@@ -1,3 +1,8 @@
+pub mod importers;
+pub mod portable;
+pub mod snapshots;
+pub mod storage_gc;
+
 use std::{collections::HashMap, fs, path::{Path, PathBuf}, time::SystemTime};
 use chrono::Local;
 use serde::Serialize;
@@ -236,7 +241,7 @@ pub fn reset_all_data() -> Result<String, String> {
     let current_dir = get_current_storage_dir()?;
     let default_dir = get_default_data_dir()?;
 
-    let _ = crate::services::database::connection::with_connection(|conn| {
+    let _ = crate::services::database::connection::with_database_file(|conn| {
         conn.execute_batch("PRAGMA wal_checkpoint(FULL); PRAGMA wal_checkpoint(TRUNCATE);")
     });
     let _ = backup_full_zip(&current_dir);
@@ -268,7 +273,7 @@ pub fn reset_all_data() -> Result<String, String> {
 
     let db_path = default_dir.join("quickclipboard.db");
     init_database(db_path.to_str().ok_or("数据库路径无效")?)?;
-    let _ = crate::services::database::connection::with_connection(|conn| {
+    let _ = crate::services::database::connection::with_database_file(|conn| {
         conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
     });
 
@@ -309,7 +314,7 @@ pub fn import_data_zip(zip_path: PathBuf, mode: &str) -> Result<String, String>
     match mode {
         "replace" => {
             let current_dir_for_backup = get_current_storage_dir()?;
-            let _ = crate::services::database::connection::with_connection(|conn| {
+            let _ = crate::services::database::connection::with_database_file(|conn| {
                 conn.execute_batch("PRAGMA wal_checkpoint(FULL); PRAGMA wal_checkpoint(TRUNCATE);")
             });
             let _ = backup_full_zip(&current_dir_for_backup);
@@ -366,7 +371,7 @@ pub fn import_data_zip(zip_path: PathBuf, mode: &str) -> Result<String, String>
 
             let db_path = target_dir.join("quickclipboard.db");
             init_database(db_path.to_str().ok_or("数据库路径无效")?)?;
-            let _ = crate::services::database::connection::with_connection(|conn| {
+            let _ = crate::services::database::connection::with_database_file(|conn| {
                 conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
             });
 
@@ -454,7 +459,7 @@ pub fn change_storage_dir(new_dir: PathBuf, mode: &str) -> Result<PathBuf, Strin
 
     let db_path = new_dir.join("quickclipboard.db");
     init_database(db_path.to_str().ok_or("数据库路径无效")?)?;
-    let _ = crate::services::database::connection::with_connection(|conn| {
+    let _ = crate::services::database::connection::with_database_file(|conn| {
         conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
     });
 
@@ -496,6 +501,11 @@ fn merge_dir_no_overwrite(src: &Path, dst: &Path) -> Result<(), String> {
 }
 
 fn merge_database(src_db: &Path) -> Result<(), String> {
+    // 导入的数据库来自启用了本地加密的备份时，先解密再按新 ID 写入；该数据库随后会被删除
+    if crate::services::local_encryption::is_enabled() {
+        let import = rusqlite::Connection::open(src_db).map_err(|e| format!("打开导入数据库失败: {}", e))?;
+        crate::services::local_encryption::open_database_copy(&import)?;
+    }
     with_connection(|conn| {
         let import_path = src_db
             .to_str()
@@ -949,7 +959,7 @@ pub fn reset_storage_dir_to_default(mode: &str) -> Result<PathBuf, String> {
 
     let db_path = default_dir.join("quickclipboard.db");
     init_database(db_path.to_str().ok_or("数据库路径无效")?)?;
-    let _ = crate::services::database::connection::with_connection(|conn| {
+    let _ = crate::services::database::connection::with_database_file(|conn| {
         conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
     });
 
@@ -957,7 +967,7 @@ pub fn reset_storage_dir_to_default(mode: &str) -> Result<PathBuf, String> {
 }
 
 fn change_storage_dir_internal(src_dir: &Path, dst_dir: &Path, mode: &str) -> Result<(), String> {
-    let _ = crate::services::database::connection::with_connection(|conn| {
+    let _ = crate::services::database::connection::with_database_file(|conn| {
         conn.execute_batch("PRAGMA wal_checkpoint(FULL); PRAGMA wal_checkpoint(TRUNCATE);")
     });
     let _ = backup_full_zip(src_dir);
@@ -1079,7 +1089,7 @@ fn change_storage_dir_internal(src_dir: &Path, dst_dir: &Path, mode: &str) -> Re
 
 pub fn export_data_zip(target_path: PathBuf) -> Result<PathBuf, String> {
     let current_dir = get_current_storage_dir()?;
-    let _ = crate::services::database::connection::with_connection(|conn| {
+    let _ = crate::services::database::connection::with_database_file(|conn| {
         conn.execute_batch("PRAGMA wal_checkpoint(FULL); PRAGMA wal_checkpoint(TRUNCATE);")
     });
     close_database();
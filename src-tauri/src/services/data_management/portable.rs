@@ -0,0 +1,887 @@
+// 开放格式导出 / 导入
+//
+// 按分组、时间范围和内容类型筛选历史与收藏，导出为：
+// - JSON Lines：每行一条记录，原始格式和图片以 base64 内嵌，可完整还原
+// - CSV：只包含文本字段，便于用表格工具查看和编辑
+// - Markdown：每条记录一个代码块，元数据写在 HTML 注释里，适合放进 git 仓库共享
+// 三种格式都可以再导入。历史记录按 UUID、收藏按 ID 去重，已存在的条目跳过。
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use base64::{engine::general_purpose, Engine as _};
+use rusqlite::{params, params_from_iter, types::Value, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::services::database::connection::with_connection;
+use crate::services::database::search::escape_like;
+use crate::services::database::tombstones::{delete_sync_tombstone_in_conn, COLLECTION_FAVORITES, COLLECTION_HISTORY};
+
+pub const KIND_CLIPBOARD: &str = "clipboard";
+pub const KIND_FAVORITE: &str = "favorite";
+const KIND_GROUP: &str = "group";
+
+const DEFAULT_GROUP: &str = "全部";
+const MARKDOWN_ITEM_PREFIX: &str = "<!-- qc-item ";
+const MARKDOWN_GROUP_PREFIX: &str = "<!-- qc-group ";
+const MARKDOWN_COMMENT_SUFFIX: &str = " -->";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PortableFormat {
+    Jsonl,
+    Csv,
+    Markdown,
+}
+
+impl PortableFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "jsonl" | "ndjson" | "json" => Some(Self::Jsonl),
+            "csv" => Some(Self::Csv),
+            "md" | "markdown" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+}
+
+// 导出筛选条件，分组只作用于收藏；列表为空表示不限制
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PortableFilter {
+    #[serde(default)]
+    pub include_history: bool,
+    #[serde(default)]
+    pub include_favorites: bool,
+    #[serde(default)]
+    pub groups: Vec<String>,
+    #[serde(default)]
+    pub content_types: Vec<String>,
+    // 创建时间范围（秒，含两端）
+    #[serde(default)]
+    pub from: Option<i64>,
+    #[serde(default)]
+    pub to: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortableGroup {
+    pub name: String,
+    #[serde(default = "default_group_icon")]
+    pub icon: String,
+    #[serde(default = "default_group_color")]
+    pub color: String,
+}
+
+fn default_group_icon() -> String {
+    "ti ti-folder".to_string()
+}
+
+fn default_group_color() -> String {
+    "#dc2626".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortableRawFormat {
+    pub format_name: String,
+    // base64
+    pub data: String,
+    #[serde(default)]
+    pub is_primary: bool,
+    #[serde(default)]
+    pub format_order: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortableImage {
+    pub id: String,
+    // base64 编码的 PNG
+    pub data: String,
+}
+
+// 导出记录；字段尽量宽松，手写的 JSON / Markdown 缺少 ID 或时间时导入会自动补齐
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortableRecord {
+    pub kind: String,
+    #[serde(default)]
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_name: Option<String>,
+    #[serde(default = "default_content_type")]
+    pub content_type: String,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html_content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_id: Option<String>,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub raw_formats: Vec<PortableRawFormat>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<PortableImage>,
+}
+
+fn default_content_type() -> String {
+    "text".to_string()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PortableData {
+    pub groups: Vec<PortableGroup>,
+    pub records: Vec<PortableRecord>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PortableExportReport {
+    pub clipboard: u32,
+    pub favorites: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct PortableImportReport {
+    pub clipboard: u32,
+    pub favorites: u32,
+    pub groups: u32,
+    pub skipped: u32,
+}
+
+// ==================== 读取 ====================
+
+pub fn collect_portable_data(
+    conn: &Connection,
+    filter: &PortableFilter,
+    embed_binary: Option<&Path>,
+) -> Result<PortableData, rusqlite::Error> {
+    let mut data = PortableData::default();
+
+    if filter.include_history {
+        data.records.extend(query_history(conn, filter)?);
+    }
+    if filter.include_favorites {
+        let favorites = query_favorites(conn, filter)?;
+        let used: HashSet<&str> = favorites.iter().filter_map(|r| r.group_name.as_deref()).collect();
+        let mut stmt = conn.prepare("SELECT name, icon, color FROM groups ORDER BY order_index")?;
+        let groups = stmt.query_map([], |row| {
+            Ok(PortableGroup {
+                name: row.get(0)?,
+                icon: row.get(1)?,
+                color: row.get(2)?,
+            })
+        })?;
+        for group in groups {
+            let group = group?;
+            if used.contains(group.name.as_str()) {
+                data.groups.push(group);
+            }
+        }
+        data.records.extend(favorites);
+    }
+
+    if let Some(data_dir) = embed_binary {
+        for record in &mut data.records {
+            attach_binary(conn, data_dir, record)?;
+        }
+    }
+    Ok(data)
+}
+
+fn filter_conditions(filter: &PortableFilter, alias: &str, sql: &mut String, values: &mut Vec<Value>) {
+    if !filter.content_types.is_empty() {
+        let parts: Vec<String> = filter
+            .content_types
+            .iter()
+            .map(|content_type| {
+                values.push(Value::Text(format!("%{}%", escape_like(content_type))));
+                format!("{}.content_type LIKE ? ESCAPE '\\'", alias)
+            })
+            .collect();
+        sql.push_str(&format!(" AND ({})", parts.join(" OR ")));
+    }
+    if let Some(from) = filter.from {
+        sql.push_str(&format!(" AND {}.created_at >= ?", alias));
+        values.push(Value::Integer(from));
+    }
+    if let Some(to) = filter.to {
+        sql.push_str(&format!(" AND {}.created_at <= ?", alias));
+        values.push(Value::Integer(to));
+    }
+}
+
+fn query_history(conn: &Connection, filter: &PortableFilter) -> Result<Vec<PortableRecord>, rusqlite::Error> {
+    let mut sql = String::from(
+        "SELECT c.id, c.uuid, c.content, c.html_content, c.content_type, c.image_id, c.created_at, c.updated_at
+         FROM clipboard c WHERE 1 = 1",
+    );
+    let mut values = Vec::new();
+    filter_conditions(filter, "c", &mut sql, &mut values);
+    sql.push_str(" ORDER BY c.created_at, c.id");
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(params_from_iter(values.iter()), |row| {
+        let id: i64 = row.get(0)?;
+        let uuid: Option<String> = row.get(1)?;
+        Ok(PortableRecord {
+            kind: KIND_CLIPBOARD.to_string(),
+            // 与同步一致：没有 UUID 的本地记录用行号作为标识
+            id: uuid.filter(|u| !u.trim().is_empty()).unwrap_or_else(|| id.to_string()),
+            content: row.get(2)?,
+            html_content: row.get(3)?,
+            content_type: row.get(4)?,
+            image_id: row.get(5)?,
+            created_at: row.get(6)?,
+            updated_at: row.get(7)?,
+            ..Default::default()
+        })
+    })?;
+    rows.collect()
+}
+
+fn query_favorites(conn: &Connection, filter: &PortableFilter) -> Result<Vec<PortableRecord>, rusqlite::Error> {
+    let mut sql = String::from(
+        "SELECT f.id, f.title, f.group_name, f.content, f.html_content, f.content_type, f.image_id, f.created_at, f.updated_at
+         FROM favorites f WHERE 1 = 1",
+    );
+    let mut values = Vec::new();
+    if !filter.groups.is_empty() {
+        let placeholders = vec!["?"; filter.groups.len()].join(", ");
+        sql.push_str(&format!(" AND f.group_name IN ({})", placeholders));
+        values.extend(filter.groups.iter().map(|g| Value::Text(g.clone())));
+    }
+    filter_conditions(filter, "f", &mut sql, &mut values);
+    sql.push_str(" ORDER BY f.group_name, f.item_order");
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(params_from_iter(values.iter()), |row| {
+        Ok(PortableRecord {
+            kind: KIND_FAVORITE.to_string(),
+            id: row.get(0)?,
+            title: Some(row.get(1)?),
+            group_name: Some(row.get(2)?),
+            content: row.get(3)?,
+            html_content: row.get(4)?,
+            content_type: row.get(5)?,
+            image_id: row.get(6)?,
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+            ..Default::default()
+        })
+    })?;
+    rows.collect()
+}
+
+// 内嵌原始格式和图片文件，缺失的图片直接跳过
+fn attach_binary(conn: &Connection, data_dir: &Path, record: &mut PortableRecord) -> Result<(), rusqlite::Error> {
+    let target_id = if record.kind == KIND_CLIPBOARD {
+        conn.query_row(
+            "SELECT CAST(id AS TEXT) FROM clipboard WHERE uuid = ?1 OR ((uuid IS NULL OR uuid = '') AND CAST(id AS TEXT) = ?1)",
+            params![record.id],
+            |row| row.get::<_, String>(0),
+        )?
+    } else {
+        record.id.clone()
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT format_name, raw_data, is_primary, format_order FROM clipboard_data
+         WHERE target_kind = ?1 AND target_id = ?2 ORDER BY format_order",
+    )?;
+    let rows = stmt.query_map(params![record.kind, target_id], |row| {
+        Ok(PortableRawFormat {
+            format_name: row.get(0)?,
+            data: general_purpose::STANDARD.encode(row.get::<_, Vec<u8>>(1)?),
+            is_primary: row.get(2)?,
+            format_order: row.get(3)?,
+        })
+    })?;
+    record.raw_formats = rows.collect::<Result<_, _>>()?;
+
+    let images_dir = data_dir.join("clipboard_images");
+    record.images = split_image_ids(record.image_id.as_deref())
+        .into_iter()
+        .filter_map(|id| {
+            let bytes = crate::services::local_encryption::read_file(&images_dir.join(format!("{}.png", id))).ok()?;
+            Some(PortableImage {
+                id,
+                data: general_purpose::STANDARD.encode(bytes),
+            })
+        })
+        .collect();
+    Ok(())
+}
+
+fn split_image_ids(image_id: Option<&str>) -> Vec<String> {
+    image_id
+        .unwrap_or_default()
+        .split(',')
+        .map(|id| id.trim())
+        .filter(|id| !id.is_empty())
+        .map(|id| id.to_string())
+        .collect()
+}
+
+// ==================== 格式化 ====================
+
+pub fn write_jsonl(data: &PortableData) -> Result<String, String> {
+    let mut out = String::new();
+    for group in &data.groups {
+        let mut value = serde_json::to_value(group).map_err(|e| e.to_string())?;
+        value["kind"] = KIND_GROUP.into();
+        out.push_str(&value.to_string());
+        out.push('\n');
+    }
+    for record in &data.records {
+        out.push_str(&serde_json::to_string(record).map_err(|e| e.to_string())?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+pub fn parse_jsonl(text: &str) -> Result<PortableData, String> {
+    let mut data = PortableData::default();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let value: serde_json::Value =
+            serde_json::from_str(line).map_err(|e| format!("第 {} 行格式错误: {}", index + 1, e))?;
+        let parsed = if value.get("kind").and_then(|k| k.as_str()) == Some(KIND_GROUP) {
+            serde_json::from_value(value).map(|group| data.groups.push(group))
+        } else {
+            serde_json::from_value(value).map(|record| data.records.push(record))
+        };
+        parsed.map_err(|e| format!("第 {} 行格式错误: {}", index + 1, e))?;
+    }
+    Ok(data)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CsvRow {
+    kind: String,
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    group_name: String,
+    #[serde(default)]
+    content_type: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    html_content: String,
+    #[serde(default)]
+    image_id: String,
+    #[serde(default)]
+    created_at: Option<i64>,
+    #[serde(default)]
+    updated_at: Option<i64>,
+}
+
+fn non_empty(value: String) -> Option<String> {
+    Some(value).filter(|v| !v.is_empty())
+}
+
+pub fn write_csv(data: &PortableData) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for record in &data.records {
+        writer
+            .serialize(CsvRow {
+                kind: record.kind.clone(),
+                id: record.id.clone(),
+                title: record.title.clone().unwrap_or_default(),
+                group_name: record.group_name.clone().unwrap_or_default(),
+                content_type: record.content_type.clone(),
+                content: record.content.clone(),
+                html_content: record.html_content.clone().unwrap_or_default(),
+                image_id: record.image_id.clone().unwrap_or_default(),
+                created_at: Some(record.created_at),
+                updated_at: Some(record.updated_at),
+            })
+            .map_err(|e| format!("写入 CSV 失败: {}", e))?;
+    }
+    let bytes = writer.into_inner().map_err(|e| format!("写入 CSV 失败: {}", e))?;
+    String::from_utf8(bytes).map_err(|e| format!("写入 CSV 失败: {}", e))
+}
+
+pub fn parse_csv(text: &str) -> Result<PortableData, String> {
+    let mut reader = csv::Reader::from_reader(text.as_bytes());
+    let mut data = PortableData::default();
+    for row in reader.deserialize::<CsvRow>() {
+        let row = row.map_err(|e| format!("CSV 格式错误: {}", e))?;
+        data.records.push(PortableRecord {
+            kind: row.kind,
+            id: row.id,
+            title: non_empty(row.title),
+            group_name: non_empty(row.group_name),
+            content_type: non_empty(row.content_type).unwrap_or_else(default_content_type),
+            content: row.content,
+            html_content: non_empty(row.html_content),
+            image_id: non_empty(row.image_id),
+            created_at: row.created_at.unwrap_or(0),
+            updated_at: row.updated_at.unwrap_or(0),
+            ..Default::default()
+        });
+    }
+    Ok(data)
+}
+
+// 元数据放进 HTML 注释，JSON 里的 '>' 转义后注释不会被提前截断
+fn markdown_comment(prefix: &str, json: String) -> String {
+    format!("{}{}{}\n", prefix, json.replace('>', "\\u003e"), MARKDOWN_COMMENT_SUFFIX)
+}
+
+// 代码块围栏比内容里最长的连续反引号多一个
+fn markdown_fence(content: &str) -> String {
+    let mut longest = 0;
+    let mut current = 0;
+    for ch in content.chars() {
+        if ch == '`' {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    "`".repeat((longest + 1).max(3))
+}
+
+fn markdown_heading(text: &str) -> String {
+    let line = text.lines().find(|l| !l.trim().is_empty()).unwrap_or_default().trim();
+    line.chars().take(80).collect()
+}
+
+pub fn write_markdown(data: &PortableData) -> Result<String, String> {
+    let mut out = String::from("# QuickClipboard\n\n");
+    for group in &data.groups {
+        out.push_str(&markdown_comment(
+            MARKDOWN_GROUP_PREFIX,
+            serde_json::to_string(group).map_err(|e| e.to_string())?,
+        ));
+    }
+
+    let mut section: Option<String> = None;
+    for record in &data.records {
+        let heading = if record.kind == KIND_FAVORITE {
+            record.group_name.clone().unwrap_or_else(|| DEFAULT_GROUP.to_string())
+        } else {
+            "剪贴板历史".to_string()
+        };
+        if section.as_deref() != Some(heading.as_str()) {
+            out.push_str(&format!("\n## {}\n", heading));
+            section = Some(heading);
+        }
+
+        let title = record
+            .title
+            .as_deref()
+            .map(markdown_heading)
+            .filter(|t| !t.is_empty())
+            .unwrap_or_else(|| markdown_heading(&record.content));
+        out.push_str(&format!("\n### {}\n\n", title));
+
+        let meta = PortableRecord {
+            content: String::new(),
+            raw_formats: Vec::new(),
+            images: Vec::new(),
+            ..record.clone()
+        };
+        out.push_str(&markdown_comment(
+            MARKDOWN_ITEM_PREFIX,
+            serde_json::to_string(&meta).map_err(|e| e.to_string())?,
+        ));
+        let fence = markdown_fence(&record.content);
+        out.push_str(&format!("{}\n{}\n{}\n", fence, record.content, fence));
+    }
+    Ok(out)
+}
+
+// 只识别带 qc-item 注释的代码块，其余 Markdown 内容忽略。
+// 换行统一为 \n，在 Windows 上经过 git 转换的文件也能导入
+pub fn parse_markdown(text: &str) -> Result<PortableData, String> {
+    let text = text.replace("\r\n", "\n");
+    let lines: Vec<&str> = text.split('\n').collect();
+    let mut data = PortableData::default();
+    let mut index = 0;
+
+    let parse_comment = |line: &str, prefix: &str| -> Option<String> {
+        line.trim()
+            .strip_prefix(prefix)?
+            .strip_suffix(MARKDOWN_COMMENT_SUFFIX)
+            .map(|json| json.trim().to_string())
+    };
+
+    while index < lines.len() {
+        let line = lines[index];
+        index += 1;
+
+        if let Some(json) = parse_comment(line, MARKDOWN_GROUP_PREFIX) {
+            let group = serde_json::from_str(&json).map_err(|e| format!("第 {} 行分组信息错误: {}", index, e))?;
+            data.groups.push(group);
+            continue;
+        }
+        let Some(json) = parse_comment(line, MARKDOWN_ITEM_PREFIX) else { continue };
+        let mut record: PortableRecord =
+            serde_json::from_str(&json).map_err(|e| format!("第 {} 行条目信息错误: {}", index, e))?;
+
+        while index < lines.len() && lines[index].trim().is_empty() {
+            index += 1;
+        }
+        let fence = lines.get(index).map(|l| l.trim_end()).unwrap_or_default();
+        if !fence.starts_with("```") {
+            return Err(format!("第 {} 行缺少代码块", index + 1));
+        }
+        let fence_len = fence.chars().take_while(|c| *c == '`').count();
+        let closing = "`".repeat(fence_len);
+        let start = index + 1;
+        let end = (start..lines.len())
+            .find(|&i| lines[i].trim_end() == closing)
+            .ok_or_else(|| format!("第 {} 行代码块未闭合", index + 1))?;
+        record.content = lines[start..end].join("\n");
+        data.records.push(record);
+        index = end + 1;
+    }
+    Ok(data)
+}
+
+pub fn render(data: &PortableData, format: PortableFormat) -> Result<String, String> {
+    match format {
+        PortableFormat::Jsonl => write_jsonl(data),
+        PortableFormat::Csv => write_csv(data),
+        PortableFormat::Markdown => write_markdown(data),
+    }
+}
+
+pub fn parse(text: &str, format: PortableFormat) -> Result<PortableData, String> {
+    let text = text.trim_start_matches('\u{feff}');
+    match format {
+        PortableFormat::Jsonl => parse_jsonl(text),
+        PortableFormat::Csv => parse_csv(text),
+        PortableFormat::Markdown => parse_markdown(text),
+    }
+}
+
+// ==================== 导入 ====================
+
+// 写入数据库，返回需要落盘的图片
+pub fn import_portable_data(
+    conn: &Connection,
+    data: &PortableData,
+) -> Result<(PortableImportReport, Vec<PortableImage>), rusqlite::Error> {
+    let tx = conn.unchecked_transaction()?;
+    let mut report = PortableImportReport::default();
+    let mut images = Vec::new();
+    let now = chrono::Local::now().timestamp();
+
+    for group in &data.groups {
+        if ensure_group(&tx, group, now)? {
+            report.groups += 1;
+        }
+    }
+
+    for record in &data.records {
+        let imported = match record.kind.as_str() {
+            KIND_CLIPBOARD => import_history_record(&tx, record, now)?,
+            KIND_FAVORITE => {
+                let group_name = record.group_name.as_deref().unwrap_or(DEFAULT_GROUP);
+                if group_name != DEFAULT_GROUP {
+                    let group = PortableGroup {
+                        name: group_name.to_string(),
+                        icon: default_group_icon(),
+                        color: default_group_color(),
+                    };
+                    if ensure_group(&tx, &group, now)? {
+                        report.groups += 1;
+                    }
+                }
+                import_favorite_record(&tx, record, now)?
+            }
+            _ => false,
+        };
+        if !imported {
+            report.skipped += 1;
+            continue;
+        }
+        if record.kind == KIND_CLIPBOARD {
+            report.clipboard += 1;
+        } else {
+            report.favorites += 1;
+        }
+        images.extend(record.images.iter().cloned());
+    }
+
+    tx.commit()?;
+    Ok((report, images))
+}
+
+fn ensure_group(conn: &Connection, group: &PortableGroup, now: i64) -> Result<bool, rusqlite::Error> {
+    if group.name.is_empty() || group.name == DEFAULT_GROUP {
+        return Ok(false);
+    }
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO groups (name, icon, color, order_index, created_at, updated_at)
+         VALUES (?1, ?2, ?3, (SELECT COALESCE(MAX(order_index), 0) + 1 FROM groups), ?4, ?4)",
+        params![group.name, group.icon, group.color, now],
+    )?;
+    Ok(inserted > 0)
+}
+
+fn timestamps(record: &PortableRecord, now: i64) -> (i64, i64) {
+    let created_at = if record.created_at > 0 { record.created_at } else { now };
+    let updated_at = if record.updated_at > 0 { record.updated_at } else { created_at };
+    (created_at, updated_at)
+}
+
+fn import_history_record(conn: &Connection, record: &PortableRecord, now: i64) -> Result<bool, rusqlite::Error> {
+    let uuid = if record.id.trim().is_empty() {
+        uuid::Uuid::new_v4().to_string()
+    } else {
+        record.id.clone()
+    };
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM clipboard
+         WHERE uuid = ?1 OR ((uuid IS NULL OR uuid = '') AND CAST(id AS TEXT) = ?1))",
+        params![uuid],
+        |row| row.get(0),
+    )?;
+    if exists {
+        return Ok(false);
+    }
+
+    let (created_at, updated_at) = timestamps(record, now);
+    conn.execute(
+        "INSERT INTO clipboard (uuid, content, html_content, content_type, image_id, item_order,
+            char_count, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, (SELECT COALESCE(MAX(item_order), 0) + 1 FROM clipboard), ?6, ?7, ?8)",
+        params![
+            uuid,
+            record.content,
+            record.html_content,
+            record.content_type,
+            record.image_id,
+            record.content.chars().count() as i64,
+            created_at,
+            updated_at,
+        ],
+    )?;
+    let target_id = conn.last_insert_rowid().to_string();
+    insert_raw_formats(conn, KIND_CLIPBOARD, &target_id, &record.raw_formats, now)?;
+    delete_sync_tombstone_in_conn(conn, COLLECTION_HISTORY, &uuid)?;
+    Ok(true)
+}
+
+fn import_favorite_record(conn: &Connection, record: &PortableRecord, now: i64) -> Result<bool, rusqlite::Error> {
+    let id = if record.id.trim().is_empty() {
+        uuid::Uuid::new_v4().to_string()
+    } else {
+        record.id.clone()
+    };
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM favorites WHERE id = ?1)",
+        params![id],
+        |row| row.get(0),
+    )?;
+    if exists {
+        return Ok(false);
+    }
+
+    let (created_at, updated_at) = timestamps(record, now);
+    let title = record
+        .title
+        .clone()
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| markdown_heading(&record.content));
+    conn.execute(
+        "INSERT INTO favorites (id, title, content, html_content, content_type, image_id, group_name,
+            item_order, char_count, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, (SELECT COALESCE(MAX(item_order), 0) + 1 FROM favorites), ?8, ?9, ?10)",
+        params![
+            id,
+            title,
+            record.content,
+            record.html_content,
+            record.content_type,
+            record.image_id,
+            record.group_name.as_deref().unwrap_or(DEFAULT_GROUP),
+            record.content.chars().count() as i64,
+            created_at,
+            updated_at,
+        ],
+    )?;
+    insert_raw_formats(conn, KIND_FAVORITE, &id, &record.raw_formats, now)?;
+    delete_sync_tombstone_in_conn(conn, COLLECTION_FAVORITES, &id)?;
+    Ok(true)
+}
+
+// 无法解码的原始格式直接丢弃，不影响文本内容导入
+fn insert_raw_formats(
+    conn: &Connection,
+    kind: &str,
+    target_id: &str,
+    formats: &[PortableRawFormat],
+    now: i64,
+) -> Result<(), rusqlite::Error> {
+    for format in formats {
+        let Ok(raw) = general_purpose::STANDARD.decode(&format.data) else { continue };
+        conn.execute(
+            "INSERT OR IGNORE INTO clipboard_data
+                (target_kind, target_id, format_name, raw_data, is_primary, format_order, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+            params![kind, target_id, format.format_name, raw, format.is_primary, format.format_order, now],
+        )?;
+    }
+    Ok(())
+}
+
+// 图片按 ID 命名，已存在的文件不覆盖
+fn write_images(data_dir: &Path, images: &[PortableImage]) -> Result<(), String> {
+    if images.is_empty() {
+        return Ok(());
+    }
+    let images_dir = data_dir.join("clipboard_images");
+    fs::create_dir_all(&images_dir).map_err(|e| format!("创建图片目录失败: {}", e))?;
+    for image in images {
+        if image.id.contains(['/', '\\']) || image.id.contains("..") {
+            continue;
+        }
+        let path = images_dir.join(format!("{}.png", image.id));
+        if path.exists() {
+            continue;
+        }
+        let Ok(bytes) = general_purpose::STANDARD.decode(&image.data) else { continue };
+        crate::services::local_encryption::write_file(&path, &bytes).map_err(|e| format!("写入图片失败: {}", e))?;
+    }
+    Ok(())
+}
+
+// ==================== 文件入口 ====================
+
+pub fn export_portable(target_path: &Path, format: PortableFormat, filter: &PortableFilter) -> Result<PortableExportReport, String> {
+    let data_dir = crate::services::get_data_directory()?;
+    let embed = (format == PortableFormat::Jsonl).then_some(data_dir.as_path());
+    let data = with_connection(|conn| collect_portable_data(conn, filter, embed))?;
+    let text = render(&data, format)?;
+    if let Some(parent) = target_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建导出目录失败: {}", e))?;
+    }
+    fs::write(target_path, text).map_err(|e| format!("写入导出文件失败: {}", e))?;
+
+    let favorites = data.records.iter().filter(|r| r.kind == KIND_FAVORITE).count() as u32;
+    Ok(PortableExportReport {
+        clipboard: data.records.len() as u32 - favorites,
+        favorites,
+    })
+}
+
+pub fn import_portable(source_path: &Path) -> Result<PortableImportReport, String> {
+    let format = PortableFormat::from_path(source_path).ok_or("不支持的文件格式，请选择 .jsonl、.csv 或 .md 文件")?;
+    let text = fs::read_to_string(source_path).map_err(|e| format!("读取导入文件失败: {}", e))?;
+    import_data(&parse(&text, format)?)
+}
+
+pub fn import_data(data: &PortableData) -> Result<PortableImportReport, String> {
+    let (report, images) = with_connection(|conn| import_portable_data(conn, data))?;
+    write_images(&crate::services::get_data_directory()?, &images)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::services::database::migrations::run_migrations(&conn, None).unwrap();
+        conn.execute_batch(
+            "INSERT INTO groups (name, icon, color, order_index, created_at, updated_at) VALUES
+                ('snippets', 'ti ti-code', '#2563eb', 1, 1, 1), ('other', 'ti ti-folder', '#dc2626', 2, 1, 1);
+             INSERT INTO clipboard (uuid, content, content_type, item_order, created_at, updated_at) VALUES
+                ('u1', 'hello', 'text', 1, 100, 100), (NULL, 'https://a.b', 'link', 2, 200, 200);
+             INSERT INTO favorites (id, title, content, content_type, group_name, item_order, created_at, updated_at) VALUES
+                ('f1', 'fence', 'use ``` and > -->', 'text', 'snippets', 1, 150, 150),
+                ('f2', 'skip', 'x', 'text', 'other', 2, 150, 150);
+             INSERT INTO clipboard_data (target_kind, target_id, format_name, raw_data, is_primary, format_order, created_at, updated_at)
+                VALUES ('favorite', 'f1', 'text/plain', x'616263', 1, 0, 1, 1);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn all_filter() -> PortableFilter {
+        PortableFilter {
+            include_history: true,
+            include_favorites: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn filter_by_group_type_and_date() {
+        let conn = setup();
+        let filter = PortableFilter {
+            groups: vec!["snippets".to_string()],
+            ..all_filter()
+        };
+        let data = collect_portable_data(&conn, &filter, None).unwrap();
+        let ids: Vec<&str> = data.records.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["u1", "2", "f1"]);
+        assert_eq!(data.groups.len(), 1);
+
+        let filter = PortableFilter {
+            content_types: vec!["link".to_string()],
+            ..all_filter()
+        };
+        let data = collect_portable_data(&conn, &filter, None).unwrap();
+        assert_eq!(data.records.len(), 1);
+
+        let filter = PortableFilter { from: Some(120), to: Some(160), ..all_filter() };
+        let data = collect_portable_data(&conn, &filter, None).unwrap();
+        assert!(data.records.iter().all(|r| r.kind == KIND_FAVORITE));
+    }
+
+    #[test]
+    fn every_format_round_trips() {
+        let conn = setup();
+        let dir = std::env::temp_dir();
+        let data = collect_portable_data(&conn, &all_filter(), Some(&dir)).unwrap();
+        let f1 = data.records.iter().find(|r| r.id == "f1").unwrap();
+        assert_eq!(f1.raw_formats.len(), 1);
+
+        for format in [PortableFormat::Jsonl, PortableFormat::Csv, PortableFormat::Markdown] {
+            let parsed = parse(&render(&data, format).unwrap(), format).unwrap();
+            assert_eq!(parsed.records.len(), data.records.len(), "{:?}", format);
+            for (a, b) in parsed.records.iter().zip(&data.records) {
+                assert_eq!((&a.id, &a.content, &a.group_name, a.created_at), (&b.id, &b.content, &b.group_name, b.created_at));
+            }
+            if format != PortableFormat::Csv {
+                assert_eq!(parsed.groups, data.groups);
+            }
+        }
+    }
+
+    #[test]
+    fn import_skips_existing_items() {
+        let conn = setup();
+        let mut data = collect_portable_data(&conn, &all_filter(), None).unwrap();
+        data.records.push(PortableRecord {
+            kind: KIND_FAVORITE.to_string(),
+            group_name: Some("shared".to_string()),
+            content: "new snippet\nsecond line".to_string(),
+            ..Default::default()
+        });
+
+        let (report, _) = import_portable_data(&conn, &data).unwrap();
+        assert_eq!(report, PortableImportReport { clipboard: 0, favorites: 1, groups: 1, skipped: 4 });
+        let title: String = conn
+            .query_row("SELECT title FROM favorites WHERE group_name = 'shared'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(title, "new snippet");
+    }
+}
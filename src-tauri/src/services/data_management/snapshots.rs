@@ -0,0 +1,788 @@
+// 本地快照备份
+//
+// 与导入导出用的完整 zip 不同，快照用于定期自动备份和按时间点恢复：
+// - 数据库通过 SQLite 在线备份接口复制，不需要暂停写入
+// - 图片按内容哈希存入 objects 目录，未变化的图片在多个快照间共用，只保存一份
+// - 每个快照目录包含 manifest.json，记录数据库哈希、条目数量和图片清单
+// 可以整体恢复到任意快照，也可以只从快照中取回选中的历史或收藏条目。
+//
+// 目录结构：
+//   backups/snapshots/objects/<哈希前两位>/<哈希>
+//   backups/snapshots/<快照ID>/quickclipboard.db
+//   backups/snapshots/<快照ID>/manifest.json
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use rusqlite::{params, Connection, DatabaseName, OpenFlags};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::services::database::connection::{close_database, with_connection, with_database_file};
+use crate::services::database::{init_database, migrations};
+
+const SNAPSHOT_DB_FILE: &str = "quickclipboard.db";
+const MANIFEST_FILE: &str = "manifest.json";
+const OBJECTS_DIR: &str = "objects";
+const SCHEDULER_CHECK_SECS: u64 = 10 * 60;
+
+pub const TRIGGER_SCHEDULED: &str = "scheduled";
+pub const TRIGGER_MANUAL: &str = "manual";
+pub const TRIGGER_PRE_RESTORE: &str = "pre_restore";
+
+static SCHEDULER_STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SnapshotImage {
+    pub name: String,
+    pub hash: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub id: String,
+    pub created_at: i64,
+    pub trigger: String,
+    pub schema_version: i32,
+    pub db_sha256: String,
+    pub db_size: u64,
+    pub clipboard_count: i64,
+    pub favorites_count: i64,
+    pub images: Vec<SnapshotImage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotSummary {
+    pub id: String,
+    pub created_at: i64,
+    pub trigger: String,
+    pub clipboard_count: i64,
+    pub favorites_count: i64,
+    pub image_count: usize,
+    pub db_size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotVerifyResult {
+    pub id: String,
+    pub ok: bool,
+    pub problems: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotItem {
+    pub kind: String,
+    pub id: String,
+    pub title: String,
+    pub preview: String,
+    pub content_type: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotRestoreReport {
+    pub clipboard: u32,
+    pub favorites: u32,
+    pub images: u32,
+}
+
+pub fn snapshots_root(data_dir: &Path) -> PathBuf {
+    data_dir.join("backups").join("snapshots")
+}
+
+fn object_path(root: &Path, hash: &str) -> PathBuf {
+    root.join(OBJECTS_DIR).join(&hash[..2]).join(hash)
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = fs::File::open(path).map_err(|e| format!("读取文件失败 {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| format!("读取文件失败 {}: {}", path.display(), e))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// ==================== 创建 ====================
+
+pub fn create_snapshot(trigger: &str) -> Result<SnapshotManifest, String> {
+    let data_dir = crate::services::get_data_directory()?;
+    let root = snapshots_root(&data_dir);
+    let staging = stage_dir(&root)?;
+    let staged_db = staging.join(SNAPSHOT_DB_FILE);
+
+    // 启用本地加密时备份磁盘上的密文数据库，不备份内存中的明文副本
+    let backup = with_database_file(|conn| conn.backup(DatabaseName::Main, &staged_db, None));
+    if let Err(e) = backup {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(format!("备份数据库失败: {}", e));
+    }
+
+    let manifest = finalize_snapshot(&data_dir, &staging, trigger);
+    if manifest.is_err() {
+        let _ = fs::remove_dir_all(&staging);
+    }
+    let manifest = manifest?;
+
+    // 恢复前的快照不触发清理，避免把要恢复的快照删掉
+    if trigger != TRIGGER_PRE_RESTORE {
+        let keep = crate::services::get_settings().snapshot_keep.max(1) as usize;
+        prune_snapshots(&root, keep)?;
+    }
+    Ok(manifest)
+}
+
+fn stage_dir(root: &Path) -> Result<PathBuf, String> {
+    fs::create_dir_all(root).map_err(|e| format!("创建快照目录失败: {}", e))?;
+    let staging = root.join(format!(".staging-{}", fastrand::u32(..)));
+    fs::create_dir_all(&staging).map_err(|e| format!("创建快照目录失败: {}", e))?;
+    Ok(staging)
+}
+
+// 校验暂存的数据库、写入图片对象和清单，再移动到正式目录
+fn finalize_snapshot(data_dir: &Path, staging: &Path, trigger: &str) -> Result<SnapshotManifest, String> {
+    let root = snapshots_root(data_dir);
+    let staged_db = staging.join(SNAPSHOT_DB_FILE);
+
+    let (schema_version, clipboard_count, favorites_count) = {
+        let conn = open_snapshot_db(&staged_db)?;
+        check_integrity(&conn)?;
+        let count = |table: &str| -> i64 {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+                .unwrap_or(0)
+        };
+        (
+            migrations::schema_version(&conn).map_err(|e| e.to_string())?,
+            count("clipboard"),
+            count("favorites"),
+        )
+    };
+
+    let images = store_image_objects(&root, &data_dir.join("clipboard_images"))?;
+
+    let now = chrono::Local::now();
+    let mut id = now.format("%Y%m%d-%H%M%S").to_string();
+    let mut suffix = 1;
+    while root.join(&id).exists() {
+        suffix += 1;
+        id = format!("{}-{}", now.format("%Y%m%d-%H%M%S"), suffix);
+    }
+
+    let manifest = SnapshotManifest {
+        id: id.clone(),
+        created_at: now.timestamp(),
+        trigger: trigger.to_string(),
+        schema_version,
+        db_sha256: sha256_file(&staged_db)?,
+        db_size: fs::metadata(&staged_db).map(|m| m.len()).unwrap_or(0),
+        clipboard_count,
+        favorites_count,
+        images,
+    };
+    let json = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+    fs::write(staging.join(MANIFEST_FILE), json).map_err(|e| format!("写入快照清单失败: {}", e))?;
+
+    let target = root.join(&id);
+    fs::rename(staging, &target).map_err(|e| format!("保存快照失败: {}", e))?;
+
+    let result = verify_snapshot_dir(&root, &target);
+    if !result.ok {
+        let _ = fs::remove_dir_all(&target);
+        return Err(format!("快照校验失败: {}", result.problems.join("; ")));
+    }
+    Ok(manifest)
+}
+
+// 已存在相同哈希的对象时不再复制
+fn store_image_objects(root: &Path, images_dir: &Path) -> Result<Vec<SnapshotImage>, String> {
+    let mut images = Vec::new();
+    let Ok(entries) = fs::read_dir(images_dir) else {
+        return Ok(images);
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let Some(name) = path.file_name().and_then(|s| s.to_str()).map(|s| s.to_string()) else {
+            continue;
+        };
+        let hash = sha256_file(&path)?;
+        let object = object_path(root, &hash);
+        if !object.exists() {
+            if let Some(parent) = object.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("创建图片对象目录失败: {}", e))?;
+            }
+            let temp = object.with_extension("tmp");
+            fs::copy(&path, &temp).map_err(|e| format!("复制图片失败: {}", e))?;
+            fs::rename(&temp, &object).map_err(|e| format!("保存图片对象失败: {}", e))?;
+        }
+        let size = fs::metadata(&object).map(|m| m.len()).unwrap_or(0);
+        images.push(SnapshotImage { name, hash, size });
+    }
+    images.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(images)
+}
+
+fn open_snapshot_db(path: &Path) -> Result<Connection, String> {
+    Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("打开快照数据库失败: {}", e))
+}
+
+fn check_integrity(conn: &Connection) -> Result<(), String> {
+    let result: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(|e| format!("数据库完整性检查失败: {}", e))?;
+    if result != "ok" {
+        return Err(format!("数据库完整性检查失败: {}", result));
+    }
+    Ok(())
+}
+
+// ==================== 列表与清理 ====================
+
+fn read_manifest(dir: &Path) -> Result<SnapshotManifest, String> {
+    let bytes = fs::read(dir.join(MANIFEST_FILE)).map_err(|e| format!("读取快照清单失败: {}", e))?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("解析快照清单失败: {}", e))
+}
+
+fn read_manifests(root: &Path) -> Vec<SnapshotManifest> {
+    let mut manifests: Vec<SnapshotManifest> = fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| read_manifest(&entry.path()).ok())
+        .collect();
+    manifests.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.id.cmp(&a.id)));
+    manifests
+}
+
+pub fn list_snapshots() -> Result<Vec<SnapshotSummary>, String> {
+    let root = snapshots_root(&crate::services::get_data_directory()?);
+    Ok(read_manifests(&root)
+        .into_iter()
+        .map(|m| SnapshotSummary {
+            id: m.id,
+            created_at: m.created_at,
+            trigger: m.trigger,
+            clipboard_count: m.clipboard_count,
+            favorites_count: m.favorites_count,
+            image_count: m.images.len(),
+            db_size: m.db_size,
+        })
+        .collect())
+}
+
+// 只保留最近 keep 个快照，并删除不再被任何快照引用的图片对象
+fn prune_snapshots(root: &Path, keep: usize) -> Result<(), String> {
+    let manifests = read_manifests(root);
+    for manifest in manifests.iter().skip(keep) {
+        let _ = fs::remove_dir_all(root.join(&manifest.id));
+    }
+
+    let referenced: HashSet<&str> = manifests
+        .iter()
+        .take(keep)
+        .flat_map(|m| m.images.iter().map(|image| image.hash.as_str()))
+        .collect();
+    for bucket in fs::read_dir(root.join(OBJECTS_DIR)).into_iter().flatten().flatten() {
+        for object in fs::read_dir(bucket.path()).into_iter().flatten().flatten() {
+            let name = object.file_name().to_string_lossy().to_string();
+            if !referenced.contains(name.as_str()) {
+                let _ = fs::remove_file(object.path());
+            }
+        }
+    }
+
+    // 清理异常退出留下的暂存目录
+    for entry in fs::read_dir(root).into_iter().flatten().flatten() {
+        if entry.file_name().to_string_lossy().starts_with(".staging-") {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+    Ok(())
+}
+
+// ==================== 校验 ====================
+
+fn verify_snapshot_dir(root: &Path, dir: &Path) -> SnapshotVerifyResult {
+    let id = dir.file_name().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let mut problems = Vec::new();
+
+    match read_manifest(dir) {
+        Ok(manifest) => {
+            let db_path = dir.join(SNAPSHOT_DB_FILE);
+            match sha256_file(&db_path) {
+                Ok(hash) if hash == manifest.db_sha256 => {}
+                Ok(_) => problems.push("数据库文件哈希不匹配".to_string()),
+                Err(e) => problems.push(e),
+            }
+            if let Err(e) = open_snapshot_db(&db_path).and_then(|conn| check_integrity(&conn)) {
+                problems.push(e);
+            }
+            for image in &manifest.images {
+                let object = object_path(root, &image.hash);
+                match sha256_file(&object) {
+                    Ok(hash) if hash == image.hash => {}
+                    Ok(_) => problems.push(format!("图片 {} 内容已损坏", image.name)),
+                    Err(_) => problems.push(format!("图片 {} 缺失", image.name)),
+                }
+            }
+        }
+        Err(e) => problems.push(e),
+    }
+
+    SnapshotVerifyResult { id, ok: problems.is_empty(), problems }
+}
+
+pub fn verify_snapshots() -> Result<Vec<SnapshotVerifyResult>, String> {
+    let root = snapshots_root(&crate::services::get_data_directory()?);
+    Ok(read_manifests(&root)
+        .iter()
+        .map(|m| verify_snapshot_dir(&root, &root.join(&m.id)))
+        .collect())
+}
+
+fn verified_snapshot_dir(root: &Path, id: &str) -> Result<PathBuf, String> {
+    if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+        return Err("快照 ID 无效".into());
+    }
+    let dir = root.join(id);
+    if !dir.join(MANIFEST_FILE).exists() {
+        return Err("快照不存在".into());
+    }
+    let result = verify_snapshot_dir(root, &dir);
+    if !result.ok {
+        return Err(format!("快照已损坏，无法恢复: {}", result.problems.join("; ")));
+    }
+    Ok(dir)
+}
+
+// ==================== 整体恢复 ====================
+
+// 恢复前先为当前数据创建一个快照，恢复后可以撤回
+pub fn restore_snapshot(id: &str) -> Result<SnapshotManifest, String> {
+    let data_dir = crate::services::get_data_directory()?;
+    let root = snapshots_root(&data_dir);
+    let dir = verified_snapshot_dir(&root, id)?;
+    let manifest = read_manifest(&dir)?;
+
+    create_snapshot(TRIGGER_PRE_RESTORE)?;
+
+    let _ = with_database_file(|conn| conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);"));
+    close_database();
+
+    let db_path = data_dir.join(SNAPSHOT_DB_FILE);
+    let copy_result = fs::copy(dir.join(SNAPSHOT_DB_FILE), &db_path)
+        .map_err(|e| format!("恢复数据库失败: {}", e))
+        .and_then(|_| {
+            for name in ["quickclipboard.db-wal", "quickclipboard.db-shm"] {
+                let _ = fs::remove_file(data_dir.join(name));
+            }
+            restore_all_images(&root, &manifest, &data_dir.join("clipboard_images"))
+        });
+
+    // 无论恢复是否成功都要重新打开数据库
+    init_database(db_path.to_str().ok_or("数据库路径无效")?)?;
+    copy_result?;
+    Ok(manifest)
+}
+
+fn restore_all_images(root: &Path, manifest: &SnapshotManifest, images_dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(images_dir).map_err(|e| format!("创建图片目录失败: {}", e))?;
+    let wanted: HashMap<&str, &SnapshotImage> = manifest
+        .images
+        .iter()
+        .map(|image| (image.name.as_str(), image))
+        .collect();
+
+    for entry in fs::read_dir(images_dir).into_iter().flatten().flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !wanted.contains_key(name.as_str()) {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+    for image in &manifest.images {
+        restore_image(root, image, images_dir)?;
+    }
+    Ok(())
+}
+
+// 返回是否写入了文件
+fn restore_image(root: &Path, image: &SnapshotImage, images_dir: &Path) -> Result<bool, String> {
+    let target = images_dir.join(&image.name);
+    if target.exists() && sha256_file(&target).map(|hash| hash == image.hash).unwrap_or(false) {
+        return Ok(false);
+    }
+    fs::copy(object_path(root, &image.hash), &target)
+        .map_err(|e| format!("恢复图片 {} 失败: {}", image.name, e))?;
+    Ok(true)
+}
+
+// ==================== 按条目恢复 ====================
+
+pub fn list_snapshot_items(id: &str, kind: &str, search: Option<&str>, limit: u32) -> Result<Vec<SnapshotItem>, String> {
+    let root = snapshots_root(&crate::services::get_data_directory()?);
+    let dir = verified_snapshot_dir(&root, id)?;
+    let conn = open_snapshot_db(&dir.join(SNAPSHOT_DB_FILE))?;
+    query_snapshot_items(&conn, kind, search, limit).map_err(|e| format!("读取快照条目失败: {}", e))
+}
+
+fn query_snapshot_items(conn: &Connection, kind: &str, search: Option<&str>, limit: u32) -> Result<Vec<SnapshotItem>, rusqlite::Error> {
+    let pattern = format!("%{}%", search.unwrap_or("").trim());
+    let sql = match kind {
+        "favorite" => {
+            "SELECT CAST(id AS TEXT), title, SUBSTR(content, 1, 200), content_type, created_at FROM favorites
+             WHERE title LIKE ?1 OR content LIKE ?1 ORDER BY item_order DESC LIMIT ?2"
+        }
+        _ => {
+            "SELECT CAST(id AS TEXT), '', SUBSTR(content, 1, 200), content_type, created_at FROM clipboard
+             WHERE content LIKE ?1 ORDER BY item_order DESC LIMIT ?2"
+        }
+    };
+    let kind = if kind == "favorite" { "favorite" } else { "clipboard" };
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map(params![pattern, limit], |row| {
+        Ok(SnapshotItem {
+            kind: kind.to_string(),
+            id: row.get(0)?,
+            title: row.get(1)?,
+            preview: row.get(2)?,
+            content_type: row.get(3)?,
+            created_at: row.get(4)?,
+        })
+    })?;
+    rows.collect()
+}
+
+// 从快照中取回选中的条目，已存在的条目（相同 UUID 或收藏 ID）跳过
+pub fn restore_snapshot_items(id: &str, clipboard_ids: &[i64], favorite_ids: &[String]) -> Result<SnapshotRestoreReport, String> {
+    let data_dir = crate::services::get_data_directory()?;
+    let root = snapshots_root(&data_dir);
+    let dir = verified_snapshot_dir(&root, id)?;
+    let manifest = read_manifest(&dir)?;
+
+    // 快照可能来自旧版本，先在临时副本上迁移到当前结构
+    let temp_db = std::env::temp_dir().join(format!("quickclipboard_snapshot_{}.db", fastrand::u32(..)));
+    fs::copy(dir.join(SNAPSHOT_DB_FILE), &temp_db).map_err(|e| format!("读取快照失败: {}", e))?;
+    let result = Connection::open(&temp_db)
+        .map_err(|e| format!("打开快照数据库失败: {}", e))
+        .and_then(|conn| {
+            migrations::run_migrations(&conn, None)?;
+            // 启用本地加密时快照中是密文，临时副本用完即删除
+            crate::services::local_encryption::open_database_copy(&conn)
+        })
+        .and_then(|_| {
+            let temp_path = temp_db.to_str().ok_or("快照路径无效")?.to_string();
+            with_connection(|conn| copy_items_from_snapshot(conn, &temp_path, clipboard_ids, favorite_ids))
+        });
+    let _ = fs::remove_file(&temp_db);
+    let (mut report, image_ids) = result?;
+
+    let images_dir = data_dir.join("clipboard_images");
+    fs::create_dir_all(&images_dir).map_err(|e| format!("创建图片目录失败: {}", e))?;
+    for image in manifest.images.iter().filter(|image| {
+        Path::new(&image.name)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .map(|stem| image_ids.contains(stem))
+            .unwrap_or(false)
+    }) {
+        if restore_image(&root, image, &images_dir)? {
+            report.images += 1;
+        }
+    }
+    Ok(report)
+}
+
+fn copy_items_from_snapshot(
+    conn: &Connection,
+    snapshot_path: &str,
+    clipboard_ids: &[i64],
+    favorite_ids: &[String],
+) -> Result<(SnapshotRestoreReport, HashSet<String>), rusqlite::Error> {
+    conn.execute("ATTACH DATABASE ?1 AS snap", params![snapshot_path])?;
+    let result = (|| {
+        let tx = conn.unchecked_transaction()?;
+        let result = copy_items_in_tx(&tx, clipboard_ids, favorite_ids)?;
+        tx.commit()?;
+        Ok(result)
+    })();
+    let _ = conn.execute("DETACH DATABASE snap", []);
+    result
+}
+
+fn copy_items_in_tx(
+    conn: &Connection,
+    clipboard_ids: &[i64],
+    favorite_ids: &[String],
+) -> Result<(SnapshotRestoreReport, HashSet<String>), rusqlite::Error> {
+    use crate::services::database::tombstones::{delete_sync_tombstone_in_conn, COLLECTION_FAVORITES, COLLECTION_HISTORY};
+
+    let mut report = SnapshotRestoreReport::default();
+    let mut image_ids = HashSet::new();
+    // 更新时间设为当前时间，避免同步时被旧的删除记录再次删除
+    let now = chrono::Local::now().timestamp();
+
+    for old_id in clipboard_ids {
+        let row = conn.query_row(
+            "SELECT uuid, image_id FROM snap.clipboard WHERE id = ?1",
+            params![old_id],
+            |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, Option<String>>(1)?)),
+        );
+        let (uuid, image_id) = match row {
+            Ok(row) => row,
+            Err(rusqlite::Error::QueryReturnedNoRows) => continue,
+            Err(e) => return Err(e),
+        };
+        if let Some(uuid) = uuid.as_deref().filter(|u| !u.is_empty()) {
+            let exists: bool = conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM main.clipboard WHERE uuid = ?1)",
+                params![uuid],
+                |row| row.get(0),
+            )?;
+            if exists {
+                continue;
+            }
+            delete_sync_tombstone_in_conn(conn, COLLECTION_HISTORY, uuid)?;
+        }
+
+        conn.execute(
+            "INSERT INTO main.clipboard (content, html_content, content_type, image_id, item_order, is_pinned,
+                paste_count, source_app, source_icon_hash, char_count, uuid, source_device_id, is_remote,
+                created_at, updated_at, expires_at)
+             SELECT content, html_content, content_type, image_id,
+                (SELECT COALESCE(MAX(item_order), 0) + 1 FROM main.clipboard), is_pinned,
+                paste_count, source_app, source_icon_hash, char_count, uuid, source_device_id, is_remote,
+                created_at, ?2, expires_at
+             FROM snap.clipboard WHERE id = ?1",
+            params![old_id, now],
+        )?;
+        let new_id = conn.last_insert_rowid();
+        conn.execute(
+            "INSERT OR IGNORE INTO main.clipboard_data
+                (target_kind, target_id, format_name, raw_data, is_primary, format_order, created_at, updated_at)
+             SELECT 'clipboard', ?2, format_name, raw_data, is_primary, format_order, created_at, updated_at
+             FROM snap.clipboard_data WHERE target_kind = 'clipboard' AND target_id = ?1",
+            params![old_id.to_string(), new_id.to_string()],
+        )?;
+        extend_image_ids(&mut image_ids, image_id);
+        report.clipboard += 1;
+    }
+
+    for fav_id in favorite_ids {
+        let row = conn.query_row(
+            "SELECT group_name, image_id FROM snap.favorites WHERE id = ?1",
+            params![fav_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)),
+        );
+        let (group_name, image_id) = match row {
+            Ok(row) => row,
+            Err(rusqlite::Error::QueryReturnedNoRows) => continue,
+            Err(e) => return Err(e),
+        };
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM main.favorites WHERE id = ?1)",
+            params![fav_id],
+            |row| row.get(0),
+        )?;
+        if exists {
+            continue;
+        }
+
+        conn.execute(
+            "INSERT OR IGNORE INTO main.groups (name, icon, color, order_index, source_device_id, created_at, updated_at)
+             SELECT name, icon, color, order_index, source_device_id, created_at, ?2 FROM snap.groups WHERE name = ?1",
+            params![group_name, now],
+        )?;
+        conn.execute(
+            "INSERT INTO main.favorites (id, title, content, html_content, content_type, image_id, group_name,
+                item_order, paste_count, char_count, source_device_id, created_at, updated_at)
+             SELECT id, title, content, html_content, content_type, image_id, group_name,
+                (SELECT COALESCE(MAX(item_order), 0) + 1 FROM main.favorites), paste_count, char_count,
+                source_device_id, created_at, ?2
+             FROM snap.favorites WHERE id = ?1",
+            params![fav_id, now],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO main.clipboard_data
+                (target_kind, target_id, format_name, raw_data, is_primary, format_order, created_at, updated_at)
+             SELECT target_kind, target_id, format_name, raw_data, is_primary, format_order, created_at, updated_at
+             FROM snap.clipboard_data WHERE target_kind = 'favorite' AND target_id = ?1",
+            params![fav_id],
+        )?;
+        delete_sync_tombstone_in_conn(conn, COLLECTION_FAVORITES, fav_id)?;
+        extend_image_ids(&mut image_ids, image_id);
+        report.favorites += 1;
+    }
+
+    Ok((report, image_ids))
+}
+
+fn extend_image_ids(output: &mut HashSet<String>, image_id: Option<String>) {
+    if let Some(image_id) = image_id {
+        output.extend(
+            image_id
+                .split(',')
+                .map(|id| id.trim())
+                .filter(|id| !id.is_empty())
+                .map(|id| id.to_string()),
+        );
+    }
+}
+
+// ==================== 定时快照 ====================
+
+pub fn init_snapshot_scheduler() {
+    if SCHEDULER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(SCHEDULER_CHECK_SECS));
+        loop {
+            interval.tick().await;
+            match tauri::async_runtime::spawn_blocking(run_scheduled_snapshot_if_due).await {
+                Ok(Err(e)) => eprintln!("定时快照失败: {}", e),
+                Err(e) => eprintln!("定时快照任务失败: {}", e),
+                _ => {}
+            }
+        }
+    });
+}
+
+fn run_scheduled_snapshot_if_due() -> Result<(), String> {
+    let interval_hours = crate::services::get_settings().snapshot_interval_hours;
+    if interval_hours == 0 {
+        return Ok(());
+    }
+    let root = snapshots_root(&crate::services::get_data_directory()?);
+    let last = read_manifests(&root)
+        .iter()
+        .filter(|m| m.trigger != TRIGGER_PRE_RESTORE)
+        .map(|m| m.created_at)
+        .max();
+    if !snapshot_due(last, chrono::Local::now().timestamp(), interval_hours) {
+        return Ok(());
+    }
+    create_snapshot(TRIGGER_SCHEDULED).map(|_| ())
+}
+
+fn snapshot_due(last_created_at: Option<i64>, now: i64, interval_hours: u32) -> bool {
+    match last_created_at {
+        Some(last) => now - last >= i64::from(interval_hours) * 60 * 60,
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_data_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("qc-snapshots-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("clipboard_images")).unwrap();
+        let conn = Connection::open(dir.join(SNAPSHOT_DB_FILE)).unwrap();
+        migrations::run_migrations(&conn, None).unwrap();
+        conn.execute(
+            "INSERT INTO clipboard (content, content_type, image_id, uuid, created_at, updated_at)
+             VALUES ('hello', 'text', 'img1', 'u1', 1, 1)",
+            [],
+        )
+        .unwrap();
+        fs::write(dir.join("clipboard_images").join("img1.png"), b"image-one").unwrap();
+        dir
+    }
+
+    fn snapshot(data_dir: &Path) -> SnapshotManifest {
+        let root = snapshots_root(data_dir);
+        let staging = stage_dir(&root).unwrap();
+        let source = Connection::open(data_dir.join(SNAPSHOT_DB_FILE)).unwrap();
+        source.backup(DatabaseName::Main, staging.join(SNAPSHOT_DB_FILE), None).unwrap();
+        finalize_snapshot(data_dir, &staging, TRIGGER_MANUAL).unwrap()
+    }
+
+    fn object_count(root: &Path) -> usize {
+        fs::read_dir(root.join(OBJECTS_DIR))
+            .unwrap()
+            .flatten()
+            .map(|bucket| fs::read_dir(bucket.path()).unwrap().count())
+            .sum()
+    }
+
+    #[test]
+    fn unchanged_images_are_stored_once_and_snapshots_verify() {
+        let data_dir = setup_data_dir("dedupe");
+        let root = snapshots_root(&data_dir);
+
+        let first = snapshot(&data_dir);
+        assert_eq!(first.clipboard_count, 1);
+        assert_eq!(first.images.len(), 1);
+        let second = snapshot(&data_dir);
+        assert_ne!(first.id, second.id);
+        assert_eq!(object_count(&root), 1);
+
+        fs::write(data_dir.join("clipboard_images").join("img2.png"), b"image-two").unwrap();
+        snapshot(&data_dir);
+        assert_eq!(object_count(&root), 2);
+        assert!(read_manifests(&root).iter().all(|m| verify_snapshot_dir(&root, &root.join(&m.id)).ok));
+
+        fs::write(object_path(&root, &first.images[0].hash), b"corrupted").unwrap();
+        assert!(!verify_snapshot_dir(&root, &root.join(&first.id)).ok);
+
+        let _ = fs::remove_dir_all(&data_dir);
+    }
+
+    #[test]
+    fn pruning_keeps_objects_of_remaining_snapshots() {
+        let data_dir = setup_data_dir("prune");
+        let root = snapshots_root(&data_dir);
+
+        snapshot(&data_dir);
+        fs::remove_file(data_dir.join("clipboard_images").join("img1.png")).unwrap();
+        fs::write(data_dir.join("clipboard_images").join("img2.png"), b"image-two").unwrap();
+        snapshot(&data_dir);
+        assert_eq!(object_count(&root), 2);
+
+        prune_snapshots(&root, 1).unwrap();
+        assert_eq!(read_manifests(&root).len(), 1);
+        assert_eq!(object_count(&root), 1);
+
+        let _ = fs::remove_dir_all(&data_dir);
+    }
+
+    #[test]
+    fn restores_selected_items_as_new_rows() {
+        let data_dir = setup_data_dir("items");
+        let manifest = snapshot(&data_dir);
+        let root = snapshots_root(&data_dir);
+
+        let snap = open_snapshot_db(&root.join(&manifest.id).join(SNAPSHOT_DB_FILE)).unwrap();
+        let items = query_snapshot_items(&snap, "clipboard", Some("hel"), 10).unwrap();
+        assert_eq!(items.len(), 1);
+
+        let conn = Connection::open_in_memory().unwrap();
+        migrations::run_migrations(&conn, None).unwrap();
+        let snapshot_path = root.join(&manifest.id).join(SNAPSHOT_DB_FILE);
+        let path = snapshot_path.to_str().unwrap();
+        let (report, images) = copy_items_from_snapshot(&conn, path, &[items[0].id.parse().unwrap()], &[]).unwrap();
+        assert_eq!(report.clipboard, 1);
+        assert!(images.contains("img1"));
+
+        // 相同 UUID 已存在时跳过
+        let (report, _) = copy_items_from_snapshot(&conn, path, &[items[0].id.parse().unwrap()], &[]).unwrap();
+        assert_eq!(report.clipboard, 0);
+
+        let _ = fs::remove_dir_all(&data_dir);
+    }
+
+    #[test]
+    fn schedule_respects_interval() {
+        assert!(snapshot_due(None, 100, 24));
+        assert!(!snapshot_due(Some(0), 23 * 3600, 24));
+        assert!(snapshot_due(Some(0), 24 * 3600, 24));
+    }
+}
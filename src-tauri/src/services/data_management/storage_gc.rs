@@ -0,0 +1,289 @@
+// 存储清理
+//
+// 图片文件、clipboard_data 原始格式和同步删除记录不会随条目删除自动回收，
+// 这里集中查找并清理：
+// - clipboard_images 下不再被 clipboard / favorites 的 image_id 引用的图片
+// - 目标条目已不存在的 clipboard_data 行
+// - 超过保留期且所有已配对设备都已同步过的删除记录
+// 清理完成后执行 VACUUM / ANALYZE。dry_run 只统计可回收的空间，不做修改。
+//
+// 核心逻辑只依赖数据库连接和数据目录，维护模式下也可直接调用。
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::services::database::connection::with_connection;
+
+// 新写入的图片可能尚未入库，跳过最近修改的文件
+const IMAGE_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StorageGcOptions {
+    pub dry_run: bool,
+    // 早于该时间（秒）的删除记录可以清理；None 表示不清理删除记录
+    pub tombstone_cutoff: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StorageGcReport {
+    pub dry_run: bool,
+    pub orphan_image_count: u64,
+    pub orphan_image_bytes: u64,
+    pub orphan_raw_format_count: u64,
+    pub orphan_raw_format_bytes: u64,
+    pub expired_tombstone_count: u64,
+    pub tombstones_skipped: bool,
+    pub database_bytes_before: u64,
+    pub database_bytes_after: u64,
+    pub database_free_bytes: u64,
+}
+
+impl StorageGcReport {
+    pub fn reclaimable_bytes(&self) -> u64 {
+        self.orphan_image_bytes + self.orphan_raw_format_bytes + self.database_free_bytes
+    }
+}
+
+pub fn collect_storage_garbage(
+    conn: &Connection,
+    data_dir: &Path,
+    options: StorageGcOptions,
+) -> Result<StorageGcReport, rusqlite::Error> {
+    let mut report = StorageGcReport {
+        dry_run: options.dry_run,
+        ..Default::default()
+    };
+
+    let (page_bytes, free_bytes) = database_page_stats(conn)?;
+    report.database_bytes_before = page_bytes;
+    report.database_bytes_after = page_bytes;
+    report.database_free_bytes = free_bytes;
+
+    let orphan_images = find_orphan_images(conn, data_dir)?;
+    report.orphan_image_count = orphan_images.len() as u64;
+    report.orphan_image_bytes = orphan_images.iter().map(|(_, size)| size).sum();
+
+    let (raw_count, raw_bytes) = conn.query_row(
+        &format!("SELECT COUNT(*), COALESCE(SUM(LENGTH(raw_data)), 0) FROM clipboard_data WHERE {}", ORPHAN_RAW_FORMAT_FILTER),
+        [],
+        |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+    )?;
+    report.orphan_raw_format_count = raw_count.max(0) as u64;
+    report.orphan_raw_format_bytes = raw_bytes.max(0) as u64;
+
+    match options.tombstone_cutoff {
+        Some(cutoff) => {
+            let count: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM sync_tombstones WHERE deleted_at < ?1",
+                params![cutoff],
+                |row| row.get(0),
+            )?;
+            report.expired_tombstone_count = count.max(0) as u64;
+        }
+        None => report.tombstones_skipped = true,
+    }
+
+    if options.dry_run {
+        return Ok(report);
+    }
+
+    conn.execute(&format!("DELETE FROM clipboard_data WHERE {}", ORPHAN_RAW_FORMAT_FILTER), [])?;
+    if let Some(cutoff) = options.tombstone_cutoff {
+        conn.execute("DELETE FROM sync_tombstones WHERE deleted_at < ?1", params![cutoff])?;
+    }
+
+    for (path, _) in &orphan_images {
+        let _ = std::fs::remove_file(path);
+    }
+
+    conn.execute_batch("VACUUM; ANALYZE;")?;
+
+    let (page_bytes, free_bytes) = database_page_stats(conn)?;
+    report.database_bytes_after = page_bytes;
+    report.database_free_bytes = free_bytes;
+
+    Ok(report)
+}
+
+// 目标条目已不存在的原始格式数据
+const ORPHAN_RAW_FORMAT_FILTER: &str = "(target_kind = 'clipboard'
+        AND NOT EXISTS (SELECT 1 FROM clipboard c WHERE CAST(c.id AS TEXT) = clipboard_data.target_id))
+    OR (target_kind = 'favorite'
+        AND NOT EXISTS (SELECT 1 FROM favorites f WHERE f.id = clipboard_data.target_id))";
+
+fn database_page_stats(conn: &Connection) -> Result<(u64, u64), rusqlite::Error> {
+    let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    let page_count: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    let freelist_count: i64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+    Ok((
+        (page_size * page_count).max(0) as u64,
+        (page_size * freelist_count).max(0) as u64,
+    ))
+}
+
+fn referenced_image_ids(conn: &Connection) -> Result<HashSet<String>, rusqlite::Error> {
+    let mut ids = HashSet::new();
+    for table in ["clipboard", "favorites"] {
+        let sql = format!("SELECT image_id FROM {} WHERE image_id IS NOT NULL AND image_id != ''", table);
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        for value in rows {
+            ids.extend(
+                value?
+                    .split(',')
+                    .map(|id| id.trim())
+                    .filter(|id| !id.is_empty())
+                    .map(|id| id.to_string()),
+            );
+        }
+    }
+    Ok(ids)
+}
+
+fn find_orphan_images(conn: &Connection, data_dir: &Path) -> Result<Vec<(std::path::PathBuf, u64)>, rusqlite::Error> {
+    let images_dir = data_dir.join("clipboard_images");
+    let Ok(entries) = std::fs::read_dir(&images_dir) else {
+        return Ok(Vec::new());
+    };
+
+    let referenced = referenced_image_ids(conn)?;
+    let now = SystemTime::now();
+    let mut orphans = Vec::new();
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else { continue };
+        if !meta.is_file() {
+            continue;
+        }
+        let Some(image_id) = path.file_stem().and_then(|s| s.to_str()) else { continue };
+        if referenced.contains(image_id) {
+            continue;
+        }
+        let recently_modified = meta
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .map(|age| age < IMAGE_GRACE_PERIOD)
+            .unwrap_or(true);
+        if recently_modified {
+            continue;
+        }
+        orphans.push((path, meta.len()));
+    }
+
+    Ok(orphans)
+}
+
+// 删除记录的清理截止时间：既要超过保留期，也要早于每台已配对设备确认收到删除记录的时间，
+// 否则尚未同步的设备会把已删除的条目重新带回来。从未确认过的设备阻止清理；
+// WebDAV 上的其他设备无法确认是否已同步，启用 WebDAV 时不清理删除记录
+pub fn tombstone_cutoff(
+    now_secs: i64,
+    retention_days: u32,
+    webdav_enabled: bool,
+    peer_acknowledged_at: &[Option<i64>],
+) -> Option<i64> {
+    if webdav_enabled {
+        return None;
+    }
+    let horizon = now_secs - i64::from(retention_days) * 24 * 60 * 60;
+    peer_acknowledged_at
+        .iter()
+        .try_fold(horizon, |cutoff, acked_at| acked_at.map(|acked_at| cutoff.min(acked_at + 1)))
+}
+
+fn current_tombstone_cutoff() -> Option<i64> {
+    let settings = crate::services::get_settings();
+    let acknowledged: Vec<Option<i64>> = crate::services::sync_transfer::lan::peer_store::list_peers()
+        .iter()
+        .map(|peer| peer.tombstones_acked_at)
+        .collect();
+    tombstone_cutoff(
+        chrono::Local::now().timestamp(),
+        settings.tombstone_retention_days,
+        settings.webdav_enabled,
+        &acknowledged,
+    )
+}
+
+pub fn run_storage_gc(dry_run: bool) -> Result<StorageGcReport, String> {
+    let data_dir = crate::services::get_data_directory()?;
+    let options = StorageGcOptions {
+        dry_run,
+        tombstone_cutoff: current_tombstone_cutoff(),
+    };
+    with_connection(|conn| collect_storage_garbage(conn, &data_dir, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(conn: &Connection) {
+        conn.execute_batch(
+            "CREATE TABLE clipboard (id INTEGER PRIMARY KEY, image_id TEXT);
+             CREATE TABLE favorites (id TEXT PRIMARY KEY, image_id TEXT);
+             CREATE TABLE clipboard_data (id INTEGER PRIMARY KEY, target_kind TEXT, target_id TEXT, raw_data BLOB);
+             CREATE TABLE sync_tombstones (collection TEXT, item_id TEXT, deleted_at INTEGER);
+             INSERT INTO clipboard (id, image_id) VALUES (1, 'a,b');
+             INSERT INTO favorites (id, image_id) VALUES ('f1', NULL);
+             INSERT INTO clipboard_data (target_kind, target_id, raw_data) VALUES
+                ('clipboard', '1', x'0102'), ('clipboard', '2', x'010203'),
+                ('favorite', 'f1', x'01'), ('favorite', 'gone', x'0102');
+             INSERT INTO sync_tombstones VALUES ('history', 'x', 100), ('history', 'y', 500);",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn dry_run_reports_without_deleting_and_run_cleans_up() {
+        let conn = Connection::open_in_memory().unwrap();
+        setup(&conn);
+        let dir = std::env::temp_dir().join(format!("qc-storage-gc-{}", std::process::id()));
+        let images = dir.join("clipboard_images");
+        std::fs::create_dir_all(&images).unwrap();
+        for name in ["a", "b", "orphan"] {
+            let path = images.join(format!("{}.png", name));
+            std::fs::write(&path, b"png").unwrap();
+            let old = SystemTime::now() - Duration::from_secs(3600);
+            std::fs::File::options().write(true).open(&path).unwrap().set_modified(old).unwrap();
+        }
+
+        let options = StorageGcOptions { dry_run: true, tombstone_cutoff: Some(200) };
+        let report = collect_storage_garbage(&conn, &dir, options).unwrap();
+        assert_eq!(report.orphan_image_count, 1);
+        assert_eq!(report.orphan_raw_format_count, 2);
+        assert_eq!(report.orphan_raw_format_bytes, 5);
+        assert_eq!(report.expired_tombstone_count, 1);
+        assert!(images.join("orphan.png").exists());
+
+        let options = StorageGcOptions { dry_run: false, ..options };
+        collect_storage_garbage(&conn, &dir, options).unwrap();
+        assert!(!images.join("orphan.png").exists());
+        assert!(images.join("a.png").exists());
+        let remaining: i64 = conn.query_row("SELECT COUNT(*) FROM clipboard_data", [], |r| r.get(0)).unwrap();
+        assert_eq!(remaining, 2);
+        let tombstones: i64 = conn.query_row("SELECT COUNT(*) FROM sync_tombstones", [], |r| r.get(0)).unwrap();
+        assert_eq!(tombstones, 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn tombstone_cutoff_waits_for_slowest_peer_ack() {
+        let day = 24 * 60 * 60;
+        assert_eq!(tombstone_cutoff(100 * day, 30, false, &[]), Some(70 * day));
+        assert_eq!(
+            tombstone_cutoff(100 * day, 30, false, &[Some(90 * day), Some(50 * day)]),
+            Some(50 * day + 1)
+        );
+        // 从未完成删除记录交换的设备、启用 WebDAV 时都不清理
+        assert_eq!(tombstone_cutoff(100 * day, 30, false, &[Some(90 * day), None]), None);
+        assert_eq!(tombstone_cutoff(100 * day, 30, true, &[Some(90 * day)]), None);
+    }
+}
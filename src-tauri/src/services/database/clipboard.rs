@@ -1,6 +1,10 @@
 use super::models::{ClipboardDataItem, ClipboardDataSeed, ClipboardItem, PaginatedResult, QueryParams};
 use super::connection::{with_connection, MAX_CONTENT_LENGTH};
-use crate::services::webdav_sync::types::{CloudRecord, CloudRecordMeta};
+use super::pattern::{matched_keyword, pattern_snippet, PatternMatcher, PATTERN_SCAN_CHARS, PATTERN_SCAN_ROWS};
+use super::query::{split_field_filters, ParsedQuery, QueryTarget};
+use super::search::{is_search_index_ready, render_snippet, snippet_sql};
+use super::sync_versions::{self, RecordUpsertResult, SyncContent, UpsertPlan};
+use crate::services::webdav_sync::types::{CloudRecord, CloudRecordMeta, CONFLICT_COPIED, CONFLICT_MERGED};
 use crate::utils::{truncate_string, truncate_around_keyword, truncate_html};
 use rusqlite::{params, OptionalExtension};
 use std::collections::{HashMap, HashSet};
@@ -184,32 +188,130 @@ fn delete_image_files(image_ids: Vec<String>) -> Result<(), String> {
     Ok(())
 }
 
+// 列表查询的列（顺序与 read_clipboard_row 对应）
+const LIST_COLUMNS: &str = "c.id, c.uuid, c.source_device_id, c.is_remote, c.content, c.html_content, c.content_type, c.image_id, c.item_order, c.is_pinned, c.paste_count, c.source_app, c.source_icon_hash, c.created_at, c.updated_at, c.char_count, c.expires_at";
+
+// 读取列表查询的一行，返回剪贴板项及字符数回填所需信息
+fn read_clipboard_row(
+    row: &rusqlite::Row,
+    keyword: Option<&str>,
+) -> rusqlite::Result<(ClipboardItem, bool, i64, String, String)> {
+    let id: i64 = row.get(0)?;
+    let uuid: Option<String> = row.get(1)?;
+    let source_device_id: Option<String> = row.get(2)?;
+    let is_remote: i64 = row.get(3)?;
+    let content: String = row.get(4)?;
+    let html_content: Option<String> = row.get(5)?;
+    let content_type: String = row.get(6)?;
+    let char_count: Option<i64> = row.get(15)?;
+    let search_snippet: Option<String> = row.get(17)?;
+    
+    let (truncated_content, truncated_html) = if content_type == "text" || content_type == "rich_text" || content_type == "link" {
+        let truncated_content = if content.len() > MAX_CONTENT_LENGTH {
+            if let Some(keyword) = keyword {
+                truncate_around_keyword(content.clone(), keyword, MAX_CONTENT_LENGTH)
+            } else {
+                truncate_string(content.clone(), MAX_CONTENT_LENGTH)
+            }
+        } else {
+            content.clone()
+        };
+        
+        let truncated_html = html_content.map(|h| {
+            if h.len() > MAX_CONTENT_LENGTH {
+                truncate_html(h, MAX_CONTENT_LENGTH)
+            } else {
+                h
+            }
+        });
+        
+        (truncated_content, truncated_html)
+    } else {
+        (content.clone(), html_content)
+    };
+
+    let needs_char_count = content_type.contains("text") || content_type.contains("rich_text");
+    let final_char_count = if char_count.is_none() && needs_char_count && !content.is_empty() {
+        Some(content.chars().count() as i64)
+    } else {
+        char_count
+    };
+    
+    Ok((ClipboardItem {
+        id,
+        uuid,
+        source_device_id,
+        is_remote: is_remote != 0,
+        content: truncated_content,
+        html_content: truncated_html,
+        content_type: content_type.clone(),
+        image_id: row.get(7)?,
+        item_order: row.get(8)?,
+        is_pinned: row.get::<_, i64>(9)? != 0,
+        paste_count: row.get(10)?,
+        source_app: row.get(11)?,
+        source_icon_hash: row.get(12)?,
+        char_count: final_char_count,
+        created_at: row.get(13)?,
+        updated_at: row.get(14)?,
+        expires_at: row.get(16)?,
+        search_snippet: search_snippet.map(|snippet| render_snippet(&snippet)),
+    }, char_count.is_none() && needs_char_count, id, content, content_type))
+}
+
 // 分页查询剪贴板历史
 pub fn query_clipboard_items(params: QueryParams) -> Result<PaginatedResult<ClipboardItem>, String> {
-    let search_keyword = params.search.clone();
-    let has_filter = search_keyword.as_ref().map(|s| !s.trim().is_empty()).unwrap_or(false)
+    if let Some(search) = params.search.as_deref() {
+        let (filters, pattern) = split_field_filters(search);
+        if let Some(matcher) = PatternMatcher::new(params.search_mode, &pattern)? {
+            return query_clipboard_items_by_pattern(&params, &matcher, &filters);
+        }
+    }
+
+    let search_query = match params.search.as_deref() {
+        Some(search) => Some(ParsedQuery::parse(search)).filter(|query| !query.is_empty()),
+        None => None,
+    };
+    let search_keyword = search_query
+        .as_ref()
+        .and_then(|query| query.first_keyword())
+        .map(|keyword| keyword.to_string());
+    let compiled = search_query
+        .as_ref()
+        .map(|query| query.compile(QueryTarget::History, is_search_index_ready()))
+        .unwrap_or_default();
+    let fts_expression = compiled.fts_match.clone();
+    let has_filter = search_query.is_some()
         || params.content_type.as_ref().map(|t| t != "all").unwrap_or(false);
     
     with_connection(|conn| {
-        let mut where_clauses = vec![];
+        let mut where_clauses: Vec<String> = vec![];
         let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = vec![];
         
-        if let Some(ref search) = search_keyword {
-            if !search.trim().is_empty() {
-                where_clauses.push("content LIKE ?");
-                let search_pattern = format!("%{}%", search);
-                query_params.push(Box::new(search_pattern));
+        if let Some(ref expression) = fts_expression {
+            where_clauses.push("clipboard_fts MATCH ?".to_string());
+            query_params.push(Box::new(expression.clone()));
+        }
+        for condition in &compiled.conditions {
+            where_clauses.push(condition.sql.clone());
+            for param in &condition.params {
+                query_params.push(Box::new(param.clone()));
             }
         }
         
         if let Some(ref content_type) = params.content_type {
             if content_type != "all" {
-                where_clauses.push("content_type LIKE ?");
+                where_clauses.push("c.content_type LIKE ?".to_string());
                 let pattern = format!("%{}%", content_type);
                 query_params.push(Box::new(pattern));
             }
         }
         
+        let from_clause = if fts_expression.is_some() {
+            "FROM clipboard c JOIN clipboard_fts ON clipboard_fts.rowid = c.id"
+        } else {
+            "FROM clipboard c"
+        };
         let where_clause = if where_clauses.is_empty() {
             String::new()
         } else {
@@ -217,19 +319,10 @@ pub fn query_clipboard_items(params: QueryParams) -> Result<PaginatedResult<Clip
         };
         
         let total_count: i64 = if has_filter {
-            let count_sql = format!("SELECT COUNT(*) FROM clipboard {}", where_clause);
-            let count_params: Vec<Box<dyn rusqlite::ToSql>> = query_params.iter().map(|p| {
-                let val: Box<dyn rusqlite::ToSql> = match p.as_ref().to_sql() {
-                    Ok(rusqlite::types::ToSqlOutput::Borrowed(rusqlite::types::ValueRef::Text(s))) => {
-                        Box::new(String::from_utf8_lossy(s).to_string())
-                    }
-                    _ => Box::new("")
-                };
-                val
-            }).collect();
+            let count_sql = format!("SELECT COUNT(*) {} {}", from_clause, where_clause);
             conn.query_row(
                 &count_sql,
-                rusqlite::params_from_iter(count_params.iter().map(|p| p.as_ref())),
+                rusqlite::params_from_iter(query_params.iter().map(|p| p.as_ref())),
                 |row| row.get(0)
             )?
         } else {
@@ -240,13 +333,24 @@ pub fn query_clipboard_items(params: QueryParams) -> Result<PaginatedResult<Clip
             return Ok(PaginatedResult::new(0, vec![], params.offset, params.limit));
         }
         
+        let (snippet_column, order_clause) = if fts_expression.is_some() {
+            let order = if params.order_by_relevance {
+                "bm25(clipboard_fts, 10.0, 1.0, 5.0), c.updated_at DESC"
+            } else {
+                "c.is_pinned DESC, c.item_order DESC, c.updated_at DESC"
+            };
+            (snippet_sql("clipboard_fts"), order)
+        } else {
+            ("NULL".to_string(), "c.is_pinned DESC, c.item_order DESC, c.updated_at DESC")
+        };
+        
         let query_sql = format!(
-            "SELECT id, uuid, source_device_id, is_remote, content, html_content, content_type, image_id, item_order, is_pinned, paste_count, source_app, source_icon_hash, created_at, updated_at, char_count 
-             FROM clipboard 
+            "SELECT {}, {} 
              {} 
-             ORDER BY is_pinned DESC, item_order DESC, updated_at DESC 
+             {} 
+             ORDER BY {} 
              LIMIT ? OFFSET ?",
-            where_clause
+            LIST_COLUMNS, snippet_column, from_clause, where_clause, order_clause
         );
         
         query_params.push(Box::new(params.limit));
@@ -258,70 +362,7 @@ pub fn query_clipboard_items(params: QueryParams) -> Result<PaginatedResult<Clip
         
         let items = stmt.query_map(
             rusqlite::params_from_iter(query_params.iter().map(|p| p.as_ref())),
-            |row| {
-                let id: i64 = row.get(0)?;
-                let uuid: Option<String> = row.get(1)?;
-                let source_device_id: Option<String> = row.get(2)?;
-                let is_remote: i64 = row.get(3)?;
-                let content: String = row.get(4)?;
-                let html_content: Option<String> = row.get(5)?;
-                let content_type: String = row.get(6)?;
-                let char_count: Option<i64> = row.get(15)?;
-                
-                let (truncated_content, truncated_html) = if content_type == "text" || content_type == "rich_text" || content_type == "link" {
-                    let truncated_content = if content.len() > MAX_CONTENT_LENGTH {
-                        if let Some(ref keyword) = search_keyword {
-                            if !keyword.trim().is_empty() {
-                                truncate_around_keyword(content.clone(), keyword, MAX_CONTENT_LENGTH)
-                            } else {
-                                truncate_string(content.clone(), MAX_CONTENT_LENGTH)
-                            }
-                        } else {
-                            truncate_string(content.clone(), MAX_CONTENT_LENGTH)
-                        }
-                    } else {
-                        content.clone()
-                    };
-                    
-                    let truncated_html = html_content.map(|h| {
-                        if h.len() > MAX_CONTENT_LENGTH {
-                            truncate_html(h, MAX_CONTENT_LENGTH)
-                        } else {
-                            h
-                        }
-                    });
-                    
-                    (truncated_content, truncated_html)
-                } else {
-                    (content.clone(), html_content)
-                };
-
-                let needs_char_count = content_type.contains("text") || content_type.contains("rich_text");
-                let final_char_count = if char_count.is_none() && needs_char_count && !content.is_empty() {
-                    Some(content.chars().count() as i64)
-                } else {
-                    char_count
-                };
-                
-                Ok((ClipboardItem {
-                    id,
-                    uuid,
-                    source_device_id,
-                    is_remote: is_remote != 0,
-                    content: truncated_content,
-                    html_content: truncated_html,
-                    content_type: content_type.clone(),
-                    image_id: row.get(7)?,
-                    item_order: row.get(8)?,
-                    is_pinned: row.get::<_, i64>(9)? != 0,
-                    paste_count: row.get(10)?,
-                    source_app: row.get(11)?,
-                    source_icon_hash: row.get(12)?,
-                    char_count: final_char_count,
-                    created_at: row.get(13)?,
-                    updated_at: row.get(14)?,
-                }, char_count.is_none() && needs_char_count, id, content, content_type))
-            }
+            |row| read_clipboard_row(row, search_keyword.as_deref()),
         )?
         .collect::<Result<Vec<_>, _>>()?;
         
@@ -341,6 +382,93 @@ pub fn query_clipboard_items(params: QueryParams) -> Result<PaginatedResult<Clip
     })
 }
 
+// 正则 / 模糊模式：字段过滤在 SQL 中完成，再逐条匹配文本内容后在内存中排序分页
+fn query_clipboard_items_by_pattern(
+    params: &QueryParams,
+    matcher: &PatternMatcher,
+    filters: &ParsedQuery,
+) -> Result<PaginatedResult<ClipboardItem>, String> {
+    let compiled = filters.compile(QueryTarget::History, false);
+    with_connection(|conn| {
+        let mut where_clauses = vec!["c.content NOT LIKE 'files:%'".to_string()];
+        let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = vec![];
+        for condition in &compiled.conditions {
+            where_clauses.push(condition.sql.clone());
+            for param in &condition.params {
+                query_params.push(Box::new(param.clone()));
+            }
+        }
+
+        if let Some(ref content_type) = params.content_type {
+            if content_type != "all" {
+                where_clauses.push("c.content_type LIKE ?".to_string());
+                query_params.push(Box::new(format!("%{}%", content_type)));
+            }
+        }
+
+        let scan_sql = format!(
+            "SELECT c.id, substr(c.content, 1, {}) FROM clipboard c WHERE {}
+             ORDER BY c.is_pinned DESC, c.item_order DESC, c.updated_at DESC LIMIT {}",
+            PATTERN_SCAN_CHARS,
+            where_clauses.join(" AND "),
+            PATTERN_SCAN_ROWS
+        );
+        let mut stmt = conn.prepare(&scan_sql)?;
+        let mut rows = stmt.query(rusqlite::params_from_iter(query_params.iter().map(|p| p.as_ref())))?;
+
+        let mut matches: Vec<(i64, f64, String)> = vec![];
+        while let Some(row) = rows.next()? {
+            let content: String = row.get(1)?;
+            if let Some(found) = matcher.find(&content) {
+                matches.push((row.get(0)?, found.score, matched_keyword(&content, &found)));
+            }
+        }
+
+        if matcher.is_ranked() {
+            matches.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        }
+
+        let total_count = matches.len() as i64;
+        let page: Vec<(i64, String)> = matches
+            .into_iter()
+            .skip(params.offset.max(0) as usize)
+            .take(params.limit.max(0) as usize)
+            .map(|(id, _, keyword)| (id, keyword))
+            .collect();
+        if page.is_empty() {
+            return Ok(PaginatedResult::new(total_count, vec![], params.offset, params.limit));
+        }
+
+        let placeholders = vec!["?"; page.len()].join(",");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, NULL FROM clipboard c WHERE c.id IN ({})",
+            LIST_COLUMNS, placeholders
+        ))?;
+        let keywords: HashMap<i64, &str> = page.iter().map(|(id, keyword)| (*id, keyword.as_str())).collect();
+        let mut loaded: HashMap<i64, ClipboardItem> = HashMap::new();
+        let mut items_to_update: Vec<(i64, String, String)> = vec![];
+
+        let mut rows = stmt.query(rusqlite::params_from_iter(page.iter().map(|(id, _)| id)))?;
+        while let Some(row) = rows.next()? {
+            let id: i64 = row.get(0)?;
+            let keyword = keywords.get(&id).copied().filter(|k| !k.is_empty());
+            let (mut item, needs_update, id, content, content_type) = read_clipboard_row(row, keyword)?;
+            item.search_snippet = keyword.and_then(|k| pattern_snippet(&content, k));
+            if needs_update {
+                items_to_update.push((id, content, content_type));
+            }
+            loaded.insert(id, item);
+        }
+
+        if !items_to_update.is_empty() {
+            update_missing_char_counts(items_to_update);
+        }
+
+        let result_items = page.iter().filter_map(|(id, _)| loaded.remove(id)).collect();
+        Ok(PaginatedResult::new(total_count, result_items, params.offset, params.limit))
+    })
+}
+
 pub fn webdav_list_history_records(device_id: &str) -> Result<Vec<CloudRecord>, String> {
     with_connection(|conn| {
         let mut stmt = conn.prepare(
@@ -377,17 +505,20 @@ pub fn webdav_list_history_records(device_id: &str) -> Result<Vec<CloudRecord>,
                 group_name: "全部".to_string(),
                 created_at: row.get(14)?,
                 updated_at: row.get(15)?,
+                version: Default::default(),
             })
         })?;
 
-        Ok(rows.filter_map(|row| row.ok()).collect())
+        let mut records: Vec<CloudRecord> = rows.filter_map(|row| row.ok()).collect();
+        sync_versions::attach_versions_in_conn(conn, super::tombstones::COLLECTION_HISTORY, &mut records)?;
+        Ok(records)
     })
 }
 
 pub fn webdav_list_history_record_metas() -> Result<Vec<CloudRecordMeta>, String> {
     with_connection(|conn| {
         let mut stmt = conn.prepare(
-            "SELECT id, uuid, updated_at, image_id
+            "SELECT id, uuid, updated_at, image_id, source_device_id, is_remote
              FROM clipboard
              ORDER BY item_order DESC, updated_at DESC, id DESC",
         )?;
@@ -400,6 +531,9 @@ pub fn webdav_list_history_record_metas() -> Result<Vec<CloudRecordMeta>, String
                 uuid,
                 updated_at: row.get(2)?,
                 image_id: row.get(3)?,
+                source_device_id: row.get(4)?,
+                is_remote: row.get::<_, i64>(5)? != 0,
+                group_name: None,
             })
         })?;
 
@@ -444,10 +578,13 @@ pub fn webdav_get_history_record_by_uuid(uuid: &str, device_id: &str) -> Result<
                 group_name: "全部".to_string(),
                 created_at: row.get(14)?,
                 updated_at: row.get(15)?,
+                version: Default::default(),
             })
         }).optional()?;
 
-        Ok(record)
+        let mut records: Vec<CloudRecord> = record.into_iter().collect();
+        sync_versions::attach_versions_in_conn(conn, super::tombstones::COLLECTION_HISTORY, &mut records)?;
+        Ok(records.pop())
     })
 }
 
@@ -484,6 +621,7 @@ pub fn webdav_list_own_history_records(device_id: &str) -> Result<Vec<CloudRecor
                 group_name: "全部".to_string(),
                 created_at: row.get(14)?,
                 updated_at: row.get(15)?,
+                version: Default::default(),
             })
         })?;
 
@@ -506,186 +644,294 @@ pub fn webdav_history_record_states() -> Result<HashMap<String, i64>, String> {
     })
 }
 
-pub fn lan_upsert_history_records(records: &[CloudRecord]) -> Result<Vec<CloudRecord>, String> {
+pub fn lan_upsert_history_records(records: &[CloudRecord]) -> Result<RecordUpsertResult, String> {
     upsert_history_records(records, false)
 }
 
-pub fn webdav_repair_history_records(records: &[CloudRecord]) -> Result<Vec<CloudRecord>, String> {
+pub fn webdav_repair_history_records(records: &[CloudRecord]) -> Result<RecordUpsertResult, String> {
     upsert_history_records(records, true)
 }
 
-fn upsert_history_records(records: &[CloudRecord], ignore_tombstones: bool) -> Result<Vec<CloudRecord>, String> {
+fn upsert_history_records(records: &[CloudRecord], ignore_tombstones: bool) -> Result<RecordUpsertResult, String> {
     if records.is_empty() {
-        return Ok(Vec::new());
+        return Ok(RecordUpsertResult::default());
     }
+    let device_id = crate::services::sync_transfer::device_id();
+    with_connection(|conn| upsert_history_records_in_conn(conn, records, ignore_tombstones, &device_id))
+}
 
-    with_connection(|conn| {
-        let tx = conn.unchecked_transaction()?;
-        let mut changed = Vec::new();
+pub(crate) fn upsert_history_records_in_conn(
+    conn: &rusqlite::Connection,
+    records: &[CloudRecord],
+    ignore_tombstones: bool,
+    device_id: &str,
+) -> Result<RecordUpsertResult, rusqlite::Error> {
+    let tx = conn.unchecked_transaction()?;
+    let mut result = RecordUpsertResult::default();
 
-        for record in records {
-            if record.uuid.trim().is_empty() {
-                continue;
-            }
-            let tombstone_deleted_at = super::tombstones::sync_tombstone_deleted_at_in_conn(
-                &tx,
-                super::tombstones::COLLECTION_HISTORY,
-                &record.uuid,
-            )?;
-            if !ignore_tombstones && tombstone_deleted_at.map(|value| value >= record.updated_at).unwrap_or(false) {
-                continue;
-            }
-            let restored_updated_at = if ignore_tombstones {
-                super::tombstones::restored_record_updated_at(record.updated_at, tombstone_deleted_at)
-            } else {
-                record.updated_at
+    for record in records {
+        if record.uuid.trim().is_empty() {
+            continue;
+        }
+        let tombstone_deleted_at = super::tombstones::sync_tombstone_deleted_at_in_conn(
+            &tx,
+            super::tombstones::COLLECTION_HISTORY,
+            &record.uuid,
+        )?;
+        if !ignore_tombstones && tombstone_deleted_at.map(|value| value >= record.updated_at).unwrap_or(false) {
+            continue;
+        }
+        let restored_updated_at = if ignore_tombstones {
+            super::tombstones::restored_record_updated_at(record.updated_at, tombstone_deleted_at)
+        } else {
+            record.updated_at
+        };
+
+        let existing = tx
+            .query_row(
+                "SELECT COALESCE(source_device_id, ''), updated_at, content, html_content, content_type,
+                        image_id, item_order, paste_count, source_app, source_icon_hash, char_count, created_at, id
+                 FROM clipboard WHERE uuid = ?1 LIMIT 1",
+                params![record.uuid],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                        row.get::<_, String>(4)?,
+                        row.get::<_, Option<String>>(5)?,
+                        row.get::<_, i64>(6)?,
+                        row.get::<_, i64>(7)?,
+                        row.get::<_, Option<String>>(8)?,
+                        row.get::<_, Option<String>>(9)?,
+                        row.get::<_, Option<i64>>(10)?,
+                        row.get::<_, i64>(11)?,
+                        row.get::<_, i64>(12)?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        if let Some((
+            source_device_id,
+            updated_at,
+            content,
+            html_content,
+            content_type,
+            image_id,
+            item_order,
+            paste_count,
+            source_app,
+            source_icon_hash,
+            char_count,
+            created_at,
+            local_id,
+        )) = existing {
+            let local = SyncContent {
+                title: String::new(),
+                content: content.clone(),
+                html_content: html_content.clone(),
             };
+            let mut incoming = record.clone();
+            incoming.updated_at = restored_updated_at;
 
-            let existing = tx
-                .query_row(
-                    "SELECT COALESCE(source_device_id, ''), updated_at, content, html_content, content_type,
-                            image_id, item_order, paste_count, source_app, source_icon_hash, char_count, created_at
-                     FROM clipboard WHERE uuid = ?1 LIMIT 1",
-                    params![record.uuid],
-                    |row| {
-                        Ok((
-                            row.get::<_, String>(0)?,
-                            row.get::<_, i64>(1)?,
-                            row.get::<_, String>(2)?,
-                            row.get::<_, Option<String>>(3)?,
-                            row.get::<_, String>(4)?,
-                            row.get::<_, Option<String>>(5)?,
-                            row.get::<_, i64>(6)?,
-                            row.get::<_, i64>(7)?,
-                            row.get::<_, Option<String>>(8)?,
-                            row.get::<_, Option<String>>(9)?,
-                            row.get::<_, Option<i64>>(10)?,
-                            row.get::<_, i64>(11)?,
-                        ))
-                    },
-                )
-                .optional()?;
-
-            if let Some((
-                source_device_id,
-                updated_at,
-                content,
-                html_content,
-                content_type,
-                image_id,
-                item_order,
-                paste_count,
-                source_app,
-                source_icon_hash,
-                char_count,
-                created_at,
-            )) = existing {
-                let same = source_device_id == record.source_device_id
-                    && updated_at == restored_updated_at
-                    && content == record.content
-                    && html_content == record.html_content
-                    && content_type == record.content_type
-                    && image_id == record.image_id
-                    && item_order == record.item_order
-                    && paste_count == record.paste_count
-                    && source_app == record.source_app
-                    && source_icon_hash == record.source_icon_hash
-                    && char_count == record.char_count
-                    && created_at == record.created_at;
-
-                if updated_at >= restored_updated_at || same {
-                    if tombstone_deleted_at.map(|deleted_at| deleted_at < updated_at).unwrap_or(false) {
-                        super::tombstones::delete_sync_tombstone_in_conn(
-                            &tx,
-                            super::tombstones::COLLECTION_HISTORY,
-                            &record.uuid,
-                        )?;
+            // None 表示保留本地内容
+            let decision = match sync_versions::plan_upsert(
+                &tx,
+                super::tombstones::COLLECTION_HISTORY,
+                &local,
+                &content_type,
+                record,
+                device_id,
+            )? {
+                UpsertPlan::ByTimestamp(version) => {
+                    let same = source_device_id == record.source_device_id
+                        && updated_at == restored_updated_at
+                        && content == record.content
+                        && html_content == record.html_content
+                        && content_type == record.content_type
+                        && image_id == record.image_id
+                        && item_order == record.item_order
+                        && paste_count == record.paste_count
+                        && source_app == record.source_app
+                        && source_icon_hash == record.source_icon_hash
+                        && char_count == record.char_count
+                        && created_at == record.created_at;
+
+                    if updated_at >= restored_updated_at || same {
+                        if let Some(version) = version {
+                            sync_versions::save_version_in_conn(
+                                &tx,
+                                super::tombstones::COLLECTION_HISTORY,
+                                &record.uuid,
+                                &local,
+                                &version,
+                                &local,
+                                &version,
+                            )?;
+                        }
+                        None
+                    } else {
+                        Some((version.unwrap_or_else(|| record.version.clone()), None))
                     }
-                    continue;
                 }
+                UpsertPlan::KeepLocal => None,
+                UpsertPlan::TakeRemote(version) => Some((version, None)),
+                UpsertPlan::Merged(merged, version) => {
+                    incoming.content = merged.content;
+                    incoming.html_content = merged.html_content;
+                    incoming.char_count = calculate_char_count(&incoming.content, &incoming.content_type);
+                    incoming.paste_count = incoming.paste_count.max(paste_count);
+                    incoming.updated_at = updated_at.max(restored_updated_at) + 1;
+                    let conflict = incoming.conflict_item("clipboard", CONFLICT_MERGED, None);
+                    Some((version, Some(conflict)))
+                }
+                UpsertPlan::Conflict(version) => {
+                    let copy_id = insert_history_conflict_copy(&tx, local_id, device_id)?;
+                    let conflict = record.conflict_item("clipboard", CONFLICT_COPIED, Some(copy_id));
+                    Some((version, Some(conflict)))
+                }
+            };
 
-                tx.execute(
-                    "UPDATE clipboard SET
-                        source_device_id = ?1,
-                        is_remote = 1,
-                        content = ?2,
-                        html_content = ?3,
-                        content_type = ?4,
-                        image_id = ?5,
-                        item_order = ?6,
-                        paste_count = ?7,
-                        source_app = ?8,
-                        source_icon_hash = ?9,
-                        char_count = ?10,
-                        created_at = ?11,
-                        updated_at = ?12
-                     WHERE uuid = ?13",
-                    params![
-                        record.source_device_id,
-                        record.content,
-                        record.html_content,
-                        record.content_type,
-                        record.image_id,
-                        record.item_order,
-                        record.paste_count,
-                        record.source_app,
-                        record.source_icon_hash,
-                        record.char_count,
-                        record.created_at,
-                        restored_updated_at,
-                        record.uuid,
-                    ],
-                )?;
-                if tombstone_deleted_at.map(|deleted_at| deleted_at < restored_updated_at).unwrap_or(false) {
+            let Some((version, conflict)) = decision else {
+                if tombstone_deleted_at.map(|deleted_at| deleted_at < updated_at).unwrap_or(false) {
                     super::tombstones::delete_sync_tombstone_in_conn(
                         &tx,
                         super::tombstones::COLLECTION_HISTORY,
                         &record.uuid,
                     )?;
                 }
-                let mut changed_record = record.clone();
-                changed_record.updated_at = restored_updated_at;
-                changed.push(changed_record);
                 continue;
-            }
+            };
 
             tx.execute(
-                "INSERT INTO clipboard (
-                    uuid, source_device_id, is_remote, content, html_content, content_type,
-                    image_id, item_order, is_pinned, paste_count, source_app, source_icon_hash,
-                    char_count, created_at, updated_at
-                 ) VALUES (?1, ?2, 1, ?3, ?4, ?5, ?6, ?7, 0, ?8, ?9, ?10, ?11, ?12, ?13)",
+                "UPDATE clipboard SET
+                    source_device_id = ?1,
+                    is_remote = 1,
+                    content = ?2,
+                    html_content = ?3,
+                    content_type = ?4,
+                    image_id = ?5,
+                    item_order = ?6,
+                    paste_count = ?7,
+                    source_app = ?8,
+                    source_icon_hash = ?9,
+                    char_count = ?10,
+                    created_at = ?11,
+                    updated_at = ?12
+                 WHERE uuid = ?13",
                 params![
+                    incoming.source_device_id,
+                    incoming.content,
+                    incoming.html_content,
+                    incoming.content_type,
+                    incoming.image_id,
+                    incoming.item_order,
+                    incoming.paste_count,
+                    incoming.source_app,
+                    incoming.source_icon_hash,
+                    incoming.char_count,
+                    incoming.created_at,
+                    incoming.updated_at,
                     record.uuid,
-                    record.source_device_id,
-                    record.content,
-                    record.html_content,
-                    record.content_type,
-                    record.image_id,
-                    record.item_order,
-                    record.paste_count,
-                    record.source_app,
-                    record.source_icon_hash,
-                    record.char_count,
-                    record.created_at,
-                    restored_updated_at,
                 ],
             )?;
-            if tombstone_deleted_at.map(|deleted_at| deleted_at < restored_updated_at).unwrap_or(false) {
+            sync_versions::save_version_in_conn(
+                &tx,
+                super::tombstones::COLLECTION_HISTORY,
+                &record.uuid,
+                &SyncContent::of_record(&incoming),
+                &version,
+                &SyncContent::of_record(record),
+                &record.version,
+            )?;
+            if tombstone_deleted_at.map(|deleted_at| deleted_at < incoming.updated_at).unwrap_or(false) {
                 super::tombstones::delete_sync_tombstone_in_conn(
                     &tx,
                     super::tombstones::COLLECTION_HISTORY,
                     &record.uuid,
                 )?;
             }
-            let mut changed_record = record.clone();
-            changed_record.updated_at = restored_updated_at;
-            changed.push(changed_record);
+            incoming.version = version;
+            result.changed.push(incoming);
+            result.conflicts.extend(conflict);
+            continue;
         }
 
-        tx.commit()?;
-        Ok(changed)
-    })
+        tx.execute(
+            "INSERT INTO clipboard (
+                uuid, source_device_id, is_remote, content, html_content, content_type,
+                image_id, item_order, is_pinned, paste_count, source_app, source_icon_hash,
+                char_count, created_at, updated_at
+             ) VALUES (?1, ?2, 1, ?3, ?4, ?5, ?6, ?7, 0, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                record.uuid,
+                record.source_device_id,
+                record.content,
+                record.html_content,
+                record.content_type,
+                record.image_id,
+                record.item_order,
+                record.paste_count,
+                record.source_app,
+                record.source_icon_hash,
+                record.char_count,
+                record.created_at,
+                restored_updated_at,
+            ],
+        )?;
+        let synced = SyncContent::of_record(record);
+        sync_versions::save_version_in_conn(
+            &tx,
+            super::tombstones::COLLECTION_HISTORY,
+            &record.uuid,
+            &synced,
+            &record.version,
+            &synced,
+            &record.version,
+        )?;
+        if tombstone_deleted_at.map(|deleted_at| deleted_at < restored_updated_at).unwrap_or(false) {
+            super::tombstones::delete_sync_tombstone_in_conn(
+                &tx,
+                super::tombstones::COLLECTION_HISTORY,
+                &record.uuid,
+            )?;
+        }
+        let mut changed_record = record.clone();
+        changed_record.updated_at = restored_updated_at;
+        result.changed.push(changed_record);
+    }
+
+    tx.commit()?;
+    Ok(result)
+}
+
+// 把本地内容另存为一条新的本机记录，原始格式数据随之转移
+fn insert_history_conflict_copy(
+    conn: &rusqlite::Connection,
+    local_id: i64,
+    device_id: &str,
+) -> Result<String, rusqlite::Error> {
+    let copy_uuid = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO clipboard (
+            uuid, source_device_id, is_remote, content, html_content, content_type,
+            image_id, item_order, is_pinned, paste_count, source_app, source_icon_hash,
+            char_count, created_at, updated_at
+         )
+         SELECT ?2, ?3, 0, content, html_content, content_type,
+                image_id, item_order, is_pinned, paste_count, source_app, source_icon_hash,
+                char_count, created_at, ?4
+         FROM clipboard WHERE id = ?1",
+        params![local_id, copy_uuid, device_id, chrono::Local::now().timestamp()],
+    )?;
+    let copy_id = conn.last_insert_rowid();
+    conn.execute(
+        "UPDATE clipboard_data SET target_id = ?2 WHERE target_kind = 'clipboard' AND target_id = ?1",
+        params![local_id.to_string(), copy_id.to_string()],
+    )?;
+    Ok(copy_uuid)
 }
 
 
@@ -769,7 +1015,7 @@ pub fn get_clipboard_item_id_by_uuid(uuid: &str) -> Result<Option<i64>, String>
 pub fn get_clipboard_item_by_id_with_limit(id: i64, max_content_length: Option<usize>) -> Result<Option<ClipboardItem>, String> {
     with_connection(|conn| {
         conn.query_row(
-            "SELECT id, uuid, source_device_id, is_remote, content, html_content, content_type, image_id, item_order, is_pinned, paste_count, source_app, source_icon_hash, created_at, updated_at, char_count 
+            "SELECT id, uuid, source_device_id, is_remote, content, html_content, content_type, image_id, item_order, is_pinned, paste_count, source_app, source_icon_hash, created_at, updated_at, char_count, expires_at 
              FROM clipboard WHERE id = ?",
             params![id],
             |row| {
@@ -815,6 +1061,8 @@ pub fn get_clipboard_item_by_id_with_limit(id: i64, max_content_length: Option<u
                     char_count: final_char_count,
                     created_at: row.get(13)?,
                     updated_at: row.get(14)?,
+                    expires_at: row.get(16)?,
+                    search_snippet: None,
                 })
             }
         )
@@ -944,6 +1192,59 @@ pub fn delete_clipboard_item(id: i64) -> Result<(), String> {
     delete_image_files(images_to_delete)
 }
 
+// 删除条目并记录同步删除状态，返回不再被引用、可以删除文件的图片 ID
+fn delete_clipboard_rows_in_conn(
+    conn: &rusqlite::Connection,
+    ids: &[i64],
+    local_device_id: &str,
+) -> Result<Vec<String>, rusqlite::Error> {
+    let mut image_id_set: HashSet<String> = HashSet::new();
+    let mut tombstone_ids = Vec::new();
+    for id in ids {
+        let item: Option<(Option<String>, Option<String>)> = conn
+            .query_row(
+                "SELECT image_id, uuid FROM clipboard WHERE id = ?",
+                params![id],
+                |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, Option<String>>(1)?)),
+            )
+            .optional()?;
+
+        if let Some((image_ids, uuid)) = item {
+            if let Some(image_ids) = image_ids {
+                for image_id in split_image_ids(&image_ids) {
+                    image_id_set.insert(image_id);
+                }
+            }
+            tombstone_ids.push(uuid.filter(|value| !value.trim().is_empty()).unwrap_or_else(|| id.to_string()));
+        }
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    let deleted_at = chrono::Local::now().timestamp();
+    for uuid in &tombstone_ids {
+        super::tombstones::record_sync_tombstone_in_conn(
+            &tx,
+            super::tombstones::COLLECTION_HISTORY,
+            uuid,
+            local_device_id,
+            deleted_at,
+        )?;
+    }
+    for id in ids {
+        tx.execute("DELETE FROM clipboard WHERE id = ?1", params![id])?;
+    }
+    tx.commit()?;
+
+    let mut to_delete = Vec::new();
+    for image_id in image_id_set {
+        if !is_image_id_referenced(conn, &image_id)? {
+            to_delete.push(image_id);
+        }
+    }
+
+    Ok(to_delete)
+}
+
 pub fn delete_clipboard_items(ids: &[i64]) -> Result<(), String> {
     if ids.is_empty() {
         return Ok(());
@@ -956,54 +1257,8 @@ pub fn delete_clipboard_items(ids: &[i64]) -> Result<(), String> {
         .into_iter()
         .collect();
 
-    let images_to_delete: Vec<String> = with_connection(|conn| {
-        let mut image_id_set: HashSet<String> = HashSet::new();
-        let mut tombstone_ids = Vec::new();
-        for id in &unique_ids {
-            let item: Option<(Option<String>, Option<String>)> = conn
-                .query_row(
-                    "SELECT image_id, uuid FROM clipboard WHERE id = ?",
-                    params![id],
-                    |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, Option<String>>(1)?)),
-                )
-                .optional()?;
-
-            if let Some((image_ids, uuid)) = item {
-                if let Some(image_ids) = image_ids {
-                    for image_id in split_image_ids(&image_ids) {
-                        image_id_set.insert(image_id);
-                    }
-                }
-                tombstone_ids.push(uuid.filter(|value| !value.trim().is_empty()).unwrap_or_else(|| id.to_string()));
-            }
-        }
-
-        let tx = conn.unchecked_transaction()?;
-        let deleted_at = chrono::Local::now().timestamp();
-        let local_device_id = crate::services::sync_transfer::device_id();
-        for uuid in &tombstone_ids {
-            super::tombstones::record_sync_tombstone_in_conn(
-                &tx,
-                super::tombstones::COLLECTION_HISTORY,
-                uuid,
-                &local_device_id,
-                deleted_at,
-            )?;
-        }
-        for id in &unique_ids {
-            tx.execute("DELETE FROM clipboard WHERE id = ?1", params![id])?;
-        }
-        tx.commit()?;
-
-        let mut to_delete = Vec::new();
-        for image_id in image_id_set {
-            if !is_image_id_referenced(conn, &image_id)? {
-                to_delete.push(image_id);
-            }
-        }
-
-        Ok(to_delete)
-    })?;
+    let local_device_id = crate::services::sync_transfer::device_id();
+    let images_to_delete = with_connection(|conn| delete_clipboard_rows_in_conn(conn, &unique_ids, &local_device_id))?;
 
     for id in &unique_ids {
         let _ = delete_clipboard_data_items("clipboard", &id.to_string());
@@ -1011,6 +1266,72 @@ pub fn delete_clipboard_items(ids: &[i64]) -> Result<(), String> {
     delete_image_files(images_to_delete)
 }
 
+// 删除已过期的剪贴板项（置顶项保留），返回删除数量
+pub fn delete_expired_clipboard_items() -> Result<usize, String> {
+    let now = chrono::Local::now().timestamp();
+    let expired_ids: Vec<i64> = with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id FROM clipboard WHERE expires_at IS NOT NULL AND expires_at <= ?1 AND is_pinned = 0",
+        )?;
+        let ids = stmt
+            .query_map(params![now], |row| row.get::<_, i64>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ids)
+    })?;
+
+    delete_clipboard_items(&expired_ids)?;
+    Ok(expired_ids.len())
+}
+
+// 删除最后更新时间早于 cutoff 的条目，content_type 为 None 时不限类型
+pub fn delete_clipboard_items_older_than(
+    cutoff: i64,
+    content_type: Option<&str>,
+    include_pinned: bool,
+) -> Result<usize, String> {
+    let old_ids = with_connection(|conn| clipboard_ids_older_than_in_conn(conn, cutoff, content_type, include_pinned))?;
+
+    delete_clipboard_items(&old_ids)?;
+    Ok(old_ids.len())
+}
+
+fn clipboard_ids_older_than_in_conn(
+    conn: &rusqlite::Connection,
+    cutoff: i64,
+    content_type: Option<&str>,
+    include_pinned: bool,
+) -> Result<Vec<i64>, rusqlite::Error> {
+    let mut sql = "SELECT id FROM clipboard WHERE updated_at < ?1".to_string();
+    if !include_pinned {
+        sql.push_str(" AND is_pinned = 0");
+    }
+    if content_type.is_some() {
+        sql.push_str(" AND content_type LIKE ?2");
+    }
+
+    let mut stmt = conn.prepare(&sql)?;
+    match content_type {
+        Some(content_type) => stmt
+            .query_map(params![cutoff, format!("%{}%", content_type)], |row| row.get::<_, i64>(0))?
+            .collect(),
+        None => stmt.query_map(params![cutoff], |row| row.get::<_, i64>(0))?.collect(),
+    }
+}
+
+// 设置或清除单个条目的过期时间
+pub fn set_clipboard_item_expiry(id: i64, expires_at: Option<i64>) -> Result<(), String> {
+    with_connection(|conn| {
+        let rows = conn.execute(
+            "UPDATE clipboard SET expires_at = ?1 WHERE id = ?2",
+            params![expires_at, id],
+        )?;
+        if rows == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+        Ok(())
+    })
+}
+
 // 清空所有剪贴板历史
 pub fn clear_clipboard_history() -> Result<(), String> {
     let images_to_delete: Vec<String> = with_connection(|conn| {
@@ -1208,3 +1529,69 @@ pub fn toggle_pin_clipboard_item(id: i64) -> Result<bool, String> {
     })
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        super::super::migrations::run_migrations(&conn, None).unwrap();
+        conn
+    }
+
+    fn insert(conn: &Connection, uuid: &str, content_type: &str, pinned: bool, updated_at: i64) -> i64 {
+        conn.execute(
+            "INSERT INTO clipboard (content, content_type, is_pinned, uuid, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?1, ?4, ?4)",
+            params![uuid, content_type, pinned as i64, updated_at],
+        )
+        .unwrap();
+        conn.last_insert_rowid()
+    }
+
+    #[test]
+    fn retention_deletes_by_age_type_and_pin_state_with_tombstones() {
+        let conn = setup();
+        let now = chrono::Local::now().timestamp();
+        let old_text = insert(&conn, "old-text", "text", false, now - 40 * DAY);
+        let old_rich = insert(&conn, "old-rich", "rich_text", false, now - 40 * DAY);
+        let old_image = insert(&conn, "old-image", "image", false, now - 40 * DAY);
+        let old_pinned = insert(&conn, "old-pinned", "text", true, now - 40 * DAY);
+        insert(&conn, "new-text", "text", false, now - DAY);
+        let cutoff = now - 30 * DAY;
+
+        let mut ids = clipboard_ids_older_than_in_conn(&conn, cutoff, Some("text"), false).unwrap();
+        ids.sort();
+        assert_eq!(ids, vec![old_text, old_rich]);
+        delete_clipboard_rows_in_conn(&conn, &ids, "local").unwrap();
+
+        let mut ids = clipboard_ids_older_than_in_conn(&conn, cutoff, None, true).unwrap();
+        ids.sort();
+        assert_eq!(ids, vec![old_image, old_pinned]);
+        delete_clipboard_rows_in_conn(&conn, &ids, "local").unwrap();
+
+        let remaining: Vec<String> = conn
+            .prepare("SELECT uuid FROM clipboard")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(remaining, vec!["new-text".to_string()]);
+
+        let tombstones: Vec<(String, String)> = conn
+            .prepare("SELECT item_id, source_device_id FROM sync_tombstones WHERE collection = 'history' ORDER BY item_id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let expected = ["old-image", "old-pinned", "old-rich", "old-text"]
+            .map(|uuid| (uuid.to_string(), "local".to_string()));
+        assert_eq!(tombstones, expected.to_vec());
+    }
+}
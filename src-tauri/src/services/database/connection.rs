@@ -1,20 +1,42 @@
-use rusqlite::{Connection, params};
+use rusqlite::Connection;
 use parking_lot::Mutex;
 use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::services::local_encryption::Workspace;
 
 pub const MAX_CONTENT_LENGTH: usize = 1600;
 
+pub const DATABASE_LOCKED_ERROR: &str = "数据已加密锁定，请先输入主密码解锁";
+
 // 数据库连接
 static DB_CONNECTION: Lazy<Mutex<Option<Connection>>> = 
     Lazy::new(|| Mutex::new(None));
 
+// 本地加密解锁期间的内存工作副本，存在时普通的数据库访问都在副本上进行
+// 加锁顺序：WORKSPACE → DB_CONNECTION
+static WORKSPACE: Lazy<Mutex<Option<Workspace>>> =
+    Lazy::new(|| Mutex::new(None));
+
+// 本地加密锁定期间拒绝普通的数据库访问
+static DATABASE_LOCKED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_database_locked(locked: bool) {
+    DATABASE_LOCKED.store(locked, Ordering::SeqCst);
+}
+
+pub fn is_database_locked() -> bool {
+    DATABASE_LOCKED.load(Ordering::SeqCst)
+}
+
 // 初始化数据库连接
 pub fn init_database(db_path: &str) -> Result<(), String> {
     let conn = Connection::open(db_path)
         .map_err(|e| format!("打开数据库失败: {}", e))?;
     
-    // 创建表结构
-    create_tables(&conn)?;
+    // 按版本执行表结构迁移，迁移前备份到数据目录的 backups 下
+    let backup_dir = std::path::Path::new(db_path).parent().map(|dir| dir.join("backups"));
+    super::migrations::run_migrations(&conn, backup_dir.as_deref())?;
 
     conn.execute_batch(
         "PRAGMA foreign_keys = ON;
@@ -24,462 +46,130 @@ pub fn init_database(db_path: &str) -> Result<(), String> {
          PRAGMA temp_store = MEMORY;"
     ).map_err(|e| format!("设置数据库参数失败: {}", e))?;
 
-    // 一次性迁移收藏为全局序号（使用 user_version 控制，仅执行一次）
-    migrate_favorites_global_order_if_needed(&conn)?;
+    // 全文检索索引不可用时不影响启动，搜索会回退到 LIKE
+    // 启用本地加密时磁盘上只有密文，索引建在解锁后的内存副本中
+    let encrypted = crate::services::local_encryption::is_enabled();
+    if !encrypted {
+        if let Err(e) = super::search::ensure_search_index(&conn) {
+            eprintln!("{}", e);
+        }
+    }
     
-    let mut db_conn = DB_CONNECTION.lock();
-    *db_conn = Some(conn);
+    {
+        let mut db_conn = DB_CONNECTION.lock();
+        *db_conn = Some(conn);
+    }
+
+    // 已解锁时为新打开的数据库（如导入或恢复后的数据）重新建立工作副本
+    if encrypted {
+        crate::services::local_encryption::reopen_workspace();
+    }
     
     Ok(())
 }
 
-// 关闭数据库连接
+// 关闭数据库连接，工作副本中的改动先写回磁盘
 pub fn close_database() {
+    let mut workspace = WORKSPACE.lock();
     let mut db_conn = DB_CONNECTION.lock();
+    if let (Some(workspace), Some(conn)) = (workspace.as_ref(), db_conn.as_ref()) {
+        if let Err(e) = workspace.persist(conn) {
+            eprintln!("{}", e);
+        }
+    }
+    *workspace = None;
     if db_conn.is_some() {
         *db_conn = None;
     }
 }
 
-// 创建数据库表
-fn create_tables(conn: &Connection) -> Result<(), String> {
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS clipboard (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            content TEXT NOT NULL,
-            html_content TEXT,
-            content_type TEXT NOT NULL DEFAULT 'text',
-            image_id TEXT,
-            item_order INTEGER NOT NULL DEFAULT 0,
-            is_pinned INTEGER NOT NULL DEFAULT 0,
-            uuid TEXT,
-            source_device_id TEXT,
-            is_remote INTEGER NOT NULL DEFAULT 0,
-            created_at INTEGER NOT NULL,
-            updated_at INTEGER NOT NULL
-        )",
-        [],
-    ).map_err(|e| format!("创建剪贴板表失败: {}", e))?;
-
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS clipboard_data (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            target_kind TEXT NOT NULL,
-            target_id TEXT NOT NULL,
-            format_name TEXT NOT NULL,
-            raw_data BLOB NOT NULL,
-            is_primary INTEGER NOT NULL DEFAULT 0,
-            format_order INTEGER NOT NULL DEFAULT 0,
-            created_at INTEGER NOT NULL,
-            updated_at INTEGER NOT NULL
-        )",
-        [],
-    ).map_err(|e| format!("创建剪贴板原始数据表失败: {}", e))?;
-
-    let pinned_exists = conn
-        .prepare("PRAGMA table_info(clipboard)")
-        .and_then(|mut stmt| {
-            let columns = stmt.query_map([], |row| Ok(row.get::<_, String>(1)?))?
-                .collect::<Result<Vec<_>, _>>()?;
-            Ok(columns.iter().any(|c| c == "is_pinned"))
-        })
-        .unwrap_or(false);
-    
-    if !pinned_exists {
-        conn.execute(
-            "ALTER TABLE clipboard ADD COLUMN is_pinned INTEGER NOT NULL DEFAULT 0",
-            [],
-        ).map_err(|e| format!("添加置顶字段失败: {}", e))?;
-    }
-
-    let paste_count_exists = conn
-        .prepare("PRAGMA table_info(clipboard)")
-        .and_then(|mut stmt| {
-            let columns = stmt.query_map([], |row| Ok(row.get::<_, String>(1)?))?
-                .collect::<Result<Vec<_>, _>>()?;
-            Ok(columns.iter().any(|c| c == "paste_count"))
-        })
-        .unwrap_or(false);
-    
-    if !paste_count_exists {
-        conn.execute(
-            "ALTER TABLE clipboard ADD COLUMN paste_count INTEGER NOT NULL DEFAULT 0",
-            [],
-        ).map_err(|e| format!("添加粘贴次数字段失败: {}", e))?;
-    }
-    
-    migrate_clipboard_order(conn);
-
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS favorites (
-            id TEXT PRIMARY KEY,
-            title TEXT NOT NULL,
-            content TEXT NOT NULL,
-            html_content TEXT,
-            content_type TEXT NOT NULL DEFAULT 'text',
-            image_id TEXT,
-            group_name TEXT NOT NULL DEFAULT '全部',
-            item_order INTEGER NOT NULL DEFAULT 0,
-            created_at INTEGER NOT NULL,
-            updated_at INTEGER NOT NULL
-        )",
-        [],
-    ).map_err(|e| format!("创建收藏表失败: {}", e))?;
-
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS groups (
-            name TEXT PRIMARY KEY,
-            icon TEXT NOT NULL DEFAULT 'ti ti-folder',
-            color TEXT NOT NULL DEFAULT '#dc2626',
-            order_index INTEGER NOT NULL DEFAULT 0,
-            created_at INTEGER NOT NULL,
-            updated_at INTEGER NOT NULL
-        )",
-        [],
-    ).map_err(|e| format!("创建分组表失败: {}", e))?;
-
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS sync_tombstones (
-            collection TEXT NOT NULL,
-            item_id TEXT NOT NULL,
-            source_device_id TEXT NOT NULL,
-            deleted_at INTEGER NOT NULL,
-            created_at INTEGER NOT NULL,
-            PRIMARY KEY (collection, item_id)
-        )",
-        [],
-    ).map_err(|e| format!("创建同步删除状态表失败: {}", e))?;
-
-    let color_exists = conn
-        .prepare("PRAGMA table_info(groups)")
-        .and_then(|mut stmt| {
-            let columns = stmt.query_map([], |row| {
-                Ok(row.get::<_, String>(1)?)
-            })?;
-            Ok(columns.into_iter().any(|col| col.map(|c| c == "color").unwrap_or(false)))
-        })
-        .unwrap_or(false);
-    
-    if !color_exists {
-        conn.execute(
-            "ALTER TABLE groups ADD COLUMN color TEXT NOT NULL DEFAULT '#dc2626'",
-            [],
-        ).map_err(|e| format!("添加颜色字段失败: {}", e))?;
-    }
-
-    let fav_paste_count_exists = conn
-        .prepare("PRAGMA table_info(favorites)")
-        .and_then(|mut stmt| {
-            let columns = stmt.query_map([], |row| Ok(row.get::<_, String>(1)?))?
-                .collect::<Result<Vec<_>, _>>()?;
-            Ok(columns.iter().any(|c| c == "paste_count"))
-        })
-        .unwrap_or(false);
-    
-    if !fav_paste_count_exists {
-        conn.execute(
-            "ALTER TABLE favorites ADD COLUMN paste_count INTEGER NOT NULL DEFAULT 0",
-            [],
-        ).map_err(|e| format!("添加收藏粘贴次数字段失败: {}", e))?;
-    }
-
-    let source_app_exists = conn
-        .prepare("PRAGMA table_info(clipboard)")
-        .and_then(|mut stmt| {
-            let columns = stmt.query_map([], |row| Ok(row.get::<_, String>(1)?))?
-                .collect::<Result<Vec<_>, _>>()?;
-            Ok(columns.iter().any(|c| c == "source_app"))
-        })
-        .unwrap_or(false);
-    
-    if !source_app_exists {
-        conn.execute("ALTER TABLE clipboard ADD COLUMN source_app TEXT", [])
-            .map_err(|e| format!("添加来源应用字段失败: {}", e))?;
-    }
-
-    let source_icon_hash_exists = conn
-        .prepare("PRAGMA table_info(clipboard)")
-        .and_then(|mut stmt| {
-            let columns = stmt.query_map([], |row| Ok(row.get::<_, String>(1)?))?
-                .collect::<Result<Vec<_>, _>>()?;
-            Ok(columns.iter().any(|c| c == "source_icon_hash"))
-        })
-        .unwrap_or(false);
-    
-    if !source_icon_hash_exists {
-        conn.execute("ALTER TABLE clipboard ADD COLUMN source_icon_hash TEXT", [])
-            .map_err(|e| format!("添加来源图标哈希字段失败: {}", e))?;
-    }
-
-    let clip_char_count_exists = conn
-        .prepare("PRAGMA table_info(clipboard)")
-        .and_then(|mut stmt| {
-            let columns = stmt.query_map([], |row| Ok(row.get::<_, String>(1)?))?
-                .collect::<Result<Vec<_>, _>>()?;
-            Ok(columns.iter().any(|c| c == "char_count"))
-        })
-        .unwrap_or(false);
-    
-    if !clip_char_count_exists {
-        conn.execute("ALTER TABLE clipboard ADD COLUMN char_count INTEGER", [])
-            .map_err(|e| format!("添加剪贴板字符数量字段失败: {}", e))?;
-    }
-
-    let clip_uuid_exists = conn
-        .prepare("PRAGMA table_info(clipboard)")
-        .and_then(|mut stmt| {
-            let columns = stmt.query_map([], |row| Ok(row.get::<_, String>(1)?))?
-                .collect::<Result<Vec<_>, _>>()?;
-            Ok(columns.iter().any(|c| c == "uuid"))
-        })
-        .unwrap_or(false);
-
-    if !clip_uuid_exists {
-        conn.execute("ALTER TABLE clipboard ADD COLUMN uuid TEXT", [])
-            .map_err(|e| format!("添加剪贴板 UUID 字段失败: {}", e))?;
-    }
-
-    let clip_source_device_id_exists = conn
-        .prepare("PRAGMA table_info(clipboard)")
-        .and_then(|mut stmt| {
-            let columns = stmt.query_map([], |row| Ok(row.get::<_, String>(1)?))?
-                .collect::<Result<Vec<_>, _>>()?;
-            Ok(columns.iter().any(|c| c == "source_device_id"))
-        })
-        .unwrap_or(false);
-
-    if !clip_source_device_id_exists {
-        conn.execute("ALTER TABLE clipboard ADD COLUMN source_device_id TEXT", [])
-            .map_err(|e| format!("添加剪贴板来源设备字段失败: {}", e))?;
-    }
-
-    let clip_is_remote_exists = conn
-        .prepare("PRAGMA table_info(clipboard)")
-        .and_then(|mut stmt| {
-            let columns = stmt.query_map([], |row| Ok(row.get::<_, String>(1)?))?
-                .collect::<Result<Vec<_>, _>>()?;
-            Ok(columns.iter().any(|c| c == "is_remote"))
-        })
-        .unwrap_or(false);
-
-    if !clip_is_remote_exists {
-        conn.execute("ALTER TABLE clipboard ADD COLUMN is_remote INTEGER NOT NULL DEFAULT 0", [])
-            .map_err(|e| format!("添加剪贴板远端标记字段失败: {}", e))?;
-    }
-
-    let fav_char_count_exists = conn
-        .prepare("PRAGMA table_info(favorites)")
-        .and_then(|mut stmt| {
-            let columns = stmt.query_map([], |row| Ok(row.get::<_, String>(1)?))?
-                .collect::<Result<Vec<_>, _>>()?;
-            Ok(columns.iter().any(|c| c == "char_count"))
-        })
-        .unwrap_or(false);
-    
-    if !fav_char_count_exists {
-        conn.execute("ALTER TABLE favorites ADD COLUMN char_count INTEGER", [])
-            .map_err(|e| format!("添加收藏字符数量字段失败: {}", e))?;
-    }
-
-    let fav_source_device_id_exists = conn
-        .prepare("PRAGMA table_info(favorites)")
-        .and_then(|mut stmt| {
-            let columns = stmt.query_map([], |row| Ok(row.get::<_, String>(1)?))?
-                .collect::<Result<Vec<_>, _>>()?;
-            Ok(columns.iter().any(|c| c == "source_device_id"))
-        })
-        .unwrap_or(false);
-
-    if !fav_source_device_id_exists {
-        conn.execute("ALTER TABLE favorites ADD COLUMN source_device_id TEXT", [])
-            .map_err(|e| format!("添加收藏来源设备字段失败: {}", e))?;
-    }
-
-    let group_source_device_id_exists = conn
-        .prepare("PRAGMA table_info(groups)")
-        .and_then(|mut stmt| {
-            let columns = stmt.query_map([], |row| Ok(row.get::<_, String>(1)?))?
-                .collect::<Result<Vec<_>, _>>()?;
-            Ok(columns.iter().any(|c| c == "source_device_id"))
-        })
-        .unwrap_or(false);
-
-    if !group_source_device_id_exists {
-        conn.execute("ALTER TABLE groups ADD COLUMN source_device_id TEXT", [])
-            .map_err(|e| format!("添加分组来源设备字段失败: {}", e))?;
+// 获取数据库连接
+pub fn with_connection<F, R>(f: F) -> Result<R, String>
+where
+    F: FnOnce(&Connection) -> Result<R, rusqlite::Error>,
+{
+    if is_database_locked() {
+        return Err(DATABASE_LOCKED_ERROR.to_string());
     }
 
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_clipboard_order ON clipboard(is_pinned DESC, item_order DESC, updated_at DESC)",
-        [],
-    ).map_err(|e| format!("创建剪贴板排序索引失败: {}", e))?;
-    
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_clipboard_content_type ON clipboard(content_type)",
-        [],
-    ).map_err(|e| format!("创建内容类型索引失败: {}", e))?;
-
-    conn.execute(
-        "CREATE UNIQUE INDEX IF NOT EXISTS idx_clipboard_data_unique
-         ON clipboard_data(target_kind, target_id, format_name)",
-        [],
-    ).map_err(|e| format!("创建剪贴板原始数据唯一索引失败: {}", e))?;
-
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_clipboard_data_target_order
-         ON clipboard_data(target_kind, target_id, format_order, id)",
-        [],
-    ).map_err(|e| format!("创建剪贴板原始数据索引失败: {}", e))?;
-
-    conn.execute(
-        "CREATE UNIQUE INDEX IF NOT EXISTS idx_clipboard_uuid_unique ON clipboard(uuid) WHERE uuid IS NOT NULL AND uuid <> ''",
-        [],
-    ).map_err(|e| format!("创建剪贴板 UUID 唯一索引失败: {}", e))?;
-
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_favorites_group ON favorites(group_name, item_order)",
-        [],
-    ).map_err(|e| format!("创建收藏索引失败: {}", e))?;
-
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_sync_tombstones_deleted_at ON sync_tombstones(deleted_at)",
-        [],
-    ).map_err(|e| format!("创建同步删除状态索引失败: {}", e))?;
-    migrate_favorites_auto_titles(conn);
-
-    Ok(())
-}
-
-// 迁移 item_order（ASC → DESC）
-pub fn migrate_clipboard_order(conn: &Connection) {
-    let need_migrate: bool = conn.query_row(
-        "SELECT EXISTS(SELECT 1 FROM clipboard WHERE item_order < 0) 
-         OR (SELECT MAX(item_order) FROM clipboard) < (SELECT COUNT(*) FROM clipboard)",
-        [], |row| row.get(0)
-    ).unwrap_or(false);
-    
-    if need_migrate {
-        if let Ok(mut stmt) = conn.prepare(
-            "SELECT id FROM clipboard ORDER BY is_pinned DESC, item_order ASC, updated_at DESC"
-        ) {
-            let ids: Vec<i64> = stmt.query_map([], |row| row.get(0))
-                .map(|rows| rows.filter_map(|r| r.ok()).collect())
-                .unwrap_or_default();
-            let count = ids.len() as i64;
-            for (i, id) in ids.iter().enumerate() {
-                conn.execute("UPDATE clipboard SET item_order = ? WHERE id = ?",
-                    rusqlite::params![count - i as i64, id]).ok();
+    let workspace = WORKSPACE.lock();
+    if let Some(workspace) = workspace.as_ref() {
+        let result = f(workspace.connection()).map_err(|e| format!("数据库操作失败: {}", e));
+        // 写回失败时改动仍记录在副本中，下次访问或锁定时重试
+        if let Some(conn) = DB_CONNECTION.lock().as_ref() {
+            if let Err(e) = workspace.persist(conn) {
+                eprintln!("{}", e);
             }
         }
+        return result;
     }
-}
-
-// 一次性迁移收藏为全局序号
-fn migrate_favorites_global_order_if_needed(conn: &Connection) -> Result<(), String> {
-    let current_version: i32 = conn
-        .query_row("PRAGMA user_version;", [], |row| row.get(0))
-        .unwrap_or(0);
-
-    if current_version >= 1 {
-        return Ok(());
-    }
+    drop(workspace);
 
-    let favorites_table_exists: i64 = conn
-        .query_row(
-            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'favorites'",
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or(0);
-    if favorites_table_exists == 0 {
-        let _ = conn.execute("PRAGMA user_version = 1;", []);
-        return Ok(());
+    let conn_guard = DB_CONNECTION.lock();
+    // 等待连接期间可能已被锁定并丢弃了工作副本
+    if is_database_locked() {
+        return Err(DATABASE_LOCKED_ERROR.to_string());
     }
+    let conn = conn_guard.as_ref()
+        .ok_or("数据库未初始化")?;
+    f(conn).map_err(|e| format!("数据库操作失败: {}", e))
+}
 
-    let favorites_count: i64 = conn
-        .query_row("SELECT COUNT(*) FROM favorites", [], |row| row.get(0))
-        .unwrap_or(0);
-    if favorites_count == 0 {
-        let _ = conn.execute("PRAGMA user_version = 1;", []);
-        return Ok(());
+// 直接访问磁盘上的数据库文件，用于检查点和整库备份等需要与文件内容一致的操作。
+// 启用本地加密时先写回工作副本中的改动，得到的是密文数据库
+pub fn with_database_file<F, R>(f: F) -> Result<R, String>
+where
+    F: FnOnce(&Connection) -> Result<R, rusqlite::Error>,
+{
+    if is_database_locked() {
+        return Err(DATABASE_LOCKED_ERROR.to_string());
     }
-
-    let tx = conn
-        .unchecked_transaction()
-        .map_err(|e| format!("启动收藏序号迁移事务失败: {}", e))?;
-
-    let ids: Vec<String> = {
-        let mut stmt = tx
-            .prepare(
-                "SELECT id FROM favorites 
-                 ORDER BY item_order DESC, updated_at DESC, created_at DESC, id ASC",
-            )
-            .map_err(|e| format!("准备收藏序号迁移查询失败: {}", e))?;
-
-        let ids: Vec<String> = stmt
-            .query_map([], |row| row.get(0))
-            .map_err(|e| format!("查询收藏序号迁移数据失败: {}", e))?
-            .filter_map(|r| r.ok())
-            .collect();
-
-        ids
-    };
-
-    let total = ids.len() as i64;
-    for (idx, id) in ids.iter().enumerate() {
-        let new_order = total - idx as i64;
-        tx.execute(
-            "UPDATE favorites SET item_order = ?1 WHERE id = ?2",
-            params![new_order, id],
-        )
-        .ok();
+    let workspace = WORKSPACE.lock();
+    let conn_guard = DB_CONNECTION.lock();
+    let conn = conn_guard.as_ref()
+        .ok_or("数据库未初始化")?;
+    if let Some(workspace) = workspace.as_ref() {
+        workspace.persist(conn)?;
     }
-
-    tx.commit()
-        .map_err(|e| format!("提交收藏序号迁移事务失败: {}", e))?;
-
-    conn.execute("PRAGMA user_version = 1;", [])
-        .map_err(|e| format!("更新 user_version 失败: {}", e))?;
-
-    Ok(())
+    f(conn).map_err(|e| format!("数据库操作失败: {}", e))
 }
 
-// 获取数据库连接
-pub fn with_connection<F, R>(f: F) -> Result<R, String>
+// 不检查锁定状态、直接访问磁盘数据库，仅供本地加密封存和解封使用
+pub(crate) fn with_connection_ignoring_lock<F, R>(f: F) -> Result<R, String>
 where
-    F: FnOnce(&Connection) -> Result<R, rusqlite::Error>,
+    F: FnOnce(&Connection) -> Result<R, String>,
 {
     let conn_guard = DB_CONNECTION.lock();
     let conn = conn_guard.as_ref()
         .ok_or("数据库未初始化")?;
-    f(conn).map_err(|e| format!("数据库操作失败: {}", e))
+    f(conn)
 }
 
+// 用磁盘数据库建立工作副本，替换已有的副本
+pub(crate) fn open_workspace<F>(open: F) -> Result<(), String>
+where
+    F: FnOnce(&Connection) -> Result<Workspace, String>,
+{
+    let mut workspace = WORKSPACE.lock();
+    let conn_guard = DB_CONNECTION.lock();
+    let conn = conn_guard.as_ref()
+        .ok_or("数据库未初始化")?;
+    if let Some(current) = workspace.as_ref() {
+        current.persist(conn)?;
+    }
+    *workspace = Some(open(conn)?);
+    Ok(())
+}
 
-// 清理文件和图片类型收藏项的自动生成标题
-fn migrate_favorites_auto_titles(conn: &Connection) {
-    if let Ok(mut stmt) = conn.prepare(
-        "SELECT id, title, content FROM favorites WHERE content_type LIKE '%file%' OR content_type LIKE '%image%'"
-    ) {
-        let items: Vec<(String, String, String)> = stmt.query_map([], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-            ))
-        }).map(|rows| rows.filter_map(|r| r.ok()).collect()).unwrap_or_default();
-        
-        for (id, title, content) in items {
-            let content_chars: Vec<char> = content.chars().collect();
-            let expected_title = if content_chars.len() > 50 {
-                format!("{}...", content_chars[..50].iter().collect::<String>())
-            } else {
-                content_chars.iter().collect::<String>()
-            };
-            
-            if title == expected_title {
-                conn.execute("UPDATE favorites SET title = '' WHERE id = ?", [&id]).ok();
-            }
-        }
+// 写回改动后丢弃工作副本；写回失败时保留副本并返回错误
+pub(crate) fn close_workspace() -> Result<(), String> {
+    let mut workspace = WORKSPACE.lock();
+    if let Some(current) = workspace.as_ref() {
+        let conn_guard = DB_CONNECTION.lock();
+        let conn = conn_guard.as_ref()
+            .ok_or("数据库未初始化")?;
+        current.persist(conn)?;
     }
+    *workspace = None;
+    Ok(())
 }
@@ -2,7 +2,10 @@ use std::collections::HashMap;
 
 use super::models::{ClipboardDataSeed, FavoriteItem, PaginatedResult, FavoritesQueryParams};
 use super::connection::{with_connection, MAX_CONTENT_LENGTH};
-use crate::services::webdav_sync::types::{CloudRecord, CloudRecordMeta};
+use super::query::{ParsedQuery, QueryTarget};
+use super::search::{is_search_index_ready, render_snippet, snippet_sql};
+use super::sync_versions::{self, RecordUpsertResult, SyncContent, UpsertPlan, CONFLICT_COPY_SUFFIX};
+use crate::services::webdav_sync::types::{CloudRecord, CloudRecordMeta, CONFLICT_COPIED, CONFLICT_MERGED};
 use crate::utils::{truncate_string, truncate_around_keyword, truncate_html};
 use rusqlite::{params, OptionalExtension};
 use chrono;
@@ -71,17 +74,20 @@ pub fn webdav_list_favorite_records(device_id: &str) -> Result<Vec<CloudRecord>,
                 source_icon_hash: None,
                 created_at: row.get(11)?,
                 updated_at: row.get(12)?,
+                version: Default::default(),
             })
         })?;
 
-        Ok(rows.filter_map(|row| row.ok()).collect())
+        let mut records: Vec<CloudRecord> = rows.filter_map(|row| row.ok()).collect();
+        sync_versions::attach_versions_in_conn(conn, super::tombstones::COLLECTION_FAVORITES, &mut records)?;
+        Ok(records)
     })
 }
 
 pub fn webdav_list_favorite_record_metas() -> Result<Vec<CloudRecordMeta>, String> {
     with_connection(|conn| {
         let mut stmt = conn.prepare(
-            "SELECT id, updated_at, image_id
+            "SELECT id, updated_at, image_id, source_device_id, group_name
              FROM favorites
              ORDER BY item_order DESC, updated_at DESC, id DESC",
         )?;
@@ -91,6 +97,9 @@ pub fn webdav_list_favorite_record_metas() -> Result<Vec<CloudRecordMeta>, Strin
                 uuid: row.get(0)?,
                 updated_at: row.get(1)?,
                 image_id: row.get(2)?,
+                source_device_id: row.get(3)?,
+                is_remote: false,
+                group_name: row.get(4)?,
             })
         })?;
 
@@ -130,10 +139,13 @@ pub fn webdav_get_favorite_record_by_uuid(uuid: &str, device_id: &str) -> Result
                 source_icon_hash: None,
                 created_at: row.get(11)?,
                 updated_at: row.get(12)?,
+                version: Default::default(),
             })
         }).optional()?;
 
-        Ok(record)
+        let mut records: Vec<CloudRecord> = record.into_iter().collect();
+        sync_versions::attach_versions_in_conn(conn, super::tombstones::COLLECTION_FAVORITES, &mut records)?;
+        Ok(records.pop())
     })
 }
 
@@ -165,6 +177,7 @@ pub fn webdav_list_own_favorite_records(device_id: &str) -> Result<Vec<CloudReco
                 source_icon_hash: None,
                 created_at: row.get(10)?,
                 updated_at: row.get(11)?,
+                version: Default::default(),
             })
         })?;
 
@@ -185,22 +198,23 @@ pub fn webdav_favorite_record_states() -> Result<HashMap<String, i64>, String> {
     })
 }
 
-pub fn lan_upsert_favorite_records(records: &[CloudRecord]) -> Result<Vec<CloudRecord>, String> {
+pub fn lan_upsert_favorite_records(records: &[CloudRecord]) -> Result<RecordUpsertResult, String> {
     upsert_favorite_records(records, false)
 }
 
-pub fn webdav_repair_favorite_records(records: &[CloudRecord]) -> Result<Vec<CloudRecord>, String> {
+pub fn webdav_repair_favorite_records(records: &[CloudRecord]) -> Result<RecordUpsertResult, String> {
     upsert_favorite_records(records, true)
 }
 
-fn upsert_favorite_records(records: &[CloudRecord], ignore_tombstones: bool) -> Result<Vec<CloudRecord>, String> {
+fn upsert_favorite_records(records: &[CloudRecord], ignore_tombstones: bool) -> Result<RecordUpsertResult, String> {
     if records.is_empty() {
-        return Ok(Vec::new());
+        return Ok(RecordUpsertResult::default());
     }
+    let device_id = crate::services::sync_transfer::device_id();
 
     with_connection(|conn| {
         let tx = conn.unchecked_transaction()?;
-        let mut changed = Vec::new();
+        let mut result = RecordUpsertResult::default();
 
         for record in records {
             if record.uuid.trim().is_empty() {
@@ -259,20 +273,74 @@ fn upsert_favorite_records(records: &[CloudRecord], ignore_tombstones: bool) ->
                 created_at,
                 updated_at,
             )) = existing {
-                let same = source_device_id == record.source_device_id
-                    && title == record.title
-                    && content == record.content
-                    && html_content == record.html_content
-                    && content_type == record.content_type
-                    && image_id == record.image_id
-                    && group_name == record.group_name
-                    && item_order == record.item_order
-                    && paste_count == record.paste_count
-                    && char_count == record.char_count
-                    && created_at == record.created_at
-                    && updated_at == restored_updated_at;
-
-                if updated_at >= restored_updated_at || same {
+                let local = SyncContent {
+                    title: title.clone(),
+                    content: content.clone(),
+                    html_content: html_content.clone(),
+                };
+                let mut incoming = record.clone();
+                incoming.updated_at = restored_updated_at;
+
+                // None 表示保留本地内容
+                let decision = match sync_versions::plan_upsert(
+                    &tx,
+                    super::tombstones::COLLECTION_FAVORITES,
+                    &local,
+                    &content_type,
+                    record,
+                    &device_id,
+                )? {
+                    UpsertPlan::ByTimestamp(version) => {
+                        let same = source_device_id == record.source_device_id
+                            && title == record.title
+                            && content == record.content
+                            && html_content == record.html_content
+                            && content_type == record.content_type
+                            && image_id == record.image_id
+                            && group_name == record.group_name
+                            && item_order == record.item_order
+                            && paste_count == record.paste_count
+                            && char_count == record.char_count
+                            && created_at == record.created_at
+                            && updated_at == restored_updated_at;
+
+                        if updated_at >= restored_updated_at || same {
+                            if let Some(version) = version {
+                                sync_versions::save_version_in_conn(
+                                    &tx,
+                                    super::tombstones::COLLECTION_FAVORITES,
+                                    &record.uuid,
+                                    &local,
+                                    &version,
+                                    &local,
+                                    &version,
+                                )?;
+                            }
+                            None
+                        } else {
+                            Some((version.unwrap_or_else(|| record.version.clone()), None))
+                        }
+                    }
+                    UpsertPlan::KeepLocal => None,
+                    UpsertPlan::TakeRemote(version) => Some((version, None)),
+                    UpsertPlan::Merged(merged, version) => {
+                        incoming.title = merged.title;
+                        incoming.content = merged.content;
+                        incoming.html_content = merged.html_content;
+                        incoming.char_count = calculate_char_count(&incoming.content, &incoming.content_type);
+                        incoming.paste_count = incoming.paste_count.max(paste_count);
+                        incoming.updated_at = updated_at.max(restored_updated_at) + 1;
+                        let conflict = incoming.conflict_item("favorites", CONFLICT_MERGED, None);
+                        Some((version, Some(conflict)))
+                    }
+                    UpsertPlan::Conflict(version) => {
+                        let copy_id = insert_favorite_conflict_copy(&tx, &record.uuid, &device_id)?;
+                        let conflict = record.conflict_item("favorites", CONFLICT_COPIED, Some(copy_id));
+                        Some((version, Some(conflict)))
+                    }
+                };
+
+                let Some((version, conflict)) = decision else {
                     if tombstone_deleted_at.map(|deleted_at| deleted_at < updated_at).unwrap_or(false) {
                         super::tombstones::delete_sync_tombstone_in_conn(
                             &tx,
@@ -281,7 +349,7 @@ fn upsert_favorite_records(records: &[CloudRecord], ignore_tombstones: bool) ->
                         )?;
                     }
                     continue;
-                }
+                };
 
                 tx.execute(
                     "UPDATE favorites SET
@@ -299,31 +367,40 @@ fn upsert_favorite_records(records: &[CloudRecord], ignore_tombstones: bool) ->
                         updated_at = ?12
                      WHERE id = ?13",
                     params![
-                        record.source_device_id,
-                        record.title,
-                        record.content,
-                        record.html_content,
-                        record.content_type,
-                        record.image_id,
-                        record.group_name,
-                        record.item_order,
-                        record.paste_count,
-                        record.char_count,
-                        record.created_at,
-                        restored_updated_at,
+                        incoming.source_device_id,
+                        incoming.title,
+                        incoming.content,
+                        incoming.html_content,
+                        incoming.content_type,
+                        incoming.image_id,
+                        incoming.group_name,
+                        incoming.item_order,
+                        incoming.paste_count,
+                        incoming.char_count,
+                        incoming.created_at,
+                        incoming.updated_at,
                         record.uuid,
                     ],
                 )?;
-                if tombstone_deleted_at.map(|deleted_at| deleted_at < restored_updated_at).unwrap_or(false) {
+                sync_versions::save_version_in_conn(
+                    &tx,
+                    super::tombstones::COLLECTION_FAVORITES,
+                    &record.uuid,
+                    &SyncContent::of_record(&incoming),
+                    &version,
+                    &SyncContent::of_record(record),
+                    &record.version,
+                )?;
+                if tombstone_deleted_at.map(|deleted_at| deleted_at < incoming.updated_at).unwrap_or(false) {
                     super::tombstones::delete_sync_tombstone_in_conn(
                         &tx,
                         super::tombstones::COLLECTION_FAVORITES,
                         &record.uuid,
                     )?;
                 }
-                let mut changed_record = record.clone();
-                changed_record.updated_at = restored_updated_at;
-                changed.push(changed_record);
+                incoming.version = version;
+                result.changed.push(incoming);
+                result.conflicts.extend(conflict);
                 continue;
             }
 
@@ -348,6 +425,16 @@ fn upsert_favorite_records(records: &[CloudRecord], ignore_tombstones: bool) ->
                     restored_updated_at,
                 ],
             )?;
+            let synced = SyncContent::of_record(record);
+            sync_versions::save_version_in_conn(
+                &tx,
+                super::tombstones::COLLECTION_FAVORITES,
+                &record.uuid,
+                &synced,
+                &record.version,
+                &synced,
+                &record.version,
+            )?;
             if tombstone_deleted_at.map(|deleted_at| deleted_at < restored_updated_at).unwrap_or(false) {
                 super::tombstones::delete_sync_tombstone_in_conn(
                     &tx,
@@ -357,66 +444,117 @@ fn upsert_favorite_records(records: &[CloudRecord], ignore_tombstones: bool) ->
             }
             let mut changed_record = record.clone();
             changed_record.updated_at = restored_updated_at;
-            changed.push(changed_record);
+            result.changed.push(changed_record);
         }
 
         tx.commit()?;
-        Ok(changed)
+        Ok(result)
     })
 }
 
+// 把本地内容另存为一条新的本机收藏，标题注明冲突副本，原始格式数据随之转移
+fn insert_favorite_conflict_copy(
+    conn: &rusqlite::Connection,
+    local_id: &str,
+    device_id: &str,
+) -> Result<String, rusqlite::Error> {
+    let copy_id = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO favorites (
+            id, source_device_id, title, content, html_content, content_type,
+            image_id, group_name, item_order, paste_count, char_count, created_at, updated_at
+         )
+         SELECT ?2, ?3, TRIM(title) || ?4, content, html_content, content_type,
+                image_id, group_name, item_order, paste_count, char_count, created_at, ?5
+         FROM favorites WHERE id = ?1",
+        params![local_id, copy_id, device_id, CONFLICT_COPY_SUFFIX, chrono::Local::now().timestamp()],
+    )?;
+    conn.execute(
+        "UPDATE clipboard_data SET target_id = ?2 WHERE target_kind = 'favorite' AND target_id = ?1",
+        params![local_id, copy_id],
+    )?;
+    Ok(copy_id)
+}
+
 // 分页查询收藏列表
 pub fn query_favorites(params: FavoritesQueryParams) -> Result<PaginatedResult<FavoriteItem>, String> {
-    let search_keyword = params.search.clone();
+    let search_query = match params.search.as_deref() {
+        Some(search) => Some(ParsedQuery::parse(search)).filter(|query| !query.is_empty()),
+        None => None,
+    };
+    let search_keyword = search_query
+        .as_ref()
+        .and_then(|query| query.first_keyword())
+        .map(|keyword| keyword.to_string());
+    let compiled = search_query
+        .as_ref()
+        .map(|query| query.compile(QueryTarget::Favorites, is_search_index_ready()))
+        .unwrap_or_default();
+    let fts_expression = compiled.fts_match.clone();
     
     with_connection(|conn| {
-        let mut where_clauses = vec![];
-        let mut count_params: Vec<Box<dyn rusqlite::ToSql>> = vec![];
+        let mut where_clauses: Vec<String> = vec![];
         let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = vec![];
 
         if let Some(ref group_name) = params.group_name {
             if group_name != "全部" {
-                where_clauses.push("group_name = ?");
-                count_params.push(Box::new(group_name.clone()));
+                where_clauses.push("f.group_name = ?".to_string());
                 query_params.push(Box::new(group_name.clone()));
             }
         }
 
-        if let Some(ref search_query) = search_keyword {
-            if !search_query.is_empty() {
-                where_clauses.push("(title LIKE ? OR content LIKE ? OR html_content LIKE ?)");
-                let search_pattern = format!("%{}%", search_query);
-                count_params.push(Box::new(search_pattern.clone()));
-                count_params.push(Box::new(search_pattern.clone()));
-                count_params.push(Box::new(search_pattern.clone()));
-                query_params.push(Box::new(search_pattern.clone()));
-                query_params.push(Box::new(search_pattern.clone()));
-                query_params.push(Box::new(search_pattern));
+        if let Some(ref expression) = fts_expression {
+            where_clauses.push("favorites_fts MATCH ?".to_string());
+            query_params.push(Box::new(expression.clone()));
+        }
+        for condition in &compiled.conditions {
+            where_clauses.push(condition.sql.clone());
+            for param in &condition.params {
+                query_params.push(Box::new(param.clone()));
             }
         }
 
         if let Some(content_type) = params.content_type {
             if content_type != "all" {
                 let pattern = format!("%{}%", content_type);
-                where_clauses.push("content_type LIKE ?");
-                count_params.push(Box::new(pattern.clone()));
+                where_clauses.push("f.content_type LIKE ?".to_string());
                 query_params.push(Box::new(pattern));
             }
         }
 
+        let from_sql = if fts_expression.is_some() {
+            "FROM favorites f JOIN favorites_fts_rowids m ON m.fav_id = f.id JOIN favorites_fts ON favorites_fts.rowid = m.rowid"
+        } else {
+            "FROM favorites f"
+        };
         let where_sql = if where_clauses.is_empty() {
             String::new()
         } else {
             format!("WHERE {}", where_clauses.join(" AND "))
         };
 
-        let total_count_sql = format!("SELECT COUNT(*) FROM favorites {}", where_sql);
-        let total_count: i64 = conn.query_row(&total_count_sql, rusqlite::params_from_iter(count_params), |row| row.get(0))?;
+        let total_count_sql = format!("SELECT COUNT(*) {} {}", from_sql, where_sql);
+        let total_count: i64 = conn.query_row(
+            &total_count_sql,
+            rusqlite::params_from_iter(query_params.iter().map(|p| p.as_ref())),
+            |row| row.get(0),
+        )?;
+
+        let (snippet_column, order_sql) = if fts_expression.is_some() {
+            let order = if params.order_by_relevance {
+                "bm25(favorites_fts, 10.0, 8.0, 1.0, 5.0), f.updated_at DESC"
+            } else {
+                "f.item_order DESC, f.updated_at DESC"
+            };
+            (snippet_sql("favorites_fts"), order)
+        } else {
+            ("NULL".to_string(), "f.item_order DESC, f.updated_at DESC")
+        };
 
         let query_sql = format!(
-            "SELECT id, title, content, html_content, content_type, image_id, group_name, item_order, paste_count, created_at, updated_at, char_count 
-             FROM favorites {} ORDER BY item_order DESC, updated_at DESC LIMIT ? OFFSET ?",
-            where_sql
+            "SELECT f.id, f.title, f.content, f.html_content, f.content_type, f.image_id, f.group_name, f.item_order, f.paste_count, f.created_at, f.updated_at, f.char_count, {} 
+             {} {} ORDER BY {} LIMIT ? OFFSET ?",
+            snippet_column, from_sql, where_sql, order_sql
         );
 
         query_params.push(Box::new(params.limit));
@@ -426,21 +564,18 @@ pub fn query_favorites(params: FavoritesQueryParams) -> Result<PaginatedResult<F
 
         let mut items_to_update: Vec<(String, String, String)> = vec![];
         
-        let items = stmt.query_map(rusqlite::params_from_iter(query_params), |row| {
+        let items = stmt.query_map(rusqlite::params_from_iter(query_params.iter().map(|p| p.as_ref())), |row| {
             let id: String = row.get(0)?;
             let content: String = row.get(2)?;
             let html_content: Option<String> = row.get(3)?;
             let content_type: String = row.get(4)?;
             let char_count: Option<i64> = row.get(11)?;
+            let search_snippet: Option<String> = row.get(12)?;
 
             let (truncated_content, truncated_html) = if content_type == "text" || content_type == "rich_text" || content_type == "link" {
                 let truncated_content = if content.len() > MAX_CONTENT_LENGTH {
                     if let Some(ref keyword) = search_keyword {
-                        if !keyword.trim().is_empty() {
-                            truncate_around_keyword(content.clone(), keyword, MAX_CONTENT_LENGTH)
-                        } else {
-                            truncate_string(content.clone(), MAX_CONTENT_LENGTH)
-                        }
+                        truncate_around_keyword(content.clone(), keyword, MAX_CONTENT_LENGTH)
                     } else {
                         truncate_string(content.clone(), MAX_CONTENT_LENGTH)
                     }
@@ -480,6 +615,7 @@ pub fn query_favorites(params: FavoritesQueryParams) -> Result<PaginatedResult<F
                 char_count: final_char_count,
                 created_at: row.get(9)?,
                 updated_at: row.get(10)?,
+                search_snippet: search_snippet.map(|snippet| render_snippet(&snippet)),
             }, char_count.is_none() && needs_char_count, id, content, content_type))
         })?
         .collect::<Result<Vec<_>, rusqlite::Error>>()?;
@@ -607,6 +743,7 @@ pub fn get_favorite_by_id_with_limit(id: &str, max_content_length: Option<usize>
                     char_count: final_char_count,
                     created_at: row.get(9)?,
                     updated_at: row.get(10)?,
+                    search_snippet: None,
                 })
             }
         )
@@ -755,6 +892,7 @@ pub fn add_clipboard_to_favorites(clipboard_id: i64, group_name: Option<String>)
             char_count: final_char_count,
             created_at: now,
             updated_at: now,
+            search_snippet: None,
         })
     })?;
 
@@ -777,6 +915,17 @@ pub fn add_clipboard_to_favorites(clipboard_id: i64, group_name: Option<String>)
     Ok(favorite)
 }
 
+// 指定分组中是否已有相同内容的收藏
+pub fn favorite_content_exists(content: &str, group_name: &str) -> Result<bool, String> {
+    with_connection(|conn| {
+        conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM favorites WHERE group_name = ?1 AND content = ?2)",
+            params![group_name, content],
+            |row| row.get(0),
+        )
+    })
+}
+
 // 移动收藏项到指定分组
 pub fn move_favorite_to_group(id: String, group_name: String) -> Result<(), String> {
     with_connection(|conn| {
@@ -936,6 +1085,7 @@ pub fn add_favorite(title: String, content: String, group_name: Option<String>)
             id: id.clone(), title, content, html_content: None,
             content_type: "text".to_string(), image_id: None, group_name,
             item_order: new_order, paste_count: 0, char_count, created_at: now, updated_at: now,
+            search_snippet: None,
         })
     })
 }
@@ -0,0 +1,657 @@
+// 数据库结构迁移
+//
+// 迁移按版本号顺序登记在 MIGRATIONS 中，当前版本记录在 PRAGMA user_version。
+// 每个迁移在独立事务中执行并在同一事务内写入版本号，失败时整体回滚。
+// 已有数据的数据库在执行迁移前先备份到数据目录下的 backups 目录；
+// 数据库版本高于当前程序支持的版本时拒绝打开，避免旧版本程序破坏新结构。
+//
+// 新增结构变更时在末尾追加迁移，不要修改已发布的迁移。
+
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+pub const NEWER_SCHEMA_ERROR: &str = "数据库版本高于当前程序支持的版本";
+
+const BACKUP_PREFIX: &str = "quickclipboard-pre-migration-";
+const BACKUP_KEEP: usize = 3;
+
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    up: fn(&Connection) -> Result<(), rusqlite::Error>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, name: "收藏改为全局序号", up: migrate_favorites_global_order },
+    Migration { version: 2, name: "基线表结构", up: migrate_baseline_schema },
+    Migration { version: 3, name: "同步版本与合并基线", up: migrate_sync_versions },
+    Migration { version: 4, name: "合并基线版本", up: migrate_sync_base_version },
+];
+
+pub fn schema_version(conn: &Connection) -> Result<i32, rusqlite::Error> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+// 执行所有未执行的迁移；backup_dir 为 None 时不备份（内存数据库等）
+pub fn run_migrations(conn: &Connection, backup_dir: Option<&Path>) -> Result<(), String> {
+    apply_migrations(conn, MIGRATIONS, backup_dir)
+}
+
+fn apply_migrations(conn: &Connection, migrations: &[Migration], backup_dir: Option<&Path>) -> Result<(), String> {
+    let current = schema_version(conn).map_err(|e| format!("读取数据库版本失败: {}", e))?;
+    let latest = migrations.last().map(|m| m.version).unwrap_or(0);
+    if current > latest {
+        return Err(format!(
+            "{}（数据库 v{}，程序 v{}），请升级 QuickClipboard 后再打开",
+            NEWER_SCHEMA_ERROR, current, latest
+        ));
+    }
+
+    let pending: Vec<&Migration> = migrations.iter().filter(|m| m.version > current).collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(dir) = backup_dir {
+        if has_user_tables(conn).map_err(|e| format!("读取数据库结构失败: {}", e))? {
+            backup_before_migration(conn, dir, current)?;
+        }
+    }
+
+    for migration in pending {
+        let tx = conn
+            .unchecked_transaction()
+            .map_err(|e| format!("启动迁移事务失败: {}", e))?;
+        (migration.up)(&tx)
+            .and_then(|_| tx.pragma_update(None, "user_version", migration.version))
+            .map_err(|e| format!("数据库迁移 v{}（{}）失败: {}", migration.version, migration.name, e))?;
+        tx.commit()
+            .map_err(|e| format!("提交数据库迁移 v{} 失败: {}", migration.version, e))?;
+    }
+
+    Ok(())
+}
+
+fn has_user_tables(conn: &Connection) -> Result<bool, rusqlite::Error> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%')",
+        [],
+        |row| row.get(0),
+    )
+}
+
+// 用 VACUUM INTO 生成一致的快照，只保留最近几份
+fn backup_before_migration(conn: &Connection, dir: &Path, from_version: i32) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("创建备份目录失败: {}", e))?;
+    let name = format!(
+        "{}v{}-{}.db",
+        BACKUP_PREFIX,
+        from_version,
+        chrono::Local::now().format("%Y%m%d_%H%M%S")
+    );
+    let target = dir.join(name);
+    if target.exists() {
+        let _ = std::fs::remove_file(&target);
+    }
+    let target_str = target.to_str().ok_or("备份路径无效")?;
+    conn.execute("VACUUM INTO ?1", params![target_str])
+        .map_err(|e| format!("迁移前备份数据库失败: {}", e))?;
+
+    let mut backups: Vec<_> = std::fs::read_dir(dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().starts_with(BACKUP_PREFIX))
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .collect();
+    backups.sort_by_key(|b| std::cmp::Reverse(b.0));
+    for (_, path) in backups.into_iter().skip(BACKUP_KEEP) {
+        let _ = std::fs::remove_file(path);
+    }
+    Ok(())
+}
+
+fn table_exists(conn: &Connection, table: &str) -> Result<bool, rusqlite::Error> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+        params![table],
+        |row| row.get(0),
+    )
+}
+
+fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(columns.iter().any(|c| c == column))
+}
+
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<(), rusqlite::Error> {
+    if !column_exists(conn, table, column)? {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+    Ok(())
+}
+
+// v1：收藏改为全局序号（新库中收藏表尚未创建，直接跳过）
+fn migrate_favorites_global_order(conn: &Connection) -> Result<(), rusqlite::Error> {
+    if !table_exists(conn, "favorites")? {
+        return Ok(());
+    }
+
+    let ids: Vec<String> = {
+        let mut stmt = conn.prepare(
+            "SELECT id FROM favorites
+             ORDER BY item_order DESC, updated_at DESC, created_at DESC, id ASC",
+        )?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        ids
+    };
+
+    let total = ids.len() as i64;
+    for (idx, id) in ids.iter().enumerate() {
+        conn.execute(
+            "UPDATE favorites SET item_order = ?1 WHERE id = ?2",
+            params![total - idx as i64, id],
+        )?;
+    }
+    Ok(())
+}
+
+// v2：基线表结构
+// 此前表结构靠启动时逐个检查字段补齐，历史数据库可能缺少其中任意字段，
+// 因此这里保留按需补字段的写法
+fn migrate_baseline_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS clipboard (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            content TEXT NOT NULL,
+            html_content TEXT,
+            content_type TEXT NOT NULL DEFAULT 'text',
+            image_id TEXT,
+            item_order INTEGER NOT NULL DEFAULT 0,
+            is_pinned INTEGER NOT NULL DEFAULT 0,
+            uuid TEXT,
+            source_device_id TEXT,
+            is_remote INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS clipboard_data (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            target_kind TEXT NOT NULL,
+            target_id TEXT NOT NULL,
+            format_name TEXT NOT NULL,
+            raw_data BLOB NOT NULL,
+            is_primary INTEGER NOT NULL DEFAULT 0,
+            format_order INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    add_column_if_missing(conn, "clipboard", "is_pinned", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "clipboard", "paste_count", "INTEGER NOT NULL DEFAULT 0")?;
+
+    migrate_clipboard_order(conn)?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS favorites (
+            id TEXT PRIMARY KEY,
+            title TEXT NOT NULL,
+            content TEXT NOT NULL,
+            html_content TEXT,
+            content_type TEXT NOT NULL DEFAULT 'text',
+            image_id TEXT,
+            group_name TEXT NOT NULL DEFAULT '全部',
+            item_order INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS groups (
+            name TEXT PRIMARY KEY,
+            icon TEXT NOT NULL DEFAULT 'ti ti-folder',
+            color TEXT NOT NULL DEFAULT '#dc2626',
+            order_index INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sync_tombstones (
+            collection TEXT NOT NULL,
+            item_id TEXT NOT NULL,
+            source_device_id TEXT NOT NULL,
+            deleted_at INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            PRIMARY KEY (collection, item_id)
+        )",
+        [],
+    )?;
+
+    add_column_if_missing(conn, "groups", "color", "TEXT NOT NULL DEFAULT '#dc2626'")?;
+    add_column_if_missing(conn, "favorites", "paste_count", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "clipboard", "source_app", "TEXT")?;
+    add_column_if_missing(conn, "clipboard", "source_icon_hash", "TEXT")?;
+    add_column_if_missing(conn, "clipboard", "char_count", "INTEGER")?;
+    add_column_if_missing(conn, "clipboard", "uuid", "TEXT")?;
+    add_column_if_missing(conn, "clipboard", "source_device_id", "TEXT")?;
+    add_column_if_missing(conn, "clipboard", "is_remote", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "clipboard", "expires_at", "INTEGER")?;
+    add_column_if_missing(conn, "favorites", "char_count", "INTEGER")?;
+    add_column_if_missing(conn, "favorites", "source_device_id", "TEXT")?;
+    add_column_if_missing(conn, "groups", "source_device_id", "TEXT")?;
+
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_clipboard_order ON clipboard(is_pinned DESC, item_order DESC, updated_at DESC);
+         CREATE INDEX IF NOT EXISTS idx_clipboard_content_type ON clipboard(content_type);
+         CREATE INDEX IF NOT EXISTS idx_clipboard_expires_at ON clipboard(expires_at) WHERE expires_at IS NOT NULL;
+         CREATE UNIQUE INDEX IF NOT EXISTS idx_clipboard_data_unique
+            ON clipboard_data(target_kind, target_id, format_name);
+         CREATE INDEX IF NOT EXISTS idx_clipboard_data_target_order
+            ON clipboard_data(target_kind, target_id, format_order, id);
+         CREATE UNIQUE INDEX IF NOT EXISTS idx_clipboard_uuid_unique ON clipboard(uuid) WHERE uuid IS NOT NULL AND uuid <> '';
+         CREATE INDEX IF NOT EXISTS idx_favorites_group ON favorites(group_name, item_order);
+         CREATE INDEX IF NOT EXISTS idx_sync_tombstones_deleted_at ON sync_tombstones(deleted_at);",
+    )?;
+
+    migrate_favorites_auto_titles(conn)?;
+
+    Ok(())
+}
+
+// v3：同步版本与合并基线，条目删除时一并清理
+fn migrate_sync_versions(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS sync_versions (
+            collection TEXT NOT NULL,
+            item_id TEXT NOT NULL,
+            fingerprint TEXT NOT NULL,
+            version TEXT NOT NULL DEFAULT '{}',
+            base_title TEXT,
+            base_content TEXT,
+            base_html_content TEXT,
+            updated_at INTEGER NOT NULL,
+            PRIMARY KEY (collection, item_id)
+        );
+        CREATE TRIGGER IF NOT EXISTS sync_versions_clipboard_ad AFTER DELETE ON clipboard BEGIN
+            DELETE FROM sync_versions WHERE collection = 'history'
+                AND item_id = COALESCE(NULLIF(old.uuid, ''), CAST(old.id AS TEXT));
+        END;
+        CREATE TRIGGER IF NOT EXISTS sync_versions_favorites_ad AFTER DELETE ON favorites BEGIN
+            DELETE FROM sync_versions WHERE collection = 'favorites' AND item_id = old.id;
+        END;",
+    )
+}
+
+// v4：记录合并基线对应的版本，已有基线视为空版本，保持原来的合并行为
+fn migrate_sync_base_version(conn: &Connection) -> Result<(), rusqlite::Error> {
+    add_column_if_missing(conn, "sync_versions", "base_version", "TEXT")
+}
+
+// 迁移 item_order（ASC → DESC）
+fn migrate_clipboard_order(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let need_migrate: bool = conn.query_row(
+        "SELECT COALESCE(EXISTS(SELECT 1 FROM clipboard WHERE item_order < 0)
+         OR (SELECT MAX(item_order) FROM clipboard) < (SELECT COUNT(*) FROM clipboard), 0)",
+        [],
+        |row| row.get(0),
+    )?;
+    if !need_migrate {
+        return Ok(());
+    }
+
+    let ids: Vec<i64> = {
+        let mut stmt = conn.prepare(
+            "SELECT id FROM clipboard ORDER BY is_pinned DESC, item_order ASC, updated_at DESC",
+        )?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        ids
+    };
+    let count = ids.len() as i64;
+    for (i, id) in ids.iter().enumerate() {
+        conn.execute(
+            "UPDATE clipboard SET item_order = ?1 WHERE id = ?2",
+            params![count - i as i64, id],
+        )?;
+    }
+    Ok(())
+}
+
+// 清理文件和图片类型收藏项的自动生成标题
+fn migrate_favorites_auto_titles(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let items: Vec<(String, String, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT id, title, content FROM favorites WHERE content_type LIKE '%file%' OR content_type LIKE '%image%'",
+        )?;
+        let items = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        items
+    };
+
+    for (id, title, content) in items {
+        let content_chars: Vec<char> = content.chars().collect();
+        let expected_title = if content_chars.len() > 50 {
+            format!("{}...", content_chars[..50].iter().collect::<String>())
+        } else {
+            content_chars.iter().collect::<String>()
+        };
+
+        if title == expected_title {
+            conn.execute("UPDATE favorites SET title = '' WHERE id = ?1", params![id])?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LATEST_SCHEMA_VERSION: i32 = 4;
+
+    // (字段名, 类型, 非空, 默认值, 主键序号)
+    type ColumnInfo = (String, String, bool, Option<String>, i64);
+
+    fn columns(conn: &Connection, table: &str) -> Vec<ColumnInfo> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table)).unwrap();
+        let columns = stmt
+            .query_map([], |row| Ok((row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)))
+            .unwrap();
+        let mut columns = columns.collect::<Result<Vec<ColumnInfo>, _>>().unwrap();
+        // 历史数据库中后加的字段排在末尾，不比较字段顺序
+        columns.sort();
+        columns
+    }
+
+    fn schema_objects(conn: &Connection, kind: &str) -> Vec<(String, String)> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT name, tbl_name FROM sqlite_master
+                 WHERE type = ?1 AND name NOT LIKE 'sqlite_%' ORDER BY name",
+            )
+            .unwrap();
+        let names = stmt.query_map([kind], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+        names.collect::<Result<Vec<_>, _>>().unwrap()
+    }
+
+    fn assert_matches_latest(conn: &Connection) {
+        let fresh = Connection::open_in_memory().unwrap();
+        run_migrations(&fresh, None).unwrap();
+        assert_eq!(schema_version(conn).unwrap(), LATEST_SCHEMA_VERSION);
+        assert_eq!(schema_objects(conn, "table"), schema_objects(&fresh, "table"));
+        for (table, _) in schema_objects(&fresh, "table") {
+            assert_eq!(columns(conn, &table), columns(&fresh, &table), "表 {} 结构不一致", table);
+        }
+        assert_eq!(schema_objects(conn, "index"), schema_objects(&fresh, "index"));
+        assert_eq!(schema_objects(conn, "trigger"), schema_objects(&fresh, "trigger"));
+    }
+
+    #[test]
+    fn latest_version_matches_registry() {
+        assert_eq!(MIGRATIONS.last().unwrap().version, LATEST_SCHEMA_VERSION);
+        assert!(MIGRATIONS.windows(2).all(|w| w[0].version + 1 == w[1].version));
+    }
+
+    // 最早的版本：没有置顶、粘贴次数、分组颜色等字段，也没有原始格式和删除记录表
+    #[test]
+    fn migrates_original_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE clipboard (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, content TEXT NOT NULL, html_content TEXT,
+                content_type TEXT NOT NULL DEFAULT 'text', image_id TEXT,
+                item_order INTEGER NOT NULL DEFAULT 0, created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL);
+             CREATE TABLE favorites (
+                id TEXT PRIMARY KEY, title TEXT NOT NULL, content TEXT NOT NULL, html_content TEXT,
+                content_type TEXT NOT NULL DEFAULT 'text', image_id TEXT, group_name TEXT NOT NULL DEFAULT '全部',
+                item_order INTEGER NOT NULL DEFAULT 0, created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL);
+             CREATE TABLE groups (
+                name TEXT PRIMARY KEY, icon TEXT NOT NULL DEFAULT 'ti ti-folder',
+                order_index INTEGER NOT NULL DEFAULT 0, created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL);
+             INSERT INTO clipboard (content, item_order, created_at, updated_at) VALUES ('a', 0, 1, 1), ('b', 1, 2, 2);
+             INSERT INTO favorites (id, title, content, group_name, item_order, created_at, updated_at) VALUES
+                ('f1', 't1', 'c1', 'g1', 0, 1, 1), ('f2', 't2', 'c2', 'g2', 0, 2, 2);",
+        )
+        .unwrap();
+
+        run_migrations(&conn, None).unwrap();
+        assert_matches_latest(&conn);
+
+        let order: Vec<(String, i64)> = conn
+            .prepare("SELECT id, item_order FROM favorites ORDER BY item_order DESC")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(order, vec![("f2".to_string(), 2), ("f1".to_string(), 1)]);
+    }
+
+    // 已执行收藏序号迁移、但缺少同步相关字段的版本
+    #[test]
+    fn migrates_version_one_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE clipboard (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, content TEXT NOT NULL, html_content TEXT,
+                content_type TEXT NOT NULL DEFAULT 'text', image_id TEXT,
+                item_order INTEGER NOT NULL DEFAULT 0, is_pinned INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL,
+                paste_count INTEGER NOT NULL DEFAULT 0, source_app TEXT, source_icon_hash TEXT, char_count INTEGER);
+             CREATE TABLE favorites (
+                id TEXT PRIMARY KEY, title TEXT NOT NULL, content TEXT NOT NULL, html_content TEXT,
+                content_type TEXT NOT NULL DEFAULT 'text', image_id TEXT, group_name TEXT NOT NULL DEFAULT '全部',
+                item_order INTEGER NOT NULL DEFAULT 0, created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL,
+                paste_count INTEGER NOT NULL DEFAULT 0, char_count INTEGER);
+             CREATE TABLE groups (
+                name TEXT PRIMARY KEY, icon TEXT NOT NULL DEFAULT 'ti ti-folder', color TEXT NOT NULL DEFAULT '#dc2626',
+                order_index INTEGER NOT NULL DEFAULT 0, created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL);
+             INSERT INTO favorites (id, title, content, item_order, created_at, updated_at) VALUES
+                ('f1', 't1', 'c1', 5, 1, 1), ('f2', 't2', 'c2', 9, 2, 2);
+             PRAGMA user_version = 1;",
+        )
+        .unwrap();
+
+        run_migrations(&conn, None).unwrap();
+        assert_matches_latest(&conn);
+
+        let order: i64 = conn
+            .query_row("SELECT item_order FROM favorites WHERE id = 'f1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(order, 5);
+    }
+
+    // 加入原始格式表之前：已有置顶、粘贴次数、来源应用等字段，没有 clipboard_data 和同步相关字段
+    #[test]
+    fn migrates_schema_before_clipboard_data() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE clipboard (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, content TEXT NOT NULL, html_content TEXT,
+                content_type TEXT NOT NULL DEFAULT 'text', image_id TEXT,
+                item_order INTEGER NOT NULL DEFAULT 0, is_pinned INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL,
+                paste_count INTEGER NOT NULL DEFAULT 0, source_app TEXT, source_icon_hash TEXT);
+             CREATE TABLE favorites (
+                id TEXT PRIMARY KEY, title TEXT NOT NULL, content TEXT NOT NULL, html_content TEXT,
+                content_type TEXT NOT NULL DEFAULT 'text', image_id TEXT, group_name TEXT NOT NULL DEFAULT '全部',
+                item_order INTEGER NOT NULL DEFAULT 0, created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL,
+                paste_count INTEGER NOT NULL DEFAULT 0);
+             CREATE TABLE groups (
+                name TEXT PRIMARY KEY, icon TEXT NOT NULL DEFAULT 'ti ti-folder', color TEXT NOT NULL DEFAULT '#dc2626',
+                order_index INTEGER NOT NULL DEFAULT 0, created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL);
+             CREATE INDEX idx_clipboard_order ON clipboard(is_pinned DESC, item_order DESC, updated_at DESC);
+             INSERT INTO clipboard (content, item_order, is_pinned, created_at, updated_at, paste_count)
+                VALUES ('a', 2, 1, 1, 1, 3), ('b', 1, 0, 2, 2, 0);
+             INSERT INTO favorites (id, title, content, item_order, created_at, updated_at) VALUES ('f1', 't1', 'c1', 1, 1, 1);
+             PRAGMA user_version = 1;",
+        )
+        .unwrap();
+
+        run_migrations(&conn, None).unwrap();
+        assert_matches_latest(&conn);
+
+        let (pinned, paste_count, is_remote): (i64, i64, i64) = conn
+            .query_row("SELECT is_pinned, paste_count, is_remote FROM clipboard WHERE content = 'a'", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap();
+        assert_eq!((pinned, paste_count, is_remote), (1, 3, 0));
+    }
+
+    // 加入同步之前：已有原始格式表，缺少 uuid、来源设备等字段和删除记录表
+    #[test]
+    fn migrates_schema_before_sync_columns() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE clipboard (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, content TEXT NOT NULL, html_content TEXT,
+                content_type TEXT NOT NULL DEFAULT 'text', image_id TEXT,
+                item_order INTEGER NOT NULL DEFAULT 0, is_pinned INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL,
+                paste_count INTEGER NOT NULL DEFAULT 0, source_app TEXT, source_icon_hash TEXT, char_count INTEGER);
+             CREATE TABLE clipboard_data (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, target_kind TEXT NOT NULL, target_id TEXT NOT NULL,
+                format_name TEXT NOT NULL, raw_data BLOB NOT NULL, is_primary INTEGER NOT NULL DEFAULT 0,
+                format_order INTEGER NOT NULL DEFAULT 0, created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL);
+             CREATE TABLE favorites (
+                id TEXT PRIMARY KEY, title TEXT NOT NULL, content TEXT NOT NULL, html_content TEXT,
+                content_type TEXT NOT NULL DEFAULT 'text', image_id TEXT, group_name TEXT NOT NULL DEFAULT '全部',
+                item_order INTEGER NOT NULL DEFAULT 0, created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL,
+                paste_count INTEGER NOT NULL DEFAULT 0, char_count INTEGER);
+             CREATE TABLE groups (
+                name TEXT PRIMARY KEY, icon TEXT NOT NULL DEFAULT 'ti ti-folder', color TEXT NOT NULL DEFAULT '#dc2626',
+                order_index INTEGER NOT NULL DEFAULT 0, created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL);
+             CREATE UNIQUE INDEX idx_clipboard_data_unique ON clipboard_data(target_kind, target_id, format_name);
+             INSERT INTO clipboard (content, item_order, created_at, updated_at) VALUES ('a', 1, 1, 1);
+             INSERT INTO clipboard_data (target_kind, target_id, format_name, raw_data, created_at, updated_at)
+                VALUES ('clipboard', '1', 'HTML Format', x'3c623e', 1, 1);
+             PRAGMA user_version = 1;",
+        )
+        .unwrap();
+
+        run_migrations(&conn, None).unwrap();
+        assert_matches_latest(&conn);
+
+        let raw_formats: i64 = conn.query_row("SELECT COUNT(*) FROM clipboard_data", [], |row| row.get(0)).unwrap();
+        assert_eq!(raw_formats, 1);
+        let uuid: Option<String> = conn.query_row("SELECT uuid FROM clipboard", [], |row| row.get(0)).unwrap();
+        assert_eq!(uuid, None);
+    }
+
+    // 加入过期时间之前：同步字段齐全，缺少 expires_at 及其索引，版本号仍是 1
+    #[test]
+    fn migrates_schema_before_expires_at() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE clipboard (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, content TEXT NOT NULL, html_content TEXT,
+                content_type TEXT NOT NULL DEFAULT 'text', image_id TEXT,
+                item_order INTEGER NOT NULL DEFAULT 0, is_pinned INTEGER NOT NULL DEFAULT 0,
+                uuid TEXT, source_device_id TEXT, is_remote INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL,
+                paste_count INTEGER NOT NULL DEFAULT 0, source_app TEXT, source_icon_hash TEXT, char_count INTEGER);
+             CREATE TABLE clipboard_data (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, target_kind TEXT NOT NULL, target_id TEXT NOT NULL,
+                format_name TEXT NOT NULL, raw_data BLOB NOT NULL, is_primary INTEGER NOT NULL DEFAULT 0,
+                format_order INTEGER NOT NULL DEFAULT 0, created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL);
+             CREATE TABLE favorites (
+                id TEXT PRIMARY KEY, title TEXT NOT NULL, content TEXT NOT NULL, html_content TEXT,
+                content_type TEXT NOT NULL DEFAULT 'text', image_id TEXT, group_name TEXT NOT NULL DEFAULT '全部',
+                item_order INTEGER NOT NULL DEFAULT 0, created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL,
+                paste_count INTEGER NOT NULL DEFAULT 0, char_count INTEGER, source_device_id TEXT);
+             CREATE TABLE groups (
+                name TEXT PRIMARY KEY, icon TEXT NOT NULL DEFAULT 'ti ti-folder', color TEXT NOT NULL DEFAULT '#dc2626',
+                order_index INTEGER NOT NULL DEFAULT 0, created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL,
+                source_device_id TEXT);
+             CREATE TABLE sync_tombstones (
+                collection TEXT NOT NULL, item_id TEXT NOT NULL, source_device_id TEXT NOT NULL,
+                deleted_at INTEGER NOT NULL, created_at INTEGER NOT NULL, PRIMARY KEY (collection, item_id));
+             CREATE INDEX idx_clipboard_order ON clipboard(is_pinned DESC, item_order DESC, updated_at DESC);
+             CREATE INDEX idx_clipboard_content_type ON clipboard(content_type);
+             CREATE UNIQUE INDEX idx_clipboard_data_unique ON clipboard_data(target_kind, target_id, format_name);
+             CREATE INDEX idx_clipboard_data_target_order ON clipboard_data(target_kind, target_id, format_order, id);
+             CREATE UNIQUE INDEX idx_clipboard_uuid_unique ON clipboard(uuid) WHERE uuid IS NOT NULL AND uuid <> '';
+             CREATE INDEX idx_favorites_group ON favorites(group_name, item_order);
+             CREATE INDEX idx_sync_tombstones_deleted_at ON sync_tombstones(deleted_at);
+             INSERT INTO clipboard (content, item_order, uuid, source_device_id, is_remote, created_at, updated_at)
+                VALUES ('a', 1, 'u1', 'laptop', 1, 1, 1);
+             INSERT INTO sync_tombstones VALUES ('history', 'u0', 'laptop', 5, 5);
+             PRAGMA user_version = 1;",
+        )
+        .unwrap();
+
+        run_migrations(&conn, None).unwrap();
+        assert_matches_latest(&conn);
+
+        let (uuid, expires_at): (String, Option<i64>) = conn
+            .query_row("SELECT uuid, expires_at FROM clipboard", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert_eq!((uuid.as_str(), expires_at), ("u1", None));
+        let tombstones: i64 = conn.query_row("SELECT COUNT(*) FROM sync_tombstones", [], |row| row.get(0)).unwrap();
+        assert_eq!(tombstones, 1);
+    }
+
+    #[test]
+    fn latest_schema_is_left_untouched() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn, None).unwrap();
+        run_migrations(&conn, None).unwrap();
+        assert_matches_latest(&conn);
+    }
+
+    #[test]
+    fn refuses_newer_database() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&format!("PRAGMA user_version = {};", LATEST_SCHEMA_VERSION + 1)).unwrap();
+        let err = run_migrations(&conn, None).unwrap_err();
+        assert!(err.starts_with(NEWER_SCHEMA_ERROR));
+    }
+
+    #[test]
+    fn failed_migration_rolls_back_and_keeps_backup() {
+        fn create(conn: &Connection) -> Result<(), rusqlite::Error> {
+            conn.execute("CREATE TABLE t (v INTEGER)", []).map(|_| ())
+        }
+        fn broken(conn: &Connection) -> Result<(), rusqlite::Error> {
+            conn.execute("INSERT INTO t (v) VALUES (1)", [])?;
+            conn.execute("INSERT INTO missing (v) VALUES (1)", []).map(|_| ())
+        }
+        let migrations = [
+            Migration { version: 1, name: "create", up: create },
+            Migration { version: 2, name: "broken", up: broken },
+        ];
+
+        let dir = std::env::temp_dir().join(format!("qc-migrations-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute("CREATE TABLE existing (v INTEGER)", []).unwrap();
+
+        assert!(apply_migrations(&conn, &migrations, Some(&dir)).is_err());
+        assert_eq!(schema_version(&conn).unwrap(), 1);
+        let rows: i64 = conn.query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0)).unwrap();
+        assert_eq!(rows, 0);
+        let backups = std::fs::read_dir(&dir).unwrap().count();
+        assert_eq!(backups, 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
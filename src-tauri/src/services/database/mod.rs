@@ -1,9 +1,14 @@
 mod models;
 pub mod connection;
+pub mod migrations;
 pub mod clipboard;
 pub mod favorites;
 pub mod groups;
 pub mod tombstones;
+pub mod sync_versions;
+pub mod search;
+mod query;
+mod pattern;
 
 pub use models::*;
 pub use connection::init_database;
@@ -11,6 +16,7 @@ pub use clipboard::*;
 pub use favorites::*;
 pub use groups::*;
 pub use tombstones::*;
+pub use sync_versions::{record_sync_bases, sync_version_states, RecordUpsertResult};
 
 pub fn webdav_local_sync_parts_signature() -> Result<WebdavLocalSyncSignature, String> {
     connection::with_connection(|conn| {
@@ -26,6 +26,12 @@ pub struct ClipboardItem {
     pub char_count: Option<i64>,
     pub created_at: i64,  
     pub updated_at: i64, 
+    // 过期时间（秒级时间戳），到期后由后台清理
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    // 搜索命中片段（已转义的 HTML，命中部分以 <mark> 包裹）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_snippet: Option<String>,
 }
 
 // 剪贴板原始格式数据
@@ -80,6 +86,9 @@ pub struct FavoriteItem {
     pub char_count: Option<i64>,
     pub created_at: i64,  
     pub updated_at: i64, 
+    // 搜索命中片段（已转义的 HTML，命中部分以 <mark> 包裹）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_snippet: Option<String>,
 }
 
 // 分组信息
@@ -121,6 +130,19 @@ impl<T> PaginatedResult<T> {
     }
 }
 
+// 搜索模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    // 关键词（支持查询语法）
+    #[default]
+    Text,
+    // 正则表达式
+    Regex,
+    // 模糊匹配（容忍拼写错误，按匹配度排序）
+    Fuzzy,
+}
+
 // 查询参数
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryParams {
@@ -134,6 +156,12 @@ pub struct QueryParams {
     // 内容类型过滤（可选）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content_type: Option<String>,
+    // 按搜索相关度排序（仅全文检索生效）
+    #[serde(default)]
+    pub order_by_relevance: bool,
+    // 搜索模式
+    #[serde(default)]
+    pub search_mode: SearchMode,
 }
 
 impl Default for QueryParams {
@@ -143,6 +171,8 @@ impl Default for QueryParams {
             limit: 50,
             search: None,
             content_type: None,
+            order_by_relevance: false,
+            search_mode: SearchMode::Text,
         }
     }
 }
@@ -163,6 +193,9 @@ pub struct FavoritesQueryParams {
     // 内容类型过滤（可选）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content_type: Option<String>,
+    // 按搜索相关度排序（仅全文检索生效）
+    #[serde(default)]
+    pub order_by_relevance: bool,
 }
 
 impl Default for FavoritesQueryParams {
@@ -173,6 +206,7 @@ impl Default for FavoritesQueryParams {
             group_name: None,
             search: None,
             content_type: None,
+            order_by_relevance: false,
         }
     }
 }
@@ -0,0 +1,202 @@
+// 正则 / 模糊搜索
+//
+// 这两种模式无法用 SQL 表达，由调用方先用字段过滤在 SQL 中缩小范围，再逐条读取文本内容后在内存中匹配。
+// 只匹配纯文本 content：html_content 是同一内容的富文本形式，其中的标签和属性不参与匹配。
+// 为保证逐字输入时的响应速度，只扫描最近的 PATTERN_SCAN_ROWS 条记录，每条只取开头 PATTERN_SCAN_CHARS 个字符。
+
+use regex::{Regex, RegexBuilder};
+
+use super::models::SearchMode;
+use super::search::render_snippet;
+use crate::utils::truncate_around_keyword;
+
+// 每条内容参与匹配的最大长度（字符数），避免超长文本拖慢整体搜索
+pub(crate) const PATTERN_SCAN_CHARS: usize = 20_000;
+
+// 参与匹配的最大记录数（按列表顺序取最近的记录）
+pub(crate) const PATTERN_SCAN_ROWS: usize = 5_000;
+
+// 命中文本用于截取上下文时的最大长度（字符数）
+const MAX_KEYWORD_CHARS: usize = 64;
+
+// 搜索结果片段长度（字节）
+const SNIPPET_LENGTH: usize = 160;
+
+// 单条内容的匹配结果（字节区间）
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PatternMatch {
+    pub start: usize,
+    pub end: usize,
+    pub score: f64,
+}
+
+pub(crate) enum PatternMatcher {
+    Regex(Regex),
+    Fuzzy(Vec<char>),
+}
+
+impl PatternMatcher {
+    // 文本模式或空关键词返回 None
+    pub fn new(mode: SearchMode, pattern: &str) -> Result<Option<Self>, String> {
+        if pattern.trim().is_empty() {
+            return Ok(None);
+        }
+        match mode {
+            SearchMode::Text => Ok(None),
+            SearchMode::Regex => RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .map(|regex| Some(PatternMatcher::Regex(regex)))
+                .map_err(|e| format!("无效的正则表达式: {}", e)),
+            SearchMode::Fuzzy => Ok(Some(PatternMatcher::Fuzzy(
+                pattern.trim().chars().map(fold_char).collect(),
+            ))),
+        }
+    }
+
+    // 模糊匹配按匹配度排序，正则保持默认顺序
+    pub fn is_ranked(&self) -> bool {
+        matches!(self, PatternMatcher::Fuzzy(_))
+    }
+
+    pub fn find(&self, text: &str) -> Option<PatternMatch> {
+        match self {
+            PatternMatcher::Regex(regex) => regex.find(text).map(|m| PatternMatch {
+                start: m.start(),
+                end: m.end(),
+                score: 1.0,
+            }),
+            PatternMatcher::Fuzzy(pattern) => fuzzy_find(pattern, text),
+        }
+    }
+}
+
+fn fold_char(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+// 允许的编辑距离：短词必须精确，之后每 4 个字符容忍一处错误
+fn max_typos(pattern_len: usize) -> usize {
+    pattern_len / 4
+}
+
+// 近似子串匹配（Sellers 算法）：在文本任意位置寻找与关键词编辑距离最小的片段
+fn fuzzy_find(pattern: &[char], text: &str) -> Option<PatternMatch> {
+    let m = pattern.len();
+    if m == 0 {
+        return None;
+    }
+    let max_distance = max_typos(m);
+
+    let offsets: Vec<usize> = text
+        .char_indices()
+        .map(|(offset, _)| offset)
+        .take(PATTERN_SCAN_CHARS)
+        .collect();
+    let byte_end = |index: usize| offsets.get(index).copied().unwrap_or(text.len());
+
+    // 每一列保存 (编辑距离, 对齐起点的字符下标)
+    let mut column: Vec<(usize, usize)> = (0..=m).map(|i| (i, 0)).collect();
+    let mut best: Option<(usize, usize, usize)> = None;
+
+    for (j, c) in text.chars().take(PATTERN_SCAN_CHARS).map(fold_char).enumerate() {
+        let mut previous_diagonal = (0, j);
+        column[0] = (0, j + 1);
+        for i in 1..=m {
+            let substitution = (
+                previous_diagonal.0 + usize::from(pattern[i - 1] != c),
+                previous_diagonal.1,
+            );
+            let deletion = (column[i].0 + 1, column[i].1);
+            let insertion = (column[i - 1].0 + 1, column[i - 1].1);
+            previous_diagonal = column[i];
+            column[i] = [substitution, deletion, insertion]
+                .into_iter()
+                .min_by_key(|(distance, _)| *distance)
+                .unwrap_or(substitution);
+        }
+
+        let (distance, start) = column[m];
+        if distance <= max_distance && best.is_none_or(|(best_distance, _, _)| distance < best_distance) {
+            best = Some((distance, start, j + 1));
+            if distance == 0 {
+                break;
+            }
+        }
+    }
+
+    best.map(|(distance, start, end)| PatternMatch {
+        start: byte_end(start),
+        end: byte_end(end),
+        score: 1.0 - distance as f64 / m as f64,
+    })
+}
+
+// 命中文本（过长时只取开头），用于 truncate_around_keyword 定位
+pub(crate) fn matched_keyword(text: &str, found: &PatternMatch) -> String {
+    text.get(found.start..found.end)
+        .unwrap_or_default()
+        .chars()
+        .take(MAX_KEYWORD_CHARS)
+        .collect()
+}
+
+// 以命中位置为中心截取片段，并将命中部分包裹 <mark>
+pub(crate) fn pattern_snippet(content: &str, keyword: &str) -> Option<String> {
+    if keyword.is_empty() {
+        return None;
+    }
+    let snippet = truncate_around_keyword(content.to_string(), keyword, SNIPPET_LENGTH);
+    let marked = match snippet.find(keyword) {
+        Some(pos) => format!(
+            "{}\u{1}{}\u{2}{}",
+            &snippet[..pos],
+            keyword,
+            &snippet[pos + keyword.len()..]
+        ),
+        None => snippet,
+    };
+    Some(render_snippet(&marked))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher(mode: SearchMode, pattern: &str) -> PatternMatcher {
+        PatternMatcher::new(mode, pattern).unwrap().expect("应生成匹配器")
+    }
+
+    #[test]
+    fn regex_mode_is_case_insensitive_and_validates_pattern() {
+        let regex = matcher(SearchMode::Regex, r"ord-\d{4}");
+        let found = regex.find("see ORD-2048 for details").unwrap();
+        assert_eq!((found.start, found.end), (4, 12));
+        assert!(PatternMatcher::new(SearchMode::Regex, "(unclosed").is_err());
+        assert!(PatternMatcher::new(SearchMode::Text, "abc").unwrap().is_none());
+    }
+
+    #[test]
+    fn fuzzy_mode_tolerates_typos_and_scores_by_distance() {
+        let fuzzy = matcher(SearchMode::Fuzzy, "NullPointerExeption");
+        let text = "java.lang.NullPointerException at Main";
+        let found = fuzzy.find(text).unwrap();
+        assert_eq!(&text[found.start..found.end], "NullPointerException");
+        assert!(found.score < 1.0 && found.score > 0.9);
+
+        let exact = matcher(SearchMode::Fuzzy, "main").find(text).unwrap();
+        assert_eq!(exact.score, 1.0);
+        assert!(matcher(SearchMode::Fuzzy, "mian").find(text).is_none());
+    }
+
+    #[test]
+    fn fuzzy_match_offsets_respect_multibyte_text() {
+        let text = "错误日志：连接超时 timeout";
+        let found = matcher(SearchMode::Fuzzy, "连接超时").find(text).unwrap();
+        assert_eq!(&text[found.start..found.end], "连接超时");
+        assert_eq!(
+            pattern_snippet(text, &matched_keyword(text, &found)).unwrap(),
+            "错误日志：<mark>连接超时</mark> timeout"
+        );
+    }
+}
@@ -0,0 +1,571 @@
+// 搜索查询语法
+//
+// 示例：`error app:chrome -type:image after:2026-01-01 len>500 OR pinned:true`
+// - 空格分隔为 AND，大写 OR 分隔为 OR（AND 优先）
+// - `-` 前缀取反，`"..."` 为短语，`term*` 为前缀
+// - 字段过滤：app / type / before / after / pinned / group / device / len / pasted
+// - 字段值无法解析时（例如输入到一半的 before:2026-0）按普通关键词处理，搜索框逐字输入时不会报错
+
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+use rusqlite::types::Value;
+
+use super::search::{escape_like, SearchTerm};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CompareOp {
+    Eq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl CompareOp {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            CompareOp::Eq => "=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FieldFilter {
+    App(String),
+    ContentType(String),
+    CreatedBefore(i64),
+    CreatedAfter(i64),
+    Pinned(bool),
+    Group(String),
+    Device(String),
+    Length(CompareOp, i64),
+    Pasted(CompareOp, i64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum QueryClause {
+    Text(SearchTerm),
+    Field(FieldFilter),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct QueryAtom {
+    pub negated: bool,
+    pub clause: QueryClause,
+}
+
+// 解析后的查询：外层为 OR，内层为 AND
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct ParsedQuery {
+    pub groups: Vec<Vec<QueryAtom>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum QueryTarget {
+    History,
+    Favorites,
+}
+
+impl QueryTarget {
+    fn alias(&self) -> &'static str {
+        match self {
+            QueryTarget::History => "c",
+            QueryTarget::Favorites => "f",
+        }
+    }
+
+    fn text_columns(&self) -> &'static [&'static str] {
+        match self {
+            QueryTarget::History => &["c.content", "c.html_content"],
+            QueryTarget::Favorites => &["f.title", "f.content", "f.html_content"],
+        }
+    }
+
+    fn fts_membership(&self) -> &'static str {
+        match self {
+            QueryTarget::History => "c.id IN (SELECT rowid FROM clipboard_fts WHERE clipboard_fts MATCH ?)",
+            QueryTarget::Favorites => "f.id IN (SELECT m.fav_id FROM favorites_fts_rowids m WHERE m.rowid IN (SELECT rowid FROM favorites_fts WHERE favorites_fts MATCH ?))",
+        }
+    }
+}
+
+// 单个 SQL 条件及其参数
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SqlCondition {
+    pub sql: String,
+    pub params: Vec<Value>,
+}
+
+// 编译结果：fts_match 需要调用方 JOIN 全文索引表（用于排序和高亮），conditions 以 AND 连接
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct CompiledQuery {
+    pub fts_match: Option<String>,
+    pub conditions: Vec<SqlCondition>,
+}
+
+impl ParsedQuery {
+    pub fn parse(input: &str) -> Self {
+        let mut groups: Vec<Vec<QueryAtom>> = Vec::new();
+        let mut current: Vec<QueryAtom> = Vec::new();
+        let mut chars = input.trim().chars().peekable();
+
+        while let Some(&ch) = chars.peek() {
+            if ch.is_whitespace() {
+                chars.next();
+                continue;
+            }
+
+            let mut negated = false;
+            if ch == '-' {
+                chars.next();
+                match chars.peek() {
+                    Some(next) if !next.is_whitespace() => negated = true,
+                    _ => continue,
+                }
+            }
+
+            if chars.peek() == Some(&'"') {
+                chars.next();
+                let text: String = chars.by_ref().take_while(|c| *c != '"').collect();
+                let prefix = chars.peek() == Some(&'*');
+                if prefix {
+                    chars.next();
+                }
+                let text = text.trim().to_string();
+                if !text.is_empty() {
+                    current.push(QueryAtom {
+                        negated,
+                        clause: QueryClause::Text(SearchTerm { text, prefix }),
+                    });
+                }
+                continue;
+            }
+
+            // 字段值允许使用引号包含空格，例如 app:"Visual Studio Code"
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                chars.next();
+                if c == '"' {
+                    word.extend(chars.by_ref().take_while(|c| *c != '"'));
+                } else {
+                    word.push(c);
+                }
+            }
+
+            if !negated && word == "OR" {
+                if !current.is_empty() {
+                    groups.push(std::mem::take(&mut current));
+                }
+                continue;
+            }
+
+            let clause = match parse_field_filter(&word) {
+                Some(filter) => QueryClause::Field(filter),
+                None => {
+                    let prefix = word.chars().count() > 1 && word.ends_with('*');
+                    let text = if prefix { word.trim_end_matches('*').to_string() } else { word };
+                    if text.is_empty() {
+                        continue;
+                    }
+                    QueryClause::Text(SearchTerm { text, prefix })
+                }
+            };
+            current.push(QueryAtom { negated, clause });
+        }
+
+        if !current.is_empty() {
+            groups.push(current);
+        }
+
+        Self { groups }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    // 用于截取内容上下文的首个正向关键词
+    pub fn first_keyword(&self) -> Option<&str> {
+        self.groups
+            .iter()
+            .flat_map(|group| group.iter())
+            .find_map(|atom| match &atom.clause {
+                QueryClause::Text(term) if !atom.negated => Some(term.text.as_str()),
+                _ => None,
+            })
+    }
+
+    pub fn compile(&self, target: QueryTarget, fts_ready: bool) -> CompiledQuery {
+        let mut compiled = CompiledQuery::default();
+        if self.is_empty() {
+            return compiled;
+        }
+
+        // 单组查询：正向关键词合并为一个 MATCH 表达式，便于相关度排序与片段高亮
+        if self.groups.len() == 1 {
+            let mut fts_terms = Vec::new();
+            for atom in &self.groups[0] {
+                match &atom.clause {
+                    QueryClause::Text(term) if fts_ready && !atom.negated && term.is_fts_searchable() => {
+                        fts_terms.push(term.fts_expression());
+                    }
+                    _ => compiled.conditions.push(atom_condition(atom, target, fts_ready)),
+                }
+            }
+            if !fts_terms.is_empty() {
+                compiled.fts_match = Some(fts_terms.join(" AND "));
+            }
+            return compiled;
+        }
+
+        let mut params = Vec::new();
+        let groups: Vec<String> = self
+            .groups
+            .iter()
+            .map(|group| {
+                let conditions: Vec<String> = group
+                    .iter()
+                    .map(|atom| {
+                        let condition = atom_condition(atom, target, fts_ready);
+                        params.extend(condition.params);
+                        condition.sql
+                    })
+                    .collect();
+                format!("({})", conditions.join(" AND "))
+            })
+            .collect();
+        compiled.conditions.push(SqlCondition {
+            sql: format!("({})", groups.join(" OR ")),
+            params,
+        });
+        compiled
+    }
+}
+
+// 正则 / 模糊模式下取出搜索内容中的字段过滤，剩余部分原样作为匹配模式
+// 只有已知字段且值有效的片段才视为过滤条件，例如 `timeout\d+ app:chrome after:2026-01-01`
+pub(crate) fn split_field_filters(input: &str) -> (ParsedQuery, String) {
+    let mut atoms = Vec::new();
+    let mut pattern = String::with_capacity(input.len());
+    let mut rest = input.trim();
+
+    while !rest.is_empty() {
+        let mut in_quotes = false;
+        let token_end = rest
+            .char_indices()
+            .find(|(_, c)| {
+                if *c == '"' {
+                    in_quotes = !in_quotes;
+                }
+                c.is_whitespace() && !in_quotes
+            })
+            .map(|(offset, _)| offset)
+            .unwrap_or(rest.len());
+        let token = &rest[..token_end];
+        let after = &rest[token_end..];
+        let next = after.trim_start();
+
+        let negated = token.len() > 1 && token.starts_with('-');
+        let word = token.strip_prefix('-').filter(|_| negated).unwrap_or(token).replace('"', "");
+        match parse_field_filter(&word) {
+            Some(filter) => atoms.push(QueryAtom {
+                negated,
+                clause: QueryClause::Field(filter),
+            }),
+            None => {
+                pattern.push_str(token);
+                pattern.push_str(&after[..after.len() - next.len()]);
+            }
+        }
+        rest = next;
+    }
+
+    let groups = if atoms.is_empty() { Vec::new() } else { vec![atoms] };
+    (ParsedQuery { groups }, pattern.trim_end().to_string())
+}
+
+fn atom_condition(atom: &QueryAtom, target: QueryTarget, fts_ready: bool) -> SqlCondition {
+    let condition = match &atom.clause {
+        QueryClause::Text(term) => {
+            if fts_ready && term.is_fts_searchable() {
+                SqlCondition {
+                    sql: target.fts_membership().to_string(),
+                    params: vec![Value::Text(term.fts_expression())],
+                }
+            } else {
+                let (sql, patterns) = term.like_condition(target.text_columns());
+                SqlCondition {
+                    sql,
+                    params: patterns.into_iter().map(Value::Text).collect(),
+                }
+            }
+        }
+        QueryClause::Field(filter) => field_condition(filter, target),
+    };
+
+    if atom.negated {
+        SqlCondition {
+            sql: format!("NOT ({})", condition.sql),
+            params: condition.params,
+        }
+    } else {
+        condition
+    }
+}
+
+// 字段过滤条件；目标表不存在对应字段时按该字段的默认值处理（例如收藏项视为未置顶、无来源应用）
+fn field_condition(filter: &FieldFilter, target: QueryTarget) -> SqlCondition {
+    let a = target.alias();
+    let constant = |matches: bool| SqlCondition {
+        sql: if matches { "1".to_string() } else { "0".to_string() },
+        params: vec![],
+    };
+
+    match filter {
+        FieldFilter::App(app) => match target {
+            QueryTarget::History => SqlCondition {
+                sql: format!("COALESCE({}.source_app, '') LIKE ? ESCAPE '\\'", a),
+                params: vec![Value::Text(format!("%{}%", escape_like(app)))],
+            },
+            QueryTarget::Favorites => constant(false),
+        },
+        FieldFilter::ContentType(content_type) => SqlCondition {
+            sql: format!("{}.content_type LIKE ? ESCAPE '\\'", a),
+            params: vec![Value::Text(format!("%{}%", escape_like(content_type)))],
+        },
+        FieldFilter::CreatedBefore(timestamp) => SqlCondition {
+            sql: format!("{}.created_at < ?", a),
+            params: vec![Value::Integer(*timestamp)],
+        },
+        FieldFilter::CreatedAfter(timestamp) => SqlCondition {
+            sql: format!("{}.created_at >= ?", a),
+            params: vec![Value::Integer(*timestamp)],
+        },
+        FieldFilter::Pinned(pinned) => match target {
+            QueryTarget::History => SqlCondition {
+                sql: format!("{}.is_pinned = ?", a),
+                params: vec![Value::Integer(if *pinned { 1 } else { 0 })],
+            },
+            QueryTarget::Favorites => constant(!*pinned),
+        },
+        FieldFilter::Group(group) => match target {
+            QueryTarget::History => constant(false),
+            QueryTarget::Favorites => SqlCondition {
+                sql: format!("{}.group_name = ? COLLATE NOCASE", a),
+                params: vec![Value::Text(group.clone())],
+            },
+        },
+        FieldFilter::Device(device) => SqlCondition {
+            sql: format!("COALESCE({}.source_device_id, '') LIKE ? ESCAPE '\\'", a),
+            params: vec![Value::Text(format!("{}%", escape_like(device)))],
+        },
+        FieldFilter::Length(op, value) => SqlCondition {
+            sql: format!("COALESCE({a}.char_count, length({a}.content)) {} ?", op.as_sql(), a = a),
+            params: vec![Value::Integer(*value)],
+        },
+        FieldFilter::Pasted(op, value) => SqlCondition {
+            sql: format!("{}.paste_count {} ?", a, op.as_sql()),
+            params: vec![Value::Integer(*value)],
+        },
+    }
+}
+
+const FIELD_NAMES: &[&str] = &["app", "type", "before", "after", "pinned", "group", "device", "len", "pasted"];
+
+// 解析字段过滤；不是已知字段或字段值无效时返回 None，按普通关键词处理
+fn parse_field_filter(word: &str) -> Option<FieldFilter> {
+    let split_at = word.find([':', '>', '<', '='])?;
+    let name = word[..split_at].to_lowercase();
+    if !FIELD_NAMES.contains(&name.as_str()) {
+        return None;
+    }
+    let rest = &word[split_at..];
+    let value = rest.strip_prefix(':').unwrap_or(rest);
+    if value.is_empty() {
+        return None;
+    }
+
+    let filter = match name.as_str() {
+        "app" => FieldFilter::App(value.to_string()),
+        "type" => FieldFilter::ContentType(value.to_lowercase()),
+        "before" => FieldFilter::CreatedBefore(parse_date_value(value)?),
+        "after" => FieldFilter::CreatedAfter(parse_date_value(value)?),
+        "pinned" => FieldFilter::Pinned(parse_bool_value(value)?),
+        "group" => FieldFilter::Group(value.to_string()),
+        "device" => FieldFilter::Device(value.to_string()),
+        "len" => {
+            let (op, number) = parse_comparison(value)?;
+            FieldFilter::Length(op, number)
+        }
+        "pasted" => {
+            let (op, number) = parse_comparison(value)?;
+            FieldFilter::Pasted(op, number)
+        }
+        _ => return None,
+    };
+    Some(filter)
+}
+
+fn parse_comparison(value: &str) -> Option<(CompareOp, i64)> {
+    let (op, number) = if let Some(rest) = value.strip_prefix(">=") {
+        (CompareOp::Ge, rest)
+    } else if let Some(rest) = value.strip_prefix("<=") {
+        (CompareOp::Le, rest)
+    } else if let Some(rest) = value.strip_prefix('>') {
+        (CompareOp::Gt, rest)
+    } else if let Some(rest) = value.strip_prefix('<') {
+        (CompareOp::Lt, rest)
+    } else if let Some(rest) = value.strip_prefix('=') {
+        (CompareOp::Eq, rest)
+    } else {
+        (CompareOp::Eq, value)
+    };
+    let number = number.trim().parse::<i64>().ok()?;
+    Some((op, number))
+}
+
+fn parse_bool_value(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "1" => Some(true),
+        "false" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+// 日期按本地时区解析，支持 2026-01-01 / 2026-01-01T08:30 / 2026-01-01 08:30:00（引号包裹）
+fn parse_date_value(value: &str) -> Option<i64> {
+    let naive = if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        date.and_hms_opt(0, 0, 0)
+    } else {
+        ["%Y-%m-%dT%H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%d %H:%M:%S"]
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    }?;
+
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|datetime| datetime.timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> QueryAtom {
+        QueryAtom {
+            negated: false,
+            clause: QueryClause::Text(SearchTerm { text: value.to_string(), prefix: false }),
+        }
+    }
+
+    #[test]
+    fn parses_field_filters_negation_and_or() {
+        let query = ParsedQuery::parse("error app:\"Google Chrome\" -type:image len>500 OR pasted>=3");
+        assert_eq!(query.groups.len(), 2);
+        assert_eq!(query.groups[0][0], text("error"));
+        assert_eq!(
+            query.groups[0][1].clause,
+            QueryClause::Field(FieldFilter::App("Google Chrome".to_string()))
+        );
+        assert!(query.groups[0][2].negated);
+        assert_eq!(
+            query.groups[0][3].clause,
+            QueryClause::Field(FieldFilter::Length(CompareOp::Gt, 500))
+        );
+        assert_eq!(
+            query.groups[1][0].clause,
+            QueryClause::Field(FieldFilter::Pasted(CompareOp::Ge, 3))
+        );
+    }
+
+    #[test]
+    fn unknown_fields_are_plain_keywords() {
+        let query = ParsedQuery::parse("https://example.com");
+        assert_eq!(query.groups[0][0], text("https://example.com"));
+    }
+
+    #[test]
+    fn splits_field_filters_from_patterns() {
+        let (filters, pattern) = split_field_filters(r#"ord-\d{4}  app:"Google Chrome" -type:image  [a-z]+ len>ab"#);
+        assert_eq!(pattern, r"ord-\d{4}  [a-z]+ len>ab");
+        assert_eq!(filters.groups.len(), 1);
+        assert_eq!(
+            filters.groups[0][0].clause,
+            QueryClause::Field(FieldFilter::App("Google Chrome".to_string()))
+        );
+        assert!(filters.groups[0][1].negated);
+
+        let compiled = filters.compile(QueryTarget::History, false);
+        assert!(compiled.fts_match.is_none());
+        assert_eq!(compiled.conditions.len(), 2);
+
+        let (filters, pattern) = split_field_filters("(?i)type:\\w+");
+        assert!(filters.is_empty());
+        assert_eq!(pattern, "(?i)type:\\w+");
+    }
+
+    #[test]
+    fn half_typed_filters_fall_back_to_keywords() {
+        for input in ["before:2026-0", "len>ab", "pinned:m", "pasted>="] {
+            assert_eq!(ParsedQuery::parse(input).groups[0][0], text(input));
+        }
+        let query = ParsedQuery::parse("-after:2026-13-01 report");
+        assert!(query.groups[0][0].negated);
+        assert_eq!(
+            query.groups[0][0].clause,
+            QueryClause::Text(SearchTerm { text: "after:2026-13-01".to_string(), prefix: false })
+        );
+    }
+
+    #[test]
+    fn parses_dates_in_local_time() {
+        let query = ParsedQuery::parse("after:2026-01-01");
+        let expected = Local
+            .from_local_datetime(&NaiveDate::from_ymd_opt(2026, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap())
+            .earliest()
+            .unwrap()
+            .timestamp();
+        assert_eq!(query.groups[0][0].clause, QueryClause::Field(FieldFilter::CreatedAfter(expected)));
+    }
+
+    #[test]
+    fn single_group_uses_fts_join_for_positive_terms() {
+        let query = ParsedQuery::parse("stack trace -panic pinned:true");
+        let compiled = query.compile(QueryTarget::History, true);
+        assert_eq!(compiled.fts_match.as_deref(), Some("\"stack\" AND \"trace\""));
+        assert_eq!(compiled.conditions.len(), 2);
+        assert_eq!(
+            compiled.conditions[0].sql,
+            "NOT (c.id IN (SELECT rowid FROM clipboard_fts WHERE clipboard_fts MATCH ?))"
+        );
+        assert_eq!(compiled.conditions[1].sql, "c.is_pinned = ?");
+        assert_eq!(compiled.conditions[1].params, vec![Value::Integer(1)]);
+    }
+
+    #[test]
+    fn or_groups_compile_to_single_condition() {
+        let query = ParsedQuery::parse("group:work OR ab");
+        let compiled = query.compile(QueryTarget::Favorites, true);
+        assert!(compiled.fts_match.is_none());
+        assert_eq!(
+            compiled.conditions[0].sql,
+            "((f.group_name = ? COLLATE NOCASE) OR ((COALESCE(f.title, '') LIKE ? ESCAPE '\\' OR COALESCE(f.content, '') LIKE ? ESCAPE '\\' OR COALESCE(f.html_content, '') LIKE ? ESCAPE '\\')))"
+        );
+        assert_eq!(compiled.conditions[0].params.len(), 4);
+    }
+
+    #[test]
+    fn missing_columns_fall_back_to_defaults() {
+        let query = ParsedQuery::parse("pinned:false -app:chrome");
+        let compiled = query.compile(QueryTarget::Favorites, true);
+        assert_eq!(compiled.conditions[0].sql, "1");
+        assert_eq!(compiled.conditions[1].sql, "NOT (0)");
+    }
+}
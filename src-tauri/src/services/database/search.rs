@@ -0,0 +1,351 @@
+// 全文检索索引（FTS5）
+//
+// 使用 trigram 分词：中文等无空格文本也能做子串匹配，查询语义与原先的 LIKE 保持一致。
+// 索引表通过触发器与 clipboard / favorites 保持同步，维护模式等其他连接的写入同样生效。
+// favorites 以文本 id 为主键，隐式 rowid 在 VACUUM 后可能变化，因此另用 favorites_fts_rowids
+// 为每个收藏分配固定的整数 rowid，索引的增删改都按 rowid 定位。
+
+use rusqlite::Connection;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// trigram 分词器的最小可检索长度（字符数），更短的关键词回退到 LIKE
+const MIN_FTS_TERM_CHARS: usize = 3;
+
+// 片段高亮的临时标记，渲染时替换为 <mark>
+const SNIPPET_MARK_START: char = '\u{1}';
+const SNIPPET_MARK_END: char = '\u{2}';
+
+static SEARCH_INDEX_READY: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn is_search_index_ready() -> bool {
+    SEARCH_INDEX_READY.load(Ordering::Relaxed)
+}
+
+// 生成索引列表达式：文件类型只索引文件名和路径，避免把图标 base64 写入索引
+fn indexed_columns_sql(row: &str) -> String {
+    format!(
+        "CASE WHEN {row}.content LIKE 'files:%' THEN '' ELSE {row}.content END,
+         COALESCE({row}.html_content, ''),
+         CASE WHEN {row}.content LIKE 'files:%' AND json_valid(substr({row}.content, 7)) THEN (
+             SELECT COALESCE(group_concat(
+                 COALESCE(json_extract(value, '$.name'), '') || ' ' || COALESCE(json_extract(value, '$.path'), ''),
+                 ' '
+             ), '')
+             FROM json_each(substr({row}.content, 7), '$.files')
+         ) ELSE '' END",
+        row = row
+    )
+}
+
+fn table_exists(conn: &Connection, name: &str) -> Result<bool, rusqlite::Error> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE name = ?1",
+        [name],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+// 旧版 favorites_fts 用未索引的 fav_id 列关联收藏，每次更新都要全表扫描索引
+fn has_legacy_favorites_index(conn: &Connection) -> Result<bool, rusqlite::Error> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('favorites_fts') WHERE name = 'fav_id'",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+fn drop_favorites_index(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "DROP TRIGGER IF EXISTS favorites_fts_ai;
+         DROP TRIGGER IF EXISTS favorites_fts_ad;
+         DROP TRIGGER IF EXISTS favorites_fts_au;
+         DROP TABLE IF EXISTS favorites_fts;
+         DROP TABLE IF EXISTS favorites_fts_rowids;",
+    )
+}
+
+// 创建检索索引与同步触发器（首次创建时回填已有数据）
+pub(crate) fn ensure_search_index(conn: &Connection) -> Result<(), String> {
+    let result = (|| -> Result<(), rusqlite::Error> {
+        if table_exists(conn, "favorites_fts")? && has_legacy_favorites_index(conn)? {
+            drop_favorites_index(conn)?;
+        }
+        let clipboard_created = !table_exists(conn, "clipboard_fts")?;
+        let favorites_created = !table_exists(conn, "favorites_fts")? || !table_exists(conn, "favorites_fts_rowids")?;
+
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS clipboard_fts USING fts5(
+                content, html_content, file_names,
+                tokenize = 'trigram'
+             );
+             CREATE VIRTUAL TABLE IF NOT EXISTS favorites_fts USING fts5(
+                title, content, html_content, file_names,
+                tokenize = 'trigram'
+             );
+             CREATE TABLE IF NOT EXISTS favorites_fts_rowids (
+                rowid INTEGER PRIMARY KEY,
+                fav_id TEXT NOT NULL UNIQUE
+             );",
+        )?;
+
+        let new_columns = indexed_columns_sql("new");
+        conn.execute_batch(&format!(
+            "CREATE TRIGGER IF NOT EXISTS clipboard_fts_ai AFTER INSERT ON clipboard BEGIN
+                INSERT INTO clipboard_fts(rowid, content, html_content, file_names)
+                VALUES (new.id, {new_columns});
+             END;
+             CREATE TRIGGER IF NOT EXISTS clipboard_fts_ad AFTER DELETE ON clipboard BEGIN
+                DELETE FROM clipboard_fts WHERE rowid = old.id;
+             END;
+             CREATE TRIGGER IF NOT EXISTS clipboard_fts_au AFTER UPDATE OF content, html_content ON clipboard BEGIN
+                DELETE FROM clipboard_fts WHERE rowid = old.id;
+                INSERT INTO clipboard_fts(rowid, content, html_content, file_names)
+                VALUES (new.id, {new_columns});
+             END;
+             CREATE TRIGGER IF NOT EXISTS favorites_fts_ai AFTER INSERT ON favorites BEGIN
+                INSERT OR IGNORE INTO favorites_fts_rowids(fav_id) VALUES (new.id);
+                INSERT INTO favorites_fts(rowid, title, content, html_content, file_names)
+                VALUES ((SELECT rowid FROM favorites_fts_rowids WHERE fav_id = new.id), new.title, {new_columns});
+             END;
+             CREATE TRIGGER IF NOT EXISTS favorites_fts_ad AFTER DELETE ON favorites BEGIN
+                DELETE FROM favorites_fts WHERE rowid = (SELECT rowid FROM favorites_fts_rowids WHERE fav_id = old.id);
+                DELETE FROM favorites_fts_rowids WHERE fav_id = old.id;
+             END;
+             CREATE TRIGGER IF NOT EXISTS favorites_fts_au AFTER UPDATE OF id, title, content, html_content ON favorites BEGIN
+                DELETE FROM favorites_fts WHERE rowid = (SELECT rowid FROM favorites_fts_rowids WHERE fav_id = old.id);
+                UPDATE favorites_fts_rowids SET fav_id = new.id WHERE fav_id = old.id;
+                INSERT OR IGNORE INTO favorites_fts_rowids(fav_id) VALUES (new.id);
+                INSERT INTO favorites_fts(rowid, title, content, html_content, file_names)
+                VALUES ((SELECT rowid FROM favorites_fts_rowids WHERE fav_id = new.id), new.title, {new_columns});
+             END;",
+            new_columns = new_columns,
+        ))?;
+
+        if clipboard_created {
+            backfill_clipboard_index(conn)?;
+        }
+        if favorites_created {
+            backfill_favorites_index(conn)?;
+        }
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => {
+            SEARCH_INDEX_READY.store(true, Ordering::Relaxed);
+            Ok(())
+        }
+        Err(e) => {
+            SEARCH_INDEX_READY.store(false, Ordering::Relaxed);
+            Err(format!("创建全文检索索引失败: {}", e))
+        }
+    }
+}
+
+// 删除检索索引与同步触发器，本地加密封存前调用，避免索引中残留明文
+pub(crate) fn drop_search_index(conn: &Connection) -> Result<(), rusqlite::Error> {
+    SEARCH_INDEX_READY.store(false, Ordering::Relaxed);
+    conn.execute_batch(
+        "DROP TRIGGER IF EXISTS clipboard_fts_ai;
+         DROP TRIGGER IF EXISTS clipboard_fts_ad;
+         DROP TRIGGER IF EXISTS clipboard_fts_au;
+         DROP TRIGGER IF EXISTS favorites_fts_ai;
+         DROP TRIGGER IF EXISTS favorites_fts_ad;
+         DROP TRIGGER IF EXISTS favorites_fts_au;
+         DROP TABLE IF EXISTS clipboard_fts;
+         DROP TABLE IF EXISTS favorites_fts;
+         DROP TABLE IF EXISTS favorites_fts_rowids;",
+    )
+}
+
+fn backfill_clipboard_index(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM clipboard_fts", [])?;
+    tx.execute(
+        &format!(
+            "INSERT INTO clipboard_fts(rowid, content, html_content, file_names)
+             SELECT c.id, {} FROM clipboard c",
+            indexed_columns_sql("c")
+        ),
+        [],
+    )?;
+    tx.commit()
+}
+
+fn backfill_favorites_index(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM favorites_fts", [])?;
+    tx.execute("DELETE FROM favorites_fts_rowids", [])?;
+    tx.execute("INSERT INTO favorites_fts_rowids(fav_id) SELECT id FROM favorites", [])?;
+    tx.execute(
+        &format!(
+            "INSERT INTO favorites_fts(rowid, title, content, html_content, file_names)
+             SELECT m.rowid, f.title, {} FROM favorites f JOIN favorites_fts_rowids m ON m.fav_id = f.id",
+            indexed_columns_sql("f")
+        ),
+        [],
+    )?;
+    tx.commit()
+}
+
+// 重建全文检索索引，连同触发器一起重新创建，用于维护模式修复损坏或不一致的索引
+pub fn rebuild_search_index(conn: &Connection) -> Result<(), String> {
+    drop_search_index(conn).map_err(|e| format!("删除全文检索索引失败: {}", e))?;
+    ensure_search_index(conn)?;
+    conn.execute_batch(
+        "INSERT INTO clipboard_fts(clipboard_fts) VALUES ('optimize');
+         INSERT INTO favorites_fts(favorites_fts) VALUES ('optimize');",
+    )
+    .map_err(|e| format!("优化全文检索索引失败: {}", e))
+}
+
+// 单个文本关键词
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SearchTerm {
+    pub text: String,
+    pub prefix: bool,
+}
+
+impl SearchTerm {
+    // trigram 分词无法检索过短的关键词
+    pub fn is_fts_searchable(&self) -> bool {
+        self.text.chars().count() >= MIN_FTS_TERM_CHARS
+    }
+
+    // FTS5 MATCH 表达式（整体作为短语，避免用户输入被解析为 FTS5 语法）
+    pub fn fts_expression(&self) -> String {
+        let quoted = format!("\"{}\"", self.text.replace('"', "\"\""));
+        if self.prefix { format!("{} *", quoted) } else { quoted }
+    }
+
+    // LIKE 回退条件，任一列包含关键词即视为命中
+    pub fn like_condition(&self, columns: &[&str]) -> (String, Vec<String>) {
+        let pattern = format!("%{}%", escape_like(&self.text));
+        let sql: Vec<String> = columns
+            .iter()
+            .map(|column| format!("COALESCE({}, '') LIKE ? ESCAPE '\\'", column))
+            .collect();
+        (format!("({})", sql.join(" OR ")), vec![pattern; columns.len()])
+    }
+}
+
+pub(crate) fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// FTS5 snippet() 表达式：优先取正文列的命中片段，正文未命中时再由 FTS5 自动选列
+// 使用控制字符作为高亮标记，最后统一转义
+pub(crate) fn snippet_sql(table: &str) -> String {
+    let start = SNIPPET_MARK_START as u32;
+    let end = SNIPPET_MARK_END as u32;
+    let content_column = if table == "favorites_fts" { 1 } else { 0 };
+    format!(
+        "CASE WHEN instr(snippet({table}, {col}, char({start}), char({end}), '...', 32), char({start})) > 0
+              THEN snippet({table}, {col}, char({start}), char({end}), '...', 32)
+              ELSE snippet({table}, -1, char({start}), char({end}), '...', 32) END",
+        table = table,
+        col = content_column,
+        start = start,
+        end = end
+    )
+}
+
+// 将 snippet 输出转为可直接渲染的 HTML（正文转义，命中部分包裹 <mark>）
+pub(crate) fn render_snippet(raw: &str) -> String {
+    let mut html = String::with_capacity(raw.len() + 16);
+    for c in raw.chars() {
+        match c {
+            SNIPPET_MARK_START => html.push_str("<mark>"),
+            SNIPPET_MARK_END => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            _ => html.push(c),
+        }
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_fts_and_like_expressions_for_terms() {
+        let term = SearchTerm { text: "say \"hi\"".to_string(), prefix: true };
+        assert_eq!(term.fts_expression(), "\"say \"\"hi\"\"\" *");
+
+        let short = SearchTerm { text: "5%".to_string(), prefix: false };
+        assert!(!short.is_fts_searchable());
+        let (sql, params) = short.like_condition(&["c.content", "c.html_content"]);
+        assert_eq!(
+            sql,
+            "(COALESCE(c.content, '') LIKE ? ESCAPE '\\' OR COALESCE(c.html_content, '') LIKE ? ESCAPE '\\')"
+        );
+        assert_eq!(params, vec!["%5\\%%", "%5\\%%"]);
+    }
+
+    fn favorite_matches(conn: &Connection, text: &str) -> Vec<String> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT m.fav_id FROM favorites_fts JOIN favorites_fts_rowids m ON m.rowid = favorites_fts.rowid
+                 WHERE favorites_fts MATCH ?1 ORDER BY m.fav_id",
+            )
+            .unwrap();
+        let rows = stmt.query_map([text], |row| row.get(0)).unwrap();
+        rows.map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn favorites_index_follows_writes_by_mapped_rowid() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::services::database::migrations::run_migrations(&conn, None).unwrap();
+        conn.execute(
+            "CREATE VIRTUAL TABLE favorites_fts USING fts5(
+                fav_id UNINDEXED, title, content, html_content, file_names, tokenize = 'trigram'
+             )",
+            [],
+        )
+        .unwrap();
+        conn.execute_batch(
+            "INSERT INTO favorites (id, title, content, created_at, updated_at) VALUES ('f1', 'alpha', 'first note', 1, 1);
+             INSERT INTO favorites (id, title, content, created_at, updated_at) VALUES ('f2', 'beta', 'second note', 1, 1);",
+        )
+        .unwrap();
+
+        // 旧结构被替换并回填
+        ensure_search_index(&conn).unwrap();
+        assert!(!has_legacy_favorites_index(&conn).unwrap());
+        assert_eq!(favorite_matches(&conn, "note"), vec!["f1", "f2"]);
+
+        conn.execute_batch(
+            "UPDATE favorites SET content = 'rewritten text' WHERE id = 'f1';
+             DELETE FROM favorites WHERE id = 'f2';
+             VACUUM;
+             INSERT INTO favorites (id, title, content, created_at, updated_at) VALUES ('f3', 'gamma', 'third note', 1, 1);",
+        )
+        .unwrap();
+        assert_eq!(favorite_matches(&conn, "note"), vec!["f3"]);
+        assert_eq!(favorite_matches(&conn, "rewritten"), vec!["f1"]);
+
+        rebuild_search_index(&conn).unwrap();
+        assert_eq!(favorite_matches(&conn, "alpha"), vec!["f1"]);
+        assert_eq!(favorite_matches(&conn, "note"), vec!["f3"]);
+    }
+
+    #[test]
+    fn escapes_snippet_markup() {
+        assert_eq!(render_snippet("a<b>\u{1}hit\u{2}"), "a&lt;b&gt;<mark>hit</mark>");
+    }
+}
@@ -0,0 +1,419 @@
+// 同步版本与合并基线
+//
+// sync_versions 表为参与同步的记录保存：
+// - version：版本向量；fingerprint 为该版本对应的内容摘要，本地内容与摘要不一致说明本机改过，
+//   在下次参与同步时为本机计数加一，本地编辑入口无需关心版本号
+// - base_*：上次与其他设备达成一致的内容，并发修改时作为三方合并的基线；
+//   base_version 为该内容的版本，远端版本不包含它时说明远端没见过这份基线（多设备转发时常见），不能用来合并
+// 没有版本号的记录（旧版本设备发来的数据）和本机从未同步过的记录仍按更新时间处理。
+
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+
+use super::connection::with_connection;
+use crate::services::sync_transfer::merge::{self, VersionOrder};
+use crate::services::webdav_sync::types::{CloudRecord, SyncConflictItem, VersionVector};
+
+pub const CONFLICT_COPY_SUFFIX: &str = "（冲突副本）";
+
+#[derive(Debug, Clone, Default)]
+pub struct RecordUpsertResult {
+    pub changed: Vec<CloudRecord>,
+    pub conflicts: Vec<SyncConflictItem>,
+}
+
+// 参与版本比较和合并的内容字段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SyncContent {
+    pub title: String,
+    pub content: String,
+    pub html_content: Option<String>,
+}
+
+impl SyncContent {
+    pub fn of_record(record: &CloudRecord) -> Self {
+        Self {
+            title: record.title.clone(),
+            content: record.content.clone(),
+            html_content: record.html_content.clone(),
+        }
+    }
+
+    fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.title.as_bytes());
+        hasher.update([0]);
+        hasher.update(self.content.as_bytes());
+        hasher.update([0]);
+        match &self.html_content {
+            Some(html) => {
+                hasher.update([1]);
+                hasher.update(html.as_bytes());
+            }
+            None => hasher.update([0]),
+        }
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+pub(crate) enum UpsertPlan {
+    // 按更新时间决定是否采用远端；Some 时内容一致，记录合并后的版本号
+    ByTimestamp(Option<VersionVector>),
+    KeepLocal,
+    TakeRemote(VersionVector),
+    Merged(SyncContent, VersionVector),
+    // 无法合并：本地内容另存为冲突副本，原条目采用远端内容
+    Conflict(VersionVector),
+}
+
+fn parse_version(text: &str) -> VersionVector {
+    serde_json::from_str(text).unwrap_or_default()
+}
+
+fn version_text(version: &VersionVector) -> String {
+    serde_json::to_string(version).unwrap_or_else(|_| "{}".to_string())
+}
+
+// 本地当前版本：没有记录时以当前内容建立（尚未同步，不记录基线），内容变化时为本机计数加一
+pub(crate) fn local_version_in_conn(
+    conn: &Connection,
+    collection: &str,
+    item_id: &str,
+    local: &SyncContent,
+    device_id: &str,
+) -> Result<VersionVector, rusqlite::Error> {
+    let stored = conn
+        .query_row(
+            "SELECT fingerprint, version FROM sync_versions WHERE collection = ?1 AND item_id = ?2",
+            params![collection, item_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )
+        .optional()?;
+    let fingerprint = local.fingerprint();
+    match stored {
+        Some((stored_fingerprint, version)) if stored_fingerprint == fingerprint => Ok(parse_version(&version)),
+        Some((_, version)) => {
+            let mut version = parse_version(&version);
+            merge::bump_version(&mut version, device_id);
+            conn.execute(
+                "UPDATE sync_versions SET fingerprint = ?3, version = ?4, updated_at = ?5
+                 WHERE collection = ?1 AND item_id = ?2",
+                params![collection, item_id, fingerprint, version_text(&version), chrono::Utc::now().timestamp()],
+            )?;
+            Ok(version)
+        }
+        None => {
+            let version = VersionVector::new();
+            conn.execute(
+                "INSERT INTO sync_versions (collection, item_id, fingerprint, version, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![collection, item_id, fingerprint, version_text(&version), chrono::Utc::now().timestamp()],
+            )?;
+            Ok(version)
+        }
+    }
+}
+
+pub(crate) fn save_version_in_conn(
+    conn: &Connection,
+    collection: &str,
+    item_id: &str,
+    content: &SyncContent,
+    version: &VersionVector,
+    base: &SyncContent,
+    base_version: &VersionVector,
+) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO sync_versions (
+            collection, item_id, fingerprint, version, base_title, base_content, base_html_content, base_version, updated_at
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+         ON CONFLICT(collection, item_id) DO UPDATE SET
+            fingerprint = excluded.fingerprint,
+            version = excluded.version,
+            base_title = excluded.base_title,
+            base_content = excluded.base_content,
+            base_html_content = excluded.base_html_content,
+            base_version = excluded.base_version,
+            updated_at = excluded.updated_at",
+        params![
+            collection,
+            item_id,
+            content.fingerprint(),
+            version_text(version),
+            base.title,
+            base.content,
+            base.html_content,
+            version_text(base_version),
+            chrono::Utc::now().timestamp(),
+        ],
+    )?;
+    Ok(())
+}
+
+fn load_base_in_conn(
+    conn: &Connection,
+    collection: &str,
+    item_id: &str,
+) -> Result<Option<(SyncContent, VersionVector)>, rusqlite::Error> {
+    let base = conn
+        .query_row(
+            "SELECT base_title, base_content, base_html_content, base_version FROM sync_versions
+             WHERE collection = ?1 AND item_id = ?2",
+            params![collection, item_id],
+            |row| {
+                Ok((
+                    row.get::<_, Option<String>>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            },
+        )
+        .optional()?;
+    Ok(base.and_then(|(title, content, html_content, version)| {
+        let content = SyncContent {
+            title: title.unwrap_or_default(),
+            content: content?,
+            html_content,
+        };
+        Some((content, version.map(|version| parse_version(&version)).unwrap_or_default()))
+    }))
+}
+
+// 多种格式并存时以逗号拼接，如 "text,link"
+fn is_text_content_type(content_type: &str) -> bool {
+    content_type.split(',').any(|t| matches!(t.trim(), "text" | "rich_text" | "link"))
+}
+
+fn merge_content(base: &SyncContent, local: &SyncContent, remote: &SyncContent, content_type: &str) -> Option<SyncContent> {
+    let content = if is_text_content_type(content_type) {
+        merge::merge_text(&base.content, &local.content, &remote.content)?
+    } else {
+        merge::merge_value(&base.content, &local.content, &remote.content)?
+    };
+    Some(SyncContent {
+        title: merge::merge_value(&base.title, &local.title, &remote.title)?,
+        content,
+        html_content: merge::merge_value(&base.html_content, &local.html_content, &remote.html_content)?,
+    })
+}
+
+// 比较本地与远端记录的版本，决定如何写入
+pub(crate) fn plan_upsert(
+    conn: &Connection,
+    collection: &str,
+    local: &SyncContent,
+    local_content_type: &str,
+    remote: &CloudRecord,
+    device_id: &str,
+) -> Result<UpsertPlan, rusqlite::Error> {
+    if remote.version.is_empty() {
+        return Ok(UpsertPlan::ByTimestamp(None));
+    }
+    let local_version = local_version_in_conn(conn, collection, &remote.uuid, local, device_id)?;
+    let remote_content = SyncContent::of_record(remote);
+    let mut joined = merge::join_versions(&local_version, &remote.version);
+    if *local == remote_content {
+        return Ok(UpsertPlan::ByTimestamp(Some(joined)));
+    }
+    let base = load_base_in_conn(conn, collection, &remote.uuid)?;
+    // 本地记录从未同步过，空版本号不代表远端已包含本地内容，按并发处理并交给更新时间决定
+    if local_version.is_empty() && base.is_none() {
+        return Ok(UpsertPlan::ByTimestamp(None));
+    }
+
+    Ok(match merge::compare_versions(&local_version, &remote.version) {
+        VersionOrder::Equal => UpsertPlan::ByTimestamp(None),
+        VersionOrder::Older => UpsertPlan::TakeRemote(remote.version.clone()),
+        VersionOrder::Newer => UpsertPlan::KeepLocal,
+        VersionOrder::Concurrent => {
+            // 基线必须是双方共同的历史，远端没见过本地的基线时三方合并会把本地独有的修改当作远端删除
+            let merged = if local_content_type == remote.content_type {
+                base.filter(|(_, base_version)| {
+                    matches!(merge::compare_versions(base_version, &remote.version), VersionOrder::Older | VersionOrder::Equal)
+                })
+                .and_then(|(base, _)| merge_content(&base, local, &remote_content, &remote.content_type))
+            } else {
+                None
+            };
+            match merged {
+                Some(merged) => {
+                    // 合并结果是新的版本，覆盖双方
+                    merge::bump_version(&mut joined, device_id);
+                    UpsertPlan::Merged(merged, joined)
+                }
+                None => UpsertPlan::Conflict(joined),
+            }
+        }
+    })
+}
+
+// 为待发送的记录附上本地版本号
+pub(crate) fn attach_versions_in_conn(
+    conn: &Connection,
+    collection: &str,
+    records: &mut [CloudRecord],
+) -> Result<(), rusqlite::Error> {
+    let device_id = crate::services::sync_transfer::device_id();
+    for record in records.iter_mut() {
+        record.version = local_version_in_conn(conn, collection, &record.uuid, &SyncContent::of_record(record), &device_id)?;
+    }
+    Ok(())
+}
+
+pub fn sync_version_states(collection: &str) -> Result<HashMap<String, VersionVector>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare("SELECT item_id, version FROM sync_versions WHERE collection = ?1")?;
+        let rows = stmt.query_map(params![collection], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        let mut states = HashMap::new();
+        for row in rows {
+            let (item_id, version) = row?;
+            states.insert(item_id, parse_version(&version));
+        }
+        Ok(states)
+    })
+}
+
+// 记录已成功推送，推送的内容成为之后合并的基线
+pub fn record_sync_bases(collection: &str, records: &[CloudRecord]) -> Result<(), String> {
+    if records.is_empty() {
+        return Ok(());
+    }
+    with_connection(|conn| record_sync_bases_in_conn(conn, collection, records))
+}
+
+pub(crate) fn record_sync_bases_in_conn(
+    conn: &Connection,
+    collection: &str,
+    records: &[CloudRecord],
+) -> Result<(), rusqlite::Error> {
+    let tx = conn.unchecked_transaction()?;
+    for record in records {
+        tx.execute(
+            "UPDATE sync_versions SET base_title = ?3, base_content = ?4, base_html_content = ?5, base_version = ?6
+             WHERE collection = ?1 AND item_id = ?2",
+            params![
+                collection,
+                record.uuid,
+                record.title,
+                record.content,
+                record.html_content,
+                version_text(&record.version),
+            ],
+        )?;
+    }
+    tx.commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::services::database::migrations::run_migrations(&conn, None).unwrap();
+        conn
+    }
+
+    fn content(text: &str) -> SyncContent {
+        SyncContent { title: String::new(), content: text.to_string(), html_content: None }
+    }
+
+    fn remote(text: &str, version: &[(&str, u64)]) -> CloudRecord {
+        remote_typed(text, "text", version)
+    }
+
+    fn remote_typed(text: &str, content_type: &str, version: &[(&str, u64)]) -> CloudRecord {
+        serde_json::from_value(serde_json::json!({
+            "uuid": "r1",
+            "source_device_id": "remote",
+            "content": text,
+            "content_type": content_type,
+            "created_at": 1,
+            "updated_at": 1,
+            "version": version.iter().map(|(d, c)| (d.to_string(), *c)).collect::<VersionVector>(),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn bumps_local_version_only_when_content_changes() {
+        let conn = setup();
+        let v0 = local_version_in_conn(&conn, "favorites", "r1", &content("a"), "local").unwrap();
+        assert!(v0.is_empty());
+        assert!(load_base_in_conn(&conn, "favorites", "r1").unwrap().is_none());
+        let v1 = local_version_in_conn(&conn, "favorites", "r1", &content("a"), "local").unwrap();
+        assert!(v1.is_empty());
+        let v2 = local_version_in_conn(&conn, "favorites", "r1", &content("b"), "local").unwrap();
+        assert_eq!(v2.get("local"), Some(&1));
+    }
+
+    #[test]
+    fn unsynced_local_record_is_not_older_than_remote() {
+        let conn = setup();
+        let local = content("local edit");
+        let plan = plan_upsert(&conn, "favorites", &local, "text", &remote("remote edit", &[("remote", 3)]), "local").unwrap();
+        assert!(matches!(plan, UpsertPlan::ByTimestamp(None)));
+
+        // 同步过一次且本地未修改时，远端的新版本直接覆盖
+        save_version_in_conn(&conn, "favorites", "r1", &local, &VersionVector::new(), &local, &VersionVector::new()).unwrap();
+        let plan = plan_upsert(&conn, "favorites", &local, "text", &remote("remote edit", &[("remote", 3)]), "local").unwrap();
+        assert!(matches!(plan, UpsertPlan::TakeRemote(_)));
+    }
+
+    #[test]
+    fn plans_merge_or_conflict_for_concurrent_edits() {
+        let conn = setup();
+        let base = content("one\ntwo\nthree\n");
+        let synced: VersionVector = [("remote".to_string(), 1)].into_iter().collect();
+        save_version_in_conn(&conn, "favorites", "r1", &base, &synced, &base, &synced).unwrap();
+
+        // 本地改第一行，远端改最后一行
+        let local = content("ONE\ntwo\nthree\n");
+        let plan = plan_upsert(&conn, "favorites", &local, "text", &remote("one\ntwo\nTHREE\n", &[("remote", 2)]), "local").unwrap();
+        let UpsertPlan::Merged(merged, version) = plan else { panic!("应当自动合并") };
+        assert_eq!(merged.content, "ONE\ntwo\nTHREE\n");
+        assert_eq!(version.get("local"), Some(&2));
+
+        // 远端已包含本地修改时直接采用远端
+        let plan = plan_upsert(&conn, "favorites", &local, "text", &remote("x\n", &[("local", 1), ("remote", 3)]), "local").unwrap();
+        assert!(matches!(plan, UpsertPlan::TakeRemote(_)));
+
+        let plan = plan_upsert(&conn, "favorites", &local, "text", &remote("one\ntwo\nthree\nfour\n", &[("remote", 2)]), "local").unwrap();
+        assert!(matches!(plan, UpsertPlan::Merged(..)));
+        let plan = plan_upsert(&conn, "favorites", &local, "text", &remote("REMOTE\ntwo\nthree\n", &[("remote", 2)]), "local").unwrap();
+        assert!(matches!(plan, UpsertPlan::Conflict(_)));
+    }
+
+    #[test]
+    fn merges_comma_joined_text_types_line_by_line() {
+        let conn = setup();
+        let base = content("one\ntwo\nthree\n");
+        let synced: VersionVector = [("remote".to_string(), 1)].into_iter().collect();
+        save_version_in_conn(&conn, "favorites", "r1", &base, &synced, &base, &synced).unwrap();
+
+        let local = content("ONE\ntwo\nthree\n");
+        let incoming = remote_typed("one\ntwo\nTHREE\n", "text,link", &[("remote", 2)]);
+        let plan = plan_upsert(&conn, "favorites", &local, "text,link", &incoming, "local").unwrap();
+        let UpsertPlan::Merged(merged, _) = plan else { panic!("应当按文本逐行合并") };
+        assert_eq!(merged.content, "ONE\ntwo\nTHREE\n");
+    }
+
+    #[test]
+    fn ignores_base_the_remote_has_not_seen() {
+        let conn = setup();
+        // 从 a 设备收到的内容成为基线，本地之后没有修改
+        let base = content("one\ntwo\nthree\n");
+        let from_a: VersionVector = [("a".to_string(), 2)].into_iter().collect();
+        save_version_in_conn(&conn, "favorites", "r1", &base, &from_a, &base, &from_a).unwrap();
+
+        // b 没见过 a 的修改，按这份基线合并会把 a 的修改当作被 b 撤销
+        let plan = plan_upsert(&conn, "favorites", &base, "text", &remote("one\ntwo\nTHREE\n", &[("b", 1)]), "local").unwrap();
+        assert!(matches!(plan, UpsertPlan::Conflict(_)));
+
+        let plan = plan_upsert(&conn, "favorites", &base, "text", &remote("one\ntwo\nTHREE\n", &[("a", 2), ("b", 1)]), "local").unwrap();
+        assert!(matches!(plan, UpsertPlan::TakeRemote(_)));
+    }
+}
@@ -139,46 +139,57 @@ pub fn upsert_sync_tombstones(tombstones: &[SyncTombstone]) -> Result<Vec<SyncTo
     if tombstones.is_empty() {
         return Ok(Vec::new());
     }
+    with_connection(|conn| upsert_sync_tombstones_in_conn(conn, tombstones))
+}
 
-    with_connection(|conn| {
-        let tx = conn.unchecked_transaction()?;
-        let mut changed = Vec::new();
-        for tombstone in tombstones {
-            if tombstone.collection.trim().is_empty() || tombstone.item_id.trim().is_empty() {
-                continue;
-            }
-
-            let existing_deleted_at = tx
-                .query_row(
-                    "SELECT deleted_at FROM sync_tombstones WHERE collection = ?1 AND item_id = ?2",
-                    params![tombstone.collection, tombstone.item_id],
-                    |row| row.get::<_, i64>(0),
-                )
-                .optional()?;
-            if existing_deleted_at.map(|value| value >= tombstone.deleted_at).unwrap_or(false) {
-                continue;
-            }
+pub(crate) fn upsert_sync_tombstones_in_conn(
+    conn: &rusqlite::Connection,
+    tombstones: &[SyncTombstone],
+) -> Result<Vec<SyncTombstone>, rusqlite::Error> {
+    let tx = conn.unchecked_transaction()?;
+    let mut changed = Vec::new();
+    for tombstone in tombstones {
+        if tombstone.collection.trim().is_empty() || tombstone.item_id.trim().is_empty() {
+            continue;
+        }
 
-            tx.execute(
-                "INSERT INTO sync_tombstones (collection, item_id, source_device_id, deleted_at, created_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5)
-                 ON CONFLICT(collection, item_id) DO UPDATE SET
-                    source_device_id = excluded.source_device_id,
-                    deleted_at = excluded.deleted_at,
-                    created_at = excluded.created_at",
-                params![
-                    tombstone.collection,
-                    tombstone.item_id,
-                    tombstone.source_device_id,
-                    tombstone.deleted_at,
-                    tombstone.created_at,
-                ],
-            )?;
-            changed.push(tombstone.clone());
+        let existing_deleted_at = tx
+            .query_row(
+                "SELECT deleted_at FROM sync_tombstones WHERE collection = ?1 AND item_id = ?2",
+                params![tombstone.collection, tombstone.item_id],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()?;
+        if existing_deleted_at.map(|value| value >= tombstone.deleted_at).unwrap_or(false) {
+            continue;
         }
-        tx.commit()?;
-        Ok(changed)
-    })
+        // 本地条目在删除之后又被修改过，删除记录已失效，保存后会被随后收到的条目再次清除并反复转发
+        if live_item_updated_at(&tx, &tombstone.collection, &tombstone.item_id)?
+            .map(|updated_at| updated_at > tombstone.deleted_at)
+            .unwrap_or(false)
+        {
+            continue;
+        }
+
+        tx.execute(
+            "INSERT INTO sync_tombstones (collection, item_id, source_device_id, deleted_at, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(collection, item_id) DO UPDATE SET
+                source_device_id = excluded.source_device_id,
+                deleted_at = excluded.deleted_at,
+                created_at = excluded.created_at",
+            params![
+                tombstone.collection,
+                tombstone.item_id,
+                tombstone.source_device_id,
+                tombstone.deleted_at,
+                tombstone.created_at,
+            ],
+        )?;
+        changed.push(tombstone.clone());
+    }
+    tx.commit()?;
+    Ok(changed)
 }
 
 pub fn apply_sync_tombstones(tombstones: &[SyncTombstone]) -> Result<SyncTombstoneApplyReport, String> {
@@ -186,38 +197,43 @@ pub fn apply_sync_tombstones(tombstones: &[SyncTombstone]) -> Result<SyncTombsto
         return Ok(SyncTombstoneApplyReport::default());
     }
 
-    let (report, images_to_delete) = with_connection(|conn| {
-        let tx = conn.unchecked_transaction()?;
-        let mut report = SyncTombstoneApplyReport::default();
-        let mut image_ids = Vec::new();
-
-        for tombstone in tombstones {
-            match tombstone.collection.as_str() {
-                COLLECTION_HISTORY => {
-                    if delete_history_by_tombstone(&tx, tombstone, &mut image_ids)? {
-                        report.history += 1;
-                    }
+    let (report, images_to_delete) = with_connection(|conn| apply_sync_tombstones_in_conn(conn, tombstones))?;
+    delete_unreferenced_image_files(images_to_delete)?;
+    Ok(report)
+}
+
+// 返回删除报告和被删除条目引用的图片 ID，图片文件由调用方清理
+pub(crate) fn apply_sync_tombstones_in_conn(
+    conn: &rusqlite::Connection,
+    tombstones: &[SyncTombstone],
+) -> Result<(SyncTombstoneApplyReport, Vec<String>), rusqlite::Error> {
+    let tx = conn.unchecked_transaction()?;
+    let mut report = SyncTombstoneApplyReport::default();
+    let mut image_ids = Vec::new();
+
+    for tombstone in tombstones {
+        match tombstone.collection.as_str() {
+            COLLECTION_HISTORY => {
+                if delete_history_by_tombstone(&tx, tombstone, &mut image_ids)? {
+                    report.history += 1;
                 }
-                COLLECTION_FAVORITES => {
-                    if delete_favorite_by_tombstone(&tx, tombstone, &mut image_ids)? {
-                        report.favorites += 1;
-                    }
+            }
+            COLLECTION_FAVORITES => {
+                if delete_favorite_by_tombstone(&tx, tombstone, &mut image_ids)? {
+                    report.favorites += 1;
                 }
-                COLLECTION_GROUPS => {
-                    if delete_group_by_tombstone(&tx, tombstone)? {
-                        report.groups += 1;
-                    }
+            }
+            COLLECTION_GROUPS => {
+                if delete_group_by_tombstone(&tx, tombstone)? {
+                    report.groups += 1;
                 }
-                _ => {}
             }
+            _ => {}
         }
+    }
 
-        tx.commit()?;
-        Ok((report, image_ids))
-    })?;
-
-    delete_unreferenced_image_files(images_to_delete)?;
-    Ok(report)
+    tx.commit()?;
+    Ok((report, image_ids))
 }
 
 pub fn filter_records_not_deleted(collection: &str, records: &[CloudRecord]) -> Result<Vec<CloudRecord>, String> {
@@ -308,6 +324,24 @@ fn sync_tombstone_from_row(row: &rusqlite::Row<'_>) -> Result<SyncTombstone, rus
     })
 }
 
+fn live_item_updated_at(
+    conn: &rusqlite::Connection,
+    collection: &str,
+    item_id: &str,
+) -> Result<Option<i64>, rusqlite::Error> {
+    let sql = match collection {
+        COLLECTION_HISTORY => {
+            "SELECT updated_at FROM clipboard
+             WHERE uuid = ?1 OR (uuid IS NULL OR uuid = '') AND CAST(id AS TEXT) = ?1
+             LIMIT 1"
+        }
+        COLLECTION_FAVORITES => "SELECT updated_at FROM favorites WHERE id = ?1 LIMIT 1",
+        COLLECTION_GROUPS => "SELECT updated_at FROM groups WHERE name = ?1 LIMIT 1",
+        _ => return Ok(None),
+    };
+    conn.query_row(sql, params![item_id], |row| row.get::<_, i64>(0)).optional()
+}
+
 fn delete_history_by_tombstone(
     conn: &rusqlite::Connection,
     tombstone: &SyncTombstone,
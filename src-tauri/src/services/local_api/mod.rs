@@ -0,0 +1,79 @@
+// 本机控制接口
+//
+// 仅监听 127.0.0.1 的 HTTP/JSON 接口，供脚本查询、复制、置顶、删除历史和订阅新内容，
+// 无需经过界面。请求需携带 Authorization: Bearer <令牌>，令牌可在设置中查看和重新生成。
+
+pub mod server;
+
+use crate::services::database::ClipboardItem;
+use once_cell::sync::Lazy;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+pub const DEFAULT_LOCAL_API_PORT: u16 = 35692;
+const TOKEN_STORE_KEY: &str = "local_api.token";
+const CAPTURE_EVENT_CAPACITY: usize = 64;
+
+static CAPTURE_EVENTS: Lazy<broadcast::Sender<String>> =
+    Lazy::new(|| broadcast::channel(CAPTURE_EVENT_CAPACITY).0);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalApiStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub port: u16,
+    pub token: String,
+}
+
+pub fn status() -> Result<LocalApiStatus, String> {
+    let settings = crate::services::get_settings();
+    Ok(LocalApiStatus {
+        enabled: settings.local_api_enabled,
+        running: server::is_running(),
+        port: server::running_port().unwrap_or(settings.local_api_port),
+        token: token()?,
+    })
+}
+
+// 首次使用时生成令牌
+pub fn token() -> Result<String, String> {
+    match crate::services::store::get::<String>(TOKEN_STORE_KEY) {
+        Some(token) if !token.is_empty() => Ok(token),
+        _ => regenerate_token(),
+    }
+}
+
+pub fn regenerate_token() -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token = hex::encode(bytes);
+    crate::services::store::set(TOKEN_STORE_KEY, &token)?;
+    Ok(token)
+}
+
+// 按当前设置启动或停止服务
+pub async fn apply_settings(app: tauri::AppHandle) -> Result<(), String> {
+    let settings = crate::services::get_settings();
+    if settings.local_api_enabled {
+        token()?;
+        server::start(app, settings.local_api_port).await.map(|_| ())
+    } else {
+        server::stop().await;
+        Ok(())
+    }
+}
+
+// 新内容入库后推送给事件订阅者
+pub fn publish_captured_item(item: &ClipboardItem) {
+    if CAPTURE_EVENTS.receiver_count() == 0 {
+        return;
+    }
+    if let Ok(json) = serde_json::to_string(item) {
+        let _ = CAPTURE_EVENTS.send(json);
+    }
+}
+
+fn subscribe_captured_items() -> broadcast::Receiver<String> {
+    CAPTURE_EVENTS.subscribe()
+}
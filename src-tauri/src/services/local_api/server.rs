@@ -0,0 +1,370 @@
+use base64::{engine::general_purpose, Engine as _};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use tauri::AppHandle;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::services::database::{ClipboardItem, QueryParams, SearchMode};
+use crate::services::sync_transfer::lan::http_server::{
+    json_response, read_request, read_request_body, request_header, write_response, HttpRequest,
+    HttpResponse,
+};
+
+const ITEMS_PATH: &str = "/api/v1/items";
+const ITEMS_PREFIX: &str = "/api/v1/items/";
+const EVENTS_PATH: &str = "/api/v1/events";
+const MAX_REQUEST_BODY_SIZE: usize = 1024 * 1024;
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 500;
+const EVENT_KEEPALIVE_SECS: u64 = 15;
+
+static SERVER: Lazy<tokio::sync::Mutex<Option<ServerState>>> = Lazy::new(|| tokio::sync::Mutex::new(None));
+
+struct ServerState {
+    port: u16,
+    task: tokio::task::JoinHandle<()>,
+}
+
+type ApiResult = Result<serde_json::Value, (u16, String)>;
+
+#[derive(Debug, Default, Deserialize)]
+struct PinRequest {
+    pinned: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FavoriteRequest {
+    group_name: Option<String>,
+}
+
+pub fn is_running() -> bool {
+    if let Ok(state) = SERVER.try_lock() {
+        return state
+            .as_ref()
+            .map(|server| !server.task.is_finished())
+            .unwrap_or(false);
+    }
+    false
+}
+
+pub fn running_port() -> Option<u16> {
+    SERVER
+        .try_lock()
+        .ok()
+        .and_then(|state| {
+            state
+                .as_ref()
+                .filter(|server| !server.task.is_finished())
+                .map(|server| server.port)
+        })
+}
+
+pub async fn start(app: AppHandle, port: u16) -> Result<u16, String> {
+    let mut state = SERVER.lock().await;
+    if let Some(server) = state.as_ref() {
+        if server.port == port && !server.task.is_finished() {
+            return Ok(port);
+        }
+    }
+
+    if let Some(server) = state.take() {
+        server.task.abort();
+    }
+
+    // 只绑定回环地址，不对局域网开放
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+        .await
+        .map_err(|e| format!("本机控制接口启动失败: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| e.to_string())?
+        .port();
+    let task = tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                break;
+            };
+            let app = app.clone();
+            tokio::spawn(async move {
+                let _ = handle_client(stream, app).await;
+            });
+        }
+    });
+    *state = Some(ServerState { port, task });
+    Ok(port)
+}
+
+pub async fn stop() {
+    let mut state = SERVER.lock().await;
+    if let Some(server) = state.take() {
+        server.task.abort();
+    }
+}
+
+async fn handle_client(mut stream: tokio::net::TcpStream, app: AppHandle) -> Result<(), String> {
+    let mut request = read_request(&mut stream).await?;
+    if let Err(response) = check_request(&request) {
+        return write_response(&mut stream, response).await;
+    }
+    if request.method == "GET" && request.path == EVENTS_PATH {
+        return stream_captured_items(stream).await;
+    }
+    if request.content_length > MAX_REQUEST_BODY_SIZE {
+        return write_response(&mut stream, error_response(413, "请求体过大")).await;
+    }
+    read_request_body(&mut request, &mut stream, MAX_REQUEST_BODY_SIZE).await?;
+
+    let response = tokio::task::spawn_blocking(move || route(&request, &app))
+        .await
+        .unwrap_or_else(|e| error_response(500, &format!("处理请求失败: {}", e)));
+    write_response(&mut stream, response).await
+}
+
+fn check_request(request: &HttpRequest) -> Result<(), HttpResponse> {
+    // 校验 Host，防止网页通过 DNS 重绑定访问本机接口
+    let host = request_header(request, "host").unwrap_or_default();
+    if !is_loopback_host(&host) {
+        return Err(error_response(403, "仅允许本机访问"));
+    }
+
+    let expected = super::token().map_err(|e| error_response(500, &e))?;
+    let provided = request_header(request, "authorization")
+        .and_then(|value| value.strip_prefix("Bearer ").map(|token| token.trim().to_string()))
+        .unwrap_or_default();
+    if !constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+        return Err(error_response(401, "令牌无效"));
+    }
+    Ok(())
+}
+
+fn is_loopback_host(host: &str) -> bool {
+    let name = match host.rsplit_once(':') {
+        Some((name, port)) if !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => host,
+    };
+    matches!(
+        name.to_ascii_lowercase().as_str(),
+        "127.0.0.1" | "localhost" | "[::1]"
+    )
+}
+
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+    left.iter().zip(right).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+fn route(request: &HttpRequest, app: &AppHandle) -> HttpResponse {
+    let method = request.method.as_str();
+    let result = if request.path == ITEMS_PATH {
+        match method {
+            "GET" => list_items(request),
+            _ => Err((404, "未找到接口".to_string())),
+        }
+    } else if let Some(rest) = request.path.strip_prefix(ITEMS_PREFIX) {
+        let (id, action) = rest.split_once('/').unwrap_or((rest, ""));
+        match id.parse::<i64>() {
+            Ok(id) => match (method, action) {
+                ("GET", "") => get_item(id),
+                ("DELETE", "") => delete_item(id, app),
+                ("POST", "copy") => copy_item(id),
+                ("POST", "pin") => pin_item(id, request, app),
+                ("POST", "favorite") => favorite_item(id, request, app),
+                _ => Err((404, "未找到接口".to_string())),
+            },
+            Err(_) => Err((400, format!("无效的条目 ID: {}", id))),
+        }
+    } else {
+        Err((404, "未找到接口".to_string()))
+    };
+
+    match result {
+        Ok(value) => json_response(200, value),
+        Err((status_code, message)) => error_response(status_code, &message),
+    }
+}
+
+fn list_items(request: &HttpRequest) -> ApiResult {
+    let search_mode = match query_value(request, "search_mode").as_deref() {
+        Some("regex") => SearchMode::Regex,
+        Some("fuzzy") => SearchMode::Fuzzy,
+        _ => SearchMode::Text,
+    };
+    let params = QueryParams {
+        offset: query_value(request, "offset")
+            .and_then(|value| value.parse::<i64>().ok())
+            .unwrap_or(0)
+            .max(0),
+        limit: query_value(request, "limit")
+            .and_then(|value| value.parse::<i64>().ok())
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE),
+        search: query_value(request, "search").filter(|value| !value.is_empty()),
+        content_type: query_value(request, "content_type").filter(|value| !value.is_empty()),
+        search_mode,
+        ..Default::default()
+    };
+    let result = crate::services::database::query_clipboard_items(params).map_err(internal_error)?;
+    to_json(&result)
+}
+
+fn get_item(id: i64) -> ApiResult {
+    let mut item = load_item(id)?;
+    crate::commands::clipboard::hydrate_clipboard_item_for_ui(&mut item);
+    let raw_formats = crate::services::database::get_clipboard_data_items("clipboard", &id.to_string())
+        .map_err(internal_error)?
+        .into_iter()
+        .map(|format| {
+            serde_json::json!({
+                "format_name": format.format_name,
+                "is_primary": format.is_primary,
+                "data_base64": general_purpose::STANDARD.encode(&format.raw_data),
+            })
+        })
+        .collect::<Vec<_>>();
+    Ok(serde_json::json!({ "item": item, "raw_formats": raw_formats }))
+}
+
+fn delete_item(id: i64, app: &AppHandle) -> ApiResult {
+    load_item(id)?;
+    crate::commands::clipboard::delete_clipboard_item(id).map_err(internal_error)?;
+    let _ = crate::commands::window::emit_clipboard_updated_event(app, None);
+    Ok(serde_json::json!({ "deleted": true }))
+}
+
+fn copy_item(id: i64) -> ApiResult {
+    let item = load_item(id)?;
+    crate::services::paste::paste_handler::copy_clipboard_item(&item).map_err(internal_error)?;
+    Ok(serde_json::json!({ "copied": true }))
+}
+
+// 未指定 pinned 时切换置顶状态
+fn pin_item(id: i64, request: &HttpRequest, app: &AppHandle) -> ApiResult {
+    let item = load_item(id)?;
+    let input = parse_body::<PinRequest>(request)?;
+    let pinned = input.pinned.unwrap_or(!item.is_pinned);
+    if pinned != item.is_pinned {
+        crate::commands::clipboard::toggle_pin_clipboard_item(id).map_err(internal_error)?;
+        let _ = crate::commands::window::emit_clipboard_updated_event(app, None);
+    }
+    Ok(serde_json::json!({ "pinned": pinned }))
+}
+
+fn favorite_item(id: i64, request: &HttpRequest, app: &AppHandle) -> ApiResult {
+    load_item(id)?;
+    let input = parse_body::<FavoriteRequest>(request)?;
+    let group_name = input.group_name.filter(|name| !name.trim().is_empty());
+    let favorite = crate::commands::favorites::add_clipboard_to_favorites(id, group_name).map_err(internal_error)?;
+    let _ = crate::commands::window::emit_quick_texts_updated_event(app, None);
+    to_json(&favorite)
+}
+
+// 以 SSE 推送新捕获的内容，空闲时定期发送心跳并检测服务是否已停止
+async fn stream_captured_items(mut stream: tokio::net::TcpStream) -> Result<(), String> {
+    let mut receiver = super::subscribe_captured_items();
+    let header = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream; charset=utf-8\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n: connected\n\n";
+    stream.write_all(header.as_bytes()).await.map_err(|e| e.to_string())?;
+    stream.flush().await.map_err(|e| e.to_string())?;
+
+    loop {
+        let keepalive = std::time::Duration::from_secs(EVENT_KEEPALIVE_SECS);
+        let chunk = match tokio::time::timeout(keepalive, receiver.recv()).await {
+            Ok(Ok(json)) => format!("event: captured\ndata: {}\n\n", json),
+            Ok(Err(RecvError::Lagged(skipped))) => format!("event: lagged\ndata: {}\n\n", skipped),
+            Ok(Err(RecvError::Closed)) => return Ok(()),
+            Err(_) => {
+                if !is_running() {
+                    return Ok(());
+                }
+                ": keep-alive\n\n".to_string()
+            }
+        };
+        if stream.write_all(chunk.as_bytes()).await.is_err() || stream.flush().await.is_err() {
+            return Ok(());
+        }
+    }
+}
+
+fn load_item(id: i64) -> Result<ClipboardItem, (u16, String)> {
+    crate::services::database::get_clipboard_item_by_id(id)
+        .map_err(internal_error)?
+        .ok_or_else(|| (404, format!("剪贴板项不存在: {}", id)))
+}
+
+fn parse_body<T: serde::de::DeserializeOwned + Default>(request: &HttpRequest) -> Result<T, (u16, String)> {
+    if request.body.iter().all(|byte| byte.is_ascii_whitespace()) {
+        return Ok(T::default());
+    }
+    serde_json::from_slice(&request.body).map_err(|e| (400, format!("解析请求体失败: {}", e)))
+}
+
+fn query_value(request: &HttpRequest, name: &str) -> Option<String> {
+    request
+        .query
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| percent_decode(value))
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'+' => out.push(b' '),
+            b'%' if index + 2 < bytes.len() => {
+                match (hex_value(bytes[index + 1]), hex_value(bytes[index + 2])) {
+                    (Some(high), Some(low)) => {
+                        out.push(high << 4 | low);
+                        index += 2;
+                    }
+                    _ => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+        index += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|value| value as u8)
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> ApiResult {
+    serde_json::to_value(value).map_err(|e| internal_error(format!("序列化响应失败: {}", e)))
+}
+
+fn internal_error(message: String) -> (u16, String) {
+    (500, message)
+}
+
+fn error_response(status_code: u16, message: &str) -> HttpResponse {
+    json_response(status_code, serde_json::json!({ "message": message }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_only_loopback_hosts() {
+        assert!(is_loopback_host("127.0.0.1:35692"));
+        assert!(is_loopback_host("LOCALHOST"));
+        assert!(is_loopback_host("[::1]:35692"));
+        assert!(!is_loopback_host("evil.example.com:35692"));
+        assert!(!is_loopback_host(""));
+    }
+
+    #[test]
+    fn decodes_query_values() {
+        assert_eq!(percent_decode("hello+world%21"), "hello world!");
+        assert_eq!(percent_decode("%E4%BD%A0%E5%A5%BD"), "你好");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+}
@@ -0,0 +1,281 @@
+// 数据库内容列的封存与解封
+//
+// 文本列写成 "qcenc1:" 前缀加加密信封，clipboard_data.raw_data 在信封前加同样的前缀字节。
+// 附加数据（AAD）绑定表名、列名和条目 ID，密文被挪到其他条目时会解密失败。
+// 没有前缀的值视为明文：封存时加密、解封时原样保留，磁盘上残留的明文（如导入的数据）也能正常处理。
+
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection};
+
+use crate::services::database::search;
+use crate::services::webdav_sync::crypto::WebdavCryptoContext;
+
+pub const SEALED_PREFIX: &str = "qcenc1:";
+
+// (表名, 组成条目 ID 的列（以 '/' 连接）, 加密列)
+const SEALED_COLUMNS: &[(&str, &[&str], &[&str])] = &[
+    ("clipboard", &["id"], &["content", "html_content"]),
+    ("favorites", &["id"], &["title", "content", "html_content"]),
+    ("clipboard_data", &["id"], &["raw_data"]),
+    ("sync_versions", &["collection", "item_id"], &["base_title", "base_content", "base_html_content"]),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Seal,
+    Open,
+}
+
+fn aad(table: &str, column: &str, id: &str) -> String {
+    format!("local/{}/{}/{}", table, column, id)
+}
+
+fn is_sealed(value: &Value) -> bool {
+    match value {
+        Value::Text(text) => text.starts_with(SEALED_PREFIX),
+        Value::Blob(bytes) => bytes.starts_with(SEALED_PREFIX.as_bytes()),
+        _ => false,
+    }
+}
+
+fn seal_value(ctx: &WebdavCryptoContext, aad: &str, value: &Value) -> Result<Option<Value>, String> {
+    if is_sealed(value) {
+        return Ok(None);
+    }
+    let (plaintext, is_text) = match value {
+        Value::Text(text) => (text.as_bytes(), true),
+        Value::Blob(bytes) => (bytes.as_slice(), false),
+        _ => return Ok(None),
+    };
+    let envelope = ctx.encrypt_bytes(aad, plaintext)?;
+    let mut sealed = SEALED_PREFIX.as_bytes().to_vec();
+    sealed.extend_from_slice(&envelope);
+    Ok(Some(if is_text {
+        // 加密信封是 JSON 文本
+        Value::Text(String::from_utf8(sealed).map_err(|e| format!("编码加密内容失败: {}", e))?)
+    } else {
+        Value::Blob(sealed)
+    }))
+}
+
+fn open_value(ctx: &WebdavCryptoContext, aad: &str, value: &Value) -> Result<Option<Value>, String> {
+    if !is_sealed(value) {
+        return Ok(None);
+    }
+    let prefix_len = SEALED_PREFIX.len();
+    Ok(Some(match value {
+        Value::Text(text) => {
+            let plaintext = ctx.decrypt_bytes(aad, &text.as_bytes()[prefix_len..])?;
+            Value::Text(String::from_utf8(plaintext).map_err(|e| format!("解密后的内容不是有效文本: {}", e))?)
+        }
+        Value::Blob(bytes) => Value::Blob(ctx.decrypt_bytes(aad, &bytes[prefix_len..])?),
+        _ => return Ok(None),
+    }))
+}
+
+fn convert_table(
+    conn: &Connection,
+    ctx: &WebdavCryptoContext,
+    table: &str,
+    id_columns: &[&str],
+    columns: &[&str],
+    direction: Direction,
+) -> Result<usize, String> {
+    let db_err = |e: rusqlite::Error| format!("读写 {} 表失败: {}", table, e);
+
+    // 旧版本的数据库（如导入的备份）可能缺少部分表或列
+    let existing = conn
+        .prepare("SELECT name FROM pragma_table_info(?1)")
+        .and_then(|mut stmt| stmt.query_map([table], |row| row.get::<_, String>(0))?.collect::<Result<Vec<_>, _>>())
+        .map_err(db_err)?;
+    if !id_columns.iter().all(|column| existing.iter().any(|name| name == column)) {
+        return Ok(0);
+    }
+    let columns: Vec<&str> = columns.iter().copied().filter(|column| existing.iter().any(|name| name == column)).collect();
+    if columns.is_empty() {
+        return Ok(0);
+    }
+
+    // rowid 在事务内稳定，用于回写；AAD 使用不随 VACUUM 变化的条目 ID
+    let id_expr = id_columns
+        .iter()
+        .map(|column| format!("CAST({} AS TEXT)", column))
+        .collect::<Vec<_>>()
+        .join(" || '/' || ");
+    let sql = format!("SELECT rowid, {}, {} FROM {}", id_expr, columns.join(", "), table);
+    let mut stmt = conn.prepare(&sql).map_err(db_err)?;
+    let rows = stmt
+        .query_map([], |row| {
+            let rowid: i64 = row.get(0)?;
+            let id: String = row.get(1)?;
+            let values = (0..columns.len())
+                .map(|index| row.get::<_, Value>(index + 2))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((rowid, id, values))
+        })
+        .map_err(db_err)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(db_err)?;
+
+    let mut converted = 0;
+    for (rowid, id, values) in rows {
+        let mut assignments = Vec::new();
+        let mut new_values = Vec::new();
+        for (column, value) in columns.iter().zip(values.iter()) {
+            let aad = aad(table, column, &id);
+            let result = match direction {
+                Direction::Seal => seal_value(ctx, &aad, value)?,
+                Direction::Open => open_value(ctx, &aad, value)?,
+            };
+            if let Some(new_value) = result {
+                assignments.push(format!("{} = ?{}", column, new_values.len() + 1));
+                new_values.push(new_value);
+            }
+        }
+        if assignments.is_empty() {
+            continue;
+        }
+        new_values.push(Value::Integer(rowid));
+        let update = format!(
+            "UPDATE {} SET {} WHERE rowid = ?{}",
+            table,
+            assignments.join(", "),
+            new_values.len()
+        );
+        conn.execute(&update, params_from_iter(new_values.iter())).map_err(db_err)?;
+        converted += 1;
+    }
+    Ok(converted)
+}
+
+fn convert_all(conn: &Connection, ctx: &WebdavCryptoContext, direction: Direction) -> Result<usize, String> {
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| format!("开启事务失败: {}", e))?;
+    let mut converted = 0;
+    for (table, id_columns, columns) in SEALED_COLUMNS {
+        converted += convert_table(&tx, ctx, table, id_columns, columns, direction)?;
+    }
+    tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok(converted)
+}
+
+// 按列名和取值封存一行，供工作副本写回磁盘时使用；不在封存范围内的表原样返回
+pub fn seal_row(ctx: &WebdavCryptoContext, table: &str, columns: &[String], values: &mut [Value]) -> Result<(), String> {
+    let Some((_, id_columns, sealed_columns)) = SEALED_COLUMNS.iter().find(|(name, _, _)| *name == table) else {
+        return Ok(());
+    };
+    let position = |name: &str| columns.iter().position(|column| column == name);
+    let id = id_columns
+        .iter()
+        .map(|column| match position(column).map(|index| &values[index]) {
+            Some(Value::Integer(value)) => value.to_string(),
+            Some(Value::Text(value)) => value.clone(),
+            _ => String::new(),
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    for column in sealed_columns.iter() {
+        let Some(index) = position(column) else { continue };
+        if let Some(sealed) = seal_value(ctx, &aad(table, column, &id), &values[index])? {
+            values[index] = sealed;
+        }
+    }
+    Ok(())
+}
+
+// 加密磁盘上残留的明文内容列并删除检索索引。
+// 有内容被加密时压缩数据库，清掉 WAL 和空闲页中的明文残留；已全部是密文时不做压缩
+pub fn seal_database(conn: &Connection, ctx: &WebdavCryptoContext) -> Result<usize, String> {
+    search::drop_search_index(conn).map_err(|e| format!("删除检索索引失败: {}", e))?;
+    let converted = convert_all(conn, ctx, Direction::Seal)?;
+    if converted > 0 {
+        conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE); VACUUM; PRAGMA wal_checkpoint(TRUNCATE);")
+            .map_err(|e| format!("压缩数据库失败: {}", e))?;
+    }
+    Ok(converted)
+}
+
+// 解密全部内容列并重建检索索引，任一条目解密失败时整体回滚。
+// 加密启用期间只对内存中的工作副本调用，关闭加密时才对磁盘数据库调用
+pub fn open_database(conn: &Connection, ctx: &WebdavCryptoContext) -> Result<usize, String> {
+    search::drop_search_index(conn).map_err(|e| format!("删除检索索引失败: {}", e))?;
+    let converted = convert_all(conn, ctx, Direction::Open)?;
+    if let Err(e) = search::ensure_search_index(conn) {
+        eprintln!("{}", e);
+    }
+    Ok(converted)
+}
+
+// 只解密已封存的值，不重建检索索引；检索索引的触发器会随更新同步
+pub fn open_values(conn: &Connection, ctx: &WebdavCryptoContext) -> Result<usize, String> {
+    convert_all(conn, ctx, Direction::Open)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::webdav_sync::crypto::{context_for_config, create_config};
+
+    fn test_context(password: &str) -> WebdavCryptoContext {
+        let mut config = create_config();
+        config.kdf.memory_kib = 1024;
+        config.kdf.iterations = 1;
+        context_for_config("local-test", &config, password).unwrap()
+    }
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::services::database::migrations::run_migrations(&conn, None).unwrap();
+        search::ensure_search_index(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO clipboard (id, content, html_content, content_type, created_at, updated_at)
+                VALUES (1, 'secret text', '<b>secret</b>', 'rich_text', 1, 1);
+             INSERT INTO favorites (id, title, content, created_at, updated_at)
+                VALUES ('f1', 'title', 'favorite secret', 1, 1);
+             INSERT INTO clipboard_data (target_kind, target_id, format_name, raw_data, created_at, updated_at)
+                VALUES ('clipboard', '1', 'HTML Format', X'00FF10', 1, 1);",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn seals_and_opens_content_columns() {
+        let conn = setup();
+        let ctx = test_context("password-1");
+
+        assert_eq!(seal_database(&conn, &ctx).unwrap(), 3);
+        let (content, html): (String, String) = conn
+            .query_row("SELECT content, html_content FROM clipboard WHERE id = 1", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert!(content.starts_with(SEALED_PREFIX) && !content.contains("secret"));
+        assert!(html.starts_with(SEALED_PREFIX));
+        let raw: Vec<u8> = conn.query_row("SELECT raw_data FROM clipboard_data", [], |row| row.get(0)).unwrap();
+        assert!(raw.starts_with(SEALED_PREFIX.as_bytes()));
+        assert!(!search::is_search_index_ready());
+
+        // 重复封存不会二次加密
+        assert_eq!(seal_database(&conn, &ctx).unwrap(), 0);
+
+        assert_eq!(open_database(&conn, &ctx).unwrap(), 3);
+        let title: String = conn.query_row("SELECT title FROM favorites WHERE id = 'f1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(title, "title");
+        let raw: Vec<u8> = conn.query_row("SELECT raw_data FROM clipboard_data", [], |row| row.get(0)).unwrap();
+        assert_eq!(raw, vec![0x00, 0xFF, 0x10]);
+        let hits: i64 = conn
+            .query_row("SELECT COUNT(*) FROM clipboard_fts WHERE clipboard_fts MATCH '\"secret\"'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(hits, 1);
+    }
+
+    #[test]
+    fn wrong_key_leaves_sealed_data_untouched() {
+        let conn = setup();
+        seal_database(&conn, &test_context("password-1")).unwrap();
+
+        assert!(open_database(&conn, &test_context("password-2")).is_err());
+        let content: String = conn.query_row("SELECT content FROM favorites WHERE id = 'f1'", [], |row| row.get(0)).unwrap();
+        assert!(content.starts_with(SEALED_PREFIX));
+    }
+}
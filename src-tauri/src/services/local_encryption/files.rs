@@ -0,0 +1,169 @@
+// 图片文件的封存与解封
+//
+// 封存后的文件以 "QCLENC1\n" 开头，后接加密信封，AAD 为相对数据目录的路径。
+// 先写临时文件再替换，中途退出时原文件保持完整；已处理的文件会被跳过，可以重复执行。
+// 加密启用期间文件始终是密文，读取时在内存中解密，只有关闭加密时才整体解封。
+
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::services::webdav_sync::crypto::WebdavCryptoContext;
+
+const FILE_MAGIC: &[u8] = b"QCLENC1\n";
+const TEMP_SUFFIX: &str = ".qclenc-tmp";
+
+// 需要封存的目录（相对数据目录）
+pub const SEALED_DIRS: &[&str] = &["clipboard_images"];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Seal,
+    Open,
+}
+
+pub fn is_sealed_bytes(bytes: &[u8]) -> bool {
+    bytes.starts_with(FILE_MAGIC)
+}
+
+// 只读取文件头判断是否已封存
+pub fn is_sealed_file(path: &Path) -> bool {
+    let mut header = [0u8; FILE_MAGIC.len()];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .map(|_| is_sealed_bytes(&header))
+        .unwrap_or(false)
+}
+
+// 文件在封存目录中时返回其 AAD（相对数据目录的路径）
+pub fn sealed_aad(data_dir: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(data_dir).ok()?;
+    let mut components = relative.components();
+    let dir_name = components.next()?.as_os_str().to_str()?;
+    let name = components.next()?.as_os_str().to_str()?;
+    if components.next().is_some() || !SEALED_DIRS.contains(&dir_name) {
+        return None;
+    }
+    Some(format!("{}/{}", dir_name, name))
+}
+
+pub fn seal_bytes(ctx: &WebdavCryptoContext, aad: &str, bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut output = FILE_MAGIC.to_vec();
+    output.extend_from_slice(&ctx.encrypt_bytes(aad, bytes)?);
+    Ok(output)
+}
+
+pub fn open_bytes(ctx: &WebdavCryptoContext, aad: &str, bytes: &[u8]) -> Result<Vec<u8>, String> {
+    ctx.decrypt_bytes(aad, &bytes[FILE_MAGIC.len()..])
+        .map_err(|e| format!("{} ({})", e, aad))
+}
+
+// 先写临时文件再替换
+pub fn replace_file(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let temp = path.with_file_name(format!("{}{}", file_name, TEMP_SUFFIX));
+    fs::write(&temp, bytes).map_err(|e| format!("写入文件失败 {}: {}", temp.display(), e))?;
+    fs::rename(&temp, path).map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        format!("替换文件失败 {}: {}", path.display(), e)
+    })
+}
+
+fn convert_file(ctx: &WebdavCryptoContext, path: &Path, aad: &str, direction: Direction) -> Result<bool, String> {
+    // 先只读文件头，已处理的文件不必整体读取
+    if is_sealed_file(path) == (direction == Direction::Seal) {
+        return Ok(false);
+    }
+    let bytes = fs::read(path).map_err(|e| format!("读取文件失败 {}: {}", path.display(), e))?;
+    let sealed = is_sealed_bytes(&bytes);
+    let output = match direction {
+        Direction::Seal if !sealed => seal_bytes(ctx, aad, &bytes)?,
+        Direction::Open if sealed => open_bytes(ctx, aad, &bytes)?,
+        _ => return Ok(false),
+    };
+    replace_file(path, &output)?;
+    Ok(true)
+}
+
+// modified_since 不为空时只处理此后修改过的文件
+fn convert_dirs(
+    data_dir: &Path,
+    ctx: &WebdavCryptoContext,
+    direction: Direction,
+    modified_since: Option<SystemTime>,
+) -> Result<usize, String> {
+    let mut converted = 0;
+    for dir_name in SEALED_DIRS {
+        let dir = data_dir.join(dir_name);
+        let Ok(entries) = fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()).map(str::to_string) else { continue };
+            let Ok(metadata) = entry.metadata() else { continue };
+            if !metadata.is_file() {
+                continue;
+            }
+            if let (Some(since), Ok(modified)) = (modified_since, metadata.modified()) {
+                if modified < since {
+                    continue;
+                }
+            }
+            // 上次中断留下的临时文件，原文件仍完整
+            if name.ends_with(TEMP_SUFFIX) {
+                let _ = fs::remove_file(&path);
+                continue;
+            }
+            let aad = format!("{}/{}", dir_name, name);
+            if convert_file(ctx, &path, &aad, direction)? {
+                converted += 1;
+            }
+        }
+    }
+    Ok(converted)
+}
+
+// 封存目录中残留的明文文件，如导入、恢复快照或旧版本写入的图片
+pub fn seal_files(data_dir: &Path, ctx: &WebdavCryptoContext, modified_since: Option<SystemTime>) -> Result<usize, String> {
+    convert_dirs(data_dir, ctx, Direction::Seal, modified_since)
+}
+
+pub fn open_files(data_dir: &Path, ctx: &WebdavCryptoContext) -> Result<usize, String> {
+    convert_dirs(data_dir, ctx, Direction::Open, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::webdav_sync::crypto::{context_for_config, create_config};
+
+    #[test]
+    fn seals_and_opens_image_files() {
+        let data_dir = std::env::temp_dir().join(format!("qc-local-files-{}", uuid::Uuid::new_v4()));
+        let images = data_dir.join("clipboard_images");
+        fs::create_dir_all(&images).unwrap();
+        fs::write(images.join("a.png"), b"png-bytes").unwrap();
+
+        let mut config = create_config();
+        config.kdf.memory_kib = 1024;
+        config.kdf.iterations = 1;
+        let ctx = context_for_config("local-test", &config, "password").unwrap();
+
+        assert_eq!(seal_files(&data_dir, &ctx, None).unwrap(), 1);
+        let sealed = fs::read(images.join("a.png")).unwrap();
+        assert!(sealed.starts_with(FILE_MAGIC) && is_sealed_file(&images.join("a.png")));
+        assert_eq!(seal_files(&data_dir, &ctx, None).unwrap(), 0);
+
+        let aad = sealed_aad(&data_dir, &images.join("a.png")).unwrap();
+        assert_eq!(aad, "clipboard_images/a.png");
+        assert_eq!(open_bytes(&ctx, &aad, &sealed).unwrap(), b"png-bytes");
+        assert!(sealed_aad(&data_dir, &data_dir.join("pin_images").join("a.png")).is_none());
+
+        // 密文挪到其他路径后无法解开
+        assert!(convert_file(&ctx, &images.join("a.png"), "clipboard_images/b.png", Direction::Open).is_err());
+
+        assert_eq!(open_files(&data_dir, &ctx).unwrap(), 1);
+        assert_eq!(fs::read(images.join("a.png")).unwrap(), b"png-bytes");
+        let _ = fs::remove_dir_all(&data_dir);
+    }
+}
@@ -0,0 +1,477 @@
+// 本地数据静态加密
+//
+// 复用 WebDAV 端到端加密的 Argon2id + XChaCha20-Poly1305 实现：
+// - 数据使用随机生成的数据密钥加密，数据密钥由主密码派生的密钥包装后保存在数据目录的
+//   local_encryption.json 中，主密码本身不落盘；更换主密码只需重新包装数据密钥
+// - 磁盘上的数据库内容列、原始格式数据和图片文件始终是密文
+// - 解锁后数据库在内存工作副本中解密（见 workspace），图片读取时在内存中解密并缓存；
+//   空闲超时、手动锁定和退出时丢弃工作副本、图片缓存和密钥
+
+mod columns;
+mod files;
+mod workspace;
+
+pub use workspace::Workspace;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::time::SystemTime;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::services::database::connection::{
+    self, is_database_locked, set_database_locked, with_connection_ignoring_lock, DATABASE_LOCKED_ERROR,
+};
+use crate::services::get_data_directory;
+use crate::services::webdav_sync::crypto::{self, WebdavCryptoContext, WebdavE2eeConfig};
+
+pub const LOCAL_ENCRYPTION_CHANGED_EVENT: &str = "local-encryption-changed";
+
+const CONFIG_FILE: &str = "local_encryption.json";
+const KEY_SCOPE: &str = "local-encryption";
+const DATA_KEY_AAD: &str = "local/data-key";
+const MIN_PASSWORD_CHARS: usize = 6;
+const IDLE_CHECK_INTERVAL_SECS: u64 = 30;
+// 解密后图片的内存缓存上限，超出时整体清空
+const IMAGE_CACHE_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LocalEncryptionConfig {
+    crypto: WebdavE2eeConfig,
+    // 主密码派生的密钥包装后的数据密钥
+    wrapped_key: String,
+}
+
+#[derive(Default)]
+struct ImageCache {
+    entries: HashMap<PathBuf, Vec<u8>>,
+    bytes: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalEncryptionStatus {
+    pub enabled: bool,
+    pub locked: bool,
+}
+
+// 解锁期间持有的数据密钥；只在短时间内加锁，不与数据库连接的锁嵌套
+static SESSION: Lazy<Mutex<Option<WebdavCryptoContext>>> = Lazy::new(|| Mutex::new(None));
+// 启用、解锁、锁定等操作串行执行
+static OPERATION_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+static IMAGE_CACHE: Lazy<Mutex<ImageCache>> = Lazy::new(|| Mutex::new(ImageCache::default()));
+// 上次检查图片目录的时间，之后写入的明文图片在下次检查时封存
+static LAST_FILE_SWEEP: Lazy<Mutex<Option<SystemTime>>> = Lazy::new(|| Mutex::new(None));
+static LAST_ACTIVITY: AtomicI64 = AtomicI64::new(0);
+static WATCHER_STARTED: AtomicBool = AtomicBool::new(false);
+
+fn config_path() -> Result<PathBuf, String> {
+    Ok(get_data_directory()?.join(CONFIG_FILE))
+}
+
+fn read_config() -> Result<Option<LocalEncryptionConfig>, String> {
+    let path = config_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("读取本地加密配置失败: {}", e))?;
+    serde_json::from_str(&text)
+        .map(Some)
+        .map_err(|e| format!("本地加密配置已损坏: {}", e))
+}
+
+fn write_config(config: &LocalEncryptionConfig) -> Result<(), String> {
+    let path = config_path()?;
+    let temp = path.with_extension("json.tmp");
+    let json = serde_json::to_string_pretty(config).map_err(|e| format!("序列化本地加密配置失败: {}", e))?;
+    std::fs::write(&temp, json).map_err(|e| format!("写入本地加密配置失败: {}", e))?;
+    std::fs::rename(&temp, &path).map_err(|e| format!("写入本地加密配置失败: {}", e))
+}
+
+fn validate_new_password(password: &str) -> Result<(), String> {
+    if password.chars().count() < MIN_PASSWORD_CHARS {
+        return Err(format!("主密码至少需要 {} 个字符", MIN_PASSWORD_CHARS));
+    }
+    Ok(())
+}
+
+fn new_config(password: &str, data_key: &WebdavCryptoContext) -> Result<LocalEncryptionConfig, String> {
+    validate_new_password(password)?;
+    let crypto_config = crypto::create_config();
+    let wrapping_key = crypto::context_for_config(KEY_SCOPE, &crypto_config, password)?;
+    let wrapped = wrapping_key.wrap_key(DATA_KEY_AAD, data_key)?;
+    Ok(LocalEncryptionConfig {
+        crypto: crypto_config,
+        wrapped_key: String::from_utf8(wrapped).map_err(|e| format!("编码数据密钥失败: {}", e))?,
+    })
+}
+
+// 用主密码解开数据密钥，密码错误时包装校验失败
+fn unwrap_data_key(config: &LocalEncryptionConfig, password: &str) -> Result<WebdavCryptoContext, String> {
+    if password.is_empty() {
+        return Err("请输入主密码".to_string());
+    }
+    let wrapping_key = crypto::context_for_config(KEY_SCOPE, &config.crypto, password)?;
+    wrapping_key
+        .unwrap_key(DATA_KEY_AAD, config.wrapped_key.as_bytes())
+        .map_err(|_| "主密码不正确".to_string())
+}
+
+fn require_config() -> Result<LocalEncryptionConfig, String> {
+    read_config()?.ok_or_else(|| "本地加密未启用".to_string())
+}
+
+fn require_unlocked() -> Result<(), String> {
+    if is_database_locked() {
+        return Err("请先解锁本地数据".to_string());
+    }
+    Ok(())
+}
+
+pub fn is_enabled() -> bool {
+    config_path().map(|path| path.exists()).unwrap_or(false)
+}
+
+pub fn status() -> LocalEncryptionStatus {
+    LocalEncryptionStatus {
+        enabled: is_enabled(),
+        locked: is_database_locked(),
+    }
+}
+
+pub fn record_activity() {
+    LAST_ACTIVITY.store(chrono::Local::now().timestamp(), Ordering::Relaxed);
+}
+
+// 初始化数据库之前调用：已启用加密时以锁定状态启动，等待输入主密码
+pub fn init_before_database() {
+    if is_enabled() {
+        set_database_locked(true);
+    }
+}
+
+fn current_key() -> Option<WebdavCryptoContext> {
+    SESSION.lock().clone()
+}
+
+fn clear_session() {
+    *SESSION.lock() = None;
+    *IMAGE_CACHE.lock() = ImageCache::default();
+    *LAST_FILE_SWEEP.lock() = None;
+    crypto::clear_cached_keys();
+}
+
+// 封存磁盘上残留的明文后建立内存工作副本，并封存图片目录中的明文文件
+fn open_sealed_workspace(ctx: &WebdavCryptoContext) -> Result<(), String> {
+    let data_dir = get_data_directory()?;
+    let sweep_started = SystemTime::now();
+    connection::open_workspace(|disk| {
+        columns::seal_database(disk, ctx)?;
+        Workspace::open(disk, ctx)
+    })?;
+    files::seal_files(&data_dir, ctx, None)?;
+    *LAST_FILE_SWEEP.lock() = Some(sweep_started);
+    Ok(())
+}
+
+// 封存上次检查后写入图片目录的明文文件
+fn sweep_new_files(ctx: &WebdavCryptoContext) -> Result<usize, String> {
+    let data_dir = get_data_directory()?;
+    let sweep_started = SystemTime::now();
+    let since = *LAST_FILE_SWEEP.lock();
+    let sealed = files::seal_files(&data_dir, ctx, since)?;
+    *LAST_FILE_SWEEP.lock() = Some(sweep_started);
+    Ok(sealed)
+}
+
+// 把磁盘上的数据库和图片解密为明文，用于关闭加密或启用失败时回退
+fn open_on_disk(ctx: &WebdavCryptoContext) -> Result<(), String> {
+    connection::close_workspace()?;
+    with_connection_ignoring_lock(|disk| columns::open_database(disk, ctx))?;
+    files::open_files(&get_data_directory()?, ctx)?;
+    Ok(())
+}
+
+// 启用时立即封存已有的数据库内容和图片
+pub fn enable(password: &str) -> Result<(), String> {
+    let _guard = OPERATION_LOCK.lock();
+    if is_enabled() {
+        return Err("本地加密已启用".to_string());
+    }
+    let data_key = crypto::random_context();
+    write_config(&new_config(password, &data_key)?)?;
+    *SESSION.lock() = Some(data_key.clone());
+
+    if let Err(e) = open_sealed_workspace(&data_key) {
+        // 已经封存的部分用同一把密钥解开，回到未启用状态
+        let rollback = open_on_disk(&data_key).and_then(|_| {
+            std::fs::remove_file(config_path()?).map_err(|e| format!("删除本地加密配置失败: {}", e))
+        });
+        match rollback {
+            Ok(()) => clear_session(),
+            Err(rollback_error) => eprintln!("启用本地加密失败后回退失败: {}", rollback_error),
+        }
+        return Err(e);
+    }
+    record_activity();
+    Ok(())
+}
+
+pub fn unlock(password: &str) -> Result<(), String> {
+    let _guard = OPERATION_LOCK.lock();
+    let config = require_config()?;
+    if !is_database_locked() {
+        return Ok(());
+    }
+    let data_key = unwrap_data_key(&config, password)?;
+    *SESSION.lock() = Some(data_key.clone());
+    if let Err(e) = open_sealed_workspace(&data_key) {
+        let _ = connection::close_workspace();
+        clear_session();
+        return Err(e);
+    }
+    set_database_locked(false);
+    record_activity();
+    Ok(())
+}
+
+// 丢弃工作副本、图片缓存和密钥；未启用或已锁定时不做任何事。
+// 工作副本中的改动或新写入的图片未能封存时保持解锁，密钥不会被清除
+pub fn lock() -> Result<bool, String> {
+    let _guard = OPERATION_LOCK.lock();
+    if !is_enabled() || is_database_locked() {
+        return Ok(false);
+    }
+    let data_key = current_key().ok_or("本地加密会话不存在，请重新解锁")?;
+
+    // 先拒绝新的访问，正在进行的操作结束后才能拿到工作副本
+    set_database_locked(true);
+    if let Err(e) = connection::close_workspace().and_then(|_| sweep_new_files(&data_key)) {
+        set_database_locked(false);
+        return Err(e);
+    }
+    drop(data_key);
+    clear_session();
+    Ok(true)
+}
+
+pub fn rekey(old_password: &str, new_password: &str) -> Result<(), String> {
+    let _guard = OPERATION_LOCK.lock();
+    let config = require_config()?;
+    require_unlocked()?;
+    let data_key = unwrap_data_key(&config, old_password)?;
+    write_config(&new_config(new_password, &data_key)?)?;
+    record_activity();
+    Ok(())
+}
+
+pub fn disable(password: &str) -> Result<(), String> {
+    let _guard = OPERATION_LOCK.lock();
+    let config = require_config()?;
+    require_unlocked()?;
+    let data_key = unwrap_data_key(&config, password)?;
+
+    // 解密期间拒绝访问，避免读到一半是密文的磁盘数据库
+    set_database_locked(true);
+    let result = open_on_disk(&data_key)
+        .and_then(|_| std::fs::remove_file(config_path()?).map_err(|e| format!("删除本地加密配置失败: {}", e)));
+    if let Err(e) = result {
+        // 磁盘上已解密的部分会在重新建立工作副本时再次封存
+        if let Err(reopen_error) = open_sealed_workspace(&data_key) {
+            eprintln!("关闭本地加密失败后恢复工作副本失败: {}", reopen_error);
+            clear_session();
+            return Err(e);
+        }
+        set_database_locked(false);
+        return Err(e);
+    }
+    clear_session();
+    set_database_locked(false);
+    Ok(())
+}
+
+// 重新打开数据库后调用（见 init_database）：已解锁时重新建立工作副本，否则保持锁定
+pub(crate) fn reopen_workspace() {
+    let Some(data_key) = current_key() else {
+        set_database_locked(true);
+        return;
+    };
+    if let Err(e) = open_sealed_workspace(&data_key) {
+        eprintln!("重新载入加密数据失败，已锁定: {}", e);
+        set_database_locked(true);
+        let _ = connection::close_workspace();
+        clear_session();
+    }
+}
+
+// 从快照或备份中复制条目前，先解密这些数据库临时副本中的密文；
+// 复制后条目 ID 会变化，按原 ID 绑定的密文无法在工作副本中解开
+pub fn open_database_copy(conn: &rusqlite::Connection) -> Result<usize, String> {
+    match current_key() {
+        Some(data_key) => columns::open_values(conn, &data_key),
+        None => Ok(0),
+    }
+}
+
+pub fn is_sealed_file(path: &Path) -> bool {
+    files::is_sealed_file(path)
+}
+
+// 读取图片等可能被封存的文件，封存的文件在内存中解密，结果在锁定前缓存
+pub fn read_file(path: &Path) -> Result<Vec<u8>, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("读取文件失败 {}: {}", path.display(), e))?;
+    if !files::is_sealed_bytes(&bytes) {
+        return Ok(bytes);
+    }
+    if let Some(cached) = IMAGE_CACHE.lock().entries.get(path) {
+        return Ok(cached.clone());
+    }
+    let data_key = current_key().ok_or(DATABASE_LOCKED_ERROR)?;
+    let aad = files::sealed_aad(&get_data_directory()?, path)
+        .ok_or_else(|| format!("加密文件不在数据目录中: {}", path.display()))?;
+    let plaintext = files::open_bytes(&data_key, &aad, &bytes)?;
+
+    let mut cache = IMAGE_CACHE.lock();
+    if cache.bytes + plaintext.len() > IMAGE_CACHE_MAX_BYTES {
+        *cache = ImageCache::default();
+    }
+    if plaintext.len() <= IMAGE_CACHE_MAX_BYTES {
+        cache.bytes += plaintext.len();
+        cache.entries.insert(path.to_path_buf(), plaintext.clone());
+    }
+    Ok(plaintext)
+}
+
+// 写入图片等文件：启用加密且位于封存目录时写入密文，锁定期间拒绝写入
+pub fn write_file(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    if !is_enabled() {
+        return std::fs::write(path, bytes).map_err(|e| format!("写入文件失败 {}: {}", path.display(), e));
+    }
+    let Some(aad) = files::sealed_aad(&get_data_directory()?, path) else {
+        return std::fs::write(path, bytes).map_err(|e| format!("写入文件失败 {}: {}", path.display(), e));
+    };
+    let data_key = current_key().ok_or(DATABASE_LOCKED_ERROR)?;
+    files::replace_file(path, &files::seal_bytes(&data_key, &aad, bytes)?)?;
+    IMAGE_CACHE.lock().entries.remove(path);
+    Ok(())
+}
+
+// qcimage 协议：前端显示封存的图片，只允许访问数据目录中的封存目录
+pub fn image_protocol_response(request_path: &str) -> tauri::http::Response<Vec<u8>> {
+    let path = PathBuf::from(percent_decode(request_path.trim_start_matches('/')));
+    let result = get_data_directory().and_then(|data_dir| match files::sealed_aad(&data_dir, &path) {
+        Some(_) => read_file(&path),
+        None => Err("不允许访问该文件".to_string()),
+    });
+    let builder = tauri::http::Response::builder().header("Access-Control-Allow-Origin", "*");
+    let response = match result {
+        Ok(bytes) => {
+            let mime = image::guess_format(&bytes)
+                .map(|format| format.to_mime_type())
+                .unwrap_or("application/octet-stream");
+            builder.status(200).header("Content-Type", mime).body(bytes)
+        }
+        Err(e) => builder.status(404).body(e.into_bytes()),
+    };
+    response.unwrap_or_default()
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' && index + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[index + 1..index + 3]).ok();
+            if let Some(value) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                out.push(value);
+                index += 3;
+                continue;
+            }
+        }
+        out.push(bytes[index]);
+        index += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+// 通知各窗口锁定状态变化并刷新列表
+pub fn notify_changed(app: &AppHandle) {
+    let _ = app.emit(LOCAL_ENCRYPTION_CHANGED_EVENT, status());
+    let _ = crate::commands::window::emit_clipboard_updated_event(app, None);
+    let _ = crate::commands::window::emit_quick_texts_updated_event(app, None);
+}
+
+// 退出前写回工作副本中的改动并清除密钥
+pub fn lock_on_exit() {
+    if let Err(e) = lock() {
+        eprintln!("退出时封存本地数据失败: {}", e);
+    }
+}
+
+pub fn init_idle_lock(app: AppHandle) {
+    if WATCHER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    record_activity();
+
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(IDLE_CHECK_INTERVAL_SECS));
+
+        loop {
+            interval.tick().await;
+
+            if is_database_locked() || !is_enabled() {
+                continue;
+            }
+            // 未经 write_file 写入的图片（如导入、恢复快照）在这里补封存
+            if let Some(data_key) = current_key() {
+                match tauri::async_runtime::spawn_blocking(move || sweep_new_files(&data_key)).await {
+                    Ok(Err(e)) => eprintln!("封存新图片失败: {}", e),
+                    Err(e) => eprintln!("封存新图片任务失败: {}", e),
+                    _ => {}
+                }
+            }
+
+            let idle_minutes = crate::services::get_settings().local_encryption_idle_minutes;
+            if idle_minutes == 0 {
+                continue;
+            }
+            let idle_secs = chrono::Local::now().timestamp() - LAST_ACTIVITY.load(Ordering::Relaxed);
+            if idle_secs < idle_minutes as i64 * 60 {
+                continue;
+            }
+
+            match tauri::async_runtime::spawn_blocking(lock).await {
+                Ok(Ok(true)) => notify_changed(&app),
+                Ok(Ok(false)) => {}
+                Ok(Err(e)) => eprintln!("空闲自动锁定失败: {}", e),
+                Err(e) => eprintln!("空闲自动锁定任务失败: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rekey_rewraps_the_same_data_key() {
+        let data_key = crypto::random_context();
+        let sealed = data_key.encrypt_bytes("item", b"secret").unwrap();
+
+        let config = new_config("password-1", &data_key).unwrap();
+        assert_eq!(unwrap_data_key(&config, "password-2").err().unwrap(), "主密码不正确");
+
+        let rekeyed = new_config("password-2", &unwrap_data_key(&config, "password-1").unwrap()).unwrap();
+        let data_key = unwrap_data_key(&rekeyed, "password-2").unwrap();
+        assert_eq!(data_key.decrypt_bytes("item", &sealed).unwrap(), b"secret");
+        assert!(unwrap_data_key(&rekeyed, "password-1").is_err());
+    }
+}
@@ -0,0 +1,303 @@
+// 解锁期间的内存工作副本
+//
+// 磁盘上的数据库始终保存密文。解锁时把磁盘数据库复制到内存连接中解密并建立检索索引，
+// 普通的数据库访问都在这份副本上进行。内存中的临时触发器按主键记录每次写入涉及的行，
+// 访问结束后把这些行重新封存写回磁盘。锁定时直接丢弃副本，磁盘上不会出现明文。
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use rusqlite::backup::Backup;
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection, OptionalExtension};
+
+use super::columns;
+use crate::services::webdav_sync::crypto::WebdavCryptoContext;
+
+// 写回时定位行用的列；没有声明主键的表使用 rowid
+struct TableShape {
+    columns: Vec<String>,
+    keys: Vec<String>,
+}
+
+pub struct Workspace {
+    conn: Connection,
+    ctx: WebdavCryptoContext,
+}
+
+impl Workspace {
+    // disk 中的内容列应已全部封存
+    pub fn open(disk: &Connection, ctx: &WebdavCryptoContext) -> Result<Self, String> {
+        let mut conn = Connection::open_in_memory().map_err(|e| format!("创建内存数据库失败: {}", e))?;
+        Backup::new(disk, &mut conn)
+            .and_then(|backup| backup.run_to_completion(256, Duration::ZERO, None))
+            .map_err(|e| format!("载入加密数据库失败: {}", e))?;
+        conn.execute_batch("PRAGMA foreign_keys = ON;")
+            .map_err(|e| format!("设置数据库参数失败: {}", e))?;
+
+        columns::open_database(&conn, ctx)?;
+        install_dirty_triggers(&conn)?;
+        Ok(Self { conn, ctx: ctx.clone() })
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    // 把记录下的改动封存后写回磁盘，返回写回的行数；失败时保留记录，下次重试
+    pub fn persist(&self, disk: &Connection) -> Result<usize, String> {
+        let db_err = |e: rusqlite::Error| format!("写回加密数据失败: {}", e);
+        let dirty = {
+            let mut stmt = self.conn.prepare("SELECT tbl, key FROM temp.qc_dirty").map_err(db_err)?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+                .map_err(db_err)?;
+            rows.collect::<Result<Vec<_>, _>>().map_err(db_err)?
+        };
+        if dirty.is_empty() {
+            return Ok(0);
+        }
+
+        let mut shapes: HashMap<String, TableShape> = HashMap::new();
+        let tx = disk.unchecked_transaction().map_err(db_err)?;
+        for (table, key) in &dirty {
+            if !shapes.contains_key(table) {
+                shapes.insert(table.clone(), table_shape(&self.conn, table)?);
+            }
+            let shape = &shapes[table];
+            let key_values = parse_key(key)?;
+            let condition = shape
+                .keys
+                .iter()
+                .enumerate()
+                .map(|(index, column)| format!("{} IS ?{}", column, index + 1))
+                .collect::<Vec<_>>()
+                .join(" AND ");
+
+            let select = format!("SELECT {} FROM main.{} WHERE {}", shape.columns.join(", "), table, condition);
+            let row = self
+                .conn
+                .query_row(&select, params_from_iter(key_values.iter()), |row| {
+                    (0..shape.columns.len()).map(|index| row.get::<_, Value>(index)).collect::<Result<Vec<_>, _>>()
+                })
+                .optional()
+                .map_err(db_err)?;
+
+            match row {
+                Some(mut values) => {
+                    columns::seal_row(&self.ctx, table, &shape.columns, &mut values)?;
+                    let placeholders = (1..=values.len()).map(|index| format!("?{}", index)).collect::<Vec<_>>();
+                    let insert = format!(
+                        "INSERT OR REPLACE INTO {} ({}) VALUES ({})",
+                        table,
+                        shape.columns.join(", "),
+                        placeholders.join(", ")
+                    );
+                    tx.execute(&insert, params_from_iter(values.iter())).map_err(db_err)?;
+                }
+                None => {
+                    let delete = format!("DELETE FROM {} WHERE {}", table, condition);
+                    tx.execute(&delete, params_from_iter(key_values.iter())).map_err(db_err)?;
+                }
+            }
+        }
+        // 自增计数器也要写回，否则在内存中插入又删除的行的 ID 会在重新载入后被复用
+        let sequences = {
+            let mut stmt = self.conn.prepare("SELECT name, seq FROM main.sqlite_sequence").map_err(db_err)?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+                .map_err(db_err)?;
+            rows.collect::<Result<Vec<_>, _>>().map_err(db_err)?
+        };
+        for (name, seq) in sequences {
+            tx.execute("UPDATE sqlite_sequence SET seq = MAX(seq, ?2) WHERE name = ?1", rusqlite::params![name, seq])
+                .map_err(db_err)?;
+        }
+        tx.commit().map_err(db_err)?;
+
+        self.conn.execute("DELETE FROM temp.qc_dirty", []).map_err(db_err)?;
+        Ok(dirty.len())
+    }
+}
+
+// 内存副本中需要写回的表：检索索引只存在于内存中，不写回
+fn tracked_tables(conn: &Connection) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT name FROM main.sqlite_master
+             WHERE type = 'table' AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\' AND name NOT LIKE '%\\_fts%' ESCAPE '\\'
+             ORDER BY name",
+        )
+        .map_err(|e| format!("读取表结构失败: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("读取表结构失败: {}", e))?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| format!("读取表结构失败: {}", e))
+}
+
+fn table_shape(conn: &Connection, table: &str) -> Result<TableShape, String> {
+    let mut stmt = conn
+        .prepare("SELECT name, pk FROM pragma_table_info(?1) ORDER BY cid")
+        .map_err(|e| format!("读取表结构失败: {}", e))?;
+    let rows = stmt
+        .query_map([table], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+        .map_err(|e| format!("读取表结构失败: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("读取表结构失败: {}", e))?;
+
+    let mut columns: Vec<String> = rows.iter().map(|(name, _)| name.clone()).collect();
+    let mut keyed: Vec<(i64, String)> = rows.into_iter().filter(|(_, pk)| *pk > 0).map(|(name, pk)| (pk, name)).collect();
+    keyed.sort();
+    let keys = if keyed.is_empty() {
+        columns.insert(0, "rowid".to_string());
+        vec!["rowid".to_string()]
+    } else {
+        keyed.into_iter().map(|(_, name)| name).collect()
+    };
+    Ok(TableShape { columns, keys })
+}
+
+fn install_dirty_triggers(conn: &Connection) -> Result<(), String> {
+    let mut sql = String::from(
+        "CREATE TEMP TABLE IF NOT EXISTS qc_dirty (tbl TEXT NOT NULL, key TEXT NOT NULL, PRIMARY KEY (tbl, key));",
+    );
+    for table in tracked_tables(conn)? {
+        let keys = table_shape(conn, &table)?.keys;
+        let key_of = |row: &str| {
+            let columns = keys.iter().map(|key| format!("{}.{}", row, key)).collect::<Vec<_>>().join(", ");
+            format!("INSERT OR IGNORE INTO qc_dirty (tbl, key) VALUES ('{}', json_array({}));", table, columns)
+        };
+        sql.push_str(&format!(
+            "CREATE TEMP TRIGGER IF NOT EXISTS qc_dirty_{table}_ai AFTER INSERT ON main.{table} BEGIN {new} END;
+             CREATE TEMP TRIGGER IF NOT EXISTS qc_dirty_{table}_au AFTER UPDATE ON main.{table} BEGIN {old} {new} END;
+             CREATE TEMP TRIGGER IF NOT EXISTS qc_dirty_{table}_ad AFTER DELETE ON main.{table} BEGIN {old} END;",
+            table = table,
+            new = key_of("new"),
+            old = key_of("old"),
+        ));
+    }
+    conn.execute_batch(&sql).map_err(|e| format!("创建变更记录触发器失败: {}", e))
+}
+
+fn parse_key(key: &str) -> Result<Vec<Value>, String> {
+    let values: Vec<serde_json::Value> = serde_json::from_str(key).map_err(|e| format!("变更记录无效: {}", e))?;
+    Ok(values
+        .into_iter()
+        .map(|value| match value {
+            serde_json::Value::Number(number) => number
+                .as_i64()
+                .map(Value::Integer)
+                .unwrap_or_else(|| Value::Real(number.as_f64().unwrap_or_default())),
+            serde_json::Value::String(text) => Value::Text(text),
+            serde_json::Value::Null => Value::Null,
+            other => Value::Text(other.to_string()),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::webdav_sync::crypto::{context_for_config, create_config};
+
+    fn test_context() -> WebdavCryptoContext {
+        let mut config = create_config();
+        config.kdf.memory_kib = 1024;
+        config.kdf.iterations = 1;
+        context_for_config("local-test", &config, "password").unwrap()
+    }
+
+    fn sealed_disk(ctx: &WebdavCryptoContext) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::services::database::migrations::run_migrations(&conn, None).unwrap();
+        conn.execute_batch(
+            "INSERT INTO clipboard (id, content, content_type, created_at, updated_at)
+                VALUES (1, 'first secret', 'text', 1, 1), (2, 'second secret', 'text', 1, 1);
+             INSERT INTO favorites (id, title, content, created_at, updated_at)
+                VALUES ('f1', 'title', 'favorite secret', 1, 1);",
+        )
+        .unwrap();
+        columns::seal_database(&conn, ctx).unwrap();
+        conn
+    }
+
+    fn disk_content(disk: &Connection, id: i64) -> Option<String> {
+        disk.query_row("SELECT content FROM clipboard WHERE id = ?1", [id], |row| row.get(0))
+            .optional()
+            .unwrap()
+    }
+
+    #[test]
+    fn writes_through_to_disk_without_plaintext() {
+        let ctx = test_context();
+        let disk = sealed_disk(&ctx);
+        let workspace = Workspace::open(&disk, &ctx).unwrap();
+        let memory = workspace.connection();
+
+        let content: String = memory.query_row("SELECT content FROM clipboard WHERE id = 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(content, "first secret");
+        let hits: i64 = memory
+            .query_row("SELECT COUNT(*) FROM clipboard_fts WHERE clipboard_fts MATCH '\"secret\"'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(hits, 2);
+        // 磁盘上没有检索索引
+        let fts: i64 = disk
+            .query_row("SELECT COUNT(*) FROM sqlite_master WHERE name LIKE '%\\_fts%' ESCAPE '\\'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(fts, 0);
+
+        memory
+            .execute_batch(
+                "UPDATE clipboard SET content = 'changed secret' WHERE id = 1;
+                 DELETE FROM clipboard WHERE id = 2;
+                 INSERT INTO clipboard (id, content, content_type, created_at, updated_at) VALUES (3, 'new secret', 'text', 2, 2);
+                 UPDATE favorites SET title = 'renamed' WHERE id = 'f1';",
+            )
+            .unwrap();
+        assert_eq!(workspace.persist(&disk).unwrap(), 4);
+        assert_eq!(workspace.persist(&disk).unwrap(), 0);
+
+        assert_eq!(disk_content(&disk, 2), None);
+        for id in [1, 3] {
+            let content = disk_content(&disk, id).unwrap();
+            assert!(content.starts_with(columns::SEALED_PREFIX) && !content.contains("secret"));
+        }
+        let title: String = disk.query_row("SELECT title FROM favorites WHERE id = 'f1'", [], |row| row.get(0)).unwrap();
+        assert!(title.starts_with(columns::SEALED_PREFIX));
+
+        // 重新载入后内容与内存副本一致
+        let reopened = Workspace::open(&disk, &ctx).unwrap();
+        let contents: Vec<String> = reopened
+            .connection()
+            .prepare("SELECT content FROM clipboard ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(contents, vec!["changed secret", "new secret"]);
+    }
+
+    #[test]
+    fn failed_write_back_keeps_pending_changes() {
+        let ctx = test_context();
+        let disk = sealed_disk(&ctx);
+        let workspace = Workspace::open(&disk, &ctx).unwrap();
+        workspace
+            .connection()
+            .execute("UPDATE clipboard SET content = 'changed secret' WHERE id = 1", [])
+            .unwrap();
+
+        disk.execute_batch("PRAGMA query_only = ON;").unwrap();
+        assert!(workspace.persist(&disk).is_err());
+        disk.execute_batch("PRAGMA query_only = OFF;").unwrap();
+
+        assert_eq!(workspace.persist(&disk).unwrap(), 1);
+        let reopened = Workspace::open(&disk, &ctx).unwrap();
+        let content: String = reopened
+            .connection()
+            .query_row("SELECT content FROM clipboard WHERE id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(content, "changed secret");
+    }
+}
@@ -277,6 +277,7 @@ fn load_page(page: i64) -> Result<PanelPage, String> {
         limit: PANEL_PAGE_SIZE,
         search: None,
         content_type: None,
+        ..Default::default()
     })?;
 
     let total_pages = if result.total_count == 0 {
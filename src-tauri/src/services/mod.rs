@@ -7,6 +7,8 @@ pub mod system;
 pub mod paste;
 pub mod sound;
 pub mod image_library;
+pub mod local_api;
+pub mod local_encryption;
 pub mod low_memory;
 pub mod memory;
 pub mod store;
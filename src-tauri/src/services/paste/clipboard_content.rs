@@ -199,7 +199,10 @@ fn set_clipboard_image_file_impl(path: &str) -> Result<(), String> {
         }
     }
 
-    let image = image::open(path).map_err(|e| format!("读取图片失败: {}", e))?;
+    // 启用本地加密时图片文件是密文：在内存中解密，且不提供文件路径
+    let sealed = crate::services::local_encryption::is_sealed_file(Path::new(path));
+    let bytes = crate::services::local_encryption::read_file(Path::new(path))?;
+    let image = image::load_from_memory(&bytes).map_err(|e| format!("读取图片失败: {}", e))?;
 
     let mut png_data = Vec::new();
     image
@@ -207,7 +210,7 @@ fn set_clipboard_image_file_impl(path: &str) -> Result<(), String> {
         .map_err(|e| format!("编码 PNG 失败: {}", e))?;
 
     let dib_data = build_dib_data(&image)?;
-    let hdrop_data = build_hdrop_data(path);
+    let hdrop_data = if sealed { None } else { Some(build_hdrop_data(path)) };
 
     let png_format = {
         let mut name: Vec<u16> = "PNG".encode_utf16().collect();
@@ -227,8 +230,10 @@ fn set_clipboard_image_file_impl(path: &str) -> Result<(), String> {
     let png_handle = alloc_global_bytes(&png_data)?;
     set_clipboard_handle(png_format, png_handle, "PNG")?;
 
-    let hdrop_handle = alloc_global_bytes(&hdrop_data)?;
-    set_clipboard_handle(CF_HDROP, hdrop_handle, "CF_HDROP")?;
+    if let Some(hdrop_data) = hdrop_data {
+        let hdrop_handle = alloc_global_bytes(&hdrop_data)?;
+        set_clipboard_handle(CF_HDROP, hdrop_handle, "CF_HDROP")?;
+    }
 
     Ok(())
 }
@@ -272,10 +277,23 @@ fn build_dib_data(image: &image::DynamicImage) -> Result<Vec<u8>, String> {
 #[cfg(not(target_os = "windows"))]
 fn set_clipboard_image_file_impl(path: &str) -> Result<(), String> {
     let ctx = ClipboardContext::new().map_err(|e| format!("创建剪贴板上下文失败: {}", e))?;
+    if crate::services::local_encryption::is_sealed_file(Path::new(path)) {
+        return ctx
+            .set_image(sealed_image_data(path)?)
+            .map_err(|e| format!("设置图片到剪贴板失败: {}", e));
+    }
     ctx.set_files(vec![path.to_string()])
         .map_err(|e| format!("设置图片到剪贴板失败: {}", e))
 }
 
+// 解密封存的图片文件，作为图片数据写入剪贴板
+pub fn sealed_image_data(path: &str) -> Result<clipboard_rs::RustImageData, String> {
+    use clipboard_rs::common::RustImage;
+
+    let bytes = crate::services::local_encryption::read_file(Path::new(path))?;
+    clipboard_rs::RustImageData::from_bytes(&bytes).map_err(|e| format!("读取图片失败: {}", e))
+}
+
 // 设置剪贴板为纯文本
 pub fn set_clipboard_text(ctx: &ClipboardContext, text: &str) -> Result<(), String> {
     let _guard = crate::services::clipboard::pause_clipboard_monitor_for(500);
@@ -5,7 +5,7 @@ use enigo::{Enigo, Direction, Key, Keyboard, Settings};
 use windows::Win32::UI::Input::KeyboardAndMouse::{
     GetAsyncKeyState, SendInput, INPUT, INPUT_KEYBOARD, KEYBDINPUT,
     KEYBD_EVENT_FLAGS, KEYEVENTF_EXTENDEDKEY, KEYEVENTF_KEYUP, VK_INSERT, VK_MENU,
-    VK_CONTROL, VK_SHIFT, VK_V,
+    VK_CONTROL, VK_LEFT, VK_SHIFT, VK_V,
 };
 
 #[cfg(target_os = "windows")]
@@ -244,3 +244,26 @@ pub fn simulate_paste() -> Result<(), String> {
     Ok(())
 }
 
+// 光标左移指定次数（模板 {cursor} 定位）
+#[cfg(target_os = "windows")]
+pub fn simulate_cursor_left(count: usize) -> Result<(), String> {
+    for _ in 0..count {
+        send_key_ex(VK_LEFT.0, false, true);
+        send_key_ex(VK_LEFT.0, true, true);
+    }
+    Ok(())
+}
+
+// 光标左移指定次数（模板 {cursor} 定位）
+#[cfg(not(target_os = "windows"))]
+pub fn simulate_cursor_left(count: usize) -> Result<(), String> {
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|e| format!("创建键盘模拟器失败: {}", e))?;
+
+    for _ in 0..count {
+        enigo.key(Key::LeftArrow, Direction::Click)
+            .map_err(|e| format!("按下方向键失败: {}", e))?;
+    }
+
+    Ok(())
+}
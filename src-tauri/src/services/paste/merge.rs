@@ -249,7 +249,8 @@ fn normalize_image_content(item: &ClipboardItem) -> Result<String, String> {
 }
 
 fn image_path_to_data_url(path: &str) -> Result<String, String> {
-    let bytes = std::fs::read(path).map_err(|e| format!("读取图片失败 [{}]: {}", path, e))?;
+    let bytes = crate::services::local_encryption::read_file(std::path::Path::new(path))
+        .map_err(|e| format!("读取图片失败 [{}]: {}", path, e))?;
     let image = image::ImageReader::new(Cursor::new(bytes))
         .with_guessed_format()
         .map_err(|e| format!("识别图片格式失败 [{}]: {}", path, e))?
@@ -268,7 +269,8 @@ fn image_path_to_data_url(path: &str) -> Result<String, String> {
 }
 
 fn image_path_to_data_url_fast(path: &str) -> Result<String, String> {
-    let bytes = std::fs::read(path).map_err(|e| format!("读取图片失败 [{}]: {}", path, e))?;
+    let bytes = crate::services::local_encryption::read_file(std::path::Path::new(path))
+        .map_err(|e| format!("读取图片失败 [{}]: {}", path, e))?;
     let format = image::guess_format(&bytes)
         .map_err(|e| format!("识别图片格式失败 [{}]: {}", path, e))?;
     let mime = image_format_to_mime(format)
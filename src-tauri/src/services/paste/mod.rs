@@ -4,6 +4,9 @@ pub mod text;
 pub mod keyboard;
 pub mod clipboard_content;
 pub mod merge;
+pub mod queue;
+pub mod template;
+pub mod transform;
 
 pub use options::PasteAction;
 pub use clipboard_content::{
@@ -2,6 +2,8 @@ use regex::Regex;
 
 use crate::services::database::{ClipboardDataItem, ClipboardItem, PasteOption};
 
+use super::transform::TextTransform;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PasteAction {
     PlainText,
@@ -10,6 +12,8 @@ pub enum PasteAction {
     AllFormats,
     ImageBundle,
     File,
+    // 以纯文本粘贴并做一次文本转换
+    Transform(TextTransform),
 }
 
 impl PasteAction {
@@ -21,7 +25,7 @@ impl PasteAction {
             "all_formats" => Some(Self::AllFormats),
             "image_bundle" => Some(Self::ImageBundle),
             "file" => Some(Self::File),
-            _ => None,
+            _ => TextTransform::from_id(id).map(Self::Transform),
         }
     }
 
@@ -33,11 +37,15 @@ impl PasteAction {
             Self::AllFormats => "all_formats",
             Self::ImageBundle => "image_bundle",
             Self::File => "file",
+            Self::Transform(transform) => transform.id(),
         }
     }
 
     pub fn kind(&self) -> &'static str {
-        self.id()
+        match self {
+            Self::Transform(_) => "transform",
+            _ => self.id(),
+        }
     }
 }
 
@@ -4,9 +4,11 @@ use crate::services::database::{get_clipboard_data_items, ClipboardDataItem, Cli
 use crate::utils::cf_html::generate_cf_html;
 
 use super::clipboard_content::{set_clipboard_contents, set_clipboard_files, set_clipboard_image_file};
-use super::keyboard::simulate_paste;
+use super::keyboard::{simulate_cursor_left, simulate_paste};
 use super::options::{resolve_default_paste_action, PasteAction};
 use super::text::paste_text;
+use super::template::{expand_template, has_placeholders, TemplateContext};
+use super::transform::TextTransform;
 
 fn emit_paste_count_updated(id: i64) {
     use tauri::Emitter;
@@ -95,7 +97,11 @@ pub fn paste_favorite_item_with_update(
     item: &ClipboardItem,
     favorite_id: &str,
 ) -> Result<(), String> {
-    let result = paste_item_internal(item, None, Some(favorite_id.to_string()), None, true, true);
+    let result = if is_template_item(item) {
+        paste_template_item(item)
+    } else {
+        paste_item_internal(item, None, Some(favorite_id.to_string()), None, true, true)
+    };
     if result.is_ok() {
         let _ = crate::services::database::increment_favorite_paste_count(favorite_id);
         emit_favorite_paste_count_updated(favorite_id);
@@ -137,6 +143,76 @@ pub fn paste_favorite_item_with_format(
     result
 }
 
+fn is_template_item(item: &ClipboardItem) -> bool {
+    let primary_type = item.content_type.split(',').next().unwrap_or(&item.content_type);
+    matches!(primary_type, "text" | "rich_text" | "link")
+        && !item.content.starts_with("files:")
+        && has_placeholders(&item.content)
+}
+
+// 模板占位符取值：当前剪贴板文本与输入对话框
+struct SystemTemplateContext {
+    prompted: bool,
+}
+
+impl TemplateContext for SystemTemplateContext {
+    fn now(&self) -> chrono::DateTime<chrono::Local> {
+        chrono::Local::now()
+    }
+
+    fn clipboard_text(&mut self) -> Result<String, String> {
+        use clipboard_rs::Clipboard;
+        let ctx = ClipboardContext::new().map_err(|e| format!("创建剪贴板上下文失败: {}", e))?;
+        Ok(ctx.get_text().unwrap_or_default())
+    }
+
+    fn prompt(&mut self, name: &str) -> Result<Option<String>, String> {
+        use crate::windows::plugins::input_dialog::window::{show_dialog, InputDialogOptions, InputType};
+
+        let app = crate::services::clipboard::get_app_handle()
+            .ok_or_else(|| "应用未初始化".to_string())?;
+        self.prompted = true;
+        tauri::async_runtime::block_on(show_dialog(
+            app,
+            InputDialogOptions {
+                title: "填写模板".to_string(),
+                message: format!("{}:", name),
+                placeholder: None,
+                default_value: None,
+                input_type: InputType::Text,
+                min_value: None,
+                max_value: None,
+            },
+        ))
+    }
+}
+
+// 展开收藏模板后以纯文本粘贴；原始格式中仍是未展开的内容，因此不使用
+fn paste_template_item(item: &ClipboardItem) -> Result<(), String> {
+    let mut context = SystemTemplateContext { prompted: false };
+    let expansion = expand_template(&item.content, &mut context)?;
+
+    // 输入对话框会抢走焦点，粘贴前切回目标窗口
+    if context.prompted {
+        let _ = crate::services::system::restore_last_focus();
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+
+    let expanded = ClipboardItem {
+        content: expansion.text,
+        html_content: None,
+        ..item.clone()
+    };
+    paste_item_internal(&expanded, None, None, Some(PasteAction::PlainText), true, false)?;
+
+    if let Some(offset) = expansion.cursor_offset.filter(|offset| *offset > 0) {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        simulate_cursor_left(offset)?;
+    }
+
+    Ok(())
+}
+
 fn paste_item_internal(
     item: &ClipboardItem,
     clipboard_id: Option<i64>,
@@ -268,9 +344,21 @@ fn build_payload_from_action(
         PasteAction::AllFormats => build_all_formats_payload(item, raw_formats),
         PasteAction::ImageBundle => build_image_bundle_payload(item),
         PasteAction::File => build_file_payload(item),
+        PasteAction::Transform(transform) => build_transform_payload(item, transform),
     }
 }
 
+fn build_transform_payload(
+    item: &ClipboardItem,
+    transform: TextTransform,
+) -> Result<Vec<RsClipboardContent>, String> {
+    if item.content.starts_with("files:") || item.content.is_empty() {
+        return Err("当前条目没有可用的纯文本内容".to_string());
+    }
+
+    Ok(vec![RsClipboardContent::Text(transform.apply(&item.content)?)])
+}
+
 fn build_plain_text_payload(
     item: &ClipboardItem,
     raw_formats: &[ClipboardDataItem],
@@ -449,11 +537,29 @@ fn build_image_bundle_payload(item: &ClipboardItem) -> Result<Vec<RsClipboardCon
 fn build_file_payload(item: &ClipboardItem) -> Result<Vec<RsClipboardContent>, String> {
     if item.content.starts_with("files:") {
         let paths = super::clipboard_content::parse_files_content_existing(&item.content)?;
-        return Ok(vec![RsClipboardContent::Files(paths)]);
+        return sealed_images_as_payload(paths);
     }
 
     let image_path = resolve_item_image_path(item)?;
-    Ok(vec![RsClipboardContent::Files(vec![image_path])])
+    sealed_images_as_payload(vec![image_path])
+}
+
+// 启用本地加密时图片缓存是密文，其他程序无法直接读取，改为写入解密后的图片数据
+fn sealed_images_as_payload(paths: Vec<String>) -> Result<Vec<RsClipboardContent>, String> {
+    if let [path] = paths.as_slice() {
+        if crate::services::local_encryption::is_sealed_file(std::path::Path::new(path)) {
+            let image = super::clipboard_content::sealed_image_data(path)?;
+            return Ok(vec![RsClipboardContent::Image(image)]);
+        }
+    }
+    let paths: Vec<String> = paths
+        .into_iter()
+        .filter(|path| !crate::services::local_encryption::is_sealed_file(std::path::Path::new(path)))
+        .collect();
+    if paths.is_empty() {
+        return Err("加密的图片无法作为文件粘贴".to_string());
+    }
+    Ok(vec![RsClipboardContent::Files(paths)])
 }
 
 fn resolve_item_image_path(item: &ClipboardItem) -> Result<String, String> {
@@ -0,0 +1,189 @@
+// 粘贴队列
+//
+// 先收集再逐条粘贴：在历史中选取多条加入队列，或在队列启用期间复制的内容自动入队。
+// 每次按下队列粘贴快捷键粘贴下一条并出队，顺序可选先进先出或后进先出。
+// 队列状态通过 paste-queue-changed 事件通知前端，并显示在托盘提示中。
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PasteQueueOrder {
+    #[default]
+    Fifo,
+    Lifo,
+}
+
+impl PasteQueueOrder {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "lifo" => PasteQueueOrder::Lifo,
+            _ => PasteQueueOrder::Fifo,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PasteQueueOrder::Fifo => "fifo",
+            PasteQueueOrder::Lifo => "lifo",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PasteQueueStatus {
+    pub armed: bool,
+    pub order: PasteQueueOrder,
+    pub item_ids: Vec<i64>,
+}
+
+#[derive(Debug, Default)]
+struct PasteQueue {
+    armed: bool,
+    items: VecDeque<i64>,
+}
+
+impl PasteQueue {
+    // 同一条目重复入队时移到末尾，避免连续粘贴相同内容
+    fn push(&mut self, id: i64) {
+        self.items.retain(|existing| *existing != id);
+        self.items.push_back(id);
+    }
+
+    fn pop(&mut self, order: PasteQueueOrder) -> Option<i64> {
+        match order {
+            PasteQueueOrder::Fifo => self.items.pop_front(),
+            PasteQueueOrder::Lifo => self.items.pop_back(),
+        }
+    }
+
+    // 粘贴失败时放回原位置
+    fn restore(&mut self, id: i64, order: PasteQueueOrder) {
+        match order {
+            PasteQueueOrder::Fifo => self.items.push_front(id),
+            PasteQueueOrder::Lifo => self.items.push_back(id),
+        }
+    }
+}
+
+static PASTE_QUEUE: Lazy<Mutex<PasteQueue>> = Lazy::new(|| Mutex::new(PasteQueue::default()));
+
+fn current_order() -> PasteQueueOrder {
+    PasteQueueOrder::parse(&crate::services::get_settings().paste_queue_order)
+}
+
+pub fn get_paste_queue_status() -> PasteQueueStatus {
+    let queue = PASTE_QUEUE.lock();
+    PasteQueueStatus {
+        armed: queue.armed,
+        order: current_order(),
+        item_ids: queue.items.iter().copied().collect(),
+    }
+}
+
+// 启用后复制的内容会自动入队；关闭时保留已收集的条目
+pub fn set_paste_queue_armed(armed: bool) {
+    PASTE_QUEUE.lock().armed = armed;
+    notify_paste_queue_changed();
+}
+
+// 出队顺序保存在设置中，重启后保持
+pub fn set_paste_queue_order(order: PasteQueueOrder) -> Result<(), String> {
+    crate::services::settings::update_with(|settings| {
+        settings.paste_queue_order = order.as_str().to_string();
+    })?;
+    if let Some(app) = crate::services::clipboard::get_app_handle() {
+        use tauri::Emitter;
+        let _ = app.emit("settings-changed", serde_json::json!({ "pasteQueueOrder": order.as_str() }));
+    }
+    notify_paste_queue_changed();
+    Ok(())
+}
+
+pub fn enqueue_paste_queue_items(ids: &[i64]) {
+    {
+        let mut queue = PASTE_QUEUE.lock();
+        for id in ids {
+            queue.push(*id);
+        }
+    }
+    notify_paste_queue_changed();
+}
+
+pub fn clear_paste_queue() {
+    PASTE_QUEUE.lock().items.clear();
+    notify_paste_queue_changed();
+}
+
+// 监听到新内容入库后调用
+pub fn record_captured_item(id: i64) {
+    {
+        let mut queue = PASTE_QUEUE.lock();
+        if !queue.armed {
+            return;
+        }
+        queue.push(id);
+    }
+    notify_paste_queue_changed();
+}
+
+// 粘贴队列中的下一条；已被删除的条目直接跳过。队列为空时返回 false
+pub fn paste_next_queued_item() -> Result<bool, String> {
+    let order = current_order();
+
+    loop {
+        let Some(id) = PASTE_QUEUE.lock().pop(order) else {
+            notify_paste_queue_changed();
+            return Ok(false);
+        };
+
+        let Some(item) = crate::services::database::get_clipboard_item_by_id(id)? else {
+            continue;
+        };
+
+        let result = super::paste_handler::paste_clipboard_item_with_update(&item);
+        if let Err(e) = result {
+            PASTE_QUEUE.lock().restore(id, order);
+            notify_paste_queue_changed();
+            return Err(e);
+        }
+
+        notify_paste_queue_changed();
+        return Ok(true);
+    }
+}
+
+fn notify_paste_queue_changed() {
+    use tauri::Emitter;
+
+    let Some(app) = crate::services::clipboard::get_app_handle() else {
+        return;
+    };
+    let status = get_paste_queue_status();
+    crate::windows::tray::update_tray_tooltip(&app, &status);
+    let _ = app.emit("paste-queue-changed", status);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pops_in_configured_order_and_moves_duplicates_to_end() {
+        let mut queue = PasteQueue::default();
+        for id in [1, 2, 3, 1] {
+            queue.push(id);
+        }
+        assert_eq!(queue.items, VecDeque::from(vec![2, 3, 1]));
+
+        assert_eq!(queue.pop(PasteQueueOrder::Fifo), Some(2));
+        assert_eq!(queue.pop(PasteQueueOrder::Lifo), Some(1));
+        queue.restore(1, PasteQueueOrder::Lifo);
+        assert_eq!(queue.pop(PasteQueueOrder::Lifo), Some(1));
+        assert_eq!(queue.pop(PasteQueueOrder::Fifo), Some(3));
+        assert_eq!(queue.pop(PasteQueueOrder::Fifo), None);
+    }
+}
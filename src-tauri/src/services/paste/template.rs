@@ -0,0 +1,218 @@
+// 收藏模板展开
+//
+// 收藏内容中可以写占位符，粘贴前替换为实际值：
+// {date} {date:%Y-%m-%d} {time} {time:%H:%M} {clipboard} {cursor} {uuid} {input:名称}
+// {{ 与 }} 输出字面量花括号；无法识别的 {...} 原样保留，避免误伤代码片段
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+
+pub const TEMPLATE_INPUT_CANCELLED_ERROR: &str = "已取消模板输入";
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_TIME_FORMAT: &str = "%H:%M:%S";
+
+// 占位符的外部取值来源，便于测试时替换
+pub trait TemplateContext {
+    fn now(&self) -> DateTime<Local>;
+    fn clipboard_text(&mut self) -> Result<String, String>;
+    // 返回 None 表示用户取消输入
+    fn prompt(&mut self, name: &str) -> Result<Option<String>, String>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateExpansion {
+    pub text: String,
+    // {cursor} 之后的字符数，粘贴后光标需左移的次数
+    pub cursor_offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Placeholder<'a> {
+    Date(&'a str),
+    Time(&'a str),
+    Clipboard,
+    Cursor,
+    Uuid,
+    Input(&'a str),
+}
+
+fn parse_placeholder(body: &str) -> Option<Placeholder<'_>> {
+    let (name, arg) = match body.split_once(':') {
+        Some((name, arg)) => (name.trim(), Some(arg)),
+        None => (body.trim(), None),
+    };
+    match (name, arg) {
+        ("date", arg) => Some(Placeholder::Date(arg.unwrap_or(DEFAULT_DATE_FORMAT))),
+        ("time", arg) => Some(Placeholder::Time(arg.unwrap_or(DEFAULT_TIME_FORMAT))),
+        ("clipboard", None) => Some(Placeholder::Clipboard),
+        ("cursor", None) => Some(Placeholder::Cursor),
+        ("uuid", None) => Some(Placeholder::Uuid),
+        ("input", Some(label)) if !label.trim().is_empty() => Some(Placeholder::Input(label.trim())),
+        _ => None,
+    }
+}
+
+// 是否包含可展开的占位符（只有转义花括号时不视为模板）
+pub fn has_placeholders(content: &str) -> bool {
+    let mut rest = content;
+    while !rest.is_empty() {
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            rest = &rest[2..];
+            continue;
+        }
+        if next_placeholder(rest).is_some() {
+            return true;
+        }
+        let c = rest.chars().next().unwrap_or_default();
+        rest = &rest[c.len_utf8()..];
+    }
+    false
+}
+
+// rest 以占位符开头时返回占位符及其字节长度
+fn next_placeholder(rest: &str) -> Option<(Placeholder<'_>, usize)> {
+    let body = rest.strip_prefix('{')?;
+    let end = body.find(['{', '}']).filter(|&end| body.as_bytes()[end] == b'}')?;
+    parse_placeholder(&body[..end]).map(|placeholder| (placeholder, end + 2))
+}
+
+pub fn expand_template(content: &str, context: &mut impl TemplateContext) -> Result<TemplateExpansion, String> {
+    let mut text = String::with_capacity(content.len());
+    let mut cursor_byte: Option<usize> = None;
+    let mut inputs: Vec<(String, String)> = Vec::new();
+    let mut clipboard: Option<String> = None;
+    let now = context.now();
+
+    let mut rest = content;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("{{") {
+            text.push('{');
+            rest = after;
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("}}") {
+            text.push('}');
+            rest = after;
+            continue;
+        }
+
+        if let Some((placeholder, consumed)) = next_placeholder(rest) {
+            match placeholder {
+                Placeholder::Date(format) | Placeholder::Time(format) => {
+                    text.push_str(&format_datetime(&now, format)?);
+                }
+                Placeholder::Clipboard => {
+                    if clipboard.is_none() {
+                        clipboard = Some(context.clipboard_text()?);
+                    }
+                    text.push_str(clipboard.as_deref().unwrap_or_default());
+                }
+                Placeholder::Cursor => {
+                    cursor_byte.get_or_insert(text.len());
+                }
+                Placeholder::Uuid => text.push_str(&uuid::Uuid::new_v4().to_string()),
+                Placeholder::Input(label) => {
+                    // 同名输入只询问一次
+                    let value = match inputs.iter().find(|(name, _)| name == label) {
+                        Some((_, value)) => value.clone(),
+                        None => {
+                            let value = context
+                                .prompt(label)?
+                                .ok_or_else(|| TEMPLATE_INPUT_CANCELLED_ERROR.to_string())?;
+                            inputs.push((label.to_string(), value.clone()));
+                            value
+                        }
+                    };
+                    text.push_str(&value);
+                }
+            }
+            rest = &rest[consumed..];
+            continue;
+        }
+
+        let c = rest.chars().next().unwrap_or_default();
+        text.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    let cursor_offset = cursor_byte.map(|byte| text[byte..].chars().count());
+    Ok(TemplateExpansion { text, cursor_offset })
+}
+
+// 格式串中有无法识别的说明符时报错，避免 chrono 格式化时 panic
+fn format_datetime(now: &DateTime<Local>, format: &str) -> Result<String, String> {
+    let items: Vec<Item> = StrftimeItems::new(format).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(format!("无效的日期格式: {}", format));
+    }
+    Ok(now.format_with_items(items.into_iter()).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    struct FakeContext {
+        prompts: Vec<String>,
+        answer: Option<String>,
+    }
+
+    impl TemplateContext for FakeContext {
+        fn now(&self) -> DateTime<Local> {
+            Local.with_ymd_and_hms(2024, 3, 5, 14, 7, 9).unwrap()
+        }
+
+        fn clipboard_text(&mut self) -> Result<String, String> {
+            Ok("剪贴板".to_string())
+        }
+
+        fn prompt(&mut self, name: &str) -> Result<Option<String>, String> {
+            self.prompts.push(name.to_string());
+            Ok(self.answer.clone())
+        }
+    }
+
+    fn context(answer: Option<&str>) -> FakeContext {
+        FakeContext { prompts: Vec::new(), answer: answer.map(str::to_string) }
+    }
+
+    #[test]
+    fn expands_builtin_placeholders() {
+        let mut ctx = context(None);
+        let expanded = expand_template("{date} {time:%H:%M} {date:%Y年%m月} [{clipboard}]", &mut ctx).unwrap();
+        assert_eq!(expanded.text, "2024-03-05 14:07 2024年03月 [剪贴板]");
+        assert_eq!(expanded.cursor_offset, None);
+
+        let uuid = expand_template("{uuid}", &mut ctx).unwrap().text;
+        assert_eq!(uuid.len(), 36);
+        assert!(expand_template("{date:%Q}", &mut ctx).is_err());
+    }
+
+    #[test]
+    fn keeps_unknown_braces_and_escapes() {
+        let mut ctx = context(None);
+        let code = "fn main() { println!(\"{}\", x); } {{date}} {unknown}";
+        assert!(!has_placeholders("fn main() { x }"));
+        assert_eq!(
+            expand_template(code, &mut ctx).unwrap().text,
+            "fn main() { println!(\"{}\", x); } {date} {unknown}"
+        );
+    }
+
+    #[test]
+    fn prompts_once_per_input_and_tracks_cursor() {
+        let mut ctx = context(Some("张三"));
+        let expanded = expand_template("您好 {input:姓名}，{cursor}此致 {input:姓名}", &mut ctx).unwrap();
+        assert_eq!(expanded.text, "您好 张三，此致 张三");
+        assert_eq!(expanded.cursor_offset, Some(5));
+        assert_eq!(ctx.prompts, vec!["姓名".to_string()]);
+
+        let mut cancelled = context(None);
+        assert_eq!(
+            expand_template("{input:姓名}", &mut cancelled),
+            Err(TEMPLATE_INPUT_CANCELLED_ERROR.to_string())
+        );
+    }
+}
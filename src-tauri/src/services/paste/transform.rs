@@ -0,0 +1,431 @@
+// 粘贴时文本转换
+//
+// 在选定的粘贴动作之上对纯文本做一次转换后再写入剪贴板，原始记录不受影响。
+// 动作 ID 形如 transform:upper_case，可通过 paste_content 的 action 参数或导航快捷键触发。
+
+use base64::{engine::general_purpose, Engine as _};
+
+pub const TRANSFORM_ACTION_PREFIX: &str = "transform:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextTransform {
+    UpperCase,
+    LowerCase,
+    TitleCase,
+    SnakeCase,
+    CamelCase,
+    KebabCase,
+    Trim,
+    CollapseWhitespace,
+    JsonPretty,
+    JsonMinify,
+    UrlEncode,
+    UrlDecode,
+    Base64Encode,
+    Base64Decode,
+    HtmlEncode,
+    HtmlDecode,
+    StringLiteral,
+    SortLines,
+    DedupLines,
+}
+
+impl TextTransform {
+    pub const ALL: [TextTransform; 19] = [
+        Self::UpperCase,
+        Self::LowerCase,
+        Self::TitleCase,
+        Self::SnakeCase,
+        Self::CamelCase,
+        Self::KebabCase,
+        Self::Trim,
+        Self::CollapseWhitespace,
+        Self::JsonPretty,
+        Self::JsonMinify,
+        Self::UrlEncode,
+        Self::UrlDecode,
+        Self::Base64Encode,
+        Self::Base64Decode,
+        Self::HtmlEncode,
+        Self::HtmlDecode,
+        Self::StringLiteral,
+        Self::SortLines,
+        Self::DedupLines,
+    ];
+
+    // 完整动作 ID（带 transform: 前缀）
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|transform| transform.id() == id)
+    }
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::UpperCase => "transform:upper_case",
+            Self::LowerCase => "transform:lower_case",
+            Self::TitleCase => "transform:title_case",
+            Self::SnakeCase => "transform:snake_case",
+            Self::CamelCase => "transform:camel_case",
+            Self::KebabCase => "transform:kebab_case",
+            Self::Trim => "transform:trim",
+            Self::CollapseWhitespace => "transform:collapse_whitespace",
+            Self::JsonPretty => "transform:json_pretty",
+            Self::JsonMinify => "transform:json_minify",
+            Self::UrlEncode => "transform:url_encode",
+            Self::UrlDecode => "transform:url_decode",
+            Self::Base64Encode => "transform:base64_encode",
+            Self::Base64Decode => "transform:base64_decode",
+            Self::HtmlEncode => "transform:html_encode",
+            Self::HtmlDecode => "transform:html_decode",
+            Self::StringLiteral => "transform:string_literal",
+            Self::SortLines => "transform:sort_lines",
+            Self::DedupLines => "transform:dedup_lines",
+        }
+    }
+
+    pub fn apply(&self, text: &str) -> Result<String, String> {
+        let result = match self {
+            Self::UpperCase => text.to_uppercase(),
+            Self::LowerCase => text.to_lowercase(),
+            Self::TitleCase => map_lines(text, title_case),
+            Self::SnakeCase => map_lines(text, |line| join_words(line, "_")),
+            Self::CamelCase => map_lines(text, camel_case),
+            Self::KebabCase => map_lines(text, |line| join_words(line, "-")),
+            Self::Trim => text.trim().to_string(),
+            Self::CollapseWhitespace => text.split_whitespace().collect::<Vec<_>>().join(" "),
+            Self::JsonPretty => reformat_json(text, true)?,
+            Self::JsonMinify => reformat_json(text, false)?,
+            Self::UrlEncode => url_encode(text),
+            Self::UrlDecode => url_decode(text)?,
+            Self::Base64Encode => general_purpose::STANDARD.encode(text.as_bytes()),
+            Self::Base64Decode => base64_decode(text)?,
+            Self::HtmlEncode => html_encode(text),
+            Self::HtmlDecode => html_decode(text),
+            Self::StringLiteral => serde_json::to_string(text)
+                .map_err(|e| format!("生成字符串字面量失败: {}", e))?,
+            Self::SortLines => {
+                let mut lines: Vec<&str> = text.lines().collect();
+                lines.sort();
+                join_lines(text, lines)
+            }
+            Self::DedupLines => {
+                let mut seen = std::collections::HashSet::new();
+                let lines: Vec<&str> = text.lines().filter(|line| seen.insert(*line)).collect();
+                join_lines(text, lines)
+            }
+        };
+
+        if result.is_empty() && !text.is_empty() {
+            return Err("转换后内容为空".to_string());
+        }
+        Ok(result)
+    }
+}
+
+// 按行转换，保留原有换行结构
+fn map_lines(text: &str, f: impl Fn(&str) -> String) -> String {
+    let lines: Vec<String> = text.lines().map(f).collect();
+    let mut result = lines.join(line_ending(text));
+    if text.ends_with('\n') {
+        result.push_str(line_ending(text));
+    }
+    result
+}
+
+fn join_lines(original: &str, lines: Vec<&str>) -> String {
+    let mut result = lines.join(line_ending(original));
+    if original.ends_with('\n') {
+        result.push_str(line_ending(original));
+    }
+    result
+}
+
+fn line_ending(text: &str) -> &'static str {
+    if text.contains("\r\n") { "\r\n" } else { "\n" }
+}
+
+fn title_case(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut at_word_start = true;
+    for c in line.chars() {
+        if c.is_alphanumeric() {
+            if at_word_start {
+                result.extend(c.to_uppercase());
+            } else {
+                result.extend(c.to_lowercase());
+            }
+            at_word_start = false;
+        } else {
+            result.push(c);
+            at_word_start = c.is_whitespace() || matches!(c, '-' | '_' | '/');
+        }
+    }
+    result
+}
+
+// 拆分标识符单词：非字母数字为分隔，同时识别 camelCase 与 HTTPServer 这样的大小写边界
+fn split_words(line: &str) -> Vec<String> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+
+    for (index, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+
+        if c.is_uppercase() && !current.is_empty() {
+            let previous = chars[index - 1];
+            let next_is_lower = chars.get(index + 1).is_some_and(|next| next.is_lowercase());
+            if previous.is_lowercase() || previous.is_numeric() || (previous.is_uppercase() && next_is_lower) {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+        current.push(c);
+    }
+
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn join_words(line: &str, separator: &str) -> String {
+    split_words(line)
+        .iter()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn camel_case(line: &str) -> String {
+    split_words(line)
+        .iter()
+        .enumerate()
+        .map(|(index, word)| {
+            let lower = word.to_lowercase();
+            if index == 0 {
+                return lower;
+            }
+            let mut chars = lower.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+// 校验后逐字符重排空白，保持原有键顺序与数字写法
+fn reformat_json(text: &str, pretty: bool) -> Result<String, String> {
+    let text = text.trim();
+    serde_json::from_str::<serde::de::IgnoredAny>(text).map_err(|e| format!("内容不是有效的 JSON: {}", e))?;
+
+    let mut result = String::with_capacity(text.len());
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = text.chars().peekable();
+
+    let newline = |result: &mut String, depth: usize| {
+        result.push('\n');
+        result.push_str(&"  ".repeat(depth));
+    };
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            result.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match c {
+            c if c.is_whitespace() => {}
+            '"' => {
+                in_string = true;
+                result.push(c);
+            }
+            '{' | '[' => {
+                result.push(c);
+                while chars.peek().is_some_and(|next| next.is_whitespace()) {
+                    chars.next();
+                }
+                if matches!(chars.peek(), Some('}') | Some(']')) {
+                    result.extend(chars.next());
+                } else {
+                    depth += 1;
+                    if pretty {
+                        newline(&mut result, depth);
+                    }
+                }
+            }
+            '}' | ']' => {
+                depth = depth.saturating_sub(1);
+                if pretty {
+                    newline(&mut result, depth);
+                }
+                result.push(c);
+            }
+            ',' => {
+                result.push(c);
+                if pretty {
+                    newline(&mut result, depth);
+                }
+            }
+            ':' => result.push_str(if pretty { ": " } else { ":" }),
+            _ => result.push(c),
+        }
+    }
+
+    Ok(result)
+}
+
+// 百分号编码：保留 RFC 3986 非保留字符
+fn url_encode(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            result.push(byte as char);
+        } else {
+            result.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    result
+}
+
+fn url_decode(text: &str) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = text.get(index + 1..index + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            if let Some(value) = hex {
+                decoded.push(value);
+                index += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[index]);
+        index += 1;
+    }
+    String::from_utf8(decoded).map_err(|_| "URL 解码结果不是有效文本".to_string())
+}
+
+fn base64_decode(text: &str) -> Result<String, String> {
+    let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let bytes = general_purpose::STANDARD
+        .decode(&compact)
+        .or_else(|_| general_purpose::STANDARD_NO_PAD.decode(&compact))
+        .or_else(|_| general_purpose::URL_SAFE.decode(&compact))
+        .or_else(|_| general_purpose::URL_SAFE_NO_PAD.decode(&compact))
+        .map_err(|e| format!("Base64 解码失败: {}", e))?;
+    String::from_utf8(bytes).map_err(|_| "Base64 解码结果不是有效文本".to_string())
+}
+
+fn html_encode(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+// 解码常用命名实体与数字实体，无法识别的实体原样保留
+fn html_decode(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| decode_entity(&rest[1..end]).map(|c| (c, end)));
+        match decoded {
+            Some((c, end)) => {
+                result.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    if let Some(number) = entity.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(transform: TextTransform, text: &str) -> String {
+        transform.apply(text).expect("转换应成功")
+    }
+
+    #[test]
+    fn transform_ids_round_trip() {
+        for transform in TextTransform::ALL {
+            assert!(transform.id().starts_with(TRANSFORM_ACTION_PREFIX));
+            assert_eq!(TextTransform::from_id(transform.id()), Some(transform));
+        }
+        assert_eq!(TextTransform::from_id("transform:unknown"), None);
+    }
+
+    #[test]
+    fn converts_identifier_cases() {
+        assert_eq!(apply(TextTransform::SnakeCase, "parseHTTPResponse v2"), "parse_http_response_v2");
+        assert_eq!(apply(TextTransform::CamelCase, "user-account_id"), "userAccountId");
+        assert_eq!(apply(TextTransform::KebabCase, "MyComponent\nOtherOne\n"), "my-component\nother-one\n");
+        assert_eq!(apply(TextTransform::TitleCase, "hello WORLD-wide"), "Hello World-Wide");
+    }
+
+    #[test]
+    fn encodes_and_decodes() {
+        assert_eq!(apply(TextTransform::UrlEncode, "a b/中"), "a%20b%2F%E4%B8%AD");
+        assert_eq!(apply(TextTransform::UrlDecode, "a%20b%2F%E4%B8%AD%zz"), "a b/中%zz");
+        assert_eq!(apply(TextTransform::Base64Decode, &apply(TextTransform::Base64Encode, "你好")), "你好");
+        assert_eq!(apply(TextTransform::HtmlDecode, "&lt;a&gt; &amp;&#39;&#x4E2D; &unknown;"), "<a> &'中 &unknown;");
+        assert_eq!(apply(TextTransform::StringLiteral, "say \"hi\"\n"), r#""say \"hi\"\n""#);
+        assert!(TextTransform::JsonPretty.apply("{oops").is_err());
+        assert_eq!(apply(TextTransform::JsonMinify, "{ \"b\": [1, 2.50], \"a\": \"x, y\" }"), r#"{"b":[1,2.50],"a":"x, y"}"#);
+        assert_eq!(apply(TextTransform::JsonPretty, r#"{"b":{},"a":[1]}"#), "{\n  \"b\": {},\n  \"a\": [\n    1\n  ]\n}");
+    }
+
+    #[test]
+    fn sorts_and_dedups_lines() {
+        assert_eq!(apply(TextTransform::SortLines, "b\na\nc\n"), "a\nb\nc\n");
+        assert_eq!(apply(TextTransform::DedupLines, "b\r\na\r\nb"), "b\r\na");
+        assert_eq!(apply(TextTransform::CollapseWhitespace, "  a \n\t b  "), "a b");
+    }
+}
@@ -3,6 +3,8 @@ use sha2::{Digest, Sha256};
 
 const WEBDAV_SERVICE: &str = "quickclipboard.webdav";
 const WEBDAV_E2EE_SERVICE: &str = "quickclipboard.webdav.e2ee";
+const LAN_DEVICE_KEY_SERVICE: &str = "quickclipboard.lan.device-key";
+const LAN_DEVICE_KEY_ACCOUNT: &str = "v1";
 
 pub fn get_webdav_password(url: &str, username: &str) -> Result<Option<String>, String> {
     let entry = webdav_entry(url, username)?;
@@ -81,6 +83,26 @@ pub fn delete_webdav_encryption_password(
     }
 }
 
+pub fn get_lan_device_key() -> Result<Option<String>, String> {
+    let entry = lan_device_key_entry()?;
+    match entry.get_password() {
+        Ok(key) => Ok(Some(key)),
+        Err(KeyringError::NoEntry) => Ok(None),
+        Err(e) => Err(format!("读取局域网设备密钥失败: {}", e)),
+    }
+}
+
+pub fn set_lan_device_key(key: &str) -> Result<(), String> {
+    lan_device_key_entry()?
+        .set_password(key)
+        .map_err(|e| format!("保存局域网设备密钥失败: {}", e))
+}
+
+fn lan_device_key_entry() -> Result<Entry, String> {
+    Entry::new(LAN_DEVICE_KEY_SERVICE, LAN_DEVICE_KEY_ACCOUNT)
+        .map_err(|e| format!("访问系统凭据库失败: {}", e))
+}
+
 fn webdav_entry(url: &str, username: &str) -> Result<Entry, String> {
     if url.trim().is_empty() {
         return Err("请先填写 WebDAV 地址".to_string());
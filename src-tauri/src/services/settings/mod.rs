@@ -2,7 +2,7 @@ mod model;
 pub mod storage;
 mod state;
 
-pub use model::AppSettings;
+pub use model::{AppSettings, CaptureRule, RetentionRule, SensitiveRule};
 pub use state::{get_settings, update_settings, update_with, get_data_directory};
 pub use storage::SettingsStorage;
 
@@ -128,6 +128,17 @@ pub struct AppSettings {
     pub app_filter_list: Vec<String>,
     pub app_filter_effect: String,
 
+    // 敏感内容检测设置
+    pub sensitive_detection_enabled: bool,
+    pub sensitive_rules: Vec<SensitiveRule>,
+    pub sensitive_expire_minutes: u32,
+
+    // 历史保留策略
+    pub retention_rules: Vec<RetentionRule>,
+
+    // 捕获规则（入库前按顺序执行）
+    pub capture_rules: Vec<CaptureRule>,
+
     // 窗口设置
     pub window_position_mode: String,
     pub remember_window_size: bool,
@@ -174,24 +185,42 @@ pub struct AppSettings {
     pub previous_group_shortcut: String,
     pub next_group_shortcut: String,
     pub toggle_pin_shortcut: String,
+    // 导航快捷键：对选中项执行转换粘贴
+    pub paste_transform_shortcuts: Vec<PasteTransformShortcut>,
     pub toggle_clipboard_monitor_shortcut: String,
     pub toggle_paste_with_format_shortcut: String,
     pub toggle_low_memory_mode_shortcut: String,
     pub paste_plain_text_shortcut: String,
+    // 粘贴队列：依次粘贴下一条，顺序为 fifo / lifo
+    pub paste_queue_shortcut: String,
+    pub paste_queue_order: String,
 
     // 数据存储设置
     #[serde(alias = "custom_storage_path")]
     pub custom_storage_path: Option<String>,
     #[serde(alias = "use_custom_storage")]
     pub use_custom_storage: bool,
+    pub tombstone_retention_days: u32,
+    pub snapshot_interval_hours: u32,
+    pub snapshot_keep: u32,
+    pub local_encryption_idle_minutes: u32,
+
+    // 本机控制接口设置
+    pub local_api_enabled: bool,
+    pub local_api_port: u16,
 
     // WebDAV Sync 设置
     pub webdav_enabled: bool,
+    // 存储后端：webdav、s3 或 folder；S3 时 url/username/password 分别为服务地址、访问密钥 ID 和密钥
+    pub webdav_backend: String,
     pub webdav_url: String,
     pub webdav_username: String,
     #[serde(default, skip_serializing)]
     pub webdav_password: String,
     pub webdav_root_path: String,
+    pub webdav_s3_bucket: String,
+    pub webdav_s3_region: String,
+    pub webdav_folder_path: String,
     pub webdav_auto_push: bool,
     pub webdav_push_delay_secs: u64,
     pub webdav_auto_pull: bool,
@@ -318,6 +347,14 @@ impl Default for AppSettings {
             app_filter_list: vec![],
             app_filter_effect: "clipboard_only".to_string(),
 
+            sensitive_detection_enabled: false,
+            sensitive_rules: SensitiveRule::builtin_defaults(),
+            sensitive_expire_minutes: 10,
+
+            retention_rules: vec![],
+
+            capture_rules: vec![],
+
             window_position_mode: "smart".to_string(),
             remember_window_size: true,
             saved_window_position: None,
@@ -357,19 +394,33 @@ impl Default for AppSettings {
             previous_group_shortcut: "Ctrl+ArrowUp".to_string(),
             next_group_shortcut: "Ctrl+ArrowDown".to_string(),
             toggle_pin_shortcut: "Ctrl+P".to_string(),
+            paste_transform_shortcuts: vec![],
             toggle_clipboard_monitor_shortcut: String::new(),
             toggle_paste_with_format_shortcut: String::new(),
             toggle_low_memory_mode_shortcut: String::new(),
             paste_plain_text_shortcut: String::new(),
+            paste_queue_shortcut: String::new(),
+            paste_queue_order: "fifo".to_string(),
 
             custom_storage_path: None,
             use_custom_storage: false,
+            tombstone_retention_days: 30,
+            snapshot_interval_hours: 24,
+            snapshot_keep: 7,
+            local_encryption_idle_minutes: 15,
+
+            local_api_enabled: false,
+            local_api_port: crate::services::local_api::DEFAULT_LOCAL_API_PORT,
 
             webdav_enabled: false,
+            webdav_backend: "webdav".to_string(),
             webdav_url: String::new(),
             webdav_username: String::new(),
             webdav_password: String::new(),
             webdav_root_path: "quickclipboard".to_string(),
+            webdav_s3_bucket: String::new(),
+            webdav_s3_region: "us-east-1".to_string(),
+            webdav_folder_path: String::new(),
             webdav_auto_push: false,
             webdav_push_delay_secs: 10,
             webdav_auto_pull: false,
@@ -386,6 +437,121 @@ impl Default for AppSettings {
     }
 }
 
+// 敏感内容规则：内置规则由 id 识别、pattern 为空；自定义规则使用 pattern 正则
+// policy: skip 不保存 / mask 打码后保存 / expire 保存后自动过期
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SensitiveRule {
+    pub id: String,
+    pub name: String,
+    pub pattern: String,
+    pub enabled: bool,
+    pub policy: String,
+}
+
+impl Default for SensitiveRule {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            name: String::new(),
+            pattern: String::new(),
+            enabled: true,
+            policy: "mask".to_string(),
+        }
+    }
+}
+
+impl SensitiveRule {
+    fn builtin(id: &str, name: &str, policy: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            policy: policy.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn builtin_defaults() -> Vec<Self> {
+        vec![
+            Self::builtin("private_key", "私钥", "skip"),
+            Self::builtin("jwt", "JWT 令牌", "mask"),
+            Self::builtin("api_key", "API 密钥", "mask"),
+            Self::builtin("password", "密码赋值", "mask"),
+            Self::builtin("credit_card", "银行卡号", "mask"),
+            // 误报较多，默认关闭，需要时在设置中手动开启
+            Self {
+                enabled: false,
+                ..Self::builtin("high_entropy", "高熵字符串", "expire")
+            },
+        ]
+    }
+}
+
+// 历史保留规则：删除超过 max_age_days 天未更新的条目
+// content_type 为 all 时不限类型，否则按包含匹配（与列表类型过滤一致）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RetentionRule {
+    pub enabled: bool,
+    pub content_type: String,
+    pub max_age_days: u32,
+    pub include_pinned: bool,
+}
+
+impl Default for RetentionRule {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            content_type: "all".to_string(),
+            max_age_days: 30,
+            include_pinned: false,
+        }
+    }
+}
+
+// 转换粘贴快捷键，transform 为粘贴动作 ID（如 transform:upper_case）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PasteTransformShortcut {
+    pub transform: String,
+    pub shortcut: String,
+}
+
+// 捕获规则：匹配条件均为空时匹配全部内容，多个条件同时满足才生效
+// source_app 支持 * / ? 通配（与应用过滤一致），content_type 为 all 时不限类型
+// action: drop / trim / strip_tracking / plain_text / assign_group / pin
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CaptureRule {
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+    pub source_app: String,
+    pub content_type: String,
+    pub pattern: String,
+    pub min_length: Option<u32>,
+    pub max_length: Option<u32>,
+    pub action: String,
+    pub group_name: String,
+}
+
+impl Default for CaptureRule {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            name: String::new(),
+            enabled: true,
+            source_app: String::new(),
+            content_type: "all".to_string(),
+            pattern: String::new(),
+            min_length: None,
+            max_length: None,
+            action: "drop".to_string(),
+            group_name: String::new(),
+        }
+    }
+}
+
 impl AppSettings {
     pub fn normalize_app_filter_blocklist(&mut self) -> bool {
         let mut changed = false;
@@ -442,4 +608,10 @@ mod tests {
         assert!(settings.app_filter_list.is_empty());
         assert_eq!(settings.app_filter_mode, "blacklist");
     }
+
+    #[test]
+    fn sensitive_detection_is_opt_in_for_existing_settings() {
+        let settings: AppSettings = serde_json::from_str("{}").unwrap();
+        assert!(!settings.sensitive_detection_enabled);
+    }
 }
@@ -1,15 +1,39 @@
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
+use rand_core::{OsRng, RngCore};
 use uuid::Uuid;
+use x25519_dalek::{PublicKey, StaticSecret};
 
 const DEVICE_ID_KEY: &str = "sync_transfer_device_id";
 const LEGACY_SYNC_TRANSFER_LAN_DEVICE_ID_KEY: &str = "sync_transfer_lan_device_id";
 
 static DEVICE_ID: Lazy<String> = Lazy::new(load_or_create_device_id);
+// 设备长期密钥，私钥保存在系统凭据库，配对时把公钥交给对方
+static DEVICE_KEY: OnceCell<[u8; 32]> = OnceCell::new();
 
 pub fn device_id() -> String {
     DEVICE_ID.clone()
 }
 
+pub fn device_secret_key() -> Result<StaticSecret, String> {
+    let bytes = DEVICE_KEY.get_or_try_init(load_or_create_device_key)?;
+    Ok(StaticSecret::from(*bytes))
+}
+
+pub fn device_public_key() -> Result<[u8; 32], String> {
+    Ok(PublicKey::from(&device_secret_key()?).to_bytes())
+}
+
+fn load_or_create_device_key() -> Result<[u8; 32], String> {
+    if let Some(stored) = crate::services::secure_credentials::get_lan_device_key()? {
+        let bytes = hex::decode(stored.trim()).map_err(|_| "局域网设备密钥格式无效".to_string())?;
+        return bytes.try_into().map_err(|_| "局域网设备密钥格式无效".to_string());
+    }
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    crate::services::secure_credentials::set_lan_device_key(&hex::encode(bytes))?;
+    Ok(bytes)
+}
+
 fn load_or_create_device_id() -> String {
     if let Some(id) = stored_device_id(DEVICE_ID_KEY) {
         return id;
@@ -0,0 +1,584 @@
+// 多跳同步转发
+//
+// 设备之间不必两两配对：收到的新数据会继续转发给其他已配对设备，经中间设备逐跳扩散。
+// - 转发批次记录依次经过的设备，不会再发回这些设备；经过的设备过多时停止转发
+// - 只转发在本机实际产生变化的数据，重复到达的数据在这一跳就停止扩散
+// - 来源游标：按 source_device_id 记录本机已有数据的最新时间（删除记录取删除时间），
+//   转发前取对方的游标，跳过对方已经有的数据，环状网络中多条路径到达的同一数据大多在发送前就被过滤
+// 游标按来源而不是按修改方记录，其他设备修改的旧记录时间可能早于游标而被跳过；
+// 这部分由完整快照比对补齐，设备每次本机修改、转发失败或对方重新上线后都会做一次完整比对。
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::services::database::SyncTombstone;
+use crate::services::webdav_sync::types::{CloudGroup, CloudRecord};
+
+pub const MAX_ROUTE_LEN: usize = 16;
+
+// 来源设备 ID → 已有数据的最新时间
+pub type OriginCursors = HashMap<String, i64>;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GossipBatch {
+    // 依次经过的设备，第一个是最先发出的设备
+    #[serde(default)]
+    pub route: Vec<String>,
+    #[serde(default)]
+    pub history: Vec<CloudRecord>,
+    #[serde(default)]
+    pub favorites: Vec<CloudRecord>,
+    #[serde(default)]
+    pub groups: Vec<CloudGroup>,
+    #[serde(default)]
+    pub tombstones: Vec<SyncTombstone>,
+}
+
+impl GossipBatch {
+    pub fn from_peer(device_id: &str) -> Self {
+        Self {
+            route: vec![device_id.to_string()],
+            ..Default::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.history.len() + self.favorites.len() + self.groups.len() + self.tombstones.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn can_forward(&self) -> bool {
+        !self.is_empty() && self.route.len() < MAX_ROUTE_LEN
+    }
+
+    pub fn forwarded_by(mut self, device_id: &str) -> Self {
+        if self.route.last().map(String::as_str) != Some(device_id) {
+            self.route.push(device_id.to_string());
+        }
+        self
+    }
+
+    // 只保留对方按游标判断可能还没有的数据
+    pub fn unseen_by(&self, cursors: &OriginCursors) -> Self {
+        Self {
+            route: self.route.clone(),
+            history: unseen_records(&self.history, cursors),
+            favorites: unseen_records(&self.favorites, cursors),
+            groups: self
+                .groups
+                .iter()
+                .filter(|group| is_unseen(cursors, &group.source_device_id, group.updated_at))
+                .cloned()
+                .collect(),
+            tombstones: self
+                .tombstones
+                .iter()
+                .filter(|tombstone| is_unseen(cursors, &tombstone.source_device_id, tombstone.deleted_at))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+fn unseen_records(records: &[CloudRecord], cursors: &OriginCursors) -> Vec<CloudRecord> {
+    records
+        .iter()
+        .filter(|record| is_unseen(cursors, &record.source_device_id, record.updated_at))
+        .cloned()
+        .collect()
+}
+
+// 来源为空的旧数据无法判断，一律视为对方没有
+pub fn is_unseen(cursors: &OriginCursors, origin: &str, stamp: i64) -> bool {
+    let origin = origin.trim();
+    origin.is_empty() || cursors.get(origin).map(|cursor| stamp > *cursor).unwrap_or(true)
+}
+
+pub fn origin_cursors<'a>(stamps: impl IntoIterator<Item = (&'a str, i64)>) -> OriginCursors {
+    let mut cursors = OriginCursors::new();
+    for (origin, stamp) in stamps {
+        let origin = origin.trim();
+        if origin.is_empty() {
+            continue;
+        }
+        let cursor = cursors.entry(origin.to_string()).or_insert(stamp);
+        *cursor = (*cursor).max(stamp);
+    }
+    cursors
+}
+
+pub fn relay_targets<'a>(peer_ids: impl IntoIterator<Item = &'a str>, route: &[String]) -> Vec<String> {
+    peer_ids
+        .into_iter()
+        .filter(|device_id| !route.iter().any(|visited| visited == device_id))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use rusqlite::{params, Connection};
+
+    use super::*;
+    use crate::services::database::sync_versions::{self, SyncContent};
+    use crate::services::database::{clipboard, migrations, tombstones, COLLECTION_HISTORY};
+
+    fn record(uuid: &str, origin: &str, content: &str, updated_at: i64) -> CloudRecord {
+        CloudRecord {
+            uuid: uuid.to_string(),
+            source_device_id: origin.to_string(),
+            is_remote: false,
+            content: content.to_string(),
+            html_content: None,
+            content_type: "text".to_string(),
+            image_id: None,
+            source_app: None,
+            source_icon_hash: None,
+            char_count: None,
+            title: String::new(),
+            group_name: "全部".to_string(),
+            item_order: 0,
+            paste_count: 0,
+            created_at: updated_at,
+            updated_at,
+            version: Default::default(),
+        }
+    }
+
+    fn tombstone(uuid: &str, origin: &str, deleted_at: i64) -> SyncTombstone {
+        SyncTombstone {
+            collection: COLLECTION_HISTORY.to_string(),
+            item_id: uuid.to_string(),
+            source_device_id: origin.to_string(),
+            deleted_at,
+            created_at: deleted_at,
+        }
+    }
+
+    // 每台设备一个内存数据库，收到的数据按 lan::mesh::apply_batch 的步骤写入：
+    // 先保存删除记录并删除对应条目，再经版本向量合并写入历史记录
+    struct SimNode {
+        id: String,
+        peers: Vec<String>,
+        online: bool,
+        // 本机时钟相对模拟时间的偏差（秒）
+        skew: i64,
+        conn: Connection,
+    }
+
+    impl SimNode {
+        fn new(id: String, skew: i64) -> Self {
+            let conn = Connection::open_in_memory().unwrap();
+            migrations::run_migrations(&conn, None).unwrap();
+            Self {
+                id,
+                peers: Vec::new(),
+                online: true,
+                skew,
+                conn,
+            }
+        }
+
+        fn cursors(&self) -> OriginCursors {
+            let mut stmt = self
+                .conn
+                .prepare(
+                    "SELECT COALESCE(source_device_id, ''), updated_at FROM clipboard
+                     UNION ALL SELECT source_device_id, deleted_at FROM sync_tombstones",
+                )
+                .unwrap();
+            let stamps = stmt
+                .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+                .unwrap()
+                .map(Result::unwrap)
+                .map(|(origin, stamp)| if origin.is_empty() { (self.id.clone(), stamp) } else { (origin, stamp) })
+                .collect::<Vec<_>>();
+            origin_cursors(stamps.iter().map(|(origin, stamp)| (origin.as_str(), *stamp)))
+        }
+
+        // 发出前附上本机版本号，与推送时的 attach_versions_in_conn 相同
+        fn records(&self, uuid: Option<&str>) -> Vec<CloudRecord> {
+            let mut stmt = self
+                .conn
+                .prepare(
+                    "SELECT uuid, COALESCE(source_device_id, ''), content, created_at, updated_at FROM clipboard
+                     WHERE ?1 IS NULL OR uuid = ?1",
+                )
+                .unwrap();
+            let records = stmt
+                .query_map(params![uuid], |row| {
+                    let (uuid, origin, content) = (row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?);
+                    let mut record = record(&uuid, &origin, &content, row.get(4)?);
+                    record.created_at = row.get(3)?;
+                    Ok(record)
+                })
+                .unwrap()
+                .map(Result::unwrap)
+                .collect::<Vec<_>>();
+            records
+                .into_iter()
+                .map(|mut record| {
+                    let content = SyncContent::of_record(&record);
+                    record.version =
+                        sync_versions::local_version_in_conn(&self.conn, COLLECTION_HISTORY, &record.uuid, &content, &self.id).unwrap();
+                    record
+                })
+                .collect()
+        }
+
+        fn tombstones(&self) -> Vec<SyncTombstone> {
+            let mut stmt = self
+                .conn
+                .prepare("SELECT collection, item_id, source_device_id, deleted_at, created_at FROM sync_tombstones")
+                .unwrap();
+            let tombstones = stmt
+                .query_map([], |row| {
+                    Ok(SyncTombstone {
+                        collection: row.get(0)?,
+                        item_id: row.get(1)?,
+                        source_device_id: row.get(2)?,
+                        deleted_at: row.get(3)?,
+                        created_at: row.get(4)?,
+                    })
+                })
+                .unwrap()
+                .map(Result::unwrap)
+                .collect();
+            tombstones
+        }
+
+        // 返回在本机产生变化的部分，以及是否生成了冲突副本
+        fn apply(&self, batch: &GossipBatch) -> (GossipBatch, bool) {
+            let changed_tombstones = tombstones::upsert_sync_tombstones_in_conn(&self.conn, &batch.tombstones).unwrap();
+            tombstones::apply_sync_tombstones_in_conn(&self.conn, &batch.tombstones).unwrap();
+            let result = clipboard::upsert_history_records_in_conn(&self.conn, &batch.history, false, &self.id).unwrap();
+            let copied = result.conflicts.iter().any(|conflict| conflict.copy_id.is_some());
+            let changed = GossipBatch {
+                route: batch.route.clone(),
+                history: result.changed,
+                tombstones: changed_tombstones,
+                ..Default::default()
+            };
+            (changed, copied)
+        }
+
+        fn state(&self) -> BTreeMap<String, String> {
+            let mut stmt = self.conn.prepare("SELECT uuid, content FROM clipboard").unwrap();
+            let state = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .unwrap()
+                .map(Result::unwrap)
+                .collect();
+            state
+        }
+    }
+
+    enum Delivery {
+        Gossip(String, GossipBatch),
+        // 完整比对：把第一台设备的全部数据发给第二台，由接收方的合并规则决定是否写入
+        FullSync(String, String),
+    }
+
+    struct Mesh {
+        nodes: BTreeMap<String, SimNode>,
+        queue: Vec<Delivery>,
+        time: i64,
+        delivered: usize,
+        rng: u64,
+    }
+
+    impl Mesh {
+        fn new(size: usize, edges: &[(usize, usize)], seed: u64) -> Self {
+            let mut mesh = Self {
+                nodes: BTreeMap::new(),
+                queue: Vec::new(),
+                // 冲突副本按真实时间写入，模拟时间从当前时间开始
+                time: chrono::Local::now().timestamp(),
+                delivered: 0,
+                rng: seed.max(1),
+            };
+            for index in 0..size {
+                let skew = mesh.next_random(61) as i64 - 30;
+                mesh.nodes.insert(node_id(index), SimNode::new(node_id(index), skew));
+            }
+            for (a, b) in edges {
+                mesh.nodes.get_mut(&node_id(*a)).unwrap().peers.push(node_id(*b));
+                mesh.nodes.get_mut(&node_id(*b)).unwrap().peers.push(node_id(*a));
+            }
+            mesh
+        }
+
+        fn next_random(&mut self, bound: usize) -> usize {
+            self.rng ^= self.rng << 13;
+            self.rng ^= self.rng >> 7;
+            self.rng ^= self.rng << 17;
+            (self.rng % bound as u64) as usize
+        }
+
+        // 设备看到的本机时间，同一秒内的修改时间相同
+        fn clock(&self, node: &str) -> i64 {
+            self.time + self.nodes[node].skew
+        }
+
+        fn relay(&mut self, from: &str, changed: GossipBatch) {
+            if !changed.can_forward() {
+                return;
+            }
+            let batch = changed.forwarded_by(from);
+            let peers = self.nodes[from].peers.clone();
+            for target in relay_targets(peers.iter().map(String::as_str), &batch.route) {
+                let node = &self.nodes[&target];
+                // 离线设备收不到，等重新上线后补齐
+                if !node.online {
+                    continue;
+                }
+                let scoped = batch.unseen_by(&node.cursors());
+                if !scoped.is_empty() {
+                    self.queue.push(Delivery::Gossip(target, scoped));
+                }
+            }
+        }
+
+        fn schedule_full_sync(&mut self, node: &str) {
+            for peer in self.nodes[node].peers.clone() {
+                self.queue.push(Delivery::FullSync(node.to_string(), peer.clone()));
+                self.queue.push(Delivery::FullSync(peer, node.to_string()));
+            }
+        }
+
+        // 按随机顺序投递，不同设备发出的批次可以交错、乱序到达
+        fn deliver(&mut self, count: usize) {
+            for _ in 0..count {
+                if self.queue.is_empty() {
+                    return;
+                }
+                let index = self.next_random(self.queue.len());
+                let delivery = self.queue.swap_remove(index);
+                self.delivered += 1;
+                assert!(self.delivered < 200_000, "转发没有收敛");
+                match delivery {
+                    Delivery::Gossip(target, batch) => {
+                        // 投递时对方已离线，转发失败，等重新上线后由完整比对补齐
+                        if !self.nodes[&target].online {
+                            continue;
+                        }
+                        let (changed, copied) = self.nodes[&target].apply(&batch);
+                        self.relay(&target, changed);
+                        if copied {
+                            self.schedule_full_sync(&target);
+                        }
+                    }
+                    Delivery::FullSync(from, to) => {
+                        if !self.nodes[&from].online || !self.nodes[&to].online {
+                            continue;
+                        }
+                        let source = &self.nodes[&from];
+                        let batch = GossipBatch {
+                            route: vec![from.clone()],
+                            history: source.records(None),
+                            tombstones: source.tombstones(),
+                            ..Default::default()
+                        };
+                        sync_versions::record_sync_bases_in_conn(&source.conn, COLLECTION_HISTORY, &batch.history).unwrap();
+                        let (changed, copied) = self.nodes[&to].apply(&batch);
+                        let any = !changed.is_empty();
+                        self.relay(&to, changed);
+                        // 收到补齐数据的设备再与它的相邻设备比对
+                        if any || copied {
+                            self.schedule_full_sync(&to);
+                        }
+                    }
+                }
+            }
+        }
+
+        fn run(&mut self) {
+            while !self.queue.is_empty() {
+                self.deliver(self.queue.len());
+            }
+        }
+
+        fn reconnect(&mut self, node: &str) {
+            self.nodes.get_mut(node).unwrap().online = true;
+            self.schedule_full_sync(node);
+        }
+
+        fn random_operation(&mut self, serial: usize) {
+            self.time += self.next_random(3) as i64;
+            let ids = self.nodes.keys().cloned().collect::<Vec<_>>();
+            let actor = ids[self.next_random(ids.len())].clone();
+            if !self.nodes[&actor].online {
+                return;
+            }
+            let existing = self.nodes[&actor].state().into_keys().collect::<Vec<_>>();
+            let roll = self.next_random(10);
+            if existing.is_empty() || roll < 4 {
+                self.create(&actor, &format!("clip-{}", serial));
+            } else if roll < 8 {
+                let uuid = existing[self.next_random(existing.len())].clone();
+                let line = self.next_random(3) * 2;
+                self.edit(&actor, &uuid, line, serial);
+            } else {
+                let uuid = existing[self.next_random(existing.len())].clone();
+                self.delete(&actor, &uuid);
+            }
+            // 不等上一批送达就继续下一次修改
+            let count = self.next_random(4);
+            self.deliver(count);
+        }
+
+        fn create(&mut self, actor: &str, uuid: &str) {
+            let now = self.clock(actor);
+            let node = &self.nodes[actor];
+            let lines = (0..5).map(|line| format!("{} line {}\n", actor, line)).collect::<String>();
+            node.conn
+                .execute(
+                    "INSERT INTO clipboard (uuid, source_device_id, content, content_type, created_at, updated_at)
+                     VALUES (?1, ?2, ?3, 'text', ?4, ?4)",
+                    params![uuid, actor, lines, now],
+                )
+                .unwrap();
+            let history = node.records(Some(uuid));
+            self.commit(actor, history, Vec::new());
+        }
+
+        // 只改其中一行，不同设备改不同行时可以自动合并，改同一行时生成冲突副本
+        fn edit(&mut self, actor: &str, uuid: &str, line: usize, serial: usize) {
+            let now = self.clock(actor);
+            let node = &self.nodes[actor];
+            let edited = node.state()[uuid]
+                .split_inclusive('\n')
+                .enumerate()
+                .map(|(index, text)| if index == line { format!("{} edited by {}\n", serial, actor) } else { text.to_string() })
+                .collect::<String>();
+            node.conn
+                .execute("UPDATE clipboard SET content = ?2, updated_at = ?3 WHERE uuid = ?1", params![uuid, edited, now])
+                .unwrap();
+            let history = node.records(Some(uuid));
+            self.commit(actor, history, Vec::new());
+        }
+
+        fn delete(&mut self, actor: &str, uuid: &str) {
+            let now = self.clock(actor);
+            let node = &self.nodes[actor];
+            node.conn.execute("DELETE FROM clipboard WHERE uuid = ?1", params![uuid]).unwrap();
+            tombstones::record_sync_tombstone_in_conn(&node.conn, COLLECTION_HISTORY, uuid, actor, now).unwrap();
+            self.commit(actor, Vec::new(), vec![tombstone(uuid, actor, now)]);
+        }
+
+        // 本机修改作为只经过本机的批次发给相邻设备，发出的内容成为之后合并的基线
+        fn commit(&mut self, actor: &str, history: Vec<CloudRecord>, tombstones: Vec<SyncTombstone>) {
+            sync_versions::record_sync_bases_in_conn(&self.nodes[actor].conn, COLLECTION_HISTORY, &history).unwrap();
+            let batch = GossipBatch {
+                history,
+                tombstones,
+                ..Default::default()
+            };
+            self.relay(actor, batch);
+        }
+
+        fn assert_converged(&self) {
+            let mut states = self.nodes.iter().map(|(id, node)| (id, node.state()));
+            let (first_id, first) = states.next().unwrap();
+            for (id, state) in states {
+                assert_eq!(state, first, "{} 与 {} 不一致", id, first_id);
+            }
+        }
+    }
+
+    fn node_id(index: usize) -> String {
+        format!("device-{}", index)
+    }
+
+    #[test]
+    fn unseen_by_skips_data_peer_already_has() {
+        let batch = GossipBatch {
+            route: vec!["a".to_string()],
+            history: vec![record("1", "a", "old", 10), record("2", "a", "new", 30), record("3", "", "legacy", 5)],
+            tombstones: vec![tombstone("4", "b", 20)],
+            ..Default::default()
+        };
+        let cursors = origin_cursors([("a", 20), ("b", 20)]);
+        let scoped = batch.unseen_by(&cursors);
+        let uuids = scoped.history.iter().map(|record| record.uuid.as_str()).collect::<Vec<_>>();
+        assert_eq!(uuids, vec!["2", "3"]);
+        assert!(scoped.tombstones.is_empty());
+    }
+
+    #[test]
+    fn relay_skips_devices_on_route() {
+        let batch = GossipBatch::from_peer("a").forwarded_by("b").forwarded_by("b");
+        assert_eq!(batch.route, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(relay_targets(["a", "b", "c", "d"], &batch.route), vec!["c".to_string(), "d".to_string()]);
+
+        let mut long = GossipBatch::from_peer("origin");
+        long.history.push(record("1", "origin", "x", 1));
+        for hop in 1..MAX_ROUTE_LEN {
+            assert!(long.can_forward());
+            long = long.forwarded_by(&format!("hop-{}", hop));
+        }
+        assert!(!long.can_forward());
+    }
+
+    #[test]
+    fn mesh_converges_across_topologies() {
+        let line = (0..4).map(|index| (index, index + 1)).collect::<Vec<_>>();
+        let ring = (0..6).map(|index| (index, (index + 1) % 6)).collect::<Vec<_>>();
+        let star = (1..7).map(|index| (0, index)).collect::<Vec<_>>();
+        let mesh = vec![(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 6), (6, 7), (0, 4), (2, 6), (1, 7)];
+        for (size, edges) in [(5, line), (6, ring), (7, star), (8, mesh)] {
+            for seed in [7, 42, 2024] {
+                let mut mesh = Mesh::new(size, &edges, seed);
+                for serial in 0..60 {
+                    mesh.random_operation(serial);
+                }
+                mesh.run();
+                // 时钟偏差下来源游标可能漏掉部分修改，由各设备与相邻设备的完整比对补齐
+                for node in mesh.nodes.keys().cloned().collect::<Vec<_>>() {
+                    mesh.schedule_full_sync(&node);
+                }
+                mesh.run();
+                mesh.assert_converged();
+            }
+        }
+    }
+
+    #[test]
+    fn offline_device_catches_up_after_reconnect() {
+        let edges = [(0, 1), (1, 2), (2, 3), (3, 4)];
+        let mut mesh = Mesh::new(5, &edges, 99);
+        for serial in 0..10 {
+            mesh.random_operation(serial);
+        }
+        mesh.run();
+        // 中间设备离线时网络被分成两半，两边各自继续修改，已在途的批次可能送不到
+        mesh.nodes.get_mut(&node_id(2)).unwrap().online = false;
+        for serial in 10..50 {
+            mesh.random_operation(serial);
+        }
+        mesh.run();
+        let left = mesh.nodes[&node_id(0)].state();
+        let right = mesh.nodes[&node_id(4)].state();
+        assert_ne!(left, right);
+        // 两边同时改同一条目的同一行，重新连通后应保留一边并为另一边生成冲突副本
+        let shared = left.keys().find(|uuid| right.contains_key(*uuid)).unwrap().clone();
+        mesh.time += 1;
+        mesh.edit(&node_id(0), &shared, 2, 100);
+        mesh.edit(&node_id(4), &shared, 2, 101);
+        mesh.run();
+
+        mesh.reconnect(&node_id(2));
+        mesh.run();
+        mesh.assert_converged();
+        let state = mesh.nodes[&node_id(0)].state();
+        for edit in ["100 edited by device-0", "101 edited by device-4"] {
+            assert!(state.values().any(|content| content.contains(edit)), "{} 丢失", edit);
+        }
+    }
+}
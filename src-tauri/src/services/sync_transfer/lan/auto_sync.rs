@@ -109,6 +109,19 @@ pub fn notify_local_change(app: AppHandle, reason: &'static str) {
     }
 }
 
+// 只对单个设备做一次完整同步，用于多跳转发失败或对方不支持转发时
+pub fn notify_peer_change(app: AppHandle, device_id: &str, reason: &'static str) {
+    if !settings().can_send() {
+        return;
+    }
+    if let Some(peer) = super::peer_store::list_peers()
+        .into_iter()
+        .find(|peer| peer.device_id == device_id)
+    {
+        schedule_peer_sync(app, reason, peer);
+    }
+}
+
 impl LanAutoSyncSettings {
     pub fn can_send(&self) -> bool {
         self.send_enabled
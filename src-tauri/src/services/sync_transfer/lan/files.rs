@@ -8,12 +8,15 @@ use uuid::Uuid;
 
 use crate::services::webdav_sync::types::CloudRecord;
 
+// 旧版整文件直传的上限，分块传输不受此限制
 pub const MAX_DIRECT_TRANSFER_FILE_SIZE: u64 = 512 * 1024 * 1024;
+const MAX_OUTGOING_ENTRIES: usize = 100_000;
 
 static RESERVED_RECEIVED_FILE_PATHS: Lazy<Mutex<HashSet<PathBuf>>> = Lazy::new(|| Mutex::new(HashSet::new()));
 static RECEIVED_FILE_INDEX_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
 
 const RECEIVED_FILE_INDEX_NAME: &str = "index.json";
+const TRANSFER_SESSIONS_DIR_NAME: &str = ".sessions";
 
 #[derive(Debug, Clone)]
 pub struct ReceivedFileReservation {
@@ -21,6 +24,14 @@ pub struct ReceivedFileReservation {
     pub temp_path: PathBuf,
 }
 
+// 待发送的单个文件，relative_path 使用 / 分隔，发送单个文件时就是文件名
+#[derive(Debug, Clone)]
+pub struct OutgoingEntry {
+    pub relative_path: String,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ReceivedFileMetadata {
@@ -57,7 +68,13 @@ pub fn read_image_file(image_id: &str) -> Result<Option<Vec<u8>>, String> {
     if !path.exists() {
         return Ok(None);
     }
-    std::fs::read(path).map(Some).map_err(|e| format!("读取局域网同步图片失败: {}", e))
+    crate::services::local_encryption::read_file(&path)
+        .map(Some)
+        .map_err(|e| format!("读取局域网同步图片失败: {}", e))
+}
+
+pub fn has_image_file(image_id: &str) -> Result<bool, String> {
+    Ok(image_path(image_id)?.exists())
 }
 
 pub fn save_image_file(image_id: &str, bytes: &[u8]) -> Result<(), String> {
@@ -65,21 +82,68 @@ pub fn save_image_file(image_id: &str, bytes: &[u8]) -> Result<(), String> {
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent).map_err(|e| format!("创建局域网同步图片目录失败: {}", e))?;
     }
-    std::fs::write(path, bytes).map_err(|e| format!("保存局域网同步图片失败: {}", e))
+    crate::services::local_encryption::write_file(&path, bytes).map_err(|e| format!("保存局域网同步图片失败: {}", e))
 }
 
-pub fn outgoing_file_info(path: &str) -> Result<(String, PathBuf, u64), String> {
+// 返回传输名称、是否为文件夹以及其中的全部文件；文件夹内的符号链接不会被跟随
+pub fn outgoing_entries(path: &str) -> Result<(String, bool, Vec<OutgoingEntry>), String> {
     let path = PathBuf::from(path);
     let metadata = std::fs::metadata(&path).map_err(|e| format!("读取待传输文件信息失败: {}", e))?;
-    if !metadata.is_file() {
-        return Err("只能传输普通文件".to_string());
-    }
-    let file_name = path
+    let name = path
         .file_name()
         .and_then(|name| name.to_str())
         .ok_or_else(|| "文件名无效".to_string())?
         .to_string();
-    Ok((file_name, path, metadata.len()))
+    if metadata.is_file() {
+        let entry = OutgoingEntry {
+            relative_path: name.clone(),
+            path,
+            size: metadata.len(),
+        };
+        return Ok((name, false, vec![entry]));
+    }
+    if !metadata.is_dir() {
+        return Err("只能传输普通文件或文件夹".to_string());
+    }
+
+    let mut entries = Vec::new();
+    let mut pending = vec![(path, String::new())];
+    while let Some((dir, prefix)) = pending.pop() {
+        let children = std::fs::read_dir(&dir).map_err(|e| format!("读取待传输文件夹失败: {}", e))?;
+        for child in children {
+            let child = child.map_err(|e| format!("读取待传输文件夹失败: {}", e))?;
+            let Some(child_name) = child.file_name().to_str().map(str::to_string) else {
+                return Err(format!("文件名无效: {}", child.path().display()));
+            };
+            let relative_path = if prefix.is_empty() {
+                child_name
+            } else {
+                format!("{}/{}", prefix, child_name)
+            };
+            let file_type = child.file_type().map_err(|e| format!("读取待传输文件信息失败: {}", e))?;
+            if file_type.is_dir() {
+                pending.push((child.path(), relative_path));
+            } else if file_type.is_file() {
+                let size = child
+                    .metadata()
+                    .map_err(|e| format!("读取待传输文件信息失败: {}", e))?
+                    .len();
+                entries.push(OutgoingEntry {
+                    relative_path,
+                    path: child.path(),
+                    size,
+                });
+                if entries.len() > MAX_OUTGOING_ENTRIES {
+                    return Err("文件夹内文件过多".to_string());
+                }
+            }
+        }
+    }
+    if entries.is_empty() {
+        return Err("文件夹为空".to_string());
+    }
+    entries.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    Ok((name, true, entries))
 }
 
 pub fn prepare_received_file(file_name: &str) -> Result<ReceivedFileReservation, String> {
@@ -121,6 +185,29 @@ pub fn commit_received_file(reservation: &ReceivedFileReservation) -> Result<Pat
     Ok(final_path)
 }
 
+// 把已接收完整的文件移入以 name 命名的新文件夹，重名时自动编号；files 为 (临时文件, 相对路径)
+pub fn commit_received_directory(name: &str, files: &[(PathBuf, PathBuf)]) -> Result<PathBuf, String> {
+    let safe_name = sanitize_name_component(name)?;
+    let dir = received_files_dir()?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("创建接收文件目录失败: {}", e))?;
+    let target = {
+        let reserved = RESERVED_RECEIVED_FILE_PATHS
+            .lock()
+            .map_err(|_| "接收文件路径状态异常".to_string())?;
+        let target = unique_path(&dir, &safe_name, &reserved);
+        std::fs::create_dir(&target).map_err(|e| format!("创建接收文件夹失败: {}", e))?;
+        target
+    };
+    for (source, relative_path) in files {
+        let destination = target.join(relative_path);
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("创建接收文件夹失败: {}", e))?;
+        }
+        std::fs::rename(source, &destination).map_err(|e| format!("完成接收文件保存失败: {}", e))?;
+    }
+    Ok(target)
+}
+
 pub fn discard_received_file(reservation: &ReceivedFileReservation) {
     if let Ok(mut reserved) = RESERVED_RECEIVED_FILE_PATHS.lock() {
         reserved.remove(&reservation.final_path);
@@ -204,6 +291,23 @@ pub fn received_files_dir() -> Result<PathBuf, String> {
     Ok(crate::services::get_data_directory()?.join("sync_transfer_files"))
 }
 
+// 分块传输未完成的会话，以 . 开头不会出现在收件盒中
+pub fn transfer_sessions_dir() -> Result<PathBuf, String> {
+    Ok(received_files_dir()?.join(TRANSFER_SESSIONS_DIR_NAME))
+}
+
+// 校验对方发来的文件夹内相对路径，拒绝绝对路径和 .. 等越出接收目录的写法
+pub fn sanitize_relative_path(raw: &str) -> Result<PathBuf, String> {
+    let mut path = PathBuf::new();
+    for part in raw.split('/') {
+        path.push(sanitize_name_component(part)?);
+    }
+    if path.as_os_str().is_empty() {
+        return Err("文件名无效".to_string());
+    }
+    Ok(path)
+}
+
 pub fn is_received_file_internal(path: &Path) -> bool {
     path.file_name()
         .and_then(|value| value.to_str())
@@ -259,9 +363,12 @@ fn sanitize_file_name(raw: &str) -> Result<String, String> {
     let name = Path::new(&decoded)
         .file_name()
         .and_then(|name| name.to_str())
-        .ok_or_else(|| "文件名无效".to_string())?
-        .trim()
-        .to_string();
+        .ok_or_else(|| "文件名无效".to_string())?;
+    sanitize_name_component(name)
+}
+
+fn sanitize_name_component(raw: &str) -> Result<String, String> {
+    let name = raw.trim().to_string();
     if name.is_empty() || name == "." || name == ".." {
         return Err("文件名无效".to_string());
     }
@@ -1,20 +1,26 @@
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 use std::time::Duration;
-use tokio::io::AsyncRead;
-use tokio_util::io::ReaderStream;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWriteExt, DuplexStream};
+
+use super::pairing::{
+    open, seal, PairingConfirmRequest, PairingConfirmResponse, PairingHandshake, PairingIdentity, PairingStartRequest,
+    PairingStartResponse,
+};
 
 pub const LAN_UNAUTHORIZED: &str = "局域网设备未授权（配对已失效）";
 const FILE_TRANSFER_BUFFER_SIZE: usize = 1024 * 1024;
 const SYNC_REQUEST_TIMEOUT_SECS: u64 = 180;
-const TRANSFER_CONNECT_TIMEOUT_SECS: u64 = 10;
 const IMAGE_REQUEST_MAX_ATTEMPTS: usize = 3;
 const IMAGE_REQUEST_RETRY_DELAYS_MS: [u64; 2] = [300, 800];
+const RESPONSE_HEADER_LIMIT: usize = 64 * 1024;
+const MAX_RESPONSE_BODY_SIZE: usize = super::files::MAX_DIRECT_TRANSFER_FILE_SIZE as usize;
 
+// 只用于配对前的明文请求
 fn build_client() -> reqwest::Client {
     reqwest::Client::builder()
         .no_proxy()
@@ -24,26 +30,6 @@ fn build_client() -> reqwest::Client {
         .unwrap_or_else(|_| reqwest::Client::new())
 }
 
-fn build_transfer_client() -> reqwest::Client {
-    reqwest::Client::builder()
-        .no_proxy()
-        .connect_timeout(Duration::from_secs(TRANSFER_CONNECT_TIMEOUT_SECS))
-        .build()
-        .unwrap_or_else(|_| reqwest::Client::new())
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LanHttpClientConfig {
-    pub base_url: String,
-    pub peer_token: String,
-}
-
-impl LanHttpClientConfig {
-    pub fn authorization_header(&self) -> String {
-        format!("Bearer {}", self.peer_token)
-    }
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LanHelloResponse {
     pub device_id: String,
@@ -52,17 +38,26 @@ pub struct LanHelloResponse {
     pub version: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct PairingConfirmPayload {
-    device_id: String,
-    device_name: String,
-    base_url: String,
-    pairing_code: String,
+pub(crate) struct LanResponse {
+    pub(crate) status: u16,
+    body: Vec<u8>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct PairingConfirmOutput {
-    peer_token: String,
+impl LanResponse {
+    pub(crate) fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub(crate) fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T, String> {
+        serde_json::from_slice(&self.body).map_err(|e| e.to_string())
+    }
+
+    pub(crate) fn message(&self) -> String {
+        serde_json::from_slice::<serde_json::Value>(&self.body)
+            .ok()
+            .and_then(|value| value.get("message").and_then(|message| message.as_str()).map(str::to_string))
+            .unwrap_or_else(|| String::from_utf8_lossy(&self.body).to_string())
+    }
 }
 
 pub async fn pair_with_peer(base_url: String, pairing_code: String) -> Result<super::PairedPeerInfo, String> {
@@ -80,41 +75,53 @@ pub async fn pair_with_peer(base_url: String, pairing_code: String) -> Result<su
         .json::<LanHelloResponse>()
         .await
         .map_err(|e| format!("解析局域网设备信息失败: {}", e))?;
-    if hello.protocol != "quickclipboard-sync-transfer-lan-http" {
+    if hello.protocol != super::http_server::PROTOCOL || hello.version < super::http_server::PROTOCOL_VERSION {
         return Err("对方不是兼容的 QuickClipboard 同步/传输服务".to_string());
     }
-    if hello.device_id == super::runtime::device_id() {
+    let local_device_id = super::runtime::device_id();
+    if hello.device_id == local_device_id {
         return Err("不能配对当前设备自身".to_string());
     }
-    let payload = PairingConfirmPayload {
-        device_id: super::runtime::device_id(),
+    let local_public_key = crate::services::sync_transfer::device_identity::device_public_key()?;
+
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let handshake = PairingHandshake::new(&pairing_code, &session_id, &local_device_id, &hello.device_id, true);
+    let start = PairingStartRequest {
+        device_id: local_device_id.clone(),
+        device_name: super::runtime::device_name(),
+        session_id: session_id.clone(),
+        share: handshake.share(),
+    };
+    let start = post_pairing_json::<_, PairingStartResponse>(&client, &base_url, "/qc-sync/pairing/start", &start).await?;
+    if start.device_id != hello.device_id {
+        return Err("局域网设备身份不匹配".to_string());
+    }
+    let keys = handshake.finish(&start.share)?;
+
+    let identity = PairingIdentity {
+        device_id: local_device_id,
         device_name: super::runtime::device_name(),
         base_url: local_base_url(),
-        pairing_code,
+        public_key: hex::encode(local_public_key),
     };
-    let confirm = client
-        .post(format!("{}/qc-sync/pairing/confirm", base_url))
-        .json(&payload)
-        .send()
-        .await
-        .map_err(|e| format!("发送配对请求失败: {}", e))?;
-    if !confirm.status().is_success() {
-        let status = confirm.status();
-        let message = confirm
-            .text()
-            .await
-            .unwrap_or_else(|_| "配对失败".to_string());
-        return Err(format!("配对失败: {} {}", status, message));
+    let confirm = PairingConfirmRequest {
+        session_id: session_id.clone(),
+        sealed: seal(&keys.initiator_key, &session_id, &identity)?,
+    };
+    let confirm = post_pairing_json::<_, PairingConfirmResponse>(&client, &base_url, "/qc-sync/pairing/confirm", &confirm).await?;
+    // 对方已确认配对码，回复仍无法解密说明连接被篡改
+    let remote = open::<PairingIdentity>(&keys.responder_key, &session_id, &confirm.sealed)
+        .filter(|remote| remote.device_id == hello.device_id)
+        .ok_or_else(|| "配对验证失败，请确认连接的是正确的设备".to_string())?;
+    if super::pairing::decode_public_key(&remote.public_key).is_none() {
+        return Err("配对验证失败，请确认连接的是正确的设备".to_string());
     }
-    let output = confirm
-        .json::<PairingConfirmOutput>()
-        .await
-        .map_err(|e| format!("解析配对响应失败: {}", e))?;
+
     let mut peer = super::peer_store::PairedPeer::new(
         hello.device_id,
         hello.device_name,
         base_url,
-        output.peer_token,
+        remote.public_key.trim().to_ascii_lowercase(),
     );
     peer.last_seen_at_ms = Some(chrono::Utc::now().timestamp_millis());
     let info = peer.info();
@@ -122,6 +129,31 @@ pub async fn pair_with_peer(base_url: String, pairing_code: String) -> Result<su
     Ok(info)
 }
 
+async fn post_pairing_json<B, T>(client: &reqwest::Client, base_url: &str, path: &str, body: &B) -> Result<T, String>
+where
+    B: Serialize,
+    T: serde::de::DeserializeOwned,
+{
+    let response = client
+        .post(format!("{}{}", base_url, path))
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| format!("发送配对请求失败: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let message = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "配对失败".to_string());
+        return Err(format!("配对失败: {} {}", status, message));
+    }
+    response
+        .json::<T>()
+        .await
+        .map_err(|e| format!("解析配对响应失败: {}", e))
+}
+
 pub async fn fetch_peer_snapshot(peer: &super::peer_store::PairedPeer) -> Result<super::LanSyncSnapshot, String> {
     authorized_get(peer, "/qc-sync/snapshot").await
 }
@@ -170,21 +202,40 @@ pub async fn push_peer_tombstones(
     authorized_post(peer, "/qc-sync/tombstones", &batch).await
 }
 
+// 旧版本没有转发接口，返回 None
+pub async fn fetch_peer_gossip_cursors(
+    peer: &super::peer_store::PairedPeer,
+) -> Result<Option<super::mesh::LanGossipCursors>, String> {
+    let path = super::http_server::GOSSIP_CURSORS_PATH;
+    let response = send_sync_request(peer, "GET", path, &[])
+        .await
+        .map_err(|e| pass_unauthorized(e, |e| format!("读取局域网同步数据失败({}): {}", path, e)))?;
+    if response.status == 404 {
+        return Ok(None);
+    }
+    if response.status == 403 {
+        return Err(LAN_UNAUTHORIZED.to_string());
+    }
+    if !response.is_success() {
+        return Err(format!("读取局域网同步数据失败({}): {}", path, response.status));
+    }
+    response
+        .json::<super::mesh::LanGossipCursors>()
+        .map(Some)
+        .map_err(|e| format!("解析局域网同步数据失败({}): {}", path, e))
+}
+
+pub async fn push_peer_gossip(
+    peer: &super::peer_store::PairedPeer,
+    batch: &crate::services::sync_transfer::gossip::GossipBatch,
+) -> Result<super::mesh::LanGossipAck, String> {
+    authorized_post(peer, super::http_server::GOSSIP_PATH, batch).await
+}
+
 pub async fn fetch_peer_image(peer: &super::peer_store::PairedPeer, image_id: &str) -> Result<Option<Vec<u8>>, String> {
-    let client = build_transfer_client();
-    let config = LanHttpClientConfig {
-        base_url: peer.base_url.clone(),
-        peer_token: peer.peer_token.clone(),
-    };
-    let url = format!("{}/qc-sync/files/{}.png", config.base_url.trim_end_matches('/'), image_id);
+    let path = format!("/qc-sync/files/{}.png", image_id);
     for attempt in 0..IMAGE_REQUEST_MAX_ATTEMPTS {
-        let response = match client
-            .get(&url)
-            .header("Authorization", config.authorization_header())
-            .header("X-Device-Id", super::runtime::device_id())
-            .send()
-            .await
-        {
+        let response = match send_request(peer, "GET", &path, &[]).await {
             Ok(response) => response,
             Err(e) if should_retry_transport_error(&e) && attempt + 1 < IMAGE_REQUEST_MAX_ATTEMPTS => {
                 wait_before_image_retry(attempt).await;
@@ -192,40 +243,21 @@ pub async fn fetch_peer_image(peer: &super::peer_store::PairedPeer, image_id: &s
             }
             Err(e) => return Err(format!("读取局域网图片失败: {}", e)),
         };
-        if response.status() == reqwest::StatusCode::NOT_FOUND {
+        if response.status == 404 {
             return Ok(None);
         }
-        if !response.status().is_success() {
-            return Err(format!("读取局域网图片失败: {}", response.status()));
-        }
-        match response.bytes().await {
-            Ok(bytes) => return Ok(Some(bytes.to_vec())),
-            Err(e) if should_retry_transport_error(&e) && attempt + 1 < IMAGE_REQUEST_MAX_ATTEMPTS => {
-                wait_before_image_retry(attempt).await;
-                continue;
-            }
-            Err(e) => return Err(format!("读取局域网图片内容失败: {}", e)),
+        if !response.is_success() {
+            return Err(format!("读取局域网图片失败: {}", response.status));
         }
+        return Ok(Some(response.body));
     }
     Err("读取局域网图片失败: 多次重试后仍无法连接".to_string())
 }
 
 pub async fn push_peer_image(peer: &super::peer_store::PairedPeer, image_id: &str, bytes: Vec<u8>) -> Result<(), String> {
-    let client = build_transfer_client();
-    let config = LanHttpClientConfig {
-        base_url: peer.base_url.clone(),
-        peer_token: peer.peer_token.clone(),
-    };
-    let url = format!("{}/qc-sync/files/{}.png", config.base_url.trim_end_matches('/'), image_id);
+    let path = format!("/qc-sync/files/{}.png", image_id);
     for attempt in 0..IMAGE_REQUEST_MAX_ATTEMPTS {
-        let response = match client
-            .put(&url)
-            .header("Authorization", config.authorization_header())
-            .header("X-Device-Id", super::runtime::device_id())
-            .body(bytes.clone())
-            .send()
-            .await
-        {
+        let response = match send_request(peer, "PUT", &path, &bytes).await {
             Ok(response) => response,
             Err(e) if should_retry_transport_error(&e) && attempt + 1 < IMAGE_REQUEST_MAX_ATTEMPTS => {
                 wait_before_image_retry(attempt).await;
@@ -233,8 +265,8 @@ pub async fn push_peer_image(peer: &super::peer_store::PairedPeer, image_id: &st
             }
             Err(e) => return Err(format!("推送局域网图片失败: {}", e)),
         };
-        if !response.status().is_success() {
-            return Err(format!("推送局域网图片失败: {}", response.status()));
+        if !response.is_success() {
+            return Err(format!("推送局域网图片失败: {}", response.status));
         }
         return Ok(());
     }
@@ -248,11 +280,6 @@ pub async fn send_peer_file_stream(
     size: u64,
     reporter: Option<super::transfer::FileTransferProgressReporter>,
 ) -> Result<super::FileTransferResult, String> {
-    let client = build_transfer_client();
-    let config = LanHttpClientConfig {
-        base_url: peer.base_url.clone(),
-        peer_token: peer.peer_token.clone(),
-    };
     let file = tokio::fs::File::open(&path)
         .await
         .map_err(|e| format!("打开待传输文件失败: {}", e))?;
@@ -260,31 +287,30 @@ pub async fn send_peer_file_stream(
         reporter.emit("sending", 0);
     }
     let hasher = Arc::new(Mutex::new(Sha256::new()));
-    let reader = ProgressHashReader::new(file, size, reporter.clone(), hasher.clone());
-    let stream = ReaderStream::with_capacity(reader, FILE_TRANSFER_BUFFER_SIZE);
-    let body = reqwest::Body::wrap_stream(stream);
-    let response = client
-        .put(format!(
-            "{}/qc-transfer/files/{}",
-            config.base_url.trim_end_matches('/'),
-            encode_path_segment(file_name)
-        ))
-        .header("Authorization", config.authorization_header())
-        .header("X-Device-Id", super::runtime::device_id())
-        .header("Content-Length", size)
-        .body(body)
-        .send()
-        .await
-        .map_err(|e| format!("发送局域网文件失败: {}", e))?;
-    if !response.status().is_success() {
+    let mut reader = ProgressHashReader::new(file, 0, size, reporter.clone(), hasher.clone());
+    let request_path = format!("/qc-transfer/files/{}", encode_path_segment(file_name));
+    let response = async {
+        let mut stream = super::secure_channel::connect(peer).await?;
+        write_request_head(&mut stream, "PUT", &request_path, size).await?;
+        let copied = tokio::io::copy(&mut (&mut reader).take(size), &mut stream)
+            .await
+            .map_err(|e| e.to_string())?;
+        if copied != size {
+            return Err(format!("待上传文件大小发生变化: {}/{}", copied, size));
+        }
+        stream.flush().await.map_err(|e| e.to_string())?;
+        read_response(&mut stream).await
+    }
+    .await
+    .map_err(|e| pass_unauthorized(e, |e| format!("发送局域网文件失败: {}", e)))?;
+    if !response.is_success() {
         if let Some(reporter) = reporter.as_ref() {
             reporter.emit("failed", 0);
         }
-        return Err(format!("发送局域网文件失败: {}", response.status()));
+        return Err(format!("发送局域网文件失败: {} {}", response.status, response.message()));
     }
     let mut result = response
         .json::<super::FileTransferResult>()
-        .await
         .map_err(|e| format!("解析局域网文件传输结果失败: {}", e))?;
     let local_sha256 = {
         let guard = hasher.lock().map_err(|_| "局域网文件校验状态异常".to_string())?;
@@ -312,8 +338,58 @@ pub async fn send_peer_file_stream(
     Ok(result)
 }
 
+// 发送分块文件的一块，返回响应和本次实际读出内容的哈希
+pub(crate) async fn send_peer_transfer_chunk(
+    peer: &super::peer_store::PairedPeer,
+    request_path: &str,
+    path: &Path,
+    offset: u64,
+    length: u64,
+    sent_before: u64,
+    reporter: Option<super::transfer::FileTransferProgressReporter>,
+) -> Result<(LanResponse, String), String> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| format!("打开待传输文件失败: {}", e))?;
+    file.seek(std::io::SeekFrom::Start(offset))
+        .await
+        .map_err(|e| format!("读取待传输文件失败: {}", e))?;
+    let hasher = Arc::new(Mutex::new(Sha256::new()));
+    let mut reader = ProgressHashReader::new(file, sent_before, length, reporter, hasher.clone());
+    let response = tokio::time::timeout(Duration::from_secs(SYNC_REQUEST_TIMEOUT_SECS), async {
+        let mut stream = super::secure_channel::connect(peer).await?;
+        write_request_head(&mut stream, "PUT", request_path, length).await?;
+        let copied = tokio::io::copy(&mut (&mut reader).take(length), &mut stream)
+            .await
+            .map_err(|e| e.to_string())?;
+        if copied != length {
+            return Err(format!("待上传文件大小发生变化: {}/{}", copied, length));
+        }
+        stream.flush().await.map_err(|e| e.to_string())?;
+        read_response(&mut stream).await
+    })
+    .await
+    .map_err(|_| "请求超时".to_string())??;
+    let local_sha256 = {
+        let guard = hasher.lock().map_err(|_| "局域网文件校验状态异常".to_string())?;
+        hex::encode(guard.clone().finalize())
+    };
+    Ok((response, local_sha256))
+}
+
+pub(crate) async fn send_transfer_request(
+    peer: &super::peer_store::PairedPeer,
+    method: &str,
+    path: &str,
+    body: &[u8],
+) -> Result<LanResponse, String> {
+    send_sync_request(peer, method, path, body).await
+}
+
+// base 为此前已确认发送的字节数，进度按整个传输累计
 struct ProgressHashReader<R> {
     inner: R,
+    base: u64,
     sent: u64,
     total: u64,
     last_reported: u64,
@@ -324,12 +400,14 @@ struct ProgressHashReader<R> {
 impl<R> ProgressHashReader<R> {
     fn new(
         inner: R,
+        base: u64,
         total: u64,
         reporter: Option<super::transfer::FileTransferProgressReporter>,
         hasher: Arc<Mutex<Sha256>>,
     ) -> Self {
         Self {
             inner,
+            base,
             sent: 0,
             total,
             last_reported: 0,
@@ -359,7 +437,7 @@ impl<R: AsyncRead + Unpin> AsyncRead for ProgressHashReader<R> {
                 if should_report {
                     self.last_reported = self.sent;
                     if let Some(reporter) = self.reporter.as_ref() {
-                        reporter.emit("sending", self.sent);
+                        reporter.emit("sending", self.base + self.sent);
                     }
                 }
             }
@@ -369,25 +447,16 @@ impl<R: AsyncRead + Unpin> AsyncRead for ProgressHashReader<R> {
 }
 
 async fn authorized_get<T: serde::de::DeserializeOwned>(peer: &super::peer_store::PairedPeer, path: &str) -> Result<T, String> {
-    let client = build_client();
-    let config = LanHttpClientConfig {
-        base_url: peer.base_url.clone(),
-        peer_token: peer.peer_token.clone(),
-    };
-    let response = client
-        .get(format!("{}{}", config.base_url.trim_end_matches('/'), path))
-        .header("Authorization", config.authorization_header())
-        .header("X-Device-Id", super::runtime::device_id())
-        .send()
+    let response = send_sync_request(peer, "GET", path, &[])
         .await
-        .map_err(|e| format!("读取局域网同步数据失败({}): {}", path, e))?;
-    if response.status() == reqwest::StatusCode::FORBIDDEN {
+        .map_err(|e| pass_unauthorized(e, |e| format!("读取局域网同步数据失败({}): {}", path, e)))?;
+    if response.status == 403 {
         return Err(LAN_UNAUTHORIZED.to_string());
     }
-    if !response.status().is_success() {
-        return Err(format!("读取局域网同步数据失败({}): {}", path, response.status()));
+    if !response.is_success() {
+        return Err(format!("读取局域网同步数据失败({}): {}", path, response.status));
     }
-    response.json::<T>().await.map_err(|e| format!("解析局域网同步数据失败({}): {}", path, e))
+    response.json::<T>().map_err(|e| format!("解析局域网同步数据失败({}): {}", path, e))
 }
 
 async fn authorized_post<T, B>(peer: &super::peer_store::PairedPeer, path: &str, body: &B) -> Result<T, String>
@@ -395,27 +464,114 @@ where
     T: serde::de::DeserializeOwned,
     B: Serialize + ?Sized,
 {
-    let client = build_client();
-    let config = LanHttpClientConfig {
-        base_url: peer.base_url.clone(),
-        peer_token: peer.peer_token.clone(),
-    };
-    let response = client
-        .post(format!("{}{}", config.base_url.trim_end_matches('/'), path))
-        .header("Authorization", config.authorization_header())
-        .header("X-Device-Id", super::runtime::device_id())
-        .json(body)
-        .send()
+    let body = serde_json::to_vec(body).map_err(|e| format!("推送局域网同步数据失败({}): {}", path, e))?;
+    let response = send_sync_request(peer, "POST", path, &body)
         .await
-        .map_err(|e| format!("推送局域网同步数据失败({}): {}", path, e))?;
-    if !response.status().is_success() {
-        return Err(format!("推送局域网同步数据失败({}): {}", path, response.status()));
+        .map_err(|e| pass_unauthorized(e, |e| format!("推送局域网同步数据失败({}): {}", path, e)))?;
+    if !response.is_success() {
+        return Err(format!("推送局域网同步数据失败({}): {}", path, response.status));
+    }
+    response.json::<T>().map_err(|e| format!("解析局域网推送结果失败({}): {}", path, e))
+}
+
+async fn send_sync_request(
+    peer: &super::peer_store::PairedPeer,
+    method: &str,
+    path: &str,
+    body: &[u8],
+) -> Result<LanResponse, String> {
+    tokio::time::timeout(
+        Duration::from_secs(SYNC_REQUEST_TIMEOUT_SECS),
+        send_request(peer, method, path, body),
+    )
+    .await
+    .map_err(|_| "请求超时".to_string())?
+}
+
+// 每个请求单独建立加密连接，服务端响应后关闭连接
+async fn send_request(
+    peer: &super::peer_store::PairedPeer,
+    method: &str,
+    path: &str,
+    body: &[u8],
+) -> Result<LanResponse, String> {
+    let mut stream = super::secure_channel::connect(peer).await?;
+    write_request_head(&mut stream, method, path, body.len() as u64).await?;
+    stream.write_all(body).await.map_err(|e| e.to_string())?;
+    stream.flush().await.map_err(|e| e.to_string())?;
+    read_response(&mut stream).await
+}
+
+pub(crate) async fn write_request_head(stream: &mut DuplexStream, method: &str, path: &str, content_length: u64) -> Result<(), String> {
+    let head = format!(
+        "{} {} HTTP/1.1\r\nHost: quickclipboard\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        method, path, content_length
+    );
+    stream.write_all(head.as_bytes()).await.map_err(|e| e.to_string())
+}
+
+pub(crate) async fn read_response(stream: &mut DuplexStream) -> Result<LanResponse, String> {
+    let mut buffer = Vec::with_capacity(4096);
+    let mut chunk = vec![0u8; 8192];
+    let header_end = loop {
+        if let Some(pos) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if buffer.len() > RESPONSE_HEADER_LIMIT {
+            return Err("响应头过大".to_string());
+        }
+        let read = stream.read(&mut chunk).await.map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Err("连接已关闭".to_string());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let header_text = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = header_text.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| "响应格式错误".to_string())?;
+    let content_length = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .map(|(_, value)| value.trim().parse::<usize>().map_err(|_| "无效的 Content-Length".to_string()))
+        .transpose()?;
+
+    let mut body = buffer[header_end..].to_vec();
+    match content_length {
+        Some(length) if length > MAX_RESPONSE_BODY_SIZE => return Err("响应体过大".to_string()),
+        Some(length) => {
+            if body.len() < length {
+                let mut rest = vec![0u8; length - body.len()];
+                stream
+                    .read_exact(&mut rest)
+                    .await
+                    .map_err(|_| "连接已关闭，响应不完整".to_string())?;
+                body.extend_from_slice(&rest);
+            }
+            body.truncate(length);
+        }
+        None => {
+            stream.read_to_end(&mut body).await.map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(LanResponse { status, body })
+}
+
+fn pass_unauthorized(error: String, wrap: impl FnOnce(String) -> String) -> String {
+    if error == LAN_UNAUTHORIZED {
+        error
+    } else {
+        wrap(error)
     }
-    response.json::<T>().await.map_err(|e| format!("解析局域网推送结果失败({}): {}", path, e))
 }
 
-fn should_retry_transport_error(error: &reqwest::Error) -> bool {
-    error.is_connect() || error.is_timeout()
+// 设备未授权时重试没有意义
+fn should_retry_transport_error(error: &str) -> bool {
+    error != LAN_UNAUTHORIZED
 }
 
 async fn wait_before_image_retry(attempt: usize) {
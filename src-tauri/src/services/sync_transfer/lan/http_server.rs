@@ -2,13 +2,15 @@ use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use tauri::{AppHandle, Emitter};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use uuid::Uuid;
 
-use super::pairing::PairingConfirmResponse;
+use super::pairing::{PairingConfirmRequest, PairingStartRequest};
+use crate::services::sync_transfer::gossip::GossipBatch;
 
 const HEADER_LIMIT: usize = 64 * 1024;
 const HELLO_PATH: &str = "/qc-sync/hello";
+const PAIRING_START_PATH: &str = "/qc-sync/pairing/start";
 const PAIRING_CONFIRM_PATH: &str = "/qc-sync/pairing/confirm";
 const STATUS_PATH: &str = "/qc-sync/status";
 const SNAPSHOT_PATH: &str = "/qc-sync/snapshot";
@@ -16,9 +18,17 @@ const HISTORY_RECORDS_PATH: &str = "/qc-sync/records/history";
 const FAVORITE_RECORDS_PATH: &str = "/qc-sync/records/favorites";
 const GROUPS_PATH: &str = "/qc-sync/groups";
 const TOMBSTONES_PATH: &str = "/qc-sync/tombstones";
+pub(crate) const GOSSIP_PATH: &str = "/qc-sync/gossip";
+pub(crate) const GOSSIP_CURSORS_PATH: &str = "/qc-sync/gossip/cursors";
 const FILES_PREFIX: &str = "/qc-sync/files/";
 const TRANSFER_FILES_PREFIX: &str = "/qc-transfer/files/";
+pub(crate) const TRANSFER_SESSIONS_PATH: &str = "/qc-transfer/sessions";
+const TRANSFER_SESSIONS_PREFIX: &str = "/qc-transfer/sessions/";
 const MAX_REQUEST_BODY_SIZE: usize = super::files::MAX_DIRECT_TRANSFER_FILE_SIZE as usize;
+const MAX_PAIRING_BODY_SIZE: usize = 64 * 1024;
+pub const PROTOCOL: &str = "quickclipboard-sync-transfer-lan-http";
+// 2 起配对改为口令认证密钥交换，同步请求只走加密通道
+pub const PROTOCOL_VERSION: u32 = 2;
 const FILE_TRANSFER_BUFFER_SIZE: usize = 1024 * 1024;
 
 static SERVER: Lazy<tokio::sync::Mutex<Option<ServerState>>> = Lazy::new(|| tokio::sync::Mutex::new(None));
@@ -74,34 +84,6 @@ impl Default for LanHttpServerConfig {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct HttpPairingConfirmRequest {
-    pub device_id: String,
-    pub device_name: String,
-    pub base_url: String,
-    pub pairing_code: String,
-}
-
-pub fn confirm_pairing(input: HttpPairingConfirmRequest) -> Result<PairingConfirmResponse, String> {
-    let peer_token = super::runtime::confirm_pairing(
-        input.device_id,
-        input.device_name,
-        input.base_url,
-        input.pairing_code,
-    )?;
-    Ok(PairingConfirmResponse {
-        peer_token,
-        expires_at_ms: None,
-    })
-}
-
-pub fn verify_authorization(device_id: &str, authorization: &str) -> bool {
-    let Some(token) = authorization.trim().strip_prefix("Bearer ") else {
-        return false;
-    };
-    super::runtime::verify_peer_token(device_id, token)
-}
-
 pub fn is_running() -> bool {
     if let Ok(state) = SERVER.try_lock() {
         return state
@@ -168,41 +150,92 @@ pub async fn stop() {
 }
 
 async fn handle_client(mut stream: tokio::net::TcpStream, remote_addr: std::net::SocketAddr, app: AppHandle) -> Result<(), String> {
-    let mut request = read_request(&mut stream).await?;
-    let response = if request.method == "PUT" && request.path.starts_with(TRANSFER_FILES_PREFIX) {
-        receive_transfer_file_stream(&request, &mut stream, &app).await
-    } else {
-        read_request_body(&mut request, &mut stream, MAX_REQUEST_BODY_SIZE).await?;
-        match (request.method.as_str(), request.path.as_str()) {
-        ("GET", HELLO_PATH) => json_response(200, serde_json::json!({
-            "device_id": super::runtime::device_id(),
-            "device_name": super::runtime::device_name(),
-            "protocol": "quickclipboard-sync-transfer-lan-http",
-            "version": 1,
-        })),
+    let mut prefix = [0u8; 4];
+    stream.read_exact(&mut prefix).await.map_err(|e| e.to_string())?;
+    if &prefix == super::secure_channel::CHANNEL_MAGIC {
+        let (mut channel, peer_device_id) = super::secure_channel::accept(stream).await?;
+        let mut request = read_request(&mut channel).await?;
+        request.peer_device_id = Some(peer_device_id);
+        if request.method == "POST" && request.path == super::live_share::LIVE_SHARE_PATH {
+            // 实时共享连接会一直保持，直到任一方断开
+            return super::live_share::serve_peer(request, channel, app).await;
+        }
+        let response = handle_secure_request(request, &mut channel, &app).await?;
+        return write_response(&mut channel, response).await;
+    }
+
+    // 明文连接只用于发现设备信息和配对，其余请求必须经过加密通道
+    let mut request = read_request_with_prefix(&mut stream, prefix.to_vec()).await?;
+    if request.content_length > MAX_PAIRING_BODY_SIZE {
+        return write_response(&mut stream, json_response(413, serde_json::json!({ "message": "请求体过大" }))).await;
+    }
+    read_request_body(&mut request, &mut stream, MAX_PAIRING_BODY_SIZE).await?;
+    let response = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", HELLO_PATH) => hello_response(),
+        ("POST", PAIRING_START_PATH) => handle_pairing_start(&request),
         ("POST", PAIRING_CONFIRM_PATH) => handle_pairing_confirm(&request, remote_addr, &app),
+        _ => json_response(403, serde_json::json!({ "message": "未授权的局域网同步请求" })),
+    };
+    write_response(&mut stream, response).await
+}
+
+async fn handle_secure_request(
+    mut request: HttpRequest,
+    stream: &mut tokio::io::DuplexStream,
+    app: &AppHandle,
+) -> Result<HttpResponse, String> {
+    if request.method == "PUT" && request.path.starts_with(TRANSFER_FILES_PREFIX) {
+        return Ok(receive_transfer_file_stream(&request, stream, app).await);
+    }
+    if request.method == "PUT" && request.path.starts_with(TRANSFER_SESSIONS_PREFIX) {
+        return Ok(receive_transfer_chunk(request, stream, app).await);
+    }
+    read_request_body(&mut request, stream, MAX_REQUEST_BODY_SIZE).await?;
+    let response = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", HELLO_PATH) => hello_response(),
         ("GET", STATUS_PATH) => authorized_json(&request, || Ok(super::runtime::status())),
-        ("GET", SNAPSHOT_PATH) => authorized_json(&request, super::snapshot::snapshot),
+        ("GET", SNAPSHOT_PATH) => authorized_json(&request, || super::snapshot::snapshot(&peer_policy(&request))),
         ("GET", HISTORY_RECORDS_PATH) => authorized_json(&request, || {
-            super::snapshot::list_history_records_since(query_i64(&request, "since"))
+            super::snapshot::list_history_records_since(query_i64(&request, "since"), &peer_policy(&request))
         }),
-        ("POST", HISTORY_RECORDS_PATH) => authorized_receive_json(&request, || save_history_records(&request, &app)),
+        ("POST", HISTORY_RECORDS_PATH) => authorized_receive_json(&request, || save_history_records(&request, app)),
         ("GET", FAVORITE_RECORDS_PATH) => authorized_json(&request, || {
-            super::snapshot::list_favorite_records_since(query_i64(&request, "since"))
+            super::snapshot::list_favorite_records_since(query_i64(&request, "since"), &peer_policy(&request))
         }),
-        ("POST", FAVORITE_RECORDS_PATH) => authorized_receive_json(&request, || save_favorite_records(&request, &app)),
-        ("GET", GROUPS_PATH) => authorized_json(&request, super::snapshot::list_groups),
-        ("POST", GROUPS_PATH) => authorized_receive_json(&request, || save_groups(&request, &app)),
+        ("POST", FAVORITE_RECORDS_PATH) => authorized_receive_json(&request, || save_favorite_records(&request, app)),
+        ("GET", GROUPS_PATH) => authorized_json(&request, || super::snapshot::list_groups(&peer_policy(&request))),
+        ("POST", GROUPS_PATH) => authorized_receive_json(&request, || save_groups(&request, app)),
         ("GET", TOMBSTONES_PATH) => authorized_json(&request, || {
-            super::snapshot::list_tombstones_since(query_i64(&request, "since"))
+            super::snapshot::list_tombstones_since(query_i64(&request, "since"), &peer_policy(&request))
         }),
-        ("POST", TOMBSTONES_PATH) => authorized_receive_json(&request, || save_tombstones(&request, &app)),
-        ("GET", path) if path.starts_with(FILES_PREFIX) => authorized_bytes(&request, || read_file(path)),
-        ("PUT", path) if path.starts_with(FILES_PREFIX) => authorized_receive_json(&request, || save_file(path, &request.body)),
+        ("POST", TOMBSTONES_PATH) => authorized_receive_json(&request, || save_tombstones(&request, app)),
+        ("GET", GOSSIP_CURSORS_PATH) => authorized_json(&request, super::mesh::local_cursors),
+        ("POST", GOSSIP_PATH) => authorized_receive_json(&request, || receive_gossip(&request, app)),
+        ("GET", path) if path.starts_with(FILES_PREFIX) => authorized_bytes(&request, || read_file(&request, path)),
+        ("PUT", path) if path.starts_with(FILES_PREFIX) => authorized_receive_json(&request, || save_file(&request, path)),
+        ("POST", TRANSFER_SESSIONS_PATH) => open_transfer_session(&request),
+        ("POST", path) if path.starts_with(TRANSFER_SESSIONS_PREFIX) => complete_transfer_session(&request, path, app),
         _ => json_response(404, serde_json::json!({ "message": "未找到接口" })),
-        }
     };
-    write_response(&mut stream, response).await
+    Ok(response)
+}
+
+fn hello_response() -> HttpResponse {
+    json_response(200, serde_json::json!({
+        "device_id": super::runtime::device_id(),
+        "device_name": super::runtime::device_name(),
+        "protocol": PROTOCOL,
+        "version": PROTOCOL_VERSION,
+    }))
+}
+
+fn handle_pairing_start(request: &HttpRequest) -> HttpResponse {
+    let input = serde_json::from_slice::<PairingStartRequest>(&request.body)
+        .map_err(|e| format!("解析配对请求失败: {}", e));
+    match input.and_then(super::runtime::start_pairing) {
+        Ok(output) => json_response(200, output),
+        Err(message) => json_response(400, serde_json::json!({ "message": message })),
+    }
 }
 
 fn handle_pairing_confirm(
@@ -210,9 +243,12 @@ fn handle_pairing_confirm(
     remote_addr: std::net::SocketAddr,
     app: &AppHandle,
 ) -> HttpResponse {
-    let input = serde_json::from_slice::<HttpPairingConfirmRequest>(&request.body)
+    let input = serde_json::from_slice::<PairingConfirmRequest>(&request.body)
         .map_err(|e| format!("解析配对请求失败: {}", e));
-    match input.map(|input| normalize_pairing_base_url(input, remote_addr)).and_then(confirm_pairing) {
+    let result = input.and_then(|input| {
+        super::runtime::confirm_pairing(input, |base_url| resolve_pairing_base_url(base_url, remote_addr))
+    });
+    match result {
         Ok(output) => {
             let _ = app.emit("sync-transfer-lan-peers-changed", serde_json::json!({}));
             json_response(200, output)
@@ -260,19 +296,9 @@ where
     }
 }
 
+// 加密通道握手时已校验对方设备的长期密钥
 fn is_authorized_request(request: &HttpRequest) -> bool {
-    request
-        .headers
-        .iter()
-        .find(|(name, _)| name.eq_ignore_ascii_case("authorization"))
-        .and_then(|(_, value)| {
-            request
-                .headers
-                .iter()
-                .find(|(name, _)| name.eq_ignore_ascii_case("x-device-id"))
-                .map(|(_, device_id)| verify_authorization(device_id, value))
-        })
-        .unwrap_or(false)
+    request.peer_device_id.is_some()
 }
 
 fn query_i64(request: &HttpRequest, name: &str) -> Option<i64> {
@@ -283,54 +309,80 @@ fn query_i64(request: &HttpRequest, name: &str) -> Option<i64> {
         .and_then(|(_, value)| value.parse::<i64>().ok())
 }
 
+fn peer_policy(request: &HttpRequest) -> super::LanPeerSyncPolicy {
+    super::sync_policy::policy_for(request.peer_device_id.as_deref().unwrap_or_default())
+}
+
 fn save_history_records(request: &HttpRequest, app: &AppHandle) -> Result<super::LanRecordBatch, String> {
     let batch = serde_json::from_slice::<super::LanRecordBatch>(&request.body)
         .map_err(|e| format!("解析局域网历史数据失败: {}", e))?;
+    let records = super::sync_policy::filter_records(
+        &peer_policy(request),
+        crate::services::database::COLLECTION_HISTORY,
+        batch.records,
+    );
     let records = crate::services::database::filter_records_not_deleted(
         crate::services::database::COLLECTION_HISTORY,
-        &batch.records,
+        &records,
     )?;
-    let changed = crate::services::database::lan_upsert_history_records(&records)?;
-    if !changed.is_empty() {
+    let result = crate::services::database::lan_upsert_history_records(&records)?;
+    if !result.changed.is_empty() {
         crate::windows::main_window::mark_clipboard_refresh_pending();
         emit_refresh_if_visible(app);
-        crate::services::sync_transfer::lan_notify_local_change(app.clone(), "relay");
+        super::mesh::relay(app, GossipBatch {
+            history: result.changed.clone(),
+            ..received_batch(request)
+        });
     }
     Ok(super::LanRecordBatch {
         collection: "history".to_string(),
-        records: changed,
+        records: result.changed,
+        conflicts: result.conflicts,
     })
 }
 
 fn save_favorite_records(request: &HttpRequest, app: &AppHandle) -> Result<super::LanRecordBatch, String> {
     let batch = serde_json::from_slice::<super::LanRecordBatch>(&request.body)
         .map_err(|e| format!("解析局域网收藏数据失败: {}", e))?;
+    let records = super::sync_policy::filter_records(
+        &peer_policy(request),
+        crate::services::database::COLLECTION_FAVORITES,
+        batch.records,
+    );
     let records = crate::services::database::filter_records_not_deleted(
         crate::services::database::COLLECTION_FAVORITES,
-        &batch.records,
+        &records,
     )?;
-    let changed = crate::services::database::lan_upsert_favorite_records(&records)?;
-    if !changed.is_empty() {
+    let result = crate::services::database::lan_upsert_favorite_records(&records)?;
+    if !result.changed.is_empty() {
         crate::windows::main_window::mark_favorites_refresh_pending();
         emit_refresh_if_visible(app);
-        crate::services::sync_transfer::lan_notify_local_change(app.clone(), "relay");
+        super::mesh::relay(app, GossipBatch {
+            favorites: result.changed.clone(),
+            ..received_batch(request)
+        });
     }
     Ok(super::LanRecordBatch {
         collection: "favorites".to_string(),
-        records: changed,
+        records: result.changed,
+        conflicts: result.conflicts,
     })
 }
 
 fn save_groups(request: &HttpRequest, app: &AppHandle) -> Result<super::LanGroupBatch, String> {
     let batch = serde_json::from_slice::<super::LanGroupBatch>(&request.body)
         .map_err(|e| format!("解析局域网分组数据失败: {}", e))?;
-    let groups = crate::services::database::filter_groups_not_deleted(&batch.groups)?;
+    let groups = super::sync_policy::filter_groups(&peer_policy(request), batch.groups);
+    let groups = crate::services::database::filter_groups_not_deleted(&groups)?;
     let changed = crate::services::database::lan_save_groups(&groups)?;
     if !changed.is_empty() {
         crate::windows::main_window::mark_groups_refresh_pending();
         crate::windows::main_window::mark_favorites_refresh_pending();
         emit_refresh_if_visible(app);
-        crate::services::sync_transfer::lan_notify_local_change(app.clone(), "relay");
+        super::mesh::relay(app, GossipBatch {
+            groups: changed.clone(),
+            ..received_batch(request)
+        });
     }
     Ok(super::LanGroupBatch { groups: changed })
 }
@@ -338,16 +390,33 @@ fn save_groups(request: &HttpRequest, app: &AppHandle) -> Result<super::LanGroup
 fn save_tombstones(request: &HttpRequest, app: &AppHandle) -> Result<super::LanTombstoneBatch, String> {
     let batch = serde_json::from_slice::<super::LanTombstoneBatch>(&request.body)
         .map_err(|e| format!("解析局域网删除记录失败: {}", e))?;
-    let changed = crate::services::database::upsert_sync_tombstones(&batch.tombstones)?;
-    let report = crate::services::database::apply_sync_tombstones(&batch.tombstones)?;
+    let tombstones = super::sync_policy::filter_tombstones(&peer_policy(request), batch.tombstones);
+    let changed = crate::services::database::upsert_sync_tombstones(&tombstones)?;
+    let report = crate::services::database::apply_sync_tombstones(&tombstones)?;
     mark_tombstone_refresh(&report, app);
-    if !changed.is_empty() || report.total() > 0 {
-        crate::services::sync_transfer::lan_notify_local_change(app.clone(), "relay");
-    }
+    super::mesh::relay(app, GossipBatch {
+        tombstones: changed.clone(),
+        ..received_batch(request)
+    });
     Ok(super::LanTombstoneBatch { tombstones: changed })
 }
 
-fn mark_tombstone_refresh(report: &crate::services::database::SyncTombstoneApplyReport, app: &AppHandle) {
+fn receive_gossip(request: &HttpRequest, app: &AppHandle) -> Result<super::mesh::LanGossipAck, String> {
+    let batch = serde_json::from_slice::<GossipBatch>(&request.body)
+        .map_err(|e| format!("解析局域网转发数据失败: {}", e))?;
+    let batch = batch.forwarded_by(request.peer_device_id.as_deref().unwrap_or_default());
+    let changed = super::mesh::apply_batch(app, batch, &peer_policy(request))?;
+    let applied = changed.len();
+    super::mesh::relay(app, changed);
+    Ok(super::mesh::LanGossipAck { applied })
+}
+
+// 经普通同步接口收到的数据，转发路径从发来数据的设备开始
+fn received_batch(request: &HttpRequest) -> GossipBatch {
+    GossipBatch::from_peer(request.peer_device_id.as_deref().unwrap_or_default())
+}
+
+pub(crate) fn mark_tombstone_refresh(report: &crate::services::database::SyncTombstoneApplyReport, app: &AppHandle) {
     if report.history > 0 {
         crate::windows::main_window::mark_clipboard_refresh_pending();
     }
@@ -363,18 +432,28 @@ fn mark_tombstone_refresh(report: &crate::services::database::SyncTombstoneApply
     }
 }
 
-fn read_file(path: &str) -> Result<Option<Vec<u8>>, String> {
+fn read_file(request: &HttpRequest, path: &str) -> Result<Option<Vec<u8>>, String> {
+    if !peer_policy(request).images {
+        return Ok(None);
+    }
     let image_id = super::files::image_id_from_file_path(path)?;
     super::files::read_image_file(&image_id)
 }
 
-fn save_file(path: &str, bytes: &[u8]) -> Result<serde_json::Value, String> {
+fn save_file(request: &HttpRequest, path: &str) -> Result<serde_json::Value, String> {
+    if !peer_policy(request).images {
+        return Err("同步范围不包含图片".to_string());
+    }
     let image_id = super::files::image_id_from_file_path(path)?;
-    super::files::save_image_file(&image_id, bytes)?;
+    let is_new = !super::files::has_image_file(&image_id)?;
+    super::files::save_image_file(&image_id, &request.body)?;
+    if is_new {
+        super::mesh::relay_image(request.peer_device_id.as_deref().unwrap_or_default(), &image_id);
+    }
     Ok(serde_json::json!({ "saved": true }))
 }
 
-async fn receive_transfer_file_stream(request: &HttpRequest, stream: &mut tokio::net::TcpStream, app: &AppHandle) -> HttpResponse {
+async fn receive_transfer_file_stream<S: AsyncRead + Unpin>(request: &HttpRequest, stream: &mut S, app: &AppHandle) -> HttpResponse {
     if !super::auto_sync::can_receive() {
         return json_response(403, serde_json::json!({ "message": "局域网接收已关闭" }));
     }
@@ -386,7 +465,7 @@ async fn receive_transfer_file_stream(request: &HttpRequest, stream: &mut tokio:
         Ok(value) => value,
         Err(message) => return json_response(500, serde_json::json!({ "message": message })),
     };
-    let source_device_id = request_header(request, "x-device-id").unwrap_or_default();
+    let source_device_id = request.peer_device_id.clone().unwrap_or_default();
     let source_device_name = source_device_name(&source_device_id);
     let reporter = ReceiveTransferProgressReporter {
         app: app.clone(),
@@ -423,9 +502,9 @@ async fn receive_transfer_file_stream(request: &HttpRequest, stream: &mut tokio:
     }
 }
 
-async fn save_transfer_file_stream(
+async fn save_transfer_file_stream<S: AsyncRead + Unpin>(
     request: &HttpRequest,
-    stream: &mut tokio::net::TcpStream,
+    stream: &mut S,
     file_name: String,
     source_device_id: &str,
     source_device_name: &str,
@@ -498,7 +577,146 @@ async fn save_transfer_file_stream(
     }
 }
 
-fn request_header(request: &HttpRequest, name: &str) -> Option<String> {
+fn open_transfer_session(request: &HttpRequest) -> HttpResponse {
+    if let Some(response) = reject_transfer_request(request) {
+        return response;
+    }
+    let manifest = match serde_json::from_slice::<super::transfer_session::TransferManifest>(&request.body)
+        .map_err(|e| format!("解析传输清单失败: {}", e))
+        .and_then(|manifest| manifest.validate().map(|_| manifest))
+    {
+        Ok(manifest) => manifest,
+        Err(message) => return json_response(400, serde_json::json!({ "message": message })),
+    };
+    let source_device_id = request.peer_device_id.clone().unwrap_or_default();
+    match super::transfer_session::open_session(manifest, &source_device_id) {
+        Ok(state) => json_response(200, state),
+        Err(message) => transfer_error_response(message),
+    }
+}
+
+// 分块内容直接从连接流写入会话临时文件，不读入内存
+async fn receive_transfer_chunk<S: AsyncRead + Unpin>(mut request: HttpRequest, stream: &mut S, app: &AppHandle) -> HttpResponse {
+    if let Some(response) = reject_transfer_request(&request) {
+        return response;
+    }
+    let Some((transfer_id, entry_index)) = transfer_chunk_target(&request.path) else {
+        return json_response(404, serde_json::json!({ "message": "未找到接口" }));
+    };
+    let Some(offset) = query_u64(&request, "offset") else {
+        return json_response(400, serde_json::json!({ "message": "缺少传输偏移" }));
+    };
+    let source_device_id = request.peer_device_id.clone().unwrap_or_default();
+    let manifest = match super::transfer_session::load_manifest(&transfer_id, &source_device_id) {
+        Ok(manifest) => manifest,
+        Err(message) => return transfer_error_response(message),
+    };
+    let reporter = transfer_session_reporter(app, &manifest, &source_device_id);
+    let length = request.content_length as u64;
+    let mut body = std::io::Cursor::new(std::mem::take(&mut request.body)).chain(stream);
+    let result = super::transfer_session::write_chunk(&manifest, entry_index, offset, length, &mut body, |received| {
+        reporter.emit("receiving", received);
+    })
+    .await;
+    match result {
+        Ok(state) => json_response(200, state),
+        Err(message) => {
+            if !super::transfer_session::is_retryable_error(&message) || message.starts_with("传输分块校验失败") {
+                reporter.emit("failed", 0);
+            }
+            transfer_error_response(message)
+        }
+    }
+}
+
+fn complete_transfer_session(request: &HttpRequest, path: &str, app: &AppHandle) -> HttpResponse {
+    if let Some(response) = reject_transfer_request(request) {
+        return response;
+    }
+    let Some(transfer_id) = path
+        .strip_prefix(TRANSFER_SESSIONS_PREFIX)
+        .and_then(|rest| rest.strip_suffix("/complete"))
+        .filter(|transfer_id| !transfer_id.contains('/'))
+    else {
+        return json_response(404, serde_json::json!({ "message": "未找到接口" }));
+    };
+    let source_device_id = request.peer_device_id.clone().unwrap_or_default();
+    let manifest = match super::transfer_session::load_manifest(transfer_id, &source_device_id) {
+        Ok(manifest) => manifest,
+        Err(message) => return transfer_error_response(message),
+    };
+    let reporter = transfer_session_reporter(app, &manifest, &source_device_id);
+    match super::transfer_session::complete_session(transfer_id, &source_device_id, &reporter.source_device_name) {
+        Ok(result) => {
+            reporter.emit("done", result.size);
+            crate::windows::receive_box::emit_lan_files_changed(app);
+            json_response(200, result)
+        }
+        Err(message) => {
+            if !super::transfer_session::is_retryable_error(&message) {
+                reporter.emit("failed", 0);
+            }
+            transfer_error_response(message)
+        }
+    }
+}
+
+fn reject_transfer_request(request: &HttpRequest) -> Option<HttpResponse> {
+    if !super::auto_sync::can_receive() {
+        return Some(json_response(403, serde_json::json!({ "message": "局域网接收已关闭" })));
+    }
+    if !is_authorized_request(request) {
+        return Some(json_response(403, serde_json::json!({ "message": "未授权的局域网同步请求" })));
+    }
+    None
+}
+
+// 同一传输的各个分块共用一个进度条目，断点续传后会接着显示
+fn transfer_session_reporter(
+    app: &AppHandle,
+    manifest: &super::transfer_session::TransferManifest,
+    source_device_id: &str,
+) -> ReceiveTransferProgressReporter {
+    ReceiveTransferProgressReporter {
+        app: app.clone(),
+        transfer_id: format!("lan-receive:{}", manifest.transfer_id),
+        file_name: manifest.name.clone(),
+        total_bytes: manifest.total_bytes(),
+        source_device_id: source_device_id.to_string(),
+        source_device_name: source_device_name(source_device_id),
+    }
+}
+
+// 可恢复的错误返回 409，发送方重新打开会话后从确认进度继续
+fn transfer_error_response(message: String) -> HttpResponse {
+    let status_code = if super::transfer_session::is_retryable_error(&message) { 409 } else { 500 };
+    json_response(status_code, serde_json::json!({ "message": message }))
+}
+
+// /qc-transfer/sessions/{transfer_id}/entries/{index}
+fn transfer_chunk_target(path: &str) -> Option<(String, usize)> {
+    let rest = path.strip_prefix(TRANSFER_SESSIONS_PREFIX)?;
+    let mut parts = rest.split('/');
+    let transfer_id = parts.next().filter(|value| !value.is_empty())?;
+    if parts.next()? != "entries" {
+        return None;
+    }
+    let entry_index = parts.next()?.parse::<usize>().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((transfer_id.to_string(), entry_index))
+}
+
+fn query_u64(request: &HttpRequest, name: &str) -> Option<u64> {
+    request
+        .query
+        .iter()
+        .find(|(key, _)| key == name)
+        .and_then(|(_, value)| value.parse::<u64>().ok())
+}
+
+pub(crate) fn request_header(request: &HttpRequest, name: &str) -> Option<String> {
     request
         .headers
         .iter()
@@ -518,8 +736,8 @@ fn source_device_name(device_id: &str) -> String {
         .unwrap_or_default()
 }
 
-async fn copy_exact_to_file(
-    stream: &mut tokio::net::TcpStream,
+async fn copy_exact_to_file<S: AsyncRead + Unpin>(
+    stream: &mut S,
     file: &mut tokio::fs::File,
     hasher: &mut Sha256,
     mut remaining: usize,
@@ -546,23 +764,23 @@ async fn copy_exact_to_file(
     Ok(())
 }
 
-fn emit_refresh_if_visible(app: &AppHandle) {
+pub(crate) fn emit_refresh_if_visible(app: &AppHandle) {
     if crate::windows::main_window::is_main_window_visible_for_updates() {
         let _ = crate::commands::window::emit_main_window_refresh_needed_event(app);
     }
 }
 
-fn normalize_pairing_base_url(mut input: HttpPairingConfirmRequest, remote_addr: std::net::SocketAddr) -> HttpPairingConfirmRequest {
-    let base_url = input.base_url.trim();
+fn resolve_pairing_base_url(base_url: &str, remote_addr: std::net::SocketAddr) -> String {
+    let base_url = base_url.trim();
     if base_url.is_empty()
         || base_url.contains("127.0.0.1")
         || base_url.contains("localhost")
         || base_url.contains("[::1]")
     {
         let port = pairing_base_url_port(base_url).unwrap_or_else(|| running_port().unwrap_or(super::DEFAULT_HTTP_PORT));
-        input.base_url = format!("http://{}:{}", remote_addr.ip(), port);
+        return format!("http://{}:{}", remote_addr.ip(), port);
     }
-    input
+    base_url.to_string()
 }
 
 fn pairing_base_url_port(base_url: &str) -> Option<u16> {
@@ -571,25 +789,36 @@ fn pairing_base_url_port(base_url: &str) -> Option<u16> {
     port.parse::<u16>().ok()
 }
 
-struct HttpRequest {
-    method: String,
-    path: String,
-    query: Vec<(String, String)>,
-    headers: Vec<(String, String)>,
-    body: Vec<u8>,
-    content_length: usize,
+pub(crate) struct HttpRequest {
+    pub(crate) method: String,
+    pub(crate) path: String,
+    pub(crate) query: Vec<(String, String)>,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Vec<u8>,
+    pub(crate) content_length: usize,
+    // 经加密通道认证的对方设备 ID，明文请求为 None
+    pub(crate) peer_device_id: Option<String>,
 }
 
-struct HttpResponse {
+pub(crate) struct HttpResponse {
     status_code: u16,
     body: Vec<u8>,
     content_type: &'static str,
 }
 
-async fn read_request(stream: &mut tokio::net::TcpStream) -> Result<HttpRequest, String> {
-    let mut buffer = Vec::with_capacity(4096);
+pub(crate) async fn read_request<S: AsyncRead + Unpin>(stream: &mut S) -> Result<HttpRequest, String> {
+    read_request_with_prefix(stream, Vec::new()).await
+}
+
+// prefix 为调用方已从连接中读出的开头字节
+async fn read_request_with_prefix<S: AsyncRead + Unpin>(stream: &mut S, prefix: Vec<u8>) -> Result<HttpRequest, String> {
+    let mut buffer = prefix;
+    buffer.reserve(4096);
     let mut chunk = vec![0u8; 2048];
     let header_end = loop {
+        if let Some(pos) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break pos + 4;
+        }
         if buffer.len() > HEADER_LIMIT {
             return Err("请求头过大".to_string());
         }
@@ -598,9 +827,6 @@ async fn read_request(stream: &mut tokio::net::TcpStream) -> Result<HttpRequest,
             return Err("连接已关闭".to_string());
         }
         buffer.extend_from_slice(&chunk[..read]);
-        if let Some(pos) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
-            break pos + 4;
-        }
     };
 
     let header_text = String::from_utf8_lossy(&buffer[..header_end]).to_string();
@@ -658,12 +884,13 @@ async fn read_request(stream: &mut tokio::net::TcpStream) -> Result<HttpRequest,
         headers,
         body,
         content_length,
+        peer_device_id: None,
     })
 }
 
-async fn read_request_body(
+pub(crate) async fn read_request_body<S: AsyncRead + Unpin>(
     request: &mut HttpRequest,
-    stream: &mut tokio::net::TcpStream,
+    stream: &mut S,
     max_size: usize,
 ) -> Result<(), String> {
     if request.content_length > max_size || request.body.len() > max_size {
@@ -681,7 +908,7 @@ async fn read_request_body(
     Ok(())
 }
 
-fn json_response<T: Serialize>(status_code: u16, value: T) -> HttpResponse {
+pub(crate) fn json_response<T: Serialize>(status_code: u16, value: T) -> HttpResponse {
     let body = serde_json::to_vec(&value)
         .unwrap_or_else(|_| "{\"message\":\"序列化响应失败\"}".as_bytes().to_vec());
     HttpResponse {
@@ -699,12 +926,15 @@ fn bytes_response(status_code: u16, body: Vec<u8>) -> HttpResponse {
     }
 }
 
-async fn write_response(stream: &mut tokio::net::TcpStream, response: HttpResponse) -> Result<(), String> {
+pub(crate) async fn write_response<S: AsyncWrite + Unpin>(stream: &mut S, response: HttpResponse) -> Result<(), String> {
     let status_text = match response.status_code {
         200 => "OK",
         400 => "Bad Request",
+        401 => "Unauthorized",
         403 => "Forbidden",
         404 => "Not Found",
+        409 => "Conflict",
+        413 => "Payload Too Large",
         500 => "Internal Server Error",
         _ => "OK",
     };
@@ -0,0 +1,449 @@
+// 局域网实时剪贴板共享
+//
+// 自动同步按快照差异批量推送，延迟较高。实时共享为每个选中的设备保持一条加密长连接，
+// 本机每捕获一条新记录就立即推送，对方收到后写入历史，并可选直接设置为系统剪贴板。
+// 文件类记录只有本机路径，不参与实时共享，仍交给自动同步和文件传输处理。
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, DuplexStream};
+use tokio::sync::broadcast;
+
+use crate::services::database::ClipboardItem;
+use crate::services::sync_transfer::gossip::GossipBatch;
+use crate::services::webdav_sync::types::CloudRecord;
+
+const SETTINGS_KEY: &str = "sync_transfer_lan_live_share_settings";
+pub const LIVE_SHARE_PATH: &str = "/qc-sync/live";
+pub const LIVE_SHARE_DISABLED: &str = "对方未开启实时剪贴板共享";
+const CAPTURE_CHANNEL_CAPACITY: usize = 32;
+const MAX_IMAGE_SIZE: usize = 16 * 1024 * 1024;
+// base64 编码后约为原图的 4/3，另留记录内容的余量
+const MAX_MESSAGE_SIZE: usize = 24 * 1024 * 1024;
+const KEEPALIVE_INTERVAL_SECS: u64 = 20;
+const IDLE_TIMEOUT_SECS: u64 = 60;
+const RECONNECT_BACKOFF_STEPS_MS: [u64; 4] = [1_000, 3_000, 10_000, 30_000];
+
+static CAPTURES: Lazy<broadcast::Sender<Arc<LiveCapture>>> =
+    Lazy::new(|| broadcast::channel(CAPTURE_CHANNEL_CAPACITY).0);
+static LINKS: Lazy<Mutex<HashMap<String, PeerLink>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 已编码的推送帧，附带按设备同步范围过滤所需的信息
+struct LiveCapture {
+    frame: Vec<u8>,
+    has_images: bool,
+}
+
+struct PeerLink {
+    task: tauri::async_runtime::JoinHandle<()>,
+    state: Arc<LinkState>,
+}
+
+#[derive(Default)]
+struct LinkState {
+    connected: AtomicBool,
+    finished: AtomicBool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LanLiveShareSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    // 实时推送的目标设备，接收不受此列表限制
+    #[serde(default)]
+    pub peer_ids: Vec<String>,
+    #[serde(default)]
+    pub apply_to_clipboard: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanLiveShareStatus {
+    pub settings: LanLiveShareSettings,
+    pub connected_peer_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum LiveShareMessage {
+    Clip {
+        record: Box<CloudRecord>,
+        #[serde(default)]
+        images: Vec<LiveShareImage>,
+    },
+    Ping,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LiveShareImage {
+    image_id: String,
+    data: String,
+}
+
+pub fn settings() -> LanLiveShareSettings {
+    crate::services::store::get::<LanLiveShareSettings>(SETTINGS_KEY).unwrap_or_default()
+}
+
+pub fn update_settings(settings: LanLiveShareSettings) -> Result<LanLiveShareSettings, String> {
+    let normalized = normalize_settings(settings);
+    crate::services::store::set(SETTINGS_KEY, &normalized)?;
+    refresh_links();
+    Ok(normalized)
+}
+
+pub fn status() -> LanLiveShareStatus {
+    let mut connected_peer_ids = LINKS
+        .lock()
+        .iter()
+        .filter(|(_, link)| link.state.connected.load(Ordering::SeqCst))
+        .map(|(device_id, _)| device_id.clone())
+        .collect::<Vec<_>>();
+    connected_peer_ids.sort();
+    LanLiveShareStatus {
+        settings: settings(),
+        connected_peer_ids,
+    }
+}
+
+pub fn is_enabled() -> bool {
+    settings().enabled
+}
+
+fn normalize_settings(settings: LanLiveShareSettings) -> LanLiveShareSettings {
+    let mut peer_ids = Vec::with_capacity(settings.peer_ids.len());
+    for device_id in settings.peer_ids {
+        let device_id = device_id.trim().to_string();
+        if !device_id.is_empty() && !peer_ids.contains(&device_id) {
+            peer_ids.push(device_id);
+        }
+    }
+    LanLiveShareSettings {
+        enabled: settings.enabled,
+        peer_ids,
+        apply_to_clipboard: settings.apply_to_clipboard,
+    }
+}
+
+// 按当前设置和配对列表启停各设备的推送连接
+pub fn refresh_links() {
+    let settings = settings();
+    let desired = if settings.enabled {
+        super::peer_store::list_peers()
+            .into_iter()
+            .map(|peer| peer.device_id)
+            .filter(|device_id| settings.peer_ids.contains(device_id))
+            .collect::<Vec<_>>()
+    } else {
+        Vec::new()
+    };
+
+    let mut links = LINKS.lock();
+    links.retain(|device_id, link| {
+        let keep = desired.contains(device_id) && !link.state.finished.load(Ordering::SeqCst);
+        if !keep {
+            link.task.abort();
+        }
+        keep
+    });
+    for device_id in desired {
+        if links.contains_key(&device_id) {
+            continue;
+        }
+        let state = Arc::new(LinkState::default());
+        // 先订阅再启动任务，保证之后发布的记录不会丢
+        let receiver = CAPTURES.subscribe();
+        let task = tauri::async_runtime::spawn(run_link(device_id.clone(), state.clone(), receiver));
+        links.insert(device_id, PeerLink { task, state });
+    }
+}
+
+// 剪贴板监听入库后调用；在捕获线程上执行，读取记录和图片不会阻塞异步运行时
+pub fn publish_capture(item: &ClipboardItem) {
+    if !is_enabled() {
+        return;
+    }
+    refresh_links();
+    if CAPTURES.receiver_count() == 0 {
+        return;
+    }
+    match build_clip_message(item) {
+        Ok(Some(capture)) => {
+            let _ = CAPTURES.send(Arc::new(capture));
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("[局域网实时共享] 准备推送内容失败 id={} 错误={}", item.id, e),
+    }
+}
+
+fn build_clip_message(item: &ClipboardItem) -> Result<Option<LiveCapture>, String> {
+    let primary_type = item.content_type.split(',').next().unwrap_or(&item.content_type);
+    if primary_type == "file" {
+        return Ok(None);
+    }
+    let uuid = match item.uuid.as_deref().filter(|uuid| !uuid.trim().is_empty()) {
+        Some(uuid) => uuid.to_string(),
+        None => crate::services::database::ensure_clipboard_item_uuid(item.id)?,
+    };
+    let Some(record) = crate::services::database::webdav_get_history_record_by_uuid(&uuid, &super::runtime::device_id())? else {
+        return Ok(None);
+    };
+
+    let mut images = Vec::new();
+    for image_id in super::files::collect_record_image_ids(std::slice::from_ref(&record)) {
+        // 图片缺失或过大时不做实时推送，由自动同步补齐
+        let Some(bytes) = super::files::read_image_file(&image_id)? else {
+            return Ok(None);
+        };
+        if bytes.len() > MAX_IMAGE_SIZE {
+            return Ok(None);
+        }
+        images.push(LiveShareImage {
+            image_id,
+            data: BASE64.encode(bytes),
+        });
+    }
+    let has_images = !images.is_empty();
+    let frame = encode_message(&LiveShareMessage::Clip { record: Box::new(record), images })?;
+    Ok(Some(LiveCapture { frame, has_images }))
+}
+
+async fn run_link(
+    device_id: String,
+    state: Arc<LinkState>,
+    mut receiver: broadcast::Receiver<Arc<LiveCapture>>,
+) {
+    let mut failure_count = 0usize;
+    // 设备被移除后退出
+    while let Some(peer) = super::peer_store::list_peers()
+        .into_iter()
+        .find(|peer| peer.device_id == device_id)
+    {
+        let result = push_captures(&peer, &state.connected, &mut receiver).await;
+        if state.connected.swap(false, Ordering::SeqCst) {
+            failure_count = 0;
+        }
+        match result {
+            Ok(()) => break,
+            Err(e) if e == super::http_client::LAN_UNAUTHORIZED => {
+                eprintln!("[局域网实时共享] 设备未授权，停止推送 device_id={}", device_id);
+                break;
+            }
+            Err(e) => {
+                if failure_count == 0 {
+                    eprintln!("[局域网实时共享] 连接中断 device_id={} 错误={}", device_id, e);
+                }
+            }
+        }
+
+        let delay_ms = RECONNECT_BACKOFF_STEPS_MS
+            .get(failure_count)
+            .copied()
+            .unwrap_or(RECONNECT_BACKOFF_STEPS_MS[RECONNECT_BACKOFF_STEPS_MS.len() - 1]);
+        failure_count = failure_count.saturating_add(1);
+        tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+        // 断线期间的旧内容不再补发，避免覆盖对方更新的剪贴板
+        receiver = receiver.resubscribe();
+    }
+    state.finished.store(true, Ordering::SeqCst);
+}
+
+// 正常情况下一直运行到连接断开；发布端关闭时返回 Ok
+async fn push_captures(
+    peer: &super::peer_store::PairedPeer,
+    connected: &AtomicBool,
+    receiver: &mut broadcast::Receiver<Arc<LiveCapture>>,
+) -> Result<(), String> {
+    let mut stream = super::secure_channel::connect(peer).await?;
+    super::http_client::write_request_head(&mut stream, "POST", LIVE_SHARE_PATH, 0).await?;
+    stream.flush().await.map_err(|e| e.to_string())?;
+    let response = super::http_client::read_response(&mut stream).await?;
+    if response.status == 403 {
+        return Err(LIVE_SHARE_DISABLED.to_string());
+    }
+    if !(200..300).contains(&response.status) {
+        return Err(format!("建立实时共享连接失败: {}", response.status));
+    }
+    connected.store(true, Ordering::SeqCst);
+    let _ = super::peer_store::mark_peer_seen(&peer.device_id);
+
+    let ping = encode_message(&LiveShareMessage::Ping)?;
+    let (mut reader, mut writer) = tokio::io::split(stream);
+    let mut probe = [0u8; 1];
+    loop {
+        tokio::select! {
+            capture = receiver.recv() => match capture {
+                Ok(capture) => {
+                    // 范围可能在连接期间修改，每条都重新读取
+                    if allows_clip(&super::sync_policy::policy_for(&peer.device_id), capture.has_images) {
+                        write_message(&mut writer, &capture.frame).await?;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            _ = tokio::time::sleep(Duration::from_secs(KEEPALIVE_INTERVAL_SECS)) => {
+                write_message(&mut writer, &ping).await?;
+            }
+            // 接收方建立连接后不再发送数据，可读说明连接已关闭
+            _ = reader.read(&mut probe) => return Err("对方已断开实时共享连接".to_string()),
+        }
+    }
+}
+
+// 处理对方发起的实时共享连接，请求已由加密通道认证
+pub(crate) async fn serve_peer(
+    request: super::http_server::HttpRequest,
+    mut stream: DuplexStream,
+    app: AppHandle,
+) -> Result<(), String> {
+    let Some(peer_device_id) = request.peer_device_id.clone() else {
+        return Err(super::http_client::LAN_UNAUTHORIZED.to_string());
+    };
+    if !is_enabled() {
+        let response = super::http_server::json_response(403, serde_json::json!({ "message": LIVE_SHARE_DISABLED }));
+        return super::http_server::write_response(&mut stream, response).await;
+    }
+    let response = super::http_server::json_response(200, serde_json::json!({ "accepted": true }));
+    super::http_server::write_response(&mut stream, response).await?;
+    let _ = super::peer_store::mark_peer_seen(&peer_device_id);
+
+    loop {
+        let message = tokio::time::timeout(
+            Duration::from_secs(IDLE_TIMEOUT_SECS),
+            read_message(&mut stream),
+        )
+        .await
+        .map_err(|_| "实时共享连接超时".to_string())??;
+        let Some(message) = message else {
+            return Ok(());
+        };
+        if !is_enabled() {
+            return Ok(());
+        }
+        let LiveShareMessage::Clip { record, images } = message else {
+            continue;
+        };
+        if !allows_clip(&super::sync_policy::policy_for(&peer_device_id), !images.is_empty()) {
+            continue;
+        }
+        let app = app.clone();
+        let from_device_id = peer_device_id.clone();
+        let result = tokio::task::spawn_blocking(move || receive_clip(&app, &from_device_id, *record, images))
+            .await
+            .map_err(|e| e.to_string())?;
+        if let Err(e) = result {
+            eprintln!("[局域网实时共享] 保存对方内容失败 device_id={} 错误={}", peer_device_id, e);
+        }
+    }
+}
+
+// 图片记录不拆开推送，范围不含图片时整条跳过，由自动同步按范围处理
+fn allows_clip(policy: &super::LanPeerSyncPolicy, has_images: bool) -> bool {
+    policy.history && (policy.images || !has_images)
+}
+
+fn receive_clip(
+    app: &AppHandle,
+    from_device_id: &str,
+    record: CloudRecord,
+    images: Vec<LiveShareImage>,
+) -> Result<(), String> {
+    for image in images {
+        let bytes = BASE64
+            .decode(image.data.as_bytes())
+            .map_err(|e| format!("解析实时共享图片失败: {}", e))?;
+        super::files::save_image_file(&image.image_id, &bytes)?;
+    }
+
+    let records = crate::services::database::filter_records_not_deleted(
+        crate::services::database::COLLECTION_HISTORY,
+        std::slice::from_ref(&record),
+    )?;
+    if records.is_empty() {
+        return Ok(());
+    }
+    let result = crate::services::database::lan_upsert_history_records(&records)?;
+    if !result.changed.is_empty() {
+        crate::windows::main_window::mark_clipboard_refresh_pending();
+        super::http_server::emit_refresh_if_visible(app);
+        super::mesh::relay(app, GossipBatch {
+            history: result.changed,
+            ..GossipBatch::from_peer(from_device_id)
+        });
+    }
+
+    if settings().apply_to_clipboard {
+        // 写入前预设内容哈希，监听器不会把它当作新复制再推送回去
+        let item = crate::services::database::get_clipboard_item_id_by_uuid(&record.uuid)?
+            .and_then(|id| crate::services::database::get_clipboard_item_by_id(id).transpose())
+            .transpose()?
+            .ok_or_else(|| "实时共享记录不存在".to_string())?;
+        crate::services::paste::paste_handler::copy_clipboard_item(&item)?;
+    }
+    Ok(())
+}
+
+fn encode_message(message: &LiveShareMessage) -> Result<Vec<u8>, String> {
+    let body = serde_json::to_vec(message).map_err(|e| e.to_string())?;
+    if body.len() > MAX_MESSAGE_SIZE {
+        return Err("实时共享内容过大".to_string());
+    }
+    let mut frame = Vec::with_capacity(4 + body.len());
+    frame.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&body);
+    Ok(frame)
+}
+
+async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, frame: &[u8]) -> Result<(), String> {
+    writer.write_all(frame).await.map_err(|e| e.to_string())?;
+    writer.flush().await.map_err(|e| e.to_string())
+}
+
+async fn read_message<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<LiveShareMessage>, String> {
+    let mut header = [0u8; 4];
+    match reader.read_exact(&mut header).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    }
+    let len = u32::from_be_bytes(header) as usize;
+    if len == 0 || len > MAX_MESSAGE_SIZE {
+        return Err("实时共享数据长度无效".to_string());
+    }
+    let mut body = vec![0u8; len];
+    reader.read_exact(&mut body).await.map_err(|e| e.to_string())?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| format!("解析实时共享数据失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_settings_dedupes_peer_ids() {
+        let settings = normalize_settings(LanLiveShareSettings {
+            enabled: true,
+            peer_ids: vec![" desktop-a ".to_string(), "desktop-a".to_string(), String::new(), "desktop-b".to_string()],
+            apply_to_clipboard: false,
+        });
+        assert_eq!(settings.peer_ids, vec!["desktop-a".to_string(), "desktop-b".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn messages_round_trip_through_frames() {
+        let (mut writer, mut reader) = tokio::io::duplex(1024);
+        let frame = encode_message(&LiveShareMessage::Ping).unwrap();
+        write_message(&mut writer, &frame).await.unwrap();
+        drop(writer);
+        assert!(matches!(read_message(&mut reader).await.unwrap(), Some(LiveShareMessage::Ping)));
+        assert!(read_message(&mut reader).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,177 @@
+// 局域网多跳转发
+//
+// 收到其他设备的数据后，把在本机实际产生变化的部分转发给其余已配对设备，
+// 转发和防环规则见 sync_transfer::gossip。对方是不支持转发接口的旧版本或转发失败时，
+// 改为对其做一次完整同步，由自动同步的重试机制保证最终送达。
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::services::database::{COLLECTION_FAVORITES, COLLECTION_HISTORY};
+use crate::services::sync_transfer::gossip::{self, GossipBatch, OriginCursors};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanGossipCursors {
+    pub device_id: String,
+    pub cursors: OriginCursors,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LanGossipAck {
+    pub applied: usize,
+}
+
+pub fn local_cursors() -> Result<LanGossipCursors, String> {
+    let device_id = super::runtime::device_id();
+    let metas = crate::services::database::webdav_list_history_record_metas()?
+        .into_iter()
+        .chain(crate::services::database::webdav_list_favorite_record_metas()?)
+        .map(|meta| (origin_or_local(meta.source_device_id, &device_id), meta.updated_at))
+        .collect::<Vec<_>>();
+    let groups = crate::services::database::webdav_list_groups(&device_id)?;
+    let tombstones = crate::services::database::list_sync_tombstones_since(None)?;
+    let cursors = gossip::origin_cursors(
+        metas
+            .iter()
+            .map(|(origin, stamp)| (origin.as_str(), *stamp))
+            .chain(groups.iter().map(|group| (group.source_device_id.as_str(), group.updated_at)))
+            .chain(
+                tombstones
+                    .iter()
+                    .map(|tombstone| (tombstone.source_device_id.as_str(), tombstone.deleted_at)),
+            ),
+    );
+    Ok(LanGossipCursors { device_id, cursors })
+}
+
+fn origin_or_local(source_device_id: Option<String>, local_device_id: &str) -> String {
+    source_device_id
+        .filter(|source| !source.trim().is_empty())
+        .unwrap_or_else(|| local_device_id.to_string())
+}
+
+// 按与对方的同步范围写入，返回在本机实际产生变化的部分
+pub(crate) fn apply_batch(
+    app: &AppHandle,
+    batch: GossipBatch,
+    policy: &super::LanPeerSyncPolicy,
+) -> Result<GossipBatch, String> {
+    let tombstones = super::sync_policy::filter_tombstones(policy, batch.tombstones);
+    let changed_tombstones = crate::services::database::upsert_sync_tombstones(&tombstones)?;
+    let report = crate::services::database::apply_sync_tombstones(&tombstones)?;
+    super::http_server::mark_tombstone_refresh(&report, app);
+
+    let history = super::sync_policy::filter_records(policy, COLLECTION_HISTORY, batch.history);
+    let history = crate::services::database::filter_records_not_deleted(COLLECTION_HISTORY, &history)?;
+    let history = crate::services::database::lan_upsert_history_records(&history)?.changed;
+
+    let favorites = super::sync_policy::filter_records(policy, COLLECTION_FAVORITES, batch.favorites);
+    let favorites = crate::services::database::filter_records_not_deleted(COLLECTION_FAVORITES, &favorites)?;
+    let favorites = crate::services::database::lan_upsert_favorite_records(&favorites)?.changed;
+
+    let groups = super::sync_policy::filter_groups(policy, batch.groups);
+    let groups = crate::services::database::filter_groups_not_deleted(&groups)?;
+    let groups = crate::services::database::lan_save_groups(&groups)?;
+
+    if !history.is_empty() {
+        crate::windows::main_window::mark_clipboard_refresh_pending();
+    }
+    if !favorites.is_empty() || !groups.is_empty() {
+        crate::windows::main_window::mark_favorites_refresh_pending();
+    }
+    if !groups.is_empty() {
+        crate::windows::main_window::mark_groups_refresh_pending();
+    }
+    if !history.is_empty() || !favorites.is_empty() || !groups.is_empty() {
+        super::http_server::emit_refresh_if_visible(app);
+    }
+
+    Ok(GossipBatch {
+        route: batch.route,
+        history,
+        favorites,
+        groups,
+        tombstones: changed_tombstones,
+    })
+}
+
+// 收到数据并写入后调用；changed.route 至少包含发来数据的设备
+pub(crate) fn relay(app: &AppHandle, changed: GossipBatch) {
+    if changed.is_empty() {
+        return;
+    }
+    crate::services::webdav_sync::notify_local_change(app.clone(), "relay");
+    if !changed.can_forward() || !super::auto_sync::settings().can_send() {
+        return;
+    }
+    let batch = changed.forwarded_by(&super::runtime::device_id());
+    let peers = super::peer_store::list_peers();
+    let targets = gossip::relay_targets(peers.iter().map(|peer| peer.device_id.as_str()), &batch.route);
+    for peer in peers.into_iter().filter(|peer| targets.contains(&peer.device_id)) {
+        let app = app.clone();
+        let batch = batch.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = relay_to_peer(&peer, batch).await {
+                eprintln!("[局域网同步] 转发失败，改为完整同步 device_id={} 错误={}", peer.device_id, e);
+                super::auto_sync::notify_peer_change(app, &peer.device_id, "relay");
+            }
+        });
+    }
+}
+
+async fn relay_to_peer(peer: &super::peer_store::PairedPeer, batch: GossipBatch) -> Result<(), String> {
+    let Some(remote) = super::http_client::fetch_peer_gossip_cursors(peer).await? else {
+        return Err("对方版本不支持多跳转发".to_string());
+    };
+    let policy = super::sync_policy::policy_for(&peer.device_id);
+    let local_device_id = super::runtime::device_id();
+    let batch = batch.unseen_by(&remote.cursors);
+    let batch = GossipBatch {
+        history: super::sync_policy::filter_outgoing_records(&policy, COLLECTION_HISTORY, batch.history, &local_device_id),
+        favorites: super::sync_policy::filter_outgoing_records(&policy, COLLECTION_FAVORITES, batch.favorites, &local_device_id),
+        groups: super::sync_policy::filter_groups(&policy, batch.groups),
+        tombstones: super::sync_policy::filter_tombstones(&policy, batch.tombstones),
+        route: batch.route,
+    };
+    if batch.is_empty() {
+        return Ok(());
+    }
+    let image_records = if policy.images {
+        batch.history.iter().chain(batch.favorites.iter()).cloned().collect()
+    } else {
+        Vec::new()
+    };
+    super::http_client::push_peer_gossip(peer, &batch).await?;
+    let _ = super::peer_store::mark_peer_seen(&peer.device_id);
+    super::push::spawn_push_images(peer.clone(), "relay", &policy, image_records);
+    Ok(())
+}
+
+// 新收到的图片继续转发；已有的图片不再转发，避免在环状网络中来回传递
+pub(crate) fn relay_image(from_device_id: &str, image_id: &str) {
+    if !super::auto_sync::settings().can_send() {
+        return;
+    }
+    let route = vec![from_device_id.to_string()];
+    let peers = super::peer_store::list_peers();
+    let targets = gossip::relay_targets(peers.iter().map(|peer| peer.device_id.as_str()), &route);
+    for peer in peers.into_iter().filter(|peer| targets.contains(&peer.device_id)) {
+        if !super::sync_policy::policy_for(&peer.device_id).images {
+            continue;
+        }
+        let image_id = image_id.to_string();
+        tauri::async_runtime::spawn(async move {
+            let bytes = match super::files::read_image_file(&image_id) {
+                Ok(Some(bytes)) => bytes,
+                Ok(None) => return,
+                Err(e) => {
+                    eprintln!("[局域网同步] 读取待转发图片失败 image_id={} 错误={}", image_id, e);
+                    return;
+                }
+            };
+            if let Err(e) = super::http_client::push_peer_image(&peer, &image_id, bytes).await {
+                eprintln!("[局域网同步] 转发图片失败 image_id={} 错误={}", image_id, e);
+            }
+        });
+    }
+}
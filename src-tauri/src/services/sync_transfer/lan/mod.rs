@@ -3,13 +3,18 @@ pub mod discovery;
 pub mod files;
 pub mod http_client;
 pub mod http_server;
+pub mod live_share;
+pub mod mesh;
 pub mod pairing;
 pub mod peer_store;
 pub mod pull;
 pub mod push;
 pub mod runtime;
+pub mod secure_channel;
 pub mod snapshot;
+pub mod sync_policy;
 pub mod transfer;
+pub mod transfer_session;
 
 pub const DEFAULT_PAIRING_CODE_TTL_SECS: u64 = 300;
 pub const DEFAULT_PAIRING_MAX_ATTEMPTS: u8 = 5;
@@ -21,3 +26,5 @@ pub use snapshot::{LanGroupBatch, LanRecordBatch, LanSyncSnapshot, LanTombstoneB
 pub use discovery::DiscoveredLanPeer;
 pub use transfer::{FileTransferProgress, FileTransferProgressCallback, FileTransferResult};
 pub use auto_sync::{LanAutoSyncSettings, LanAutoSyncStatus};
+pub use live_share::{LanLiveShareSettings, LanLiveShareStatus};
+pub use sync_policy::LanPeerSyncPolicy;
@@ -1,7 +1,26 @@
+// 局域网配对
+//
+// 配对码只用于一次 CPace 口令认证密钥交换（Ristretto255），不会以任何形式出现在网络上：
+// - 双方用配对码、会话 ID 和两端设备 ID 派生同一个群元素作为生成元，各自发送随机标量乘生成元的结果
+// - 配对码一致时双方得到相同的共享密钥，再用它加密交换各自的设备长期公钥
+// - 中间人每次只能验证一个猜测的配对码，错误次数由被配对方统计并限制
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use hkdf::Hkdf;
+use rand_core::{OsRng, RngCore};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
 
 use super::{DEFAULT_PAIRING_CODE_TTL_SECS, DEFAULT_PAIRING_MAX_ATTEMPTS};
 
+const PAKE_DOMAIN: &[u8] = b"quickclipboard-lan-pairing-cpace-v1";
+const NONCE_LEN: usize = 12;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PairingChallenge {
     pub pairing_code: String,
@@ -9,10 +28,145 @@ pub struct PairingChallenge {
     pub max_attempts: u8,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairingStartRequest {
+    pub device_id: String,
+    pub device_name: String,
+    pub session_id: String,
+    pub share: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairingStartResponse {
+    pub device_id: String,
+    pub share: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairingConfirmRequest {
+    pub session_id: String,
+    pub sealed: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PairingConfirmResponse {
-    pub peer_token: String,
-    pub expires_at_ms: Option<i64>,
+    pub sealed: String,
+}
+
+// 配对成功后交换的设备信息，始终以密文传输
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairingIdentity {
+    pub device_id: String,
+    pub device_name: String,
+    pub base_url: String,
+    pub public_key: String,
+}
+
+pub struct PairingKeys {
+    pub initiator_key: [u8; 32],
+    pub responder_key: [u8; 32],
+}
+
+pub struct PairingHandshake {
+    scalar: Scalar,
+    share: [u8; 32],
+    session_id: String,
+    initiator: bool,
+}
+
+impl PairingHandshake {
+    pub fn new(
+        pairing_code: &str,
+        session_id: &str,
+        initiator_id: &str,
+        responder_id: &str,
+        initiator: bool,
+    ) -> Self {
+        let mut hasher = Sha512::new();
+        for part in [PAKE_DOMAIN, pairing_code.trim().as_bytes(), session_id.as_bytes(), initiator_id.as_bytes(), responder_id.as_bytes()] {
+            hasher.update((part.len() as u64).to_be_bytes());
+            hasher.update(part);
+        }
+        let mut uniform = [0u8; 64];
+        uniform.copy_from_slice(&hasher.finalize());
+        let generator = RistrettoPoint::from_uniform_bytes(&uniform);
+
+        let mut random = [0u8; 64];
+        OsRng.fill_bytes(&mut random);
+        let scalar = Scalar::from_bytes_mod_order_wide(&random);
+        Self {
+            scalar,
+            share: (scalar * generator).compress().to_bytes(),
+            session_id: session_id.to_string(),
+            initiator,
+        }
+    }
+
+    pub fn share(&self) -> String {
+        BASE64.encode(self.share)
+    }
+
+    pub fn finish(self, peer_share: &str) -> Result<PairingKeys, String> {
+        let peer_bytes: [u8; 32] = BASE64
+            .decode(peer_share.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| "配对握手数据无效".to_string())?;
+        let peer_point = CompressedRistretto(peer_bytes)
+            .decompress()
+            .filter(|point| *point != RistrettoPoint::default())
+            .ok_or_else(|| "配对握手数据无效".to_string())?;
+        let shared = (self.scalar * peer_point).compress().to_bytes();
+
+        let (initiator_share, responder_share) = if self.initiator {
+            (self.share, peer_bytes)
+        } else {
+            (peer_bytes, self.share)
+        };
+        let mut ikm = Vec::with_capacity(96);
+        ikm.extend_from_slice(&shared);
+        ikm.extend_from_slice(&initiator_share);
+        ikm.extend_from_slice(&responder_share);
+        let salt = Sha256::digest(self.session_id.as_bytes());
+        let hkdf = Hkdf::<Sha256>::new(Some(&salt), &ikm);
+        let mut keys = PairingKeys {
+            initiator_key: [0u8; 32],
+            responder_key: [0u8; 32],
+        };
+        hkdf.expand(b"initiator", &mut keys.initiator_key)
+            .and_then(|_| hkdf.expand(b"responder", &mut keys.responder_key))
+            .map_err(|_| "派生配对密钥失败".to_string())?;
+        Ok(keys)
+    }
+}
+
+pub fn seal<T: Serialize>(key: &[u8; 32], session_id: &str, value: &T) -> Result<String, String> {
+    let plaintext = serde_json::to_vec(value).map_err(|e| e.to_string())?;
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: &plaintext, aad: session_id.as_bytes() })
+        .map_err(|_| "加密配对数据失败".to_string())?;
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&ciphertext);
+    Ok(BASE64.encode(out))
+}
+
+// 解密失败说明双方配对码不一致，或数据被篡改
+pub fn open<T: DeserializeOwned>(key: &[u8; 32], session_id: &str, sealed: &str) -> Option<T> {
+    let bytes = BASE64.decode(sealed.trim()).ok()?;
+    if bytes.len() <= NONCE_LEN {
+        return None;
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    let plaintext = ChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: session_id.as_bytes() })
+        .ok()?;
+    serde_json::from_slice(&plaintext).ok()
+}
+
+pub fn decode_public_key(value: &str) -> Option<[u8; 32]> {
+    hex::decode(value.trim()).ok()?.try_into().ok()
 }
 
 pub fn create_pairing_challenge() -> PairingChallenge {
@@ -23,10 +177,51 @@ pub fn create_pairing_challenge() -> PairingChallenge {
     }
 }
 
-pub fn create_peer_token() -> String {
-    uuid::Uuid::new_v4().to_string()
-}
-
 fn now_ms() -> i64 {
     chrono::Utc::now().timestamp_millis()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handshake(initiator_code: &str, responder_code: &str) -> (PairingKeys, PairingKeys) {
+        let initiator = PairingHandshake::new(initiator_code, "session", "desktop-a", "desktop-b", true);
+        let responder = PairingHandshake::new(responder_code, "session", "desktop-a", "desktop-b", false);
+        let initiator_share = initiator.share();
+        let responder_share = responder.share();
+        (
+            initiator.finish(&responder_share).unwrap(),
+            responder.finish(&initiator_share).unwrap(),
+        )
+    }
+
+    #[test]
+    fn matching_codes_derive_the_same_keys() {
+        let (initiator, responder) = handshake("123456", "123456");
+        assert_eq!(initiator.initiator_key, responder.initiator_key);
+        assert_eq!(initiator.responder_key, responder.responder_key);
+        assert_ne!(initiator.initiator_key, initiator.responder_key);
+
+        let identity = PairingIdentity {
+            device_id: "desktop-a".to_string(),
+            device_name: "A".to_string(),
+            base_url: "http://192.168.1.2:35691".to_string(),
+            public_key: hex::encode([7u8; 32]),
+        };
+        let sealed = seal(&initiator.initiator_key, "session", &identity).unwrap();
+        let opened = open::<PairingIdentity>(&responder.initiator_key, "session", &sealed).unwrap();
+        assert_eq!(decode_public_key(&opened.public_key), Some([7u8; 32]));
+        assert!(open::<PairingIdentity>(&responder.initiator_key, "other", &sealed).is_none());
+    }
+
+    #[test]
+    fn wrong_code_cannot_open_sealed_identity() {
+        let (initiator, responder) = handshake("123456", "654321");
+        let sealed = seal(&initiator.initiator_key, "session", &"secret").unwrap();
+        assert!(open::<String>(&responder.initiator_key, "session", &sealed).is_none());
+
+        let invalid = PairingHandshake::new("123456", "session", "a", "b", true);
+        assert!(invalid.finish(&BASE64.encode([0u8; 32])).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use super::sync_policy::LanPeerSyncPolicy;
+
 const PAIRED_PEERS_KEY: &str = "sync_transfer_lan_paired_peers";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -7,9 +9,16 @@ pub struct PairedPeer {
     pub device_id: String,
     pub device_name: String,
     pub base_url: String,
-    pub peer_token: String,
+    // 配对时交换的设备长期公钥（hex），旧版本配对的设备为空，需要重新配对
+    #[serde(default)]
+    pub public_key: String,
     pub paired_at_ms: i64,
     pub last_seen_at_ms: Option<i64>,
+    // 对方已确认收到的删除记录截止时间（秒），早于它的删除记录对方都已掌握
+    #[serde(default)]
+    pub tombstones_acked_at: Option<i64>,
+    #[serde(default)]
+    pub sync_policy: LanPeerSyncPolicy,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,17 +28,21 @@ pub struct PairedPeerInfo {
     pub base_url: String,
     pub paired_at_ms: i64,
     pub last_seen_at_ms: Option<i64>,
+    #[serde(default)]
+    pub sync_policy: LanPeerSyncPolicy,
 }
 
 impl PairedPeer {
-    pub fn new(device_id: String, device_name: String, base_url: String, peer_token: String) -> Self {
+    pub fn new(device_id: String, device_name: String, base_url: String, public_key: String) -> Self {
         Self {
             device_id,
             device_name,
             base_url,
-            peer_token,
+            public_key,
             paired_at_ms: chrono::Utc::now().timestamp_millis(),
             last_seen_at_ms: None,
+            tombstones_acked_at: None,
+            sync_policy: LanPeerSyncPolicy::default(),
         }
     }
 
@@ -40,6 +53,7 @@ impl PairedPeer {
             base_url: self.base_url.clone(),
             paired_at_ms: self.paired_at_ms,
             last_seen_at_ms: self.last_seen_at_ms,
+            sync_policy: self.sync_policy.clone(),
         }
     }
 }
@@ -62,8 +76,12 @@ pub fn save_peers(peers: &[PairedPeer]) -> Result<(), String> {
     crate::services::store::set(PAIRED_PEERS_KEY, &peers.to_vec())
 }
 
-pub fn upsert_peer(peer: PairedPeer) -> Result<(), String> {
+// 重新配对同一设备时保留原来的同步范围
+pub fn upsert_peer(mut peer: PairedPeer) -> Result<(), String> {
     let mut peers = list_peers();
+    if let Some(existing) = peers.iter().find(|item| item.device_id == peer.device_id) {
+        peer.sync_policy = existing.sync_policy.clone();
+    }
     peers.retain(|item| !same_peer_identity(item, &peer));
     peers.push(peer);
     save_peers(&peers)
@@ -102,6 +120,30 @@ pub fn mark_peer_seen(device_id: &str) -> Result<(), String> {
     save_peers(&peers)
 }
 
+pub fn mark_peer_tombstones_acked(device_id: &str, acked_at: i64) -> Result<(), String> {
+    let mut peers = list_peers();
+    let Some(peer) = peers.iter_mut().find(|peer| peer.device_id == device_id) else {
+        return Ok(());
+    };
+    if peer.tombstones_acked_at.is_some_and(|current| current >= acked_at) {
+        return Ok(());
+    }
+    peer.tombstones_acked_at = Some(acked_at);
+    save_peers(&peers)
+}
+
+pub fn update_sync_policy(device_id: &str, policy: LanPeerSyncPolicy) -> Result<PairedPeerInfo, String> {
+    let mut peers = list_peers();
+    let peer = peers
+        .iter_mut()
+        .find(|peer| peer.device_id == device_id)
+        .ok_or_else(|| "未找到已配对设备".to_string())?;
+    peer.sync_policy = policy.normalized();
+    let info = peer.info();
+    save_peers(&peers)?;
+    Ok(info)
+}
+
 pub fn remove_peer(device_id: &str) -> Result<bool, String> {
     let mut peers = list_peers();
     let before = peers.len();
@@ -6,43 +6,63 @@ pub async fn pull_from_peer(device_id: &str) -> Result<SyncReport, String> {
         .find(|peer| peer.device_id == device_id)
         .ok_or_else(|| "未找到已配对设备".to_string())?;
 
+    let policy = &peer.sync_policy;
     let mut report = SyncReport::default();
     let tombstones = super::http_client::fetch_peer_tombstones(&peer).await?;
-    let _ = crate::services::database::upsert_sync_tombstones(&tombstones.tombstones)?;
-    let tombstone_report = crate::services::database::apply_sync_tombstones(&tombstones.tombstones)?;
+    let tombstones = super::sync_policy::filter_tombstones(policy, tombstones.tombstones);
+    let _ = crate::services::database::upsert_sync_tombstones(&tombstones)?;
+    let tombstone_report = crate::services::database::apply_sync_tombstones(&tombstones)?;
     report.pulled_clipboard += tombstone_report.history;
     report.pulled_favorites += tombstone_report.favorites;
     report.pulled_groups += tombstone_report.groups;
     report.pulled += tombstone_report.total();
 
-    let history = super::http_client::fetch_peer_history_records(&peer).await?;
+    // 范围外的集合不再请求，对方返回的数据也按范围再过滤一次
+    let history = if policy.history {
+        super::http_client::fetch_peer_history_records(&peer).await?.records
+    } else {
+        Vec::new()
+    };
+    let history = super::sync_policy::filter_records(policy, crate::services::database::COLLECTION_HISTORY, history);
     let history_records = crate::services::database::filter_records_not_deleted(
         crate::services::database::COLLECTION_HISTORY,
-        &history.records,
+        &history,
     )?;
     let changed_history = crate::services::database::lan_upsert_history_records(&history_records)?;
-    let changed_history_count = changed_history.len() as u32;
+    let changed_history_count = changed_history.changed.len() as u32;
     report.pulled_clipboard += changed_history_count;
     report.pulled += changed_history_count;
     report
         .pulled_items
-        .extend(changed_history.iter().map(|record| record.report_item("clipboard")));
+        .extend(changed_history.changed.iter().map(|record| record.report_item("clipboard")));
+    report.conflicts.extend(changed_history.conflicts);
 
-    let favorites = super::http_client::fetch_peer_favorite_records(&peer).await?;
+    let favorites = if policy.favorites {
+        super::http_client::fetch_peer_favorite_records(&peer).await?.records
+    } else {
+        Vec::new()
+    };
+    let favorites = super::sync_policy::filter_records(policy, crate::services::database::COLLECTION_FAVORITES, favorites);
     let favorite_records = crate::services::database::filter_records_not_deleted(
         crate::services::database::COLLECTION_FAVORITES,
-        &favorites.records,
+        &favorites,
     )?;
     let changed_favorites = crate::services::database::lan_upsert_favorite_records(&favorite_records)?;
-    let changed_favorites_count = changed_favorites.len() as u32;
+    let changed_favorites_count = changed_favorites.changed.len() as u32;
     report.pulled_favorites += changed_favorites_count;
     report.pulled += changed_favorites_count;
     report
         .pulled_items
-        .extend(changed_favorites.iter().map(|record| record.report_item("favorites")));
+        .extend(changed_favorites.changed.iter().map(|record| record.report_item("favorites")));
+    report.conflicts.extend(changed_favorites.conflicts);
 
-    let groups = super::http_client::fetch_peer_groups(&peer).await?;
-    let groups = crate::services::database::filter_groups_not_deleted(&groups.groups)?;
+    let groups = if policy.groups {
+        super::http_client::fetch_peer_groups(&peer).await?.groups
+    } else {
+        Vec::new()
+    };
+    let groups = super::sync_policy::filter_groups(policy, groups);
+    let groups = crate::services::database::filter_groups_not_deleted(&groups)?;
     let changed_groups = crate::services::database::lan_save_groups(&groups)?;
     let changed_groups_count = changed_groups.len() as u32;
     report.pulled_groups += changed_groups_count;
@@ -57,11 +77,13 @@ pub async fn pull_from_peer(device_id: &str) -> Result<SyncReport, String> {
         }
     }));
 
-    let image_peer = peer.clone();
-    tauri::async_runtime::spawn(async move {
-        fetch_missing_images_best_effort(&image_peer, &history_records).await;
-        fetch_missing_images_best_effort(&image_peer, &favorite_records).await;
-    });
+    if policy.images {
+        let image_peer = peer.clone();
+        tauri::async_runtime::spawn(async move {
+            fetch_missing_images_best_effort(&image_peer, &history_records).await;
+            fetch_missing_images_best_effort(&image_peer, &favorite_records).await;
+        });
+    }
 
     Ok(report)
 }
@@ -71,9 +93,9 @@ async fn fetch_missing_images_best_effort(
     records: &[crate::services::webdav_sync::types::CloudRecord],
 ) {
     for image_id in super::files::collect_record_image_ids(records) {
-        match super::files::read_image_file(&image_id) {
-            Ok(Some(_)) => continue,
-            Ok(None) => {}
+        match super::files::has_image_file(&image_id) {
+            Ok(true) => continue,
+            Ok(false) => {}
             Err(e) => {
                 eprintln!("[局域网同步] 检查本地图片失败 image_id={} 错误={}", image_id, e);
                 continue;
@@ -9,12 +9,18 @@ pub async fn push_to_peer(device_id: &str) -> Result<SyncReport, String> {
     let local_device_id = super::runtime::device_id();
     let mut report = SyncReport::default();
     let remote_snapshot = super::http_client::fetch_peer_snapshot(&peer).await?;
+    let policy = peer
+        .sync_policy
+        .intersect(&remote_snapshot.peer_policy.clone().unwrap_or_default());
     let mut image_task_started = false;
 
+    let local_tombstones = crate::services::database::list_sync_tombstones_since(None)?;
+    let tombstones_acked_at = super::sync_policy::acknowledged_tombstones_until(&policy, &local_tombstones);
     let tombstones = crate::services::database::tombstones_newer_than_remote(
-        crate::services::database::list_sync_tombstones_since(None)?,
+        local_tombstones,
         &remote_snapshot.tombstone_states,
     );
+    let tombstones = super::sync_policy::filter_tombstones(&policy, tombstones);
     if !tombstones.is_empty() {
         let _ = super::http_client::push_peer_tombstones(
             &peer,
@@ -24,8 +30,17 @@ pub async fn push_to_peer(device_id: &str) -> Result<SyncReport, String> {
         )
         .await?;
     }
+    // 删除记录清理以此为准，只在推送成功后记录
+    if let Some(acked_at) = tombstones_acked_at {
+        let _ = super::peer_store::mark_peer_tombstones_acked(&peer.device_id, acked_at);
+    }
 
-    let local_history_metas = crate::services::database::webdav_list_history_record_metas()?;
+    let local_history_metas = scoped_metas(
+        &policy,
+        crate::services::database::COLLECTION_HISTORY,
+        crate::services::database::webdav_list_history_record_metas()?,
+        &local_device_id,
+    );
     let history_metas = crate::services::database::filter_record_metas_not_deleted_by_states(
         crate::services::database::COLLECTION_HISTORY,
         local_history_metas,
@@ -38,17 +53,19 @@ pub async fn push_to_peer(device_id: &str) -> Result<SyncReport, String> {
     let history_records_to_push = load_history_records(&history_metas_to_push, &local_device_id)?;
     if !history_records_to_push.is_empty() {
         let history_image_records = history_records_to_push.clone();
+        let pushed_history = history_records_to_push.clone();
         let changed_history = match super::http_client::push_peer_history_records(
             &peer,
             super::LanRecordBatch {
                 collection: "history".to_string(),
                 records: history_records_to_push,
+                conflicts: Vec::new(),
             },
         )
         .await {
             Ok(value) => value,
             Err(e) => {
-                spawn_push_images(peer.clone(), "history", history_image_records);
+                spawn_push_images(peer.clone(), "history", &policy, history_image_records);
                 return Err(e);
             }
         };
@@ -57,11 +74,18 @@ pub async fn push_to_peer(device_id: &str) -> Result<SyncReport, String> {
         report
             .pushed_items
             .extend(changed_history.records.iter().map(|record| record.report_item("clipboard")));
-        spawn_push_images(peer.clone(), "history", history_image_records);
+        report.conflicts.extend(changed_history.conflicts);
+        crate::services::database::record_sync_bases(crate::services::database::COLLECTION_HISTORY, &pushed_history)?;
+        spawn_push_images(peer.clone(), "history", &policy, history_image_records);
         image_task_started = true;
     }
 
-    let local_favorite_metas = crate::services::database::webdav_list_favorite_record_metas()?;
+    let local_favorite_metas = scoped_metas(
+        &policy,
+        crate::services::database::COLLECTION_FAVORITES,
+        crate::services::database::webdav_list_favorite_record_metas()?,
+        &local_device_id,
+    );
     let favorite_metas = crate::services::database::filter_record_metas_not_deleted_by_states(
         crate::services::database::COLLECTION_FAVORITES,
         local_favorite_metas,
@@ -74,17 +98,19 @@ pub async fn push_to_peer(device_id: &str) -> Result<SyncReport, String> {
     let favorite_records_to_push = load_favorite_records(&favorite_metas_to_push, &local_device_id)?;
     if !favorite_records_to_push.is_empty() {
         let favorite_image_records = favorite_records_to_push.clone();
+        let pushed_favorites = favorite_records_to_push.clone();
         let changed_favorites = match super::http_client::push_peer_favorite_records(
             &peer,
             super::LanRecordBatch {
                 collection: "favorites".to_string(),
                 records: favorite_records_to_push,
+                conflicts: Vec::new(),
             },
         )
         .await {
             Ok(value) => value,
             Err(e) => {
-                spawn_push_images(peer.clone(), "favorites", favorite_image_records);
+                spawn_push_images(peer.clone(), "favorites", &policy, favorite_image_records);
                 return Err(e);
             }
         };
@@ -93,11 +119,14 @@ pub async fn push_to_peer(device_id: &str) -> Result<SyncReport, String> {
         report
             .pushed_items
             .extend(changed_favorites.records.iter().map(|record| record.report_item("favorites")));
-        spawn_push_images(peer.clone(), "favorites", favorite_image_records);
+        report.conflicts.extend(changed_favorites.conflicts);
+        crate::services::database::record_sync_bases(crate::services::database::COLLECTION_FAVORITES, &pushed_favorites)?;
+        spawn_push_images(peer.clone(), "favorites", &policy, favorite_image_records);
         image_task_started = true;
     }
 
     let local_groups = crate::services::database::webdav_list_groups(&local_device_id)?;
+    let local_groups = super::sync_policy::filter_groups(&policy, local_groups);
     let local_groups = crate::services::database::filter_groups_not_deleted(&local_groups)?;
     let groups = crate::services::database::filter_groups_not_deleted_by_states(
         local_groups,
@@ -129,13 +158,26 @@ pub async fn push_to_peer(device_id: &str) -> Result<SyncReport, String> {
     }
 
     if !image_task_started && report.pushed == 0 {
-        spawn_push_images_from_metas(peer.clone(), "history", history_metas);
-        spawn_push_images_from_metas(peer.clone(), "favorites", favorite_metas);
+        spawn_push_images_from_metas(peer.clone(), "history", &policy, history_metas);
+        spawn_push_images_from_metas(peer.clone(), "favorites", &policy, favorite_metas);
     }
 
     Ok(report)
 }
 
+fn scoped_metas(
+    policy: &super::LanPeerSyncPolicy,
+    collection: &str,
+    metas: Vec<crate::services::webdav_sync::types::CloudRecordMeta>,
+    local_device_id: &str,
+) -> Vec<crate::services::webdav_sync::types::CloudRecordMeta> {
+    let now_secs = chrono::Utc::now().timestamp();
+    metas
+        .into_iter()
+        .filter(|meta| policy.allows_outgoing_meta(collection, meta, local_device_id, now_secs))
+        .collect()
+}
+
 fn load_history_records(
     metas: &[crate::services::webdav_sync::types::CloudRecordMeta],
     device_id: &str,
@@ -165,8 +207,12 @@ fn load_favorite_records(
 fn spawn_push_images_from_metas(
     peer: super::peer_store::PairedPeer,
     collection: &'static str,
+    policy: &super::LanPeerSyncPolicy,
     metas: Vec<crate::services::webdav_sync::types::CloudRecordMeta>,
 ) {
+    if !policy.images {
+        return;
+    }
     let image_ids = metas
         .into_iter()
         .flat_map(|meta| meta.image_id.unwrap_or_default().split(',').map(str::trim).map(str::to_string).collect::<Vec<_>>())
@@ -180,12 +226,13 @@ fn spawn_push_images_from_metas(
     });
 }
 
-fn spawn_push_images(
+pub(super) fn spawn_push_images(
     peer: super::peer_store::PairedPeer,
     collection: &'static str,
+    policy: &super::LanPeerSyncPolicy,
     records: Vec<crate::services::webdav_sync::types::CloudRecord>,
 ) {
-    if records.is_empty() {
+    if !policy.images || records.is_empty() {
         return;
     }
     tauri::async_runtime::spawn(async move {
@@ -2,9 +2,14 @@ use once_cell::sync::Lazy;
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 
-use super::pairing::{create_pairing_challenge, PairingChallenge};
+use super::pairing::{
+    create_pairing_challenge, decode_public_key, open, seal, PairingChallenge, PairingConfirmRequest,
+    PairingConfirmResponse, PairingHandshake, PairingIdentity, PairingKeys, PairingStartRequest, PairingStartResponse,
+};
 use super::peer_store::PairedPeer;
 
+const PENDING_PAIRING_TTL_MS: i64 = 60_000;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PairingCodeView {
     pub pairing_code: String,
@@ -30,9 +35,19 @@ struct PairingState {
     failed_attempts: u8,
 }
 
-#[derive(Debug, Default)]
+// 已完成密钥交换、等待对方确认的配对会话
+struct PendingPairing {
+    session_id: String,
+    device_id: String,
+    device_name: String,
+    keys: PairingKeys,
+    expires_at_ms: i64,
+}
+
+#[derive(Default)]
 struct LanRuntime {
     pairing_state: Option<PairingState>,
+    pending_pairing: Option<PendingPairing>,
 }
 
 static RUNTIME: Lazy<Mutex<LanRuntime>> = Lazy::new(|| Mutex::new(LanRuntime::default()));
@@ -77,13 +92,98 @@ pub fn refresh_pairing_code() -> PairingCodeView {
         failed_attempts: 0,
     };
     let view = view_pairing_state(&state);
-    RUNTIME.lock().pairing_state = Some(state);
+    let mut runtime = RUNTIME.lock();
+    runtime.pairing_state = Some(state);
+    runtime.pending_pairing = None;
     view
 }
 
-pub fn verify_pairing_code(pairing_code: &str) -> Result<(), String> {
+pub fn start_pairing(input: PairingStartRequest) -> Result<PairingStartResponse, String> {
+    let device_id = input.device_id.trim().to_string();
+    if device_id.is_empty() {
+        return Err("设备 ID 不能为空".to_string());
+    }
+    if device_id == self::device_id() {
+        return Err("不能配对当前设备自身".to_string());
+    }
+    let session_id = input.session_id.trim().to_string();
+    if session_id.is_empty() {
+        return Err("配对请求无效".to_string());
+    }
+
     let mut runtime = RUNTIME.lock();
-    let Some(state) = runtime.pairing_state.as_mut() else {
+    let pairing_code = usable_pairing_code(&mut runtime)?;
+    let handshake = PairingHandshake::new(&pairing_code, &session_id, &device_id, &self::device_id(), false);
+    let share = handshake.share();
+    let keys = handshake.finish(&input.share)?;
+    // 同一时间只保留最近一次配对会话
+    runtime.pending_pairing = Some(PendingPairing {
+        session_id,
+        device_id,
+        device_name: input.device_name.trim().to_string(),
+        keys,
+        expires_at_ms: chrono::Utc::now().timestamp_millis() + PENDING_PAIRING_TTL_MS,
+    });
+    Ok(PairingStartResponse {
+        device_id: self::device_id(),
+        share,
+    })
+}
+
+pub fn confirm_pairing<F>(input: PairingConfirmRequest, resolve_base_url: F) -> Result<PairingConfirmResponse, String>
+where
+    F: FnOnce(&str) -> String,
+{
+    let local_public_key = crate::services::sync_transfer::device_identity::device_public_key()?;
+    let (pending, identity) = {
+        let mut runtime = RUNTIME.lock();
+        let pending = runtime
+            .pending_pairing
+            .take()
+            .filter(|pending| pending.session_id == input.session_id.trim() && !is_expired(pending.expires_at_ms))
+            .ok_or_else(|| "配对会话已失效，请重新配对".to_string())?;
+        let identity = open::<PairingIdentity>(&pending.keys.initiator_key, &pending.session_id, &input.sealed);
+        let Some(state) = runtime.pairing_state.as_mut() else {
+            return Err("配对码已刷新，请重新输入".to_string());
+        };
+        // 无法解密说明对方输入的配对码不一致
+        let Some(identity) = identity else {
+            state.failed_attempts = state.failed_attempts.saturating_add(1);
+            return Err("配对码不正确".to_string());
+        };
+        state.failed_attempts = 0;
+        (pending, identity)
+    };
+    if identity.device_id.trim() != pending.device_id || decode_public_key(&identity.public_key).is_none() {
+        return Err("配对请求无效".to_string());
+    }
+
+    let peer_name = if identity.device_name.trim().is_empty() {
+        pending.device_name.clone()
+    } else {
+        identity.device_name.trim().to_string()
+    };
+    let peer = PairedPeer::new(
+        pending.device_id.clone(),
+        peer_name,
+        resolve_base_url(identity.base_url.trim()),
+        identity.public_key.trim().to_ascii_lowercase(),
+    );
+    super::peer_store::upsert_peer(peer)?;
+
+    let reply = PairingIdentity {
+        device_id: self::device_id(),
+        device_name: device_name(),
+        base_url: String::new(),
+        public_key: hex::encode(local_public_key),
+    };
+    Ok(PairingConfirmResponse {
+        sealed: seal(&pending.keys.responder_key, &pending.session_id, &reply)?,
+    })
+}
+
+fn usable_pairing_code(runtime: &mut LanRuntime) -> Result<String, String> {
+    let Some(state) = runtime.pairing_state.as_ref() else {
         runtime.pairing_state = Some(PairingState {
             challenge: create_pairing_challenge(),
             failed_attempts: 0,
@@ -100,45 +200,7 @@ pub fn verify_pairing_code(pairing_code: &str) -> Result<(), String> {
     if state.failed_attempts >= state.challenge.max_attempts {
         return Err("配对码尝试次数过多，请刷新后重试".to_string());
     }
-    if state.challenge.pairing_code != pairing_code.trim() {
-        state.failed_attempts = state.failed_attempts.saturating_add(1);
-        return Err("配对码不正确".to_string());
-    }
-    state.failed_attempts = 0;
-    Ok(())
-}
-
-pub fn confirm_pairing(device_id: String, device_name: String, base_url: String, pairing_code: String) -> Result<String, String> {
-    let device_id = device_id.trim().to_string();
-    if device_id.is_empty() {
-        return Err("设备 ID 不能为空".to_string());
-    }
-    if device_id == self::device_id() {
-        return Err("不能配对当前设备自身".to_string());
-    }
-
-    verify_pairing_code(&pairing_code)?;
-
-    let peer_token = super::pairing::create_peer_token();
-    let peer = PairedPeer::new(
-        device_id,
-        device_name.trim().to_string(),
-        base_url.trim().to_string(),
-        peer_token.clone(),
-    );
-    super::peer_store::upsert_peer(peer)?;
-    Ok(peer_token)
-}
-
-pub fn verify_peer_token(device_id: &str, peer_token: &str) -> bool {
-    let device_id = device_id.trim();
-    let peer_token = peer_token.trim();
-    if device_id.is_empty() || peer_token.is_empty() {
-        return false;
-    }
-    super::peer_store::list_peers()
-        .into_iter()
-        .any(|peer| peer.device_id == device_id && peer.peer_token == peer_token)
+    Ok(state.challenge.pairing_code.clone())
 }
 
 fn view_pairing_state(state: &PairingState) -> PairingCodeView {
@@ -0,0 +1,485 @@
+// 局域网加密通道
+//
+// 配对后的每个 TCP 连接先握手：双方交换临时 X25519 公钥，结合配对时交换的设备长期公钥做三次 DH，
+// 派生出两个方向各自的会话密钥。之后的数据按帧用 ChaCha20-Poly1305 加密，帧序号作为 nonce，
+// 篡改、重放和乱序都会导致解密失败并断开连接。
+// 只有持有对应长期私钥的设备才能算出会话密钥，窃听到的流量无法解密，也无法用来冒充任一方。
+
+use std::time::Duration;
+
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, DuplexStream};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use super::peer_store::PairedPeer;
+
+// 加密连接的前 4 个字节，用于和配对使用的明文 HTTP 请求区分
+pub const CHANNEL_MAGIC: &[u8; 4] = b"QCS1";
+const PROTOCOL_LABEL: &[u8] = b"quickclipboard-lan-channel-v1";
+const MAX_HANDSHAKE_FRAME_SIZE: usize = 4 * 1024;
+const MAX_PLAINTEXT_FRAME_SIZE: usize = 64 * 1024;
+const TAG_SIZE: usize = 16;
+const CONNECT_TIMEOUT_SECS: u64 = 3;
+const HANDSHAKE_TIMEOUT_SECS: u64 = 10;
+
+pub struct LocalIdentity {
+    pub device_id: String,
+    pub secret: StaticSecret,
+}
+
+impl LocalIdentity {
+    pub fn current() -> Result<Self, String> {
+        Ok(Self {
+            device_id: super::runtime::device_id(),
+            secret: crate::services::sync_transfer::device_identity::device_secret_key()?,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct HandshakeHello {
+    device_id: String,
+    ephemeral_key: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HandshakeReply {
+    #[serde(default)]
+    device_id: String,
+    #[serde(default)]
+    ephemeral_key: String,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+struct SessionKeys {
+    send: [u8; 32],
+    receive: [u8; 32],
+}
+
+struct FrameCipher {
+    cipher: ChaCha20Poly1305,
+    counter: u64,
+}
+
+impl FrameCipher {
+    fn new(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(key)),
+            counter: 0,
+        }
+    }
+
+    fn next_nonce(&mut self) -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+        nonce[4..].copy_from_slice(&self.counter.to_be_bytes());
+        self.counter += 1;
+        nonce
+    }
+
+    fn seal(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let nonce = self.next_nonce();
+        self.cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .map_err(|_| "局域网数据加密失败".to_string())
+    }
+
+    fn open(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+        let nonce = self.next_nonce();
+        self.cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext)
+            .map_err(|_| "局域网数据校验失败".to_string())
+    }
+}
+
+// 连接已配对设备，返回的流读写的都是明文
+pub async fn connect(peer: &PairedPeer) -> Result<DuplexStream, String> {
+    let Some(peer_key) = super::pairing::decode_public_key(&peer.public_key) else {
+        // 旧版本配对没有交换设备公钥，需要重新配对
+        return Err(super::http_client::LAN_UNAUTHORIZED.to_string());
+    };
+    let local = LocalIdentity::current()?;
+    let address = peer_address(&peer.base_url)?;
+    let stream = tokio::time::timeout(
+        Duration::from_secs(CONNECT_TIMEOUT_SECS),
+        tokio::net::TcpStream::connect(address.as_str()),
+    )
+    .await
+    .map_err(|_| "连接局域网设备失败: 连接超时".to_string())?
+    .map_err(|e| format!("连接局域网设备失败: {}", e))?;
+    let _ = stream.set_nodelay(true);
+    initiate(stream, &local, &peer.device_id, &peer_key).await
+}
+
+// 接受加密连接，调用前连接开头的 CHANNEL_MAGIC 已被读出；返回明文流和对方设备 ID
+pub async fn accept(stream: tokio::net::TcpStream) -> Result<(DuplexStream, String), String> {
+    let local = LocalIdentity::current()?;
+    let _ = stream.set_nodelay(true);
+    respond(stream, &local, |device_id| {
+        super::peer_store::list_peers()
+            .into_iter()
+            .find(|peer| peer.device_id == device_id)
+            .and_then(|peer| super::pairing::decode_public_key(&peer.public_key))
+    })
+    .await
+}
+
+pub async fn initiate<S>(
+    mut stream: S,
+    local: &LocalIdentity,
+    peer_device_id: &str,
+    peer_key: &[u8; 32],
+) -> Result<DuplexStream, String>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let ephemeral = StaticSecret::random_from_rng(OsRng);
+    let ephemeral_public = PublicKey::from(&ephemeral).to_bytes();
+    let hello = HandshakeHello {
+        device_id: local.device_id.clone(),
+        ephemeral_key: hex::encode(ephemeral_public),
+    };
+    let reply = with_handshake_timeout(async {
+        stream.write_all(CHANNEL_MAGIC).await.map_err(handshake_io_error)?;
+        write_json_frame(&mut stream, &hello).await?;
+        read_json_frame::<_, HandshakeReply>(&mut stream).await
+    })
+    .await?;
+    if reply.error.is_some() {
+        return Err(super::http_client::LAN_UNAUTHORIZED.to_string());
+    }
+    if reply.device_id != peer_device_id {
+        return Err("局域网设备身份不匹配".to_string());
+    }
+    let peer_ephemeral = decode_key(&reply.ephemeral_key)?;
+    let peer_static = PublicKey::from(*peer_key);
+    let peer_ephemeral_public = PublicKey::from(peer_ephemeral);
+
+    let shared = [
+        ephemeral.diffie_hellman(&peer_ephemeral_public),
+        ephemeral.diffie_hellman(&peer_static),
+        local.secret.diffie_hellman(&peer_ephemeral_public),
+    ];
+    let transcript = Transcript {
+        initiator_id: &local.device_id,
+        responder_id: peer_device_id,
+        initiator_static: PublicKey::from(&local.secret).to_bytes(),
+        responder_static: *peer_key,
+        initiator_ephemeral: ephemeral_public,
+        responder_ephemeral: peer_ephemeral,
+    };
+    let keys = derive_session_keys(&shared, &transcript, true)?;
+    Ok(spawn_pumps(stream, keys))
+}
+
+pub async fn respond<S, F>(
+    mut stream: S,
+    local: &LocalIdentity,
+    lookup_peer_key: F,
+) -> Result<(DuplexStream, String), String>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    F: FnOnce(&str) -> Option<[u8; 32]>,
+{
+    let hello = with_handshake_timeout(read_json_frame::<_, HandshakeHello>(&mut stream)).await?;
+    let Some(peer_key) = lookup_peer_key(&hello.device_id) else {
+        let reply = HandshakeReply {
+            error: Some("unauthorized".to_string()),
+            ..Default::default()
+        };
+        let _ = with_handshake_timeout(write_json_frame(&mut stream, &reply)).await;
+        return Err("未授权的局域网同步请求".to_string());
+    };
+    let peer_ephemeral = decode_key(&hello.ephemeral_key)?;
+    let ephemeral = StaticSecret::random_from_rng(OsRng);
+    let ephemeral_public = PublicKey::from(&ephemeral).to_bytes();
+    let reply = HandshakeReply {
+        device_id: local.device_id.clone(),
+        ephemeral_key: hex::encode(ephemeral_public),
+        error: None,
+    };
+    with_handshake_timeout(write_json_frame(&mut stream, &reply)).await?;
+
+    let peer_static = PublicKey::from(peer_key);
+    let peer_ephemeral_public = PublicKey::from(peer_ephemeral);
+    let shared = [
+        ephemeral.diffie_hellman(&peer_ephemeral_public),
+        local.secret.diffie_hellman(&peer_ephemeral_public),
+        ephemeral.diffie_hellman(&peer_static),
+    ];
+    let transcript = Transcript {
+        initiator_id: &hello.device_id,
+        responder_id: &local.device_id,
+        initiator_static: peer_key,
+        responder_static: PublicKey::from(&local.secret).to_bytes(),
+        initiator_ephemeral: peer_ephemeral,
+        responder_ephemeral: ephemeral_public,
+    };
+    let keys = derive_session_keys(&shared, &transcript, false)?;
+    Ok((spawn_pumps(stream, keys), hello.device_id))
+}
+
+struct Transcript<'a> {
+    initiator_id: &'a str,
+    responder_id: &'a str,
+    initiator_static: [u8; 32],
+    responder_static: [u8; 32],
+    initiator_ephemeral: [u8; 32],
+    responder_ephemeral: [u8; 32],
+}
+
+fn derive_session_keys(
+    shared: &[x25519_dalek::SharedSecret; 3],
+    transcript: &Transcript,
+    initiator: bool,
+) -> Result<SessionKeys, String> {
+    if shared.iter().any(|secret| !secret.was_contributory()) {
+        return Err("局域网握手数据无效".to_string());
+    }
+    let mut hasher = Sha256::new();
+    for part in [PROTOCOL_LABEL, transcript.initiator_id.as_bytes(), transcript.responder_id.as_bytes()] {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    hasher.update(transcript.initiator_static);
+    hasher.update(transcript.responder_static);
+    hasher.update(transcript.initiator_ephemeral);
+    hasher.update(transcript.responder_ephemeral);
+    let salt = hasher.finalize();
+
+    let mut ikm = Vec::with_capacity(96);
+    for secret in shared {
+        ikm.extend_from_slice(secret.as_bytes());
+    }
+    let hkdf = Hkdf::<Sha256>::new(Some(&salt), &ikm);
+    let mut to_responder = [0u8; 32];
+    let mut to_initiator = [0u8; 32];
+    hkdf.expand(b"initiator->responder", &mut to_responder)
+        .and_then(|_| hkdf.expand(b"responder->initiator", &mut to_initiator))
+        .map_err(|_| "派生局域网会话密钥失败".to_string())?;
+    Ok(if initiator {
+        SessionKeys { send: to_responder, receive: to_initiator }
+    } else {
+        SessionKeys { send: to_initiator, receive: to_responder }
+    })
+}
+
+// 后台任务负责加解密，调用方像普通连接一样读写返回的明文流；任一方向关闭后对应任务退出
+fn spawn_pumps<S>(stream: S, keys: SessionKeys) -> DuplexStream
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (local_side, pump_side) = tokio::io::duplex(MAX_PLAINTEXT_FRAME_SIZE * 2);
+    let (mut network_reader, mut network_writer) = tokio::io::split(stream);
+    let (mut plain_reader, mut plain_writer) = tokio::io::split(pump_side);
+
+    tokio::spawn(async move {
+        let mut cipher = FrameCipher::new(&keys.receive);
+        while let Ok(Some(frame)) = read_frame(&mut network_reader, MAX_PLAINTEXT_FRAME_SIZE + TAG_SIZE).await {
+            let Ok(plaintext) = cipher.open(&frame) else {
+                break;
+            };
+            if plain_writer.write_all(&plaintext).await.is_err() {
+                break;
+            }
+        }
+        let _ = plain_writer.shutdown().await;
+    });
+
+    tokio::spawn(async move {
+        let mut cipher = FrameCipher::new(&keys.send);
+        let mut buffer = vec![0u8; MAX_PLAINTEXT_FRAME_SIZE];
+        loop {
+            let read = match plain_reader.read(&mut buffer).await {
+                Ok(0) | Err(_) => break,
+                Ok(read) => read,
+            };
+            let Ok(frame) = cipher.seal(&buffer[..read]) else {
+                break;
+            };
+            if write_frame(&mut network_writer, &frame).await.is_err() {
+                break;
+            }
+        }
+        let _ = network_writer.shutdown().await;
+    });
+
+    local_side
+}
+
+async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R, max_size: usize) -> Result<Option<Vec<u8>>, String> {
+    let mut header = [0u8; 4];
+    match reader.read_exact(&mut header).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    }
+    let len = u32::from_be_bytes(header) as usize;
+    if len == 0 || len > max_size {
+        return Err("局域网数据帧长度无效".to_string());
+    }
+    let mut frame = vec![0u8; len];
+    reader.read_exact(&mut frame).await.map_err(|e| e.to_string())?;
+    Ok(Some(frame))
+}
+
+async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, frame: &[u8]) -> Result<(), String> {
+    writer
+        .write_all(&(frame.len() as u32).to_be_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    writer.write_all(frame).await.map_err(|e| e.to_string())?;
+    writer.flush().await.map_err(|e| e.to_string())
+}
+
+async fn read_json_frame<R, T>(reader: &mut R) -> Result<T, String>
+where
+    R: AsyncRead + Unpin,
+    T: serde::de::DeserializeOwned,
+{
+    let frame = read_frame(reader, MAX_HANDSHAKE_FRAME_SIZE)
+        .await
+        .map_err(|e| format!("局域网握手失败: {}", e))?
+        .ok_or_else(|| "局域网握手失败: 连接已关闭".to_string())?;
+    serde_json::from_slice(&frame).map_err(|_| "局域网握手数据无效".to_string())
+}
+
+async fn write_json_frame<W: AsyncWrite + Unpin, T: Serialize>(writer: &mut W, value: &T) -> Result<(), String> {
+    let frame = serde_json::to_vec(value).map_err(|e| e.to_string())?;
+    write_frame(writer, &frame)
+        .await
+        .map_err(|e| format!("局域网握手失败: {}", e))
+}
+
+async fn with_handshake_timeout<T>(
+    future: impl std::future::Future<Output = Result<T, String>>,
+) -> Result<T, String> {
+    tokio::time::timeout(Duration::from_secs(HANDSHAKE_TIMEOUT_SECS), future)
+        .await
+        .map_err(|_| "局域网握手超时".to_string())?
+}
+
+fn handshake_io_error(error: std::io::Error) -> String {
+    format!("局域网握手失败: {}", error)
+}
+
+fn decode_key(value: &str) -> Result<[u8; 32], String> {
+    super::pairing::decode_public_key(value).ok_or_else(|| "局域网握手数据无效".to_string())
+}
+
+fn peer_address(base_url: &str) -> Result<String, String> {
+    let trimmed = base_url.trim();
+    let without_scheme = trimmed
+        .strip_prefix("http://")
+        .or_else(|| trimmed.strip_prefix("https://"))
+        .unwrap_or(trimmed);
+    let authority = without_scheme.split('/').next().unwrap_or("").trim();
+    if authority.is_empty() {
+        return Err("局域网设备地址不能为空".to_string());
+    }
+    let has_port = authority
+        .rsplit_once(':')
+        .map(|(host, port)| !host.ends_with('[') && !port.contains(']') && port.parse::<u16>().is_ok())
+        .unwrap_or(false);
+    if has_port {
+        Ok(authority.to_string())
+    } else {
+        Ok(format!("{}:{}", authority, super::DEFAULT_HTTP_PORT))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(device_id: &str) -> LocalIdentity {
+        LocalIdentity {
+            device_id: device_id.to_string(),
+            secret: StaticSecret::random_from_rng(OsRng),
+        }
+    }
+
+    #[tokio::test]
+    async fn paired_devices_exchange_encrypted_data() {
+        let client = identity("desktop-a");
+        let server = identity("desktop-b");
+        let client_public = PublicKey::from(&client.secret).to_bytes();
+        let server_public = PublicKey::from(&server.secret).to_bytes();
+        let (client_stream, mut server_stream) = tokio::io::duplex(1024);
+
+        let server_task = tokio::spawn(async move {
+            let mut magic = [0u8; 4];
+            server_stream.read_exact(&mut magic).await.unwrap();
+            assert_eq!(&magic, CHANNEL_MAGIC);
+            let (mut channel, peer_id) = respond(server_stream, &server, |id| {
+                (id == "desktop-a").then_some(client_public)
+            })
+            .await
+            .unwrap();
+            let mut request = vec![0u8; 5];
+            channel.read_exact(&mut request).await.unwrap();
+            channel.write_all(&vec![b'x'; 200_000]).await.unwrap();
+            channel.shutdown().await.unwrap();
+            (peer_id, request)
+        });
+
+        let mut channel = initiate(client_stream, &client, "desktop-b", &server_public).await.unwrap();
+        channel.write_all(b"hello").await.unwrap();
+        let mut response = Vec::new();
+        channel.read_to_end(&mut response).await.unwrap();
+        assert_eq!(response.len(), 200_000);
+
+        let (peer_id, request) = server_task.await.unwrap();
+        assert_eq!(peer_id, "desktop-a");
+        assert_eq!(request, b"hello");
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_device_and_wrong_keys() {
+        let client = identity("desktop-a");
+        let server = identity("desktop-b");
+        let server_public = PublicKey::from(&server.secret).to_bytes();
+
+        let (client_stream, mut server_stream) = tokio::io::duplex(1024);
+        let server_task = tokio::spawn(async move {
+            let mut magic = [0u8; 4];
+            server_stream.read_exact(&mut magic).await.unwrap();
+            respond(server_stream, &server, |_| None).await.map(|(_, id)| id)
+        });
+        let result = initiate(client_stream, &client, "desktop-b", &server_public).await;
+        assert_eq!(result.err().as_deref(), Some(super::super::http_client::LAN_UNAUTHORIZED));
+        assert!(server_task.await.unwrap().is_err());
+
+        // 对方记录的公钥与实际私钥不符时，数据无法解密，连接直接断开
+        let impostor = identity("desktop-a");
+        let server = identity("desktop-b");
+        let server_public = PublicKey::from(&server.secret).to_bytes();
+        let expected_client = PublicKey::from(&client.secret).to_bytes();
+        let (client_stream, mut server_stream) = tokio::io::duplex(1024);
+        let server_task = tokio::spawn(async move {
+            let mut magic = [0u8; 4];
+            server_stream.read_exact(&mut magic).await.unwrap();
+            let (mut channel, _) = respond(server_stream, &server, |_| Some(expected_client)).await.unwrap();
+            let mut received = Vec::new();
+            channel.read_to_end(&mut received).await.unwrap();
+            received
+        });
+        let mut channel = initiate(client_stream, &impostor, "desktop-b", &server_public).await.unwrap();
+        channel.write_all(b"steal").await.unwrap();
+        channel.shutdown().await.unwrap();
+        assert!(server_task.await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn resolves_peer_address_from_base_url() {
+        assert_eq!(peer_address("http://192.168.1.5:35691").unwrap(), "192.168.1.5:35691");
+        assert_eq!(peer_address("192.168.1.5").unwrap(), "192.168.1.5:35691");
+        assert_eq!(peer_address("http://[fe80::1]:4000/").unwrap(), "[fe80::1]:4000");
+        assert!(peer_address(" ").is_err());
+    }
+}
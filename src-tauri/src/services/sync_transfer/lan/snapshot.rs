@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use crate::services::webdav_sync::types::{CloudGroup, CloudRecord};
+use crate::services::webdav_sync::types::{CloudGroup, CloudRecord, SyncConflictItem};
+
+use super::sync_policy::{self, LanPeerSyncPolicy};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LanSyncSnapshot {
@@ -11,12 +13,17 @@ pub struct LanSyncSnapshot {
     pub groups: Vec<CloudGroup>,
     #[serde(default)]
     pub tombstone_states: HashMap<String, i64>,
+    // 本机对请求方设置的同步范围，对方推送前据此过滤；旧版本没有该字段
+    #[serde(default)]
+    pub peer_policy: Option<LanPeerSyncPolicy>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LanRecordBatch {
     pub collection: String,
     pub records: Vec<CloudRecord>,
+    #[serde(default)]
+    pub conflicts: Vec<SyncConflictItem>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,23 +36,71 @@ pub struct LanTombstoneBatch {
     pub tombstones: Vec<crate::services::database::SyncTombstone>,
 }
 
-pub fn snapshot() -> Result<LanSyncSnapshot, String> {
+pub fn snapshot(policy: &LanPeerSyncPolicy) -> Result<LanSyncSnapshot, String> {
     let device_id = super::runtime::device_id();
+    let groups = crate::services::database::webdav_list_groups(&device_id)?;
+    let (history_states, favorite_states) = if policy.is_unrestricted() {
+        (
+            crate::services::database::webdav_history_record_states()?,
+            crate::services::database::webdav_favorite_record_states()?,
+        )
+    } else {
+        (
+            scoped_record_states(
+                policy,
+                crate::services::database::COLLECTION_HISTORY,
+                crate::services::database::webdav_list_history_record_metas()?,
+            ),
+            scoped_record_states(
+                policy,
+                crate::services::database::COLLECTION_FAVORITES,
+                crate::services::database::webdav_list_favorite_record_metas()?,
+            ),
+        )
+    };
     Ok(LanSyncSnapshot {
         device_id: device_id.clone(),
-        history_states: crate::services::database::webdav_history_record_states()?,
-        favorite_states: crate::services::database::webdav_favorite_record_states()?,
-        groups: crate::services::database::webdav_list_groups(&device_id)?,
+        history_states,
+        favorite_states,
+        groups: sync_policy::filter_groups(policy, groups),
         tombstone_states: crate::services::database::sync_tombstone_states()?,
+        peer_policy: Some(policy.clone()),
     })
 }
 
-pub fn list_history_records_since(since_updated_at: Option<i64>) -> Result<LanRecordBatch, String> {
+// 范围外的条目不出现在快照中，对方不会据此判断本机已有
+fn scoped_record_states(
+    policy: &LanPeerSyncPolicy,
+    collection: &str,
+    metas: Vec<crate::services::webdav_sync::types::CloudRecordMeta>,
+) -> HashMap<String, i64> {
+    let now_secs = chrono::Utc::now().timestamp();
+    metas
+        .into_iter()
+        .filter(|meta| policy.allows_meta(collection, meta, now_secs))
+        .map(|meta| (meta.uuid, meta.updated_at))
+        .collect()
+}
+
+pub fn list_history_records_since(
+    since_updated_at: Option<i64>,
+    policy: &LanPeerSyncPolicy,
+) -> Result<LanRecordBatch, String> {
     let device_id = super::runtime::device_id();
-    let mut records = crate::services::database::webdav_list_history_records(&device_id)?;
+    let mut records = if policy.history {
+        crate::services::database::webdav_list_history_records(&device_id)?
+    } else {
+        Vec::new()
+    };
     if let Some(since_updated_at) = since_updated_at {
         records.retain(|record| record.updated_at > since_updated_at);
     }
+    let records = sync_policy::filter_outgoing_records(
+        policy,
+        crate::services::database::COLLECTION_HISTORY,
+        records,
+        &device_id,
+    );
     let records = crate::services::database::filter_records_not_deleted(
         crate::services::database::COLLECTION_HISTORY,
         &records,
@@ -53,15 +108,29 @@ pub fn list_history_records_since(since_updated_at: Option<i64>) -> Result<LanRe
     Ok(LanRecordBatch {
         collection: "history".to_string(),
         records,
+        conflicts: Vec::new(),
     })
 }
 
-pub fn list_favorite_records_since(since_updated_at: Option<i64>) -> Result<LanRecordBatch, String> {
+pub fn list_favorite_records_since(
+    since_updated_at: Option<i64>,
+    policy: &LanPeerSyncPolicy,
+) -> Result<LanRecordBatch, String> {
     let device_id = super::runtime::device_id();
-    let mut records = crate::services::database::webdav_list_favorite_records(&device_id)?;
+    let mut records = if policy.favorites {
+        crate::services::database::webdav_list_favorite_records(&device_id)?
+    } else {
+        Vec::new()
+    };
     if let Some(since_updated_at) = since_updated_at {
         records.retain(|record| record.updated_at > since_updated_at);
     }
+    let records = sync_policy::filter_outgoing_records(
+        policy,
+        crate::services::database::COLLECTION_FAVORITES,
+        records,
+        &device_id,
+    );
     let records = crate::services::database::filter_records_not_deleted(
         crate::services::database::COLLECTION_FAVORITES,
         &records,
@@ -69,19 +138,23 @@ pub fn list_favorite_records_since(since_updated_at: Option<i64>) -> Result<LanR
     Ok(LanRecordBatch {
         collection: "favorites".to_string(),
         records,
+        conflicts: Vec::new(),
     })
 }
 
-pub fn list_groups() -> Result<LanGroupBatch, String> {
+pub fn list_groups(policy: &LanPeerSyncPolicy) -> Result<LanGroupBatch, String> {
     let device_id = super::runtime::device_id();
-    let groups = crate::services::database::webdav_list_groups(&device_id)?;
+    let groups = sync_policy::filter_groups(policy, crate::services::database::webdav_list_groups(&device_id)?);
     Ok(LanGroupBatch {
         groups: crate::services::database::filter_groups_not_deleted(&groups)?,
     })
 }
 
-pub fn list_tombstones_since(since_deleted_at: Option<i64>) -> Result<LanTombstoneBatch, String> {
+pub fn list_tombstones_since(since_deleted_at: Option<i64>, policy: &LanPeerSyncPolicy) -> Result<LanTombstoneBatch, String> {
     Ok(LanTombstoneBatch {
-        tombstones: crate::services::database::list_sync_tombstones_since(since_deleted_at)?,
+        tombstones: sync_policy::filter_tombstones(
+            policy,
+            crate::services::database::list_sync_tombstones_since(since_deleted_at)?,
+        ),
     })
 }
@@ -0,0 +1,343 @@
+// 局域网同步的按设备范围
+//
+// 每个已配对设备单独决定同步哪些数据。范围对两个方向都生效：发给对方之前过滤，
+// 收到对方的数据时也丢弃范围外的内容。双方都设置了范围时按两者的交集同步。
+
+use serde::{Deserialize, Serialize};
+
+use crate::services::database::{SyncTombstone, COLLECTION_FAVORITES, COLLECTION_GROUPS, COLLECTION_HISTORY};
+use crate::services::webdav_sync::types::{CloudGroup, CloudRecord, CloudRecordMeta};
+
+// 条目的 updated_at 以秒为单位
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanPeerSyncPolicy {
+    #[serde(default = "default_true")]
+    pub history: bool,
+    #[serde(default = "default_true")]
+    pub favorites: bool,
+    #[serde(default = "default_true")]
+    pub groups: bool,
+    #[serde(default = "default_true")]
+    pub images: bool,
+    // 共享的收藏分组，为空表示全部分组
+    #[serde(default)]
+    pub favorite_groups: Vec<String>,
+    // 只同步最近若干天内更新过的历史和收藏，None 表示不限
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+    // 是否把从其他设备同步来的条目继续转发给该设备
+    #[serde(default = "default_true")]
+    pub relay_remote: bool,
+}
+
+impl Default for LanPeerSyncPolicy {
+    fn default() -> Self {
+        Self {
+            history: true,
+            favorites: true,
+            groups: true,
+            images: true,
+            favorite_groups: Vec::new(),
+            max_age_days: None,
+            relay_remote: true,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl LanPeerSyncPolicy {
+    pub fn normalized(self) -> Self {
+        let mut favorite_groups: Vec<String> = Vec::with_capacity(self.favorite_groups.len());
+        for name in self.favorite_groups {
+            let name = name.trim().to_string();
+            if !name.is_empty() && !favorite_groups.contains(&name) {
+                favorite_groups.push(name);
+            }
+        }
+        Self {
+            favorite_groups,
+            max_age_days: self.max_age_days.filter(|days| *days > 0),
+            ..self
+        }
+    }
+
+    pub fn is_unrestricted(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // 对方也限制了范围时取交集；分组列表没有交集时不再同步收藏
+    pub fn intersect(&self, other: &Self) -> Self {
+        let (favorite_groups, favorites) = match (self.favorite_groups.is_empty(), other.favorite_groups.is_empty()) {
+            (true, _) => (other.favorite_groups.clone(), true),
+            (false, true) => (self.favorite_groups.clone(), true),
+            (false, false) => {
+                let shared = self
+                    .favorite_groups
+                    .iter()
+                    .filter(|name| other.favorite_groups.contains(name))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let any = !shared.is_empty();
+                (shared, any)
+            }
+        };
+        let max_age_days = match (self.max_age_days, other.max_age_days) {
+            (Some(left), Some(right)) => Some(left.min(right)),
+            (left, right) => left.or(right),
+        };
+        Self {
+            history: self.history && other.history,
+            favorites: self.favorites && other.favorites && favorites,
+            groups: self.groups && other.groups,
+            images: self.images && other.images,
+            favorite_groups,
+            max_age_days,
+            relay_remote: self.relay_remote && other.relay_remote,
+        }
+    }
+
+    pub fn allows_collection(&self, collection: &str) -> bool {
+        match collection {
+            COLLECTION_HISTORY => self.history,
+            COLLECTION_FAVORITES => self.favorites,
+            COLLECTION_GROUPS => self.groups,
+            _ => false,
+        }
+    }
+
+    pub fn allows_favorite_group(&self, group_name: &str) -> bool {
+        self.favorite_groups.is_empty() || self.favorite_groups.iter().any(|name| name == group_name)
+    }
+
+    // 收到对方的条目时使用，不检查转发
+    pub fn allows_record(&self, collection: &str, record: &CloudRecord, now_secs: i64) -> bool {
+        self.allows_item(collection, Some(&record.group_name), record.updated_at, now_secs)
+    }
+
+    pub fn allows_outgoing_record(&self, collection: &str, record: &CloudRecord, local_device_id: &str, now_secs: i64) -> bool {
+        self.allows_record(collection, record, now_secs)
+            && (self.relay_remote || !is_relayed(record.is_remote, Some(&record.source_device_id), local_device_id))
+    }
+
+    pub fn allows_meta(&self, collection: &str, meta: &CloudRecordMeta, now_secs: i64) -> bool {
+        self.allows_item(collection, meta.group_name.as_deref(), meta.updated_at, now_secs)
+    }
+
+    pub fn allows_outgoing_meta(&self, collection: &str, meta: &CloudRecordMeta, local_device_id: &str, now_secs: i64) -> bool {
+        self.allows_meta(collection, meta, now_secs)
+            && (self.relay_remote || !is_relayed(meta.is_remote, meta.source_device_id.as_deref(), local_device_id))
+    }
+
+    // 只限定了收藏分组时，分组本身也只同步选中的那些
+    pub fn allows_group(&self, group: &CloudGroup) -> bool {
+        self.groups && self.allows_favorite_group(&group.name)
+    }
+
+    // 删除的收藏已无法得知所在分组，只按集合判断
+    pub fn allows_tombstone(&self, tombstone: &SyncTombstone) -> bool {
+        if tombstone.collection == COLLECTION_GROUPS {
+            return self.groups && self.allows_favorite_group(&tombstone.item_id);
+        }
+        self.allows_collection(&tombstone.collection)
+    }
+
+    fn allows_item(&self, collection: &str, group_name: Option<&str>, updated_at: i64, now_secs: i64) -> bool {
+        if !self.allows_collection(collection) {
+            return false;
+        }
+        if collection == COLLECTION_FAVORITES && !self.allows_favorite_group(group_name.unwrap_or_default()) {
+            return false;
+        }
+        match self.max_age_days {
+            Some(days) => updated_at >= now_secs.saturating_sub(i64::from(days) * DAY_SECS),
+            None => true,
+        }
+    }
+}
+
+// 收藏没有 is_remote 标记，按来源设备判断；来源为空的旧数据视为本机
+fn is_relayed(is_remote: bool, source_device_id: Option<&str>, local_device_id: &str) -> bool {
+    is_remote
+        || source_device_id
+            .map(str::trim)
+            .map(|source| !source.is_empty() && source != local_device_id)
+            .unwrap_or(false)
+}
+
+pub fn policy_for(device_id: &str) -> LanPeerSyncPolicy {
+    super::peer_store::list_peers()
+        .into_iter()
+        .find(|peer| peer.device_id == device_id)
+        .map(|peer| peer.sync_policy)
+        .unwrap_or_default()
+}
+
+pub fn filter_records(policy: &LanPeerSyncPolicy, collection: &str, records: Vec<CloudRecord>) -> Vec<CloudRecord> {
+    let now_secs = chrono::Utc::now().timestamp();
+    records
+        .into_iter()
+        .filter(|record| policy.allows_record(collection, record, now_secs))
+        .collect()
+}
+
+pub fn filter_outgoing_records(
+    policy: &LanPeerSyncPolicy,
+    collection: &str,
+    records: Vec<CloudRecord>,
+    local_device_id: &str,
+) -> Vec<CloudRecord> {
+    let now_secs = chrono::Utc::now().timestamp();
+    records
+        .into_iter()
+        .filter(|record| policy.allows_outgoing_record(collection, record, local_device_id, now_secs))
+        .collect()
+}
+
+pub fn filter_groups(policy: &LanPeerSyncPolicy, groups: Vec<CloudGroup>) -> Vec<CloudGroup> {
+    groups.into_iter().filter(|group| policy.allows_group(group)).collect()
+}
+
+pub fn filter_tombstones(policy: &LanPeerSyncPolicy, tombstones: Vec<SyncTombstone>) -> Vec<SyncTombstone> {
+    tombstones
+        .into_iter()
+        .filter(|tombstone| policy.allows_tombstone(tombstone))
+        .collect()
+}
+
+// 把本机全部删除记录推送给对方后，对方已掌握的删除时间上限；
+// 范围外的删除记录没有发出，只能确认到其中最早一条之前
+pub fn acknowledged_tombstones_until(policy: &LanPeerSyncPolicy, tombstones: &[SyncTombstone]) -> Option<i64> {
+    let first_excluded = tombstones
+        .iter()
+        .filter(|tombstone| !policy.allows_tombstone(tombstone))
+        .map(|tombstone| tombstone.deleted_at)
+        .min();
+    match first_excluded {
+        Some(deleted_at) => Some(deleted_at - 1),
+        None => tombstones.iter().map(|tombstone| tombstone.deleted_at).max(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn favorite(group_name: &str, source_device_id: &str, updated_at: i64) -> CloudRecord {
+        CloudRecord {
+            uuid: "fav-1".to_string(),
+            source_device_id: source_device_id.to_string(),
+            is_remote: false,
+            content: "snippet".to_string(),
+            html_content: None,
+            content_type: "text".to_string(),
+            image_id: None,
+            source_app: None,
+            source_icon_hash: None,
+            char_count: None,
+            title: String::new(),
+            group_name: group_name.to_string(),
+            item_order: 0,
+            paste_count: 0,
+            created_at: updated_at,
+            updated_at,
+            version: Default::default(),
+        }
+    }
+
+    #[test]
+    fn work_group_only_policy_filters_history_and_other_groups() {
+        let policy = LanPeerSyncPolicy {
+            history: false,
+            favorite_groups: vec!["Work".to_string()],
+            ..Default::default()
+        };
+        let now = chrono::Local::now().timestamp();
+        assert!(policy.allows_record(COLLECTION_FAVORITES, &favorite("Work", "local", now), now));
+        assert!(!policy.allows_record(COLLECTION_FAVORITES, &favorite("Personal", "local", now), now));
+        assert!(!policy.allows_record(COLLECTION_HISTORY, &favorite("全部", "local", now), now));
+        let group = CloudGroup {
+            name: "Personal".to_string(),
+            icon: String::new(),
+            color: String::new(),
+            order: 0,
+            source_device_id: "local".to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        assert!(!policy.allows_group(&group));
+    }
+
+    #[test]
+    fn max_age_and_relay_limit_outgoing_records() {
+        let policy = LanPeerSyncPolicy {
+            max_age_days: Some(7),
+            relay_remote: false,
+            ..Default::default()
+        };
+        // 与数据库写入时一样取秒级时间戳
+        let now = chrono::Local::now().timestamp();
+        let fresh = favorite("Work", "local", now - DAY_SECS);
+        let stale = favorite("Work", "local", now - 8 * DAY_SECS);
+        assert!(policy.allows_outgoing_record(COLLECTION_FAVORITES, &favorite("Work", "local", now), "local", now));
+        assert!(policy.allows_outgoing_record(COLLECTION_FAVORITES, &fresh, "local", now));
+        assert!(!policy.allows_outgoing_record(COLLECTION_FAVORITES, &stale, "local", now));
+        let relayed = favorite("Work", "laptop", now);
+        assert!(policy.allows_record(COLLECTION_FAVORITES, &relayed, now));
+        assert!(!policy.allows_outgoing_record(COLLECTION_FAVORITES, &relayed, "local", now));
+    }
+
+    #[test]
+    fn intersect_keeps_only_shared_scope() {
+        let local = LanPeerSyncPolicy {
+            favorite_groups: vec!["Work".to_string(), "Shared".to_string()],
+            max_age_days: Some(30),
+            ..Default::default()
+        };
+        let remote = LanPeerSyncPolicy {
+            images: false,
+            favorite_groups: vec!["Shared".to_string()],
+            max_age_days: Some(7),
+            ..Default::default()
+        };
+        let policy = local.intersect(&remote);
+        assert!(policy.favorites && !policy.images);
+        assert_eq!(policy.favorite_groups, vec!["Shared".to_string()]);
+        assert_eq!(policy.max_age_days, Some(7));
+
+        let disjoint = LanPeerSyncPolicy {
+            favorite_groups: vec!["Personal".to_string()],
+            ..Default::default()
+        };
+        assert!(!local.intersect(&disjoint).favorites);
+        assert!(LanPeerSyncPolicy::default().intersect(&LanPeerSyncPolicy::default()).is_unrestricted());
+    }
+
+    #[test]
+    fn tombstone_ack_stops_before_first_out_of_scope_deletion() {
+        let tombstone = |collection: &str, deleted_at: i64| SyncTombstone {
+            collection: collection.to_string(),
+            item_id: format!("{}-{}", collection, deleted_at),
+            source_device_id: "local".to_string(),
+            deleted_at,
+            created_at: deleted_at,
+        };
+        let tombstones = vec![
+            tombstone(COLLECTION_HISTORY, 100),
+            tombstone(COLLECTION_FAVORITES, 200),
+            tombstone(COLLECTION_HISTORY, 300),
+        ];
+        assert_eq!(acknowledged_tombstones_until(&LanPeerSyncPolicy::default(), &tombstones), Some(300));
+
+        let favorites_only = LanPeerSyncPolicy {
+            history: false,
+            ..Default::default()
+        };
+        assert_eq!(acknowledged_tombstones_until(&favorites_only, &tombstones), Some(99));
+        assert_eq!(acknowledged_tombstones_until(&favorites_only, &[]), None);
+    }
+}
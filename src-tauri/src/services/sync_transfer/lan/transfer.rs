@@ -1,6 +1,21 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::files::OutgoingEntry;
+use super::transfer_session::{TransferEntry, TransferManifest, TransferSessionState, TRANSFER_CHUNK_SIZE};
+
+const RETRY_DELAYS_SECS: [u64; 4] = [1, 2, 5, 10];
+const MAX_RETRIES_WITHOUT_PROGRESS: usize = 5;
+const HASH_BUFFER_SIZE: usize = 1024 * 1024;
+
+// 同一内容发往同一设备时沿用上次未完成的传输 ID，重新发送即可续传；完成后换新 ID
+static OUTGOING_TRANSFER_IDS: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileTransferResult {
@@ -82,16 +97,263 @@ pub async fn send_file_to_peer_with_progress(
         .into_iter()
         .find(|peer| peer.device_id == device_id)
         .ok_or_else(|| "未找到已配对设备".to_string())?;
-    let (file_name, path, size) = super::files::outgoing_file_info(file_path)?;
+    let (name, is_directory, entries) = super::files::outgoing_entries(file_path)?;
+    let total_bytes = entries.iter().map(|entry| entry.size).sum::<u64>();
     let reporter = progress.map(|callback| {
         FileTransferProgressReporter::new(
             transfer_id.unwrap_or_else(|| format!("{}:{}", device_id, file_path)),
             device_id.to_string(),
-            path.to_string_lossy().to_string(),
-            file_name.clone(),
-            size,
+            file_path.to_string(),
+            name.clone(),
+            total_bytes,
             callback,
         )
     });
-    super::http_client::send_peer_file_stream(&peer, &file_name, path, size, reporter).await
+    if let Some(reporter) = reporter.as_ref() {
+        reporter.emit("sending", 0);
+    }
+
+    let result = async {
+        let manifest = build_manifest(device_id, name.clone(), is_directory, entries.clone()).await?;
+        match upload_manifest(&peer, &manifest, &entries, reporter.as_ref()).await {
+            Err(UploadError::Unsupported)
+                if !is_directory && total_bytes <= super::files::MAX_DIRECT_TRANSFER_FILE_SIZE =>
+            {
+                // 对方版本不支持分块传输，退回整文件直传
+                let entry = &entries[0];
+                super::http_client::send_peer_file_stream(&peer, &name, entry.path.clone(), entry.size, reporter.clone())
+                    .await
+            }
+            Err(UploadError::Unsupported) => Err("对方版本不支持该传输，请升级后重试".to_string()),
+            Err(UploadError::Retry(message)) | Err(UploadError::Fatal(message)) => Err(message),
+            Ok(result) => {
+                let key = outgoing_key(device_id, &manifest);
+                if let Ok(mut ids) = OUTGOING_TRANSFER_IDS.lock() {
+                    ids.remove(&key);
+                }
+                verify_result(&manifest, &result)?;
+                if let Some(reporter) = reporter.as_ref() {
+                    reporter.emit("done", total_bytes);
+                }
+                Ok(result)
+            }
+        }
+    }
+    .await;
+    if result.is_err() {
+        if let Some(reporter) = reporter.as_ref() {
+            reporter.emit("failed", 0);
+        }
+    }
+    result
+}
+
+enum UploadError {
+    // 连接中断或对方暂时无法处理，重新打开会话后从确认进度继续
+    Retry(String),
+    Fatal(String),
+    // 对方没有分块传输接口
+    Unsupported,
+}
+
+async fn build_manifest(
+    device_id: &str,
+    name: String,
+    is_directory: bool,
+    entries: Vec<OutgoingEntry>,
+) -> Result<TransferManifest, String> {
+    let entries = tokio::task::spawn_blocking(move || {
+        entries.iter().map(hash_outgoing_entry).collect::<Result<Vec<_>, String>>()
+    })
+    .await
+    .map_err(|e| format!("计算文件校验值失败: {}", e))??;
+    let mut manifest = TransferManifest {
+        transfer_id: String::new(),
+        name,
+        is_directory,
+        chunk_size: TRANSFER_CHUNK_SIZE,
+        entries,
+    };
+    let key = outgoing_key(device_id, &manifest);
+    manifest.transfer_id = OUTGOING_TRANSFER_IDS
+        .lock()
+        .map_err(|_| "传输状态异常".to_string())?
+        .entry(key)
+        .or_insert_with(|| uuid::Uuid::new_v4().simple().to_string())
+        .clone();
+    Ok(manifest)
+}
+
+fn hash_outgoing_entry(entry: &OutgoingEntry) -> Result<TransferEntry, String> {
+    let mut file = std::fs::File::open(&entry.path).map_err(|e| format!("打开待传输文件失败: {}", e))?;
+    let mut whole = Sha256::new();
+    let mut chunk_hashes = Vec::with_capacity(TransferManifest::chunk_count(entry.size, TRANSFER_CHUNK_SIZE));
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+    let mut remaining = entry.size;
+    while remaining > 0 {
+        let mut chunk = Sha256::new();
+        let mut chunk_remaining = remaining.min(TRANSFER_CHUNK_SIZE);
+        remaining -= chunk_remaining;
+        while chunk_remaining > 0 {
+            let read_len = (chunk_remaining as usize).min(buffer.len());
+            let read = file
+                .read(&mut buffer[..read_len])
+                .map_err(|e| format!("读取待传输文件失败: {}", e))?;
+            if read == 0 {
+                return Err("待发送文件在传输过程中被修改".to_string());
+            }
+            chunk.update(&buffer[..read]);
+            whole.update(&buffer[..read]);
+            chunk_remaining -= read as u64;
+        }
+        chunk_hashes.push(hex::encode(chunk.finalize()));
+    }
+    Ok(TransferEntry {
+        path: entry.relative_path.clone(),
+        size: entry.size,
+        sha256: hex::encode(whole.finalize()),
+        chunk_hashes,
+    })
+}
+
+fn outgoing_key(device_id: &str, manifest: &TransferManifest) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(device_id.as_bytes());
+    hasher.update([0]);
+    hasher.update(manifest.name.as_bytes());
+    for entry in &manifest.entries {
+        hasher.update([0]);
+        hasher.update(entry.path.as_bytes());
+        hasher.update(entry.size.to_be_bytes());
+        hasher.update(entry.sha256.as_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+async fn upload_manifest(
+    peer: &super::peer_store::PairedPeer,
+    manifest: &TransferManifest,
+    entries: &[OutgoingEntry],
+    reporter: Option<&FileTransferProgressReporter>,
+) -> Result<FileTransferResult, UploadError> {
+    let mut confirmed = 0u64;
+    let mut failures = 0usize;
+    loop {
+        let before = confirmed;
+        let message = match upload_once(peer, manifest, entries, reporter, &mut confirmed).await {
+            Err(UploadError::Retry(message)) => message,
+            other => return other,
+        };
+        if confirmed > before {
+            failures = 0;
+        }
+        failures += 1;
+        if failures >= MAX_RETRIES_WITHOUT_PROGRESS {
+            return Err(UploadError::Fatal(message));
+        }
+        let delay = RETRY_DELAYS_SECS
+            .get(failures - 1)
+            .copied()
+            .unwrap_or(RETRY_DELAYS_SECS[RETRY_DELAYS_SECS.len() - 1]);
+        tokio::time::sleep(Duration::from_secs(delay)).await;
+    }
+}
+
+// 打开（或恢复）会话后按对方确认的进度补传剩余分块
+async fn upload_once(
+    peer: &super::peer_store::PairedPeer,
+    manifest: &TransferManifest,
+    entries: &[OutgoingEntry],
+    reporter: Option<&FileTransferProgressReporter>,
+    confirmed: &mut u64,
+) -> Result<FileTransferResult, UploadError> {
+    let body = serde_json::to_vec(manifest).map_err(|e| UploadError::Fatal(format!("生成传输清单失败: {}", e)))?;
+    let sessions_path = super::http_server::TRANSFER_SESSIONS_PATH;
+    let response = super::http_client::send_transfer_request(peer, "POST", sessions_path, &body)
+        .await
+        .map_err(transport_error)?;
+    if response.status == 404 {
+        return Err(UploadError::Unsupported);
+    }
+    let mut state = session_response::<TransferSessionState>(response)?;
+
+    for (index, entry) in manifest.entries.iter().enumerate() {
+        loop {
+            if state.offsets.len() != manifest.entries.len() {
+                return Err(UploadError::Fatal("传输会话状态异常".to_string()));
+            }
+            *confirmed = state.offsets.iter().sum();
+            let offset = state.offsets[index];
+            if offset >= entry.size {
+                break;
+            }
+            let length = manifest.chunk_size.min(entry.size - offset);
+            let chunk_index = (offset / manifest.chunk_size) as usize;
+            let request_path = format!(
+                "{}/{}/entries/{}?offset={}",
+                sessions_path, manifest.transfer_id, index, offset
+            );
+            let (response, local_sha256) = super::http_client::send_peer_transfer_chunk(
+                peer,
+                &request_path,
+                &entries[index].path,
+                offset,
+                length,
+                *confirmed,
+                reporter.cloned(),
+            )
+            .await
+            .map_err(transport_error)?;
+            if !local_sha256.eq_ignore_ascii_case(&entry.chunk_hashes[chunk_index]) {
+                return Err(UploadError::Fatal("待发送文件在传输过程中被修改".to_string()));
+            }
+            state = session_response::<TransferSessionState>(response)?;
+            if state.offsets.get(index).copied().unwrap_or(0) <= offset {
+                return Err(UploadError::Retry("局域网文件传输进度没有推进".to_string()));
+            }
+        }
+    }
+    *confirmed = manifest.total_bytes();
+
+    let complete_path = format!("{}/{}/complete", sessions_path, manifest.transfer_id);
+    let response = super::http_client::send_transfer_request(peer, "POST", &complete_path, &[])
+        .await
+        .map_err(transport_error)?;
+    session_response::<FileTransferResult>(response)
+}
+
+fn session_response<T: serde::de::DeserializeOwned>(
+    response: super::http_client::LanResponse,
+) -> Result<T, UploadError> {
+    if response.is_success() {
+        return response
+            .json::<T>()
+            .map_err(|e| UploadError::Fatal(format!("解析局域网文件传输结果失败: {}", e)));
+    }
+    let message = format!("发送局域网文件失败: {} {}", response.status, response.message());
+    match response.status {
+        400 | 403 => Err(UploadError::Fatal(message)),
+        _ => Err(UploadError::Retry(message)),
+    }
+}
+
+fn transport_error(error: String) -> UploadError {
+    if error == super::http_client::LAN_UNAUTHORIZED {
+        UploadError::Fatal(error)
+    } else {
+        UploadError::Retry(format!("发送局域网文件失败: {}", error))
+    }
+}
+
+fn verify_result(manifest: &TransferManifest, result: &FileTransferResult) -> Result<(), String> {
+    let total_bytes = manifest.total_bytes();
+    if result.size != total_bytes {
+        return Err(format!("局域网文件大小校验失败: 本地 {} 字节，对方 {} 字节", total_bytes, result.size));
+    }
+    if let (false, Some(remote_sha256)) = (manifest.is_directory, result.sha256.as_deref()) {
+        if !remote_sha256.eq_ignore_ascii_case(&manifest.entries[0].sha256) {
+            return Err("局域网文件内容校验失败，请重新发送".to_string());
+        }
+    }
+    Ok(())
 }
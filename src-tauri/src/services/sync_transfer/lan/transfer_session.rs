@@ -0,0 +1,499 @@
+// 局域网分块文件传输（接收端）
+//
+// 发送方先提交传输清单（每个文件的大小、整体哈希和逐块哈希），再按顺序逐块上传。
+// 每块直接写入会话目录下的临时文件并校验哈希，不一致时回退到块起点。
+// 已确认的进度由临时文件长度推出，连接中断后重新提交同一清单即可从断点继续；全部完成后才移入接收目录。
+// 完成后会话只保留保存结果，发送方没收到完成响应而重试时直接返回同一结果。
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+pub const TRANSFER_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+const MIN_CHUNK_SIZE: u64 = 64 * 1024;
+const MAX_CHUNK_SIZE: u64 = 64 * 1024 * 1024;
+const MAX_MANIFEST_ENTRIES: usize = 100_000;
+const SESSION_FILE_NAME: &str = "session.json";
+const STALE_SESSION_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+const COMPLETED_SESSION_MS: i64 = 24 * 60 * 60 * 1000;
+const WRITE_BUFFER_SIZE: usize = 1024 * 1024;
+
+// 发送方稍后重新打开会话即可恢复的错误
+const RETRYABLE_ERRORS: [&str; 5] = [
+    "传输会话正忙",
+    "传输进度不一致",
+    "传输分块校验失败",
+    "文件尚未接收完整",
+    "传输会话不存在或已过期",
+];
+
+// 同一会话同时只允许一个分块写入
+static ACTIVE_SESSIONS: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferManifest {
+    pub transfer_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub is_directory: bool,
+    pub chunk_size: u64,
+    pub entries: Vec<TransferEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferEntry {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+    pub chunk_hashes: Vec<String>,
+}
+
+// offsets 为每个文件已确认写入的字节数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferSessionState {
+    pub transfer_id: String,
+    pub offsets: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredSession {
+    manifest: TransferManifest,
+    source_device_id: String,
+    updated_at_ms: i64,
+    #[serde(default)]
+    result: Option<super::FileTransferResult>,
+}
+
+impl TransferManifest {
+    pub fn total_bytes(&self) -> u64 {
+        self.entries.iter().map(|entry| entry.size).sum()
+    }
+
+    pub fn chunk_count(size: u64, chunk_size: u64) -> usize {
+        size.div_ceil(chunk_size) as usize
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !is_valid_transfer_id(&self.transfer_id) {
+            return Err("无效的传输 ID".to_string());
+        }
+        if !(MIN_CHUNK_SIZE..=MAX_CHUNK_SIZE).contains(&self.chunk_size) {
+            return Err("无效的传输分块大小".to_string());
+        }
+        if self.entries.is_empty() || self.entries.len() > MAX_MANIFEST_ENTRIES {
+            return Err("传输清单文件数量无效".to_string());
+        }
+        if !self.is_directory && self.entries.len() != 1 {
+            return Err("传输清单无效".to_string());
+        }
+        if super::files::sanitize_relative_path(&self.name)?.components().count() != 1 {
+            return Err("文件名无效".to_string());
+        }
+        let mut paths = HashSet::new();
+        for entry in &self.entries {
+            let path = super::files::sanitize_relative_path(&entry.path)?;
+            if !paths.insert(path) {
+                return Err("传输清单包含重复文件".to_string());
+            }
+            if entry.chunk_hashes.len() != Self::chunk_count(entry.size, self.chunk_size) {
+                return Err("传输清单分块信息不完整".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+// 创建或恢复会话；清单与已有会话不一致时（发送方文件已变化）丢弃旧进度
+pub fn open_session(manifest: TransferManifest, source_device_id: &str) -> Result<TransferSessionState, String> {
+    manifest.validate()?;
+    cleanup_stale_sessions();
+    let _guard = SessionGuard::acquire(&manifest.transfer_id)?;
+    let dir = session_dir(&manifest.transfer_id)?;
+    match load_session(&manifest.transfer_id)? {
+        Some(stored) if stored.source_device_id != source_device_id => {
+            return Err("传输会话不属于该设备".to_string());
+        }
+        Some(stored) if stored.manifest == manifest && stored.result.is_some() => {
+            return Ok(TransferSessionState {
+                transfer_id: manifest.transfer_id.clone(),
+                offsets: manifest.entries.iter().map(|entry| entry.size).collect(),
+            });
+        }
+        Some(stored) if stored.manifest == manifest => {}
+        Some(_) => {
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+        None => {}
+    }
+    std::fs::create_dir_all(&dir).map_err(|e| format!("创建传输会话失败: {}", e))?;
+    save_session(&StoredSession {
+        manifest: manifest.clone(),
+        source_device_id: source_device_id.to_string(),
+        updated_at_ms: chrono::Utc::now().timestamp_millis(),
+        result: None,
+    })?;
+    session_state(&manifest)
+}
+
+pub fn load_manifest(transfer_id: &str, source_device_id: &str) -> Result<TransferManifest, String> {
+    Ok(load_owned_session(transfer_id, source_device_id)?.manifest)
+}
+
+fn load_owned_session(transfer_id: &str, source_device_id: &str) -> Result<StoredSession, String> {
+    let stored = load_session(transfer_id)?.ok_or_else(|| "传输会话不存在或已过期".to_string())?;
+    if stored.source_device_id != source_device_id {
+        return Err("传输会话不属于该设备".to_string());
+    }
+    Ok(stored)
+}
+
+// 从流中读取一个分块写入磁盘，on_progress 收到整个会话已接收的字节数
+pub async fn write_chunk<S, F>(
+    manifest: &TransferManifest,
+    entry_index: usize,
+    offset: u64,
+    length: u64,
+    stream: &mut S,
+    on_progress: F,
+) -> Result<TransferSessionState, String>
+where
+    S: AsyncRead + Unpin,
+    F: Fn(u64),
+{
+    let entry = manifest
+        .entries
+        .get(entry_index)
+        .ok_or_else(|| "传输文件序号无效".to_string())?;
+    let _guard = SessionGuard::acquire(&manifest.transfer_id)?;
+    if load_session(&manifest.transfer_id)?.and_then(|stored| stored.result).is_some() {
+        return Err("传输已完成".to_string());
+    }
+    let confirmed = confirmed_offset(manifest, entry_index)?;
+    if offset != confirmed {
+        return Err(format!("传输进度不一致: 期望 {}，实际 {}", confirmed, offset));
+    }
+    let expected_length = manifest.chunk_size.min(entry.size.saturating_sub(offset));
+    if length != expected_length || length == 0 {
+        return Err("传输分块长度无效".to_string());
+    }
+    let base = received_bytes(manifest);
+    let chunk_index = (offset / manifest.chunk_size) as usize;
+    let expected_hash = &entry.chunk_hashes[chunk_index];
+
+    let path = part_path(&manifest.transfer_id, entry_index)?;
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .await
+        .map_err(|e| format!("创建接收文件失败: {}", e))?;
+    file.seek(std::io::SeekFrom::Start(offset))
+        .await
+        .map_err(|e| format!("保存接收文件失败: {}", e))?;
+
+    let result = async {
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0u8; WRITE_BUFFER_SIZE];
+        let mut written = 0u64;
+        while written < length {
+            let read_len = ((length - written) as usize).min(buffer.len());
+            let read = stream
+                .read(&mut buffer[..read_len])
+                .await
+                .map_err(|e| format!("读取局域网传输内容失败: {}", e))?;
+            if read == 0 {
+                return Err("局域网文件传输连接提前关闭".to_string());
+            }
+            file.write_all(&buffer[..read])
+                .await
+                .map_err(|e| format!("保存接收文件失败: {}", e))?;
+            hasher.update(&buffer[..read]);
+            written += read as u64;
+            on_progress(base + written);
+        }
+        if !hex::encode(hasher.finalize()).eq_ignore_ascii_case(expected_hash) {
+            return Err("传输分块校验失败".to_string());
+        }
+        file.flush().await.map_err(|e| format!("保存接收文件失败: {}", e))
+    }
+    .await;
+
+    if let Err(message) = result {
+        // 回退到块起点，下次从这里重传
+        let _ = file.set_len(offset).await;
+        return Err(message);
+    }
+    drop(file);
+    touch_session(&manifest.transfer_id);
+    session_state(manifest)
+}
+
+// 所有文件接收完整后移入接收目录并写入收件盒索引，返回保存位置
+pub fn complete_session(
+    transfer_id: &str,
+    source_device_id: &str,
+    source_device_name: &str,
+) -> Result<super::FileTransferResult, String> {
+    let _guard = SessionGuard::acquire(transfer_id)?;
+    let mut stored = load_owned_session(transfer_id, source_device_id)?;
+    if let Some(result) = stored.result.clone() {
+        return Ok(result);
+    }
+    let manifest = &stored.manifest;
+    for index in 0..manifest.entries.len() {
+        if confirmed_offset(manifest, index)? != manifest.entries[index].size {
+            return Err("文件尚未接收完整".to_string());
+        }
+    }
+
+    let mut files = Vec::with_capacity(manifest.entries.len());
+    for (index, entry) in manifest.entries.iter().enumerate() {
+        let part = part_path(&manifest.transfer_id, index)?;
+        if entry.size == 0 && !part.exists() {
+            std::fs::File::create(&part).map_err(|e| format!("创建接收文件失败: {}", e))?;
+        }
+        files.push((part, super::files::sanitize_relative_path(&entry.path)?));
+    }
+
+    let (saved_path, sha256) = if manifest.is_directory {
+        let target = super::files::commit_received_directory(&manifest.name, &files)?;
+        for ((_, relative_path), entry) in files.iter().zip(&manifest.entries) {
+            record_received(&target.join(relative_path), entry, source_device_id, source_device_name);
+        }
+        (target, None)
+    } else {
+        let entry = &manifest.entries[0];
+        let reservation = super::files::prepare_received_file(&manifest.name)?;
+        let reservation = super::files::ReceivedFileReservation {
+            final_path: reservation.final_path,
+            temp_path: files[0].0.clone(),
+        };
+        let saved = super::files::commit_received_file(&reservation)?;
+        record_received(&saved, entry, source_device_id, source_device_name);
+        (saved, Some(entry.sha256.clone()))
+    };
+
+    let result = super::FileTransferResult {
+        saved: true,
+        path: saved_path.to_string_lossy().to_string(),
+        size: manifest.total_bytes(),
+        sha256,
+    };
+    stored.result = Some(result.clone());
+    stored.updated_at_ms = chrono::Utc::now().timestamp_millis();
+    if let Err(message) = save_session(&stored) {
+        eprintln!("[局域网文件接收] 保存传输结果失败: {}", message);
+    }
+    Ok(result)
+}
+
+fn received_bytes(manifest: &TransferManifest) -> u64 {
+    (0..manifest.entries.len())
+        .filter_map(|index| confirmed_offset(manifest, index).ok())
+        .sum()
+}
+
+pub fn is_retryable_error(message: &str) -> bool {
+    RETRYABLE_ERRORS.iter().any(|prefix| message.starts_with(prefix))
+}
+
+fn record_received(path: &std::path::Path, entry: &TransferEntry, source_device_id: &str, source_device_name: &str) {
+    if let Err(message) = super::files::record_received_file(
+        path,
+        entry.size,
+        &entry.sha256,
+        source_device_id,
+        source_device_name,
+    ) {
+        eprintln!("[局域网文件接收] 写入接收文件索引失败: {}", message);
+    }
+}
+
+fn session_state(manifest: &TransferManifest) -> Result<TransferSessionState, String> {
+    let offsets = (0..manifest.entries.len())
+        .map(|index| confirmed_offset(manifest, index))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(TransferSessionState {
+        transfer_id: manifest.transfer_id.clone(),
+        offsets,
+    })
+}
+
+// 临时文件中按整块校验过的长度；异常退出留下的半块会被截掉
+fn confirmed_offset(manifest: &TransferManifest, entry_index: usize) -> Result<u64, String> {
+    let entry = &manifest.entries[entry_index];
+    let path = part_path(&manifest.transfer_id, entry_index)?;
+    let Ok(metadata) = std::fs::metadata(&path) else {
+        return Ok(0);
+    };
+    let len = metadata.len();
+    let confirmed = if len >= entry.size {
+        entry.size
+    } else {
+        len - len % manifest.chunk_size
+    };
+    if confirmed != len {
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_len(confirmed))
+            .map_err(|e| format!("恢复传输进度失败: {}", e))?;
+    }
+    Ok(confirmed)
+}
+
+fn cleanup_stale_sessions() {
+    let Ok(root) = super::files::transfer_sessions_dir() else {
+        return;
+    };
+    let Ok(entries) = std::fs::read_dir(&root) else {
+        return;
+    };
+    let now = chrono::Utc::now().timestamp_millis();
+    for entry in entries.flatten() {
+        let Some(transfer_id) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if ACTIVE_SESSIONS.lock().map(|active| active.contains(&transfer_id)).unwrap_or(true) {
+            continue;
+        }
+        let stale = load_session(&transfer_id)
+            .ok()
+            .flatten()
+            .map(|stored| {
+                let keep_ms = if stored.result.is_some() { COMPLETED_SESSION_MS } else { STALE_SESSION_MS };
+                now.saturating_sub(stored.updated_at_ms) > keep_ms
+            })
+            .unwrap_or(true);
+        if stale {
+            let _ = std::fs::remove_dir_all(entry.path());
+        }
+    }
+}
+
+fn load_session(transfer_id: &str) -> Result<Option<StoredSession>, String> {
+    let path = session_dir(transfer_id)?.join(SESSION_FILE_NAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let bytes = std::fs::read(&path).map_err(|e| format!("读取传输会话失败: {}", e))?;
+    Ok(serde_json::from_slice(&bytes).ok())
+}
+
+fn save_session(session: &StoredSession) -> Result<(), String> {
+    let path = session_dir(&session.manifest.transfer_id)?.join(SESSION_FILE_NAME);
+    let bytes = serde_json::to_vec(session).map_err(|e| format!("保存传输会话失败: {}", e))?;
+    std::fs::write(path, bytes).map_err(|e| format!("保存传输会话失败: {}", e))
+}
+
+fn touch_session(transfer_id: &str) {
+    if let Ok(Some(mut stored)) = load_session(transfer_id) {
+        stored.updated_at_ms = chrono::Utc::now().timestamp_millis();
+        let _ = save_session(&stored);
+    }
+}
+
+fn session_dir(transfer_id: &str) -> Result<PathBuf, String> {
+    if !is_valid_transfer_id(transfer_id) {
+        return Err("无效的传输 ID".to_string());
+    }
+    Ok(super::files::transfer_sessions_dir()?.join(transfer_id))
+}
+
+fn part_path(transfer_id: &str, entry_index: usize) -> Result<PathBuf, String> {
+    Ok(session_dir(transfer_id)?.join(format!("{}.qcpart", entry_index)))
+}
+
+fn is_valid_transfer_id(transfer_id: &str) -> bool {
+    !transfer_id.is_empty()
+        && transfer_id.len() <= 64
+        && transfer_id
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_')
+}
+
+struct SessionGuard {
+    transfer_id: String,
+}
+
+impl SessionGuard {
+    fn acquire(transfer_id: &str) -> Result<Self, String> {
+        let mut active = ACTIVE_SESSIONS
+            .lock()
+            .map_err(|_| "传输会话状态异常".to_string())?;
+        if !active.insert(transfer_id.to_string()) {
+            return Err("传输会话正忙".to_string());
+        }
+        Ok(Self {
+            transfer_id: transfer_id.to_string(),
+        })
+    }
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        if let Ok(mut active) = ACTIVE_SESSIONS.lock() {
+            active.remove(&self.transfer_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(entries: Vec<(&str, u64)>) -> TransferManifest {
+        TransferManifest {
+            transfer_id: "0123456789abcdef".to_string(),
+            name: "photos".to_string(),
+            is_directory: true,
+            chunk_size: MIN_CHUNK_SIZE,
+            entries: entries
+                .into_iter()
+                .map(|(path, size)| TransferEntry {
+                    path: path.to_string(),
+                    size,
+                    sha256: String::new(),
+                    chunk_hashes: vec![String::new(); TransferManifest::chunk_count(size, MIN_CHUNK_SIZE)],
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn validate_accepts_nested_directory_entries() {
+        let manifest = manifest(vec![("a.txt", 0), ("raw/b.png", MIN_CHUNK_SIZE * 2 + 1)]);
+        assert!(manifest.validate().is_ok());
+        assert_eq!(manifest.entries[1].chunk_hashes.len(), 3);
+        assert_eq!(manifest.total_bytes(), MIN_CHUNK_SIZE * 2 + 1);
+    }
+
+    #[test]
+    fn validate_rejects_paths_outside_target() {
+        for path in ["../evil", "a/../../evil", "/etc/passwd", "a\\b", "a//b"] {
+            assert!(manifest(vec![(path, 1)]).validate().is_err(), "{}", path);
+        }
+        assert!(manifest(vec![("a", 1), ("a", 2)]).validate().is_err());
+
+        let mut nested_name = manifest(vec![("a", 1)]);
+        nested_name.name = "x/y".to_string();
+        assert!(nested_name.validate().is_err());
+
+        let mut bad_id = manifest(vec![("a", 1)]);
+        bad_id.transfer_id = "../session".to_string();
+        assert!(bad_id.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_incomplete_chunk_list() {
+        let mut manifest = manifest(vec![("a", MIN_CHUNK_SIZE + 1)]);
+        manifest.entries[0].chunk_hashes.pop();
+        assert!(manifest.validate().is_err());
+    }
+}
@@ -0,0 +1,233 @@
+// 同步冲突检测与三方合并
+//
+// 每条记录携带版本向量（设备 ID → 修改次数），比较两侧向量即可判断是先后修改还是并发修改；
+// 并发修改时以上次同步的内容为基线，对文本按行做三方合并，双方改动了同一区域时视为无法合并。
+
+use std::cmp::Ordering;
+use std::ops::Range;
+
+use crate::services::webdav_sync::types::VersionVector;
+
+// 超过这个规模的文本不做逐行比对，直接按冲突处理
+const MAX_DIFF_CELLS: usize = 1_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionOrder {
+    Equal,
+    // 本地版本落后于远端
+    Older,
+    // 本地版本包含远端的全部修改
+    Newer,
+    Concurrent,
+}
+
+pub fn compare_versions(local: &VersionVector, remote: &VersionVector) -> VersionOrder {
+    let mut local_ahead = false;
+    let mut remote_ahead = false;
+    for device in local.keys().chain(remote.keys()) {
+        let l = local.get(device).copied().unwrap_or(0);
+        let r = remote.get(device).copied().unwrap_or(0);
+        match l.cmp(&r) {
+            Ordering::Greater => local_ahead = true,
+            Ordering::Less => remote_ahead = true,
+            Ordering::Equal => {}
+        }
+    }
+    match (local_ahead, remote_ahead) {
+        (false, false) => VersionOrder::Equal,
+        (false, true) => VersionOrder::Older,
+        (true, false) => VersionOrder::Newer,
+        (true, true) => VersionOrder::Concurrent,
+    }
+}
+
+pub fn join_versions(a: &VersionVector, b: &VersionVector) -> VersionVector {
+    let mut joined = a.clone();
+    for (device, count) in b {
+        let entry = joined.entry(device.clone()).or_insert(0);
+        *entry = (*entry).max(*count);
+    }
+    joined
+}
+
+pub fn bump_version(version: &mut VersionVector, device_id: &str) {
+    *version.entry(device_id.to_string()).or_insert(0) += 1;
+}
+
+// 不可拆分的字段（标题、HTML 等）：只有一侧修改时取修改的一侧
+pub fn merge_value<T: PartialEq + Clone>(base: &T, local: &T, remote: &T) -> Option<T> {
+    if local == remote || remote == base {
+        Some(local.clone())
+    } else if local == base {
+        Some(remote.clone())
+    } else {
+        None
+    }
+}
+
+// 按行三方合并，双方改动重叠或相邻且结果不同时返回 None
+pub fn merge_text(base: &str, local: &str, remote: &str) -> Option<String> {
+    if let Some(value) = merge_value(&base, &local, &remote) {
+        return Some(value.to_string());
+    }
+
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let local_lines: Vec<&str> = local.split_inclusive('\n').collect();
+    let remote_lines: Vec<&str> = remote.split_inclusive('\n').collect();
+    let local_hunks = diff_hunks(&base_lines, &local_lines)?;
+    let remote_hunks = diff_hunks(&base_lines, &remote_lines)?;
+
+    let mut merged = String::with_capacity(local.len().max(remote.len()));
+    let mut base_pos = 0;
+    let (mut li, mut ri) = (0, 0);
+    loop {
+        let start = match (local_hunks.get(li), remote_hunks.get(ri)) {
+            (Some(l), Some(r)) => l.base.start.min(r.base.start),
+            (Some(l), None) => l.base.start,
+            (None, Some(r)) => r.base.start,
+            (None, None) => break,
+        };
+
+        // 收集与当前区域重叠或相接的改动
+        let mut end = start;
+        let (local_from, remote_from) = (li, ri);
+        loop {
+            if let Some(hunk) = local_hunks.get(li).filter(|h| h.base.start <= end) {
+                end = end.max(hunk.base.end);
+                li += 1;
+            } else if let Some(hunk) = remote_hunks.get(ri).filter(|h| h.base.start <= end) {
+                end = end.max(hunk.base.end);
+                ri += 1;
+            } else {
+                break;
+            }
+        }
+
+        merged.extend(base_lines[base_pos..start].iter().copied());
+        let region = start..end;
+        let local_text = apply_hunks(&base_lines, &local_lines, &local_hunks[local_from..li], &region);
+        let remote_text = apply_hunks(&base_lines, &remote_lines, &remote_hunks[remote_from..ri], &region);
+        if local_from == li {
+            merged.push_str(&remote_text);
+        } else if remote_from == ri || local_text == remote_text {
+            merged.push_str(&local_text);
+        } else {
+            return None;
+        }
+        base_pos = end;
+    }
+    merged.extend(base_lines[base_pos..].iter().copied());
+    Some(merged)
+}
+
+struct Hunk {
+    base: Range<usize>,
+    side: Range<usize>,
+}
+
+fn apply_hunks(base: &[&str], side: &[&str], hunks: &[Hunk], region: &Range<usize>) -> String {
+    let mut text = String::new();
+    let mut pos = region.start;
+    for hunk in hunks {
+        text.extend(base[pos..hunk.base.start].iter().copied());
+        text.extend(side[hunk.side.clone()].iter().copied());
+        pos = hunk.base.end;
+    }
+    text.extend(base[pos..region.end].iter().copied());
+    text
+}
+
+// 基于最长公共子序列求出基线到一侧的改动区间
+fn diff_hunks(base: &[&str], side: &[&str]) -> Option<Vec<Hunk>> {
+    let prefix = base.iter().zip(side).take_while(|(a, b)| a == b).count();
+    let suffix = base[prefix..]
+        .iter()
+        .rev()
+        .zip(side[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &base[prefix..base.len() - suffix];
+    let b = &side[prefix..side.len() - suffix];
+    if (a.len() + 1).saturating_mul(b.len() + 1) > MAX_DIFF_CELLS {
+        return None;
+    }
+
+    // lcs[i][j]：a[i..] 与 b[j..] 的最长公共子序列长度
+    let width = b.len() + 1;
+    let mut lcs = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i * width + j] = if a[i] == b[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut hunks = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let (mut hunk_i, mut hunk_j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            if hunk_i < i || hunk_j < j {
+                hunks.push(Hunk { base: prefix + hunk_i..prefix + i, side: prefix + hunk_j..prefix + j });
+            }
+            i += 1;
+            j += 1;
+            hunk_i = i;
+            hunk_j = j;
+        } else if j < b.len() && (i == a.len() || lcs[i * width + j + 1] >= lcs[(i + 1) * width + j]) {
+            j += 1;
+        } else {
+            i += 1;
+        }
+    }
+    if hunk_i < i || hunk_j < j {
+        hunks.push(Hunk { base: prefix + hunk_i..prefix + i, side: prefix + hunk_j..prefix + j });
+    }
+    Some(hunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(entries: &[(&str, u64)]) -> VersionVector {
+        entries.iter().map(|(device, count)| (device.to_string(), *count)).collect()
+    }
+
+    #[test]
+    fn compares_version_vectors() {
+        let a1 = version(&[("a", 1)]);
+        let a2b1 = version(&[("a", 2), ("b", 1)]);
+        let b1 = version(&[("b", 1)]);
+        assert_eq!(compare_versions(&a1, &a1), VersionOrder::Equal);
+        assert_eq!(compare_versions(&a1, &a2b1), VersionOrder::Older);
+        assert_eq!(compare_versions(&a2b1, &b1), VersionOrder::Newer);
+        assert_eq!(compare_versions(&a1, &b1), VersionOrder::Concurrent);
+        assert_eq!(join_versions(&a1, &b1), version(&[("a", 1), ("b", 1)]));
+    }
+
+    #[test]
+    fn merges_edits_in_different_lines() {
+        let base = "one\ntwo\nthree\nfour\n";
+        let local = "ONE\ntwo\nthree\nfour\n";
+        let remote = "one\ntwo\nthree\nfour\nfive\n";
+        assert_eq!(merge_text(base, local, remote).as_deref(), Some("ONE\ntwo\nthree\nfour\nfive\n"));
+
+        // 双方做了相同的修改
+        let same = "one\nTWO\nthree\nfour\n";
+        assert_eq!(merge_text(base, same, same).as_deref(), Some(same));
+    }
+
+    #[test]
+    fn conflicting_edits_are_not_merged() {
+        let base = "one\ntwo\nthree\n";
+        assert_eq!(merge_text(base, "one\nlocal\nthree\n", "one\nremote\nthree\n"), None);
+        // 相邻行的修改同样视为冲突
+        assert_eq!(merge_text(base, "ONE\ntwo\nthree\n", "one\nTWO\nthree\n"), None);
+        assert_eq!(merge_value(&"t", &"local", &"remote"), None);
+        assert_eq!(merge_value(&"t", &"t", &"remote"), Some("remote"));
+    }
+}
@@ -1,5 +1,7 @@
 pub mod device_identity;
+pub mod gossip;
 pub mod lan;
+pub mod merge;
 pub mod sync_plan;
 pub mod types;
 
@@ -30,7 +32,20 @@ pub fn lan_list_paired_peers() -> Vec<lan::PairedPeerInfo> {
 }
 
 pub fn lan_remove_paired_peer(device_id: &str) -> Result<bool, String> {
-    lan::peer_store::remove_peer(device_id)
+    let removed = lan::peer_store::remove_peer(device_id)?;
+    lan::live_share::refresh_links();
+    Ok(removed)
+}
+
+// 范围放宽后新纳入的数据需要补一次同步
+pub fn lan_update_peer_sync_policy(
+    app: tauri::AppHandle,
+    device_id: &str,
+    policy: lan::LanPeerSyncPolicy,
+) -> Result<lan::PairedPeerInfo, String> {
+    let peer = lan::peer_store::update_sync_policy(device_id, policy)?;
+    lan::auto_sync::notify_local_change(app, "policy");
+    Ok(peer)
 }
 
 pub async fn lan_pair_with_peer(base_url: String, pairing_code: String) -> Result<lan::PairedPeerInfo, String> {
@@ -38,7 +53,7 @@ pub async fn lan_pair_with_peer(base_url: String, pairing_code: String) -> Resul
 }
 
 pub fn lan_snapshot() -> Result<lan::LanSyncSnapshot, String> {
-    lan::snapshot::snapshot()
+    lan::snapshot::snapshot(&lan::LanPeerSyncPolicy::default())
 }
 
 pub async fn lan_fetch_peer_snapshot(device_id: &str) -> Result<lan::LanSyncSnapshot, String> {
@@ -63,17 +78,34 @@ pub fn lan_update_auto_sync_settings(settings: lan::LanAutoSyncSettings) -> Resu
     lan::auto_sync::update_settings(settings)
 }
 
+pub fn lan_live_share_status() -> lan::LanLiveShareStatus {
+    lan::live_share::status()
+}
+
+pub fn lan_update_live_share_settings(settings: lan::LanLiveShareSettings) -> Result<lan::LanLiveShareSettings, String> {
+    lan::live_share::update_settings(settings)
+}
+
+// 接收自动同步或实时共享任一开启时都需要保持局域网服务运行
+pub fn lan_needs_http_server() -> bool {
+    lan::auto_sync::can_receive() || lan::live_share::is_enabled()
+}
+
+pub fn lan_publish_capture(item: &crate::services::database::ClipboardItem) {
+    lan::live_share::publish_capture(item);
+}
+
 pub fn lan_notify_local_change(app: tauri::AppHandle, reason: &'static str) {
     crate::services::webdav_sync::notify_local_change(app.clone(), reason);
     lan::auto_sync::notify_local_change(app, reason);
 }
 
 pub async fn lan_start_configured_services(app: tauri::AppHandle) {
-    let settings = lan::auto_sync::settings();
-    if !settings.receive_enabled {
+    if !lan_needs_http_server() {
         return;
     }
     let _ = lan::http_server::start(app, Default::default()).await;
+    lan::live_share::refresh_links();
 }
 
 pub async fn lan_pull_from_peer(device_id: &str) -> Result<crate::services::webdav_sync::SyncReport, String> {
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 
-use crate::services::webdav_sync::types::{CloudGroup, CloudRecordMeta};
+use crate::services::webdav_sync::types::{CloudGroup, CloudRecordMeta, VersionVector};
+
+use super::merge::{compare_versions, VersionOrder};
 
 pub fn record_metas_newer_than_remote(
     local_records: Vec<CloudRecordMeta>,
@@ -18,6 +20,15 @@ pub fn record_metas_newer_than_remote(
         .collect()
 }
 
+// 远端版本包含本地尚未见过的修改（落后或并发），此时不能直接按更新时间取舍
+pub fn has_unseen_changes(local: Option<&VersionVector>, remote: &VersionVector) -> bool {
+    let empty = VersionVector::new();
+    matches!(
+        compare_versions(local.unwrap_or(&empty), remote),
+        VersionOrder::Older | VersionOrder::Concurrent
+    )
+}
+
 pub fn groups_newer_than_remote(
     local_groups: Vec<CloudGroup>,
     remote_groups: &[CloudGroup],
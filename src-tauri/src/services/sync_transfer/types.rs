@@ -14,9 +14,23 @@ pub enum SyncMode {
 #[serde(rename_all = "snake_case")]
 pub enum TransferBackend {
     WebDavStore,
+    S3Store,
+    FolderStore,
     LanHttp,
 }
 
+impl TransferBackend {
+    /// 云端同步当前使用的存储后端。
+    pub fn for_cloud(kind: &str) -> Self {
+        use crate::services::webdav_sync::backend::{normalize_backend_kind, BACKEND_FOLDER, BACKEND_S3};
+        match normalize_backend_kind(kind) {
+            BACKEND_S3 => TransferBackend::S3Store,
+            BACKEND_FOLDER => TransferBackend::FolderStore,
+            _ => TransferBackend::WebDavStore,
+        }
+    }
+}
+
 /// 同步/传输页面逐步开放的能力。
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -34,10 +48,11 @@ pub struct SyncTransferModeInfo {
 }
 
 pub fn mode_infos() -> Vec<SyncTransferModeInfo> {
+    let settings = crate::services::get_settings();
     vec![
         SyncTransferModeInfo {
             mode: SyncMode::WebDav,
-            backend: TransferBackend::WebDavStore,
+            backend: TransferBackend::for_cloud(&settings.webdav_backend),
             features: vec![SyncTransferFeature::Records, SyncTransferFeature::Files],
             available: true,
         },
@@ -402,7 +402,7 @@ pub use windows_impl::{
 };
 
 // 通配符匹配（支持 * 和 ?）
-fn wildcard_match(pattern: &str, text: &str) -> bool {
+pub(crate) fn wildcard_match(pattern: &str, text: &str) -> bool {
     let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
     let text: Vec<char> = text.to_lowercase().chars().collect();
 
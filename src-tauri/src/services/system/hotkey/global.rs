@@ -551,6 +551,23 @@ pub fn register_paste_plain_text_hotkey(shortcut_str: &str) -> Result<(), String
     Ok(())
 }
 
+pub fn register_paste_queue_hotkey(shortcut_str: &str) -> Result<(), String> {
+    register_shortcut("paste_queue", shortcut_str, |app| {
+        let app = app.clone();
+        std::thread::spawn(move || {
+            use crate::services::paste::keyboard::set_trigger_key_from_shortcut;
+            set_trigger_key_from_shortcut(&crate::get_settings().paste_queue_shortcut);
+            match crate::services::paste::queue::paste_next_queued_item() {
+                Ok(true) => {}
+                Ok(false) => {
+                    let _ = crate::services::notification::show_notification(&app, "QuickClipboard", "粘贴队列已空");
+                }
+                Err(e) => eprintln!("粘贴队列下一项失败: {}", e),
+            }
+        });
+    })
+}
+
 // 首次按下
 fn handle_paste_plain_text_press(app: &AppHandle) -> Result<(), String> {
     use crate::services::database::{query_clipboard_items, get_clipboard_item_by_id, QueryParams};
@@ -573,6 +590,7 @@ fn handle_paste_plain_text_press(app: &AppHandle) -> Result<(), String> {
             limit: 1,
             search: None,
             content_type: None,
+            ..Default::default()
         })?
         .items;
 
@@ -718,6 +736,7 @@ fn handle_number_shortcut_press(index: usize) -> Result<(), String> {
         limit: 9,
         search: None,
         content_type: None,
+        ..Default::default()
     })?
     .items;
 
@@ -907,6 +926,12 @@ pub fn reload_from_settings() -> Result<(), String> {
                 eprintln!("注册纯文本粘贴快捷键失败: {}", e);
             }
         }
+
+        if !settings.paste_queue_shortcut.is_empty() {
+            if let Err(e) = register_paste_queue_hotkey(&settings.paste_queue_shortcut) {
+                eprintln!("注册粘贴队列快捷键失败: {}", e);
+            }
+        }
         
         if settings.number_shortcuts && !settings.number_shortcuts_modifier.is_empty() {
             if let Err(e) = register_number_shortcuts(&settings.number_shortcuts_modifier) {
@@ -7,6 +7,7 @@ use tauri::{Emitter, Manager};
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
 
 use super::global::{get_app, parse_shortcut};
+use crate::services::paste::transform::{TextTransform, TRANSFORM_ACTION_PREFIX};
 
 static NAVIGATION_SHORTCUTS: Lazy<Mutex<Vec<NavigationShortcutRegistration>>> =
     Lazy::new(|| Mutex::new(Vec::new()));
@@ -30,8 +31,8 @@ struct NavigationShortcutRegistration {
 
 #[derive(Clone)]
 struct NavigationShortcutConfig {
-    id: &'static str,
-    action: &'static str,
+    id: String,
+    action: String,
     shortcut: String,
 }
 
@@ -97,8 +98,8 @@ fn register_navigation_hotkeys_from_settings() -> Result<(), String> {
             }
         };
 
-        let id = config.id.to_string();
-        let action = config.action.to_string();
+        let id = config.id.clone();
+        let action = config.action.clone();
         let shortcut_for_log = config.shortcut.clone();
 
         match app.global_shortcut().on_shortcut(shortcut, move |_app, _shortcut, event| {
@@ -110,7 +111,7 @@ fn register_navigation_hotkeys_from_settings() -> Result<(), String> {
             Ok(_) => {
                 println!("已注册导航快捷键 [{}]: {}", config.id, config.shortcut);
                 registrations.push(NavigationShortcutRegistration {
-                    id: config.id.to_string(),
+                    id: config.id.clone(),
                     shortcut: shortcut_for_log,
                 });
             }
@@ -154,58 +155,72 @@ fn unregister_navigation_hotkeys() {
 
 fn navigation_shortcut_configs() -> Vec<NavigationShortcutConfig> {
     let settings = crate::get_settings();
-    vec![
+    let mut configs = vec![
         NavigationShortcutConfig {
-            id: "navigation_navigate_up",
-            action: "navigate-up",
+            id: "navigation_navigate_up".to_string(),
+            action: "navigate-up".to_string(),
             shortcut: settings.navigate_up_shortcut,
         },
         NavigationShortcutConfig {
-            id: "navigation_navigate_down",
-            action: "navigate-down",
+            id: "navigation_navigate_down".to_string(),
+            action: "navigate-down".to_string(),
             shortcut: settings.navigate_down_shortcut,
         },
         NavigationShortcutConfig {
-            id: "navigation_execute_item",
-            action: "execute-item",
+            id: "navigation_execute_item".to_string(),
+            action: "execute-item".to_string(),
             shortcut: settings.paste_item_shortcut,
         },
         NavigationShortcutConfig {
-            id: "navigation_tab_left",
-            action: "tab-left",
+            id: "navigation_tab_left".to_string(),
+            action: "tab-left".to_string(),
             shortcut: settings.tab_left_shortcut,
         },
         NavigationShortcutConfig {
-            id: "navigation_tab_right",
-            action: "tab-right",
+            id: "navigation_tab_right".to_string(),
+            action: "tab-right".to_string(),
             shortcut: settings.tab_right_shortcut,
         },
         NavigationShortcutConfig {
-            id: "navigation_previous_group",
-            action: "previous-group",
+            id: "navigation_previous_group".to_string(),
+            action: "previous-group".to_string(),
             shortcut: settings.previous_group_shortcut,
         },
         NavigationShortcutConfig {
-            id: "navigation_next_group",
-            action: "next-group",
+            id: "navigation_next_group".to_string(),
+            action: "next-group".to_string(),
             shortcut: settings.next_group_shortcut,
         },
         NavigationShortcutConfig {
-            id: "navigation_focus_search",
-            action: "focus-search",
+            id: "navigation_focus_search".to_string(),
+            action: "focus-search".to_string(),
             shortcut: settings.focus_search_shortcut,
         },
         NavigationShortcutConfig {
-            id: "navigation_hide_window",
-            action: "hide-window",
+            id: "navigation_hide_window".to_string(),
+            action: "hide-window".to_string(),
             shortcut: settings.hide_window_shortcut,
         },
         NavigationShortcutConfig {
-            id: "navigation_toggle_pin",
-            action: "toggle-pin",
+            id: "navigation_toggle_pin".to_string(),
+            action: "toggle-pin".to_string(),
             shortcut: settings.toggle_pin_shortcut,
         },
-    ]
+    ];
+
+    // 转换粘贴：动作即粘贴动作 ID，由前端对选中项执行
+    for entry in settings.paste_transform_shortcuts {
+        if TextTransform::from_id(&entry.transform).is_none() {
+            continue;
+        }
+        configs.push(NavigationShortcutConfig {
+            id: format!("navigation_{}", entry.transform),
+            action: entry.transform,
+            shortcut: entry.shortcut,
+        });
+    }
+
+    configs
 }
 
 fn handle_navigation_pressed(id: &str, action: &str) {
@@ -323,6 +338,7 @@ fn get_throttle_delay(action: &str) -> Option<Duration> {
         "execute-item" | "focus-search" | "hide-window" | "toggle-pin" => {
             Some(Duration::from_millis(200))
         }
+        _ if action.starts_with(TRANSFORM_ACTION_PREFIX) => Some(Duration::from_millis(200)),
         _ => Some(Duration::from_millis(100)),
     }
 }
@@ -0,0 +1,322 @@
+// 本地文件夹后端，配合 Syncthing、Dropbox 等第三方同步工具使用
+//
+// 所有写入都先写临时文件再改名，第三方工具不会同步到写了一半的文件。
+// ETag 取文件内容的哈希，条件写入只在本机内生效，跨设备的并发修改由版本向量合并处理。
+
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use futures_util::future::BoxFuture;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+use tokio::io::DuplexStream;
+
+use super::{normalize_path, ByteReader, StorageBackend, StoredObject, WriteCondition};
+
+const TEMP_SUFFIX: &str = ".tmp";
+
+// 条件检查和写入之间不允许本机其他任务插入
+static WRITE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+pub struct FolderBackend {
+    root: PathBuf,
+}
+
+impl FolderBackend {
+    pub fn new(folder: &str, root_path: &str) -> Result<Self, String> {
+        let folder = folder.trim();
+        if folder.is_empty() {
+            return Err("同步文件夹不能为空".to_string());
+        }
+        let folder = PathBuf::from(folder);
+        if !folder.is_dir() {
+            return Err(format!("同步文件夹不存在: {}", folder.display()));
+        }
+        let root = normalize_path(root_path);
+        if root.split('/').any(|part| part == "..") {
+            return Err("同步目录不能包含 ..".to_string());
+        }
+        Ok(Self {
+            root: if root.is_empty() { folder } else { folder.join(root) },
+        })
+    }
+
+    fn resolve(&self, path: &str) -> Result<PathBuf, String> {
+        let path = normalize_path(path);
+        let mut resolved = self.root.clone();
+        for part in path.split('/').filter(|part| !part.is_empty()) {
+            if part == "." || part == ".." {
+                return Err(format!("同步路径无效: {}", path));
+            }
+            resolved.push(part);
+        }
+        Ok(resolved)
+    }
+}
+
+impl StorageBackend for FolderBackend {
+    fn scope(&self) -> String {
+        format!("folder:{}", self.root.display())
+    }
+
+    fn get<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<Option<StoredObject>, String>> {
+        Box::pin(async move {
+            let target = self.resolve(path)?;
+            blocking(move || read_file(&target)).await
+        })
+    }
+
+    fn get_stream<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<Option<ByteReader>, String>> {
+        Box::pin(async move {
+            match tokio::fs::File::open(self.resolve(path)?).await {
+                Ok(file) => {
+                    let reader: ByteReader = Box::new(file);
+                    Ok(Some(reader))
+                }
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(format!("读取同步文件失败: {}", e)),
+            }
+        })
+    }
+
+    fn put<'a>(
+        &'a self,
+        path: &'a str,
+        bytes: Vec<u8>,
+        condition: &'a WriteCondition,
+    ) -> BoxFuture<'a, Result<bool, String>> {
+        Box::pin(async move {
+            let target = self.resolve(path)?;
+            let condition = condition.clone();
+            blocking(move || write_file(&target, &bytes, &condition)).await
+        })
+    }
+
+    fn put_stream<'a>(
+        &'a self,
+        path: &'a str,
+        mut reader: DuplexStream,
+        content_length: u64,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let target = self.resolve(path)?;
+            let temp = temp_path(&target);
+            let mut file = tokio::fs::File::create(&temp)
+                .await
+                .map_err(|e| format!("创建同步文件失败: {}", e))?;
+            let written = tokio::io::copy(&mut reader, &mut file).await;
+            drop(file);
+            match written {
+                Ok(written) if written == content_length => {}
+                Ok(written) => {
+                    let _ = tokio::fs::remove_file(&temp).await;
+                    return Err(format!("写入同步文件不完整: {}/{}", written, content_length));
+                }
+                Err(e) => {
+                    let _ = tokio::fs::remove_file(&temp).await;
+                    return Err(format!("写入同步文件失败: {}", e));
+                }
+            }
+            blocking(move || {
+                let _guard = WRITE_LOCK.lock();
+                rename_into_place(&temp, &target)
+            })
+            .await
+        })
+    }
+
+    fn replace<'a>(&'a self, path: &'a str, bytes: Vec<u8>) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            self.put(path, bytes, &WriteCondition::Any).await?;
+            Ok(())
+        })
+    }
+
+    fn exists<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<bool, String>> {
+        Box::pin(async move {
+            match tokio::fs::metadata(self.resolve(path)?).await {
+                Ok(metadata) => Ok(metadata.is_file()),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+                Err(e) => Err(format!("读取同步文件失败: {}", e)),
+            }
+        })
+    }
+
+    fn delete<'a>(&'a self, path: &'a str, condition: &'a WriteCondition) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let target = self.resolve(path)?;
+            let condition = condition.clone();
+            blocking(move || remove_file(&target, &condition)).await
+        })
+    }
+
+    fn list<'a>(&'a self, dir: &'a str) -> BoxFuture<'a, Result<Vec<String>, String>> {
+        Box::pin(async move {
+            let dir = self.resolve(dir)?;
+            blocking(move || list_files(&dir)).await
+        })
+    }
+
+    fn create_dir<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            tokio::fs::create_dir_all(self.resolve(path)?)
+                .await
+                .map_err(|e| format!("创建同步目录失败: {}", e))
+        })
+    }
+}
+
+// 文件读写和 WRITE_LOCK 都会阻塞，放到阻塞线程池执行，避免占住异步运行时的工作线程
+async fn blocking<T, F>(task: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    tokio::task::spawn_blocking(task)
+        .await
+        .map_err(|e| format!("同步文件操作失败: {}", e))?
+}
+
+fn read_file(target: &Path) -> Result<Option<StoredObject>, String> {
+    match std::fs::read(target) {
+        Ok(bytes) => {
+            let etag = Some(content_etag(&bytes));
+            Ok(Some(StoredObject { bytes, etag }))
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("读取同步文件失败: {}", e)),
+    }
+}
+
+fn write_file(target: &Path, bytes: &[u8], condition: &WriteCondition) -> Result<bool, String> {
+    let _guard = WRITE_LOCK.lock();
+    match condition {
+        WriteCondition::Any => {}
+        WriteCondition::Absent => {
+            let temp = write_temp(target, bytes)?;
+            // 硬链接在目标已存在时失败，可以原子地完成“不存在才写入”
+            let result = std::fs::hard_link(&temp, target);
+            let _ = std::fs::remove_file(&temp);
+            return match result {
+                Ok(()) => Ok(true),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(false),
+                // 文件系统不支持硬链接时退回普通的检查后写入
+                Err(_) if !target.exists() => {
+                    let temp = write_temp(target, bytes)?;
+                    rename_into_place(&temp, target)?;
+                    Ok(true)
+                }
+                Err(_) => Ok(false),
+            };
+        }
+        WriteCondition::Match(etag) => {
+            let current = read_file(target)?.and_then(|object| object.etag);
+            if current.as_deref() != Some(etag.as_str()) {
+                return Ok(false);
+            }
+        }
+    }
+    let temp = write_temp(target, bytes)?;
+    rename_into_place(&temp, target)?;
+    Ok(true)
+}
+
+fn remove_file(target: &Path, condition: &WriteCondition) -> Result<(), String> {
+    let _guard = WRITE_LOCK.lock();
+    if let WriteCondition::Match(etag) = condition {
+        let current = read_file(target)?.and_then(|object| object.etag);
+        if current.as_deref() != Some(etag.as_str()) {
+            return Ok(());
+        }
+    }
+    match std::fs::remove_file(target) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("删除同步文件失败: {}", e)),
+    }
+}
+
+fn list_files(dir: &Path) -> Result<Vec<String>, String> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("读取同步目录失败: {}", e)),
+    };
+    let mut names = Vec::new();
+    for entry in entries.flatten() {
+        if !entry.file_type().map(|kind| kind.is_file()).unwrap_or(false) {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !name.ends_with(TEMP_SUFFIX) {
+            names.push(name);
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+fn content_etag(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+fn temp_path(target: &Path) -> PathBuf {
+    let name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    target.with_file_name(format!(".{}.{}{}", name, uuid::Uuid::new_v4().simple(), TEMP_SUFFIX))
+}
+
+fn write_temp(target: &Path, bytes: &[u8]) -> Result<PathBuf, String> {
+    let temp = temp_path(target);
+    std::fs::write(&temp, bytes).map_err(|e| format!("写入同步文件失败: {}", e))?;
+    Ok(temp)
+}
+
+fn rename_into_place(temp: &Path, target: &Path) -> Result<(), String> {
+    std::fs::rename(temp, target).map_err(|e| {
+        let _ = std::fs::remove_file(temp);
+        format!("写入同步文件失败: {}", e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backend() -> FolderBackend {
+        let dir = std::env::temp_dir().join(format!("qc_folder_backend_{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        FolderBackend::new(&dir.to_string_lossy(), "quickclipboard").unwrap()
+    }
+
+    #[tokio::test]
+    async fn conditional_writes_and_listing() {
+        let backend = backend();
+        backend.create_dir("history/log").await.unwrap();
+        assert!(backend.put("history/log/op_0.json", b"a".to_vec(), &WriteCondition::Absent).await.unwrap());
+        assert!(!backend.put("history/log/op_0.json", b"b".to_vec(), &WriteCondition::Absent).await.unwrap());
+
+        let object = backend.get("history/log/op_0.json").await.unwrap().unwrap();
+        assert_eq!(object.bytes, b"a");
+        let stale = WriteCondition::Match(content_etag(b"old"));
+        assert!(!backend.put("history/log/op_0.json", b"c".to_vec(), &stale).await.unwrap());
+        let current = WriteCondition::for_existing(object.etag);
+        assert!(backend.put("history/log/op_0.json", b"c".to_vec(), &current).await.unwrap());
+
+        backend.replace("history/log/head.json", b"{}".to_vec()).await.unwrap();
+        assert_eq!(
+            backend.list("history/log").await.unwrap(),
+            vec!["head.json".to_string(), "op_0.json".to_string()]
+        );
+        assert!(backend.list("missing").await.unwrap().is_empty());
+
+        backend.delete("history/log/op_0.json", &current).await.unwrap();
+        assert!(backend.exists("history/log/op_0.json").await.unwrap());
+        backend.delete("history/log/op_0.json", &WriteCondition::Any).await.unwrap();
+        assert!(!backend.exists("history/log/op_0.json").await.unwrap());
+        assert!(backend.get("../outside").await.is_err());
+    }
+}
@@ -0,0 +1,96 @@
+// 同步存储后端
+//
+// 分块、索引、操作日志、删除记录和云端加密只依赖这里定义的文件操作，
+// WebDAV、S3 兼容对象存储和本地文件夹各实现一份即可复用整套同步流程。
+// 路径均为相对同步根目录、以 / 分隔的路径，加密由上层完成，后端只读写原始字节。
+
+pub mod folder;
+pub mod s3;
+pub mod sigv4;
+pub mod webdav;
+
+use futures_util::future::BoxFuture;
+use tokio::io::{AsyncRead, DuplexStream};
+
+pub const BACKEND_WEBDAV: &str = "webdav";
+pub const BACKEND_S3: &str = "s3";
+pub const BACKEND_FOLDER: &str = "folder";
+
+pub type ByteReader = Box<dyn AsyncRead + Send + Unpin>;
+
+// 条件写入：Absent 只在文件不存在时写入，Match 只在文件 ETag 未变化时写入
+#[derive(Debug, Clone)]
+pub enum WriteCondition {
+    Any,
+    Absent,
+    Match(String),
+}
+
+impl WriteCondition {
+    // 已存在的文件按读取时的 ETag 写入，服务器不提供 ETag 时不做检查
+    pub fn for_existing(etag: Option<String>) -> Self {
+        etag.map(WriteCondition::Match).unwrap_or(WriteCondition::Any)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StoredObject {
+    pub bytes: Vec<u8>,
+    // 后端不提供 ETag 时为 None
+    pub etag: Option<String>,
+}
+
+pub trait StorageBackend: Send + Sync {
+    // 区分不同存储位置的标识，用于缓存云端加密配置
+    fn scope(&self) -> String;
+
+    fn get<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<Option<StoredObject>, String>>;
+
+    // 流式读取大文件，文件不存在时返回 None
+    fn get_stream<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<Option<ByteReader>, String>>;
+
+    // 条件不满足（文件已被其他设备修改）时返回 false
+    fn put<'a>(
+        &'a self,
+        path: &'a str,
+        bytes: Vec<u8>,
+        condition: &'a WriteCondition,
+    ) -> BoxFuture<'a, Result<bool, String>>;
+
+    fn put_stream<'a>(
+        &'a self,
+        path: &'a str,
+        reader: DuplexStream,
+        content_length: u64,
+    ) -> BoxFuture<'a, Result<(), String>>;
+
+    // 整体替换目标文件，其他设备不会读到写了一半的内容
+    fn replace<'a>(&'a self, path: &'a str, bytes: Vec<u8>) -> BoxFuture<'a, Result<(), String>>;
+
+    fn exists<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<bool, String>>;
+
+    // 文件不存在视为删除成功；Match 条件不满足时保留文件
+    fn delete<'a>(&'a self, path: &'a str, condition: &'a WriteCondition) -> BoxFuture<'a, Result<(), String>>;
+
+    // 列出目录下的文件名，不含子目录；目录不存在时返回空列表
+    fn list<'a>(&'a self, dir: &'a str) -> BoxFuture<'a, Result<Vec<String>, String>>;
+
+    // 对象存储没有目录概念，实现可以什么都不做
+    fn create_dir<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<(), String>>;
+}
+
+pub fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/")
+        .split('/')
+        .filter(|p| !p.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+pub fn normalize_backend_kind(kind: &str) -> &'static str {
+    match kind.trim() {
+        BACKEND_S3 => BACKEND_S3,
+        BACKEND_FOLDER => BACKEND_FOLDER,
+        _ => BACKEND_WEBDAV,
+    }
+}
@@ -0,0 +1,611 @@
+// S3 兼容对象存储后端，使用路径风格地址 {endpoint}/{bucket}/{前缀}/{路径}，
+// 可用于 AWS S3、MinIO、Cloudflare R2 等服务
+
+use futures_util::future::BoxFuture;
+use futures_util::TryStreamExt;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::{Body, Client, Method, StatusCode, Url};
+use tokio::io::DuplexStream;
+use tokio_util::io::ReaderStream;
+
+use super::sigv4::{self, Credentials, SigningRequest, UNSIGNED_PAYLOAD};
+use super::{normalize_path, ByteReader, StorageBackend, StoredObject, WriteCondition};
+
+const S3_NETWORK_ERROR: &str = "无法连接 S3 存储服务，请检查地址、网络或服务器状态";
+
+static KEY_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<Key>(.*?)</Key>").unwrap());
+static TRUNCATED_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<IsTruncated>\s*true\s*</IsTruncated>").unwrap());
+static TOKEN_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<NextContinuationToken>(.*?)</NextContinuationToken>").unwrap());
+
+#[derive(Debug, Clone)]
+pub struct S3Config {
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    pub access_key: String,
+    pub secret_key: String,
+    pub root_path: String,
+}
+
+pub struct S3Backend {
+    client: Client,
+    config: S3Config,
+    endpoint: Url,
+    host: String,
+    prefix: String,
+}
+
+impl S3Backend {
+    pub fn new(config: S3Config) -> Result<Self, String> {
+        let endpoint = config.endpoint.trim().trim_end_matches('/');
+        if endpoint.is_empty() {
+            return Err("S3 服务地址不能为空".to_string());
+        }
+        if config.bucket.trim().is_empty() {
+            return Err("S3 存储桶不能为空".to_string());
+        }
+        let endpoint = Url::parse(endpoint).map_err(|e| format!("S3 服务地址无效: {}", e))?;
+        let host = match (endpoint.host_str(), endpoint.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err("S3 服务地址无效".to_string()),
+        };
+        let prefix = normalize_path(&config.root_path);
+        Ok(Self {
+            client: Client::new(),
+            config,
+            endpoint,
+            host,
+            prefix,
+        })
+    }
+
+    fn object_key(&self, path: &str) -> String {
+        let path = normalize_path(path);
+        match (self.prefix.is_empty(), path.is_empty()) {
+            (true, _) => path,
+            (false, true) => self.prefix.clone(),
+            (false, false) => format!("{}/{}", self.prefix, path),
+        }
+    }
+
+    fn canonical_uri(&self, key: Option<&str>) -> String {
+        let base = self.endpoint.path().trim_end_matches('/');
+        let bucket = sigv4::uri_encode(self.config.bucket.trim(), true);
+        match key {
+            Some(key) => format!("{}/{}/{}", base, bucket, sigv4::uri_encode(key, false)),
+            None => format!("{}/{}", base, bucket),
+        }
+    }
+
+    // 生成已签名的请求，key 为 None 时请求存储桶本身
+    fn request(&self, method: Method, key: Option<&str>, query: &[(String, String)]) -> reqwest::RequestBuilder {
+        let canonical_uri = self.canonical_uri(key);
+        let canonical_query = sigv4::canonical_query(query);
+        let mut url = format!(
+            "{}://{}{}",
+            self.endpoint.scheme(),
+            self.host,
+            canonical_uri
+        );
+        if !canonical_query.is_empty() {
+            url.push('?');
+            url.push_str(&canonical_query);
+        }
+
+        let amz_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let headers = vec![
+            ("host".to_string(), self.host.clone()),
+            ("x-amz-content-sha256".to_string(), UNSIGNED_PAYLOAD.to_string()),
+            ("x-amz-date".to_string(), amz_date.clone()),
+        ];
+        let authorization = sigv4::authorization(
+            &SigningRequest {
+                method: method.as_str(),
+                canonical_uri: &canonical_uri,
+                query,
+                headers: &headers,
+                payload_hash: UNSIGNED_PAYLOAD,
+            },
+            &Credentials {
+                access_key: self.config.access_key.trim(),
+                secret_key: &self.config.secret_key,
+                region: self.region(),
+            },
+            &amz_date,
+        );
+        self.client
+            .request(method, url)
+            .header("x-amz-content-sha256", UNSIGNED_PAYLOAD)
+            .header("x-amz-date", amz_date)
+            .header(reqwest::header::AUTHORIZATION, authorization)
+    }
+
+    fn region(&self) -> &str {
+        match self.config.region.trim() {
+            "" => "us-east-1",
+            region => region,
+        }
+    }
+
+    async fn get_response(&self, path: &str) -> Result<Option<reqwest::Response>, String> {
+        let key = self.object_key(path);
+        let resp = self
+            .request(Method::GET, Some(&key), &[])
+            .send()
+            .await
+            .map_err(map_reqwest_error)?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(format_s3_status_error("读取 S3 对象失败", resp.status()));
+        }
+        Ok(Some(resp))
+    }
+
+    async fn send_put(&self, path: &str, bytes: Vec<u8>, condition: &WriteCondition) -> Result<StatusCode, String> {
+        let key = self.object_key(path);
+        let request = self.request(Method::PUT, Some(&key), &[]).body(bytes);
+        let request = match condition {
+            WriteCondition::Any => request,
+            WriteCondition::Absent => request.header(reqwest::header::IF_NONE_MATCH, "*"),
+            WriteCondition::Match(etag) => request.header(reqwest::header::IF_MATCH, etag.as_str()),
+        };
+        let resp = request.send().await.map_err(map_reqwest_error)?;
+        Ok(resp.status())
+    }
+
+    async fn put_bytes(&self, path: &str, bytes: Vec<u8>, condition: &WriteCondition) -> Result<bool, String> {
+        let fallback = (!matches!(condition, WriteCondition::Any)).then(|| bytes.clone());
+        let status = self.send_put(path, bytes, condition).await?;
+        if status.is_success() {
+            return Ok(true);
+        }
+        // 412 为条件不满足，409 为同一对象的条件写入正在进行
+        if status == StatusCode::PRECONDITION_FAILED || status == StatusCode::CONFLICT {
+            return Ok(false);
+        }
+        // 不支持条件写入的旧版服务，先检查再写入，只能尽量避免覆盖
+        if let (StatusCode::NOT_IMPLEMENTED, Some(bytes)) = (status, fallback) {
+            let current = self.get_response(path).await?.map(|resp| response_etag(&resp));
+            let allowed = match (condition, current) {
+                (WriteCondition::Absent, current) => current.is_none(),
+                (WriteCondition::Match(etag), Some(current)) => current.as_deref() == Some(etag.as_str()),
+                _ => false,
+            };
+            if !allowed {
+                return Ok(false);
+            }
+            let status = self.send_put(path, bytes, &WriteCondition::Any).await?;
+            if status.is_success() {
+                return Ok(true);
+            }
+            return Err(format_s3_status_error("写入 S3 对象失败", status));
+        }
+        Err(format_s3_status_error("写入 S3 对象失败", status))
+    }
+
+    async fn delete_object(&self, path: &str, condition: &WriteCondition) -> Result<(), String> {
+        let key = self.object_key(path);
+        let request = self.request(Method::DELETE, Some(&key), &[]);
+        let request = match condition {
+            WriteCondition::Match(etag) => request.header(reqwest::header::IF_MATCH, etag.as_str()),
+            _ => request,
+        };
+        let resp = request.send().await.map_err(map_reqwest_error)?;
+        if resp.status().is_success()
+            || resp.status() == StatusCode::NOT_FOUND
+            || resp.status() == StatusCode::PRECONDITION_FAILED
+        {
+            Ok(())
+        } else {
+            Err(format_s3_status_error("删除 S3 对象失败", resp.status()))
+        }
+    }
+
+    async fn list_objects(&self, dir: &str) -> Result<Vec<String>, String> {
+        let dir_key = self.object_key(dir);
+        let prefix = if dir_key.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", dir_key)
+        };
+        let mut names = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("list-type".to_string(), "2".to_string()),
+                ("delimiter".to_string(), "/".to_string()),
+                ("prefix".to_string(), prefix.clone()),
+            ];
+            if let Some(token) = &token {
+                query.push(("continuation-token".to_string(), token.clone()));
+            }
+            let resp = self
+                .request(Method::GET, None, &query)
+                .send()
+                .await
+                .map_err(map_reqwest_error)?;
+            if !resp.status().is_success() {
+                return Err(format_s3_status_error("列出 S3 对象失败", resp.status()));
+            }
+            let body = resp.text().await.map_err(map_reqwest_error)?;
+            for caps in KEY_RE.captures_iter(&body) {
+                let key = xml_unescape(&caps[1]);
+                if let Some(name) = key.strip_prefix(&prefix).filter(|name| !name.is_empty()) {
+                    names.push(name.to_string());
+                }
+            }
+            token = TOKEN_RE.captures(&body).map(|caps| xml_unescape(&caps[1]));
+            if !TRUNCATED_RE.is_match(&body) || token.is_none() {
+                break;
+            }
+        }
+        Ok(names)
+    }
+}
+
+impl StorageBackend for S3Backend {
+    fn scope(&self) -> String {
+        format!(
+            "s3:{}{}\n{}",
+            self.host,
+            self.canonical_uri(Some(&self.prefix)),
+            self.config.access_key.trim()
+        )
+    }
+
+    fn get<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<Option<StoredObject>, String>> {
+        Box::pin(async move {
+            let Some(resp) = self.get_response(path).await? else {
+                return Ok(None);
+            };
+            let etag = response_etag(&resp);
+            let bytes = resp.bytes().await.map_err(map_reqwest_error)?.to_vec();
+            Ok(Some(StoredObject { bytes, etag }))
+        })
+    }
+
+    fn get_stream<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<Option<ByteReader>, String>> {
+        Box::pin(async move {
+            let Some(resp) = self.get_response(path).await? else {
+                return Ok(None);
+            };
+            let stream = resp
+                .bytes_stream()
+                .map_err(std::io::Error::other);
+            let reader: ByteReader = Box::new(tokio_util::io::StreamReader::new(stream));
+            Ok(Some(reader))
+        })
+    }
+
+    fn put<'a>(
+        &'a self,
+        path: &'a str,
+        bytes: Vec<u8>,
+        condition: &'a WriteCondition,
+    ) -> BoxFuture<'a, Result<bool, String>> {
+        Box::pin(self.put_bytes(path, bytes, condition))
+    }
+
+    fn put_stream<'a>(
+        &'a self,
+        path: &'a str,
+        reader: DuplexStream,
+        content_length: u64,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let key = self.object_key(path);
+            let resp = self
+                .request(Method::PUT, Some(&key), &[])
+                .header(reqwest::header::CONTENT_LENGTH, content_length)
+                .body(Body::wrap_stream(ReaderStream::new(reader)))
+                .send()
+                .await
+                .map_err(map_reqwest_error)?;
+            if resp.status().is_success() {
+                Ok(())
+            } else {
+                Err(format_s3_status_error("写入 S3 对象失败", resp.status()))
+            }
+        })
+    }
+
+    // 对象存储的单次写入本身就是整体替换
+    fn replace<'a>(&'a self, path: &'a str, bytes: Vec<u8>) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            self.put_bytes(path, bytes, &WriteCondition::Any).await?;
+            Ok(())
+        })
+    }
+
+    fn exists<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<bool, String>> {
+        Box::pin(async move {
+            let key = self.object_key(path);
+            let resp = self
+                .request(Method::HEAD, Some(&key), &[])
+                .send()
+                .await
+                .map_err(map_reqwest_error)?;
+            if resp.status() == StatusCode::NOT_FOUND {
+                return Ok(false);
+            }
+            if !resp.status().is_success() {
+                return Err(format_s3_status_error("读取 S3 对象失败", resp.status()));
+            }
+            Ok(true)
+        })
+    }
+
+    fn delete<'a>(&'a self, path: &'a str, condition: &'a WriteCondition) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(self.delete_object(path, condition))
+    }
+
+    fn list<'a>(&'a self, dir: &'a str) -> BoxFuture<'a, Result<Vec<String>, String>> {
+        Box::pin(self.list_objects(dir))
+    }
+
+    fn create_dir<'a>(&'a self, _path: &'a str) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+fn response_etag(resp: &reqwest::Response) -> Option<String> {
+    resp.headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+fn xml_unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn map_reqwest_error(error: reqwest::Error) -> String {
+    if error.is_timeout() {
+        return "S3 请求超时".to_string();
+    }
+    if error.is_connect() || error.is_request() {
+        return S3_NETWORK_ERROR.to_string();
+    }
+    format!("S3 请求失败: {}", error)
+}
+
+fn format_s3_status_error(action: &str, status: StatusCode) -> String {
+    let hint = match status {
+        StatusCode::BAD_REQUEST => "请求无效，请检查区域设置",
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => "访问密钥不正确或没有访问权限",
+        StatusCode::NOT_FOUND => "存储桶不存在",
+        StatusCode::MOVED_PERMANENTLY | StatusCode::TEMPORARY_REDIRECT => "区域或服务地址不匹配",
+        _ if status.is_server_error() => "服务器返回异常",
+        _ => "请求被服务器拒绝",
+    };
+    format!("{}: {} ({})", action, hint, status.as_u16())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use parking_lot::Mutex;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    type Objects = Arc<Mutex<HashMap<String, (Vec<u8>, String)>>>;
+
+    struct MockRequest {
+        method: String,
+        path: String,
+        query: HashMap<String, String>,
+        headers: HashMap<String, String>,
+        body: Vec<u8>,
+    }
+
+    // 回环地址上的最小 S3 服务，conditional 为 false 时模拟不支持条件写入的旧版服务
+    async fn mock_s3(conditional: bool) -> String {
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let objects: Objects = Arc::default();
+        tokio::spawn(async move {
+            let mut next_etag = 0u64;
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let Some(request) = read_mock_request(&mut stream).await else {
+                    continue;
+                };
+                next_etag += 1;
+                let (status, headers, body) = handle_mock_request(&objects, conditional, next_etag, request);
+                let mut response = format!("HTTP/1.1 {}\r\nconnection: close\r\ncontent-length: {}\r\n", status, body.len());
+                for (name, value) in headers {
+                    response.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                response.push_str("\r\n");
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.write_all(&body).await;
+            }
+        });
+        format!("http://127.0.0.1:{}", port)
+    }
+
+    async fn read_mock_request(stream: &mut tokio::net::TcpStream) -> Option<MockRequest> {
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let header_end = loop {
+            if let Some(pos) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+                break pos + 4;
+            }
+            let read = stream.read(&mut chunk).await.ok()?;
+            if read == 0 {
+                return None;
+            }
+            buffer.extend_from_slice(&chunk[..read]);
+        };
+        let head = String::from_utf8_lossy(&buffer[..header_end]).into_owned();
+        let mut lines = head.split("\r\n");
+        let mut request_line = lines.next()?.split(' ');
+        let method = request_line.next()?.to_string();
+        let target = request_line.next()?.to_string();
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            .collect::<HashMap<_, _>>();
+        let length = headers.get("content-length").and_then(|value| value.parse::<usize>().ok()).unwrap_or(0);
+        let mut body = buffer[header_end..].to_vec();
+        while body.len() < length {
+            let read = stream.read(&mut chunk).await.ok()?;
+            if read == 0 {
+                return None;
+            }
+            body.extend_from_slice(&chunk[..read]);
+        }
+        let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+        let query = query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(name, value)| (percent_decode(name), percent_decode(value)))
+            .collect();
+        Some(MockRequest { method, path: percent_decode(path), query, headers, body })
+    }
+
+    fn percent_decode(value: &str) -> String {
+        let bytes = value.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut index = 0;
+        while index < bytes.len() {
+            if bytes[index] == b'%' && index + 2 < bytes.len() {
+                let hex = std::str::from_utf8(&bytes[index + 1..index + 3]).ok();
+                if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    decoded.push(byte);
+                    index += 3;
+                    continue;
+                }
+            }
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+        String::from_utf8_lossy(&decoded).into_owned()
+    }
+
+    fn handle_mock_request(
+        objects: &Objects,
+        conditional: bool,
+        next_etag: u64,
+        request: MockRequest,
+    ) -> (&'static str, Vec<(&'static str, String)>, Vec<u8>) {
+        let mut objects = objects.lock();
+        let if_none_match = request.headers.get("if-none-match");
+        let if_match = request.headers.get("if-match");
+        if !conditional && (if_none_match.is_some() || if_match.is_some()) {
+            return ("501 Not Implemented", Vec::new(), Vec::new());
+        }
+
+        if request.path == "/bucket" {
+            let prefix = request.query.get("prefix").cloned().unwrap_or_default();
+            let mut keys = objects
+                .keys()
+                .filter(|key| key.strip_prefix(&prefix).map(|name| !name.contains('/')).unwrap_or(false))
+                .cloned()
+                .collect::<Vec<_>>();
+            keys.sort();
+            let contents = keys
+                .iter()
+                .map(|key| format!("<Contents><Key>{}</Key></Contents>", key))
+                .collect::<String>();
+            let body = format!("<ListBucketResult>{}<IsTruncated>false</IsTruncated></ListBucketResult>", contents);
+            return ("200 OK", Vec::new(), body.into_bytes());
+        }
+
+        let Some(key) = request.path.strip_prefix("/bucket/").map(str::to_string) else {
+            return ("404 Not Found", Vec::new(), Vec::new());
+        };
+        let current = objects.get(&key).map(|(_, etag)| etag.clone());
+        let precondition_failed = match (if_none_match, if_match) {
+            (Some(_), _) => current.is_some(),
+            (_, Some(etag)) => current.as_deref() != Some(etag.as_str()),
+            _ => false,
+        };
+        match request.method.as_str() {
+            "GET" | "HEAD" => match objects.get(&key) {
+                Some((bytes, etag)) => {
+                    let body = if request.method == "GET" { bytes.clone() } else { Vec::new() };
+                    ("200 OK", vec![("etag", etag.clone())], body)
+                }
+                None => ("404 Not Found", Vec::new(), Vec::new()),
+            },
+            "PUT" if precondition_failed => ("412 Precondition Failed", Vec::new(), Vec::new()),
+            "PUT" => {
+                let etag = format!("\"{}\"", next_etag);
+                objects.insert(key, (request.body, etag.clone()));
+                ("200 OK", vec![("etag", etag)], Vec::new())
+            }
+            "DELETE" if precondition_failed => ("412 Precondition Failed", Vec::new(), Vec::new()),
+            "DELETE" => {
+                objects.remove(&key);
+                ("204 No Content", Vec::new(), Vec::new())
+            }
+            _ => ("405 Method Not Allowed", Vec::new(), Vec::new()),
+        }
+    }
+
+    fn backend(endpoint: String) -> S3Backend {
+        S3Backend::new(S3Config {
+            endpoint,
+            region: String::new(),
+            bucket: "bucket".to_string(),
+            access_key: "access".to_string(),
+            secret_key: "secret".to_string(),
+            root_path: "quickclipboard".to_string(),
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn reads_writes_and_lists_with_conditional_requests() {
+        let backend = backend(mock_s3(true).await);
+        assert!(backend.put("history/log/op_0.json", b"a".to_vec(), &WriteCondition::Absent).await.unwrap());
+        assert!(!backend.put("history/log/op_0.json", b"b".to_vec(), &WriteCondition::Absent).await.unwrap());
+
+        let object = backend.get("history/log/op_0.json").await.unwrap().unwrap();
+        assert_eq!(object.bytes, b"a");
+        let stale = WriteCondition::Match("\"stale\"".to_string());
+        assert!(!backend.put("history/log/op_0.json", b"c".to_vec(), &stale).await.unwrap());
+        let current = WriteCondition::for_existing(object.etag);
+        assert!(backend.put("history/log/op_0.json", b"c".to_vec(), &current).await.unwrap());
+        assert_eq!(backend.get("history/log/op_0.json").await.unwrap().unwrap().bytes, b"c");
+
+        backend.replace("history/log/head.json", b"{}".to_vec()).await.unwrap();
+        backend.replace("history/chunks/chunk_000.json", b"{}".to_vec()).await.unwrap();
+        assert_eq!(
+            backend.list("history/log").await.unwrap(),
+            vec!["head.json".to_string(), "op_0.json".to_string()]
+        );
+        assert!(backend.get("missing.json").await.unwrap().is_none());
+
+        // ETag 已变化的条件删除保留文件
+        backend.delete("history/log/op_0.json", &current).await.unwrap();
+        assert!(backend.exists("history/log/op_0.json").await.unwrap());
+        backend.delete("history/log/op_0.json", &WriteCondition::Any).await.unwrap();
+        assert!(!backend.exists("history/log/op_0.json").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn falls_back_when_conditional_writes_are_not_implemented() {
+        let backend = backend(mock_s3(false).await);
+        assert!(backend.put("lock.json", b"a".to_vec(), &WriteCondition::Absent).await.unwrap());
+        assert!(!backend.put("lock.json", b"b".to_vec(), &WriteCondition::Absent).await.unwrap());
+
+        let object = backend.get("lock.json").await.unwrap().unwrap();
+        assert!(!backend.put("lock.json", b"c".to_vec(), &WriteCondition::Match("\"stale\"".to_string())).await.unwrap());
+        assert!(backend.put("lock.json", b"c".to_vec(), &WriteCondition::for_existing(object.etag)).await.unwrap());
+        assert_eq!(backend.get("lock.json").await.unwrap().unwrap().bytes, b"c");
+    }
+}
@@ -0,0 +1,317 @@
+use futures_util::future::BoxFuture;
+use futures_util::TryStreamExt;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::{Body, Client, Method, StatusCode};
+use tokio::io::DuplexStream;
+use tokio_util::io::ReaderStream;
+
+use super::{normalize_path, ByteReader, StorageBackend, StoredObject, WriteCondition};
+use crate::services::webdav_sync::types::WebdavConfig;
+
+const WEBDAV_NETWORK_ERROR: &str = "无法连接 WebDAV 服务，请检查地址、网络或服务器状态";
+
+static RESPONSE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<(?:[\w-]+:)?response\b.*?</(?:[\w-]+:)?response>").unwrap());
+static HREF_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<(?:[\w-]+:)?href>(.*?)</(?:[\w-]+:)?href>").unwrap());
+static COLLECTION_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<(?:[\w-]+:)?collection\s*/?>").unwrap());
+
+pub struct WebdavBackend {
+    client: Client,
+    config: WebdavConfig,
+    base_url: String,
+}
+
+impl WebdavBackend {
+    pub fn new(config: WebdavConfig) -> Result<Self, String> {
+        let url = config.url.trim().trim_end_matches('/').to_string();
+        if url.is_empty() {
+            return Err("WebDAV 地址不能为空".to_string());
+        }
+        let root = normalize_path(&config.root_path);
+        let base_url = if root.is_empty() {
+            url.clone()
+        } else {
+            format!("{}/{}", url, root)
+        };
+        Ok(Self {
+            client: Client::new(),
+            config,
+            base_url,
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> reqwest::RequestBuilder {
+        let url = self.url_for(path);
+        let builder = self.client.request(method, url);
+        if self.config.username.trim().is_empty() {
+            builder
+        } else {
+            builder.basic_auth(self.config.username.trim().to_string(), Some(self.config.password.clone()))
+        }
+    }
+
+    fn url_for(&self, path: &str) -> String {
+        let path = normalize_path(path);
+        if path.is_empty() {
+            self.base_url.clone()
+        } else {
+            format!("{}/{}", self.base_url, path)
+        }
+    }
+
+    async fn get_response(&self, path: &str) -> Result<Option<reqwest::Response>, String> {
+        let resp = self.request(Method::GET, path).send().await.map_err(map_reqwest_error)?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(format_webdav_status_error("读取 WebDAV 文件失败", resp.status()));
+        }
+        Ok(Some(resp))
+    }
+
+    async fn put_bytes(&self, path: &str, bytes: Vec<u8>, condition: &WriteCondition) -> Result<bool, String> {
+        let request = self.request(Method::PUT, path).body(bytes);
+        let request = match condition {
+            WriteCondition::Any => request,
+            WriteCondition::Absent => request.header(reqwest::header::IF_NONE_MATCH, "*"),
+            WriteCondition::Match(etag) => request.header(reqwest::header::IF_MATCH, etag.as_str()),
+        };
+        let resp = request.send().await.map_err(map_reqwest_error)?;
+        if resp.status().is_success() {
+            Ok(true)
+        } else if resp.status() == StatusCode::PRECONDITION_FAILED {
+            Ok(false)
+        } else {
+            Err(format_webdav_status_error("写入 WebDAV 文件失败", resp.status()))
+        }
+    }
+
+    // 先写入临时文件再 MOVE 覆盖目标文件，服务器不支持 MOVE 时退回直接覆盖
+    async fn replace_bytes(&self, path: &str, bytes: Vec<u8>) -> Result<(), String> {
+        let temp_path = format!("{}.{}.tmp", normalize_path(path), uuid::Uuid::new_v4().simple());
+        self.put_bytes(&temp_path, bytes.clone(), &WriteCondition::Any).await?;
+
+        let resp = self
+            .request(Method::from_bytes(b"MOVE").map_err(|e| e.to_string())?, &temp_path)
+            .header("Destination", self.url_for(path))
+            .header("Overwrite", "T")
+            .send()
+            .await
+            .map_err(map_reqwest_error)?;
+        if resp.status().is_success() {
+            return Ok(());
+        }
+        if resp.status() == StatusCode::METHOD_NOT_ALLOWED || resp.status() == StatusCode::NOT_IMPLEMENTED {
+            self.put_bytes(path, bytes, &WriteCondition::Any).await?;
+            return self.delete_path(&temp_path, &WriteCondition::Any).await;
+        }
+        let _ = self.delete_path(&temp_path, &WriteCondition::Any).await;
+        Err(format_webdav_status_error("替换 WebDAV 文件失败", resp.status()))
+    }
+
+    async fn delete_path(&self, path: &str, condition: &WriteCondition) -> Result<(), String> {
+        let request = self.request(Method::DELETE, path);
+        let request = match condition {
+            WriteCondition::Match(etag) => request.header(reqwest::header::IF_MATCH, etag.as_str()),
+            _ => request,
+        };
+        let resp = request.send().await.map_err(map_reqwest_error)?;
+        if resp.status().is_success()
+            || resp.status() == StatusCode::NOT_FOUND
+            || resp.status() == StatusCode::PRECONDITION_FAILED
+        {
+            Ok(())
+        } else {
+            Err(format_webdav_status_error("删除 WebDAV 文件失败", resp.status()))
+        }
+    }
+
+    async fn list_dir(&self, dir: &str) -> Result<Vec<String>, String> {
+        let method = Method::from_bytes(b"PROPFIND").map_err(|e| e.to_string())?;
+        let resp = self
+            .request(method, &format!("{}/", normalize_path(dir)))
+            .header("Depth", "1")
+            .header(reqwest::header::CONTENT_TYPE, "application/xml")
+            .body(r#"<?xml version="1.0" encoding="utf-8"?><d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/></d:prop></d:propfind>"#)
+            .send()
+            .await
+            .map_err(map_reqwest_error)?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        if !resp.status().is_success() {
+            return Err(format_webdav_status_error("读取 WebDAV 目录失败", resp.status()));
+        }
+        let body = resp.text().await.map_err(map_reqwest_error)?;
+        Ok(parse_propfind_names(&body))
+    }
+
+    async fn mkcol(&self, path: &str) -> Result<(), String> {
+        let method = Method::from_bytes(b"MKCOL").map_err(|e| e.to_string())?;
+        let resp = self.request(method, path).send().await.map_err(map_reqwest_error)?;
+        if resp.status().is_success() || resp.status() == StatusCode::METHOD_NOT_ALLOWED {
+            Ok(())
+        } else {
+            Err(format_webdav_status_error("创建 WebDAV 目录失败", resp.status()))
+        }
+    }
+}
+
+impl StorageBackend for WebdavBackend {
+    fn scope(&self) -> String {
+        format!("{}\n{}", self.base_url, self.config.username.trim())
+    }
+
+    fn get<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<Option<StoredObject>, String>> {
+        Box::pin(async move {
+            let Some(resp) = self.get_response(path).await? else {
+                return Ok(None);
+            };
+            let etag = resp
+                .headers()
+                .get(reqwest::header::ETAG)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string());
+            let bytes = resp.bytes().await.map_err(map_reqwest_error)?.to_vec();
+            Ok(Some(StoredObject { bytes, etag }))
+        })
+    }
+
+    fn get_stream<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<Option<ByteReader>, String>> {
+        Box::pin(async move {
+            let Some(resp) = self.get_response(path).await? else {
+                return Ok(None);
+            };
+            let stream = resp
+                .bytes_stream()
+                .map_err(std::io::Error::other);
+            let reader: ByteReader = Box::new(tokio_util::io::StreamReader::new(stream));
+            Ok(Some(reader))
+        })
+    }
+
+    fn put<'a>(
+        &'a self,
+        path: &'a str,
+        bytes: Vec<u8>,
+        condition: &'a WriteCondition,
+    ) -> BoxFuture<'a, Result<bool, String>> {
+        Box::pin(self.put_bytes(path, bytes, condition))
+    }
+
+    fn put_stream<'a>(
+        &'a self,
+        path: &'a str,
+        reader: DuplexStream,
+        content_length: u64,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let stream = ReaderStream::new(reader);
+            let resp = self
+                .request(Method::PUT, path)
+                .header(reqwest::header::CONTENT_LENGTH, content_length)
+                .body(Body::wrap_stream(stream))
+                .send()
+                .await
+                .map_err(map_reqwest_error)?;
+            if resp.status().is_success() {
+                Ok(())
+            } else {
+                Err(format_webdav_status_error("写入 WebDAV 文件失败", resp.status()))
+            }
+        })
+    }
+
+    fn replace<'a>(&'a self, path: &'a str, bytes: Vec<u8>) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(self.replace_bytes(path, bytes))
+    }
+
+    fn exists<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<bool, String>> {
+        Box::pin(async move {
+            let resp = self.request(Method::HEAD, path).send().await.map_err(map_reqwest_error)?;
+            if resp.status() == StatusCode::NOT_FOUND {
+                return Ok(false);
+            }
+            if !resp.status().is_success() {
+                return Err(format_webdav_status_error("读取 WebDAV 文件失败", resp.status()));
+            }
+            Ok(true)
+        })
+    }
+
+    fn delete<'a>(&'a self, path: &'a str, condition: &'a WriteCondition) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(self.delete_path(path, condition))
+    }
+
+    fn list<'a>(&'a self, dir: &'a str) -> BoxFuture<'a, Result<Vec<String>, String>> {
+        Box::pin(self.list_dir(dir))
+    }
+
+    fn create_dir<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(self.mkcol(path))
+    }
+}
+
+// 从 PROPFIND 响应中取出文件名，跳过目录本身和子目录
+fn parse_propfind_names(body: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    for response in RESPONSE_RE.find_iter(body) {
+        let response = response.as_str();
+        if COLLECTION_RE.is_match(response) {
+            continue;
+        }
+        let Some(href) = HREF_RE.captures(response).and_then(|caps| caps.get(1)) else {
+            continue;
+        };
+        let href = percent_decode(href.as_str().trim());
+        if let Some(name) = href.rsplit('/').find(|segment| !segment.is_empty()) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn map_reqwest_error(error: reqwest::Error) -> String {
+    if error.is_timeout() {
+        return "WebDAV 请求超时".to_string();
+    }
+    if error.is_connect() || error.is_request() {
+        return WEBDAV_NETWORK_ERROR.to_string();
+    }
+    format!("WebDAV 请求失败: {}", error)
+}
+
+fn format_webdav_status_error(action: &str, status: StatusCode) -> String {
+    let hint = match status {
+        StatusCode::UNAUTHORIZED => "账号或密码不正确",
+        StatusCode::FORBIDDEN => "当前账号没有访问权限",
+        StatusCode::NOT_FOUND => "路径不存在",
+        StatusCode::CONFLICT => "目标目录不存在或路径冲突",
+        _ if status.as_u16() == 507 => "存储空间不足",
+        _ if status.is_server_error() => "服务器返回异常",
+        _ => "请求被服务器拒绝",
+    };
+    format!("{}: {} ({})", action, hint, status.as_u16())
+}
@@ -1,4 +1,6 @@
-use super::types::{RecordChunk, SyncCollection};
+use std::collections::{BTreeMap, HashMap};
+
+use super::types::{CloudRecord, RecordChunk, SyncCollection, SyncIndex, SyncIndexEntry, CHUNK_RECORD_LIMIT};
 use super::webdav_client::WebdavClient;
 
 pub fn chunk_path(collection: SyncCollection, chunk: u32) -> String {
@@ -24,3 +26,121 @@ pub async fn save_chunk(
 ) -> Result<(), String> {
     client.put_json(&chunk_path(collection, chunk), data).await
 }
+
+// 把记录写入分块并更新索引：已有记录写回原分块，新记录优先填满未满的分块
+// records 中每项为记录及推送该记录的设备
+pub async fn store_records(
+    client: &WebdavClient,
+    collection: SyncCollection,
+    index: &mut SyncIndex,
+    records: Vec<(CloudRecord, String)>,
+) -> Result<(), String> {
+    let mut existing_by_chunk: HashMap<u32, Vec<(CloudRecord, String)>> = HashMap::new();
+    let mut new_records = Vec::new();
+
+    for (record, device_id) in records {
+        if let Some(entry) = index.entries.get(&record.uuid) {
+            existing_by_chunk.entry(entry.chunk).or_default().push((record, device_id));
+        } else {
+            new_records.push((record, device_id));
+        }
+    }
+
+    let mut new_records_by_chunk = Vec::<(u32, Vec<(CloudRecord, String)>)>::new();
+    let mut chunk_counts = chunk_record_counts(&index.entries);
+    let fillable_chunk_ids = chunk_counts
+        .iter()
+        .filter_map(|(chunk_id, count)| {
+            if *count < CHUNK_RECORD_LIMIT {
+                Some(*chunk_id)
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut fillable_index = 0usize;
+    let next_available_chunk = chunk_counts
+        .keys()
+        .next_back()
+        .copied()
+        .map(|chunk_id| chunk_id.saturating_add(1))
+        .unwrap_or(0);
+    let mut current_chunk_id = index.next_chunk.max(next_available_chunk);
+    let mut current_chunk_records = Vec::new();
+
+    for record in new_records {
+        let fillable_chunk_id = loop {
+            let Some(chunk_id) = fillable_chunk_ids.get(fillable_index).copied() else {
+                break None;
+            };
+            let count = chunk_counts.get(&chunk_id).copied().unwrap_or(0);
+            if count < CHUNK_RECORD_LIMIT {
+                break Some(chunk_id);
+            }
+            fillable_index += 1;
+        };
+
+        if let Some(chunk_id) = fillable_chunk_id {
+            existing_by_chunk.entry(chunk_id).or_default().push(record);
+            *chunk_counts.entry(chunk_id).or_default() += 1;
+            continue;
+        }
+
+        current_chunk_records.push(record);
+        if current_chunk_records.len() >= CHUNK_RECORD_LIMIT {
+            let count = current_chunk_records.len();
+            new_records_by_chunk.push((current_chunk_id, current_chunk_records));
+            chunk_counts.insert(current_chunk_id, count);
+            current_chunk_id = current_chunk_id.saturating_add(1);
+            current_chunk_records = Vec::new();
+        }
+    }
+    if !current_chunk_records.is_empty() {
+        let count = current_chunk_records.len();
+        new_records_by_chunk.push((current_chunk_id, current_chunk_records));
+        chunk_counts.insert(current_chunk_id, count);
+        current_chunk_id = current_chunk_id.saturating_add(1);
+    }
+
+    if !existing_by_chunk.is_empty() || !new_records_by_chunk.is_empty() {
+        client.ensure_collection_dirs(collection).await?;
+    }
+
+    for (chunk_id, records) in existing_by_chunk {
+        let mut chunk = load_chunk(client, collection, chunk_id).await?;
+        for (record, device_id) in records {
+            index.entries.insert(record.uuid.clone(), chunk_entry(chunk_id, &record, device_id));
+            chunk.records.insert(record.uuid.clone(), record);
+        }
+        save_chunk(client, collection, chunk_id, &chunk).await?;
+    }
+
+    for (chunk_id, records) in new_records_by_chunk {
+        let mut chunk = RecordChunk::default();
+        for (record, device_id) in records {
+            index.entries.insert(record.uuid.clone(), chunk_entry(chunk_id, &record, device_id));
+            chunk.records.insert(record.uuid.clone(), record);
+        }
+        save_chunk(client, collection, chunk_id, &chunk).await?;
+    }
+    index.next_chunk = current_chunk_id;
+    Ok(())
+}
+
+fn chunk_entry(chunk_id: u32, record: &CloudRecord, device_id: String) -> SyncIndexEntry {
+    SyncIndexEntry {
+        chunk: chunk_id,
+        updated_at: record.updated_at,
+        source_device_id: device_id,
+        version: record.version.clone(),
+        log_seq: None,
+    }
+}
+
+fn chunk_record_counts(index_entries: &HashMap<String, SyncIndexEntry>) -> BTreeMap<u32, usize> {
+    let mut counts = BTreeMap::new();
+    for entry in index_entries.values() {
+        *counts.entry(entry.chunk).or_default() += 1;
+    }
+    counts
+}
@@ -117,6 +117,15 @@ pub fn context_for_config(
     Ok(WebdavCryptoContext { key })
 }
 
+// 随机生成的数据密钥，不经口令派生；调用方用口令派生的密钥包装后保存
+pub fn random_context() -> WebdavCryptoContext {
+    let mut bytes = [0u8; KEY_LEN];
+    OsRng.fill_bytes(&mut bytes);
+    WebdavCryptoContext {
+        key: Arc::new(MasterKey { bytes }),
+    }
+}
+
 pub fn cached_config(scope: &str) -> Option<WebdavE2eeConfig> {
     CONFIG_CACHE.lock().get(scope).cloned()
 }
@@ -131,6 +140,20 @@ pub fn clear_cached_keys() {
 }
 
 impl WebdavCryptoContext {
+    // 用当前密钥加密另一把密钥，更换口令时只需重新包装，数据无需重新加密
+    pub fn wrap_key(&self, aad: &str, key: &WebdavCryptoContext) -> Result<Vec<u8>, String> {
+        self.encrypt_bytes(aad, &key.key.bytes)
+    }
+
+    pub fn unwrap_key(&self, aad: &str, wrapped: &[u8]) -> Result<WebdavCryptoContext, String> {
+        let mut plaintext = self.decrypt_bytes(aad, wrapped)?;
+        let bytes = <[u8; KEY_LEN]>::try_from(plaintext.as_slice()).map_err(|_| "密钥长度无效".to_string());
+        plaintext.zeroize();
+        Ok(WebdavCryptoContext {
+            key: Arc::new(MasterKey { bytes: bytes? }),
+        })
+    }
+
     pub fn encrypted_file_size(&self, plain_size: u64, chunk_size: usize) -> Result<u64, String> {
         validate_file_chunk_size(chunk_size)?;
         let chunk_size = chunk_size as u64;
@@ -466,7 +489,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::{clear_cached_keys, context_for_config, create_config};
+    use super::{clear_cached_keys, context_for_config, create_config, random_context};
     use tokio::io::AsyncReadExt;
 
     #[test]
@@ -490,6 +513,21 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn wraps_and_unwraps_data_key() {
+        clear_cached_keys();
+        let config = create_config();
+        let context = context_for_config("test", &config, "secret").unwrap();
+        let data_key = random_context();
+        let wrapped = context.wrap_key("local/data-key", &data_key).unwrap();
+        let unwrapped = context.unwrap_key("local/data-key", &wrapped).unwrap();
+        let encrypted = data_key.encrypt_bytes("item", b"hello").unwrap();
+        assert_eq!(unwrapped.decrypt_bytes("item", &encrypted).unwrap(), b"hello");
+
+        let other = context_for_config("test", &create_config(), "other").unwrap();
+        assert!(other.unwrap_key("local/data-key", &wrapped).is_err());
+    }
+
     #[tokio::test]
     async fn streams_encrypted_file_frames() {
         clear_cached_keys();
@@ -1,8 +1,11 @@
 use std::collections::{HashMap, HashSet};
 
 use super::chunk_manager::load_chunk;
-use super::index_manager::load_index;
-use super::types::{CloudRecord, SyncCollection, SyncIndexEntry, SyncReport};
+use super::local_state::{load_log_state, save_log_state, LocalLogState};
+use super::op_log;
+use super::types::{CloudRecord, SyncCollection, SyncIndexEntry, SyncReport, VersionVector};
+use crate::services::database::RecordUpsertResult;
+use crate::services::sync_transfer::sync_plan::has_unseen_changes;
 use super::webdav_client::WebdavClient;
 
 pub async fn download_all(
@@ -32,20 +35,22 @@ pub async fn download_all(
 
     if settings.webdav_sync_clipboard {
         let local_states = crate::services::database::webdav_history_record_states()?;
+        let local_versions = crate::services::database::sync_version_states(crate::services::database::COLLECTION_HISTORY)?;
         let mut records_for_images = Vec::new();
         match download_collection(
             client,
             SyncCollection::History,
             force_download,
             &local_states,
+            &local_versions,
             &tombstone_states,
         )
         .await
         {
-            Ok(records) => {
+            Ok((records, log_state)) => {
                 records_for_images.extend(records.iter().cloned());
-                let changed = if records.is_empty() {
-                    Vec::new()
+                let result = if records.is_empty() {
+                    RecordUpsertResult::default()
                 } else {
                     if force_download {
                         crate::services::database::webdav_repair_history_records(&records)?
@@ -53,13 +58,15 @@ pub async fn download_all(
                         crate::services::database::lan_upsert_history_records(&records)?
                     }
                 };
-                records_for_images.extend(changed.iter().cloned());
-                let count = changed.len() as u32;
+                records_for_images.extend(result.changed.iter().cloned());
+                let count = result.changed.len() as u32;
                 report.pulled += count;
                 report.pulled_clipboard += count;
                 report
                     .pulled_items
-                    .extend(changed.iter().map(|record| record.report_item("clipboard")));
+                    .extend(result.changed.iter().map(|record| record.report_item("clipboard")));
+                report.conflicts.extend(result.conflicts);
+                save_log_state(SyncCollection::History, &log_state)?;
             }
             Err(e) => report.errors.push(format!("剪贴板历史拉取失败: {}", e)),
         }
@@ -72,20 +79,22 @@ pub async fn download_all(
 
     if settings.webdav_sync_favorites {
         let local_states = crate::services::database::webdav_favorite_record_states()?;
+        let local_versions = crate::services::database::sync_version_states(crate::services::database::COLLECTION_FAVORITES)?;
         let mut records_for_images = Vec::new();
         match download_collection(
             client,
             SyncCollection::Favorites,
             force_download,
             &local_states,
+            &local_versions,
             &tombstone_states,
         )
         .await
         {
-            Ok(records) => {
+            Ok((records, log_state)) => {
                 records_for_images.extend(records.iter().cloned());
-                let changed = if records.is_empty() {
-                    Vec::new()
+                let result = if records.is_empty() {
+                    RecordUpsertResult::default()
                 } else {
                     if force_download {
                         crate::services::database::webdav_repair_favorite_records(&records)?
@@ -93,13 +102,15 @@ pub async fn download_all(
                         crate::services::database::lan_upsert_favorite_records(&records)?
                     }
                 };
-                records_for_images.extend(changed.iter().cloned());
-                let count = changed.len() as u32;
+                records_for_images.extend(result.changed.iter().cloned());
+                let count = result.changed.len() as u32;
                 report.pulled += count;
                 report.pulled_favorites += count;
                 report
                     .pulled_items
-                    .extend(changed.iter().map(|record| record.report_item("favorites")));
+                    .extend(result.changed.iter().map(|record| record.report_item("favorites")));
+                report.conflicts.extend(result.conflicts);
+                save_log_state(SyncCollection::Favorites, &log_state)?;
             }
             Err(e) => report.errors.push(format!("收藏拉取失败: {}", e)),
         }
@@ -131,20 +142,45 @@ pub async fn download_all(
     Ok(report)
 }
 
+// 返回需要写入本地的记录和新的日志读取进度，记录写入成功后再保存进度
 async fn download_collection(
     client: &WebdavClient,
     collection: SyncCollection,
     force_download: bool,
     local_states: &HashMap<String, i64>,
+    local_versions: &HashMap<String, VersionVector>,
     tombstone_states: &HashMap<String, i64>,
-) -> Result<Vec<CloudRecord>, String> {
-    let index = load_index(client, collection).await?;
-    if index.entries.is_empty() {
-        return Ok(Vec::new());
+) -> Result<(Vec<CloudRecord>, LocalLogState), String> {
+    let mut state = load_log_state(collection)?;
+    let head = op_log::load_head(client, collection).await?;
+    // 首次同步、强制拉取或本机进度已被合并进分块时读取完整状态，否则只读取新的日志批次
+    let full = force_download || state.cursor == 0 || state.cursor < head.compacted_seq;
+    let (entries, mut logged) = if full {
+        let (index, changes) = op_log::load_full_view(client, collection).await?;
+        state.entries = index.entries.clone();
+        state.cursor = changes.end_seq;
+        state.own_seqs.clear();
+        (index.entries, changes.records)
+    } else {
+        let changes = op_log::read_changes(client, collection, state.cursor, &state.own_seqs).await?;
+        let entries = changes
+            .records
+            .iter()
+            .map(|(uuid, logged)| (uuid.clone(), logged.entry()))
+            .collect::<HashMap<_, _>>();
+        state.entries.extend(entries.clone());
+        state.cursor = changes.end_seq;
+        let cursor = state.cursor;
+        state.own_seqs.retain(|seq| *seq >= cursor);
+        (entries, changes.records)
+    };
+    if entries.is_empty() {
+        return Ok((Vec::new(), state));
     }
+
     let collection_name = collection.dir();
     let mut selected_entries = HashMap::<String, SyncIndexEntry>::new();
-    for (uuid, entry) in index.entries {
+    for (uuid, entry) in entries {
         if tombstone_states
             .get(&crate::services::database::tombstone_state_key(collection_name, &uuid))
             .map(|deleted_at| *deleted_at >= entry.updated_at)
@@ -155,7 +191,9 @@ async fn download_collection(
 
         if !force_download {
             if let Some(local_updated_at) = local_states.get(&uuid) {
-                if *local_updated_at >= entry.updated_at {
+                if *local_updated_at >= entry.updated_at
+                    && !has_unseen_changes(local_versions.get(&uuid), &entry.version)
+                {
                     continue;
                 }
             }
@@ -165,40 +203,56 @@ async fn download_collection(
     }
 
     if selected_entries.is_empty() {
-        return Ok(Vec::new());
+        return Ok((Vec::new(), state));
     }
 
-    let mut chunk_ids = selected_entries
-        .values()
-        .map(|entry| entry.chunk)
-        .collect::<HashSet<_>>()
-        .into_iter()
-        .collect::<Vec<_>>();
+    let mut out = Vec::new();
+    let mut chunk_ids = HashSet::new();
+    for (uuid, entry) in &selected_entries {
+        match entry.log_seq {
+            Some(_) => {
+                if let Some(logged) = logged.remove(uuid) {
+                    out.push(logged.record);
+                }
+            }
+            None => {
+                chunk_ids.insert(entry.chunk);
+            }
+        }
+    }
+    let mut chunk_ids = chunk_ids.into_iter().collect::<Vec<_>>();
     chunk_ids.sort_unstable();
 
-    let mut out = Vec::new();
+    let mut dangling = selected_entries.values().filter(|entry| entry.log_seq.is_none()).count();
     for chunk_id in chunk_ids {
         let chunk = load_chunk(client, collection, chunk_id).await?;
         for (uuid, record) in chunk.records {
             let Some(entry) = selected_entries.get(&uuid) else {
                 continue;
             };
-            if entry.chunk != chunk_id {
+            if entry.log_seq.is_some() || entry.chunk != chunk_id {
                 continue;
             }
+            dangling -= 1;
             if record.updated_at < entry.updated_at {
                 continue;
             }
-            if tombstone_states
-                .get(&crate::services::database::tombstone_state_key(collection_name, &record.uuid))
-                .map(|deleted_at| *deleted_at >= record.updated_at)
-                .unwrap_or(false)
-            {
-                continue;
-            }
             out.push(record);
         }
     }
+    // 索引指向的记录在分块中不存在，说明索引与分块不一致，重建索引并在下次同步时重新完整读取
+    if dangling > 0 {
+        state.cursor = 0;
+        if let Err(e) = super::index_manager::rebuild_index(client, collection).await {
+            eprintln!("[WebDAV同步] 重建索引失败 collection={} 错误={}", collection.dir(), e);
+        }
+    }
+    out.retain(|record| {
+        !tombstone_states
+            .get(&crate::services::database::tombstone_state_key(collection_name, &record.uuid))
+            .map(|deleted_at| *deleted_at >= record.updated_at)
+            .unwrap_or(false)
+    });
 
     out.sort_by(|a, b| {
         b.item_order
@@ -206,7 +260,7 @@ async fn download_collection(
             .then_with(|| b.updated_at.cmp(&a.updated_at))
             .then_with(|| a.uuid.cmp(&b.uuid))
     });
-    Ok(out)
+    Ok((out, state))
 }
 
 async fn download_images(client: &WebdavClient, image_ids: HashSet<String>) -> Result<(), String> {
@@ -226,7 +280,7 @@ async fn download_images(client: &WebdavClient, image_ids: HashSet<String>) -> R
         let Some(bytes) = client.get_bytes(&format!("files/{}.png", image_id)).await? else {
             continue;
         };
-        std::fs::write(path, bytes).map_err(|e| e.to_string())?;
+        crate::services::local_encryption::write_file(&path, &bytes)?;
     }
 
     Ok(())
@@ -1,48 +1,61 @@
 use std::collections::HashMap;
 
 use super::types::{CloudGroup, GroupList};
-use super::webdav_client::WebdavClient;
+use super::webdav_client::{WebdavClient, WriteCondition, MAX_WRITE_ATTEMPTS, WRITE_CONFLICT_ERROR};
 
 pub async fn upload_groups_with_tombstones(
     client: &WebdavClient,
     device_id: &str,
     tombstone_states: &HashMap<String, i64>,
 ) -> Result<Vec<CloudGroup>, String> {
-    let remote_list = client.get_json::<GroupList>("groups/groups.json").await?;
-    if remote_list.is_some() {
-        client.mark_dir_ensured("");
-        client.mark_dir_ensured("groups");
-    }
-    let mut remote = remote_list
-        .unwrap_or_default()
-        .groups
-        .into_iter()
-        .map(|group| (group.name.clone(), group))
-        .collect::<HashMap<_, _>>();
-    let mut changed = Vec::new();
+    // 其他设备同时写入时重新读取云端分组再合并
+    for _ in 0..MAX_WRITE_ATTEMPTS {
+        let remote_list = client.get_json_with_etag::<GroupList>("groups/groups.json").await?;
+        if remote_list.is_some() {
+            client.mark_dir_ensured("");
+            client.mark_dir_ensured("groups");
+        }
+        let condition = match &remote_list {
+            Some((_, etag)) => WriteCondition::for_existing(etag.clone()),
+            None => WriteCondition::Absent,
+        };
+        let mut remote = remote_list
+            .map(|(list, _)| list)
+            .unwrap_or_default()
+            .groups
+            .into_iter()
+            .map(|group| (group.name.clone(), group))
+            .collect::<HashMap<_, _>>();
+        let mut changed = Vec::new();
 
-    let local_groups = crate::services::database::webdav_list_groups(device_id)?;
-    let local_groups = crate::services::database::filter_groups_not_deleted_by_states(local_groups, tombstone_states);
-    for mut group in local_groups {
-        group.source_device_id = device_id.to_string();
-        match remote.get(&group.name) {
-            Some(existing) if existing.updated_at >= group.updated_at => {}
-            _ => {
-                remote.insert(group.name.clone(), group.clone());
-                changed.push(group);
+        let local_groups = crate::services::database::webdav_list_groups(device_id)?;
+        let local_groups = crate::services::database::filter_groups_not_deleted_by_states(local_groups, tombstone_states);
+        for mut group in local_groups {
+            group.source_device_id = device_id.to_string();
+            match remote.get(&group.name) {
+                Some(existing) if existing.updated_at >= group.updated_at => {}
+                _ => {
+                    remote.insert(group.name.clone(), group.clone());
+                    changed.push(group);
+                }
             }
         }
-    }
 
-    if changed.is_empty() {
-        return Ok(changed);
-    }
+        if changed.is_empty() {
+            return Ok(changed);
+        }
 
-    let mut groups = remote.into_values().collect::<Vec<_>>();
-    groups.sort_by_key(|g| (g.order, g.name.clone()));
-    client.ensure_groups_dir().await?;
-    client.put_json("groups/groups.json", &GroupList { groups }).await?;
-    Ok(changed)
+        let mut groups = remote.into_values().collect::<Vec<_>>();
+        groups.sort_by_key(|g| (g.order, g.name.clone()));
+        client.ensure_groups_dir().await?;
+        if client
+            .put_json_if("groups/groups.json", &GroupList { groups }, &condition)
+            .await?
+        {
+            return Ok(changed);
+        }
+    }
+    Err(WRITE_CONFLICT_ERROR.to_string())
 }
 
 pub async fn download_groups(
@@ -1,15 +1,49 @@
-use super::types::{SyncCollection, SyncIndex};
+use std::collections::HashMap;
+
+use super::chunk_manager::chunk_path;
+use super::op_log;
+use super::remote_lock;
+use super::types::{CloudRecord, RecordChunk, SyncCollection, SyncIndex, SyncIndexEntry};
 use super::webdav_client::WebdavClient;
 
-pub async fn load_index(client: &WebdavClient, collection: SyncCollection) -> Result<SyncIndex, String> {
-    let path = format!("{}/index.json", collection.dir());
-    let index = client.get_json(&path).await?;
-    if index.is_some() {
-        client.mark_dir_ensured("");
-        client.mark_dir_ensured(collection.dir());
-        client.mark_dir_ensured(&format!("{}/chunks", collection.dir()));
+fn index_path(collection: SyncCollection) -> String {
+    format!("{}/index.json", collection.dir())
+}
+
+// lock_held 为 true 表示调用方已持有云端租约，索引损坏时直接重建，不再重复加锁
+pub async fn load_index(
+    client: &WebdavClient,
+    collection: SyncCollection,
+    lock_held: bool,
+) -> Result<SyncIndex, String> {
+    let bytes = match client.get_bytes(&index_path(collection)).await {
+        Ok(Some(bytes)) => bytes,
+        Ok(None) => return Ok(SyncIndex::default()),
+        // 写了一半的加密索引连加密信封都无法解析
+        Err(e) if e.contains("不是 QuickClipboard 加密格式") => {
+            eprintln!("[WebDAV同步] 索引已损坏，从分块重建 collection={} 错误={}", collection.dir(), e);
+            return repair_index(client, collection, lock_held).await;
+        }
+        Err(e) => return Err(e),
+    };
+    client.mark_dir_ensured("");
+    client.mark_dir_ensured(collection.dir());
+    client.mark_dir_ensured(&format!("{}/chunks", collection.dir()));
+    match serde_json::from_slice(&bytes) {
+        Ok(index) => Ok(index),
+        Err(e) => {
+            eprintln!("[WebDAV同步] 索引已损坏，从分块重建 collection={} 错误={}", collection.dir(), e);
+            repair_index(client, collection, lock_held).await
+        }
+    }
+}
+
+async fn repair_index(client: &WebdavClient, collection: SyncCollection, lock_held: bool) -> Result<SyncIndex, String> {
+    if lock_held {
+        rebuild_index_locked(client, collection).await
+    } else {
+        rebuild_index(client, collection).await
     }
-    Ok(index.unwrap_or_default())
 }
 
 pub async fn save_index(
@@ -17,6 +51,127 @@ pub async fn save_index(
     collection: SyncCollection,
     index: &SyncIndex,
 ) -> Result<(), String> {
-    let path = format!("{}/index.json", collection.dir());
-    client.put_json(&path, index).await
+    client.commit_json(&index_path(collection), index).await
+}
+
+// 从分块重建索引：读取分块目录下的全部分块，同一条记录出现在多个分块时保留最新的
+pub async fn rebuild_index(client: &WebdavClient, collection: SyncCollection) -> Result<SyncIndex, String> {
+    let device_id = crate::services::sync_transfer::device_id();
+    let guard = remote_lock::acquire(client, collection, &device_id).await?;
+    let result = rebuild_index_locked(client, collection).await;
+    guard.release(client).await;
+    result
+}
+
+async fn rebuild_index_locked(client: &WebdavClient, collection: SyncCollection) -> Result<SyncIndex, String> {
+    let head = op_log::load_head(client, collection).await?;
+    let mut chunk_ids = client
+        .list(&format!("{}/chunks", collection.dir()))
+        .await?
+        .iter()
+        .filter_map(|name| name.strip_prefix("chunk_")?.strip_suffix(".json")?.parse::<u32>().ok())
+        .collect::<Vec<_>>();
+    chunk_ids.sort_unstable();
+
+    let mut newest: HashMap<String, (CloudRecord, u32)> = HashMap::new();
+    for &chunk_id in &chunk_ids {
+        let Some(bytes) = client.get_bytes(&chunk_path(collection, chunk_id)).await? else {
+            continue;
+        };
+        match serde_json::from_slice::<RecordChunk>(&bytes) {
+            Ok(chunk) => {
+                for record in chunk.records.into_values() {
+                    let replace = newest
+                        .get(&record.uuid)
+                        .map(|(existing, _)| op_log::supersedes(&record, existing))
+                        .unwrap_or(true);
+                    if replace {
+                        newest.insert(record.uuid.clone(), (record, chunk_id));
+                    }
+                }
+            }
+            Err(e) => eprintln!("[WebDAV同步] 跳过损坏的分块 {} 错误={}", chunk_id, e),
+        }
+    }
+    let next_chunk = chunk_ids.last().map(|id| id + 1).unwrap_or(0);
+
+    let entries = newest
+        .into_iter()
+        .map(|(uuid, (record, chunk))| {
+            let entry = SyncIndexEntry {
+                chunk,
+                updated_at: record.updated_at,
+                source_device_id: record.source_device_id,
+                version: record.version,
+                log_seq: None,
+            };
+            (uuid, entry)
+        })
+        .collect();
+    let index = SyncIndex {
+        entries,
+        next_chunk,
+        compacted_seq: head.compacted_seq,
+    };
+    save_index(client, collection, &index).await?;
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::services::webdav_sync::backend::folder::FolderBackend;
+    use crate::services::webdav_sync::chunk_manager::save_chunk;
+
+    fn folder_client() -> WebdavClient {
+        let dir = std::env::temp_dir().join(format!("qc_index_manager_{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        WebdavClient::new(Arc::new(FolderBackend::new(&dir.to_string_lossy(), "quickclipboard").unwrap()))
+    }
+
+    fn record(uuid: &str, text: &str, version: u64) -> CloudRecord {
+        serde_json::from_value(serde_json::json!({
+            "uuid": uuid,
+            "source_device_id": "a",
+            "content": text,
+            "content_type": "text",
+            "created_at": 1,
+            "updated_at": 1,
+            "version": { "a": version },
+        }))
+        .unwrap()
+    }
+
+    fn chunk(records: Vec<CloudRecord>) -> RecordChunk {
+        RecordChunk {
+            records: records.into_iter().map(|record| (record.uuid.clone(), record)).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn rebuilds_torn_index_from_chunks_under_held_lease() {
+        let client = folder_client();
+        let collection = SyncCollection::History;
+        client.ensure_collection_dirs(collection).await.unwrap();
+        save_chunk(&client, collection, 0, &chunk(vec![record("r1", "old", 1), record("r2", "b", 1)])).await.unwrap();
+        save_chunk(&client, collection, 1, &chunk(vec![record("r1", "new", 2)])).await.unwrap();
+        client.put_bytes(&index_path(collection), b"{\"entries\":{".to_vec()).await.unwrap();
+
+        let device_id = crate::services::sync_transfer::device_id();
+        let guard = remote_lock::acquire(&client, collection, &device_id).await.unwrap();
+        let lease = client.get_bytes(&format!("{}/lock.json", collection.dir())).await.unwrap();
+
+        let index = load_index(&client, collection, true).await.unwrap();
+        assert_eq!(index.entries["r1"].chunk, 1);
+        assert_eq!(index.entries["r2"].chunk, 0);
+        assert_eq!(index.next_chunk, 2);
+        // 已持有租约时不能重新加锁，否则内层释放会删掉外层的租约
+        assert_eq!(client.get_bytes(&format!("{}/lock.json", collection.dir())).await.unwrap(), lease);
+
+        guard.release(&client).await;
+        let reloaded = load_index(&client, collection, false).await.unwrap();
+        assert_eq!(reloaded.entries.len(), 2);
+    }
 }
@@ -1,7 +1,67 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
 use serde::{Deserialize, Serialize};
 
+use super::types::{SyncCollection, SyncIndexEntry};
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LocalSyncState {
     pub last_upload_at: i64,
     pub last_download_at: i64,
 }
+
+// 本机读取云端操作日志的进度，换了同步后端、地址或目录后从头同步
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocalLogState {
+    #[serde(default)]
+    pub remote: String,
+    // 序号小于此值的操作已应用到本地
+    #[serde(default)]
+    pub cursor: u64,
+    // 本机所知的云端记录状态
+    #[serde(default)]
+    pub entries: HashMap<String, SyncIndexEntry>,
+    // 本机追加但尚未越过 cursor 的批次，拉取时不必再下载
+    #[serde(default)]
+    pub own_seqs: Vec<u64>,
+}
+
+impl LocalLogState {
+    pub fn next_seq_hint(&self) -> u64 {
+        self.own_seqs
+            .iter()
+            .map(|seq| seq + 1)
+            .max()
+            .unwrap_or(0)
+            .max(self.cursor)
+    }
+}
+
+fn state_path(collection: SyncCollection) -> Result<PathBuf, String> {
+    Ok(crate::services::get_data_directory()?
+        .join("webdav_sync")
+        .join(format!("{}_log.json", collection.dir())))
+}
+
+pub fn load_log_state(collection: SyncCollection) -> Result<LocalLogState, String> {
+    let remote = super::remote_identity(&crate::services::get_settings());
+    let path = state_path(collection)?;
+    let state = match std::fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice::<LocalLogState>(&bytes).unwrap_or_default(),
+        Err(_) => LocalLogState::default(),
+    };
+    if state.remote != remote {
+        return Ok(LocalLogState { remote, ..Default::default() });
+    }
+    Ok(state)
+}
+
+pub fn save_log_state(collection: SyncCollection, state: &LocalLogState) -> Result<(), String> {
+    let path = state_path(collection)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建同步状态目录失败: {}", e))?;
+    }
+    let bytes = serde_json::to_vec(state).map_err(|e| format!("序列化同步状态失败: {}", e))?;
+    std::fs::write(&path, bytes).map_err(|e| format!("保存同步状态失败: {}", e))
+}
@@ -1,3 +1,4 @@
+pub mod backend;
 pub mod chunk_manager;
 pub mod cloud_files;
 pub mod crypto;
@@ -5,6 +6,8 @@ pub mod downloader;
 pub mod groups_sync;
 pub mod index_manager;
 pub mod local_state;
+pub mod op_log;
+pub mod remote_lock;
 pub mod sync_scheduler;
 pub mod tombstones_sync;
 pub mod types;
@@ -13,8 +16,12 @@ pub mod webdav_client;
 
 pub use types::{SyncReport, WebdavStatus};
 
+use std::sync::Arc;
+
+use backend::{StorageBackend, BACKEND_FOLDER, BACKEND_S3};
 use types::WebdavConfig;
 use webdav_client::WebdavClient;
+use crate::services::AppSettings;
 
 pub async fn test_connection() -> Result<(), String> {
     let client = build_client().await?;
@@ -99,37 +106,101 @@ pub fn notify_main_window_shown(app: tauri::AppHandle) {
     sync_scheduler::notify_main_window_shown(app);
 }
 
+// 凭据库中保存密码和加密密码所用的地址与账号，文件夹后端没有账号，以文件夹路径区分
+pub fn credential_target(settings: &AppSettings) -> (String, String) {
+    match backend::normalize_backend_kind(&settings.webdav_backend) {
+        BACKEND_FOLDER => (settings.webdav_folder_path.trim().to_string(), String::new()),
+        _ => (
+            settings.webdav_url.trim().to_string(),
+            settings.webdav_username.trim().to_string(),
+        ),
+    }
+}
+
+pub fn is_remote_configured(settings: &AppSettings) -> bool {
+    match backend::normalize_backend_kind(&settings.webdav_backend) {
+        BACKEND_S3 => !settings.webdav_url.trim().is_empty() && !settings.webdav_s3_bucket.trim().is_empty(),
+        BACKEND_FOLDER => !settings.webdav_folder_path.trim().is_empty(),
+        _ => !settings.webdav_url.trim().is_empty(),
+    }
+}
+
+// 同步目标的标识，换了后端、地址或目录后视为新的云端；WebDAV 沿用旧格式，升级后不必重新同步
+pub(crate) fn remote_identity(settings: &AppSettings) -> String {
+    let root_path = settings.webdav_root_path.trim();
+    match backend::normalize_backend_kind(&settings.webdav_backend) {
+        BACKEND_S3 => [
+            BACKEND_S3,
+            settings.webdav_url.trim().trim_end_matches('/'),
+            settings.webdav_s3_bucket.trim(),
+            settings.webdav_username.trim(),
+            root_path,
+        ]
+        .join("|"),
+        BACKEND_FOLDER => [BACKEND_FOLDER, settings.webdav_folder_path.trim(), root_path].join("|"),
+        _ => [
+            settings.webdav_url.trim().trim_end_matches('/'),
+            settings.webdav_username.trim(),
+            root_path,
+        ]
+        .join("|"),
+    }
+}
+
+fn build_backend(settings: &AppSettings, root_path: &str) -> Result<Arc<dyn StorageBackend>, String> {
+    let (url, username) = credential_target(settings);
+    match backend::normalize_backend_kind(&settings.webdav_backend) {
+        BACKEND_S3 => {
+            if username.is_empty() {
+                return Err("请先填写 S3 访问密钥 ID".to_string());
+            }
+            let secret_key = crate::services::secure_credentials::get_webdav_password(&url, &username)?
+                .ok_or_else(|| "请先在设置中保存 S3 访问密钥".to_string())?;
+            let config = backend::s3::S3Config {
+                endpoint: url,
+                region: settings.webdav_s3_region.trim().to_string(),
+                bucket: settings.webdav_s3_bucket.trim().to_string(),
+                access_key: username,
+                secret_key,
+                root_path: root_path.to_string(),
+            };
+            Ok(Arc::new(backend::s3::S3Backend::new(config)?))
+        }
+        BACKEND_FOLDER => Ok(Arc::new(backend::folder::FolderBackend::new(&url, root_path)?)),
+        _ => {
+            let password = if username.is_empty() {
+                String::new()
+            } else {
+                crate::services::secure_credentials::get_webdav_password(&url, &username)?
+                    .ok_or_else(|| "请先在设置中保存 WebDAV 密码".to_string())?
+            };
+            let config = WebdavConfig {
+                url,
+                username,
+                password,
+                root_path: root_path.to_string(),
+            };
+            Ok(Arc::new(backend::webdav::WebdavBackend::new(config)?))
+        }
+    }
+}
+
 async fn build_client() -> Result<WebdavClient, String> {
     let settings = crate::services::get_settings();
-    let webdav_url = settings.webdav_url.trim().to_string();
-    let webdav_username = settings.webdav_username.trim().to_string();
-    let webdav_root_path = if settings.webdav_root_path.trim().is_empty() {
+    let root_path = if settings.webdav_root_path.trim().is_empty() {
         "quickclipboard".to_string()
     } else {
         settings.webdav_root_path.clone()
     };
-    let password = if settings.webdav_username.trim().is_empty() {
-        String::new()
-    } else {
-        crate::services::secure_credentials::get_webdav_password(
-            &webdav_url,
-            &webdav_username,
-        )?
-        .ok_or_else(|| "请先在设置中保存 WebDAV 密码".to_string())?
-    };
+    let backend = build_backend(&settings, &root_path)?;
+    let (credential_url, credential_username) = credential_target(&settings);
     let encryption_password = crate::services::secure_credentials::get_webdav_encryption_password(
-        &webdav_url,
-        &webdav_username,
-        &webdav_root_path,
+        &credential_url,
+        &credential_username,
+        &root_path,
     )?
     .ok_or_else(|| "请先设置 WebDAV 云端加密密码".to_string())?;
-    let config = WebdavConfig {
-        url: webdav_url,
-        username: webdav_username,
-        password,
-        root_path: webdav_root_path,
-    };
-    let mut client = WebdavClient::new(config)?;
+    let mut client = WebdavClient::new(backend);
     client.enable_encryption(&encryption_password).await?;
     Ok(client)
 }
@@ -0,0 +1,234 @@
+// 云端操作日志
+//
+// 每次推送把改动的记录作为一个批次追加到 {集合}/log/op_{序号}.json，拉取时只读取本机游标之后的批次，
+// 不再整块重写分块和索引。批次累计到 LOG_COMPACT_THRESHOLD 个后由推送方合并进分块和索引，
+// 并在 log/head.json 记录合并位置，随后删除已合并的批次。合并在云端租约内进行，索引和合并位置整体替换写入。
+
+use std::collections::HashMap;
+
+use super::chunk_manager::store_records;
+use super::index_manager::{load_index, save_index};
+use super::remote_lock;
+use super::types::{CloudRecord, SyncCollection, SyncIndex, SyncIndexEntry, SyncLogBatch, SyncLogHead, LOG_COMPACT_THRESHOLD};
+use super::webdav_client::{WebdavClient, WriteCondition};
+use crate::services::sync_transfer::merge::{compare_versions, VersionOrder};
+
+// 日志中某条记录的最新状态
+#[derive(Debug, Clone)]
+pub struct LoggedRecord {
+    pub record: CloudRecord,
+    pub device_id: String,
+    pub seq: u64,
+}
+
+impl LoggedRecord {
+    pub fn entry(&self) -> SyncIndexEntry {
+        SyncIndexEntry {
+            chunk: 0,
+            updated_at: self.record.updated_at,
+            source_device_id: self.device_id.clone(),
+            version: self.record.version.clone(),
+            log_seq: Some(self.seq),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LogChanges {
+    pub records: HashMap<String, LoggedRecord>,
+    // 下一个待读取的序号
+    pub end_seq: u64,
+}
+
+pub fn op_path(collection: SyncCollection, seq: u64) -> String {
+    format!("{}/log/op_{:08}.json", collection.dir(), seq)
+}
+
+fn head_path(collection: SyncCollection) -> String {
+    format!("{}/log/head.json", collection.dir())
+}
+
+pub async fn load_head(client: &WebdavClient, collection: SyncCollection) -> Result<SyncLogHead, String> {
+    let head = client.get_json(&head_path(collection)).await?;
+    if head.is_some() {
+        client.mark_dir_ensured(&format!("{}/log", collection.dir()));
+    }
+    Ok(head.unwrap_or_default())
+}
+
+// 从 from_seq 起顺序读取批次，遇到不存在的序号即停止；skip 中的批次是本机写入的，跳过不下载
+pub async fn read_changes(
+    client: &WebdavClient,
+    collection: SyncCollection,
+    from_seq: u64,
+    skip: &[u64],
+) -> Result<LogChanges, String> {
+    let mut changes = LogChanges {
+        records: HashMap::new(),
+        end_seq: from_seq,
+    };
+    loop {
+        let seq = changes.end_seq;
+        if !skip.contains(&seq) {
+            let Some(batch) = client.get_json::<SyncLogBatch>(&op_path(collection, seq)).await? else {
+                break;
+            };
+            for record in batch.records {
+                fold_record(&mut changes.records, LoggedRecord {
+                    record,
+                    device_id: batch.device_id.clone(),
+                    seq,
+                });
+            }
+        }
+        changes.end_seq = seq + 1;
+    }
+    Ok(changes)
+}
+
+// 读取完整的云端状态：已合并的索引加上尚未合并的日志
+pub async fn load_full_view(
+    client: &WebdavClient,
+    collection: SyncCollection,
+) -> Result<(SyncIndex, LogChanges), String> {
+    let head = load_head(client, collection).await?;
+    let mut index = load_index(client, collection, false).await?;
+    let start = head.compacted_seq.max(index.compacted_seq);
+    let changes = read_changes(client, collection, start, &[]).await?;
+    for (uuid, logged) in &changes.records {
+        index.entries.insert(uuid.clone(), logged.entry());
+    }
+    Ok((index, changes))
+}
+
+// 追加一个批次，序号被占用时顺延，返回实际写入的序号
+// 以“文件不存在才写入”的条件请求占用序号，多台设备同时推送时不会互相覆盖
+pub async fn append_batch(
+    client: &WebdavClient,
+    collection: SyncCollection,
+    from_seq: u64,
+    records: Vec<CloudRecord>,
+    device_id: &str,
+) -> Result<u64, String> {
+    client.ensure_collection_dirs(collection).await?;
+    let mut batch = SyncLogBatch {
+        seq: from_seq,
+        device_id: device_id.to_string(),
+        created_at: chrono::Utc::now().timestamp_millis(),
+        records,
+    };
+    loop {
+        while client.exists(&op_path(collection, batch.seq)).await? {
+            batch.seq += 1;
+        }
+        if !client
+            .put_json_if(&op_path(collection, batch.seq), &batch, &WriteCondition::Absent)
+            .await?
+        {
+            batch.seq += 1;
+            continue;
+        }
+
+        // 写入期间其他设备完成了合并，序号落在合并范围内的批次不会再被读取，改到合并位置之后重写
+        let head = load_head(client, collection).await?;
+        if batch.seq >= head.compacted_seq {
+            return Ok(batch.seq);
+        }
+        client.delete_path(&op_path(collection, batch.seq)).await?;
+        batch.seq = head.compacted_seq;
+    }
+}
+
+pub fn needs_compaction(head: &SyncLogHead, last_seq: u64) -> bool {
+    (last_seq + 1).saturating_sub(head.compacted_seq) >= LOG_COMPACT_THRESHOLD
+}
+
+// 把尚未合并的批次写入分块和索引，先提交索引和合并位置，再删除已合并的批次
+pub async fn compact(client: &WebdavClient, collection: SyncCollection, device_id: &str) -> Result<(), String> {
+    let guard = remote_lock::acquire(client, collection, device_id).await?;
+    let result = compact_locked(client, collection).await;
+    guard.release(client).await;
+    result
+}
+
+async fn compact_locked(client: &WebdavClient, collection: SyncCollection) -> Result<(), String> {
+    let head = load_head(client, collection).await?;
+    let mut index = load_index(client, collection, true).await?;
+    let start = head.compacted_seq.max(index.compacted_seq);
+    let changes = read_changes(client, collection, start, &[]).await?;
+    if changes.end_seq == start {
+        return Ok(());
+    }
+
+    let records = changes
+        .records
+        .into_values()
+        .map(|logged| (logged.record, logged.device_id))
+        .collect();
+    store_records(client, collection, &mut index, records).await?;
+    index.compacted_seq = changes.end_seq;
+    save_index(client, collection, &index).await?;
+    client
+        .commit_json(&head_path(collection), &SyncLogHead { compacted_seq: changes.end_seq })
+        .await?;
+
+    for seq in start..changes.end_seq {
+        client.delete_path(&op_path(collection, seq)).await?;
+    }
+    Ok(())
+}
+
+// 同一条记录出现多次时保留最新的：版本更新的优先，并发修改时以后写入的为准，交给拉取方合并
+fn fold_record(records: &mut HashMap<String, LoggedRecord>, logged: LoggedRecord) {
+    let replace = match records.get(&logged.record.uuid) {
+        Some(existing) => supersedes(&logged.record, &existing.record),
+        None => true,
+    };
+    if replace {
+        records.insert(logged.record.uuid.clone(), logged);
+    }
+}
+
+pub(super) fn supersedes(new: &CloudRecord, old: &CloudRecord) -> bool {
+    if new.version.is_empty() || old.version.is_empty() {
+        return new.updated_at >= old.updated_at;
+    }
+    compare_versions(&new.version, &old.version) != VersionOrder::Older
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logged(version: &[(&str, u64)], updated_at: i64, seq: u64) -> LoggedRecord {
+        let record = serde_json::from_value(serde_json::json!({
+            "uuid": "r1",
+            "source_device_id": "a",
+            "content": format!("seq {}", seq),
+            "content_type": "text",
+            "created_at": 1,
+            "updated_at": updated_at,
+            "version": version.iter().map(|(d, c)| (d.to_string(), *c)).collect::<HashMap<_, _>>(),
+        }))
+        .unwrap();
+        LoggedRecord { record, device_id: "a".to_string(), seq }
+    }
+
+    #[test]
+    fn folds_to_latest_version() {
+        let mut records = HashMap::new();
+        fold_record(&mut records, logged(&[("a", 2)], 20, 0));
+        // 版本落后的旧批次不覆盖
+        fold_record(&mut records, logged(&[("a", 1)], 30, 1));
+        assert_eq!(records["r1"].seq, 0);
+        // 并发修改取后写入的
+        fold_record(&mut records, logged(&[("a", 1), ("b", 1)], 10, 2));
+        assert_eq!(records["r1"].seq, 2);
+        // 没有版本时按更新时间
+        fold_record(&mut records, logged(&[], 5, 3));
+        assert_eq!(records["r1"].seq, 2);
+
+        assert!(!needs_compaction(&SyncLogHead { compacted_seq: 10 }, 10));
+        assert!(needs_compaction(&SyncLogHead { compacted_seq: 0 }, LOG_COMPACT_THRESHOLD - 1));
+    }
+}
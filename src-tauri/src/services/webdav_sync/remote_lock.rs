@@ -0,0 +1,125 @@
+// 云端写入租约
+//
+// 合并操作日志、重建索引等会改写多个文件的操作需要先在集合目录下取得 lock.json 租约，
+// 同一时间只有一台设备执行；持有者异常退出时租约到期后可被其他设备接管。
+
+use serde::{Deserialize, Serialize};
+
+use super::types::SyncCollection;
+use super::webdav_client::{WebdavClient, WriteCondition};
+
+const LEASE_MILLIS: i64 = 2 * 60 * 1000;
+pub const LOCK_BUSY_ERROR: &str = "其他设备正在整理云端数据，请稍后重试";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RemoteLease {
+    owner: String,
+    token: String,
+    expires_at: i64,
+}
+
+pub struct LeaseGuard {
+    path: String,
+    token: String,
+}
+
+fn lock_path(collection: SyncCollection) -> String {
+    format!("{}/lock.json", collection.dir())
+}
+
+pub async fn acquire(
+    client: &WebdavClient,
+    collection: SyncCollection,
+    device_id: &str,
+) -> Result<LeaseGuard, String> {
+    let path = lock_path(collection);
+    let now = chrono::Utc::now().timestamp_millis();
+    let current = client.get_json_with_etag::<RemoteLease>(&path).await?;
+    if let Some((lease, _)) = &current {
+        if lease.expires_at > now && lease.owner != device_id {
+            return Err(LOCK_BUSY_ERROR.to_string());
+        }
+    }
+    let condition = match current {
+        Some((_, etag)) => WriteCondition::for_existing(etag),
+        None => WriteCondition::Absent,
+    };
+
+    let lease = RemoteLease {
+        owner: device_id.to_string(),
+        token: uuid::Uuid::new_v4().to_string(),
+        expires_at: now + LEASE_MILLIS,
+    };
+    client.ensure_collection_dirs(collection).await?;
+    if !client.put_json_if(&path, &lease, &condition).await? {
+        return Err(LOCK_BUSY_ERROR.to_string());
+    }
+
+    // 服务器忽略条件请求时，以写入后读回的内容为准
+    match client.get_json::<RemoteLease>(&path).await? {
+        Some(written) if written.token == lease.token => Ok(LeaseGuard { path, token: lease.token }),
+        _ => Err(LOCK_BUSY_ERROR.to_string()),
+    }
+}
+
+impl LeaseGuard {
+    // 只删除自己持有的租约，已被接管时保留
+    pub async fn release(self, client: &WebdavClient) {
+        let Ok(Some((lease, etag))) = client.get_json_with_etag::<RemoteLease>(&self.path).await else {
+            return;
+        };
+        if lease.token == self.token {
+            let _ = client
+                .delete_path_if(&self.path, &WriteCondition::for_existing(etag))
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::services::webdav_sync::backend::folder::FolderBackend;
+
+    fn folder_client() -> WebdavClient {
+        let dir = std::env::temp_dir().join(format!("qc_remote_lock_{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        WebdavClient::new(Arc::new(FolderBackend::new(&dir.to_string_lossy(), "quickclipboard").unwrap()))
+    }
+
+    #[tokio::test]
+    async fn lease_is_exclusive_until_released() {
+        let client = folder_client();
+        let guard = acquire(&client, SyncCollection::History, "a").await.unwrap();
+        let busy = acquire(&client, SyncCollection::History, "b").await;
+        assert_eq!(busy.err().as_deref(), Some(LOCK_BUSY_ERROR));
+
+        guard.release(&client).await;
+        assert!(!client.exists(&lock_path(SyncCollection::History)).await.unwrap());
+        acquire(&client, SyncCollection::History, "b").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn expired_lease_is_taken_over() {
+        let client = folder_client();
+        let path = lock_path(SyncCollection::History);
+        client.ensure_collection_dirs(SyncCollection::History).await.unwrap();
+        let expired = RemoteLease {
+            owner: "a".to_string(),
+            token: "stale".to_string(),
+            expires_at: chrono::Utc::now().timestamp_millis() - 1,
+        };
+        client.put_json(&path, &expired).await.unwrap();
+
+        let guard = acquire(&client, SyncCollection::History, "b").await.unwrap();
+        // 原持有者恢复后释放，不能删掉已被接管的租约
+        LeaseGuard { path: path.clone(), token: expired.token }.release(&client).await;
+        let current = client.get_json::<RemoteLease>(&path).await.unwrap().unwrap();
+        assert_eq!(current.owner, "b");
+
+        guard.release(&client).await;
+        assert!(!client.exists(&path).await.unwrap());
+    }
+}
@@ -118,7 +118,7 @@ pub fn notify_main_window_shown(app: AppHandle) {
         return;
     }
 
-    if settings.webdav_url.trim().is_empty() {
+    if !super::is_remote_configured(&settings) {
         return;
     }
 
@@ -154,7 +154,7 @@ pub fn status() -> WebdavStatus {
     let settings = crate::services::get_settings();
     WebdavStatus {
         enabled: settings.webdav_enabled,
-        configured: !settings.webdav_url.trim().is_empty(),
+        configured: super::is_remote_configured(&settings),
         auto_push: settings.webdav_auto_push,
         auto_pull: settings.webdav_auto_pull,
         running: is_running(),
@@ -277,13 +277,11 @@ fn store_uploaded_signature(signature: &WebdavLocalSyncSignature) {
 
 fn last_uploaded_signature_key() -> String {
     let settings = crate::services::get_settings();
-    [
+    format!(
+        "{}|{}",
         LAST_UPLOADED_SIGNATURE_KEY_PREFIX,
-        settings.webdav_url.trim().trim_end_matches('/'),
-        settings.webdav_username.trim(),
-        settings.webdav_root_path.trim(),
-    ]
-    .join("|")
+        super::remote_identity(&settings)
+    )
 }
 
 fn store_report(mode: &'static str, result: SyncReport, automatic: bool) {
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use super::types::{SyncReport, TombstoneList};
-use super::webdav_client::WebdavClient;
+use super::webdav_client::{WebdavClient, WriteCondition, MAX_WRITE_ATTEMPTS, WRITE_CONFLICT_ERROR};
 
 const TOMBSTONES_PATH: &str = "tombstones/tombstones.json";
 
@@ -11,36 +11,40 @@ pub struct WebdavTombstoneUploadResult {
 }
 
 pub async fn upload_tombstones(client: &WebdavClient) -> Result<WebdavTombstoneUploadResult, String> {
-    let mut remote = load_remote_tombstones(client).await?;
-    let remote_tombstones = remote.values().cloned().collect::<Vec<_>>();
-    let _ = crate::services::database::upsert_sync_tombstones(&remote_tombstones)?;
-    let applied = crate::services::database::apply_sync_tombstones(&remote_tombstones)?;
+    // 其他设备同时写入时重新读取云端记录再合并
+    for _ in 0..MAX_WRITE_ATTEMPTS {
+        let (mut remote, condition) = load_remote_tombstones_for_write(client).await?;
+        let remote_tombstones = remote.values().cloned().collect::<Vec<_>>();
+        let _ = crate::services::database::upsert_sync_tombstones(&remote_tombstones)?;
+        let applied = crate::services::database::apply_sync_tombstones(&remote_tombstones)?;
 
-    let mut changed = Vec::new();
-    let local = crate::services::database::list_sync_tombstones_since(None)?;
-    for tombstone in local {
-        let key = crate::services::database::tombstone_state_key(&tombstone.collection, &tombstone.item_id);
-        let needs_upload = remote
-            .get(&key)
-            .map(|existing| existing.deleted_at < tombstone.deleted_at)
-            .unwrap_or(true);
-        if needs_upload {
-            remote.insert(key, tombstone.clone());
-            changed.push(tombstone);
+        let mut changed = Vec::new();
+        let local = crate::services::database::list_sync_tombstones_since(None)?;
+        for tombstone in local {
+            let key = crate::services::database::tombstone_state_key(&tombstone.collection, &tombstone.item_id);
+            let needs_upload = remote
+                .get(&key)
+                .map(|existing| existing.deleted_at < tombstone.deleted_at)
+                .unwrap_or(true);
+            if needs_upload {
+                remote.insert(key, tombstone.clone());
+                changed.push(tombstone);
+            }
         }
-    }
 
-    if !changed.is_empty() {
-        save_remote_tombstones(client, &remote).await?;
+        if !changed.is_empty() && !save_remote_tombstones(client, &remote, &condition).await? {
+            continue;
+        }
+        let states = remote
+            .iter()
+            .map(|(key, tombstone)| (key.clone(), tombstone.deleted_at))
+            .collect();
+        return Ok(WebdavTombstoneUploadResult {
+            states,
+            applied,
+        });
     }
-    let states = remote
-        .iter()
-        .map(|(key, tombstone)| (key.clone(), tombstone.deleted_at))
-        .collect();
-    Ok(WebdavTombstoneUploadResult {
-        states,
-        applied,
-    })
+    Err(WRITE_CONFLICT_ERROR.to_string())
 }
 
 pub async fn download_tombstones(client: &WebdavClient) -> Result<SyncReport, String> {
@@ -71,15 +75,21 @@ pub async fn remote_tombstone_states(client: &WebdavClient) -> Result<HashMap<St
 async fn load_remote_tombstones(
     client: &WebdavClient,
 ) -> Result<HashMap<String, crate::services::database::SyncTombstone>, String> {
-    let remote = client.get_json::<TombstoneList>(TOMBSTONES_PATH).await?;
+    Ok(load_remote_tombstones_for_write(client).await?.0)
+}
+
+async fn load_remote_tombstones_for_write(
+    client: &WebdavClient,
+) -> Result<(HashMap<String, crate::services::database::SyncTombstone>, WriteCondition), String> {
+    let remote = client.get_json_with_etag::<TombstoneList>(TOMBSTONES_PATH).await?;
     if remote.is_some() {
         client.mark_dir_ensured("");
         client.mark_dir_ensured("tombstones");
     }
-    let Some(remote) = remote else {
-        return Ok(HashMap::new());
+    let Some((remote, etag)) = remote else {
+        return Ok((HashMap::new(), WriteCondition::Absent));
     };
-    Ok(remote
+    let tombstones = remote
         .tombstones
         .into_iter()
         .filter(|tombstone| !tombstone.collection.trim().is_empty() && !tombstone.item_id.trim().is_empty())
@@ -89,13 +99,15 @@ async fn load_remote_tombstones(
                 tombstone,
             )
         })
-        .collect())
+        .collect();
+    Ok((tombstones, WriteCondition::for_existing(etag)))
 }
 
 async fn save_remote_tombstones(
     client: &WebdavClient,
     tombstones: &HashMap<String, crate::services::database::SyncTombstone>,
-) -> Result<(), String> {
+    condition: &WriteCondition,
+) -> Result<bool, String> {
     let mut tombstones = tombstones.values().cloned().collect::<Vec<_>>();
     tombstones.sort_by(|a, b| {
         a.collection
@@ -105,6 +117,6 @@ async fn save_remote_tombstones(
     });
     client.ensure_tombstones_dir().await?;
     client
-        .put_json(TOMBSTONES_PATH, &TombstoneList { tombstones })
+        .put_json_if(TOMBSTONES_PATH, &TombstoneList { tombstones }, condition)
         .await
 }
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 pub const CHUNK_RECORD_LIMIT: usize = 500;
+// 操作日志累计到这个批次数后合并进分块
+pub const LOG_COMPACT_THRESHOLD: u64 = 64;
 
 #[derive(Debug, Clone)]
 pub struct WebdavConfig {
@@ -33,6 +35,8 @@ pub struct SyncReport {
     pub pulled_groups: u32,
     pub pushed_items: Vec<SyncReportItem>,
     pub pulled_items: Vec<SyncReportItem>,
+    #[serde(default)]
+    pub conflicts: Vec<SyncConflictItem>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,6 +48,21 @@ pub struct SyncReportItem {
     pub updated_at: i64,
 }
 
+pub const CONFLICT_MERGED: &str = "merged";
+pub const CONFLICT_COPIED: &str = "copied";
+
+// 双方同时修改的条目：merged 为自动合并，copied 为无法合并时另存的冲突副本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncConflictItem {
+    pub category: String,
+    pub id: String,
+    pub summary: String,
+    pub resolution: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub copy_id: Option<String>,
+    pub source_device_id: String,
+}
+
 impl CloudRecord {
     pub fn report_item(&self, category: &str) -> SyncReportItem {
         SyncReportItem {
@@ -54,8 +73,22 @@ impl CloudRecord {
             updated_at: self.updated_at,
         }
     }
+
+    pub fn conflict_item(&self, category: &str, resolution: &str, copy_id: Option<String>) -> SyncConflictItem {
+        SyncConflictItem {
+            category: category.to_string(),
+            id: self.uuid.clone(),
+            summary: summarize_record(self),
+            resolution: resolution.to_string(),
+            copy_id,
+            source_device_id: self.source_device_id.clone(),
+        }
+    }
 }
 
+// 每台设备对条目内容的修改次数，用于区分先后修改和并发修改
+pub type VersionVector = BTreeMap<String, u64>;
+
 fn summarize_record(record: &CloudRecord) -> String {
     let raw = if !record.title.trim().is_empty() {
         record.title.trim()
@@ -74,6 +107,9 @@ fn summarize_record(record: &CloudRecord) -> String {
 pub struct SyncIndex {
     pub entries: HashMap<String, SyncIndexEntry>,
     pub next_chunk: u32,
+    // 序号小于此值的操作日志已合并进分块
+    #[serde(default)]
+    pub compacted_seq: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -81,6 +117,25 @@ pub struct SyncIndexEntry {
     pub chunk: u32,
     pub updated_at: i64,
     pub source_device_id: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub version: VersionVector,
+    // 记录仍在操作日志中时为所在批次序号，合并进分块后为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_seq: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncLogHead {
+    pub compacted_seq: u64,
+}
+
+// 一次推送追加的一批记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncLogBatch {
+    pub seq: u64,
+    pub device_id: String,
+    pub created_at: i64,
+    pub records: Vec<CloudRecord>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -116,6 +171,8 @@ pub struct CloudRecord {
     pub paste_count: i64,
     pub created_at: i64,
     pub updated_at: i64,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub version: VersionVector,
 }
 
 #[derive(Debug, Clone)]
@@ -123,6 +180,10 @@ pub struct CloudRecordMeta {
     pub uuid: String,
     pub updated_at: i64,
     pub image_id: Option<String>,
+    pub source_device_id: Option<String>,
+    pub is_remote: bool,
+    // 只有收藏有分组
+    pub group_name: Option<String>,
 }
 
 fn default_group_name() -> String {
@@ -1,9 +1,10 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{HashMap, HashSet};
 
-use super::chunk_manager::{load_chunk, save_chunk};
-use super::index_manager::{load_index, save_index};
-use super::types::{CloudRecord, CloudRecordMeta, ImageFileIndex, ImageFileIndexEntry, RecordChunk, SyncCollection, SyncIndexEntry, SyncReport, CHUNK_RECORD_LIMIT};
+use super::local_state::{load_log_state, save_log_state, LocalLogState};
+use super::op_log;
+use super::types::{CloudRecord, CloudRecordMeta, ImageFileIndex, ImageFileIndexEntry, SyncCollection, SyncIndexEntry, SyncReport};
 use super::webdav_client::WebdavClient;
+use crate::services::sync_transfer::sync_plan::has_unseen_changes;
 
 pub async fn upload_all(client: &WebdavClient, device_id: &str) -> Result<SyncReport, String> {
     upload_parts(client, device_id, true, true, true, true).await
@@ -40,36 +41,36 @@ pub async fn upload_parts(
     };
 
     let history_records = if settings.webdav_sync_clipboard && upload_clipboard {
-        let index = load_index(client, SyncCollection::History).await?;
+        let state = load_remote_state(client, SyncCollection::History).await?;
         let metas = crate::services::database::webdav_list_history_record_metas()?;
         let metas = crate::services::database::filter_record_metas_not_deleted_by_states(
             crate::services::database::COLLECTION_HISTORY,
             metas,
             &tombstone_states,
         );
-        let metas = metas_newer_than_index(metas, &index.entries);
+        let metas = metas_newer_than_index(metas, &state.entries);
         let records = load_history_records(&metas, device_id)?;
-        Some((index, records))
+        Some((state, records))
     } else {
         None
     };
     let favorite_records = if settings.webdav_sync_favorites && upload_favorites {
-        let index = load_index(client, SyncCollection::Favorites).await?;
+        let state = load_remote_state(client, SyncCollection::Favorites).await?;
         let metas = crate::services::database::webdav_list_favorite_record_metas()?;
         let metas = crate::services::database::filter_record_metas_not_deleted_by_states(
             crate::services::database::COLLECTION_FAVORITES,
             metas,
             &tombstone_states,
         );
-        let metas = metas_newer_than_index(metas, &index.entries);
+        let metas = metas_newer_than_index(metas, &state.entries);
         let records = load_favorite_records(&metas, device_id)?;
-        Some((index, records))
+        Some((state, records))
     } else {
         None
     };
 
-    if let Some((index, history_records)) = history_records {
-        match upload_collection_incremental(client, SyncCollection::History, index, history_records.clone(), device_id).await {
+    if let Some((state, history_records)) = history_records {
+        match upload_collection_incremental(client, SyncCollection::History, state, history_records, device_id).await {
             Ok(records) => {
                 let count = records.len() as u32;
                 report.pushed += count;
@@ -84,8 +85,8 @@ pub async fn upload_parts(
     }
 
     if settings.webdav_sync_favorites && (upload_favorites || upload_groups) {
-        if let Some((index, favorite_records)) = favorite_records {
-            match upload_collection_incremental(client, SyncCollection::Favorites, index, favorite_records.clone(), device_id).await {
+        if let Some((state, favorite_records)) = favorite_records {
+            match upload_collection_incremental(client, SyncCollection::Favorites, state, favorite_records, device_id).await {
                 Ok(records) => {
                     let count = records.len() as u32;
                     report.pushed += count;
@@ -132,130 +133,68 @@ pub async fn upload_parts(
 async fn upload_collection_incremental(
     client: &WebdavClient,
     collection: SyncCollection,
-    mut index: super::types::SyncIndex,
+    mut state: LocalLogState,
     records: Vec<CloudRecord>,
     device_id: &str,
 ) -> Result<Vec<CloudRecord>, String> {
     let mut changed = Vec::new();
-    let mut existing_by_chunk: HashMap<u32, Vec<CloudRecord>> = HashMap::new();
-    let mut new_records = Vec::new();
-
     for record in records {
-        let needs_upload = match index.entries.get(&record.uuid) {
-            Some(entry) => entry.updated_at < record.updated_at,
+        // 远端有尚未合并的修改时先不覆盖，等下次拉取合并后再推送
+        let needs_upload = match state.entries.get(&record.uuid) {
+            Some(entry) => {
+                entry.updated_at < record.updated_at
+                    && !has_unseen_changes(Some(&record.version), &entry.version)
+            }
             None => true,
         };
-
-        if !needs_upload {
-            continue;
+        if needs_upload {
+            changed.push(record);
         }
-
-        if let Some(entry) = index.entries.get(&record.uuid) {
-            existing_by_chunk.entry(entry.chunk).or_default().push(record.clone());
-        } else {
-            new_records.push(record.clone());
-        }
-        changed.push(record);
     }
 
-    let mut new_records_by_chunk = Vec::<(u32, Vec<CloudRecord>)>::new();
-    let mut chunk_counts = chunk_record_counts(&index.entries);
-    let fillable_chunk_ids = chunk_counts
-        .iter()
-        .filter_map(|(chunk_id, count)| {
-            if *count < CHUNK_RECORD_LIMIT {
-                Some(*chunk_id)
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<_>>();
-    let mut fillable_index = 0usize;
-    let next_available_chunk = chunk_counts
-        .keys()
-        .next_back()
-        .copied()
-        .map(|chunk_id| chunk_id.saturating_add(1))
-        .unwrap_or(0);
-    let mut current_chunk_id = index.next_chunk.max(next_available_chunk);
-    let mut current_chunk_records = Vec::new();
-
-    for record in new_records {
-        let fillable_chunk_id = loop {
-            let Some(chunk_id) = fillable_chunk_ids.get(fillable_index).copied() else {
-                break None;
-            };
-            let count = chunk_counts.get(&chunk_id).copied().unwrap_or(0);
-            if count < CHUNK_RECORD_LIMIT {
-                break Some(chunk_id);
-            }
-            fillable_index += 1;
-        };
-
-        if let Some(chunk_id) = fillable_chunk_id {
-            existing_by_chunk.entry(chunk_id).or_default().push(record);
-            *chunk_counts.entry(chunk_id).or_default() += 1;
-            continue;
-        }
-
-        current_chunk_records.push(record);
-        if current_chunk_records.len() >= CHUNK_RECORD_LIMIT {
-            let count = current_chunk_records.len();
-            new_records_by_chunk.push((current_chunk_id, current_chunk_records));
-            chunk_counts.insert(current_chunk_id, count);
-            current_chunk_id = current_chunk_id.saturating_add(1);
-            current_chunk_records = Vec::new();
-        }
-    }
-    if !current_chunk_records.is_empty() {
-        let count = current_chunk_records.len();
-        new_records_by_chunk.push((current_chunk_id, current_chunk_records));
-        chunk_counts.insert(current_chunk_id, count);
-        current_chunk_id = current_chunk_id.saturating_add(1);
+    if changed.is_empty() {
+        return Ok(changed);
     }
 
-    if !existing_by_chunk.is_empty() || !new_records_by_chunk.is_empty() {
-        client.ensure_collection_dirs(collection).await?;
+    let head = op_log::load_head(client, collection).await?;
+    let from_seq = state.next_seq_hint().max(head.compacted_seq);
+    let seq = op_log::append_batch(client, collection, from_seq, changed.clone(), device_id).await?;
+    for record in &changed {
+        state.entries.insert(
+            record.uuid.clone(),
+            SyncIndexEntry {
+                chunk: 0,
+                updated_at: record.updated_at,
+                source_device_id: device_id.to_string(),
+                version: record.version.clone(),
+                log_seq: Some(seq),
+            },
+        );
     }
-
-    for (chunk_id, records) in existing_by_chunk {
-        let mut chunk = load_chunk(client, collection, chunk_id).await?;
-        for record in records {
-            chunk.records.insert(record.uuid.clone(), record.clone());
-            index.entries.insert(
-                record.uuid.clone(),
-                SyncIndexEntry {
-                    chunk: chunk_id,
-                    updated_at: record.updated_at,
-                    source_device_id: device_id.to_string(),
-                },
-            );
+    state.own_seqs.push(seq);
+    save_log_state(collection, &state)?;
+    crate::services::database::record_sync_bases(collection.dir(), &changed)?;
+
+    if op_log::needs_compaction(&head, seq) {
+        match op_log::compact(client, collection, device_id).await {
+            Ok(()) => {}
+            // 其他设备正在合并，由它完成即可
+            Err(e) if e == super::remote_lock::LOCK_BUSY_ERROR => {}
+            Err(e) => eprintln!("[WebDAV同步] 合并操作日志失败 collection={} 错误={}", collection.dir(), e),
         }
-        save_chunk(client, collection, chunk_id, &chunk).await?;
     }
 
-    for (chunk_id, records) in new_records_by_chunk {
-        let mut chunk = RecordChunk::default();
-        for record in records {
-            chunk.records.insert(record.uuid.clone(), record.clone());
-            index.entries.insert(
-                record.uuid.clone(),
-                SyncIndexEntry {
-                    chunk: chunk_id,
-                    updated_at: record.updated_at,
-                    source_device_id: device_id.to_string(),
-                },
-            );
-        }
-        save_chunk(client, collection, chunk_id, &chunk).await?;
-    }
-    index.next_chunk = current_chunk_id;
+    Ok(changed)
+}
 
-    if !changed.is_empty() {
-        save_index(client, collection, &index).await?;
+// 本机所知的云端记录状态，首次同步时读取完整的索引和日志
+async fn load_remote_state(client: &WebdavClient, collection: SyncCollection) -> Result<LocalLogState, String> {
+    let mut state = load_log_state(collection)?;
+    if state.cursor == 0 && state.entries.is_empty() {
+        let (index, _) = op_log::load_full_view(client, collection).await?;
+        state.entries = index.entries;
     }
-
-    Ok(changed)
+    Ok(state)
 }
 
 fn metas_newer_than_index(
@@ -299,14 +238,6 @@ fn load_favorite_records(
     Ok(records)
 }
 
-fn chunk_record_counts(index_entries: &HashMap<String, SyncIndexEntry>) -> BTreeMap<u32, usize> {
-    let mut counts = BTreeMap::new();
-    for entry in index_entries.values() {
-        *counts.entry(entry.chunk).or_default() += 1;
-    }
-    counts
-}
-
 async fn upload_images(client: &WebdavClient, records: &[CloudRecord]) -> Result<(), String> {
     let mut image_ids = HashSet::new();
     for record in records {
@@ -327,7 +258,7 @@ async fn upload_images(client: &WebdavClient, records: &[CloudRecord]) -> Result
             continue;
         }
         let path = images_dir.join(format!("{}.png", image_id));
-        let Ok(bytes) = std::fs::read(path) else {
+        let Ok(bytes) = crate::services::local_encryption::read_file(&path) else {
             continue;
         };
         if !changed {
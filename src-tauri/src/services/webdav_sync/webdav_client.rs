@@ -1,25 +1,25 @@
 use std::path::Path;
 use std::sync::Arc;
 
-use futures_util::TryStreamExt;
 use parking_lot::Mutex;
-use reqwest::{Body, Client, Method, StatusCode};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::collections::HashSet;
-use tokio::io::DuplexStream;
-use tokio_util::io::ReaderStream;
 
+use super::backend::{normalize_path, StorageBackend};
 use super::crypto::{self, WebdavCryptoContext};
-use super::types::{SyncCollection, WebdavConfig};
+use super::types::SyncCollection;
 
-const WEBDAV_NETWORK_ERROR: &str = "无法连接 WebDAV 服务，请检查地址、网络或服务器状态";
+pub use super::backend::WriteCondition;
 
+// 条件写入冲突时重新读取合并的最多次数
+pub const MAX_WRITE_ATTEMPTS: usize = 3;
+pub const WRITE_CONFLICT_ERROR: &str = "云端数据被其他设备同时修改，请稍后重试";
+
+// 同步流程使用的云端文件接口，负责加密和目录缓存，具体存储由 backend 决定
 #[derive(Clone)]
 pub struct WebdavClient {
-    client: Client,
-    config: WebdavConfig,
-    base_url: String,
+    backend: Arc<dyn StorageBackend>,
     crypto: Arc<Mutex<Option<EncryptionState>>>,
     ensured_dirs: Arc<Mutex<HashSet<String>>>,
 }
@@ -31,24 +31,12 @@ struct EncryptionState {
 }
 
 impl WebdavClient {
-    pub fn new(config: WebdavConfig) -> Result<Self, String> {
-        let url = config.url.trim().trim_end_matches('/').to_string();
-        if url.is_empty() {
-            return Err("WebDAV 地址不能为空".to_string());
-        }
-        let root = normalize_path(&config.root_path);
-        let base_url = if root.is_empty() {
-            url.clone()
-        } else {
-            format!("{}/{}", url, root)
-        };
-        Ok(Self {
-            client: Client::new(),
-            config,
-            base_url,
+    pub fn new(backend: Arc<dyn StorageBackend>) -> Self {
+        Self {
+            backend,
             crypto: Arc::new(Mutex::new(None)),
             ensured_dirs: Arc::new(Mutex::new(HashSet::new())),
-        })
+        }
     }
 
     pub async fn enable_encryption(&mut self, password: &str) -> Result<(), String> {
@@ -119,18 +107,23 @@ impl WebdavClient {
         self.mkcol("").await?;
         self.mkcol("history").await?;
         self.mkcol("history/chunks").await?;
+        self.mkcol("history/log").await?;
         self.mkcol("favorites").await?;
         self.mkcol("favorites/chunks").await?;
+        self.mkcol("favorites/log").await?;
         self.mkcol("groups").await?;
         self.mkcol("files").await?;
         self.mkcol("tombstones").await?;
+        // 确认有列出目录的权限，对象存储没有目录时也能检查到凭据错误
+        self.backend.list("").await?;
         Ok(())
     }
 
     pub async fn ensure_collection_dirs(&self, collection: SyncCollection) -> Result<(), String> {
         self.ensure_dir_cached("").await?;
         self.ensure_dir_cached(collection.dir()).await?;
-        self.ensure_dir_cached(&format!("{}/chunks", collection.dir())).await
+        self.ensure_dir_cached(&format!("{}/chunks", collection.dir())).await?;
+        self.ensure_dir_cached(&format!("{}/log", collection.dir())).await
     }
 
     pub async fn ensure_groups_dir(&self) -> Result<(), String> {
@@ -180,32 +173,77 @@ impl WebdavClient {
     }
 
     pub async fn get_bytes(&self, path: &str) -> Result<Option<Vec<u8>>, String> {
-        let Some(bytes) = self.get_raw_bytes(path).await? else {
+        Ok(self.get_bytes_with_etag(path).await?.map(|(bytes, _)| bytes))
+    }
+
+    // 同时返回 ETag，供条件写入使用；服务器不提供 ETag 时为 None
+    pub async fn get_bytes_with_etag(&self, path: &str) -> Result<Option<(Vec<u8>, Option<String>)>, String> {
+        let Some((bytes, etag)) = self.get_raw_bytes_with_etag(path).await? else {
             return Ok(None);
         };
+        Ok(Some((self.decrypt_bytes(path, bytes).await?, etag)))
+    }
+
+    pub async fn get_json_with_etag<T: DeserializeOwned>(&self, path: &str) -> Result<Option<(T, Option<String>)>, String> {
+        let Some((bytes, etag)) = self.get_bytes_with_etag(path).await? else {
+            return Ok(None);
+        };
+        let value = serde_json::from_slice(&bytes).map_err(|e| format!("解析 WebDAV JSON 失败: {}", e))?;
+        Ok(Some((value, etag)))
+    }
+
+    // 条件不满足（文件已被其他设备修改）时返回 false
+    pub async fn put_json_if<T: Serialize + ?Sized>(
+        &self,
+        path: &str,
+        value: &T,
+        condition: &WriteCondition,
+    ) -> Result<bool, String> {
+        let body = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+        let body = self.encrypt_bytes(path, body)?;
+        self.put_raw_bytes_if(path, body, condition).await
+    }
+
+    // 整体替换目标文件，中途失败不会留下写了一半的目标文件
+    pub async fn commit_json<T: Serialize + ?Sized>(&self, path: &str, value: &T) -> Result<(), String> {
+        let body = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+        let body = self.encrypt_bytes(path, body)?;
+        self.backend.replace(&normalize_path(path), body).await
+    }
+
+    // 列出目录下的文件名
+    pub async fn list(&self, dir: &str) -> Result<Vec<String>, String> {
+        self.backend.list(&normalize_path(dir)).await
+    }
+
+    async fn decrypt_bytes(&self, path: &str, bytes: Vec<u8>) -> Result<Vec<u8>, String> {
         let path = normalize_path(path);
         match self.crypto_context() {
             Some(crypto) => match crypto.decrypt_bytes(&path, &bytes) {
-                Ok(bytes) => Ok(Some(bytes)),
+                Ok(bytes) => Ok(bytes),
                 Err(error) if should_refresh_encryption_config(&error) => {
                     self.refresh_encryption_config().await?;
                     let crypto = self.crypto_context().ok_or_else(|| "WebDAV 云端加密未启用".to_string())?;
-                    Ok(Some(crypto.decrypt_bytes(&path, &bytes)?))
+                    crypto.decrypt_bytes(&path, &bytes)
                 }
                 Err(error) => Err(error),
             },
-            None => Ok(Some(bytes)),
+            None => Ok(bytes),
         }
     }
 
     pub async fn put_bytes(&self, path: &str, bytes: Vec<u8>) -> Result<(), String> {
-        let bytes = match self.crypto_context() {
-            Some(crypto) => crypto.encrypt_bytes(&normalize_path(path), &bytes)?,
-            None => bytes,
-        };
+        let bytes = self.encrypt_bytes(path, bytes)?;
         self.put_raw_bytes(path, bytes).await
     }
 
+    fn encrypt_bytes(&self, path: &str, bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+        match self.crypto_context() {
+            Some(crypto) => crypto.encrypt_bytes(&normalize_path(path), &bytes),
+            None => Ok(bytes),
+        }
+    }
+
     pub async fn upload_encrypted_file_with_progress(
         &self,
         path: &str,
@@ -228,7 +266,10 @@ impl WebdavClient {
                 .await
         });
 
-        let upload_result = self.put_raw_stream(path, reader, encrypted_size).await;
+        let upload_result = self
+            .backend
+            .put_stream(&normalize_path(path), reader, encrypted_size)
+            .await;
         if let Err(error) = upload_result {
             encrypt_task.abort();
             let _ = encrypt_task.await;
@@ -258,44 +299,21 @@ impl WebdavClient {
     }
 
     async fn get_raw_bytes(&self, path: &str) -> Result<Option<Vec<u8>>, String> {
-        let resp = self.request(Method::GET, path).send().await.map_err(map_reqwest_error)?;
-        if resp.status() == StatusCode::NOT_FOUND {
-            return Ok(None);
-        }
-        if !resp.status().is_success() {
-            return Err(format_webdav_status_error("读取 WebDAV 文件失败", resp.status()));
-        }
-        Ok(Some(resp.bytes().await.map_err(map_reqwest_error)?.to_vec()))
+        Ok(self.get_raw_bytes_with_etag(path).await?.map(|(bytes, _)| bytes))
+    }
+
+    async fn get_raw_bytes_with_etag(&self, path: &str) -> Result<Option<(Vec<u8>, Option<String>)>, String> {
+        let object = self.backend.get(&normalize_path(path)).await?;
+        Ok(object.map(|object| (object.bytes, object.etag)))
     }
 
     async fn put_raw_bytes(&self, path: &str, bytes: Vec<u8>) -> Result<(), String> {
-        let resp = self
-            .request(Method::PUT, path)
-            .body(bytes)
-            .send()
-            .await
-            .map_err(map_reqwest_error)?;
-        if resp.status().is_success() {
-            Ok(())
-        } else {
-            Err(format_webdav_status_error("写入 WebDAV 文件失败", resp.status()))
-        }
+        self.put_raw_bytes_if(path, bytes, &WriteCondition::Any).await?;
+        Ok(())
     }
 
-    async fn put_raw_stream(&self, path: &str, reader: DuplexStream, content_length: u64) -> Result<(), String> {
-        let stream = ReaderStream::new(reader);
-        let resp = self
-            .request(Method::PUT, path)
-            .header(reqwest::header::CONTENT_LENGTH, content_length)
-            .body(Body::wrap_stream(stream))
-            .send()
-            .await
-            .map_err(map_reqwest_error)?;
-        if resp.status().is_success() {
-            Ok(())
-        } else {
-            Err(format_webdav_status_error("写入 WebDAV 文件失败", resp.status()))
-        }
+    async fn put_raw_bytes_if(&self, path: &str, bytes: Vec<u8>, condition: &WriteCondition) -> Result<bool, String> {
+        self.backend.put(&normalize_path(path), bytes, condition).await
     }
 
     pub async fn download_encrypted_file(&self, path: &str, destination: &Path) -> Result<String, String> {
@@ -323,18 +341,11 @@ impl WebdavClient {
                 .map_err(|e| format!("创建下载目录失败: {}", e))?;
         }
 
-        let resp = self.request(Method::GET, path).send().await.map_err(map_reqwest_error)?;
-        if resp.status() == StatusCode::NOT_FOUND {
-            return Err("云端文件不存在".to_string());
-        }
-        if !resp.status().is_success() {
-            return Err(format_webdav_status_error("读取 WebDAV 文件失败", resp.status()));
-        }
-
-        let stream = resp
-            .bytes_stream()
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e));
-        let reader = tokio_util::io::StreamReader::new(stream);
+        let reader = self
+            .backend
+            .get_stream(&normalize_path(path))
+            .await?
+            .ok_or_else(|| "云端文件不存在".to_string())?;
         let file = tokio::fs::File::create(destination)
             .await
             .map_err(|e| format!("创建本地下载文件失败: {}", e))?;
@@ -343,26 +354,20 @@ impl WebdavClient {
             .await
     }
 
+    pub async fn exists(&self, path: &str) -> Result<bool, String> {
+        self.backend.exists(&normalize_path(path)).await
+    }
+
     pub async fn delete_path(&self, path: &str) -> Result<(), String> {
-        let resp = self.request(Method::DELETE, path).send().await.map_err(map_reqwest_error)?;
-        if resp.status().is_success() || resp.status() == StatusCode::NOT_FOUND {
-            Ok(())
-        } else {
-            Err(format_webdav_status_error("删除 WebDAV 文件失败", resp.status()))
-        }
+        self.delete_path_if(path, &WriteCondition::Any).await
+    }
+
+    pub async fn delete_path_if(&self, path: &str, condition: &WriteCondition) -> Result<(), String> {
+        self.backend.delete(&normalize_path(path), condition).await
     }
 
     pub async fn mkcol(&self, path: &str) -> Result<(), String> {
-        let method = Method::from_bytes(b"MKCOL").map_err(|e| e.to_string())?;
-        let resp = self.request(method, path).send().await.map_err(map_reqwest_error)?;
-        if resp.status().is_success()
-            || resp.status() == StatusCode::METHOD_NOT_ALLOWED
-            || resp.status().as_u16() == 405
-        {
-            Ok(())
-        } else {
-            Err(format_webdav_status_error("创建 WebDAV 目录失败", resp.status()))
-        }
+        self.backend.create_dir(&normalize_path(path)).await
     }
 
     pub fn mark_dir_ensured(&self, path: &str) {
@@ -379,38 +384,11 @@ impl WebdavClient {
         Ok(())
     }
 
-    fn request(&self, method: Method, path: &str) -> reqwest::RequestBuilder {
-        let url = self.url_for(path);
-        let builder = self.client.request(method, url);
-        if self.config.username.trim().is_empty() {
-            builder
-        } else {
-            builder.basic_auth(self.config.username.trim().to_string(), Some(self.config.password.clone()))
-        }
-    }
-
-    fn url_for(&self, path: &str) -> String {
-        let path = normalize_path(path);
-        if path.is_empty() {
-            self.base_url.clone()
-        } else {
-            format!("{}/{}", self.base_url, path)
-        }
-    }
-
     fn encryption_scope(&self) -> String {
-        format!("{}\n{}", self.base_url, self.config.username.trim())
+        self.backend.scope()
     }
 }
 
-fn normalize_path(path: &str) -> String {
-    path.replace('\\', "/")
-        .split('/')
-        .filter(|p| !p.trim().is_empty())
-        .collect::<Vec<_>>()
-        .join("/")
-}
-
 fn is_webdav_conflict(error: &str) -> bool {
     error.contains("409") || error.contains("Conflict")
 }
@@ -420,26 +398,3 @@ fn should_refresh_encryption_config(error: &str) -> bool {
         || error.contains("加密格式不兼容")
         || error.contains("不是 QuickClipboard 加密格式")
 }
-
-fn map_reqwest_error(error: reqwest::Error) -> String {
-    if error.is_timeout() {
-        return "WebDAV 请求超时".to_string();
-    }
-    if error.is_connect() || error.is_request() {
-        return WEBDAV_NETWORK_ERROR.to_string();
-    }
-    format!("WebDAV 请求失败: {}", error)
-}
-
-fn format_webdav_status_error(action: &str, status: StatusCode) -> String {
-    let hint = match status {
-        StatusCode::UNAUTHORIZED => "账号或密码不正确",
-        StatusCode::FORBIDDEN => "当前账号没有访问权限",
-        StatusCode::NOT_FOUND => "路径不存在",
-        StatusCode::CONFLICT => "目标目录不存在或路径冲突",
-        _ if status.as_u16() == 507 => "存储空间不足",
-        _ if status.is_server_error() => "服务器返回异常",
-        _ => "请求被服务器拒绝",
-    };
-    format!("{}: {} ({})", action, hint, status.as_u16())
-}
@@ -33,6 +33,7 @@ pub fn show_main_window(window: &WebviewWindow) {
     if crate::services::system::is_front_app_globally_disabled_from_settings() {
         return;
     }
+    crate::services::local_encryption::record_activity();
 
     let state = super::state::get_window_state();
 
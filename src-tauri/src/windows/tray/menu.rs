@@ -108,6 +108,40 @@ fn build_pin_images_children() -> Vec<CtxMenuItem> {
     children
 }
 
+fn paste_queue_label() -> String {
+    let status = crate::services::paste::queue::get_paste_queue_status();
+    if status.item_ids.is_empty() && !status.armed {
+        "粘贴队列".to_string()
+    } else {
+        format!("粘贴队列 ({})", status.item_ids.len())
+    }
+}
+
+// 构建粘贴队列子菜单项列表
+fn build_paste_queue_children() -> Vec<CtxMenuItem> {
+    use crate::services::paste::queue::{get_paste_queue_status, PasteQueueOrder};
+
+    let status = get_paste_queue_status();
+    let count = status.item_ids.len();
+    let arm_label = if status.armed { "停止收集" } else { "开始收集" };
+    let order_icon = |order: PasteQueueOrder| (status.order == order).then_some("ti ti-check");
+
+    vec![
+        menu_item("paste-queue-arm", arm_label, Some("ti ti-player-record")),
+        menu_item_with_state(
+            "paste-queue-next",
+            &format!("粘贴下一项 (剩余{}项)", count),
+            Some("ti ti-player-track-next"),
+            count == 0,
+        ),
+        separator_item(),
+        menu_item("paste-queue-fifo", "先进先出", order_icon(PasteQueueOrder::Fifo)),
+        menu_item("paste-queue-lifo", "后进先出", order_icon(PasteQueueOrder::Lifo)),
+        separator_item(),
+        menu_item_with_state("paste-queue-clear", "清空队列", Some("ti ti-trash"), count == 0),
+    ]
+}
+
 // 托盘菜单
 pub async fn show_tray_menu(app: AppHandle) -> Result<(), String> {
     let settings = crate::get_settings();
@@ -144,6 +178,13 @@ pub async fn show_tray_menu(app: AppHandle) -> Result<(), String> {
             ],
         )
         .with_disabled(is_force_update),
+        CtxMenuItem::submenu(
+            "paste-queue",
+            paste_queue_label(),
+            Some("ti ti-stack-2"),
+            build_paste_queue_children(),
+        )
+        .with_disabled(is_force_update),
         separator_item(),
         menu_item_with_state("toggle-hotkeys", hotkeys_label, Some("ti ti-keyboard"), is_force_update),
         menu_item_with_state("toggle-clipboard-monitor", monitor_label, Some("ti ti-clipboard"), is_force_update),
@@ -252,6 +293,29 @@ fn handle_tray_menu_selection(app: &AppHandle, selected_id: &str) {
                 eprintln!("进入低占用模式失败: {}", e);
             }
         }
+        "paste-queue-arm" => {
+            use crate::services::paste::queue;
+            queue::set_paste_queue_armed(!queue::get_paste_queue_status().armed);
+        }
+        "paste-queue-next" => {
+            std::thread::spawn(|| {
+                // 等待菜单关闭、焦点回到目标窗口
+                std::thread::sleep(std::time::Duration::from_millis(150));
+                if let Err(e) = crate::services::paste::queue::paste_next_queued_item() {
+                    eprintln!("粘贴队列下一项失败: {}", e);
+                }
+            });
+        }
+        "paste-queue-fifo" | "paste-queue-lifo" => {
+            use crate::services::paste::queue::{set_paste_queue_order, PasteQueueOrder};
+            let order = PasteQueueOrder::parse(selected_id.trim_start_matches("paste-queue-"));
+            if let Err(e) = set_paste_queue_order(order) {
+                eprintln!("设置粘贴队列顺序失败: {}", e);
+            }
+        }
+        "paste-queue-clear" => {
+            crate::services::paste::queue::clear_paste_queue();
+        }
         "restart" => {
             super::restart_app_gracefully(app);
         }
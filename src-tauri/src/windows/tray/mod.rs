@@ -27,6 +27,23 @@ pub fn switch_to_native_menu(app: &AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+// 托盘提示中显示粘贴队列状态
+pub fn update_tray_tooltip(app: &AppHandle, status: &crate::services::paste::queue::PasteQueueStatus) {
+    let Some(tray) = app.tray_by_id("main-tray") else {
+        return;
+    };
+    let tooltip = if status.armed || !status.item_ids.is_empty() {
+        let order = match status.order {
+            crate::services::paste::queue::PasteQueueOrder::Fifo => "顺序",
+            crate::services::paste::queue::PasteQueueOrder::Lifo => "倒序",
+        };
+        format!("快速剪贴板 - 粘贴队列({}): {} 项", order, status.item_ids.len())
+    } else {
+        "快速剪贴板".to_string()
+    };
+    let _ = tray.set_tooltip(Some(tooltip));
+}
+
 // 切换回 WebView 菜单
 pub fn switch_to_webview_menu(app: &AppHandle) -> Result<(), String> {
     let tray_id = TrayIconId::new("main-tray");
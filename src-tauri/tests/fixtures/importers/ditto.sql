@@ -0,0 +1,34 @@
+-- Ditto 数据库结构（节选）与示例数据
+CREATE TABLE Main (
+    lID INTEGER PRIMARY KEY AUTOINCREMENT,
+    lDate INTEGER,
+    mText TEXT,
+    lShortCut INTEGER,
+    lDontAutoDelete INTEGER,
+    CRC INTEGER,
+    bIsGroup INTEGER,
+    lParentID INTEGER,
+    QuickPasteText TEXT,
+    clipOrder REAL,
+    clipGroupOrder REAL
+);
+CREATE TABLE Data (
+    lID INTEGER PRIMARY KEY AUTOINCREMENT,
+    lParentID INTEGER,
+    strClipBoardFormat TEXT,
+    ooData BLOB
+);
+
+INSERT INTO Main (lID, lDate, mText, bIsGroup, lParentID) VALUES
+    (1, 1700000000, '你好 Ditto', 0, -1),
+    (2, 1700000100, 'https://example.com', 0, -1),
+    (3, 1700000200, 'CF_DIB', 0, -1),
+    (4, 1700000300, 'Snippets', 1, -1),
+    (5, 1700000400, 'git status --short', 0, 4);
+
+INSERT INTO Data (lParentID, strClipBoardFormat, ooData) VALUES
+    (1, 'CF_UNICODETEXT', X'604F7D59200044006900740074006F000000'),
+    (2, 'CF_UNICODETEXT', X'680074007400700073003A002F002F006500780061006D0070006C0065002E0063006F006D000000'),
+    (2, 'HTML Format', X'56657273696F6E3A302E390D0A537461727448544D4C3A303030303030303130350D0A456E6448544D4C3A303030303030303139390D0A5374617274467261676D656E743A303030303030303133390D0A456E64467261676D656E743A303030303030303136350D0A3C68746D6C3E3C626F64793E0D0A3C212D2D5374617274467261676D656E742D2D3E3C623E68747470733A2F2F6578616D706C652E636F6D3C2F623E3C212D2D456E64467261676D656E742D2D3E0D0A3C2F626F64793E3C2F68746D6C3E'),
+    (3, 'PNG', X'89504E470D0A1A0A0000000D4948445200000001000000010802000000907753DE0000000C49444154789C636068F80F00020301802461F5970000000049454E44AE426082'),
+    (5, 'CF_TEXT', X'67697420737461747573202D2D73686F727400');
@@ -7,7 +7,11 @@ export async function getClipboardHistory(params = {}) {
     const { offset = 0, limit = 50, search, contentType } = params
 
     const invokeParams = { offset, limit }
-    if (search) invokeParams.search = search
+    if (search) {
+      invokeParams.search = search
+      // 搜索时按命中相关度排序
+      invokeParams.orderByRelevance = true
+    }
     if (contentType) invokeParams.contentType = contentType
 
     return await invoke('get_clipboard_history', invokeParams)
@@ -255,6 +259,31 @@ export async function mergePasteClipboardItems(ids) {
   return await invoke('merge_paste_clipboard_items', { ids })
 }
 
+// 粘贴队列
+export async function getPasteQueueStatus() {
+  return await invoke('get_paste_queue_status')
+}
+
+export async function setPasteQueueArmed(armed) {
+  return await invoke('set_paste_queue_armed', { armed })
+}
+
+export async function setPasteQueueOrder(order) {
+  return await invoke('set_paste_queue_order', { order })
+}
+
+export async function enqueuePasteQueueItems(ids) {
+  return await invoke('enqueue_paste_queue_items', { ids })
+}
+
+export async function clearPasteQueue() {
+  return await invoke('clear_paste_queue')
+}
+
+export async function pasteNextQueuedItem() {
+  return await invoke('paste_next_queued_item')
+}
+
 // 切换剪贴板项置顶状态
 export async function togglePinClipboardItem(id) {
   const isPinned = await invoke('toggle_pin_clipboard_item', { id })
@@ -265,3 +294,13 @@ export async function togglePinClipboardItem(id) {
   })
   return isPinned
 }
+
+// 设置剪贴板项过期时间（秒级时间戳，null 表示取消过期）
+export async function setClipboardItemExpiry(id, expiresAt) {
+  await invoke('set_clipboard_item_expiry', { id, expiresAt })
+  await invoke('emit_clipboard_updated', {
+    payload: {
+      kind: 'updated',
+    },
+  })
+}
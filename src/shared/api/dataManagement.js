@@ -38,3 +38,73 @@ export async function resetAllData() {
 export async function listBackups() {
   return await invoke('dm_list_backups')
 }
+
+//按筛选条件导出为 JSON Lines / CSV / Markdown
+export async function exportPortable(targetPath, format, filter) {
+  return await invoke('dm_export_portable', { payload: { target_path: targetPath, format, filter } })
+}
+
+export async function importPortable(sourcePath) {
+  return await invoke('dm_import_portable', { payload: { source_path: sourcePath } })
+}
+
+//从其他剪贴板管理器的数据导入（ditto / copyq / clipboard_history_json）
+export async function importExternal(source, path) {
+  return await invoke('dm_import_external', { payload: { source, path } })
+}
+
+export async function createSnapshot() {
+  return await invoke('dm_create_snapshot')
+}
+
+export async function listSnapshots() {
+  return await invoke('dm_list_snapshots')
+}
+
+export async function verifySnapshots() {
+  return await invoke('dm_verify_snapshots')
+}
+
+//整体恢复到指定快照
+export async function restoreSnapshot(id) {
+  return await invoke('dm_restore_snapshot', { payload: { id } })
+}
+
+export async function listSnapshotItems(id, kind, search) {
+  return await invoke('dm_list_snapshot_items', { payload: { id, kind, search } })
+}
+
+//从快照中取回选中的条目
+export async function restoreSnapshotItems(id, clipboardIds, favoriteIds) {
+  return await invoke('dm_restore_snapshot_items', { payload: { id, clipboard_ids: clipboardIds, favorite_ids: favoriteIds } })
+}
+
+//清理孤立图片、原始格式数据和过期删除记录，dryRun 时只统计
+export async function collectStorageGarbage(dryRun) {
+  return await invoke('dm_collect_storage_garbage', { payload: { dry_run: dryRun } })
+}
+
+export async function getLocalEncryptionStatus() {
+  return await invoke('dm_local_encryption_status')
+}
+
+export async function enableLocalEncryption(password) {
+  return await invoke('dm_enable_local_encryption', { payload: { password } })
+}
+
+export async function unlockLocalEncryption(password) {
+  return await invoke('dm_unlock_local_encryption', { payload: { password } })
+}
+
+//封存本地数据并清除内存中的密钥
+export async function lockLocalEncryption() {
+  return await invoke('dm_lock_local_encryption')
+}
+
+export async function rekeyLocalEncryption(oldPassword, newPassword) {
+  return await invoke('dm_rekey_local_encryption', { payload: { old_password: oldPassword, new_password: newPassword } })
+}
+
+export async function disableLocalEncryption(password) {
+  return await invoke('dm_disable_local_encryption', { payload: { password } })
+}
@@ -8,7 +8,11 @@ export async function getFavoritesHistory(params = {}) {
 
   const invokeParams = { offset, limit }
   if (groupName && groupName !== '全部') invokeParams.groupName = groupName
-  if (search) invokeParams.search = search
+  if (search) {
+    invokeParams.search = search
+    // 搜索时按命中相关度排序
+    invokeParams.orderByRelevance = true
+  }
   if (contentType) invokeParams.contentType = contentType
 
   return await invoke('get_favorites_history', invokeParams)
@@ -92,3 +92,12 @@ export async function setOneTimePasteEnabledToStore(enabled) {
   return await invoke('set_one_time_paste_enabled', { enabled })
 }
 
+// 本机控制接口
+export async function getLocalApiStatus() {
+  return await invoke('get_local_api_status')
+}
+
+export async function regenerateLocalApiToken() {
+  return await invoke('regenerate_local_api_token')
+}
+
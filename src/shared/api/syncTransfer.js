@@ -30,6 +30,13 @@ export async function removeSyncTransferLanPairedPeer(deviceId) {
   });
 }
 
+export async function updateSyncTransferLanPeerSyncPolicy(deviceId, policy) {
+  return await invoke('sync_transfer_lan_update_peer_sync_policy', {
+    deviceId,
+    policy,
+  });
+}
+
 export async function pairSyncTransferLanPeer(baseUrl, pairingCode) {
   return await invoke('sync_transfer_lan_pair_with_peer', {
     baseUrl,
@@ -63,6 +70,16 @@ export async function updateSyncTransferLanAutoSyncSettings(settings) {
   });
 }
 
+export async function getSyncTransferLanLiveShareStatus() {
+  return await invoke('sync_transfer_lan_get_live_share_status');
+}
+
+export async function updateSyncTransferLanLiveShareSettings(settings) {
+  return await invoke('sync_transfer_lan_update_live_share_settings', {
+    settings,
+  });
+}
+
 export async function pullSyncTransferLanPeer(deviceId) {
   return await invoke('sync_transfer_lan_pull_from_peer', {
     deviceId,
@@ -13,6 +13,7 @@ export function useNavigationKeyboard({
   onTogglePin = null,
   onPreviousGroup = null,
   onNextGroup = null,
+  onPasteAction = null,
   enabled = true
 }) {
   const handlersRef = useRef({
@@ -24,7 +25,8 @@ export function useNavigationKeyboard({
     onToggleSearch,
     onTogglePin,
     onPreviousGroup,
-    onNextGroup
+    onNextGroup,
+    onPasteAction
   })
 
   useEffect(() => {
@@ -37,7 +39,8 @@ export function useNavigationKeyboard({
       onToggleSearch,
       onTogglePin,
       onPreviousGroup,
-      onNextGroup
+      onNextGroup,
+      onPasteAction
     }
   }, [
     onNavigateUp,
@@ -48,7 +51,8 @@ export function useNavigationKeyboard({
     onToggleSearch,
     onTogglePin,
     onPreviousGroup,
-    onNextGroup
+    onNextGroup,
+    onPasteAction
   ])
 
   useEffect(() => {
@@ -99,6 +103,10 @@ export function useNavigationKeyboard({
               if (handlers.onNextGroup) handlers.onNextGroup()
               break
             default:
+              // 转换粘贴快捷键：动作即粘贴动作 ID
+              if (action?.startsWith('transform:') && handlers.onPasteAction) {
+                handlers.onPasteAction(action)
+              }
               break
           }
         })
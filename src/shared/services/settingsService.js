@@ -68,10 +68,13 @@ export const defaultSettings = {
   previousGroupShortcut: 'Ctrl+ArrowUp',
   nextGroupShortcut: 'Ctrl+ArrowDown',
   togglePinShortcut: 'Ctrl+P',
+  pasteTransformShortcuts: [],
   toggleClipboardMonitorShortcut: '',
   togglePasteWithFormatShortcut: '',
   toggleLowMemoryModeShortcut: '',
   pastePlainTextShortcut: '',
+  pasteQueueShortcut: '',
+  pasteQueueOrder: 'fifo',
   
   // 剪贴板设置
   clipboardMonitor: true,
@@ -161,6 +164,24 @@ export const defaultSettings = {
   appFilterEnabled: false,
   appFilterBlocklist: [],
   appFilterEffect: 'clipboard_only',
+
+  // 敏感内容检测
+  sensitiveDetectionEnabled: false,
+  sensitiveRules: [
+    { id: 'private_key', name: '私钥', pattern: '', enabled: true, policy: 'skip' },
+    { id: 'jwt', name: 'JWT 令牌', pattern: '', enabled: true, policy: 'mask' },
+    { id: 'api_key', name: 'API 密钥', pattern: '', enabled: true, policy: 'mask' },
+    { id: 'password', name: '密码赋值', pattern: '', enabled: true, policy: 'mask' },
+    { id: 'credit_card', name: '银行卡号', pattern: '', enabled: true, policy: 'mask' },
+    { id: 'high_entropy', name: '高熵字符串', pattern: '', enabled: false, policy: 'expire' },
+  ],
+  sensitiveExpireMinutes: 10,
+
+  // 历史保留策略
+  retentionRules: [],
+
+  // 捕获规则
+  captureRules: [],
   
   // 保存的窗口状态
   savedWindowPosition: null,
@@ -169,13 +190,25 @@ export const defaultSettings = {
   // 数据存储设置
   customStoragePath: null,
   useCustomStorage: false,
+  tombstoneRetentionDays: 30,
+  snapshotIntervalHours: 24,
+  snapshotKeep: 7,
+  localEncryptionIdleMinutes: 15,
+
+  // 本机控制接口设置
+  localApiEnabled: false,
+  localApiPort: 35692,
 
   // WebDAV Sync 设置
   webdavEnabled: false,
+  webdavBackend: 'webdav',
   webdavUrl: '',
   webdavUsername: '',
   webdavPassword: '',
   webdavRootPath: 'quickclipboard',
+  webdavS3Bucket: '',
+  webdavS3Region: 'us-east-1',
+  webdavFolderPath: '',
   webdavAutoPush: false,
   webdavPushDelaySecs: 10,
   webdavAutoPull: false,
@@ -190,6 +223,19 @@ export const defaultSettings = {
 
 }
 
+// 云端同步目标是否已填写：WebDAV 需要地址，S3 需要地址和存储桶，文件夹需要路径
+export function isCloudSyncConfigured(settings) {
+  const filled = value => Boolean(String(value || '').trim())
+  switch (settings?.webdavBackend) {
+    case 's3':
+      return filled(settings.webdavUrl) && filled(settings.webdavS3Bucket)
+    case 'folder':
+      return filled(settings.webdavFolderPath)
+    default:
+      return filled(settings?.webdavUrl)
+  }
+}
+
 // 加载设置
 export async function loadSettingsFromBackend() {
   try {
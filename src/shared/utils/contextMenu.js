@@ -29,6 +29,7 @@ import {
   getClipboardItemPasteOptions,
   listTransferShelves,
   addPathsToTransferShelf,
+  setClipboardItemExpiry,
 } from "@shared/api";
 import { getFavoriteItemPasteOptions } from "@shared/api/favorites";
 import { clipboardStore } from "@shared/store/clipboardStore";
@@ -38,6 +39,54 @@ const TOAST_CONFIG = {
   position: TOAST_POSITIONS.BOTTOM_RIGHT,
 };
 
+// 单条历史的自动删除时长（秒）
+const EXPIRY_PRESETS = [
+  { id: "expire-1h", seconds: 60 * 60, labelKey: "contextMenu.autoDeleteIn1Hour" },
+  { id: "expire-1d", seconds: 24 * 60 * 60, labelKey: "contextMenu.autoDeleteIn1Day" },
+  { id: "expire-7d", seconds: 7 * 24 * 60 * 60, labelKey: "contextMenu.autoDeleteIn7Days" },
+];
+
+function createExpiryMenuItem(item) {
+  const expiryItem = createMenuItem({
+    id: "auto-delete",
+    label: i18n.t("contextMenu.autoDelete"),
+    icon: "ti ti-clock-x",
+  });
+  expiryItem.children = EXPIRY_PRESETS.map((preset) =>
+    createMenuItem({
+      id: preset.id,
+      label: i18n.t(preset.labelKey),
+    }),
+  );
+  if (item.expires_at) {
+    expiryItem.children.push(
+      createSeparator(),
+      createMenuItem({
+        id: "expire-cancel",
+        label: i18n.t("contextMenu.cancelAutoDelete"),
+      }),
+    );
+  }
+  return expiryItem;
+}
+
+async function handleExpiryActions(result, item) {
+  let expiresAt = null;
+  if (result !== "expire-cancel") {
+    const preset = EXPIRY_PRESETS.find((entry) => entry.id === result);
+    if (!preset) return false;
+    expiresAt = Math.floor(Date.now() / 1000) + preset.seconds;
+  }
+  await setClipboardItemExpiry(item.id, expiresAt);
+  const { refreshClipboardHistory } = await import("@shared/store/clipboardStore");
+  await refreshClipboardHistory();
+  toast.success(
+    i18n.t(expiresAt ? "contextMenu.autoDeleteSet" : "contextMenu.autoDeleteCancelled"),
+    TOAST_CONFIG,
+  );
+  return true;
+}
+
 // 获取搜索引擎列表
 function getSearchEngines() {
   return [
@@ -613,6 +662,7 @@ export async function showClipboardItemContextMenu(event, item, index) {
   // 添加通用菜单项
   menuItems.push(
     addToFavoritesItem,
+    createExpiryMenuItem(item),
     createMenuItem({
       id: "delete-item",
       label: i18n.t("contextMenu.deleteItem"),
@@ -674,6 +724,9 @@ export async function showClipboardItemContextMenu(event, item, index) {
       return;
     }
 
+    // 处理自动删除
+    if (await handleExpiryActions(result, item)) return;
+
     // 处理内容类型操作
     if (await handleContentTypeActions(result, item, index)) return;
 
@@ -1,4 +1,4 @@
-import { convertFileSrc } from '@tauri-apps/api/core';
+import { toLocalImageSrc } from './localImageSrc';
 
 function drawRoundRect(ctx, x, y, width, height, radius) {
   if (typeof ctx.roundRect === 'function') {
@@ -73,7 +73,7 @@ async function loadImageForDragPreview(path) {
 
   const source = String(path);
   const isInlineImage = source.startsWith('data:image/') || source.startsWith('blob:');
-  const imageUrl = isInlineImage ? source : convertFileSrc(source, 'asset');
+  const imageUrl = isInlineImage ? source : toLocalImageSrc(source);
   const objectUrl = isInlineImage ? '' : await fetch(imageUrl)
     .then((response) => {
       if (!response.ok) {
@@ -25,6 +25,37 @@ export function highlightText(text, keyword) {
   });
 }
 
+const SNIPPET_ENTITIES = {
+  '&amp;': '&',
+  '&lt;': '<',
+  '&gt;': '>',
+  '&quot;': '"',
+  '&#39;': "'"
+};
+
+function decodeSnippetText(text) {
+  return text.replace(/&(amp|lt|gt|quot|#39);/g, (entity) => SNIPPET_ENTITIES[entity]);
+}
+
+// 渲染后端返回的检索片段：正文已转义，命中部分用 <mark> 包裹
+export function renderSearchSnippet(snippet) {
+  if (!snippet) return snippet;
+  return snippet.split(/(<mark>.*?<\/mark>)/s).filter(Boolean).map((part, index) => {
+    if (part.startsWith('<mark>') && part.endsWith('</mark>')) {
+      return (
+        <mark
+          key={index}
+          className="search-highlight bg-yellow-300 text-qc-fg rounded-sm px-0.5"
+          data-highlight="true"
+        >
+          {decodeSnippetText(part.slice(6, -7))}
+        </mark>
+      );
+    }
+    return decodeSnippetText(part);
+  });
+}
+
 // 转义正则表达式特殊字符
 function escapeRegExp(string) {
   return string.replace(/[.*+?^${}()|[\]\\]/g, (match) => '\\' + match);
@@ -0,0 +1,8 @@
+import { convertFileSrc } from '@tauri-apps/api/core';
+
+// 启用本地加密后剪贴板图片缓存是密文，改用 qcimage 协议由后端解密后返回
+export function toLocalImageSrc(filePath) {
+  const normalized = String(filePath || '').replace(/\\/g, '/');
+  const protocol = /\/clipboard_images\/[^/]+$/.test(normalized) ? 'qcimage' : 'asset';
+  return convertFileSrc(filePath, protocol);
+}
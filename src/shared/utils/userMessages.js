@@ -51,6 +51,10 @@ const EXACT_RULES = [
   { patterns: ['请先在设置中保存 WebDAV 密码'], key: 'errors.webdav.passwordRequired' },
   { patterns: ['请先设置 WebDAV 云端加密密码'], key: 'errors.webdav.encryptionPasswordRequired' },
   { patterns: ['WebDAV 云端加密未启用'], key: 'errors.webdav.encryptionPasswordRequired' },
+  { patterns: ['S3 服务地址不能为空'], key: 'errors.webdav.s3EndpointRequired' },
+  { patterns: ['S3 存储桶不能为空'], key: 'errors.webdav.s3BucketRequired' },
+  { patterns: ['请先填写 S3 访问密钥 ID', '请先在设置中保存 S3 访问密钥'], key: 'errors.webdav.s3CredentialsRequired' },
+  { patterns: ['同步文件夹不能为空', '同步文件夹不存在'], key: 'errors.webdav.folderMissing' },
   { patterns: ['WebDAV 数据解密失败', 'WebDAV 云端文件解密失败'], key: 'errors.webdav.decryptFailed' },
   { patterns: ['WebDAV 数据不是 QuickClipboard 加密格式'], key: 'errors.webdav.notEncrypted' },
   { patterns: ['WebDAV 数据加密格式不兼容', '云端文件加密格式不兼容'], key: 'errors.webdav.encryptionFormatUnsupported' },
@@ -60,7 +64,9 @@ const EXACT_RULES = [
   { patterns: ['云端文件 ID 无效'], key: 'errors.webdav.cloudFileInvalid' },
   { patterns: ['下载文件校验失败', '局域网文件内容校验失败', '局域网文件大小校验失败'], key: 'errors.file.checksumFailed' },
   { patterns: ['云端文件过大', '云端加密文件大小溢出'], key: 'errors.file.tooLarge' },
-  { patterns: ['待上传文件大小发生变化'], key: 'errors.file.changedDuringTransfer' },
+  { patterns: ['待上传文件大小发生变化', '待发送文件在传输过程中被修改'], key: 'errors.file.changedDuringTransfer' },
+  { patterns: ['只能传输普通文件或文件夹'], key: 'errors.file.onlyRegularFileOrFolder' },
+  { patterns: ['文件夹为空'], key: 'errors.file.emptyFolder' },
   { patterns: ['只能上传普通文件', '只能传输普通文件', '只能操作收件盒内的普通文件'], key: 'errors.file.onlyRegularFile' },
   { patterns: ['文件不存在或无法访问', '文件不存在'], key: 'errors.file.notFound' },
   { patterns: ['文件名无效', '文件名包含非法字符', '文件名编码无效'], key: 'errors.file.invalidName' },
@@ -69,10 +75,13 @@ const EXACT_RULES = [
   { patterns: ['云端上传状态异常', '局域网文件校验状态异常'], key: 'errors.transferShelf.stateInvalid' },
   { patterns: ['局域网文件传输连接提前关闭'], key: 'errors.lan.connectionClosed' },
   { patterns: ['局域网接收已关闭'], key: 'errors.lan.receiveDisabled' },
-  { patterns: ['未授权的局域网同步请求'], key: 'errors.lan.unauthorized' },
+  { patterns: ['未授权的局域网同步请求', '局域网设备未授权'], key: 'errors.lan.unauthorized' },
   { patterns: ['对方不是兼容的 QuickClipboard 同步/传输服务'], key: 'errors.lan.incompatiblePeer' },
+  { patterns: ['对方版本不支持该传输'], key: 'errors.lan.peerTransferUnsupported' },
   { patterns: ['不能配对当前设备自身'], key: 'errors.lan.selfPairing' },
   { patterns: ['配对码已刷新', '配对码已过期', '配对码尝试次数过多', '配对码不正确'], key: 'errors.lan.pairingCodeInvalid' },
+  { patterns: ['配对会话已失效'], key: 'errors.lan.pairingSessionExpired' },
+  { patterns: ['配对验证失败', '局域网设备身份不匹配'], key: 'errors.lan.pairingVerifyFailed' },
   { patterns: ['只能操作收件盒管理的文件'], key: 'errors.receiveBox.unmanagedFile' },
 ];
 
@@ -28,6 +28,7 @@ import FavoritesTab from './components/FavoritesTab';
 const EmojiTab = lazy(() => import('./components/EmojiTab'));
 import MultiSelectActionBar from './components/MultiSelectActionBar';
 import WindowResizeHandles from './components/WindowResizeHandles';
+import LocalLockOverlay from './components/LocalLockOverlay';
 import ToastContainer from '@shared/components/common/ToastContainer';
 
 const TAB_NAVIGATION_MODE = {
@@ -382,6 +383,13 @@ function App() {
       favoritesTabRef.current.executeCurrentItem();
     }
   };
+  const handlePasteAction = action => {
+    if (activeTab === 'clipboard' && clipboardTabRef.current?.executePasteAction) {
+      clipboardTabRef.current.executePasteAction(action);
+    } else if (activeTab === 'favorites' && favoritesTabRef.current?.executePasteAction) {
+      favoritesTabRef.current.executePasteAction(action);
+    }
+  };
   const handleTabLeft = () => {
     setActiveTab(currentTab => {
       const tabs = visibleTabs;
@@ -464,6 +472,7 @@ function App() {
     onTogglePin: handleTogglePin,
     onPreviousGroup: handlePreviousGroup,
     onNextGroup: handleNextGroup,
+    onPasteAction: handlePasteAction,
     enabled: true
   });
   const outerContainerClasses = `
@@ -485,6 +494,7 @@ function App() {
       {activeTab === 'clipboard' && <ClipboardTab ref={clipboardTabRef} contentFilter={contentFilter} searchQuery={searchQuery} />}
       {activeTab === 'favorites' && <FavoritesTab ref={favoritesTabRef} contentFilter={contentFilter} searchQuery={searchQuery} />}
       {activeTab === 'emoji' && <Suspense fallback={null}><EmojiTab emojiMode={emojiMode} onEmojiModeChange={setEmojiMode} /></Suspense>}
+      <LocalLockOverlay />
     </div>;
   const ActionBarComponent = <MultiSelectActionBar activeTab={activeTab} />;
   const renderWorkspace = () => {
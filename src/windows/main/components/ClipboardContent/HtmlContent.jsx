@@ -1,5 +1,5 @@
 import { useEffect, useRef } from 'react';
-import { convertFileSrc } from '@tauri-apps/api/core';
+import { toLocalImageSrc } from '@shared/utils/localImageSrc';
 import { sanitizeHTML } from '@shared/utils/htmlProcessor';
 import { invoke } from '@tauri-apps/api/core';
 import { highlightHtmlContent, clearHighlights, scrollToFirstHighlight } from '@shared/utils/highlightText';
@@ -38,7 +38,7 @@ function HtmlContent({
         img.classList.add('html-image-pending');
         invoke('get_data_directory').then(dataDir => {
           const filePath = `${dataDir}/clipboard_images/${imageId}.png`;
-          const assetUrl = convertFileSrc(filePath, 'asset');
+          const assetUrl = toLocalImageSrc(filePath);
           img.src = assetUrl;
           img.classList.remove('html-image-pending');
         }).catch(error => {
@@ -52,7 +52,7 @@ function HtmlContent({
         img.classList.add('html-image-pending');
         invoke('get_data_directory').then(dataDir => {
           const filePath = `${dataDir}/clipboard_images/${legacyImageId}.png`;
-          const assetUrl = convertFileSrc(filePath, 'asset');
+          const assetUrl = toLocalImageSrc(filePath);
           img.src = assetUrl;
           img.classList.remove('html-image-pending');
         }).catch(error => {
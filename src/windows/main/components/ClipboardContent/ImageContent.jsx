@@ -1,5 +1,6 @@
 import { useState, useEffect, useRef } from 'react';
-import { convertFileSrc, invoke } from '@tauri-apps/api/core';
+import { invoke } from '@tauri-apps/api/core';
+import { toLocalImageSrc } from '@shared/utils/localImageSrc';
 import { useTranslation } from 'react-i18next';
 import { useSnapshot } from 'valtio';
 import { settingsStore } from '@shared/store/settingsStore';
@@ -96,7 +97,7 @@ function ImageContent({ item, maxContentHeightPx }) {
               if (isSizeOversized || isDimensionOversized) {
                 setIsOversized(true);
               } else {
-                const assetUrl = convertFileSrc(actualPath, 'asset');
+                const assetUrl = toLocalImageSrc(actualPath);
                 setImageSrc(assetUrl);
               }
             }
@@ -115,7 +116,7 @@ function ImageContent({ item, maxContentHeightPx }) {
           const filePath = `${normalizedDataDir}/clipboard_images/${imageId}.png`;
           imagePathRef.current = filePath;
           setFileName(`${imageId}.png`);
-          setImageSrc(`${convertFileSrc(filePath, 'asset')}?retry=${retryToken}`);
+          setImageSrc(`${toLocalImageSrc(filePath)}?retry=${retryToken}`);
           return;
         }
 
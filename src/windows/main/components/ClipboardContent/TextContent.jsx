@@ -1,6 +1,6 @@
 import { useRef, useEffect, useLayoutEffect, useMemo, useState } from 'react';
 import { useTranslation } from 'react-i18next';
-import { highlightText, scrollToFirstHighlight } from '@shared/utils/highlightText';
+import { highlightText, renderSearchSnippet, scrollToFirstHighlight } from '@shared/utils/highlightText';
 import { toast, TOAST_POSITIONS, TOAST_SIZES } from '@shared/store/toastStore';
 import Tooltip from '@shared/components/common/Tooltip.jsx';
 import { formatColorCodeLike, parseStandaloneColorCode } from '@shared/utils/colorCode';
@@ -152,9 +152,13 @@ function TextContent({
     }
   };
 
-  const renderedContent = searchKeyword
-    ? highlightText(content, searchKeyword)
-    : content;
+  // 有检索片段时显示命中位置附近的内容，字段过滤等语法不会被当作关键词高亮
+  const searchSnippet = searchKeyword ? item?.search_snippet : null;
+  const renderedContent = searchSnippet
+    ? renderSearchSnippet(searchSnippet)
+    : searchKeyword
+      ? highlightText(content, searchKeyword)
+      : content;
 
   const clampClass = searchKeyword || rowHeight === 'auto' ? '' : lineClampClass;
   const autoClampStyle = !searchKeyword && rowHeight === 'auto'
@@ -318,31 +318,33 @@ const ClipboardList = forwardRef(({
       }
     }
   }, [clipSnap.totalCount, itemsCount]);
+  const executeSelectedPasteAction = async action => {
+    if (isMultiSelectMode) {
+      return;
+    }
+    const item = itemsWithId[currentSelectedIndex];
+    if (item?.item && !item._isPlaceholder) {
+      try {
+        const { pasteClipboardItem } = await import('@shared/api/clipboard');
+        await pasteClipboardItem(item.item.id, action);
+        if (!getOneTimePasteEnabled() && settingsStore.pasteToTop && item.item.id && !item.item.is_pinned) {
+          try {
+            await moveClipboardItemToTop(item.item.id);
+          } finally {
+            clipboardStore.items = {};
+          }
+        }
+      } catch (error) {
+        console.error('粘贴失败:', error);
+      }
+    }
+  };
   useImperativeHandle(ref, () => ({
     navigateUp,
     navigateDown,
     executeCurrentItem,
-    executePlainTextPaste: async () => {
-      if (isMultiSelectMode) {
-        return;
-      }
-      const item = itemsWithId[currentSelectedIndex];
-      if (item?.item && !item._isPlaceholder) {
-        try {
-          const { pasteClipboardItem } = await import('@shared/api/clipboard');
-          await pasteClipboardItem(item.item.id, 'plain_text');
-          if (!getOneTimePasteEnabled() && settingsStore.pasteToTop && item.item.id && !item.item.is_pinned) {
-            try {
-              await moveClipboardItemToTop(item.item.id);
-            } finally {
-              clipboardStore.items = {};
-            }
-          }
-        } catch (error) {
-          console.error('纯文本粘贴失败:', error);
-        }
-      }
-    },
+    executePlainTextPaste: () => executeSelectedPasteAction('plain_text'),
+    executePasteAction: action => executeSelectedPasteAction(action),
     scrollToTop: () => {
       virtuosoRef.current?.scrollToIndex({
         index: 0,
@@ -71,7 +71,8 @@ const ClipboardTab = forwardRef(({
     navigateUp: () => listRef.current?.navigateUp?.(),
     navigateDown: () => listRef.current?.navigateDown?.(),
     executeCurrentItem: () => listRef.current?.executeCurrentItem?.(),
-    executePlainTextPaste: () => listRef.current?.executePlainTextPaste?.()
+    executePlainTextPaste: () => listRef.current?.executePlainTextPaste?.(),
+    executePasteAction: action => listRef.current?.executePasteAction?.(action)
   }));
 
   // 处理滚动状态变化
@@ -314,24 +314,26 @@ const FavoritesList = forwardRef(({
       }
     }
   }, [favSnap.totalCount, itemsCount, groupsSnap.currentGroup]);
+  const executeSelectedPasteAction = async action => {
+    if (isMultiSelectMode) {
+      return;
+    }
+    const item = itemsWithId[currentSelectedIndex];
+    if (item?.item && !item._isPlaceholder) {
+      try {
+        const { pasteFavorite } = await import('@shared/api/favorites');
+        await pasteFavorite(item.item.id, action);
+      } catch (error) {
+        console.error('粘贴收藏失败:', error);
+      }
+    }
+  };
   useImperativeHandle(ref, () => ({
     navigateUp,
     navigateDown,
     executeCurrentItem,
-    executePlainTextPaste: async () => {
-      if (isMultiSelectMode) {
-        return;
-      }
-      const item = itemsWithId[currentSelectedIndex];
-      if (item?.item && !item._isPlaceholder) {
-        try {
-          const { pasteFavorite } = await import('@shared/api/favorites');
-          await pasteFavorite(item.item.id, 'plain_text');
-        } catch (error) {
-          console.error('纯文本粘贴收藏失败:', error);
-        }
-      }
-    },
+    executePlainTextPaste: () => executeSelectedPasteAction('plain_text'),
+    executePasteAction: action => executeSelectedPasteAction(action),
     scrollToTop: () => {
       virtuosoRef.current?.scrollToIndex({
         index: 0,
@@ -73,7 +73,8 @@ const FavoritesTab = forwardRef(({
     navigateUp: () => listRef.current?.navigateUp?.(),
     navigateDown: () => listRef.current?.navigateDown?.(),
     executeCurrentItem: () => listRef.current?.executeCurrentItem?.(),
-    executePlainTextPaste: () => listRef.current?.executePlainTextPaste?.()
+    executePlainTextPaste: () => listRef.current?.executePlainTextPaste?.(),
+    executePasteAction: action => listRef.current?.executePasteAction?.(action)
   }));
 
   // 处理滚动状态变化
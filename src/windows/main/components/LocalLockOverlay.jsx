@@ -0,0 +1,61 @@
+import { useEffect, useState } from 'react';
+import { useTranslation } from 'react-i18next';
+import { listen } from '@tauri-apps/api/event';
+import Button from '@shared/components/ui/Button';
+import Input from '@shared/components/ui/Input';
+import { getLocalEncryptionStatus, unlockLocalEncryption } from '@shared/api/dataManagement';
+import { formatUserMessage } from '@shared/utils/userMessages';
+
+// 本地加密锁定时覆盖主窗口内容，输入主密码解锁
+function LocalLockOverlay() {
+  const {
+    t
+  } = useTranslation();
+  const [locked, setLocked] = useState(false);
+  const [password, setPassword] = useState('');
+  const [error, setError] = useState('');
+  const [busy, setBusy] = useState(false);
+
+  useEffect(() => {
+    const refresh = () => getLocalEncryptionStatus().then(status => setLocked(status.enabled && status.locked)).catch(() => {});
+    refresh();
+    const unlisteners = [
+      listen('local-encryption-changed', event => setLocked(event.payload.enabled && event.payload.locked)),
+      listen('window-show-animation', refresh)
+    ];
+    return () => {
+      unlisteners.forEach(promise => promise.then(unlisten => unlisten()).catch(() => {}));
+    };
+  }, []);
+
+  if (!locked) return null;
+
+  const handleUnlock = async () => {
+    setBusy(true);
+    setError('');
+    try {
+      await unlockLocalEncryption(password);
+      setPassword('');
+      setLocked(false);
+    } catch (e) {
+      setError(formatUserMessage(e, t, 'errors.operationFailed'));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  return <div className="absolute inset-0 z-50 flex items-center justify-center bg-qc-surface">
+      <div className="w-64 flex flex-col items-center gap-3 text-center">
+        <i className="ti ti-lock text-3xl text-qc-fg-muted"></i>
+        <div className="text-sm font-medium text-qc-fg">{t('settings.dataManagement.localEncryptionLockedTitle')}</div>
+        <div className="text-xs text-qc-fg-muted">{t('settings.dataManagement.localEncryptionLockedHint')}</div>
+        <Input type="password" value={password} onChange={e => setPassword(e.target.value)} onKeyDown={e => e.key === 'Enter' && !busy && handleUnlock()} placeholder={t('settings.dataManagement.localEncryptionCurrentPassword')} className="w-full" autoFocus />
+        {error && <div className="text-xs text-red-500">{error}</div>}
+        <Button variant="primary" disabled={busy || !password} onClick={handleUnlock} className="w-full">
+          {busy ? t('settings.dataManagement.localEncryptionWorking') : t('settings.dataManagement.localEncryptionUnlockButton')}
+        </Button>
+      </div>
+    </div>;
+}
+
+export default LocalLockOverlay;
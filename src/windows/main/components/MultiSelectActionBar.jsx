@@ -12,6 +12,8 @@ import {
   deleteClipboardItems,
   mergeCopyClipboardItems,
   mergePasteClipboardItems,
+  enqueuePasteQueueItems,
+  getPasteQueueStatus,
 } from "@shared/api/clipboard";
 import {
   deleteFavoriteItems,
@@ -95,6 +97,24 @@ function MultiSelectActionBar({ activeTab }) {
       toast.error(error?.message || t("common.pasteFailed"), withToastConfig);
     }
   };
+  const handleAddToPasteQueue = async () => {
+    if (!selectedCount) return;
+    try {
+      await enqueuePasteQueueItems(selectedIds);
+      const status = await getPasteQueueStatus();
+      currentStore.exitMultiSelectMode();
+      toast.success(
+        t("multiSelect.addedToPasteQueue", { count: status.item_ids.length }),
+        withToastConfig,
+      );
+    } catch (error) {
+      console.error("加入粘贴队列失败:", error);
+      toast.error(
+        error?.message || t("common.operationFailed"),
+        withToastConfig,
+      );
+    }
+  };
   const handleDelete = async () => {
     if (!selectedCount) return;
     const confirmed = await showConfirm(
@@ -252,6 +272,31 @@ function MultiSelectActionBar({ activeTab }) {
             </button>
           </Tooltip>
 
+          {activeTab === "clipboard" && (
+            <Tooltip
+              content={
+                selectedCount
+                  ? t("multiSelect.addToPasteQueue")
+                  : getDisabledTooltip("selectFirst")
+              }
+              placement="top"
+              asChild
+            >
+              <button
+                className={makeActionButtonClasses(!selectedCount)}
+                onClick={handleAddToPasteQueue}
+                aria-disabled={!selectedCount}
+              >
+                <i
+                  className="ti ti-stack-2"
+                  style={{
+                    fontSize: 15,
+                  }}
+                ></i>
+              </button>
+            </Tooltip>
+          )}
+
           <Tooltip
             content={
               selectedCount
@@ -17,6 +17,7 @@ import {
 import { clipboardStore } from "@shared/store/clipboardStore";
 import { favoritesStore } from "@shared/store/favoritesStore";
 import { settingsStore } from "@shared/store/settingsStore";
+import { isCloudSyncConfigured } from "@shared/services/settingsService";
 import {
   showContextMenu,
   createMenuPlacementFromEvent,
@@ -231,7 +232,7 @@ const TitleBar = forwardRef(
       }
       if (
         !settingsSnap.webdavEnabled ||
-        !String(settingsSnap.webdavUrl || "").trim()
+        !isCloudSyncConfigured(settingsSnap)
       ) {
         toast.warning(t("settings.webdav.notReady"), TOAST_CONFIG);
         return;
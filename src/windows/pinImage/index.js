@@ -2,7 +2,8 @@
 
 import '@tabler/icons-webfont/dist/tabler-icons.min.css';
 import { getCurrentWindow } from '@tauri-apps/api/window';
-import { invoke, convertFileSrc } from '@tauri-apps/api/core';
+import { invoke } from '@tauri-apps/api/core';
+import { toLocalImageSrc } from '@shared/utils/localImageSrc';
 import { loadSettings, saveSettings } from './settings.js';
 import { createContextMenu } from './contextMenu.js';
 import { enterThumbnailMode, exitThumbnailMode } from './thumbnail.js';
@@ -90,7 +91,7 @@ import {
     try {
         const data = await invoke('get_pin_image_data', { window: currentWindow });
         if (data && data.file_path) {
-            const assetUrl = convertFileSrc(data.file_path, 'asset');
+            const assetUrl = toLocalImageSrc(data.file_path);
             
             await new Promise((resolve, reject) => {
                 img.onload = resolve;
@@ -170,7 +171,7 @@ import {
         currentWindow.listen('pin-image:refresh', async (event) => {
             const { file_path } = event.payload;
             if (!file_path) return;
-            img.src = convertFileSrc(file_path, 'asset') + '?t=' + Date.now();
+            img.src = toLocalImageSrc(file_path) + '?t=' + Date.now();
         });
     } catch (error) {
         console.error('加载图片失败:', error);
@@ -2,7 +2,8 @@ import { useEffect, useMemo, useRef, useState } from 'react';
 import { flushSync } from 'react-dom';
 import { useTranslation } from 'react-i18next';
 import { listen } from '@tauri-apps/api/event';
-import { convertFileSrc, invoke } from '@tauri-apps/api/core';
+import { invoke } from '@tauri-apps/api/core';
+import { toLocalImageSrc } from '@shared/utils/localImageSrc';
 import { useSnapshot } from 'valtio';
 import { defaultSettings } from '@shared/services/settingsService';
 import { settingsStore, initSettings } from '@shared/store/settingsStore';
@@ -133,7 +134,7 @@ async function resolveImageUrlFromItem(item) {
     const resolvedPath = parsedPath.includes(':') || parsedPath.startsWith('\\\\')
       ? parsedPath
       : await invoke('resolve_image_path', { storedPath: parsedPath });
-    return convertFileSrc(resolvedPath, 'asset');
+    return toLocalImageSrc(resolvedPath);
   }
 
   const imageId = parseFirstImageId(item?.image_id);
@@ -141,7 +142,7 @@ async function resolveImageUrlFromItem(item) {
     const dataDir = await invoke('get_data_directory');
     const normalizedDataDir = String(dataDir).replace(/\\/g, '/');
     const filePath = `${normalizedDataDir}/clipboard_images/${imageId}.png`;
-    return convertFileSrc(filePath, 'asset');
+    return toLocalImageSrc(filePath);
   }
 
   const rawPath = parseRawImagePath(content);
@@ -149,7 +150,7 @@ async function resolveImageUrlFromItem(item) {
     const resolvedPath = rawPath.includes(':') || rawPath.startsWith('\\\\')
       ? rawPath
       : await invoke('resolve_image_path', { storedPath: rawPath });
-    return convertFileSrc(resolvedPath, 'asset');
+    return toLocalImageSrc(resolvedPath);
   }
 
   if (rawPath.startsWith('image-id:')) {
@@ -157,7 +158,7 @@ async function resolveImageUrlFromItem(item) {
     const dataDir = await invoke('get_data_directory');
     const normalizedDataDir = String(dataDir).replace(/\\/g, '/');
     const filePath = `${normalizedDataDir}/clipboard_images/${legacyImageId}.png`;
-    return convertFileSrc(filePath, 'asset');
+    return toLocalImageSrc(filePath);
   }
 
   return '';
@@ -1090,7 +1091,7 @@ function App() {
     }
 
     try {
-      const assetUrl = convertFileSrc(backgroundImagePath, 'asset');
+      const assetUrl = toLocalImageSrc(backgroundImagePath);
       return {
         backgroundImage: `url("${assetUrl}")`,
         backgroundSize: 'cover',
@@ -1,5 +1,5 @@
 import { forwardRef, useCallback, useImperativeHandle, useLayoutEffect, useRef, useState } from 'react';
-import { convertFileSrc } from '@tauri-apps/api/core';
+import { toLocalImageSrc } from '@shared/utils/localImageSrc';
 import { Virtuoso } from 'react-virtuoso';
 import { formatFileSize } from '@shared/utils/format';
 
@@ -35,7 +35,7 @@ function FileIcon({ file, size = 28 }) {
   if (isImageFile && actualPath) {
     return (
       <img
-        src={convertFileSrc(actualPath, 'asset')}
+        src={toLocalImageSrc(actualPath)}
         alt={file?.name || '文件'}
         className="flex-shrink-0 rounded-sm object-cover"
         style={{
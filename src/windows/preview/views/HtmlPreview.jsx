@@ -1,5 +1,6 @@
 import { forwardRef, useEffect, useImperativeHandle, useLayoutEffect, useMemo, useRef } from 'react';
-import { convertFileSrc, invoke } from '@tauri-apps/api/core';
+import { invoke } from '@tauri-apps/api/core';
+import { toLocalImageSrc } from '@shared/utils/localImageSrc';
 import { sanitizeHTML } from '@shared/utils/htmlProcessor';
 import {
   HTML_MIN_WIDTH,
@@ -144,7 +145,7 @@ function copyChildNodes(source, target) {
 function resolveImageIdToAsset(imageId) {
   return invoke('get_data_directory').then((dataDir) => {
     const filePath = `${dataDir}/clipboard_images/${imageId}.png`;
-    return convertFileSrc(filePath, 'asset');
+    return toLocalImageSrc(filePath);
   });
 }
 
@@ -356,7 +357,7 @@ const HtmlPreview = forwardRef(function HtmlPreview(
           img.classList.add('html-image-pending');
           try {
             const filePath = `${dataDir}/clipboard_images/${imageId}.png`;
-            img.src = convertFileSrc(filePath, 'asset');
+            img.src = toLocalImageSrc(filePath);
           } catch (error) {
             console.error('加载本地图片失败，恢复原始src:', error, 'imageId:', imageId);
             img.src = originalSrc;
@@ -158,7 +158,7 @@ function App() {
         content = <AppFilterSection settings={snap} onSettingChange={handleSettingChange} />;
         break;
       case 'dataManagement':
-        content = <DataManagementSection />;
+        content = <DataManagementSection settings={snap} onSettingChange={handleSettingChange} />;
         break;
       case 'about':
         content = <AboutSection settings={snap} onSettingChange={handleSettingChange} />;
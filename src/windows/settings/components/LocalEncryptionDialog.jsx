@@ -0,0 +1,105 @@
+import '@tabler/icons-webfont/dist/tabler-icons.min.css';
+import { useTranslation } from 'react-i18next';
+import { useState } from 'react';
+import { createPortal } from 'react-dom';
+import Button from '@shared/components/ui/Button';
+import Input from '@shared/components/ui/Input';
+import { enableLocalEncryption, unlockLocalEncryption, rekeyLocalEncryption, disableLocalEncryption } from '@shared/api/dataManagement';
+import { formatUserMessage } from '@shared/utils/userMessages';
+
+// 各模式需要的输入框：当前密码 / 新密码 / 确认新密码
+const MODE_FIELDS = {
+  enable: { current: false, next: true },
+  unlock: { current: true, next: false },
+  rekey: { current: true, next: true },
+  disable: { current: true, next: false }
+};
+
+const MODE_TITLES = {
+  enable: 'localEncryptionEnableTitle',
+  unlock: 'localEncryptionUnlockTitle',
+  rekey: 'localEncryptionRekeyTitle',
+  disable: 'localEncryptionDisableTitle'
+};
+
+function LocalEncryptionDialog({
+  mode,
+  onClose,
+  onDone
+}) {
+  const {
+    t
+  } = useTranslation();
+  const [current, setCurrent] = useState('');
+  const [next, setNext] = useState('');
+  const [confirm, setConfirm] = useState('');
+  const [error, setError] = useState('');
+  const [busy, setBusy] = useState(false);
+  const fields = MODE_FIELDS[mode];
+
+  const handleSubmit = async () => {
+    if (fields.next && next !== confirm) {
+      setError(t('settings.dataManagement.localEncryptionMismatch'));
+      return;
+    }
+    setBusy(true);
+    setError('');
+    try {
+      if (mode === 'enable') await enableLocalEncryption(next);
+      if (mode === 'unlock') await unlockLocalEncryption(current);
+      if (mode === 'rekey') await rekeyLocalEncryption(current, next);
+      if (mode === 'disable') await disableLocalEncryption(current);
+      onDone?.();
+      onClose();
+    } catch (e) {
+      setError(formatUserMessage(e, t, 'errors.operationFailed'));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const handleKeyDown = e => {
+    if (e.key === 'Enter' && !busy) handleSubmit();
+  };
+
+  return createPortal(
+    <div className="fixed inset-0 z-[9999] bg-black/40 backdrop-blur-sm flex items-center justify-center">
+      <div className="bg-qc-surface rounded-xl p-6 shadow-xl max-w-md w-full mx-4 border border-qc-border">
+        <div className="flex items-center gap-3 mb-4">
+          <div className="w-10 h-10 rounded-full bg-blue-100 flex items-center justify-center">
+            <i className={`ti ${mode === 'disable' ? 'ti-lock-open' : 'ti-lock'} text-blue-600 text-xl`}></i>
+          </div>
+          <div className="flex-1 min-w-0">
+            <h3 className="text-lg font-semibold text-qc-fg">{t(`settings.dataManagement.${MODE_TITLES[mode]}`)}</h3>
+            {mode === 'enable' && <p className="text-sm text-qc-fg-muted">{t('settings.dataManagement.localEncryptionEnableHint')}</p>}
+          </div>
+        </div>
+
+        <div className="space-y-3">
+          {fields.current && (
+            <Input type="password" value={current} onChange={e => setCurrent(e.target.value)} onKeyDown={handleKeyDown} placeholder={t('settings.dataManagement.localEncryptionCurrentPassword')} className="w-full" autoFocus />
+          )}
+          {fields.next && (
+            <>
+              <Input type="password" value={next} onChange={e => setNext(e.target.value)} onKeyDown={handleKeyDown} placeholder={t('settings.dataManagement.localEncryptionNewPassword')} className="w-full" autoFocus={!fields.current} />
+              <Input type="password" value={confirm} onChange={e => setConfirm(e.target.value)} onKeyDown={handleKeyDown} placeholder={t('settings.dataManagement.localEncryptionConfirmPassword')} className="w-full" />
+            </>
+          )}
+          {error && <div className="text-sm text-red-500">{error}</div>}
+        </div>
+
+        <div className="mt-4 pt-4 border-t border-qc-border flex justify-end gap-2">
+          <button onClick={onClose} className="px-4 py-2 text-sm text-qc-fg-muted hover:text-qc-fg transition-colors">
+            {t('common.cancel')}
+          </button>
+          <Button variant={mode === 'disable' ? 'danger' : 'primary'} disabled={busy} onClick={handleSubmit}>
+            {busy ? t('settings.dataManagement.localEncryptionWorking') : t('common.confirm')}
+          </Button>
+        </div>
+      </div>
+    </div>,
+    document.body
+  );
+}
+
+export default LocalEncryptionDialog;
@@ -0,0 +1,145 @@
+import '@tabler/icons-webfont/dist/tabler-icons.min.css';
+import { useTranslation } from 'react-i18next';
+import { useEffect, useState } from 'react';
+import { createPortal } from 'react-dom';
+import { save } from '@tauri-apps/plugin-dialog';
+import Button from '@shared/components/ui/Button';
+import Select from '@shared/components/ui/Select';
+import MultiSegmentedControl from '@shared/components/ui/MultiSegmentedControl';
+import { exportPortable } from '@shared/api/dataManagement';
+import { getGroups } from '@shared/api/groups';
+import { showError, showMessage } from '@shared/utils/dialog';
+import { formatUserMessage } from '@shared/utils/userMessages';
+
+const FORMAT_EXTENSIONS = {
+  jsonl: 'jsonl',
+  csv: 'csv',
+  markdown: 'md'
+};
+
+// 日期输入转为秒级时间戳，结束日期包含当天
+const toSeconds = (date, endOfDay) => {
+  if (!date) return null;
+  const value = new Date(`${date}T${endOfDay ? '23:59:59' : '00:00:00'}`);
+  return Math.floor(value.getTime() / 1000);
+};
+
+// 按分组、时间范围和内容类型导出为开放格式
+function PortableExportDialog({
+  onClose
+}) {
+  const {
+    t
+  } = useTranslation();
+  const [format, setFormat] = useState('jsonl');
+  const [sources, setSources] = useState(['history', 'favorites']);
+  const [groups, setGroups] = useState([]);
+  const [selectedGroups, setSelectedGroups] = useState([]);
+  const [contentTypes, setContentTypes] = useState([]);
+  const [from, setFrom] = useState('');
+  const [to, setTo] = useState('');
+  const [busy, setBusy] = useState(false);
+  const messageOf = (error) => formatUserMessage(error, t, 'errors.operationFailed');
+
+  useEffect(() => {
+    getGroups().then(list => setGroups(list.map(g => g.name))).catch(() => setGroups([]));
+  }, []);
+
+  const handleExport = async () => {
+    try {
+      const ext = FORMAT_EXTENSIONS[format];
+      const target = await save({
+        defaultPath: `quickclipboard_export.${ext}`,
+        filters: [{ name: format.toUpperCase(), extensions: [ext] }]
+      });
+      if (!target) return;
+
+      const targetPath = target.toLowerCase().endsWith(`.${ext}`) ? target : `${target}.${ext}`;
+      setBusy(true);
+      const report = await exportPortable(targetPath, format, {
+        include_history: sources.includes('history'),
+        include_favorites: sources.includes('favorites'),
+        groups: selectedGroups,
+        content_types: contentTypes,
+        from: toSeconds(from, false),
+        to: toSeconds(to, true)
+      });
+      await showMessage(t('settings.dataManagement.portableExportSuccess', { clipboard: report.clipboard, favorites: report.favorites, path: targetPath }));
+      onClose();
+    } catch (e) {
+      await showError(t('settings.dataManagement.exportFailed', { message: messageOf(e) }));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  return createPortal(
+    <div className="fixed inset-0 z-[9999] bg-black/40 backdrop-blur-sm flex items-center justify-center">
+      <div className="bg-qc-surface rounded-xl p-6 shadow-xl max-w-lg w-full mx-4 max-h-[80vh] flex flex-col border border-qc-border">
+        <div className="flex items-center gap-3 mb-4">
+          <div className="w-10 h-10 rounded-full bg-blue-100 flex items-center justify-center">
+            <i className="ti ti-file-export text-blue-600 text-xl"></i>
+          </div>
+          <div className="flex-1 min-w-0">
+            <h3 className="text-lg font-semibold text-qc-fg">{t('settings.dataManagement.portableExport')}</h3>
+            <p className="text-sm text-qc-fg-muted">{t('settings.dataManagement.portableExportDesc')}</p>
+          </div>
+        </div>
+
+        <div className="flex-1 overflow-y-auto space-y-4 min-h-0">
+          <div>
+            <div className="text-sm font-medium text-qc-fg mb-2">{t('settings.dataManagement.portableFormat')}</div>
+            <Select value={format} onChange={setFormat} className="w-full" options={[
+              { value: 'jsonl', label: t('settings.dataManagement.portableFormatJsonl') },
+              { value: 'csv', label: t('settings.dataManagement.portableFormatCsv') },
+              { value: 'markdown', label: t('settings.dataManagement.portableFormatMarkdown') }
+            ]} />
+          </div>
+
+          <div>
+            <div className="text-sm font-medium text-qc-fg mb-2">{t('settings.dataManagement.portableSources')}</div>
+            <MultiSegmentedControl values={sources} onChange={setSources} wrap columns={2} options={[
+              { value: 'history', label: t('settings.dataManagement.snapshotKindClipboard') },
+              { value: 'favorites', label: t('settings.dataManagement.snapshotKindFavorite') }
+            ]} />
+          </div>
+
+          {sources.includes('favorites') && groups.length > 0 && (
+            <div>
+              <div className="text-sm font-medium text-qc-fg mb-1">{t('settings.dataManagement.portableGroups')}</div>
+              <div className="text-xs text-qc-fg-muted mb-2">{t('settings.dataManagement.portableGroupsDesc')}</div>
+              <MultiSegmentedControl values={selectedGroups} onChange={setSelectedGroups} wrap columns={2} options={groups.map(name => ({ value: name, label: name }))} />
+            </div>
+          )}
+
+          <div>
+            <div className="text-sm font-medium text-qc-fg mb-1">{t('settings.dataManagement.portableContentTypes')}</div>
+            <div className="text-xs text-qc-fg-muted mb-2">{t('settings.dataManagement.portableContentTypesDesc')}</div>
+            <MultiSegmentedControl values={contentTypes} onChange={setContentTypes} wrap columns={2} options={['text', 'image', 'file', 'link'].map(value => ({ value, label: t(`filter.${value}`) }))} />
+          </div>
+
+          <div>
+            <div className="text-sm font-medium text-qc-fg mb-2">{t('settings.dataManagement.portableDateRange')}</div>
+            <div className="flex items-center gap-2">
+              <input type="date" value={from} onChange={e => setFrom(e.target.value)} className="flex-1 px-3 py-1.5 text-sm border border-qc-border rounded-lg bg-qc-surface text-qc-fg" />
+              <span className="text-qc-fg-muted">-</span>
+              <input type="date" value={to} onChange={e => setTo(e.target.value)} className="flex-1 px-3 py-1.5 text-sm border border-qc-border rounded-lg bg-qc-surface text-qc-fg" />
+            </div>
+          </div>
+        </div>
+
+        <div className="mt-4 pt-4 border-t border-qc-border flex justify-end gap-2">
+          <button onClick={onClose} className="px-4 py-2 text-sm text-qc-fg-muted hover:text-qc-fg transition-colors">
+            {t('common.cancel')}
+          </button>
+          <Button variant="primary" disabled={busy || sources.length === 0} onClick={handleExport} icon={<i className="ti ti-download"></i>}>
+            {t('settings.dataManagement.exportButton')}
+          </Button>
+        </div>
+      </div>
+    </div>,
+    document.body
+  );
+}
+
+export default PortableExportDialog;
@@ -0,0 +1,177 @@
+import '@tabler/icons-webfont/dist/tabler-icons.min.css';
+import { useTranslation } from 'react-i18next';
+import { useEffect, useState } from 'react';
+import { createPortal } from 'react-dom';
+import Button from '@shared/components/ui/Button';
+import { listSnapshots, restoreSnapshot, listSnapshotItems, restoreSnapshotItems } from '@shared/api/dataManagement';
+import { showError, showMessage, showConfirm } from '@shared/utils/dialog';
+import { reloadAllWindows } from '@shared/api/window';
+import { formatUserMessage } from '@shared/utils/userMessages';
+
+// 快照列表：整体恢复，或进入条目列表选择部分条目恢复
+function SnapshotDialog({
+  onClose,
+  formatSize
+}) {
+  const {
+    t
+  } = useTranslation();
+  const [snapshots, setSnapshots] = useState([]);
+  const [picking, setPicking] = useState(null); // 正在选择条目的快照
+  const [kind, setKind] = useState('clipboard');
+  const [search, setSearch] = useState('');
+  const [items, setItems] = useState([]);
+  const [selected, setSelected] = useState(new Set());
+  const [busy, setBusy] = useState(false);
+  const messageOf = (error) => formatUserMessage(error, t, 'errors.operationFailed');
+
+  useEffect(() => {
+    listSnapshots().then(setSnapshots).catch(async e => {
+      await showError(messageOf(e));
+    });
+  }, []);
+
+  useEffect(() => {
+    if (!picking) return;
+    const timer = setTimeout(() => {
+      listSnapshotItems(picking.id, kind, search).then(setItems).catch(() => setItems([]));
+    }, 200);
+    return () => clearTimeout(timer);
+  }, [picking, kind, search]);
+
+  const formatTime = (secs) => new Date(secs * 1000).toLocaleString();
+  const itemKey = (item) => `${item.kind}:${item.id}`;
+
+  const handleRestoreAll = async (snapshot) => {
+    const ok = await showConfirm(t('settings.dataManagement.snapshotRestoreConfirm', { time: formatTime(snapshot.created_at) }));
+    if (!ok) return;
+    try {
+      setBusy(true);
+      await restoreSnapshot(snapshot.id);
+      await showMessage(t('settings.dataManagement.snapshotRestoreSuccess'));
+      try { await reloadAllWindows(); } catch (_) {}
+      onClose();
+    } catch (e) {
+      await showError(t('settings.dataManagement.snapshotRestoreFailed', { message: messageOf(e) }));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const toggleItem = (item) => {
+    setSelected(prev => {
+      const next = new Set(prev);
+      const key = itemKey(item);
+      if (next.has(key)) next.delete(key); else next.add(key);
+      return next;
+    });
+  };
+
+  const handleRestoreSelected = async () => {
+    const keys = [...selected];
+    const clipboardIds = keys.filter(k => k.startsWith('clipboard:')).map(k => Number(k.slice('clipboard:'.length)));
+    const favoriteIds = keys.filter(k => k.startsWith('favorite:')).map(k => k.slice('favorite:'.length));
+    try {
+      setBusy(true);
+      const report = await restoreSnapshotItems(picking.id, clipboardIds, favoriteIds);
+      await showMessage(t('settings.dataManagement.snapshotItemsRestored', { clipboard: report.clipboard, favorites: report.favorites }));
+      setSelected(new Set());
+    } catch (e) {
+      await showError(t('settings.dataManagement.snapshotRestoreFailed', { message: messageOf(e) }));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  return createPortal(
+    <div className="fixed inset-0 z-[9999] bg-black/40 backdrop-blur-sm flex items-center justify-center">
+      <div className="bg-qc-surface rounded-xl p-6 shadow-xl max-w-2xl w-full mx-4 max-h-[80vh] flex flex-col border border-qc-border">
+        <div className="flex items-center gap-3 mb-4">
+          <div className="w-10 h-10 rounded-full bg-blue-100 flex items-center justify-center">
+            <i className="ti ti-clock-record text-blue-600 text-xl"></i>
+          </div>
+          <div className="flex-1 min-w-0">
+            <h3 className="text-lg font-semibold text-qc-fg">
+              {picking ? t('settings.dataManagement.snapshotPickItems') : t('settings.dataManagement.snapshotList')}
+            </h3>
+            <p className="text-sm text-qc-fg-muted truncate">
+              {picking ? formatTime(picking.created_at) : t('settings.dataManagement.snapshotListDesc')}
+            </p>
+          </div>
+        </div>
+
+        {picking ? (
+          <>
+            <div className="flex gap-2 mb-3">
+              <select value={kind} onChange={e => { setKind(e.target.value); setSelected(new Set()); }} className="px-2 py-1 text-sm border border-qc-border rounded-lg bg-qc-surface text-qc-fg">
+                <option value="clipboard">{t('settings.dataManagement.snapshotKindClipboard')}</option>
+                <option value="favorite">{t('settings.dataManagement.snapshotKindFavorite')}</option>
+              </select>
+              <input value={search} onChange={e => setSearch(e.target.value)} placeholder={t('settings.dataManagement.snapshotSearch')} className="flex-1 px-3 py-1 text-sm border border-qc-border rounded-lg bg-qc-surface text-qc-fg" />
+            </div>
+            <div className="flex-1 overflow-y-auto space-y-1 min-h-0">
+              {items.map(item => (
+                <label key={itemKey(item)} className="flex items-start gap-3 p-2 border border-qc-border rounded-lg cursor-pointer hover:bg-qc-hover transition-colors">
+                  <input type="checkbox" checked={selected.has(itemKey(item))} onChange={() => toggleItem(item)} className="mt-1" />
+                  <div className="flex-1 min-w-0">
+                    <div className="text-sm text-qc-fg truncate">{item.title || item.preview}</div>
+                    <div className="text-xs text-qc-fg-muted flex gap-3">
+                      <span>{item.content_type}</span>
+                      <span>{formatTime(item.created_at)}</span>
+                    </div>
+                  </div>
+                </label>
+              ))}
+            </div>
+          </>
+        ) : (
+          <div className="flex-1 overflow-y-auto space-y-2 min-h-0">
+            {snapshots.length === 0 && (
+              <div className="text-sm text-qc-fg-muted text-center py-6">{t('settings.dataManagement.snapshotEmpty')}</div>
+            )}
+            {snapshots.map(snapshot => (
+              <div key={snapshot.id} className="flex items-center gap-3 p-3 border border-qc-border rounded-lg">
+                <i className="ti ti-database text-blue-500 text-xl"></i>
+                <div className="flex-1 min-w-0">
+                  <div className="font-medium text-qc-fg truncate">
+                    {formatTime(snapshot.created_at)}
+                    <span className="ml-2 text-xs text-qc-fg-muted">{t(`settings.dataManagement.snapshotTrigger_${snapshot.trigger}`)}</span>
+                  </div>
+                  <div className="text-xs text-qc-fg-muted flex gap-3">
+                    <span>{t('settings.dataManagement.snapshotCounts', { clipboard: snapshot.clipboard_count, favorites: snapshot.favorites_count, images: snapshot.image_count })}</span>
+                    <span>{formatSize(snapshot.db_size)}</span>
+                  </div>
+                </div>
+                <Button size="sm" variant="secondary" disabled={busy} onClick={() => { setPicking(snapshot); setSelected(new Set()); setSearch(''); }}>
+                  {t('settings.dataManagement.snapshotPickButton')}
+                </Button>
+                <Button size="sm" variant="danger" disabled={busy} onClick={() => handleRestoreAll(snapshot)}>
+                  {t('settings.dataManagement.snapshotRestoreButton')}
+                </Button>
+              </div>
+            ))}
+          </div>
+        )}
+
+        <div className="mt-4 pt-4 border-t border-qc-border flex justify-end gap-2">
+          {picking && (
+            <>
+              <button onClick={() => setPicking(null)} className="px-4 py-2 text-sm text-qc-fg-muted hover:text-qc-fg transition-colors">
+                {t('common.back')}
+              </button>
+              <Button variant="primary" disabled={busy || selected.size === 0} onClick={handleRestoreSelected}>
+                {t('settings.dataManagement.snapshotRestoreSelected', { count: selected.size })}
+              </Button>
+            </>
+          )}
+          <button onClick={onClose} className="px-4 py-2 text-sm text-qc-fg-muted hover:text-qc-fg transition-colors">
+            {t('common.close')}
+          </button>
+        </div>
+      </div>
+    </div>,
+    document.body
+  );
+}
+
+export default SnapshotDialog;
@@ -6,6 +6,15 @@ import Select from '@shared/components/ui/Select';
 import Input from '@shared/components/ui/Input';
 import Textarea from '@shared/components/ui/Textarea';
 import MultiSegmentedControl from '@shared/components/ui/MultiSegmentedControl';
+import Button from '@shared/components/ui/Button';
+
+// 与后端 RetentionRule 默认值一致
+const DEFAULT_RETENTION_RULE = {
+  enabled: true,
+  contentType: 'all',
+  maxAgeDays: 30,
+  includePinned: false
+};
 function ClipboardSection({
   settings,
   onSettingChange
@@ -69,6 +78,25 @@ function ClipboardSection({
       filePreview: nextPreviewValues.includes('filePreview')
     });
   };
+  const retentionRules = Array.isArray(settings.retentionRules) ? settings.retentionRules : [];
+  const retentionTypeOptions = ['all', 'text', 'rich_text', 'image', 'file', 'link'].map(value => ({
+    value,
+    label: t(`settings.clipboard.retentionType.${value}`)
+  }));
+  const updateRetentionRule = (index, patch) => {
+    onSettingChange('retentionRules', retentionRules.map((rule, i) => i === index ? {
+      ...rule,
+      ...patch
+    } : rule));
+  };
+  const addRetentionRule = () => {
+    onSettingChange('retentionRules', [...retentionRules, {
+      ...DEFAULT_RETENTION_RULE
+    }]);
+  };
+  const removeRetentionRule = index => {
+    onSettingChange('retentionRules', retentionRules.filter((_, i) => i !== index));
+  };
   return <>
       <SettingsSection title={t('settings.clipboard.title')} description={t('settings.clipboard.description')}>
         <SettingItem label={t('settings.clipboard.monitor')} description={t('settings.clipboard.monitorDesc')}>
@@ -162,6 +190,41 @@ function ClipboardSection({
           <Toggle checked={settings.modifierClickMultiSelect !== false} onChange={checked => onSettingChange('modifierClickMultiSelect', checked)} />
         </SettingItem>
       </SettingsSection>
+
+      <SettingsSection title={t('settings.clipboard.retentionTitle')} description={t('settings.clipboard.retentionDescription')}>
+        <SettingItem label={t('settings.clipboard.retentionRules')} description={t('settings.clipboard.retentionRulesDesc')} stacked>
+          <div className="flex flex-col gap-2">
+            {retentionRules.map((rule, index) => <div key={index} className="flex flex-wrap items-center gap-3 rounded-lg border border-qc-border bg-qc-panel-2 px-3 py-2">
+                <Toggle checked={rule.enabled !== false} onChange={checked => updateRetentionRule(index, {
+              enabled: checked
+            })} />
+                <Select value={rule.contentType || 'all'} onChange={value => updateRetentionRule(index, {
+              contentType: value
+            })} options={retentionTypeOptions} className="w-32" />
+                <Input type="number" value={rule.maxAgeDays ?? DEFAULT_RETENTION_RULE.maxAgeDays} onChange={e => updateRetentionRule(index, {
+              maxAgeDays: Math.max(1, parseInt(e.target.value) || DEFAULT_RETENTION_RULE.maxAgeDays)
+            })} min={1} max={3650} className="w-24" suffix={t('settings.clipboard.retentionDays')} />
+                <label className="flex items-center gap-2 text-sm text-qc-fg-muted">
+                  <Toggle checked={rule.includePinned === true} onChange={checked => updateRetentionRule(index, {
+                includePinned: checked
+              })} />
+                  {t('settings.clipboard.retentionIncludePinned')}
+                </label>
+                <button type="button" onClick={() => removeRetentionRule(index)} className="ml-auto inline-flex h-7 w-7 items-center justify-center rounded text-qc-fg-muted transition-colors hover:bg-qc-hover hover:text-red-500" aria-label={t('settings.clipboard.retentionRemove')}>
+                  <i className="ti ti-trash"></i>
+                </button>
+              </div>)}
+            {retentionRules.length === 0 && <p className="text-xs text-qc-fg-subtle">
+                {t('settings.clipboard.retentionEmpty')}
+              </p>}
+            <div>
+              <Button variant="secondary" size="sm" icon={<i className="ti ti-plus"></i>} onClick={addRetentionRule}>
+                {t('settings.clipboard.retentionAdd')}
+              </Button>
+            </div>
+          </div>
+        </SettingItem>
+      </SettingsSection>
     </>;
 }
 export default ClipboardSection;
@@ -5,16 +5,25 @@ import { createPortal } from 'react-dom';
 import SettingsSection from '../components/SettingsSection';
 import SettingItem from '../components/SettingItem';
 import Button from '@shared/components/ui/Button';
+import Input from '@shared/components/ui/Input';
+import Select from '@shared/components/ui/Select';
 import { open, save } from '@tauri-apps/plugin-dialog';
 import { openPath } from '@tauri-apps/plugin-opener';
-import { getCurrentStoragePath, getDefaultStoragePath, changeStoragePath, resetStoragePathToDefault, exportDataZip, importDataZip, resetAllData, checkTargetHasData, listBackups } from '@shared/api/dataManagement';
+import { listen } from '@tauri-apps/api/event';
+import { getCurrentStoragePath, getDefaultStoragePath, changeStoragePath, resetStoragePathToDefault, exportDataZip, importDataZip, resetAllData, checkTargetHasData, listBackups, collectStorageGarbage, createSnapshot, verifySnapshots, importPortable, importExternal, getLocalEncryptionStatus, lockLocalEncryption } from '@shared/api/dataManagement';
 import { showError, showMessage, showConfirm } from '@shared/utils/dialog';
 import { reloadAllWindows } from '@shared/api/window';
 import { resetSettingsToDefault } from '@shared/api/settings';
 import { isPortableMode } from '@shared/api/system';
 import { clearClipboardHistory } from '@shared/api/clipboard';
 import { formatUserMessage } from '@shared/utils/userMessages';
-function DataManagementSection() {
+import SnapshotDialog from '../components/SnapshotDialog';
+import PortableExportDialog from '../components/PortableExportDialog';
+import LocalEncryptionDialog from '../components/LocalEncryptionDialog';
+function DataManagementSection({
+  settings,
+  onSettingChange
+}) {
   const {
     t
   } = useTranslation();
@@ -25,6 +34,11 @@ function DataManagementSection() {
   const [busyText, setBusyText] = useState('');
   const [migrationDialog, setMigrationDialog] = useState(null); // { type: 'change' | 'reset', targetPath?: string, targetInfo?: object }
   const [backupDialog, setBackupDialog] = useState(null); // { backups: [] }
+  const [snapshotDialogOpen, setSnapshotDialogOpen] = useState(false);
+  const [portableExportOpen, setPortableExportOpen] = useState(false);
+  const [externalSource, setExternalSource] = useState('ditto');
+  const [encryptionStatus, setEncryptionStatus] = useState({ enabled: false, locked: false });
+  const [encryptionDialog, setEncryptionDialog] = useState(null); // 'enable' | 'unlock' | 'rekey' | 'disable'
   const messageOf = (error) => formatUserMessage(error, t, 'errors.operationFailed');
 
   const formatSize = (bytes) => {
@@ -48,6 +62,18 @@ function DataManagementSection() {
     })();
   }, []);
 
+  const refreshEncryptionStatus = () => {
+    getLocalEncryptionStatus().then(setEncryptionStatus).catch(() => {});
+  };
+
+  useEffect(() => {
+    refreshEncryptionStatus();
+    const unlistenPromise = listen('local-encryption-changed', event => setEncryptionStatus(event.payload));
+    return () => {
+      unlistenPromise.then(unlisten => unlisten()).catch(() => {});
+    };
+  }, []);
+
   const handleExportData = async () => {
     try {
       const ts = new Date();
@@ -84,6 +110,46 @@ function DataManagementSection() {
     }
   };
 
+  const handleImportPortable = async () => {
+    try {
+      const file = await open({ multiple: false, filters: [{ name: 'JSON Lines / CSV / Markdown', extensions: ['jsonl', 'ndjson', 'json', 'csv', 'md', 'markdown'] }] });
+      if (!file) return;
+      setBusyText(t('settings.dataManagement.overlayImporting'));
+      setBusy(true);
+      const report = await importPortable(file);
+      await showMessage(t('settings.dataManagement.portableImportSuccess', report));
+    } catch (e) {
+      await showError(t('settings.dataManagement.importFailed', { message: messageOf(e) }));
+    } finally {
+      setBusy(false);
+      setBusyText('');
+    }
+  };
+
+  const handleImportExternal = async () => {
+    try {
+      // CopyQ 每个标签页一个文件，直接选择配置目录
+      const path = externalSource === 'copyq'
+        ? await open({ directory: true, multiple: false })
+        : await open({
+          multiple: false,
+          filters: externalSource === 'ditto'
+            ? [{ name: 'Ditto', extensions: ['db'] }]
+            : [{ name: 'JSON', extensions: ['json'] }]
+        });
+      if (!path) return;
+      setBusyText(t('settings.dataManagement.overlayImporting'));
+      setBusy(true);
+      const report = await importExternal(externalSource, path);
+      await showMessage(t('settings.dataManagement.portableImportSuccess', report));
+    } catch (e) {
+      await showError(t('settings.dataManagement.importFailed', { message: messageOf(e) }));
+    } finally {
+      setBusy(false);
+      setBusyText('');
+    }
+  };
+
   const handleImportFromBackup = async () => {
     try {
       const backups = await listBackups();
@@ -239,6 +305,99 @@ function DataManagementSection() {
     }
   };
 
+  const handleStorageGc = async () => {
+    try {
+      setBusyText(t('settings.dataManagement.overlayScanning'));
+      setBusy(true);
+      const report = await collectStorageGarbage(true);
+      setBusy(false);
+      const reclaimable = report.orphan_image_bytes + report.orphan_raw_format_bytes + report.database_free_bytes;
+      const ok = await showConfirm(t('settings.dataManagement.storageGcConfirm', {
+        images: report.orphan_image_count,
+        imagesSize: formatSize(report.orphan_image_bytes),
+        rawFormats: report.orphan_raw_format_count,
+        rawFormatsSize: formatSize(report.orphan_raw_format_bytes),
+        tombstones: report.expired_tombstone_count,
+        total: formatSize(reclaimable)
+      }));
+      if (!ok) return;
+      setBusyText(t('settings.dataManagement.overlayStorageGc'));
+      setBusy(true);
+      const result = await collectStorageGarbage(false);
+      const freed = Math.max(0, result.database_bytes_before - result.database_bytes_after) + result.orphan_image_bytes;
+      await showMessage(t('settings.dataManagement.storageGcSuccess', { size: formatSize(freed) }));
+    } catch (e) {
+      await showError(t('settings.dataManagement.storageGcFailed', { message: messageOf(e) }));
+    } finally {
+      setBusy(false);
+      setBusyText('');
+    }
+  };
+
+  const handleCreateSnapshot = async () => {
+    try {
+      setBusyText(t('settings.dataManagement.overlaySnapshot'));
+      setBusy(true);
+      const manifest = await createSnapshot();
+      await showMessage(t('settings.dataManagement.snapshotCreated', { clipboard: manifest.clipboard_count, favorites: manifest.favorites_count }));
+    } catch (e) {
+      await showError(t('settings.dataManagement.snapshotFailed', { message: messageOf(e) }));
+    } finally {
+      setBusy(false);
+      setBusyText('');
+    }
+  };
+
+  const handleVerifySnapshots = async () => {
+    try {
+      setBusyText(t('settings.dataManagement.overlayVerifying'));
+      setBusy(true);
+      const results = await verifySnapshots();
+      const broken = results.filter(r => !r.ok);
+      if (broken.length === 0) {
+        await showMessage(t('settings.dataManagement.snapshotVerifyOk', { count: results.length }));
+      } else {
+        await showError(t('settings.dataManagement.snapshotVerifyBroken', {
+          count: broken.length,
+          details: broken.map(r => `${r.id}: ${r.problems.join('; ')}`).join('\n')
+        }));
+      }
+    } catch (e) {
+      await showError(messageOf(e));
+    } finally {
+      setBusy(false);
+      setBusyText('');
+    }
+  };
+
+  const handleLockNow = async () => {
+    try {
+      setBusy(true);
+      setBusyText(t('settings.dataManagement.localEncryptionWorking'));
+      await lockLocalEncryption();
+      refreshEncryptionStatus();
+    } catch (e) {
+      await showError(messageOf(e));
+    } finally {
+      setBusy(false);
+      setBusyText('');
+    }
+  };
+
+  const handleSnapshotNumberCommit = (key, min, max) => value => {
+    const num = Math.trunc(Number(String(value).trim()));
+    const normalized = Number.isFinite(num) && num >= min && num <= max ? num : settings[key];
+    onSettingChange(key, normalized);
+    return String(normalized);
+  };
+
+  const handleRetentionDaysCommit = value => {
+    const days = Math.trunc(Number(String(value).trim()));
+    const normalizedDays = Number.isFinite(days) && days >= 1 && days <= 3650 ? days : settings.tombstoneRetentionDays;
+    onSettingChange('tombstoneRetentionDays', normalizedDays);
+    return String(normalizedDays);
+  };
+
   const handleResetSettings = async () => {
     const ok = await showConfirm(t('settings.dataManagement.resetConfirm'));
     if (!ok) return;
@@ -286,6 +445,12 @@ function DataManagementSection() {
             {t('settings.dataManagement.exportButton')}
           </Button>
         </SettingItem>
+
+        <SettingItem label={t('settings.dataManagement.portableExport')} description={t('settings.dataManagement.portableExportDesc')}>
+          <Button onClick={() => setPortableExportOpen(true)} disabled={busy} variant="secondary" icon={<i className="ti ti-file-export"></i>}>
+            {t('settings.dataManagement.portableExportButton')}
+          </Button>
+        </SettingItem>
       </SettingsSection>
 
       {/* 数据导入 */}
@@ -301,6 +466,25 @@ function DataManagementSection() {
           </div>
         </SettingItem>
 
+        <SettingItem label={t('settings.dataManagement.portableImport')} description={t('settings.dataManagement.portableImportDesc')}>
+          <Button onClick={handleImportPortable} disabled={busy} variant="secondary" icon={<i className="ti ti-file-import"></i>}>
+            {t('settings.dataManagement.selectFile')}
+          </Button>
+        </SettingItem>
+
+        <SettingItem label={t('settings.dataManagement.externalImport')} description={t('settings.dataManagement.externalImportDesc')}>
+          <div className="flex gap-2">
+            <Select value={externalSource} onChange={setExternalSource} options={[
+              { value: 'ditto', label: 'Ditto' },
+              { value: 'copyq', label: 'CopyQ' },
+              { value: 'clipboard_history_json', label: t('settings.dataManagement.externalSourceJson') }
+            ]} />
+            <Button onClick={handleImportExternal} disabled={busy} variant="secondary" icon={<i className="ti ti-file-import"></i>}>
+              {t('settings.dataManagement.externalImportButton')}
+            </Button>
+          </div>
+        </SettingItem>
+
         <SettingItem stacked label={t('settings.dataManagement.importMode')} description={t('settings.dataManagement.importModeDesc')}>
           <div className="flex flex-col gap-2">
             <label className="flex items-start gap-3 p-3 border border-qc-border rounded-lg cursor-pointer hover:bg-qc-hover transition-colors">
@@ -329,6 +513,68 @@ function DataManagementSection() {
         </SettingItem>
       </SettingsSection>
 
+      {/* 快照备份 */}
+      <SettingsSection title={t('settings.dataManagement.snapshotTitle')} description={t('settings.dataManagement.snapshotDesc')}>
+        <SettingItem label={t('settings.dataManagement.snapshotInterval')} description={t('settings.dataManagement.snapshotIntervalDesc')}>
+          <Input type="number" value={String(settings.snapshotIntervalHours)} commitOnBlur onCommit={handleSnapshotNumberCommit('snapshotIntervalHours', 0, 720)} min={0} max={720} className="w-40" />
+        </SettingItem>
+
+        <SettingItem label={t('settings.dataManagement.snapshotKeep')} description={t('settings.dataManagement.snapshotKeepDesc')}>
+          <Input type="number" value={String(settings.snapshotKeep)} commitOnBlur onCommit={handleSnapshotNumberCommit('snapshotKeep', 1, 100)} min={1} max={100} className="w-40" />
+        </SettingItem>
+
+        <SettingItem label={t('settings.dataManagement.snapshotManage')} description={t('settings.dataManagement.snapshotManageDesc')}>
+          <div className="flex gap-2">
+            <Button onClick={handleCreateSnapshot} disabled={busy} variant="primary" icon={<i className="ti ti-camera"></i>}>
+              {t('settings.dataManagement.snapshotCreateButton')}
+            </Button>
+            <Button onClick={() => setSnapshotDialogOpen(true)} disabled={busy} variant="secondary" icon={<i className="ti ti-clock-record"></i>}>
+              {t('settings.dataManagement.snapshotRestoreEntry')}
+            </Button>
+            <Button onClick={handleVerifySnapshots} disabled={busy} variant="secondary" icon={<i className="ti ti-shield-check"></i>}>
+              {t('settings.dataManagement.snapshotVerifyButton')}
+            </Button>
+          </div>
+        </SettingItem>
+      </SettingsSection>
+
+      {/* 本地加密 */}
+      <SettingsSection title={t('settings.dataManagement.localEncryptionTitle')} description={t('settings.dataManagement.localEncryptionDesc')}>
+        <SettingItem label={t('settings.dataManagement.localEncryptionStatus')} description={t(`settings.dataManagement.${!encryptionStatus.enabled ? 'localEncryptionStatusOff' : encryptionStatus.locked ? 'localEncryptionStatusLocked' : 'localEncryptionStatusUnlocked'}`)}>
+          <div className="flex gap-2">
+            {!encryptionStatus.enabled && (
+              <Button onClick={() => setEncryptionDialog('enable')} disabled={busy} variant="primary" icon={<i className="ti ti-lock"></i>}>
+                {t('settings.dataManagement.localEncryptionEnableButton')}
+              </Button>
+            )}
+            {encryptionStatus.enabled && encryptionStatus.locked && (
+              <Button onClick={() => setEncryptionDialog('unlock')} disabled={busy} variant="primary" icon={<i className="ti ti-lock-open"></i>}>
+                {t('settings.dataManagement.localEncryptionUnlockButton')}
+              </Button>
+            )}
+            {encryptionStatus.enabled && !encryptionStatus.locked && (
+              <>
+                <Button onClick={handleLockNow} disabled={busy} variant="primary" icon={<i className="ti ti-lock"></i>}>
+                  {t('settings.dataManagement.localEncryptionLockButton')}
+                </Button>
+                <Button onClick={() => setEncryptionDialog('rekey')} disabled={busy} variant="secondary" icon={<i className="ti ti-key"></i>}>
+                  {t('settings.dataManagement.localEncryptionRekeyButton')}
+                </Button>
+                <Button onClick={() => setEncryptionDialog('disable')} disabled={busy} variant="danger" icon={<i className="ti ti-lock-off"></i>}>
+                  {t('settings.dataManagement.localEncryptionDisableButton')}
+                </Button>
+              </>
+            )}
+          </div>
+        </SettingItem>
+
+        {encryptionStatus.enabled && (
+          <SettingItem label={t('settings.dataManagement.localEncryptionIdle')} description={t('settings.dataManagement.localEncryptionIdleDesc')}>
+            <Input type="number" value={String(settings.localEncryptionIdleMinutes)} commitOnBlur onCommit={handleSnapshotNumberCommit('localEncryptionIdleMinutes', 0, 1440)} min={0} max={1440} className="w-40" />
+          </SettingItem>
+        )}
+      </SettingsSection>
+
       {/* 数据存储位置 */}
       <SettingsSection title={t('settings.dataManagement.storageTitle')} description={t('settings.dataManagement.storageDesc')}>
         <SettingItem label={t('settings.dataManagement.currentPath')} description={storagePath}>
@@ -358,6 +604,16 @@ function DataManagementSection() {
           </Button>
         </SettingItem>
 
+        <SettingItem label={t('settings.dataManagement.storageGc')} description={t('settings.dataManagement.storageGcDesc')}>
+          <Button onClick={handleStorageGc} disabled={busy} variant="secondary" icon={<i className="ti ti-recycle"></i>}>
+            {t('settings.dataManagement.storageGcButton')}
+          </Button>
+        </SettingItem>
+
+        <SettingItem label={t('settings.dataManagement.tombstoneRetentionDays')} description={t('settings.dataManagement.tombstoneRetentionDaysDesc')}>
+          <Input type="number" value={String(settings.tombstoneRetentionDays)} commitOnBlur onCommit={handleRetentionDaysCommit} min={1} max={3650} className="w-40" />
+        </SettingItem>
+
         <SettingItem label={t('settings.dataManagement.resetSettings')} description={t('settings.dataManagement.resetSettingsDesc')}>
           <Button onClick={handleResetSettings} variant="danger" icon={<i className="ti ti-restore"></i>}>
             {t('settings.dataManagement.resetButton')}
@@ -381,6 +637,12 @@ function DataManagementSection() {
         document.body
       )}
 
+      {portableExportOpen && <PortableExportDialog onClose={() => setPortableExportOpen(false)} />}
+
+      {encryptionDialog && <LocalEncryptionDialog mode={encryptionDialog} onClose={() => setEncryptionDialog(null)} onDone={refreshEncryptionStatus} />}
+
+      {snapshotDialogOpen && <SnapshotDialog onClose={() => setSnapshotDialogOpen(false)} formatSize={formatSize} />}
+
       {/* 迁移模式选择对话框 */}
       {migrationDialog && createPortal(
         <div className="fixed inset-0 z-[9999] bg-black/40 backdrop-blur-sm flex items-center justify-center">
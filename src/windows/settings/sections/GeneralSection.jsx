@@ -5,7 +5,9 @@ import SettingItem from '../components/SettingItem';
 import Toggle from '@shared/components/ui/Toggle';
 import Select from '@shared/components/ui/Select';
 import PresetInput from '@shared/components/ui/PresetInput';
-import { setAutoStart, getAutoStartStatus, setRunAsAdmin, getRunAsAdminStatus, restartAsAdmin, isRunningAsAdmin } from '@shared/api/settings';
+import Input from '@shared/components/ui/Input';
+import Button from '@shared/components/ui/Button';
+import { setAutoStart, getAutoStartStatus, setRunAsAdmin, getRunAsAdminStatus, restartAsAdmin, isRunningAsAdmin, getLocalApiStatus, regenerateLocalApiToken } from '@shared/api/settings';
 import { toast } from '@shared/store/toastStore';
 import { showConfirm } from '@shared/utils/dialog';
 import { formatUserMessage } from '@shared/utils/userMessages';
@@ -24,6 +26,7 @@ function GeneralSection({
   const [autoStartMismatch, setAutoStartMismatch] = useState(false);
   const [runAsAdminLoading, setRunAsAdminLoading] = useState(false);
   const [currentlyRunningAsAdmin, setCurrentlyRunningAsAdmin] = useState(false);
+  const [localApiToken, setLocalApiToken] = useState('');
 
   // 初同步自启动状态和管理员权限状态
   useEffect(() => {
@@ -60,6 +63,13 @@ function GeneralSection({
     };
     syncStatuses();
   }, []);
+
+  useEffect(() => {
+    if (!settings.localApiEnabled) return;
+    getLocalApiStatus().then(status => setLocalApiToken(status.token)).catch(error => {
+      console.error('获取本机控制接口状态失败:', error);
+    });
+  }, [settings.localApiEnabled]);
   const historyLimitOptions = [{
     value: '50',
     label: `50 ${t('settings.general.items')}`
@@ -147,6 +157,31 @@ function GeneralSection({
       toast.error(t('settings.general.languageChangeFailed'));
     }
   };
+  const handleLocalApiPortCommit = value => {
+    const port = Math.trunc(Number(String(value).trim()));
+    const normalizedPort = Number.isFinite(port) && port >= 1024 && port <= 65535 ? port : settings.localApiPort;
+    onSettingChange('localApiPort', normalizedPort);
+    return String(normalizedPort);
+  };
+  const handleCopyLocalApiToken = async () => {
+    try {
+      await navigator.clipboard.writeText(localApiToken);
+      toast.success(t('settings.general.localApiTokenCopied'));
+    } catch (error) {
+      console.error('复制本机控制接口令牌失败:', error);
+    }
+  };
+  const handleRegenerateLocalApiToken = async () => {
+    const confirmed = await showConfirm(t('settings.general.localApiRegenerateConfirm'), t('settings.general.localApiRegenerate'));
+    if (!confirmed) return;
+    try {
+      setLocalApiToken(await regenerateLocalApiToken());
+      toast.success(t('settings.general.localApiTokenRegenerated'));
+    } catch (error) {
+      console.error('重新生成本机控制接口令牌失败:', error);
+      toast.error(formatUserMessage(error, t, 'settings.general.localApiTokenRegenerateFailed'));
+    }
+  };
   const handleHistoryLimitCommit = value => {
     const rawValue = String(value).trim();
     const currentValue = settings.historyLimit ?? 100;
@@ -215,6 +250,27 @@ function GeneralSection({
           className="w-40"
         />
       </SettingItem>
+
+      <SettingItem label={t('settings.general.localApi')} description={t('settings.general.localApiDesc')}>
+        <Toggle checked={settings.localApiEnabled} onChange={checked => onSettingChange('localApiEnabled', checked)} />
+      </SettingItem>
+
+      {settings.localApiEnabled && <>
+          <SettingItem label={t('settings.general.localApiPort')} description={t('settings.general.localApiPortDesc', { port: settings.localApiPort })}>
+            <Input type="number" value={String(settings.localApiPort)} commitOnBlur onCommit={handleLocalApiPortCommit} min={1024} max={65535} className="w-40" />
+          </SettingItem>
+
+          <SettingItem label={t('settings.general.localApiToken')} description={t('settings.general.localApiTokenDesc')}>
+            <div className="flex items-center gap-2">
+              <Button size="sm" variant="secondary" onClick={handleCopyLocalApiToken} disabled={!localApiToken}>
+                {t('settings.general.localApiCopyToken')}
+              </Button>
+              <Button size="sm" variant="secondary" onClick={handleRegenerateLocalApiToken}>
+                {t('settings.general.localApiRegenerate')}
+              </Button>
+            </div>
+          </SettingItem>
+        </>}
     </SettingsSection>;
 }
 export default GeneralSection;
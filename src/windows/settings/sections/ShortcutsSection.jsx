@@ -119,6 +119,12 @@ function ShortcutsSection({ settings, onSettingChange, activeTab }) {
             <SettingItem label={t('settings.shortcuts.pastePlainText')} description={t('settings.shortcuts.pastePlainTextDesc')}>
               <ShortcutComboInput value={settings.pastePlainTextShortcut} onChange={value => handleShortcutChange('pastePlainTextShortcut', value)} modifierOptions={['Ctrl', 'Shift', 'Alt']} disabledKeys={['C', 'X', 'A', 'Z', 'Y']} hasError={hasErrorStatus('pastePlainTextShortcut', 'paste_plain_text')} errorMessage={getErrorMessage('pastePlainTextShortcut', 'paste_plain_text')} />
             </SettingItem>
+            <SettingItem label={t('settings.shortcuts.pasteQueue')} description={t('settings.shortcuts.pasteQueueDesc')}>
+              <ShortcutInput value={settings.pasteQueueShortcut} onChange={value => handleShortcutChange('pasteQueueShortcut', value)} onReset={() => handleShortcutChange('pasteQueueShortcut', '')} hasError={hasErrorStatus('pasteQueueShortcut', 'paste_queue')} errorMessage={getErrorMessage('pasteQueueShortcut', 'paste_queue')} />
+            </SettingItem>
+            <SettingItem label={t('settings.shortcuts.pasteQueueOrder')} description={t('settings.shortcuts.pasteQueueOrderDesc')}>
+              <Select value={settings.pasteQueueOrder} onChange={value => onSettingChange('pasteQueueOrder', value)} options={[{ value: 'fifo', label: t('settings.shortcuts.pasteQueueOrderFifo') }, { value: 'lifo', label: t('settings.shortcuts.pasteQueueOrderLifo') }]} className="w-56" />
+            </SettingItem>
           </SettingsSection>
         );
 
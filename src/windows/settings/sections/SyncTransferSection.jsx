@@ -12,6 +12,7 @@ import {
   discoverSyncTransferLanPeers,
   fetchSyncTransferLanPeerSnapshot,
   getSyncTransferLanAutoSyncStatus,
+  getSyncTransferLanLiveShareStatus,
   getSyncTransferLanStatus,
   getSyncTransferLanLocalSnapshot,
   getSyncTransferModeInfos,
@@ -21,6 +22,8 @@ import {
   refreshSyncTransferLanPairingCode,
   removeSyncTransferLanPairedPeer,
   updateSyncTransferLanAutoSyncSettings,
+  updateSyncTransferLanLiveShareSettings,
+  updateSyncTransferLanPeerSyncPolicy,
 } from '@shared/api/syncTransfer';
 import { toast } from '@shared/store/toastStore';
 import WebdavSection from './WebdavSection';
@@ -36,6 +39,7 @@ function SyncTransferSection({ settings, onSettingChange }) {
   const [lastActionReport, setLastActionReport] = useState(null);
   const [localSnapshot, setLocalSnapshot] = useState(null);
   const [autoSyncStatus, setAutoSyncStatus] = useState(null);
+  const [liveShareStatus, setLiveShareStatus] = useState(null);
   const [discoveredPeers, setDiscoveredPeers] = useState([]);
   const [peerBaseUrl, setPeerBaseUrl] = useState('');
   const [peerPairingCode, setPeerPairingCode] = useState('');
@@ -73,6 +77,11 @@ function SyncTransferSection({ settings, onSettingChange }) {
     } catch {
       setAutoSyncStatus(null);
     }
+    try {
+      setLiveShareStatus(await getSyncTransferLanLiveShareStatus());
+    } catch {
+      setLiveShareStatus(null);
+    }
   };
 
   useEffect(() => {
@@ -196,6 +205,7 @@ function SyncTransferSection({ settings, onSettingChange }) {
           status={lanStatus}
           localSnapshot={localSnapshot}
           autoSyncStatus={autoSyncStatus}
+          liveShareStatus={liveShareStatus}
           peers={pairedPeers}
           busy={lanBusy}
           onRefresh={() => runLanAction('refresh', refreshSyncTransferLanPairingCode)}
@@ -220,7 +230,9 @@ function SyncTransferSection({ settings, onSettingChange }) {
           pairingCodeVisible={pairingCodeVisible}
           onTogglePairingCodeVisible={() => setPairingCodeVisible(value => !value)}
           onRemovePeer={deviceId => runLanAction(`remove-${deviceId}`, () => removeSyncTransferLanPairedPeer(deviceId))}
+          onUpdatePeerPolicy={(deviceId, policy) => runLanAction(`policy-${deviceId}`, () => updateSyncTransferLanPeerSyncPolicy(deviceId, policy))}
           onUpdateAutoSync={settings => runLanAction('updateAutoSync', () => updateSyncTransferLanAutoSyncSettings(settings))}
+          onUpdateLiveShare={settings => runLanAction('updateLiveShare', () => updateSyncTransferLanLiveShareSettings(settings))}
           lastActionReport={lastActionReport}
           onClearActionReport={() => setLastActionReport(null)}
           t={t}
@@ -234,6 +246,7 @@ function LanModePanel({
   status,
   localSnapshot,
   autoSyncStatus,
+  liveShareStatus,
   peers,
   busy,
   peerBaseUrl,
@@ -249,7 +262,9 @@ function LanModePanel({
   pairingCodeVisible,
   onTogglePairingCodeVisible,
   onRemovePeer,
+  onUpdatePeerPolicy,
   onUpdateAutoSync,
+  onUpdateLiveShare,
   lastActionReport,
   onClearActionReport,
   t,
@@ -266,6 +281,24 @@ function LanModePanel({
     onUpdateAutoSync({ ...autoSettings, [direction]: checked });
   };
 
+  const [policyPeerId, setPolicyPeerId] = useState('');
+  const groupNames = (localSnapshot?.groups || []).map(group => group.name);
+
+  const liveSettings = liveShareStatus?.settings || {
+    enabled: false,
+    peer_ids: [],
+    apply_to_clipboard: false,
+  };
+  const livePeerIds = Array.isArray(liveSettings.peer_ids) ? liveSettings.peer_ids : [];
+  const liveConnectedIds = Array.isArray(liveShareStatus?.connected_peer_ids) ? liveShareStatus.connected_peer_ids : [];
+
+  const toggleLivePeer = (deviceId) => {
+    const peerIds = livePeerIds.includes(deviceId)
+      ? livePeerIds.filter(id => id !== deviceId)
+      : [...livePeerIds, deviceId];
+    onUpdateLiveShare({ ...liveSettings, peer_ids: peerIds });
+  };
+
   const formatTime = (value) => {
     if (!value) return t('settings.syncTransfer.neverSeen');
     try {
@@ -469,41 +502,71 @@ function LanModePanel({
             ) : (
               <div className="divide-y divide-qc-border">
                 {peers.map(peer => (
-                  <div
-                    key={peer.device_id}
-                    className="flex flex-wrap items-center justify-between gap-3 py-3 first:pt-0 last:pb-0"
-                  >
-                    <div className="min-w-0 flex-1">
-                      <div className="truncate text-sm font-medium text-qc-fg">{peer.device_name || peer.device_id}</div>
-                      <div className="truncate text-xs text-qc-fg-muted">{peer.base_url || peer.device_id}</div>
-                      <div className="truncate text-xs text-qc-fg-subtle">
-                        {t('settings.syncTransfer.lastSeen')}: {formatTime(peer.last_seen_at_ms)}
+                  <div key={peer.device_id} className="py-3 first:pt-0 last:pb-0">
+                    <div className="flex flex-wrap items-center justify-between gap-3">
+                      <div className="min-w-0 flex-1">
+                        <div className="truncate text-sm font-medium text-qc-fg">{peer.device_name || peer.device_id}</div>
+                        <div className="truncate text-xs text-qc-fg-muted">{peer.base_url || peer.device_id}</div>
+                        <div className="truncate text-xs text-qc-fg-subtle">
+                          {t('settings.syncTransfer.lastSeen')}: {formatTime(peer.last_seen_at_ms)}
+                          {liveSettings.enabled && liveConnectedIds.includes(peer.device_id) && (
+                            <span className="ml-2 text-blue-500">{t('settings.syncTransfer.liveShareConnected')}</span>
+                          )}
+                          {isRestrictedPolicy(peer.sync_policy) && (
+                            <span className="ml-2 text-amber-500">{t('settings.syncTransfer.syncScopeRestricted')}</span>
+                          )}
+                        </div>
+                      </div>
+                      <div className="flex shrink-0 items-center gap-1.5">
+                        <button
+                          type="button"
+                          onClick={() => onPushPeer(peer.device_id)}
+                          disabled={busy === `push-${peer.device_id}`}
+                          className="qc-accent-button inline-flex h-9 items-center justify-center gap-1.5 rounded-lg border border-[var(--qc-accent)] !bg-[var(--qc-accent)] px-3 text-sm font-medium !text-[var(--qc-accent-fg)] shadow-sm transition-colors hover:!bg-[var(--qc-accent-hover)] disabled:cursor-not-allowed disabled:opacity-50"
+                        >
+                          <i className={busy === `push-${peer.device_id}` ? 'ti ti-loader-2 animate-spin' : 'ti ti-upload'} />
+                          {t('settings.syncTransfer.pushPeer')}
+                        </button>
+                        <IconActionButton
+                          tooltip={livePeerIds.includes(peer.device_id)
+                            ? t('settings.syncTransfer.liveSharePeerOn')
+                            : t('settings.syncTransfer.liveSharePeerOff')}
+                          icon="ti ti-bolt"
+                          variant={livePeerIds.includes(peer.device_id) ? 'primary' : 'secondary'}
+                          onClick={() => toggleLivePeer(peer.device_id)}
+                          loading={busy === 'updateLiveShare'}
+                        />
+                        <IconActionButton
+                          tooltip={t('settings.syncTransfer.syncScope')}
+                          icon="ti ti-adjustments-horizontal"
+                          variant={policyPeerId === peer.device_id ? 'primary' : 'secondary'}
+                          onClick={() => setPolicyPeerId(current => (current === peer.device_id ? '' : peer.device_id))}
+                          loading={busy === `policy-${peer.device_id}`}
+                        />
+                        <IconActionButton
+                          tooltip={t('settings.syncTransfer.fetchSnapshot')}
+                          icon="ti ti-list-search"
+                          onClick={() => onFetchPeerSnapshot(peer.device_id)}
+                          loading={busy === `snapshot-${peer.device_id}`}
+                        />
+                        <IconActionButton
+                          tooltip={t('common.delete')}
+                          icon="ti ti-trash"
+                          variant="danger"
+                          onClick={() => onRemovePeer(peer.device_id)}
+                          loading={busy === `remove-${peer.device_id}`}
+                        />
                       </div>
                     </div>
-                    <div className="flex shrink-0 items-center gap-1.5">
-                      <button
-                        type="button"
-                        onClick={() => onPushPeer(peer.device_id)}
-                        disabled={busy === `push-${peer.device_id}`}
-                        className="qc-accent-button inline-flex h-9 items-center justify-center gap-1.5 rounded-lg border border-[var(--qc-accent)] !bg-[var(--qc-accent)] px-3 text-sm font-medium !text-[var(--qc-accent-fg)] shadow-sm transition-colors hover:!bg-[var(--qc-accent-hover)] disabled:cursor-not-allowed disabled:opacity-50"
-                      >
-                        <i className={busy === `push-${peer.device_id}` ? 'ti ti-loader-2 animate-spin' : 'ti ti-upload'} />
-                        {t('settings.syncTransfer.pushPeer')}
-                      </button>
-                      <IconActionButton
-                        tooltip={t('settings.syncTransfer.fetchSnapshot')}
-                        icon="ti ti-list-search"
-                        onClick={() => onFetchPeerSnapshot(peer.device_id)}
-                        loading={busy === `snapshot-${peer.device_id}`}
-                      />
-                      <IconActionButton
-                        tooltip={t('common.delete')}
-                        icon="ti ti-trash"
-                        variant="danger"
-                        onClick={() => onRemovePeer(peer.device_id)}
-                        loading={busy === `remove-${peer.device_id}`}
+                    {policyPeerId === peer.device_id && (
+                      <PeerSyncPolicyEditor
+                        policy={peer.sync_policy}
+                        groupNames={groupNames}
+                        disabled={busy === `policy-${peer.device_id}`}
+                        onChange={policy => onUpdatePeerPolicy(peer.device_id, policy)}
+                        t={t}
                       />
-                    </div>
+                    )}
                   </div>
                 ))}
               </div>
@@ -549,11 +612,141 @@ function LanModePanel({
             </label>
           </div>
         </SettingItem>
+        <SettingItem
+          label={t('settings.syncTransfer.liveShare')}
+          description={t('settings.syncTransfer.liveShareDesc')}
+        >
+          <Toggle
+            checked={Boolean(liveSettings.enabled)}
+            onChange={checked => onUpdateLiveShare({ ...liveSettings, enabled: checked })}
+            disabled={busy === 'updateLiveShare'}
+          />
+        </SettingItem>
+        <SettingItem
+          label={t('settings.syncTransfer.liveShareApplyClipboard')}
+          description={t('settings.syncTransfer.liveShareApplyClipboardDesc')}
+        >
+          <Toggle
+            checked={Boolean(liveSettings.apply_to_clipboard)}
+            onChange={checked => onUpdateLiveShare({ ...liveSettings, apply_to_clipboard: checked })}
+            disabled={busy === 'updateLiveShare' || !liveSettings.enabled}
+          />
+        </SettingItem>
       </SettingsSection>
     </div>
   );
 }
 
+const DEFAULT_PEER_SYNC_POLICY = {
+  history: true,
+  favorites: true,
+  groups: true,
+  images: true,
+  favorite_groups: [],
+  max_age_days: null,
+  relay_remote: true,
+};
+
+const MAX_AGE_OPTIONS = [0, 7, 30, 90];
+
+function isRestrictedPolicy(policy) {
+  if (!policy) return false;
+  return !policy.history
+    || !policy.favorites
+    || !policy.groups
+    || !policy.images
+    || !policy.relay_remote
+    || (policy.favorite_groups || []).length > 0
+    || Boolean(policy.max_age_days);
+}
+
+function PeerSyncPolicyEditor({ policy, groupNames, disabled, onChange, t }) {
+  const current = { ...DEFAULT_PEER_SYNC_POLICY, ...(policy || {}) };
+  const selectedGroups = current.favorite_groups || [];
+  // 已选但本机不存在的分组也保留显示，便于取消
+  const allGroups = [...groupNames, ...selectedGroups.filter(name => !groupNames.includes(name))];
+
+  const update = (patch) => onChange({ ...current, ...patch });
+  const toggleGroup = (name) => {
+    update({
+      favorite_groups: selectedGroups.includes(name)
+        ? selectedGroups.filter(item => item !== name)
+        : [...selectedGroups, name],
+    });
+  };
+
+  const switches = [
+    ['history', 'syncScopeHistory'],
+    ['favorites', 'syncScopeFavorites'],
+    ['groups', 'syncScopeGroups'],
+    ['images', 'syncScopeImages'],
+    ['relay_remote', 'syncScopeRelay'],
+  ];
+  const maxAgeOptions = MAX_AGE_OPTIONS.map(days => ({
+    value: String(days),
+    label: days === 0
+      ? t('settings.syncTransfer.syncScopeMaxAgeAll')
+      : t('settings.syncTransfer.syncScopeMaxAgeDays', { days }),
+  }));
+
+  return (
+    <div className="mt-3 space-y-3 rounded-lg border border-qc-border bg-qc-panel-2 p-3">
+      <div>
+        <div className="text-sm font-medium text-qc-fg">{t('settings.syncTransfer.syncScope')}</div>
+        <div className="text-xs text-qc-fg-muted">{t('settings.syncTransfer.syncScopeDesc')}</div>
+      </div>
+      <div className="flex flex-wrap items-center gap-4">
+        {switches.map(([key, label]) => (
+          <label key={key} className="flex items-center gap-2 text-sm text-qc-fg">
+            <Toggle
+              checked={Boolean(current[key])}
+              onChange={checked => update({ [key]: checked })}
+              disabled={disabled}
+            />
+            {t(`settings.syncTransfer.${label}`)}
+          </label>
+        ))}
+      </div>
+      {allGroups.length > 0 && (
+        <div>
+          <div className="mb-1.5 text-xs text-qc-fg-muted">
+            {t('settings.syncTransfer.syncScopeFavoriteGroups')}
+            {selectedGroups.length === 0 && ` · ${t('settings.syncTransfer.syncScopeAllGroups')}`}
+          </div>
+          <div className="flex flex-wrap gap-1.5">
+            {allGroups.map(name => {
+              const selected = selectedGroups.includes(name);
+              return (
+                <button
+                  key={name}
+                  type="button"
+                  onClick={() => toggleGroup(name)}
+                  disabled={disabled || !current.favorites}
+                  className={`rounded-md border px-2.5 py-1 text-xs transition-colors disabled:cursor-not-allowed disabled:opacity-50 ${
+                    selected
+                      ? 'border-blue-400/60 bg-blue-500/10 text-qc-fg'
+                      : 'border-qc-border bg-qc-surface text-qc-fg-muted hover:bg-qc-hover'
+                  }`}
+                >
+                  {name}
+                </button>
+              );
+            })}
+          </div>
+        </div>
+      )}
+      <div className="flex flex-wrap items-center gap-3">
+        <span className="text-xs text-qc-fg-muted">{t('settings.syncTransfer.syncScopeMaxAge')}</span>
+        <SegmentedControl
+          value={String(current.max_age_days || 0)}
+          onChange={value => update({ max_age_days: Number(value) || null })}
+          options={maxAgeOptions}
+        />
+      </div>
+    </div>
+  );
+}
+
 function StatusPill({ label, value, active = false }) {
   return (
     <span
@@ -633,6 +826,11 @@ function ActionResultCard({ report, peers, onClear, t }) {
     });
   }
 
+  const conflictCount = (report.payload?.conflicts || []).length;
+  if (conflictCount > 0) {
+    summary += t('settings.syncTransfer.conflictSummary', { count: conflictCount });
+  }
+
   let timeText = '';
   try {
     timeText = new Date(report.time).toLocaleTimeString();
@@ -1,10 +1,12 @@
 import { useEffect, useState } from 'react';
 import { listen } from '@tauri-apps/api/event';
 import { useTranslation } from 'react-i18next';
+import { open } from '@tauri-apps/plugin-dialog';
 import SettingsSection from '../components/SettingsSection';
 import SettingItem from '../components/SettingItem';
 import Button from '@shared/components/ui/Button';
 import Input from '@shared/components/ui/Input';
+import Select from '@shared/components/ui/Select';
 import Toggle from '@shared/components/ui/Toggle';
 import { showConfirm } from '@shared/utils/dialog';
 import { formatUserMessage, formatUserMessages } from '@shared/utils/userMessages';
@@ -49,6 +51,17 @@ function WebdavSection({ settings, onSettingChange }) {
   const webdavUrl = String(settings.webdavUrl || '').trim();
   const webdavUsername = String(settings.webdavUsername || '').trim();
   const webdavRootPath = String(settings.webdavRootPath || 'quickclipboard').trim() || 'quickclipboard';
+  const webdavBackend = settings.webdavBackend || 'webdav';
+  const webdavFolderPath = String(settings.webdavFolderPath || '').trim();
+  // 文件夹后端没有账号，加密密码按文件夹路径保存
+  const isS3 = webdavBackend === 's3';
+  const credentialUrl = webdavBackend === 'folder' ? webdavFolderPath : webdavUrl;
+  const credentialUsername = webdavBackend === 'folder' ? '' : webdavUsername;
+  const backendOptions = [
+    { value: 'webdav', label: t('settings.webdav.backendWebdav') },
+    { value: 's3', label: t('settings.webdav.backendS3') },
+    { value: 'folder', label: t('settings.webdav.backendFolder') },
+  ];
 
   useEffect(() => {
     let mounted = true;
@@ -98,13 +111,13 @@ function WebdavSection({ settings, onSettingChange }) {
 
   useEffect(() => {
     let mounted = true;
-    if (!webdavUrl) {
+    if (!credentialUrl) {
       setEncryptionPasswordSaved(false);
       return () => {
         mounted = false;
       };
     }
-    hasSavedWebdavEncryptionPassword(webdavUrl, webdavUsername, webdavRootPath)
+    hasSavedWebdavEncryptionPassword(credentialUrl, credentialUsername, webdavRootPath)
       .then(saved => {
         if (mounted) setEncryptionPasswordSaved(Boolean(saved));
       })
@@ -114,7 +127,12 @@ function WebdavSection({ settings, onSettingChange }) {
     return () => {
       mounted = false;
     };
-  }, [webdavUrl, webdavUsername, webdavRootPath]);
+  }, [credentialUrl, credentialUsername, webdavRootPath]);
+
+  const chooseFolder = async () => {
+    const dir = await open({ directory: true, multiple: false });
+    if (dir) await update('webdavFolderPath', String(dir));
+  };
 
   const update = async (key, value) => {
     await onSettingChange(key, value);
@@ -174,8 +192,8 @@ function WebdavSection({ settings, onSettingChange }) {
     try {
       setEncryptionPasswordBusy(true);
       const saved = await setWebdavEncryptionPassword(
-        webdavUrl,
-        webdavUsername,
+        credentialUrl,
+        credentialUsername,
         webdavRootPath,
         encryptionPasswordDraft
       );
@@ -194,7 +212,7 @@ function WebdavSection({ settings, onSettingChange }) {
   const clearWebdavEncryptionPassword = async () => {
     try {
       setEncryptionPasswordBusy(true);
-      await setWebdavEncryptionPassword(webdavUrl, webdavUsername, webdavRootPath, '');
+      await setWebdavEncryptionPassword(credentialUrl, credentialUsername, webdavRootPath, '');
       setEncryptionPasswordDraft('');
       setEncryptionPasswordSaved(false);
       toast.success(t('settings.webdav.encryptionPasswordCleared'));
@@ -250,6 +268,7 @@ function WebdavSection({ settings, onSettingChange }) {
 
     const result = lastReport.result;
     const total = lastReport.mode === 'push' ? result.pushed || 0 : result.pulled || 0;
+    const conflicts = Array.isArray(result.conflicts) ? result.conflicts : [];
 
     return (
       <div className="mt-3 rounded-xl border border-qc-border bg-qc-surface/60 p-3 text-sm text-qc-fg">
@@ -276,6 +295,20 @@ function WebdavSection({ settings, onSettingChange }) {
             )}
           </div>
         )}
+        {conflicts.length > 0 && (
+          <div className="mt-3 border-t border-qc-border pt-2">
+            <div className="mb-1 text-xs font-medium text-amber-600">{t('settings.webdav.conflicts', { count: conflicts.length })}</div>
+            <div className="max-h-32 space-y-1 overflow-auto pr-1">
+              {conflicts.map((item, index) => (
+                <div key={`${item.category}-${item.id}-${index}`} className="flex items-center gap-2 text-xs text-qc-fg-muted">
+                  <span className="shrink-0 rounded-md bg-qc-hover px-1.5 py-0.5 text-qc-fg">{t(`settings.webdav.category.${item.category}`)}</span>
+                  <span className="min-w-0 flex-1 truncate">{item.summary || item.id}</span>
+                  <span className="shrink-0">{t(item.resolution === 'merged' ? 'settings.webdav.conflictMerged' : 'settings.webdav.conflictCopied')}</span>
+                </div>
+              ))}
+            </div>
+          </div>
+        )}
       </div>
     );
   };
@@ -290,46 +323,75 @@ function WebdavSection({ settings, onSettingChange }) {
         <Toggle checked={Boolean(settings.webdavEnabled)} onChange={checked => update('webdavEnabled', checked)} />
       </SettingItem>
 
-      <SettingItem label={t('settings.webdav.url')}>
-        <Input value={settings.webdavUrl || ''} commitOnBlur onCommit={v => update('webdavUrl', String(v))} placeholder={t('settings.webdav.urlPlaceholder')} className="w-80" />
+      <SettingItem label={t('settings.webdav.backend')} description={t('settings.webdav.backendDesc')}>
+        <Select value={webdavBackend} onChange={value => update('webdavBackend', value)} options={backendOptions} className="w-40" />
       </SettingItem>
 
-      <SettingItem label={t('settings.webdav.username')}>
-        <Input value={settings.webdavUsername || ''} commitOnBlur onCommit={v => update('webdavUsername', String(v))} placeholder={t('settings.webdav.usernamePlaceholder')} className="w-80" />
-      </SettingItem>
-
-      <SettingItem label={t('settings.webdav.password')}>
-        <div className="flex w-80 items-center gap-2">
-          <Input
-            type="password"
-            value={passwordDraft}
-            onChange={e => setPasswordDraft(e.target.value)}
-            onBlur={saveWebdavPasswordDraft}
-            onKeyDown={e => {
-              if (e.key === 'Enter') e.currentTarget.blur();
-            }}
-            placeholder={passwordSaved ? t('settings.webdav.passwordSavedPlaceholder') : t('settings.webdav.passwordPlaceholder')}
-            className="min-w-0 flex-1"
-            disabled={passwordBusy}
-          />
-          {passwordSaved && (
-            <Button
-              type="button"
-              size="sm"
-              variant="secondary"
-              className="shrink-0"
-              onClick={clearWebdavPassword}
-              disabled={passwordBusy}
-              loading={passwordBusy}
-              icon={<i className="ti ti-key-off" />}
-            >
-              {t('settings.webdav.clearPassword')}
+      {webdavBackend === 'folder' ? (
+        <SettingItem label={t('settings.webdav.folderPath')} description={t('settings.webdav.folderPathDesc')}>
+          <div className="flex w-80 items-center gap-2">
+            <Input value={settings.webdavFolderPath || ''} commitOnBlur onCommit={v => update('webdavFolderPath', String(v))} placeholder={t('settings.webdav.folderPathPlaceholder')} className="min-w-0 flex-1" />
+            <Button type="button" size="sm" variant="secondary" className="shrink-0" onClick={chooseFolder} icon={<i className="ti ti-folder-open" />}>
+              {t('settings.webdav.chooseFolder')}
             </Button>
+          </div>
+        </SettingItem>
+      ) : (
+        <>
+          <SettingItem label={t(isS3 ? 'settings.webdav.s3Endpoint' : 'settings.webdav.url')}>
+            <Input value={settings.webdavUrl || ''} commitOnBlur onCommit={v => update('webdavUrl', String(v))} placeholder={t(isS3 ? 'settings.webdav.s3EndpointPlaceholder' : 'settings.webdav.urlPlaceholder')} className="w-80" />
+          </SettingItem>
+
+          {isS3 && (
+            <>
+              <SettingItem label={t('settings.webdav.s3Bucket')}>
+                <Input value={settings.webdavS3Bucket || ''} commitOnBlur onCommit={v => update('webdavS3Bucket', String(v))} placeholder={t('settings.webdav.s3BucketPlaceholder')} className="w-80" />
+              </SettingItem>
+
+              <SettingItem label={t('settings.webdav.s3Region')} description={t('settings.webdav.s3RegionDesc')}>
+                <Input value={settings.webdavS3Region || ''} commitOnBlur onCommit={v => update('webdavS3Region', String(v))} placeholder="us-east-1" className="w-80" />
+              </SettingItem>
+            </>
           )}
-        </div>
-      </SettingItem>
 
-      <SettingItem label={t('settings.webdav.rootPath')}>
+          <SettingItem label={t(isS3 ? 'settings.webdav.s3AccessKey' : 'settings.webdav.username')}>
+            <Input value={settings.webdavUsername || ''} commitOnBlur onCommit={v => update('webdavUsername', String(v))} placeholder={t(isS3 ? 'settings.webdav.s3AccessKeyPlaceholder' : 'settings.webdav.usernamePlaceholder')} className="w-80" />
+          </SettingItem>
+
+          <SettingItem label={t(isS3 ? 'settings.webdav.s3SecretKey' : 'settings.webdav.password')}>
+            <div className="flex w-80 items-center gap-2">
+              <Input
+                type="password"
+                value={passwordDraft}
+                onChange={e => setPasswordDraft(e.target.value)}
+                onBlur={saveWebdavPasswordDraft}
+                onKeyDown={e => {
+                  if (e.key === 'Enter') e.currentTarget.blur();
+                }}
+                placeholder={passwordSaved ? t('settings.webdav.passwordSavedPlaceholder') : t('settings.webdav.passwordPlaceholder')}
+                className="min-w-0 flex-1"
+                disabled={passwordBusy}
+              />
+              {passwordSaved && (
+                <Button
+                  type="button"
+                  size="sm"
+                  variant="secondary"
+                  className="shrink-0"
+                  onClick={clearWebdavPassword}
+                  disabled={passwordBusy}
+                  loading={passwordBusy}
+                  icon={<i className="ti ti-key-off" />}
+                >
+                  {t('settings.webdav.clearPassword')}
+                </Button>
+              )}
+            </div>
+          </SettingItem>
+        </>
+      )}
+
+      <SettingItem label={t(webdavBackend === 'webdav' ? 'settings.webdav.rootPath' : 'settings.webdav.syncRootPath')}>
         <Input value={settings.webdavRootPath || 'quickclipboard'} commitOnBlur onCommit={v => update('webdavRootPath', String(v))} placeholder={t('settings.webdav.rootPathPlaceholder')} className="w-80" />
       </SettingItem>
 
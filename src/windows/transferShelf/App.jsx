@@ -9,6 +9,7 @@ import { showConfirm } from '@shared/utils/dialog';
 import { createDragPreviewIcon } from '@shared/utils/dragPreviewIcon';
 import { formatUserMessage } from '@shared/utils/userMessages';
 import { initSettings, settingsStore } from '@shared/store/settingsStore';
+import { isCloudSyncConfigured } from '@shared/services/settingsService';
 import { useSettingsSync } from '@shared/hooks/useSettingsSync';
 import { useTheme, applyThemeToBody } from '@shared/hooks/useTheme';
 import { applyBackgroundImage, clearBackgroundImage } from '@shared/utils/backgroundManager';
@@ -394,7 +395,7 @@ export default function App() {
   const isUploading = task.status === 'uploading';
   const isBusy = isSending || isUploading;
   isBusyRef.current = isBusy;
-  const cloudEnabled = Boolean(settings.webdavEnabled) && Boolean(String(settings.webdavUrl || '').trim());
+  const cloudEnabled = Boolean(settings.webdavEnabled) && isCloudSyncConfigured(settings);
   const cloudSelected = selectedPeerIds.includes(CLOUD_TARGET_ID);
   const selectedLanPeerIds = selectedPeerIds.filter((id) => id !== CLOUD_TARGET_ID);
   const canSend = files.length > 0 && selectedPeerIds.length > 0 && !isBusy && (!cloudSelected || cloudEnabled);